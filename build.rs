@@ -12,6 +12,7 @@ fn main() -> Result<(), anyhow::Error> {
         "varfish/v1/seqvars/output.proto",
         "varfish/v1/strucvars/clinvar.proto",
         "varfish/v1/strucvars/bgdb.proto",
+        "varfish/v1/strucvars/output.proto",
     ]
     .iter()
     .map(|f| root.join(f))
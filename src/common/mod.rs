@@ -10,6 +10,8 @@ use clap_verbosity_flag::{InfoLevel, Verbosity};
 use indexmap::IndexMap;
 
 pub mod noodles;
+pub mod remote;
+pub mod rocksdb_tuning;
 pub mod s3;
 
 /// Commonly used command line arguments.
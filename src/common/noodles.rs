@@ -9,7 +9,8 @@
 
 use async_compression::tokio::bufread::GzipDecoder;
 use mehari::common::io::{std::is_gz, tokio::open_read_maybe_gz};
-use mehari::common::noodles::{AsyncVcfReader, VariantReader};
+use mehari::common::noodles::{open_vcf_writer, AsyncVcfReader, AsyncVcfWriter, VariantReader};
+use noodles::bcf;
 use noodles::bgzf;
 use noodles::core::Position;
 use noodles::csi::{self as csi, binning_index::index::reference_sequence::bin::Chunk};
@@ -17,7 +18,9 @@ use noodles::tabix;
 use noodles::vcf;
 use noodles::vcf::variant::Record;
 use std::{path::Path, pin::Pin};
-use tokio::io::{AsyncBufRead, BufReader};
+use tokio::io::{AsyncBufRead, AsyncWriteExt as _, BufReader};
+
+use crate::flush_and_shutdown;
 
 /// Build TBI for file at `path_src` and write to `path_dst`.
 pub async fn build_tbi<S, D>(path_src: S, path_dst: D) -> Result<(), anyhow::Error>
@@ -96,6 +99,20 @@ where
     Ok(())
 }
 
+/// Build CSI for the BCF file at `path_src` and write to `path_dst`.
+pub fn build_csi_for_bcf<S, D>(path_src: S, path_dst: D) -> Result<(), anyhow::Error>
+where
+    S: AsRef<std::path::Path>,
+    D: AsRef<std::path::Path>,
+{
+    let index = bcf::fs::index(path_src.as_ref())
+        .map_err(|e| anyhow::anyhow!("error building CSI index: {}", e))?;
+    csi::fs::write(path_dst.as_ref(), &index)
+        .map_err(|e| anyhow::anyhow!("error writing CSI index: {}", e))?;
+
+    Ok(())
+}
+
 /// Open plain text or gzip reader via S3.
 pub async fn s3_open_read_maybe_gz<P>(path: P) -> Result<Pin<Box<dyn AsyncBufRead>>, anyhow::Error>
 where
@@ -198,6 +215,17 @@ pub async fn open_vcf_reader(path_in: &str) -> Result<VariantReader, anyhow::Err
                 .await
                 .map_err(|e| anyhow::anyhow!("could not build VCF reader from S3 file: {}", e))?,
         )))
+    } else if path_in == "-" {
+        tracing::debug!("Opening stdin for reading (async)");
+        let buf_read: Pin<Box<dyn AsyncBufRead>> = Box::pin(BufReader::new(tokio::io::stdin()));
+        Ok(VariantReader::Vcf(vcf::AsyncReader::new(buf_read)))
+    } else if is_bcf(path_in) {
+        tracing::debug!("Opening local BCF file {} for reading (async)", path_in);
+        Ok(VariantReader::Bcf(bcf::AsyncReader::new(
+            tokio::fs::File::open(path_in).await.map_err(|e| {
+                anyhow::anyhow!("could not build BCF reader from local file: {}", e)
+            })?,
+        )))
     } else {
         tracing::debug!("Opening local file {} for reading (async)", path_in);
         Ok(VariantReader::Vcf(vcf::AsyncReader::new(
@@ -208,6 +236,85 @@ pub async fn open_vcf_reader(path_in: &str) -> Result<VariantReader, anyhow::Err
     }
 }
 
+/// Return whether the local file at `path` is BCF, by extension or magic bytes.
+pub fn is_bcf(path: &str) -> bool {
+    use std::io::Read as _;
+
+    if path.ends_with(".bcf") {
+        return true;
+    }
+    let mut magic = [0u8; 3];
+    std::fs::File::open(path)
+        .map(bgzf::Reader::new)
+        .and_then(|mut reader| reader.read_exact(&mut magic))
+        .map(|_| &magic == b"BCF")
+        .unwrap_or(false)
+}
+
+/// Alias for the asynchronous BCF writer.
+pub type AsyncBcfWriter = bcf::AsyncWriter<bgzf::AsyncWriter<tokio::fs::File>>;
+
+/// Writer for VCF or BCF output files.
+pub enum VariantWriter {
+    /// Writer for plain text or bgzip-compressed VCF.
+    Vcf(AsyncVcfWriter),
+    /// Writer for BCF.
+    Bcf(AsyncBcfWriter),
+}
+
+impl VariantWriter {
+    /// Write the VCF `header`.
+    pub async fn write_header(&mut self, header: &vcf::Header) -> std::io::Result<()> {
+        match self {
+            VariantWriter::Vcf(writer) => writer.write_header(header).await,
+            VariantWriter::Bcf(writer) => writer.write_header(header).await,
+        }
+    }
+
+    /// Write the `record` given the `header`.
+    pub async fn write_variant_record(
+        &mut self,
+        header: &vcf::Header,
+        record: &vcf::variant::RecordBuf,
+    ) -> std::io::Result<()> {
+        match self {
+            VariantWriter::Vcf(writer) => writer.write_variant_record(header, record).await,
+            VariantWriter::Bcf(writer) => writer.write_variant_record(header, record).await,
+        }
+    }
+
+    /// Flush all buffers and shut down the writer.
+    pub async fn shutdown(self) -> Result<(), anyhow::Error> {
+        match self {
+            VariantWriter::Vcf(output_writer) => {
+                flush_and_shutdown!(output_writer);
+            }
+            VariantWriter::Bcf(mut output_writer) => {
+                output_writer
+                    .get_mut()
+                    .shutdown()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("problem shutting down BCF writer: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open a BCF writer if `path_out` ends with `.bcf` and a VCF writer otherwise.
+pub async fn open_variant_writer(path_out: &str) -> Result<VariantWriter, anyhow::Error> {
+    if path_out.ends_with(".bcf") {
+        tracing::debug!("Opening local BCF file {} for writing (async)", path_out);
+        Ok(VariantWriter::Bcf(bcf::AsyncWriter::new(
+            tokio::fs::File::create(path_out)
+                .await
+                .map_err(|e| anyhow::anyhow!("could not create BCF file {}: {}", path_out, e))?,
+        )))
+    } else {
+        Ok(VariantWriter::Vcf(open_vcf_writer(path_out).await?))
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[tokio::test]
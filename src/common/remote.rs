@@ -0,0 +1,531 @@
+//! Access to databases at remote locations with local caching.
+//!
+//! Database paths may point to S3 (`s3://bucket/prefix`), to GCS through its S3
+//! interoperability API (`gs://bucket/prefix`), or to HTTP(S) servers.  The files
+//! are downloaded to a local cache directory once and the local copy is used from
+//! then on, such that workers do not need the databases baked into their images.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncWriteExt as _;
+
+/// Environment variable with the path of the cache directory.
+pub const CACHE_DIR_ENV: &str = "VARFISH_DB_CACHE_DIR";
+
+/// Name of the file listing the files below an HTTP(S) location.
+///
+/// HTTP servers cannot be listed, so the file must contain one path relative to
+/// the location per line.
+pub const HTTP_FILE_LIST: &str = "files.txt";
+
+/// Name of the file in the cache directory with the versions of the cached files.
+pub const CACHE_INDEX: &str = "index.json";
+
+/// Prefix of the temporary files that downloads and the cache index are written to.
+const DOWNLOAD_PREFIX: &str = ".download";
+
+/// Return whether `path` points to a remote location.
+pub fn is_remote(path: &str) -> bool {
+    ["s3://", "gs://", "http://", "https://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+/// Return the cache directory from `VARFISH_DB_CACHE_DIR` or below the temporary directory.
+pub fn cache_dir() -> PathBuf {
+    std::env::var(CACHE_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join("varfish-server-worker-cache"))
+}
+
+/// Join the `/`-separated `relative` path to `base`, refusing to leave `base`.
+fn join_relative(base: &Path, relative: &str) -> Result<PathBuf, anyhow::Error> {
+    let mut result = base.to_path_buf();
+    for segment in relative.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == "." || segment == ".." {
+            anyhow::bail!("invalid path segment {:?} in {}", segment, relative);
+        }
+        result.push(segment);
+    }
+    Ok(result)
+}
+
+/// Return the local path of the remote location `url` in `cache_dir`.
+pub fn cache_path(cache_dir: &Path, url: &str) -> Result<PathBuf, anyhow::Error> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| anyhow::anyhow!("invalid remote location: {}", url))?;
+    join_relative(&cache_dir.join(scheme), rest)
+}
+
+/// Return the local path to use for `path`, downloading remote locations to the cache first.
+///
+/// Local paths are returned unchanged.  Files that are already in the cache with
+/// the same version as the remote file are not downloaded again and cached files
+/// that are no longer at the remote location are removed.
+pub async fn localize(path: &str) -> Result<String, anyhow::Error> {
+    if !is_remote(path) {
+        return Ok(path.to_string());
+    }
+
+    let mut cache = Cache::open(&cache_dir());
+    let path_local = cache_path(&cache.dir, path)?;
+    tracing::info!("Caching {} at {}", path, path_local.display());
+    let before_caching = std::time::Instant::now();
+    let count = if path.starts_with("http://") || path.starts_with("https://") {
+        localize_http(path, &mut cache, &path_local).await?
+    } else {
+        localize_s3(path, &mut cache, &path_local).await?
+    };
+    tracing::info!(
+        "... downloaded {} file(s) in {:?}",
+        count,
+        before_caching.elapsed()
+    );
+
+    Ok(path_local.display().to_string())
+}
+
+/// Version of a remote file as recorded in the cache index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct RemoteVersion {
+    /// The `ETag` of the remote file.
+    etag: Option<String>,
+    /// The `Last-Modified` time of the remote file.
+    last_modified: Option<String>,
+    /// Size of the remote file.
+    size: Option<u64>,
+}
+
+/// The cache directory together with the versions of the cached files.
+///
+/// The versions are kept in the single file `CACHE_INDEX` in the cache directory
+/// rather than next to the files, such that the cached locations only contain the
+/// remote files.
+struct Cache {
+    /// Path to the cache directory.
+    dir: PathBuf,
+    /// Remote versions by path of the cached file relative to `dir`.
+    index: BTreeMap<String, RemoteVersion>,
+}
+
+impl Cache {
+    /// Open the cache at `dir`; a missing or unreadable index is treated as empty.
+    fn open(dir: &Path) -> Self {
+        let index = std::fs::read_to_string(dir.join(CACHE_INDEX))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            dir: dir.to_path_buf(),
+            index,
+        }
+    }
+
+    /// Atomically write the index to the cache directory.
+    fn save(&self) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = tempfile::Builder::new()
+            .prefix(DOWNLOAD_PREFIX)
+            .tempfile_in(&self.dir)?;
+        serde_json::to_writer(&mut file, &self.index)?;
+        let path = self.dir.join(CACHE_INDEX);
+        file.persist(&path)
+            .map_err(|e| anyhow::anyhow!("could not write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// Return the key of the cached `path` in the index.
+    fn key(&self, path: &Path) -> String {
+        path.strip_prefix(&self.dir)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Return whether `path` is cached in the given `version`.
+    ///
+    /// Files without `ETag` or `Last-Modified` are always downloaded again.
+    fn is_cached(&self, path: &Path, version: &RemoteVersion) -> bool {
+        if version.etag.is_none() && version.last_modified.is_none() {
+            return false;
+        }
+        path.is_file() && self.index.get(&self.key(path)) == Some(version)
+    }
+
+    /// Move the downloaded `temp_path` to `path` and record its `version` in the index.
+    fn persist(
+        &mut self,
+        temp_path: tempfile::TempPath,
+        path: &Path,
+        version: &RemoteVersion,
+    ) -> Result<(), anyhow::Error> {
+        temp_path
+            .persist(path)
+            .map_err(|e| anyhow::anyhow!("could not move download to {}: {}", path.display(), e))?;
+        self.index.insert(self.key(path), version.clone());
+        self.save()
+    }
+
+    /// Remove the files below `path_local` that are not in `listed`, i.e., that were
+    /// removed from the remote location, and return their number.
+    fn prune(
+        &mut self,
+        path_local: &Path,
+        listed: &HashSet<PathBuf>,
+    ) -> Result<usize, anyhow::Error> {
+        let mut count = 0;
+        let mut dirs = vec![path_local.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue; // nothing cached yet
+            };
+            for entry in entries {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    dirs.push(path);
+                } else if !listed.contains(&path)
+                    && !entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(DOWNLOAD_PREFIX)
+                {
+                    tracing::debug!("  removing {}", path.display());
+                    std::fs::remove_file(&path)?;
+                    self.index.remove(&self.key(&path));
+                    count += 1;
+                }
+            }
+        }
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
+    }
+}
+
+/// Create a uniquely named temporary file next to `path` to download to.
+///
+/// Concurrent downloads of the same file thus do not write to the same file.
+fn temp_file(path: &Path) -> Result<(tokio::fs::File, tempfile::TempPath), anyhow::Error> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("no parent directory of {}", path.display()))?;
+    std::fs::create_dir_all(parent)?;
+    let (file, temp_path) = tempfile::Builder::new()
+        .prefix(DOWNLOAD_PREFIX)
+        .tempfile_in(parent)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "could not create temporary file in {}: {}",
+                parent.display(),
+                e
+            )
+        })?
+        .into_parts();
+    Ok((tokio::fs::File::from_std(file), temp_path))
+}
+
+/// Download the objects below the S3 location `url` to `path_local` in `cache`.
+///
+/// Returns the number of downloaded files.
+async fn localize_s3(
+    url: &str,
+    cache: &mut Cache,
+    path_local: &Path,
+) -> Result<usize, anyhow::Error> {
+    let (_, rest) = url.split_once("://").expect("checked in is_remote");
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    // List below the prefix as a directory, such that `db` does not match `db-old`.
+    let prefix = match prefix.trim_end_matches('/') {
+        "" => String::new(),
+        prefix => format!("{prefix}/"),
+    };
+
+    let client = aws_sdk_s3::Client::from_conf(super::s3::config_from_env().await?);
+    let mut pages = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .prefix(&prefix)
+        .into_paginator()
+        .send();
+
+    let mut listed = HashSet::new();
+    let mut count = 0;
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|e| anyhow::anyhow!("could not list {}: {}", url, e))?;
+        for object in page.contents() {
+            let Some(key) = object.key() else {
+                continue;
+            };
+            let relative = key[prefix.len()..].trim_start_matches('/');
+            if relative.is_empty() || key.ends_with('/') {
+                continue; // the location itself or a directory marker
+            }
+            let path_file = join_relative(path_local, relative)?;
+            let version = RemoteVersion {
+                etag: object.e_tag().map(|etag| etag.to_string()),
+                last_modified: object.last_modified().map(|time| time.to_string()),
+                size: object.size().map(|size| size as u64),
+            };
+            listed.insert(path_file.clone());
+            if cache.is_cached(&path_file, &version) {
+                continue;
+            }
+
+            tracing::debug!("  downloading s3://{}/{}", bucket, key);
+            let response = client
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| anyhow::anyhow!("could not download {}: {}", key, e))?;
+            let (mut file, temp_path) = temp_file(&path_file)?;
+            tokio::io::copy(&mut response.body.into_async_read(), &mut file).await?;
+            file.flush().await?;
+            cache.persist(temp_path, &path_file, &version)?;
+            count += 1;
+        }
+    }
+    let removed = cache.prune(path_local, &listed)?;
+    if removed > 0 {
+        tracing::info!("... removed {} file(s) no longer at {}", removed, url);
+    }
+
+    Ok(count)
+}
+
+/// Download the files listed in `files.txt` below the HTTP(S) location `url` to
+/// `path_local` in `cache`.
+///
+/// Returns the number of downloaded files.
+async fn localize_http(
+    url: &str,
+    cache: &mut Cache,
+    path_local: &Path,
+) -> Result<usize, anyhow::Error> {
+    let url = url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let file_list = client
+        .get(format!("{}/{}", url, HTTP_FILE_LIST))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| anyhow::anyhow!("could not get {}/{}: {}", url, HTTP_FILE_LIST, e))?
+        .text()
+        .await?;
+
+    let mut listed = HashSet::new();
+    let mut count = 0;
+    for relative in file_list
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+    {
+        let url_file = format!("{}/{}", url, relative);
+        let path_file = join_relative(path_local, relative)?;
+        listed.insert(path_file.clone());
+        let response = client
+            .head(&url_file)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| anyhow::anyhow!("could not get {}: {}", &url_file, e))?;
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let version = RemoteVersion {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            size: header(reqwest::header::CONTENT_LENGTH).and_then(|value| value.parse().ok()),
+        };
+        if cache.is_cached(&path_file, &version) {
+            continue;
+        }
+
+        tracing::debug!("  downloading {}", &url_file);
+        let mut response = client
+            .get(&url_file)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| anyhow::anyhow!("could not download {}: {}", &url_file, e))?;
+        let (mut file, temp_path) = temp_file(&path_file)?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        cache.persist(temp_path, &path_file, &version)?;
+        count += 1;
+    }
+    let removed = cache.prune(path_local, &listed)?;
+    if removed > 0 {
+        tracing::info!("... removed {} file(s) no longer at {}", removed, url);
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+
+    #[rstest::rstest]
+    #[case::local("path/to/db", false)]
+    #[case::absolute("/data/db", false)]
+    #[case::s3("s3://bucket/db", true)]
+    #[case::gs("gs://bucket/db", true)]
+    #[case::https("https://example.com/db", true)]
+    fn is_remote(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(super::is_remote(path), expected);
+    }
+
+    #[test]
+    fn cache_path() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            super::cache_path(Path::new("/cache"), "s3://bucket/prefix/db/")?,
+            Path::new("/cache/s3/bucket/prefix/db")
+        );
+        assert!(super::cache_path(Path::new("/cache"), "https://host/../etc").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn localize_local() -> Result<(), anyhow::Error> {
+        assert_eq!(super::localize("tests/db").await?, "tests/db");
+
+        Ok(())
+    }
+
+    /// Files served by `serve`, by path, with contents and `ETag`.
+    type Files = Arc<Mutex<HashMap<String, (String, String)>>>;
+
+    /// Serve `files` via HTTP on a local port and return the base URL.
+    async fn serve(files: Files) -> Result<String, anyhow::Error> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let files = files.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    let Ok(Some(request)) = lines.next_line().await else {
+                        return;
+                    };
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if line.is_empty() {
+                            break;
+                        }
+                    }
+                    let mut fields = request.split(' ');
+                    let method = fields.next().unwrap_or_default();
+                    let path = fields.next().unwrap_or_default();
+                    let file = files.lock().unwrap().get(path).cloned();
+                    let response = match file {
+                        Some((contents, etag)) => format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: {}\r\n\
+                            Connection: close\r\n\r\n{}",
+                            contents.len(),
+                            etag,
+                            if method == "HEAD" {
+                                ""
+                            } else {
+                                contents.as_str()
+                            }
+                        ),
+                        None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\
+                            Connection: close\r\n\r\n"
+                            .to_string(),
+                    };
+                    let _ = writer.write_all(response.as_bytes()).await;
+                    let _ = writer.shutdown().await;
+                });
+            }
+        });
+        Ok(url)
+    }
+
+    #[tokio::test]
+    async fn localize_http() -> Result<(), anyhow::Error> {
+        let files: Files = Arc::new(Mutex::new(
+            [
+                ("/db/files.txt", "a.txt\nsub/b.txt\n", "\"0\""),
+                ("/db/a.txt", "hello", "\"1\""),
+                ("/db/sub/b.txt", "world", "\"2\""),
+            ]
+            .into_iter()
+            .map(|(path, contents, etag)| (path.into(), (contents.into(), etag.into())))
+            .collect(),
+        ));
+        let url = format!("{}/db", serve(files.clone()).await?);
+        let tmpdir = temp_testdir::TempDir::default();
+        let cache_dir = tmpdir.to_path_buf();
+        let path_local = tmpdir.join("db");
+        let localize = |url: String, path_local: std::path::PathBuf| {
+            let cache_dir = cache_dir.clone();
+            async move {
+                let mut cache = super::Cache::open(&cache_dir);
+                super::localize_http(&url, &mut cache, &path_local).await
+            }
+        };
+
+        assert_eq!(localize(url.clone(), path_local.clone()).await?, 2);
+        assert_eq!(std::fs::read_to_string(path_local.join("a.txt"))?, "hello");
+        assert_eq!(
+            std::fs::read_to_string(path_local.join("sub/b.txt"))?,
+            "world"
+        );
+
+        // Unchanged files are not downloaded again.
+        assert_eq!(localize(url.clone(), path_local.clone()).await?, 0);
+
+        // A changed file of the same size is downloaded again.
+        files
+            .lock()
+            .unwrap()
+            .insert("/db/a.txt".into(), ("hallo".into(), "\"3\"".into()));
+        assert_eq!(localize(url.clone(), path_local.clone()).await?, 1);
+        assert_eq!(std::fs::read_to_string(path_local.join("a.txt"))?, "hallo");
+
+        // Files no longer listed are removed from the cache and its index.
+        files
+            .lock()
+            .unwrap()
+            .insert("/db/files.txt".into(), ("a.txt\n".into(), "\"4\"".into()));
+        assert_eq!(localize(url.clone(), path_local.clone()).await?, 0);
+        assert!(!path_local.join("sub/b.txt").exists());
+        let index: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            cache_dir.join(super::CACHE_INDEX),
+        )?)?;
+        assert_eq!(
+            index
+                .as_object()
+                .map(|index| index.keys().cloned().collect::<Vec<_>>()),
+            Some(vec!["db/a.txt".to_string()])
+        );
+
+        // Only the files remain, no temporary files or version files.
+        let mut names = std::fs::read_dir(&path_local)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        names.sort();
+        assert_eq!(names, vec!["a.txt", "sub"]);
+
+        Ok(())
+    }
+}
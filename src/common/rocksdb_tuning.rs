@@ -0,0 +1,146 @@
+//! Tuning of RocksDB from the command line.
+
+/// Workload to choose the default RocksDB settings for.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Writing the database, e.g., in `seqvars aggregate`.
+    #[default]
+    Build,
+    /// Read-only lookups, e.g., in `seqvars query`.
+    Query,
+}
+
+impl Profile {
+    /// Default size of the block cache in bytes.
+    pub fn block_cache_size(self) -> u64 {
+        match self {
+            Profile::Build => 64 * 1024 * 1024,
+            Profile::Query => 512 * 1024 * 1024,
+        }
+    }
+
+    /// Default number of background threads for flushes and compactions.
+    pub fn background_threads(self) -> i32 {
+        match self {
+            Profile::Build => std::thread::available_parallelism()
+                .map(|count| count.get() as i32)
+                .unwrap_or(1),
+            Profile::Query => 1,
+        }
+    }
+}
+
+/// Compression of the RocksDB data blocks.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression.
+    None,
+    /// LZ4 compression, fast.
+    Lz4,
+    /// Zstandard compression, small.
+    Zstd,
+}
+
+impl From<Compression> for rocksdb::DBCompressionType {
+    fn from(value: Compression) -> Self {
+        match value {
+            Compression::None => rocksdb::DBCompressionType::None,
+            Compression::Lz4 => rocksdb::DBCompressionType::Lz4,
+            Compression::Zstd => rocksdb::DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// Command line arguments for tuning RocksDB, shared by the commands using it.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RocksdbArgs {
+    /// Profile to take the defaults of the settings below from.
+    #[arg(long, value_enum)]
+    pub rocksdb_profile: Option<Profile>,
+    /// Size of the block cache, e.g., `1GiB`.
+    #[arg(long)]
+    pub rocksdb_block_cache: Option<bytesize::ByteSize>,
+    /// Compression of newly written data blocks.
+    #[arg(long, value_enum)]
+    pub rocksdb_compression: Option<Compression>,
+    /// Compression level for `--rocksdb-compression zstd`.
+    #[arg(long)]
+    pub rocksdb_zstd_level: Option<i32>,
+    /// Number of background threads for flushes and compactions.
+    #[arg(long)]
+    pub rocksdb_background_threads: Option<i32>,
+}
+
+impl RocksdbArgs {
+    /// Apply the settings to `options`.
+    ///
+    /// Settings that are not given are taken from `--rocksdb-profile` or, if that
+    /// is not given either, from `profile`.  The compression is only changed if
+    /// given explicitly.
+    pub fn apply(&self, mut options: rocksdb::Options, profile: Profile) -> rocksdb::Options {
+        let profile = self.rocksdb_profile.unwrap_or(profile);
+
+        let block_cache_size = self
+            .rocksdb_block_cache
+            .map(|size| size.as_u64())
+            .unwrap_or_else(|| profile.block_cache_size());
+        let cache = rocksdb::Cache::new_lru_cache(block_cache_size as usize);
+        let mut block_options = rocksdb::BlockBasedOptions::default();
+        block_options.set_block_cache(&cache);
+        options.set_block_based_table_factory(&block_options);
+
+        if let Some(compression) = self.rocksdb_compression {
+            options.set_compression_type(compression.into());
+            options.set_bottommost_compression_type(compression.into());
+        }
+        if let Some(level) = self.rocksdb_zstd_level {
+            if self.rocksdb_compression != Some(Compression::Zstd) {
+                tracing::warn!(
+                    "--rocksdb-zstd-level is ignored without --rocksdb-compression zstd"
+                );
+            }
+            // window bits, level, strategy, max dict bytes
+            options.set_compression_options(-14, level, 0, 0);
+            options.set_bottommost_compression_options(-14, level, 0, 0, true);
+        }
+        options.increase_parallelism(
+            self.rocksdb_background_threads
+                .unwrap_or_else(|| profile.background_threads())
+                .max(1),
+        );
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Profile, RocksdbArgs};
+
+    #[test]
+    fn profile_defaults() {
+        assert!(Profile::Query.block_cache_size() > Profile::Build.block_cache_size());
+        assert_eq!(Profile::Query.background_threads(), 1);
+        assert!(Profile::Build.background_threads() >= 1);
+    }
+
+    #[test]
+    fn apply_and_open() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let args = RocksdbArgs {
+            rocksdb_block_cache: Some(bytesize::ByteSize::mib(1)),
+            rocksdb_compression: Some(super::Compression::Zstd),
+            rocksdb_zstd_level: Some(5),
+            rocksdb_background_threads: Some(2),
+            ..Default::default()
+        };
+        let mut options = args.apply(rocksdb::Options::default(), Profile::Build);
+        options.create_if_missing(true);
+
+        let db = rocksdb::DB::open(&options, tmpdir.join("rocksdb"))?;
+        db.put("key", "value")?;
+        assert_eq!(db.get("key")?.as_deref(), Some(b"value".as_slice()));
+
+        Ok(())
+    }
+}
@@ -70,7 +70,20 @@ pub async fn upload_file(src: &str, dst: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-/// Helper struct to encapsulate VCF S3 file upload and TBI creation.
+/// Return the suffix of the index file for `path`, if any.
+///
+/// BGZF-compressed VCF files are indexed with TBI and BCF files with CSI.
+pub fn index_suffix(path: &str) -> Option<&'static str> {
+    if path.ends_with(".bcf") {
+        Some(".csi")
+    } else if is_gz(path) {
+        Some(".tbi")
+    } else {
+        None
+    }
+}
+
+/// Helper struct to encapsulate VCF S3 file upload and TBI/CSI creation.
 pub struct OutputPathHelper {
     /// Temporary directory to use.
     #[allow(dead_code)] // keep around for RAII
@@ -88,9 +101,9 @@ impl OutputPathHelper {
         })?;
         Ok(Self {
             path_out_orig: path_out.to_string(),
-            path_out_effective: if s3_mode() {
+            path_out_effective: if path_out == "-" || s3_mode() {
                 tracing::debug!(
-                    "S3 mode, using temporary directory: {}",
+                    "S3 or stdout mode, using temporary directory: {}",
                     tmpdir.path().display()
                 );
                 let p = std::path::Path::new(path_out);
@@ -113,19 +126,36 @@ impl OutputPathHelper {
         &self.path_out_effective
     }
 
-    /// Create TBI file if necessary.
-    pub async fn create_tbi_for_bgzf(&self) -> Result<(), anyhow::Error> {
-        if is_gz(&self.path_out_orig) {
-            tracing::info!("Creating TBI index for BGZF VCF file...");
-            crate::common::noodles::build_tbi(
-                &self.path_out_effective,
-                &format!("{}.tbi", &self.path_out_effective),
-            )
-            .await
-            .map_err(|e| anyhow::anyhow!("problem building TBI: {}", e))?;
-            tracing::info!("... done writing TBI index");
-        } else {
-            tracing::info!("(not building TBI index for plain text VCF file");
+    /// Return whether the output is to be written to stdout.
+    pub fn is_stdout(&self) -> bool {
+        self.path_out_orig == "-"
+    }
+
+    /// Create TBI or CSI file if necessary.
+    pub async fn create_index(&self) -> Result<(), anyhow::Error> {
+        match index_suffix(&self.path_out_orig) {
+            Some(".csi") => {
+                tracing::info!("Creating CSI index for BCF file...");
+                crate::common::noodles::build_csi_for_bcf(
+                    &self.path_out_effective,
+                    &format!("{}.csi", &self.path_out_effective),
+                )
+                .map_err(|e| anyhow::anyhow!("problem building CSI: {}", e))?;
+                tracing::info!("... done writing CSI index");
+            }
+            Some(_) => {
+                tracing::info!("Creating TBI index for BGZF VCF file...");
+                crate::common::noodles::build_tbi(
+                    &self.path_out_effective,
+                    &format!("{}.tbi", &self.path_out_effective),
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("problem building TBI: {}", e))?;
+                tracing::info!("... done writing TBI index");
+            }
+            None => {
+                tracing::info!("(not building index for plain text VCF file");
+            }
         }
 
         Ok(())
@@ -133,19 +163,139 @@ impl OutputPathHelper {
 
     /// Upload to S3 if necessary.
     pub async fn upload_for_s3(&self) -> Result<(), anyhow::Error> {
-        if s3_mode() {
+        if s3_mode() && !self.is_stdout() {
             tracing::info!("Uploading to S3...");
             upload_file(&self.path_out_effective, &self.path_out_orig).await?;
-            if is_gz(&self.path_out_orig) {
-                upload_file(
-                    &format!("{}.tbi", &self.path_out_effective),
-                    &format!("{}.tbi", &self.path_out_orig),
-                )
-                .await?;
+            if let Some(suffix) = index_suffix(&self.path_out_orig) {
+                let path_index = format!("{}{}", &self.path_out_effective, suffix);
+                if std::path::Path::new(&path_index).exists() {
+                    upload_file(&path_index, &format!("{}{}", &self.path_out_orig, suffix)).await?;
+                }
             }
             tracing::info!("... done uploading to S3");
         }
 
         Ok(())
     }
+
+    /// Copy the output file to stdout if necessary.
+    pub async fn copy_to_stdout(&self) -> Result<(), anyhow::Error> {
+        self.copy_to_writer(&mut tokio::io::stdout()).await
+    }
+
+    /// Copy the output file to `writer` if the output is to be written to stdout.
+    async fn copy_to_writer<W>(&self, writer: &mut W) -> Result<(), anyhow::Error>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt as _;
+
+        if self.is_stdout() {
+            let mut file = tokio::fs::File::open(&self.path_out_effective)
+                .await
+                .map_err(|e| anyhow::anyhow!("could not open temporary output file: {}", e))?;
+            tokio::io::copy(&mut file, writer)
+                .await
+                .map_err(|e| anyhow::anyhow!("could not copy output file to stdout: {}", e))?;
+            writer
+                .flush()
+                .await
+                .map_err(|e| anyhow::anyhow!("could not flush stdout: {}", e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[rstest::rstest]
+    #[case::stdout("-", true)]
+    #[case::vcf("out.vcf", false)]
+    #[case::vcf_gz("out.vcf.gz", false)]
+    #[tokio::test]
+    async fn output_path_helper_stdout(
+        #[case] path_out: &str,
+        #[case] is_stdout: bool,
+    ) -> Result<(), anyhow::Error> {
+        let helper = super::OutputPathHelper::new(path_out)?;
+        assert_eq!(helper.is_stdout(), is_stdout);
+
+        let mut written = Vec::new();
+        if is_stdout {
+            // The output is staged in the temporary directory and then copied.
+            assert_ne!(helper.path_out(), path_out);
+            std::fs::write(helper.path_out(), "output\n")?;
+            helper.copy_to_writer(&mut written).await?;
+            assert_eq!(written, b"output\n");
+        } else {
+            // Nothing is copied, the output file is not even opened.
+            helper.copy_to_writer(&mut written).await?;
+            assert!(written.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::vcf("out.vcf", None)]
+    #[case::vcf_gz("out.vcf.gz", Some(".tbi"))]
+    #[case::bcf("out.bcf", Some(".csi"))]
+    fn index_suffix(#[case] path: &str, #[case] expected: Option<&str>) {
+        assert_eq!(super::index_suffix(path), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::vcf_gz("out.vcf.gz")]
+    #[case::bcf("out.bcf")]
+    #[tokio::test]
+    async fn create_index(#[case] name: &str) -> Result<(), anyhow::Error> {
+        use noodles::vcf;
+
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_out = tmpdir.join(name);
+        let helper = super::OutputPathHelper::new(path_out.to_str().expect("invalid path"))?;
+
+        let header: vcf::Header = "##fileformat=VCFv4.4\n\
+            ##contig=<ID=1>\n\
+            ##contig=<ID=2>\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n"
+            .parse()?;
+        let mut writer = crate::common::noodles::open_variant_writer(helper.path_out()).await?;
+        writer.write_header(&header).await?;
+        for (chrom, pos) in [("1", 100), ("1", 200), ("2", 100)] {
+            let record = vcf::variant::RecordBuf::builder()
+                .set_reference_sequence_name(chrom)
+                .set_variant_start(noodles::core::Position::try_from(pos)?)
+                .set_reference_bases("A")
+                .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                    "C".to_string()
+                ]))
+                .build();
+            writer.write_variant_record(&header, &record).await?;
+        }
+        writer.shutdown().await?;
+
+        helper.create_index().await?;
+
+        // The records can be queried by contig with the written index.
+        let mut counts = Vec::new();
+        for chrom in ["1", "2"] {
+            let region = noodles::core::Region::new(chrom, ..);
+            counts.push(if name.ends_with(".bcf") {
+                let mut reader = noodles::bcf::io::indexed_reader::Builder::default()
+                    .build_from_path(helper.path_out())?;
+                let header = reader.read_header()?;
+                reader.query(&header, &region)?.count()
+            } else {
+                let mut reader = vcf::io::indexed_reader::Builder::default()
+                    .build_from_path(helper.path_out())?;
+                let header = reader.read_header()?;
+                reader.query(&header, &region)?.count()
+            });
+        }
+        assert_eq!(counts, vec![2, 1]);
+
+        Ok(())
+    }
 }
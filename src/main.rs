@@ -2,6 +2,7 @@
 
 pub mod common;
 pub mod pbs;
+pub mod repeats;
 pub mod seqvars;
 pub mod strucvars;
 
@@ -34,6 +35,8 @@ enum Commands {
     Strucvars(Strucvars),
     /// Sequence variant related commands.
     Seqvars(Seqvars),
+    /// Repeat expansion related commands.
+    Repeats(Repeats),
 }
 
 /// Parsing of "strucvars *" sub commands.
@@ -49,11 +52,32 @@ struct Strucvars {
 #[derive(Debug, Subcommand)]
 enum StrucvarsCommands {
     Aggregate(strucvars::aggregate::cli::Args),
+    BinDiff(strucvars::txt_to_bin::diff::Args),
+    BinExport(strucvars::txt_to_bin::export::Args),
+    BinInfo(strucvars::txt_to_bin::info::Args),
+    CompoundHet(strucvars::comphet::Args),
     Ingest(strucvars::ingest::Args),
+    Lift(strucvars::lift::Args),
     Query(strucvars::query::Args),
     TxtToBin(strucvars::txt_to_bin::cli::Args),
 }
 
+/// Parsing of "repeats *" sub commands.
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Repeats {
+    /// The sub command to run
+    #[command(subcommand)]
+    command: RepeatsCommands,
+}
+
+/// Enum supporting the parsing of "repeats *" sub commands.
+#[derive(Debug, Subcommand)]
+enum RepeatsCommands {
+    Ingest(repeats::ingest::Args),
+    Query(repeats::query::Args),
+}
+
 /// Parsing of "seqvars *" sub commands.
 #[derive(Debug, Args)]
 #[command(args_conflicts_with_subcommands = true)]
@@ -67,6 +91,7 @@ struct Seqvars {
 #[derive(Debug, Subcommand)]
 enum SeqvarsCommands {
     Aggregate(seqvars::aggregate::Args),
+    Bench(seqvars::bench::Args),
     Ingest(seqvars::ingest::Args),
     Prefilter(seqvars::prefilter::Args),
     Query(seqvars::query::Args),
@@ -78,6 +103,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
     // Build a tracing subscriber according to the configuration in `cli.common`.
     let collector = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_target(false)
         .with_max_level(match cli.common.verbose.log_level() {
             Some(level) => match level {
@@ -96,12 +122,23 @@ async fn main() -> Result<(), anyhow::Error> {
     // Install collector and go into sub commands.
     let term = Term::stderr();
     match &cli.command {
+        Commands::Repeats(repeats) => match &repeats.command {
+            RepeatsCommands::Ingest(args) => {
+                repeats::ingest::run(&cli.common, args).await?;
+            }
+            RepeatsCommands::Query(args) => {
+                repeats::query::run(&cli.common, args).await?;
+            }
+        },
         Commands::Seqvars(seqvars) => match &seqvars.command {
             SeqvarsCommands::Aggregate(args) => {
                 // Note that aggregate is not async as it uses Rayon and will
                 // block internally for the read files.
                 seqvars::aggregate::run(&cli.common, args).await?;
             }
+            SeqvarsCommands::Bench(args) => {
+                seqvars::bench::run(&cli.common, args)?;
+            }
             SeqvarsCommands::Ingest(args) => {
                 seqvars::ingest::run(&cli.common, args).await?;
             }
@@ -116,9 +153,24 @@ async fn main() -> Result<(), anyhow::Error> {
             StrucvarsCommands::Aggregate(args) => {
                 strucvars::aggregate::cli::run(&cli.common, args).await?;
             }
+            StrucvarsCommands::BinDiff(args) => {
+                strucvars::txt_to_bin::diff::run(&cli.common, args)?;
+            }
+            StrucvarsCommands::BinExport(args) => {
+                strucvars::txt_to_bin::export::run(&cli.common, args)?;
+            }
+            StrucvarsCommands::BinInfo(args) => {
+                strucvars::txt_to_bin::info::run(&cli.common, args)?;
+            }
+            StrucvarsCommands::CompoundHet(args) => {
+                strucvars::comphet::run(&cli.common, args).await?;
+            }
             StrucvarsCommands::Ingest(args) => {
                 strucvars::ingest::run(&cli.common, args).await?;
             }
+            StrucvarsCommands::Lift(args) => {
+                strucvars::lift::run(&cli.common, args)?;
+            }
             StrucvarsCommands::Query(args) => {
                 strucvars::query::run(&cli.common, args).await?;
             }
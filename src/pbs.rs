@@ -62,6 +62,15 @@ pub mod varfish {
                     "/varfish.v1.strucvars.bgdb.serde.rs"
                 ));
             }
+
+            /// Code generate for protobufs by `prost-build`.
+            pub mod output {
+                include!(concat!(env!("OUT_DIR"), "/varfish.v1.strucvars.output.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/varfish.v1.strucvars.output.serde.rs"
+                ));
+            }
         }
     }
 }
@@ -0,0 +1,290 @@
+//! Implementation of `repeats ingest` subcommand.
+//!
+//! Converts the repeat genotyping output of ExpansionHunter (VCF) or STRling
+//! (genotype TSV) into JSONL records for `repeats query`.
+
+use std::{collections::HashMap, io::BufRead, io::Write, time::Instant};
+
+use clap::{command, Parser};
+use indexmap::IndexMap;
+use mehari::common::io::std::open_read_maybe_gz;
+use serde::{Deserialize, Serialize};
+
+/// The tool that the input file was created with.
+#[derive(clap::ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    /// ExpansionHunter VCF file.
+    #[default]
+    ExpansionHunter,
+    /// STRling genotype TSV file.
+    Strling,
+}
+
+/// Command line arguments for `repeats ingest` sub command.
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Ingest repeat expansion calls", long_about = None)]
+pub struct Args {
+    /// Format of the input file.
+    #[arg(long, value_enum, default_value_t = InputFormat::ExpansionHunter)]
+    pub input_format: InputFormat,
+    /// Name of the sample, required for STRling as its output has no sample name.
+    #[arg(long, required_if_eq("input_format", "strling"))]
+    pub sample: Option<String>,
+    /// Path to the input file.
+    #[arg(long)]
+    pub path_in: String,
+    /// Path to the output JSONL file.
+    #[arg(long)]
+    pub path_out: String,
+}
+
+/// Repeat genotype of one sample.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RepeatCall {
+    /// The genotype, if given by the caller, e.g., "1/2".
+    pub genotype: Option<String>,
+    /// Repeat counts of the alleles.
+    pub repeat_counts: Vec<u32>,
+    /// Type of the supporting reads, e.g., "SPANNING/IRR", if given by the caller.
+    pub support: Option<String>,
+}
+
+/// A genotyped repeat locus.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RepeatRecord {
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based start position of the repeat.
+    pub start: i32,
+    /// 1-based end position of the repeat.
+    pub end: i32,
+    /// Repeat ID from the variant catalog, e.g., "HTT".
+    pub repeat_id: Option<String>,
+    /// The repeat unit on the reference strand, e.g., "CAG".
+    pub repeat_unit: String,
+    /// Repeat count in the reference, if known.
+    pub ref_repeat_count: Option<u32>,
+    /// The tool that genotyped the repeat.
+    pub caller: InputFormat,
+    /// Genotypes by sample name.
+    pub calls: IndexMap<String, RepeatCall>,
+}
+
+/// Parse the semicolon separated `INFO` column into a map.
+fn parse_info(info: &str) -> HashMap<&str, &str> {
+    info.split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .collect()
+}
+
+/// Parse the slash-separated repeat counts, e.g., "20/45", ignoring missing values.
+fn parse_repeat_counts(value: &str) -> Vec<u32> {
+    value
+        .split('/')
+        .filter_map(|count| count.parse::<u32>().ok())
+        .collect()
+}
+
+/// Parse one data line of an ExpansionHunter VCF file with the given `samples`.
+pub fn parse_expansion_hunter_line(
+    line: &str,
+    samples: &[String],
+) -> Result<RepeatRecord, anyhow::Error> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() < 9 + samples.len() {
+        anyhow::bail!("too few columns in VCF line: {}", line);
+    }
+    let info = parse_info(fields[7]);
+    let pos = fields[1]
+        .parse::<i32>()
+        .map_err(|e| anyhow::anyhow!("invalid position {}: {}", fields[1], e))?;
+    let end = info
+        .get("END")
+        .ok_or_else(|| anyhow::anyhow!("missing INFO/END in: {}", line))?
+        .parse::<i32>()
+        .map_err(|e| anyhow::anyhow!("invalid INFO/END: {}", e))?;
+
+    let format = fields[8].split(':').collect::<Vec<_>>();
+    let calls = samples
+        .iter()
+        .zip(fields[9..].iter())
+        .map(|(sample, values)| {
+            let values = format
+                .iter()
+                .copied()
+                .zip(values.split(':'))
+                .collect::<HashMap<_, _>>();
+            let value = |key: &str| {
+                values
+                    .get(key)
+                    .filter(|value| **value != ".")
+                    .map(|value| value.to_string())
+            };
+            let call = RepeatCall {
+                genotype: value("GT"),
+                repeat_counts: value("REPCN")
+                    .map(|repcn| parse_repeat_counts(&repcn))
+                    .unwrap_or_default(),
+                support: value("SO"),
+            };
+            (sample.clone(), call)
+        })
+        .collect();
+
+    Ok(RepeatRecord {
+        chrom: annonars::common::cli::canonicalize(fields[0]),
+        // The VCF position is the base before the repeat.
+        start: pos + 1,
+        end,
+        repeat_id: info
+            .get("REPID")
+            .or_else(|| info.get("VARID"))
+            .map(|id| id.to_string()),
+        repeat_unit: info.get("RU").map(|ru| ru.to_string()).unwrap_or_default(),
+        ref_repeat_count: info.get("REF").and_then(|count| count.parse().ok()),
+        caller: InputFormat::ExpansionHunter,
+        calls,
+    })
+}
+
+/// Parse one data line of a STRling genotype file for `sample`.
+///
+/// The columns are `#chrom`, `left`, `right`, `repeatunit`, `allele1_est`,
+/// `allele2_est`, followed by read counts that are ignored.
+pub fn parse_strling_line(line: &str, sample: &str) -> Result<RepeatRecord, anyhow::Error> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() < 6 {
+        anyhow::bail!("too few columns in STRling line: {}", line);
+    }
+    let parse_pos = |value: &str| {
+        value
+            .parse::<i32>()
+            .map_err(|e| anyhow::anyhow!("invalid position {}: {}", value, e))
+    };
+    let repeat_counts = fields[4..6]
+        .iter()
+        .filter_map(|value| value.parse::<f32>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .map(|value| value.round() as u32)
+        .collect();
+
+    Ok(RepeatRecord {
+        chrom: annonars::common::cli::canonicalize(fields[0]),
+        start: parse_pos(fields[1])? + 1,
+        end: parse_pos(fields[2])?,
+        repeat_id: None,
+        repeat_unit: fields[3].to_string(),
+        ref_repeat_count: None,
+        caller: InputFormat::Strling,
+        calls: IndexMap::from([(
+            sample.to_string(),
+            RepeatCall {
+                repeat_counts,
+                ..Default::default()
+            },
+        )]),
+    })
+}
+
+/// Read all records from `reader` in the given `input_format`.
+fn read_records(
+    reader: Box<dyn BufRead>,
+    input_format: InputFormat,
+    sample: Option<&str>,
+) -> Result<Vec<RepeatRecord>, anyhow::Error> {
+    let mut samples = Vec::new();
+    let mut result = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(header) = line.strip_prefix("#CHROM") {
+            samples = header
+                .split('\t')
+                .skip(9)
+                .map(|sample| sample.to_string())
+                .collect();
+            continue;
+        } else if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        result.push(match input_format {
+            InputFormat::ExpansionHunter => parse_expansion_hunter_line(&line, &samples)?,
+            InputFormat::Strling => parse_strling_line(&line, sample.expect("checked by clap"))?,
+        });
+    }
+    Ok(result)
+}
+
+/// Main entry point for `repeats ingest` sub command.
+pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    let before_anything = Instant::now();
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Reading input file...");
+    let records = read_records(
+        open_read_maybe_gz(&args.path_in)?,
+        args.input_format,
+        args.sample.as_deref(),
+    )?;
+    tracing::info!("... done reading {} records", records.len());
+
+    let mut writer = std::io::BufWriter::new(
+        std::fs::File::create(&args.path_out)
+            .map_err(|e| anyhow::anyhow!("could not create output file: {}", e))?,
+    );
+    for record in &records {
+        writeln!(writer, "{}", serde_json::to_string(record)?)
+            .map_err(|e| anyhow::anyhow!("could not write record: {}", e))?;
+    }
+    writer.flush()?;
+
+    tracing::info!(
+        "All of `repeats ingest` completed in {:?}",
+        before_anything.elapsed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InputFormat, RepeatCall};
+
+    #[test]
+    fn parse_expansion_hunter_line() -> Result<(), anyhow::Error> {
+        let line = "chr4\t3076603\t.\tC\t<STR45>\t.\tPASS\t\
+            SVTYPE=STR;END=3076660;REF=19;RL=57;RU=CAG;VARID=HTT;REPID=HTT\t\
+            GT:SO:REPCN:REPCI\t0/1:SPANNING/SPANNING:19/45:19-19/43-47";
+        let record = super::parse_expansion_hunter_line(line, &["index".to_string()])?;
+
+        assert_eq!(record.chrom, "4");
+        assert_eq!((record.start, record.end), (3076604, 3076660));
+        assert_eq!(record.repeat_id.as_deref(), Some("HTT"));
+        assert_eq!(record.repeat_unit, "CAG");
+        assert_eq!(record.ref_repeat_count, Some(19));
+        assert_eq!(record.caller, InputFormat::ExpansionHunter);
+        assert_eq!(
+            record.calls.get("index"),
+            Some(&RepeatCall {
+                genotype: Some("0/1".into()),
+                repeat_counts: vec![19, 45],
+                support: Some("SPANNING/SPANNING".into()),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_strling_line() -> Result<(), anyhow::Error> {
+        let line = "chr9\t27573527\t27573546\tGGGGCC\t7.2\tnan\t40\t30\t2\t0\t0\t0";
+        let record = super::parse_strling_line(line, "index")?;
+
+        assert_eq!(record.chrom, "9");
+        assert_eq!((record.start, record.end), (27573528, 27573546));
+        assert_eq!(record.repeat_id, None);
+        assert_eq!(record.calls.get("index").unwrap().repeat_counts, vec![7]);
+
+        Ok(())
+    }
+}
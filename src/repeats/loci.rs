@@ -0,0 +1,142 @@
+//! Known disease-associated repeat loci.
+//!
+//! The loci are identified by the repeat ID used in the ExpansionHunter
+//! variant catalogs, i.e., the gene symbol.
+
+use serde::Serialize;
+
+/// Mode of inheritance of a repeat expansion disorder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Inheritance {
+    /// Autosomal dominant.
+    AutosomalDominant,
+    /// Autosomal recessive.
+    AutosomalRecessive,
+    /// X-linked.
+    XLinked,
+}
+
+/// A known disease-associated repeat locus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DiseaseLocus {
+    /// Repeat ID, equal to the gene symbol.
+    pub repeat_id: &'static str,
+    /// Repeat unit in the gene's orientation.
+    pub repeat_unit: &'static str,
+    /// Minimal repeat count of pathogenic alleles.
+    pub pathogenic_min: u32,
+    /// Name of the disease.
+    pub disease: &'static str,
+    /// Mode of inheritance.
+    pub inheritance: Inheritance,
+}
+
+/// The known disease loci.
+pub const DISEASE_LOCI: &[DiseaseLocus] = &[
+    DiseaseLocus {
+        repeat_id: "AR",
+        repeat_unit: "CAG",
+        pathogenic_min: 38,
+        disease: "Spinal and bulbar muscular atrophy",
+        inheritance: Inheritance::XLinked,
+    },
+    DiseaseLocus {
+        repeat_id: "ATN1",
+        repeat_unit: "CAG",
+        pathogenic_min: 48,
+        disease: "Dentatorubral-pallidoluysian atrophy",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "ATXN1",
+        repeat_unit: "CAG",
+        pathogenic_min: 39,
+        disease: "Spinocerebellar ataxia type 1",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "ATXN2",
+        repeat_unit: "CAG",
+        pathogenic_min: 33,
+        disease: "Spinocerebellar ataxia type 2",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "ATXN3",
+        repeat_unit: "CAG",
+        pathogenic_min: 60,
+        disease: "Spinocerebellar ataxia type 3",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "ATXN7",
+        repeat_unit: "CAG",
+        pathogenic_min: 36,
+        disease: "Spinocerebellar ataxia type 7",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "C9ORF72",
+        repeat_unit: "GGGGCC",
+        pathogenic_min: 31,
+        disease: "Frontotemporal dementia and/or amyotrophic lateral sclerosis",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "CACNA1A",
+        repeat_unit: "CAG",
+        pathogenic_min: 20,
+        disease: "Spinocerebellar ataxia type 6",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "DMPK",
+        repeat_unit: "CTG",
+        pathogenic_min: 50,
+        disease: "Myotonic dystrophy type 1",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+    DiseaseLocus {
+        repeat_id: "FMR1",
+        repeat_unit: "CGG",
+        pathogenic_min: 200,
+        disease: "Fragile X syndrome",
+        inheritance: Inheritance::XLinked,
+    },
+    DiseaseLocus {
+        repeat_id: "FXN",
+        repeat_unit: "GAA",
+        pathogenic_min: 66,
+        disease: "Friedreich ataxia",
+        inheritance: Inheritance::AutosomalRecessive,
+    },
+    DiseaseLocus {
+        repeat_id: "HTT",
+        repeat_unit: "CAG",
+        pathogenic_min: 36,
+        disease: "Huntington disease",
+        inheritance: Inheritance::AutosomalDominant,
+    },
+];
+
+/// Return the known disease locus with the given `repeat_id`, if any.
+pub fn disease_locus(repeat_id: &str) -> Option<&'static DiseaseLocus> {
+    DISEASE_LOCI
+        .iter()
+        .find(|locus| locus.repeat_id.eq_ignore_ascii_case(repeat_id))
+}
+
+#[cfg(test)]
+mod test {
+    #[rstest::rstest]
+    #[case("HTT", Some(36))]
+    #[case("C9orf72", Some(31))]
+    #[case("NOTCH2NLC", None)]
+    fn disease_locus(#[case] repeat_id: &str, #[case] pathogenic_min: Option<u32>) {
+        assert_eq!(
+            super::disease_locus(repeat_id).map(|locus| locus.pathogenic_min),
+            pathogenic_min
+        );
+    }
+}
@@ -0,0 +1,5 @@
+//! Code for short tandem repeat expansions.
+
+pub mod ingest;
+pub mod loci;
+pub mod query;
@@ -0,0 +1,206 @@
+//! Implementation of `repeats query` subcommand.
+//!
+//! Filters the records written by `repeats ingest` on the repeat unit, the
+//! allele repeat counts relative to the pathogenic threshold, and the known
+//! disease loci.
+
+use std::{fs::File, io::BufRead, io::Write, time::Instant};
+
+use clap::{command, Parser};
+use mehari::common::io::std::open_read_maybe_gz;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ingest::RepeatRecord,
+    loci::{disease_locus, DiseaseLocus},
+};
+
+/// Command line arguments for `repeats query` sub command.
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Run query for repeat expansions", long_about = None)]
+pub struct Args {
+    /// Path to query JSON file.
+    #[arg(long)]
+    pub path_query_json: String,
+    /// Path to the JSONL file from `repeats ingest`.
+    #[arg(long)]
+    pub path_input: String,
+    /// Path to the output JSONL file.
+    #[arg(long)]
+    pub path_output: String,
+}
+
+/// Query for repeat expansions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RepeatQuery {
+    /// Samples to consider the alleles of, all if empty.
+    #[serde(default)]
+    pub samples: Vec<String>,
+    /// Repeat units to limit the results to, case insensitive, all if empty.
+    #[serde(default)]
+    pub repeat_units: Vec<String>,
+    /// Whether to limit the results to known disease loci.
+    #[serde(default)]
+    pub known_loci_only: bool,
+    /// Minimal repeat count of the longest allele.
+    pub min_repeat_count: Option<u32>,
+    /// Minimal ratio of the longest allele to the pathogenic threshold of known
+    /// disease loci, e.g., `0.9` to include alleles close to the threshold.
+    pub min_pathogenic_ratio: Option<f32>,
+}
+
+/// A record passing the query with its annotation.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResultRecord {
+    /// The genotyped repeat locus.
+    #[serde(flatten)]
+    pub record: RepeatRecord,
+    /// The longest allele in the considered samples.
+    pub max_repeat_count: u32,
+    /// The known disease locus, if any.
+    pub disease_locus: Option<DiseaseLocus>,
+    /// Ratio of `max_repeat_count` to the pathogenic threshold of the disease locus.
+    pub pathogenic_ratio: Option<f32>,
+    /// Whether `max_repeat_count` reaches the pathogenic threshold.
+    pub is_pathogenic: bool,
+}
+
+impl RepeatQuery {
+    /// Annotate `record` and return it if it passes the query.
+    pub fn apply(&self, record: RepeatRecord) -> Option<ResultRecord> {
+        if !self.repeat_units.is_empty()
+            && !self
+                .repeat_units
+                .iter()
+                .any(|unit| unit.eq_ignore_ascii_case(&record.repeat_unit))
+        {
+            return None;
+        }
+
+        let disease_locus = record.repeat_id.as_deref().and_then(disease_locus).copied();
+        if self.known_loci_only && disease_locus.is_none() {
+            return None;
+        }
+
+        let max_repeat_count = record
+            .calls
+            .iter()
+            .filter(|(sample, _)| self.samples.is_empty() || self.samples.contains(sample))
+            .flat_map(|(_, call)| call.repeat_counts.iter().copied())
+            .max()?;
+        if self
+            .min_repeat_count
+            .is_some_and(|min| max_repeat_count < min)
+        {
+            return None;
+        }
+
+        let pathogenic_ratio =
+            disease_locus.map(|locus| max_repeat_count as f32 / locus.pathogenic_min as f32);
+        if let Some(min_ratio) = self.min_pathogenic_ratio {
+            if pathogenic_ratio.map_or(true, |ratio| ratio < min_ratio) {
+                return None;
+            }
+        }
+
+        Some(ResultRecord {
+            record,
+            max_repeat_count,
+            disease_locus,
+            pathogenic_ratio,
+            is_pathogenic: pathogenic_ratio.is_some_and(|ratio| ratio >= 1.0),
+        })
+    }
+}
+
+/// Main entry point for `repeats query` sub command.
+pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    let before_anything = Instant::now();
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading query...");
+    let query: RepeatQuery = serde_json::from_reader(File::open(&args.path_query_json)?)?;
+    tracing::info!(
+        "... done loading query = {}",
+        &serde_json::to_string(&query)?
+    );
+
+    let mut writer = std::io::BufWriter::new(
+        File::create(&args.path_output)
+            .map_err(|e| anyhow::anyhow!("could not create output file: {}", e))?,
+    );
+    let (mut count_total, mut count_passed) = (0, 0);
+    for line in open_read_maybe_gz(&args.path_input)?.lines() {
+        let record: RepeatRecord = serde_json::from_str(&line?)
+            .map_err(|e| anyhow::anyhow!("could not parse input record: {}", e))?;
+        count_total += 1;
+        if let Some(result) = query.apply(record) {
+            count_passed += 1;
+            writeln!(writer, "{}", serde_json::to_string(&result)?)
+                .map_err(|e| anyhow::anyhow!("could not write record: {}", e))?;
+        }
+    }
+    writer.flush()?;
+
+    tracing::info!(
+        "summary: {} records passed out of {}",
+        count_passed,
+        count_total
+    );
+    tracing::info!(
+        "All of `repeats query` completed in {:?}",
+        before_anything.elapsed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+
+    use super::RepeatQuery;
+    use crate::repeats::ingest::{RepeatCall, RepeatRecord};
+
+    fn record(repeat_id: Option<&str>, repeat_counts: Vec<u32>) -> RepeatRecord {
+        RepeatRecord {
+            chrom: "4".into(),
+            start: 3076604,
+            end: 3076660,
+            repeat_id: repeat_id.map(String::from),
+            repeat_unit: "CAG".into(),
+            calls: IndexMap::from([(
+                "index".to_string(),
+                RepeatCall {
+                    repeat_counts,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::default(RepeatQuery::default(), Some("HTT"), vec![19, 45], Some(true))]
+    #[case::normal(RepeatQuery::default(), Some("HTT"), vec![19, 20], Some(false))]
+    #[case::unit(RepeatQuery { repeat_units: vec!["cag".into()], ..Default::default() }, Some("HTT"), vec![19, 45], Some(true))]
+    #[case::other_unit(RepeatQuery { repeat_units: vec!["GAA".into()], ..Default::default() }, Some("HTT"), vec![19, 45], None)]
+    #[case::known_only(RepeatQuery { known_loci_only: true, ..Default::default() }, None, vec![19, 45], None)]
+    #[case::min_count(RepeatQuery { min_repeat_count: Some(50), ..Default::default() }, Some("HTT"), vec![19, 45], None)]
+    #[case::near_threshold(RepeatQuery { min_pathogenic_ratio: Some(0.9), ..Default::default() }, Some("HTT"), vec![19, 33], Some(false))]
+    #[case::below_ratio(RepeatQuery { min_pathogenic_ratio: Some(0.9), ..Default::default() }, Some("HTT"), vec![19, 30], None)]
+    #[case::other_sample(RepeatQuery { samples: vec!["father".into()], ..Default::default() }, Some("HTT"), vec![19, 45], None)]
+    fn apply(
+        #[case] query: RepeatQuery,
+        #[case] repeat_id: Option<&str>,
+        #[case] repeat_counts: Vec<u32>,
+        #[case] expected: Option<bool>,
+    ) {
+        assert_eq!(
+            query
+                .apply(record(repeat_id, repeat_counts))
+                .map(|result| result.is_pathogenic),
+            expected
+        );
+    }
+}
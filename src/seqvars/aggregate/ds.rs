@@ -48,6 +48,135 @@ impl Counts {
         self.count_homalt += other.count_homalt;
         self.count_hemialt += other.count_hemialt;
     }
+
+    /// Subtract other from self, e.g., when removing a case.
+    ///
+    /// Self is left unchanged if other exceeds any of the counts.
+    pub fn subtract(&mut self, other: &Self) -> Result<(), CountsUnderflowError> {
+        let (
+            Some(count_homref),
+            Some(count_hemiref),
+            Some(count_het),
+            Some(count_homalt),
+            Some(count_hemialt),
+        ) = (
+            self.count_homref.checked_sub(other.count_homref),
+            self.count_hemiref.checked_sub(other.count_hemiref),
+            self.count_het.checked_sub(other.count_het),
+            self.count_homalt.checked_sub(other.count_homalt),
+            self.count_hemialt.checked_sub(other.count_hemialt),
+        )
+        else {
+            return Err(CountsUnderflowError {
+                minuend: self.clone(),
+                subtrahend: other.clone(),
+            });
+        };
+        *self = Self {
+            count_homref,
+            count_hemiref,
+            count_het,
+            count_homalt,
+            count_hemialt,
+        };
+        Ok(())
+    }
+
+    /// Whether all counts are zero.
+    pub fn is_empty(&self) -> bool {
+        self.count_homref == 0
+            && self.count_hemiref == 0
+            && self.count_het == 0
+            && self.count_homalt == 0
+            && self.count_hemialt == 0
+    }
+}
+
+/// Error for subtracting counts that exceed the counts subtracted from.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("cannot subtract {subtrahend:?} from {minuend:?}")]
+pub struct CountsUnderflowError {
+    /// The counts subtracted from.
+    pub minuend: Counts,
+    /// The counts to subtract.
+    pub subtrahend: Counts,
+}
+
+/// Genotype counts by sex of the carriers, individuals of unknown sex are not counted.
+#[derive(Debug, Default, Clone)]
+pub struct CountsBySex {
+    /// Counts of female individuals.
+    pub female: Counts,
+    /// Counts of male individuals.
+    pub male: Counts,
+}
+
+impl CountsBySex {
+    /// Convert to a byte vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = self.female.to_vec();
+        buf.extend_from_slice(&self.male.to_vec());
+        buf
+    }
+
+    /// Convert from a byte vector.
+    pub fn from_vec(buf: &[u8]) -> Self {
+        Self {
+            female: Counts::from_vec(&buf[0..20]),
+            male: Counts::from_vec(&buf[20..40]),
+        }
+    }
+
+    /// Aggregate other into self.
+    pub fn aggregate(&mut self, other: Self) {
+        self.female.aggregate(other.female);
+        self.male.aggregate(other.male);
+    }
+
+    /// Subtract other from self, e.g., when removing a case.
+    ///
+    /// Self is left unchanged if other exceeds any of the counts.
+    pub fn subtract(&mut self, other: &Self) -> Result<(), CountsUnderflowError> {
+        let mut female = self.female.clone();
+        female.subtract(&other.female)?;
+        self.male.subtract(&other.male)?;
+        self.female = female;
+        Ok(())
+    }
+}
+
+/// Contribution of one case to the counts of a variant.
+///
+/// This is stored with the manifest such that the case can be removed again.
+#[derive(Debug, Default, Clone)]
+pub struct Contribution {
+    /// Contribution to the counts.
+    pub counts: Counts,
+    /// Contribution to the counts by sex.
+    pub counts_by_sex: CountsBySex,
+}
+
+impl Contribution {
+    /// Convert to a byte vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = self.counts.to_vec();
+        buf.extend_from_slice(&self.counts_by_sex.to_vec());
+        buf
+    }
+
+    /// Convert from a byte vector.
+    pub fn from_vec(buf: &[u8]) -> Self {
+        Self {
+            counts: Counts::from_vec(&buf[0..20]),
+            counts_by_sex: CountsBySex::from_vec(&buf[20..60]),
+        }
+    }
+
+    /// Aggregate other into self.
+    pub fn aggregate(&mut self, other: Self) {
+        self.counts.aggregate(other.counts);
+        self.counts_by_sex.aggregate(other.counts_by_sex);
+    }
 }
 
 /// Genotype in a carrier.
@@ -140,6 +269,11 @@ impl CarrierList {
         self.carriers.sort();
         self.carriers.dedup();
     }
+
+    /// Remove all carriers of the case with `uuid`.
+    pub fn remove_case(&mut self, uuid: &uuid::Uuid) {
+        self.carriers.retain(|carrier| carrier.uuid != *uuid);
+    }
 }
 
 impl TryFrom<&[u8]> for CarrierList {
@@ -185,6 +319,65 @@ mod test {
         insta::assert_debug_snapshot!(&counts2);
     }
 
+    #[test]
+    fn test_counts_by_sex() {
+        let counts_by_sex = CountsBySex {
+            female: Counts {
+                count_homref: 1,
+                count_het: 2,
+                count_homalt: 3,
+                ..Default::default()
+            },
+            male: Counts {
+                count_hemiref: 4,
+                count_hemialt: 5,
+                ..Default::default()
+            },
+        };
+
+        let buf = counts_by_sex.to_vec();
+        assert_eq!(buf.len(), 40);
+
+        let mut counts_by_sex2 = CountsBySex::from_vec(&buf);
+        assert_eq!(counts_by_sex2.female.count_het, 2);
+        assert_eq!(counts_by_sex2.male.count_hemialt, 5);
+
+        counts_by_sex2.subtract(&counts_by_sex).unwrap();
+        assert!(counts_by_sex2.female.is_empty() && counts_by_sex2.male.is_empty());
+
+        // Subtracting more than the counts fails and leaves the counts unchanged.
+        let mut counts_by_sex3 = CountsBySex::from_vec(&buf);
+        counts_by_sex3.female.count_het = 1;
+        assert!(counts_by_sex3.subtract(&counts_by_sex).is_err());
+        assert_eq!(counts_by_sex3.female.count_het, 1);
+        assert_eq!(counts_by_sex3.male.count_hemialt, 5);
+    }
+
+    #[test]
+    fn test_contribution() {
+        let contribution = Contribution {
+            counts: Counts {
+                count_het: 1,
+                ..Default::default()
+            },
+            counts_by_sex: CountsBySex {
+                female: Counts {
+                    count_het: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        };
+
+        let buf = contribution.to_vec();
+        assert_eq!(buf.len(), 60);
+
+        let mut contribution2 = Contribution::from_vec(&buf);
+        contribution2.aggregate(contribution);
+        assert_eq!(contribution2.counts.count_het, 2);
+        assert_eq!(contribution2.counts_by_sex.female.count_het, 2);
+    }
+
     #[test]
     fn test_carrier_list() -> Result<(), anyhow::Error> {
         let carrier_list = CarrierList {
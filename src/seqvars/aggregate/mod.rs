@@ -30,6 +30,9 @@ pub struct Args {
     /// Column family name for the carrier UUID data.
     #[clap(long, default_value = "carriers")]
     pub cf_carriers: String,
+    /// Column family name for the count data by sex.
+    #[clap(long, default_value = "counts_by_sex")]
+    pub cf_counts_by_sex: String,
     /// Set the number of threads to use, defaults to number of cores.
     #[clap(long)]
     pub num_threads: Option<usize>,
@@ -37,10 +40,92 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Tuning of the RocksDB.
+    #[clap(flatten)]
+    pub rocksdb: common::rocksdb_tuning::RocksdbArgs,
+
+    /// UUID of a case to remove from the database, using its contribution stored with
+    /// the manifest.
+    #[arg(long)]
+    pub remove_case: Vec<String>,
+    /// Path to the in-house RocksDB of another site to merge into the database.
+    #[arg(long)]
+    pub path_merge_rocksdb: Vec<String>,
+    /// Upgrade an existing database with an older schema version in place.
+    ///
+    /// The upgrade from version 1 reads the VCF files of the cases in the database
+    /// from `--path-input` or from the manifest.
+    #[arg(long, default_value_t = false)]
+    pub migrate: bool,
+}
+
+/// Version of the database schema written by `seqvars aggregate`.
+///
+/// - 1: counts and carriers, databases without `schema-version` in `meta`
+/// - 2: adds the counts by sex
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Parse the value of the `schema-version` key in the `meta` column family.
+///
+/// Databases written before the key was introduced have schema version 1.
+pub fn parse_schema_version(value: Option<&[u8]>) -> Result<u32, anyhow::Error> {
+    let Some(value) = value else {
+        return Ok(1);
+    };
+    String::from_utf8_lossy(value).parse().map_err(|e| {
+        anyhow::anyhow!(
+            "invalid schema version {}: {}",
+            String::from_utf8_lossy(value),
+            e
+        )
+    })
+}
+
+/// Prefix of the keys in the `meta` column family that list the imported cases.
+///
+/// The key is followed by the case UUID and the value is the path of the imported
+/// VCF file.
+pub const MANIFEST_KEY_PREFIX: &str = "case:";
+
+/// Return the key of the case with `case_uuid` in the `meta` column family.
+pub(crate) fn manifest_key(case_uuid: &uuid::Uuid) -> String {
+    format!("{}{}", MANIFEST_KEY_PREFIX, case_uuid)
+}
+
+/// Prefix of the keys in the `meta` column family that mark cases being imported.
+///
+/// The key is followed by the case UUID and the value is the path of the VCF file.
+/// It is written before the first record of the case and replaced by the manifest
+/// key once all records are imported.
+pub const PENDING_KEY_PREFIX: &str = "pending:";
+
+/// Return the key of the pending import of the case with `case_uuid` in the `meta`
+/// column family.
+pub(crate) fn pending_key(case_uuid: &uuid::Uuid) -> String {
+    format!("{}{}", PENDING_KEY_PREFIX, case_uuid)
+}
+
+/// Prefix of the keys in the `meta` column family with the contribution of the
+/// cases in the manifest to the counts.
+///
+/// The key is followed by the case UUID, a colon, and the variant key, and the
+/// value is the `ds::Contribution` of the case to the variant.
+pub const CONTRIBUTION_KEY_PREFIX: &str = "contribution:";
+
+/// Return the prefix of the contribution keys of the case with `case_uuid`.
+pub(crate) fn contribution_key_prefix(case_uuid: &uuid::Uuid) -> Vec<u8> {
+    format!("{}{}:", CONTRIBUTION_KEY_PREFIX, case_uuid).into_bytes()
+}
+
+/// Return the key of the contribution of the case with `case_uuid` to the variant `key`.
+pub(crate) fn contribution_key(case_uuid: &uuid::Uuid, key: &[u8]) -> Vec<u8> {
+    let mut result = contribution_key_prefix(case_uuid);
+    result.extend_from_slice(key);
+    result
 }
 
 /// Returns whether the given coordinate is in PAR for `chrom`, `pos` (1-based) and `genombuild`.
-fn is_par(chrom: Chrom, pos: usize, genomebuild: crate::common::GenomeRelease) -> bool {
+pub(crate) fn is_par(chrom: Chrom, pos: usize, genomebuild: crate::common::GenomeRelease) -> bool {
     match (chrom, genomebuild) {
         (Chrom::X, crate::common::GenomeRelease::Grch37) => {
             (60001..=2699520).contains(&pos) || (154931044..=155260560).contains(&pos)
@@ -58,14 +143,14 @@ fn is_par(chrom: Chrom, pos: usize, genomebuild: crate::common::GenomeRelease) -
     }
 }
 
-/// Extract counts and carrier data from a single VCF record.
+/// Extract counts, counts by sex, and carrier data from a single VCF record.
 fn handle_record(
     input_record: &vcf::variant::RecordBuf,
     input_header: &vcf::Header,
     pedigree: &mehari::ped::PedigreeByName,
     case_uuid: &uuid::Uuid,
     genomebuild: crate::common::GenomeRelease,
-) -> Result<(ds::Counts, ds::CarrierList), anyhow::Error> {
+) -> Result<(ds::Counts, ds::CountsBySex, ds::CarrierList), anyhow::Error> {
     let chrom: Chrom = annonars::common::cli::canonicalize(
         input_record.reference_sequence_name().to_string().as_str(),
     )
@@ -73,6 +158,7 @@ fn handle_record(
     .parse()?;
 
     let mut res_counts = ds::Counts::default();
+    let mut res_counts_by_sex = ds::CountsBySex::default();
     let mut res_carriers = ds::CarrierList::default();
 
     let start: usize = input_record
@@ -113,6 +199,7 @@ fn handle_record(
             NoPar
         };
 
+        let counts_before = res_counts.clone();
         let carrier_genotype = match (chrom, is_par, individual.sex, genotype) {
             (_, _, _, Genotype::WithNoCall) => continue,
             // On the autosomes, male/female are handled the same.
@@ -171,6 +258,14 @@ fn handle_record(
             | (Chrom::Y, _, mehari::ped::Sex::Unknown, _) => ds::Genotype::HomRef,
         };
 
+        let mut counts_sample = res_counts.clone();
+        counts_sample.subtract(&counts_before)?;
+        match individual.sex {
+            mehari::ped::Sex::Female => res_counts_by_sex.female.aggregate(counts_sample),
+            mehari::ped::Sex::Male => res_counts_by_sex.male.aggregate(counts_sample),
+            mehari::ped::Sex::Unknown => (),
+        }
+
         if carrier_genotype != ds::Genotype::HomRef {
             res_carriers.carriers.push(ds::Carrier {
                 uuid: *case_uuid,
@@ -184,17 +279,25 @@ fn handle_record(
         }
     }
 
-    Ok((res_counts, res_carriers))
+    Ok((res_counts, res_counts_by_sex, res_carriers))
 }
 
 /// Import one VCF file into the database.
 ///
+/// Files of cases that are already listed in the manifest or in `claimed` are
+/// skipped, such that the counts can be updated incrementally.  The records are
+/// written in one transaction each, so the case is marked as pending until all
+/// records are imported.  The contributions of a case that is still pending from an
+/// interrupted earlier run are removed before importing it again.
+///
 /// This function is `async` because we potentially need to read from S3.
 async fn import_vcf(
     db: &Arc<rocksdb::TransactionDB<rocksdb::MultiThreaded>>,
+    claimed: &std::sync::Mutex<std::collections::HashSet<uuid::Uuid>>,
     path_input: &str,
     cf_counts: &str,
     cf_carriers: &str,
+    cf_counts_by_sex: &str,
     genomebuild: crate::common::GenomeRelease,
 ) -> Result<(), anyhow::Error> {
     let mut input_reader = common::noodles::open_vcf_reader(path_input)
@@ -202,16 +305,42 @@ async fn import_vcf(
         .map_err(|e| anyhow::anyhow!("could not open file {} for reading: {}", path_input, e))?;
     let input_header = input_reader.read_header().await?;
 
+    let (pedigree, case_uuid) = common::extract_pedigree_and_case_uuid(&input_header)?;
+
+    let cf_meta = db.cf_handle("meta").expect("checked earlier");
+    let manifest_key = manifest_key(&case_uuid);
+    let is_imported = db.get_cf(&cf_meta, &manifest_key)?.is_some();
+    if is_imported || !claimed.lock().expect("lock poisoned").insert(case_uuid) {
+        tracing::warn!(
+            "skipping {} as case {} is already in the database",
+            path_input,
+            &case_uuid
+        );
+        return Ok(());
+    }
+
+    let pending_key = pending_key(&case_uuid);
+    if db.get_cf(&cf_meta, &pending_key)?.is_some() {
+        tracing::warn!(
+            "removing the contributions of case {} from an interrupted import before importing {}",
+            &case_uuid,
+            path_input
+        );
+        remove_case(db, &case_uuid, cf_counts, cf_carriers, cf_counts_by_sex)?;
+    }
+    db.put_cf(&cf_meta, &pending_key, path_input)
+        .map_err(|e| anyhow::anyhow!("problem marking {} as pending: {}", path_input, e))?;
+
     let cf_counts = db.cf_handle(cf_counts).expect("checked earlier");
     let cf_carriers = db.cf_handle(cf_carriers).expect("checked earlier");
+    let cf_counts_by_sex = db.cf_handle(cf_counts_by_sex).expect("checked earlier");
 
-    let (pedigree, case_uuid) = common::extract_pedigree_and_case_uuid(&input_header)?;
     let mut prev = std::time::Instant::now();
 
     let mut records = input_reader.records(&input_header).await;
     while let Some(record_buf) = records.try_next().await? {
         // Obtain counts from the current variant.
-        let (this_counts_data, this_carrier_data) = handle_record(
+        let (this_counts_data, this_counts_by_sex_data, this_carrier_data) = handle_record(
             &record_buf,
             &input_header,
             &pedigree,
@@ -226,7 +355,12 @@ async fn import_vcf(
         let mut retries = 0;
         while retries < max_retries {
             let this_counts_data = this_counts_data.clone();
+            let this_counts_by_sex_data = this_counts_by_sex_data.clone();
             let this_carrier_data = this_carrier_data.clone();
+            let mut this_contribution = ds::Contribution {
+                counts: this_counts_data.clone(),
+                counts_by_sex: this_counts_by_sex_data.clone(),
+            };
 
             let transaction = db.transaction();
 
@@ -238,6 +372,17 @@ async fn import_vcf(
                             e
                         )
                     })?.map(|buffer| ds::Counts::from_vec(&buffer)).unwrap_or_default();
+            let mut db_counts_by_sex_data = transaction
+                .get_cf(&cf_counts_by_sex, key.clone())
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "problem acessing counts by sex data for variant {:?}: {}",
+                        &vcf_var,
+                        e
+                    )
+                })?
+                .map(|buffer| ds::CountsBySex::from_vec(&buffer))
+                .unwrap_or_default();
             let mut db_carrier_data = transaction.get_cf(&cf_carriers, key.clone()).map_err(|e| {
                         anyhow::anyhow!(
                             "problem acessing carrier data for variant {:?}: {} (non-existing would be fine)",
@@ -256,8 +401,24 @@ async fn import_vcf(
                     })?
                     .unwrap_or_default();
 
+            // The case may contribute to the variant already, e.g., for duplicate records.
+            let key_contribution = contribution_key(&case_uuid, &key);
+            if let Some(buffer) = transaction
+                .get_cf(&cf_meta, &key_contribution)
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "problem acessing contribution for variant {:?}: {}",
+                        &vcf_var,
+                        e
+                    )
+                })?
+            {
+                this_contribution.aggregate(ds::Contribution::from_vec(&buffer));
+            }
+
             // Aggregate the data.
             db_counts_data.aggregate(this_counts_data);
+            db_counts_by_sex_data.aggregate(this_counts_by_sex_data);
             db_carrier_data.aggregate(this_carrier_data);
 
             // Write data for variant back to database.
@@ -270,6 +431,19 @@ async fn import_vcf(
                         e
                     )
                 })?;
+            transaction
+                .put_cf(
+                    &cf_counts_by_sex,
+                    key.clone(),
+                    db_counts_by_sex_data.to_vec(),
+                )
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "problem writing counts by sex data for variant {:?}: {}",
+                        &vcf_var,
+                        e
+                    )
+                })?;
             transaction
                 .put_cf(&cf_carriers, key.clone(), db_carrier_data.to_vec())
                 .map_err(|e| {
@@ -279,6 +453,15 @@ async fn import_vcf(
                         e
                     )
                 })?;
+            transaction
+                .put_cf(&cf_meta, &key_contribution, this_contribution.to_vec())
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "problem writing contribution for variant {:?}: {}",
+                        &vcf_var,
+                        e
+                    )
+                })?;
 
             let res = transaction.commit();
             match res {
@@ -311,6 +494,406 @@ async fn import_vcf(
         }
     }
 
+    let transaction = db.transaction();
+    transaction.put_cf(&cf_meta, &manifest_key, path_input)?;
+    transaction.delete_cf(&cf_meta, &pending_key)?;
+    transaction
+        .commit()
+        .map_err(|e| anyhow::anyhow!("problem writing manifest for {}: {}", path_input, e))?;
+
+    Ok(())
+}
+
+/// Remove the case with `case_uuid` from the database.
+///
+/// The contribution of the case stored with the manifest is subtracted, so the VCF
+/// file is not read again.  This also applies to cases that are still pending from an
+/// interrupted import.  All changes are written in a single transaction.
+fn remove_case(
+    db: &Arc<rocksdb::TransactionDB<rocksdb::MultiThreaded>>,
+    case_uuid: &uuid::Uuid,
+    cf_counts: &str,
+    cf_carriers: &str,
+    cf_counts_by_sex: &str,
+) -> Result<(), anyhow::Error> {
+    let cf_meta = db.cf_handle("meta").expect("checked earlier");
+    let cf_counts = db.cf_handle(cf_counts).expect("checked earlier");
+    let cf_carriers = db.cf_handle(cf_carriers).expect("checked earlier");
+    let cf_counts_by_sex = db.cf_handle(cf_counts_by_sex).expect("checked earlier");
+
+    let manifest_key = manifest_key(case_uuid);
+    let pending_key = pending_key(case_uuid);
+    if db.get_cf(&cf_meta, &manifest_key)?.is_none() && db.get_cf(&cf_meta, &pending_key)?.is_none()
+    {
+        anyhow::bail!("case {} is not in the database", case_uuid);
+    }
+
+    let transaction = db.transaction();
+    let prefix = contribution_key_prefix(case_uuid);
+    let contributions = transaction
+        .prefix_iterator_cf(&cf_meta, &prefix)
+        .take_while(|item| {
+            item.as_ref()
+                .map(|(key, _)| key.starts_with(&prefix))
+                .unwrap_or(true)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (contribution_key, value) in contributions {
+        let key = &contribution_key[prefix.len()..];
+        let contribution = ds::Contribution::from_vec(&value);
+
+        let Some(mut db_counts_data) = transaction
+            .get_cf(&cf_counts, key)?
+            .map(|buffer| ds::Counts::from_vec(&buffer))
+        else {
+            tracing::warn!("variant {:?} not found in database", key);
+            transaction.delete_cf(&cf_meta, &contribution_key)?;
+            continue;
+        };
+        let mut db_carrier_data = transaction
+            .get_cf(&cf_carriers, key)?
+            .map(|buffer| ds::CarrierList::try_from(buffer.as_slice()))
+            .transpose()
+            .map_err(|e| {
+                anyhow::anyhow!("problem decoding carrier data for variant {:?}: {}", key, e)
+            })?
+            .unwrap_or_default();
+        let mut db_counts_by_sex_data = transaction
+            .get_cf(&cf_counts_by_sex, key)?
+            .map(|buffer| ds::CountsBySex::from_vec(&buffer))
+            .unwrap_or_default();
+
+        // The stored contribution can only exceed the counts if the database is
+        // inconsistent, so nothing is removed in this case.
+        db_counts_data
+            .subtract(&contribution.counts)
+            .and_then(|_| db_counts_by_sex_data.subtract(&contribution.counts_by_sex))
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "contribution of case {} to variant {:?} exceeds the counts in the database: {}",
+                    case_uuid,
+                    key,
+                    e
+                )
+            })?;
+        db_carrier_data.remove_case(case_uuid);
+
+        if db_counts_data.is_empty() {
+            transaction.delete_cf(&cf_counts, key)?;
+            transaction.delete_cf(&cf_counts_by_sex, key)?;
+            transaction.delete_cf(&cf_carriers, key)?;
+        } else {
+            transaction.put_cf(&cf_counts, key, db_counts_data.to_vec())?;
+            transaction.put_cf(&cf_counts_by_sex, key, db_counts_by_sex_data.to_vec())?;
+            transaction.put_cf(&cf_carriers, key, db_carrier_data.to_vec())?;
+        }
+        transaction.delete_cf(&cf_meta, &contribution_key)?;
+    }
+    transaction.delete_cf(&cf_meta, &manifest_key)?;
+    transaction.delete_cf(&cf_meta, &pending_key)?;
+
+    transaction
+        .commit()
+        .map_err(|e| anyhow::anyhow!("problem committing removal of case {}: {}", case_uuid, e))
+}
+
+/// Merge the in-house database of another site at `path_rocksdb` into `db`.
+///
+/// Counts are summed up and carrier lists are joined, and the manifest is copied such
+/// that the cases can be removed again.  The other database must be
+/// a `seqvars aggregate` database of the same genome release, and cases that are
+/// in both databases are refused so that no carriers are counted twice.  All changes
+/// are written in a single transaction, so a failed merge leaves `db` unchanged.
+fn merge_rocksdb(
+    db: &Arc<rocksdb::TransactionDB<rocksdb::MultiThreaded>>,
+    path_rocksdb: &str,
+    cf_counts: &str,
+    cf_carriers: &str,
+    cf_counts_by_sex: &str,
+    genomebuild: crate::common::GenomeRelease,
+) -> Result<(), anyhow::Error> {
+    let other = rocksdb::DB::open_cf_for_read_only(
+        &rocksdb::Options::default(),
+        path_rocksdb,
+        ["meta", cf_counts, cf_carriers, cf_counts_by_sex],
+        false,
+    )
+    .map_err(|e| anyhow::anyhow!("could not open {} for merging: {}", path_rocksdb, e))?;
+
+    let other_cf_meta = other.cf_handle("meta").expect("checked when opening");
+    let db_name = other.get_cf(&other_cf_meta, "db-name")?;
+    if db_name.as_deref() != Some(b"seqvars-aggregation".as_slice()) {
+        anyhow::bail!("{} is not a seqvars aggregation database", path_rocksdb);
+    }
+    let expected = genomebuild.name().to_lowercase();
+    let genome_release = other
+        .get_cf(&other_cf_meta, "genome-release")?
+        .unwrap_or_default();
+    if genome_release != expected.as_bytes() {
+        anyhow::bail!(
+            "genome release of {} is {}, expected {}",
+            path_rocksdb,
+            String::from_utf8_lossy(&genome_release),
+            expected
+        );
+    }
+    let schema_version =
+        parse_schema_version(other.get_cf(&other_cf_meta, "schema-version")?.as_deref())?;
+    if schema_version != SCHEMA_VERSION {
+        anyhow::bail!(
+            "schema version of {} is {}, expected {}; run `seqvars aggregate --migrate` on it first",
+            path_rocksdb,
+            schema_version,
+            SCHEMA_VERSION
+        );
+    }
+
+    if let Some(item) = other
+        .prefix_iterator_cf(&other_cf_meta, PENDING_KEY_PREFIX)
+        .next()
+    {
+        let (key, _) = item?;
+        if key.starts_with(PENDING_KEY_PREFIX.as_bytes()) {
+            anyhow::bail!(
+                "case {} of {} is not completely imported",
+                String::from_utf8_lossy(&key[PENDING_KEY_PREFIX.len()..]),
+                path_rocksdb
+            );
+        }
+    }
+
+    let cf_meta = db.cf_handle("meta").expect("checked earlier");
+    let manifest = other
+        .prefix_iterator_cf(&other_cf_meta, MANIFEST_KEY_PREFIX)
+        .take_while(|item| {
+            item.as_ref()
+                .map(|(key, _)| key.starts_with(MANIFEST_KEY_PREFIX.as_bytes()))
+                .unwrap_or(true)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (key, _) in &manifest {
+        let case_uuid = &key[MANIFEST_KEY_PREFIX.len()..];
+        let pending_key = [PENDING_KEY_PREFIX.as_bytes(), case_uuid].concat();
+        if db.get_cf(&cf_meta, key)?.is_some() || db.get_cf(&cf_meta, pending_key)?.is_some() {
+            anyhow::bail!(
+                "case {} of {} is already in the database",
+                String::from_utf8_lossy(case_uuid),
+                path_rocksdb
+            );
+        }
+    }
+
+    let transaction = db.transaction();
+    let cf_counts = (
+        db.cf_handle(cf_counts).expect("checked earlier"),
+        other.cf_handle(cf_counts).expect("checked when opening"),
+    );
+    for item in other.iterator_cf(&cf_counts.1, rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        let mut counts = transaction
+            .get_cf(&cf_counts.0, &key)?
+            .map(|buffer| ds::Counts::from_vec(&buffer))
+            .unwrap_or_default();
+        counts.aggregate(ds::Counts::from_vec(&value));
+        transaction.put_cf(&cf_counts.0, &key, counts.to_vec())?;
+    }
+    let cf_counts_by_sex = (
+        db.cf_handle(cf_counts_by_sex).expect("checked earlier"),
+        other
+            .cf_handle(cf_counts_by_sex)
+            .expect("checked when opening"),
+    );
+    for item in other.iterator_cf(&cf_counts_by_sex.1, rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        let mut counts_by_sex = transaction
+            .get_cf(&cf_counts_by_sex.0, &key)?
+            .map(|buffer| ds::CountsBySex::from_vec(&buffer))
+            .unwrap_or_default();
+        counts_by_sex.aggregate(ds::CountsBySex::from_vec(&value));
+        transaction.put_cf(&cf_counts_by_sex.0, &key, counts_by_sex.to_vec())?;
+    }
+    let cf_carriers = (
+        db.cf_handle(cf_carriers).expect("checked earlier"),
+        other.cf_handle(cf_carriers).expect("checked when opening"),
+    );
+    for item in other.iterator_cf(&cf_carriers.1, rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        let mut carriers = transaction
+            .get_cf(&cf_carriers.0, &key)?
+            .map(|buffer| ds::CarrierList::try_from(buffer.as_slice()))
+            .transpose()?
+            .unwrap_or_default();
+        carriers.aggregate(ds::CarrierList::try_from(&value[..])?);
+        transaction.put_cf(&cf_carriers.0, &key, carriers.to_vec())?;
+    }
+
+    for (key, value) in &manifest {
+        transaction.put_cf(&cf_meta, key, value)?;
+    }
+    for item in other.prefix_iterator_cf(&other_cf_meta, CONTRIBUTION_KEY_PREFIX) {
+        let (key, value) = item?;
+        if !key.starts_with(CONTRIBUTION_KEY_PREFIX.as_bytes()) {
+            break;
+        }
+        transaction.put_cf(&cf_meta, key, value)?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|e| anyhow::anyhow!("problem committing merge of {}: {}", path_rocksdb, e))
+}
+
+/// Return the case UUID from the header of the VCF file at `path`.
+async fn read_case_uuid(path: &str) -> Result<uuid::Uuid, anyhow::Error> {
+    let mut input_reader = common::noodles::open_vcf_reader(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("could not open file {} for reading: {}", path, e))?;
+    let input_header = input_reader.read_header().await?;
+    let (_, case_uuid) = common::extract_pedigree_and_case_uuid(&input_header)?;
+    Ok(case_uuid)
+}
+
+/// Migrate the database from schema version 1 by computing the counts by sex.
+///
+/// The counts by sex and the contributions are computed from the VCF files of all
+/// cases with carriers in the database or in the manifest, taken from `path_input`
+/// or else from the manifest.  If the file of any case is missing, nothing is written
+/// and an error is returned, such that the schema version is only updated once the
+/// counts by sex are complete.  The cases are added to the manifest, so the files in
+/// `path_input` are not imported again afterwards.
+async fn migrate_v1(
+    db: &Arc<rocksdb::TransactionDB<rocksdb::MultiThreaded>>,
+    path_input: &[String],
+    cf_carriers: &str,
+    cf_counts_by_sex: &str,
+    genomebuild: crate::common::GenomeRelease,
+) -> Result<(), anyhow::Error> {
+    let cf_meta = db.cf_handle("meta").expect("checked earlier");
+    let cf_carriers = db.cf_handle(cf_carriers).expect("checked earlier");
+    let cf_counts_by_sex = db.cf_handle(cf_counts_by_sex).expect("checked earlier");
+
+    // Collect the cases from the carriers and the manifest.
+    let mut case_uuids = std::collections::BTreeSet::new();
+    for item in db.iterator_cf(&cf_carriers, rocksdb::IteratorMode::Start) {
+        let (_, value) = item?;
+        let carriers = ds::CarrierList::try_from(value.as_ref())
+            .map_err(|e| anyhow::anyhow!("problem decoding carrier data: {}", e))?;
+        case_uuids.extend(carriers.carriers.iter().map(|carrier| carrier.uuid));
+    }
+    let mut manifest = Vec::new();
+    for item in db.prefix_iterator_cf(&cf_meta, MANIFEST_KEY_PREFIX) {
+        let (key, value) = item?;
+        if !key.starts_with(MANIFEST_KEY_PREFIX.as_bytes()) {
+            break;
+        }
+        let case_uuid =
+            uuid::Uuid::parse_str(&String::from_utf8_lossy(&key[MANIFEST_KEY_PREFIX.len()..]))?;
+        case_uuids.insert(case_uuid);
+        manifest.push((case_uuid, String::from_utf8_lossy(&value).to_string()));
+    }
+
+    // Find the VCF file of each case, the files given on the command line first.
+    let mut paths = std::collections::BTreeMap::new();
+    for path in path_input {
+        let case_uuid = read_case_uuid(path).await?;
+        if case_uuids.contains(&case_uuid) {
+            paths.insert(case_uuid, path.clone());
+        }
+    }
+    for (case_uuid, path) in manifest {
+        if paths.contains_key(&case_uuid) {
+            continue;
+        }
+        match read_case_uuid(&path).await {
+            Ok(vcf_case_uuid) if vcf_case_uuid == case_uuid => {
+                paths.insert(case_uuid, path);
+            }
+            Ok(vcf_case_uuid) => tracing::warn!(
+                "file {} in the manifest is for case {}, not {}",
+                &path,
+                &vcf_case_uuid,
+                &case_uuid
+            ),
+            Err(e) => tracing::warn!("file {} in the manifest cannot be read: {}", &path, e),
+        }
+    }
+    let missing = case_uuids
+        .iter()
+        .filter(|case_uuid| !paths.contains_key(*case_uuid))
+        .map(|case_uuid| case_uuid.to_string())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "cannot compute the counts by sex without the VCF files of cases {}, pass them \
+            with --path-input",
+            missing.join(", ")
+        );
+    }
+
+    // Clear the counts by sex and contributions of a previous, incomplete, migration.
+    let stale_keys = db
+        .iterator_cf(&cf_counts_by_sex, rocksdb::IteratorMode::Start)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<Result<Vec<_>, _>>()?;
+    for key in stale_keys {
+        db.delete_cf(&cf_counts_by_sex, key)?;
+    }
+    let stale_keys = db
+        .prefix_iterator_cf(&cf_meta, CONTRIBUTION_KEY_PREFIX)
+        .map(|item| item.map(|(key, _)| key))
+        .take_while(|item| {
+            item.as_ref()
+                .map(|key| key.starts_with(CONTRIBUTION_KEY_PREFIX.as_bytes()))
+                .unwrap_or(true)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for key in stale_keys {
+        db.delete_cf(&cf_meta, key)?;
+    }
+
+    for (case_uuid, path_input) in &paths {
+        let mut input_reader = common::noodles::open_vcf_reader(path_input)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("could not open file {} for reading: {}", path_input, e)
+            })?;
+        let input_header = input_reader.read_header().await?;
+        let (pedigree, _) = common::extract_pedigree_and_case_uuid(&input_header)?;
+
+        let mut records = input_reader.records(&input_header).await;
+        while let Some(record_buf) = records.try_next().await? {
+            let (this_counts_data, this_counts_by_sex_data, _) = handle_record(
+                &record_buf,
+                &input_header,
+                &pedigree,
+                case_uuid,
+                genomebuild,
+            )?;
+            let vcf_var = annonars::common::keys::Var::from_vcf_allele(&record_buf, 0);
+            let key: Vec<u8> = vcf_var.into();
+
+            let mut db_counts_by_sex_data = db
+                .get_cf(&cf_counts_by_sex, &key)?
+                .map(|buffer| ds::CountsBySex::from_vec(&buffer))
+                .unwrap_or_default();
+            db_counts_by_sex_data.aggregate(this_counts_by_sex_data.clone());
+            db.put_cf(&cf_counts_by_sex, &key, db_counts_by_sex_data.to_vec())?;
+
+            let key_contribution = contribution_key(case_uuid, &key);
+            let mut contribution = db
+                .get_cf(&cf_meta, &key_contribution)?
+                .map(|buffer| ds::Contribution::from_vec(&buffer))
+                .unwrap_or_default();
+            contribution.aggregate(ds::Contribution {
+                counts: this_counts_data,
+                counts_by_sex: this_counts_by_sex_data,
+            });
+            db.put_cf(&cf_meta, &key_contribution, contribution.to_vec())?;
+        }
+        db.put_cf(&cf_meta, manifest_key(case_uuid), path_input)?;
+        tracing::info!("  computed counts by sex for case {}", case_uuid);
+    }
+
     Ok(())
 }
 
@@ -320,17 +903,21 @@ async fn vcf_import(
     path_input: &[&str],
     cf_counts: &str,
     cf_carriers: &str,
+    cf_counts_by_sex: &str,
     genomebuild: crate::common::GenomeRelease,
 ) -> Result<(), anyhow::Error> {
     let handle = tokio::runtime::Handle::current();
+    let claimed = std::sync::Mutex::new(std::collections::HashSet::new());
     path_input.par_iter().try_for_each(|path_input| {
         tokio::task::block_in_place(|| {
             handle
                 .block_on(import_vcf(
                     db,
+                    &claimed,
                     path_input,
                     cf_counts,
                     cf_carriers,
+                    cf_counts_by_sex,
                     genomebuild,
                 ))
                 .map_err(|e| anyhow::anyhow!("processing VCF file {} failed: {}", path_input, e))
@@ -373,12 +960,20 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         .collect::<Vec<_>>();
 
     tracing::info!("Opening RocksDB...");
-    let options = rocksdb_utils_lookup::tune_options(
-        rocksdb::Options::default(),
-        args.path_wal_dir.as_ref().map(|s| s.as_ref()),
+    let options = args.rocksdb.apply(
+        rocksdb_utils_lookup::tune_options(
+            rocksdb::Options::default(),
+            args.path_wal_dir.as_ref().map(|s| s.as_ref()),
+        ),
+        common::rocksdb_tuning::Profile::Build,
     );
     let tx_options = rocksdb::TransactionDBOptions::default();
-    let cf_names = &["meta", &args.cf_counts, &args.cf_carriers];
+    let cf_names = &[
+        "meta",
+        &args.cf_counts,
+        &args.cf_carriers,
+        &args.cf_counts_by_sex,
+    ];
     let cf_descriptors = cf_names
         .iter()
         .map(|name| rocksdb::ColumnFamilyDescriptor::new(*name, options.clone()))
@@ -395,7 +990,50 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             )?);
         tracing::info!("  writing meta information");
         let cf_meta = db.cf_handle("meta").unwrap();
+        if let Some(genome_release) = db.get_cf(&cf_meta, "genome-release")? {
+            let expected = args.genomebuild.name().to_lowercase();
+            if genome_release != expected.as_bytes() {
+                anyhow::bail!(
+                    "genome release of existing database is {}, expected {}",
+                    String::from_utf8_lossy(&genome_release),
+                    expected
+                );
+            }
+        }
+        if db.get_cf(&cf_meta, "db-name")?.is_some() {
+            let schema_version =
+                parse_schema_version(db.get_cf(&cf_meta, "schema-version")?.as_deref())?;
+            if schema_version > SCHEMA_VERSION {
+                anyhow::bail!(
+                    "schema version of existing database is {}, this worker supports up to {}",
+                    schema_version,
+                    SCHEMA_VERSION
+                );
+            } else if schema_version < SCHEMA_VERSION {
+                if !args.migrate {
+                    anyhow::bail!(
+                        "schema version of existing database is {}, run with --migrate to upgrade to {}",
+                        schema_version,
+                        SCHEMA_VERSION
+                    );
+                }
+                tracing::info!(
+                    "  migrating from schema version {} to {}",
+                    schema_version,
+                    SCHEMA_VERSION
+                );
+                migrate_v1(
+                    &db,
+                    &path_input,
+                    &args.cf_carriers,
+                    &args.cf_counts_by_sex,
+                    args.genomebuild,
+                )
+                .await?;
+            }
+        }
         db.put_cf(&cf_meta, "varfish-worker-version", common::worker_version())?;
+        db.put_cf(&cf_meta, "schema-version", SCHEMA_VERSION.to_string())?;
         db.put_cf(
             &cf_meta,
             "genome-release",
@@ -404,6 +1042,39 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         db.put_cf(&cf_meta, "db-name", "seqvars-aggregation")?;
         tracing::info!("... done opening RocksDB");
 
+        if !args.remove_case.is_empty() {
+            tracing::info!("Removing cases ...");
+            for case_uuid in &args.remove_case {
+                let case_uuid = uuid::Uuid::parse_str(case_uuid)
+                    .map_err(|e| anyhow::anyhow!("invalid case UUID {}: {}", case_uuid, e))?;
+                remove_case(
+                    &db,
+                    &case_uuid,
+                    &args.cf_counts,
+                    &args.cf_carriers,
+                    &args.cf_counts_by_sex,
+                )?;
+                tracing::info!("  removed case {}", &case_uuid);
+            }
+            tracing::info!("... done removing cases");
+        }
+
+        if !args.path_merge_rocksdb.is_empty() {
+            tracing::info!("Merging databases ...");
+            for path_rocksdb in &args.path_merge_rocksdb {
+                merge_rocksdb(
+                    &db,
+                    path_rocksdb,
+                    &args.cf_counts,
+                    &args.cf_carriers,
+                    &args.cf_counts_by_sex,
+                    args.genomebuild,
+                )?;
+                tracing::info!("  merged {}", path_rocksdb);
+            }
+            tracing::info!("... done merging databases");
+        }
+
         tracing::info!("Importing VCF files ...");
         let before_import = std::time::Instant::now();
         let paths = path_input.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
@@ -412,6 +1083,7 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             &paths,
             &args.cf_counts,
             &args.cf_carriers,
+            &args.cf_counts_by_sex,
             args.genomebuild,
         )
         .await?;
@@ -525,6 +1197,439 @@ mod test {
         ));
     }
 
+    /// Read all values of the column family `cf_name` from the RocksDB at `path`.
+    fn read_cf_values(path: &std::path::Path, cf_name: &str) -> Vec<Vec<u8>> {
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            path,
+            ["meta", "counts", "carriers", "counts_by_sex"],
+            false,
+        )
+        .unwrap();
+        let cf = db.cf_handle(cf_name).unwrap();
+        db.iterator_cf(&cf, rocksdb::IteratorMode::Start)
+            .map(|item| item.unwrap().1.to_vec())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_incremental() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_out_rocksdb = tmpdir.join("rocksdb");
+        let path_input = tmpdir.join("ingest.vcf").to_string_lossy().to_string();
+        std::fs::copy("tests/seqvars/aggregate/ingest.vcf", &path_input)?;
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec![path_input.clone()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+
+        super::run(&args_common, &args).await?;
+        let counts = read_cf_values(&path_out_rocksdb, "counts");
+        let meta = read_cf_values(&path_out_rocksdb, "meta");
+        assert_eq!(
+            read_cf_values(&path_out_rocksdb, "counts_by_sex").len(),
+            counts.len()
+        );
+
+        // Importing the same case again does not change the counts.
+        super::run(&args_common, &args).await?;
+        assert_eq!(read_cf_values(&path_out_rocksdb, "counts"), counts);
+        assert_eq!(read_cf_values(&path_out_rocksdb, "meta"), meta);
+        assert!(meta.contains(&path_input.as_bytes().to_vec()));
+
+        // Removing the case leaves an empty database, without reading the VCF file again.
+        let (_, case_uuid) = crate::common::extract_pedigree_and_case_uuid(
+            &vcf::io::reader::Builder::default()
+                .build_from_path(&path_input)?
+                .read_header()?,
+        )?;
+        std::fs::remove_file(&path_input)?;
+        let args = super::Args {
+            path_input: vec![],
+            remove_case: vec![case_uuid.to_string()],
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        assert!(read_cf_values(&path_out_rocksdb, "counts").is_empty());
+        assert!(read_cf_values(&path_out_rocksdb, "carriers").is_empty());
+        assert!(read_cf_values(&path_out_rocksdb, "counts_by_sex").is_empty());
+        // Only the database information is left in `meta`, without manifest and contributions.
+        assert_eq!(read_cf_values(&path_out_rocksdb, "meta").len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_resume_pending() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_out_rocksdb = tmpdir.join("rocksdb");
+        let path_input = "tests/seqvars/aggregate/ingest.vcf";
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec![path_input.into()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+        super::run(&args_common, &args).await?;
+        let counts = read_cf_values(&path_out_rocksdb, "counts");
+        let counts_by_sex = read_cf_values(&path_out_rocksdb, "counts_by_sex");
+        let meta = read_cf_values(&path_out_rocksdb, "meta");
+
+        // Simulate an import that was interrupted after writing all records but before
+        // writing the manifest.
+        let (_, case_uuid) = crate::common::extract_pedigree_and_case_uuid(
+            &vcf::io::reader::Builder::default()
+                .build_from_path(path_input)?
+                .read_header()?,
+        )?;
+        {
+            let db = rocksdb::DB::open_cf(
+                &rocksdb::Options::default(),
+                &path_out_rocksdb,
+                ["meta", "counts", "carriers", "counts_by_sex"],
+            )?;
+            let cf_meta = db.cf_handle("meta").unwrap();
+            db.delete_cf(&cf_meta, super::manifest_key(&case_uuid))?;
+            db.put_cf(&cf_meta, super::pending_key(&case_uuid), path_input)?;
+        }
+
+        // Importing the pending case again does not count it twice.
+        super::run(&args_common, &args).await?;
+        assert_eq!(read_cf_values(&path_out_rocksdb, "counts"), counts);
+        assert_eq!(
+            read_cf_values(&path_out_rocksdb, "counts_by_sex"),
+            counts_by_sex
+        );
+        assert_eq!(read_cf_values(&path_out_rocksdb, "meta"), meta);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_remove_case_exceeding_counts() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_out_rocksdb = tmpdir.join("rocksdb");
+        let path_input = "tests/seqvars/aggregate/ingest.vcf";
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec![path_input.into()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+        super::run(&args_common, &args).await?;
+
+        // Clear the counts of one variant such that the stored contribution exceeds them.
+        {
+            let db = rocksdb::DB::open_cf(
+                &rocksdb::Options::default(),
+                &path_out_rocksdb,
+                ["meta", "counts", "carriers", "counts_by_sex"],
+            )?;
+            let cf_counts = db.cf_handle("counts").unwrap();
+            let (key, _) = db
+                .iterator_cf(&cf_counts, rocksdb::IteratorMode::Start)
+                .next()
+                .unwrap()?;
+            let counts = super::ds::Counts {
+                count_homref: 1,
+                ..Default::default()
+            };
+            db.put_cf(&cf_counts, key, counts.to_vec())?;
+        }
+        let counts = read_cf_values(&path_out_rocksdb, "counts");
+        let meta = read_cf_values(&path_out_rocksdb, "meta");
+
+        // The removal fails and leaves the database unchanged.
+        let (_, case_uuid) = crate::common::extract_pedigree_and_case_uuid(
+            &vcf::io::reader::Builder::default()
+                .build_from_path(path_input)?
+                .read_header()?,
+        )?;
+        let args = super::Args {
+            path_input: vec![],
+            remove_case: vec![case_uuid.to_string()],
+            ..args
+        };
+        let err = super::run(&args_common, &args).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds the counts"));
+        assert_eq!(read_cf_values(&path_out_rocksdb, "counts"), counts);
+        assert_eq!(read_cf_values(&path_out_rocksdb, "meta"), meta);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_merge() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_site_rocksdb = tmpdir.join("site");
+        let path_out_rocksdb = tmpdir.join("merged");
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_site_rocksdb.to_string_lossy().to_string(),
+            path_input: vec!["tests/seqvars/aggregate/ingest.vcf".into()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+        super::run(&args_common, &args).await?;
+
+        // Merging into an empty database yields the same counts and carriers.
+        let args = super::Args {
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec![],
+            path_merge_rocksdb: vec![path_site_rocksdb.to_string_lossy().to_string()],
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        for cf_name in ["counts", "carriers", "counts_by_sex"] {
+            assert_eq!(
+                read_cf_values(&path_out_rocksdb, cf_name),
+                read_cf_values(&path_site_rocksdb, cf_name)
+            );
+        }
+
+        // Merging the same cases again is refused.
+        assert!(super::run(&args_common, &args).await.is_err());
+
+        // The merged cases can be removed again.
+        let (_, case_uuid) = crate::common::extract_pedigree_and_case_uuid(
+            &vcf::io::reader::Builder::default()
+                .build_from_path("tests/seqvars/aggregate/ingest.vcf")?
+                .read_header()?,
+        )?;
+        let args = super::Args {
+            path_merge_rocksdb: vec![],
+            remove_case: vec![case_uuid.to_string()],
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        assert!(read_cf_values(&path_out_rocksdb, "counts").is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_merge_failed() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_site_rocksdb = tmpdir.join("site");
+        let path_out_rocksdb = tmpdir.join("merged");
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_site_rocksdb.to_string_lossy().to_string(),
+            path_input: vec!["tests/seqvars/aggregate/ingest.vcf".into()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+        super::run(&args_common, &args).await?;
+
+        // Corrupt the genotype of a carrier, which is read after the counts are merged.
+        {
+            let db = rocksdb::DB::open_cf(
+                &rocksdb::Options::default(),
+                &path_site_rocksdb,
+                ["meta", "counts", "carriers", "counts_by_sex"],
+            )?;
+            let cf_carriers = db.cf_handle("carriers").unwrap();
+            let (key, value) = db
+                .iterator_cf(&cf_carriers, rocksdb::IteratorMode::Start)
+                .next()
+                .unwrap()?;
+            let mut value = value.to_vec();
+            *value.last_mut().unwrap() = 0xff;
+            db.put_cf(&cf_carriers, key, value)?;
+        }
+
+        // The failed merge does not leave partially merged counts or manifest entries.
+        let args = super::Args {
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec![],
+            path_merge_rocksdb: vec![path_site_rocksdb.to_string_lossy().to_string()],
+            ..args
+        };
+        assert!(super::run(&args_common, &args).await.is_err());
+        for cf_name in ["counts", "carriers", "counts_by_sex"] {
+            assert!(read_cf_values(&path_out_rocksdb, cf_name).is_empty());
+        }
+        assert_eq!(read_cf_values(&path_out_rocksdb, "meta").len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_migrate() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_out_rocksdb = tmpdir.join("rocksdb");
+        let args = super::Args {
+            genomebuild: crate::common::GenomeRelease::Grch37,
+            path_out_rocksdb: path_out_rocksdb.to_string_lossy().to_string(),
+            path_input: vec!["tests/seqvars/aggregate/ingest.vcf".into()],
+            cf_counts: "counts".into(),
+            cf_carriers: "carriers".into(),
+            cf_counts_by_sex: "counts_by_sex".into(),
+            num_threads: None,
+            path_wal_dir: None,
+            rocksdb: Default::default(),
+            remove_case: vec![],
+            path_merge_rocksdb: vec![],
+            migrate: false,
+        };
+        let args_common = crate::common::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(0, 0),
+        };
+        super::run(&args_common, &args).await?;
+        let counts = read_cf_values(&path_out_rocksdb, "counts");
+        let counts_by_sex = read_cf_values(&path_out_rocksdb, "counts_by_sex");
+
+        // Turn the database into one of schema version 1, i.e., without schema version,
+        // manifest, contributions, and counts by sex.
+        {
+            let db = rocksdb::DB::open_cf(
+                &rocksdb::Options::default(),
+                &path_out_rocksdb,
+                ["meta", "counts", "carriers", "counts_by_sex"],
+            )?;
+            let cf_meta = db.cf_handle("meta").unwrap();
+            let keys = db
+                .iterator_cf(&cf_meta, rocksdb::IteratorMode::Start)
+                .map(|item| item.map(|(key, _)| key))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in keys {
+                if key.starts_with(b"case:")
+                    || key.starts_with(b"contribution:")
+                    || key.as_ref() == b"schema-version"
+                {
+                    db.delete_cf(&cf_meta, key)?;
+                }
+            }
+            let cf_counts_by_sex = db.cf_handle("counts_by_sex").unwrap();
+            let keys = db
+                .iterator_cf(&cf_counts_by_sex, rocksdb::IteratorMode::Start)
+                .map(|item| item.map(|(key, _)| key))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in keys {
+                db.delete_cf(&cf_counts_by_sex, key)?;
+            }
+        }
+        let read_schema_version = || {
+            let db = rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                &path_out_rocksdb,
+                ["meta", "counts", "carriers", "counts_by_sex"],
+                false,
+            )
+            .unwrap();
+            let cf_meta = db.cf_handle("meta").unwrap();
+            db.get_cf(&cf_meta, "schema-version").unwrap()
+        };
+
+        // Opening the old database is refused without `--migrate`.
+        let args = super::Args {
+            path_input: vec![],
+            ..args
+        };
+        assert!(super::run(&args_common, &args).await.is_err());
+
+        // Without the VCF file of the case, the migration fails and the version is kept.
+        let args = super::Args {
+            migrate: true,
+            ..args
+        };
+        assert!(super::run(&args_common, &args).await.is_err());
+        assert_eq!(read_schema_version(), None);
+        assert!(read_cf_values(&path_out_rocksdb, "counts_by_sex").is_empty());
+
+        // With the VCF file, the counts by sex are rebuilt and the case is not counted twice.
+        let args = super::Args {
+            path_input: vec!["tests/seqvars/aggregate/ingest.vcf".into()],
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        assert_eq!(read_schema_version(), Some(b"2".to_vec()));
+        assert_eq!(read_cf_values(&path_out_rocksdb, "counts"), counts);
+        assert_eq!(
+            read_cf_values(&path_out_rocksdb, "counts_by_sex"),
+            counts_by_sex
+        );
+
+        // The migrated case can be removed again.
+        let (_, case_uuid) = crate::common::extract_pedigree_and_case_uuid(
+            &vcf::io::reader::Builder::default()
+                .build_from_path("tests/seqvars/aggregate/ingest.vcf")?
+                .read_header()?,
+        )?;
+        let args = super::Args {
+            path_input: vec![],
+            migrate: false,
+            remove_case: vec![case_uuid.to_string()],
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        assert!(read_cf_values(&path_out_rocksdb, "counts").is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::missing(None, 1)]
+    #[case::present(Some(b"2".as_slice()), 2)]
+    fn parse_schema_version(#[case] value: Option<&[u8]>, #[case] expected: u32) {
+        assert_eq!(super::parse_schema_version(value).unwrap(), expected);
+    }
+
     #[tracing_test::traced_test]
     #[test]
     fn handle_record_snapshot() -> Result<(), anyhow::Error> {
@@ -544,7 +1649,7 @@ mod test {
             }
 
             let (pedigree, case_uuid) = common::extract_pedigree_and_case_uuid(&header)?;
-            let (counts, carriers) = super::handle_record(
+            let (counts, _, carriers) = super::handle_record(
                 &record_buf,
                 &header,
                 &pedigree,
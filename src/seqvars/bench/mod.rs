@@ -0,0 +1,359 @@
+//! Implementation of `seqvars bench` subcommand.
+//!
+//! Runs a standard set of workloads against a RocksDB database such as the
+//! in-house database written by `seqvars aggregate` and reports latency and
+//! throughput, e.g., to compare the `--rocksdb-*` settings and hardware before
+//! deployment.
+
+use std::time::{Duration, Instant};
+
+use rand::{seq::SliceRandom as _, Rng as _, SeedableRng as _};
+
+use crate::common::rocksdb_tuning::{Profile, RocksdbArgs};
+
+/// Command line arguments for `seqvars bench` subcommand.
+#[derive(Debug, clap::Parser)]
+#[command(author, version, about = "benchmark RocksDB lookups", long_about = None)]
+pub struct Args {
+    /// Path to the RocksDB to benchmark.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Column family name of the data to benchmark.
+    #[arg(long, default_value = "counts")]
+    pub cf_name: String,
+    /// Number of keys to sample for the lookup workloads.
+    #[arg(long, default_value_t = 10_000)]
+    pub num_samples: usize,
+    /// Number of records to read per range scan.
+    #[arg(long, default_value_t = 100)]
+    pub scan_length: usize,
+    /// Optional seed for RNG.
+    #[arg(long)]
+    pub rng_seed: Option<u64>,
+    /// Tuning of the RocksDB.
+    #[clap(flatten)]
+    pub rocksdb: RocksdbArgs,
+}
+
+/// Workload to run against the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Workload {
+    /// Read all records of the column family, also used for sampling the keys.
+    FullScan,
+    /// Look up the sampled keys in random order.
+    PointLookup,
+    /// Read `--scan-length` records starting at each sampled key.
+    RangeScan,
+    /// Look up the sampled keys in key order, as `seqvars query` does for a
+    /// position-sorted VCF file.
+    QuerySimulation,
+}
+
+/// Latency statistics in microseconds.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct Latency {
+    /// Mean latency.
+    pub mean: f64,
+    /// Median latency.
+    pub p50: f64,
+    /// 90th percentile of the latency.
+    pub p90: f64,
+    /// 99th percentile of the latency.
+    pub p99: f64,
+    /// Maximal latency.
+    pub max: f64,
+}
+
+impl Latency {
+    /// Compute the statistics of `durations`, all zero if empty.
+    pub fn from_durations(durations: &mut [Duration]) -> Self {
+        if durations.is_empty() {
+            return Default::default();
+        }
+        durations.sort();
+        let micros = |duration: Duration| duration.as_nanos() as f64 / 1e3;
+        let quantile = |q: f64| {
+            let idx = ((durations.len() - 1) as f64 * q).round() as usize;
+            micros(durations[idx])
+        };
+        Self {
+            mean: micros(durations.iter().sum::<Duration>()) / durations.len() as f64,
+            p50: quantile(0.5),
+            p90: quantile(0.9),
+            p99: quantile(0.99),
+            max: micros(durations[durations.len() - 1]),
+        }
+    }
+}
+
+/// Result of running one workload.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WorkloadReport {
+    /// The workload.
+    pub workload: Workload,
+    /// Number of operations, i.e., lookups or scans.
+    pub operations: usize,
+    /// Number of records read.
+    pub records: usize,
+    /// Wall clock time in seconds.
+    pub elapsed_secs: f64,
+    /// Operations per second.
+    pub ops_per_sec: f64,
+    /// Latency of the operations.
+    pub latency_us: Latency,
+}
+
+impl WorkloadReport {
+    /// Construct from the `elapsed` wall clock time and the `durations` of the operations.
+    fn new(
+        workload: Workload,
+        records: usize,
+        elapsed: Duration,
+        durations: &mut [Duration],
+    ) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        Self {
+            workload,
+            operations: durations.len(),
+            records,
+            elapsed_secs,
+            ops_per_sec: if elapsed_secs > 0.0 {
+                durations.len() as f64 / elapsed_secs
+            } else {
+                0.0
+            },
+            latency_us: Latency::from_durations(durations),
+        }
+    }
+}
+
+/// Read all records of `cf` and sample up to `num_samples` keys with reservoir sampling.
+fn full_scan(
+    db: &rocksdb::DB,
+    cf: &rocksdb::ColumnFamily,
+    num_samples: usize,
+    rng: &mut rand::rngs::StdRng,
+) -> Result<(WorkloadReport, Vec<Vec<u8>>), anyhow::Error> {
+    let mut samples = Vec::with_capacity(num_samples);
+    let mut durations = Vec::new();
+    let mut count = 0;
+
+    let before_scan = Instant::now();
+    let mut iter = db.raw_iterator_cf(cf);
+    iter.seek_to_first();
+    let mut before_next = Instant::now();
+    while let Some(key) = iter.key() {
+        durations.push(before_next.elapsed());
+        if samples.len() < num_samples {
+            samples.push(key.to_vec());
+        } else {
+            let idx = rng.gen_range(0..=count);
+            if idx < num_samples {
+                samples[idx] = key.to_vec();
+            }
+        }
+        count += 1;
+        before_next = Instant::now();
+        iter.next();
+    }
+    iter.status()?;
+
+    let report = WorkloadReport::new(
+        Workload::FullScan,
+        count,
+        before_scan.elapsed(),
+        &mut durations,
+    );
+    Ok((report, samples))
+}
+
+/// Look up each of `keys` in the given order.
+fn lookups(
+    db: &rocksdb::DB,
+    cf: &rocksdb::ColumnFamily,
+    workload: Workload,
+    keys: &[Vec<u8>],
+) -> Result<WorkloadReport, anyhow::Error> {
+    let mut durations = Vec::with_capacity(keys.len());
+    let mut count = 0;
+
+    let before_lookups = Instant::now();
+    for key in keys {
+        let before_get = Instant::now();
+        if db.get_pinned_cf(cf, key)?.is_some() {
+            count += 1;
+        }
+        durations.push(before_get.elapsed());
+    }
+
+    Ok(WorkloadReport::new(
+        workload,
+        count,
+        before_lookups.elapsed(),
+        &mut durations,
+    ))
+}
+
+/// Read up to `scan_length` records starting at each of `keys`.
+fn range_scans(
+    db: &rocksdb::DB,
+    cf: &rocksdb::ColumnFamily,
+    keys: &[Vec<u8>],
+    scan_length: usize,
+) -> Result<WorkloadReport, anyhow::Error> {
+    let mut durations = Vec::with_capacity(keys.len());
+    let mut count = 0;
+
+    let before_scans = Instant::now();
+    for key in keys {
+        let before_scan = Instant::now();
+        let mut iter = db.raw_iterator_cf(cf);
+        iter.seek(key);
+        let mut remaining = scan_length;
+        while remaining > 0 && iter.valid() {
+            count += 1;
+            remaining -= 1;
+            iter.next();
+        }
+        iter.status()?;
+        durations.push(before_scan.elapsed());
+    }
+
+    Ok(WorkloadReport::new(
+        Workload::RangeScan,
+        count,
+        before_scans.elapsed(),
+        &mut durations,
+    ))
+}
+
+/// Run all workloads against the database and return the reports.
+pub fn run_workloads(args: &Args) -> Result<Vec<WorkloadReport>, anyhow::Error> {
+    let mut rng = if let Some(rng_seed) = args.rng_seed {
+        rand::rngs::StdRng::seed_from_u64(rng_seed)
+    } else {
+        rand::rngs::StdRng::from_entropy()
+    };
+
+    tracing::info!("Opening RocksDB database ...");
+    let before_open = Instant::now();
+    let options = args
+        .rocksdb
+        .apply(rocksdb::Options::default(), Profile::Query);
+    let db = rocksdb::DB::open_cf_for_read_only(
+        &options,
+        annonars::common::readlink_f(&args.path_rocksdb)?,
+        [args.cf_name.as_str()],
+        false,
+    )?;
+    let cf = db
+        .cf_handle(&args.cf_name)
+        .ok_or_else(|| anyhow::anyhow!("missing column family {}", &args.cf_name))?;
+    tracing::info!(
+        "... opening RocksDB database took {:?}",
+        before_open.elapsed()
+    );
+
+    let mut reports = Vec::new();
+
+    tracing::info!("Running {} ...", Workload::FullScan);
+    let (report, mut keys) = full_scan(&db, cf, args.num_samples, &mut rng)?;
+    reports.push(report);
+    if keys.is_empty() {
+        tracing::warn!("column family {} is empty", &args.cf_name);
+    }
+
+    tracing::info!("Running {} ...", Workload::PointLookup);
+    keys.shuffle(&mut rng);
+    reports.push(lookups(&db, cf, Workload::PointLookup, &keys)?);
+
+    tracing::info!("Running {} ...", Workload::RangeScan);
+    reports.push(range_scans(&db, cf, &keys, args.scan_length)?);
+
+    tracing::info!("Running {} ...", Workload::QuerySimulation);
+    keys.sort();
+    reports.push(lookups(&db, cf, Workload::QuerySimulation, &keys)?);
+
+    Ok(reports)
+}
+
+/// Main entry point for `seqvars bench` sub command.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting `seqvars bench`");
+    tracing::info!("  args_common = {:?}", &args_common);
+    tracing::info!("  args = {:?}", &args);
+
+    for report in run_workloads(args)? {
+        println!("{}", serde_json::to_string(&report)?);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Args, Latency, Workload};
+
+    #[test]
+    fn latency_from_durations() {
+        let mut durations = (1..=100)
+            .rev()
+            .map(Duration::from_micros)
+            .collect::<Vec<_>>();
+
+        let latency = Latency::from_durations(&mut durations);
+
+        assert_eq!(latency.mean, 50.5);
+        assert_eq!(latency.p50, 51.0);
+        assert_eq!(latency.p99, 99.0);
+        assert_eq!(latency.max, 100.0);
+        assert_eq!(Latency::from_durations(&mut []), Latency::default());
+    }
+
+    #[test]
+    fn run_workloads() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_rocksdb = tmpdir.join("rocksdb");
+        {
+            let mut options = rocksdb::Options::default();
+            options.create_if_missing(true);
+            options.create_missing_column_families(true);
+            let db = rocksdb::DB::open_cf(&options, &path_rocksdb, ["counts"])?;
+            let cf = db.cf_handle("counts").unwrap();
+            for i in 0u32..50 {
+                db.put_cf(cf, i.to_be_bytes(), b"value")?;
+            }
+        }
+
+        let args = Args {
+            path_rocksdb: path_rocksdb.to_str().unwrap().into(),
+            cf_name: "counts".into(),
+            num_samples: 10,
+            scan_length: 5,
+            rng_seed: Some(42),
+            rocksdb: Default::default(),
+        };
+        let reports = super::run_workloads(&args)?;
+
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| (report.workload, report.operations))
+                .collect::<Vec<_>>(),
+            vec![
+                (Workload::FullScan, 50),
+                (Workload::PointLookup, 10),
+                (Workload::RangeScan, 10),
+                (Workload::QuerySimulation, 10),
+            ]
+        );
+        assert_eq!(reports[0].records, 50);
+        assert_eq!(reports[1].records, 10);
+
+        Ok(())
+    }
+}
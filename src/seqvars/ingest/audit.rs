@@ -0,0 +1,142 @@
+//! Audit log of the records that are dropped or rewritten during ingest.
+
+use super::liftover::RejectReason;
+
+/// What happened to the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Action {
+    /// The record or allele was not written to the output.
+    Dropped,
+    /// The record or allele was written with changes.
+    Rewritten,
+}
+
+/// Reason code for dropping or rewriting a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, strum::Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Reason {
+    /// Record on a contig not in the canonical set of the genome release.
+    UnknownContig,
+    /// Record not in one aligned block of the liftover chain.
+    LiftoverUnmapped,
+    /// Record mapping to more than one target position in the liftover.
+    LiftoverAmbiguous,
+    /// Record with reference allele differing from the target reference in the liftover.
+    LiftoverRefMismatch,
+    /// Symbolic "any other" allele, e.g., `<NON_REF>`.
+    SymbolicAllele,
+    /// Spanning deletion allele `*`.
+    SpanningDeletion,
+    /// No genotype carries the allele after applying the quality filters.
+    FailedGenotypes,
+    /// `FORMAT` fields synthesized for a record of an unknown caller.
+    SynthesizedFormat,
+    /// Allele trimmed or left-aligned against the reference.
+    Normalized,
+    /// Genotypes set to no-call by the quality filters.
+    NoCallGenotypes,
+    /// Haploid genotypes in the PAR written as diploid.
+    DiploidizedPar,
+}
+
+impl From<RejectReason> for Reason {
+    fn from(reason: RejectReason) -> Self {
+        match reason {
+            RejectReason::Unmapped => Reason::LiftoverUnmapped,
+            RejectReason::Ambiguous => Reason::LiftoverAmbiguous,
+            RejectReason::RefMismatch => Reason::LiftoverRefMismatch,
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Entry {
+    /// Chromosome of the record, after the optional liftover.
+    pub chrom: String,
+    /// 1-based position of the record, after the optional liftover.
+    pub pos: usize,
+    /// Reference allele of the record.
+    #[serde(rename = "ref")]
+    pub reference: String,
+    /// The affected alternative allele(s), comma-separated.
+    #[serde(rename = "alt")]
+    pub alternative: String,
+    /// What happened to the record.
+    pub action: Action,
+    /// Why it happened.
+    pub reason: Reason,
+}
+
+impl Entry {
+    /// Create for `alternative` of `record`.
+    pub fn new(
+        record: &noodles::vcf::variant::RecordBuf,
+        alternative: &str,
+        action: Action,
+        reason: Reason,
+    ) -> Self {
+        Self {
+            chrom: record.reference_sequence_name().to_string(),
+            pos: record.variant_start().map(usize::from).unwrap_or_default(),
+            reference: record.reference_bases().to_string(),
+            alternative: alternative.to_string(),
+            action,
+            reason,
+        }
+    }
+
+    /// Create for all alternative alleles of `record`.
+    pub fn for_record(
+        record: &noodles::vcf::variant::RecordBuf,
+        action: Action,
+        reason: Reason,
+    ) -> Self {
+        Self::new(
+            record,
+            &record.alternate_bases().as_ref().join(","),
+            action,
+            reason,
+        )
+    }
+}
+
+/// Writer for the audit log as headered TSV.
+pub struct Writer {
+    /// The underlying CSV writer.
+    inner: csv::Writer<std::fs::File>,
+}
+
+impl Writer {
+    /// Create the audit log at `path`.
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            inner: csv::WriterBuilder::new()
+                .has_headers(true)
+                .delimiter(b'\t')
+                .quote_style(csv::QuoteStyle::Never)
+                .from_path(path)
+                .map_err(|e| anyhow::anyhow!("could not open audit log {}: {}", path, e))?,
+        })
+    }
+
+    /// Write out `entries`.
+    pub fn write(&mut self, entries: impl IntoIterator<Item = Entry>) -> Result<(), anyhow::Error> {
+        for entry in entries {
+            self.inner
+                .serialize(&entry)
+                .map_err(|e| anyhow::anyhow!("could not write audit log entry: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Flush the audit log.
+    pub fn flush(&mut self) -> Result<(), anyhow::Error> {
+        self.inner
+            .flush()
+            .map_err(|e| anyhow::anyhow!("could not flush audit log: {}", e))
+    }
+}
@@ -20,6 +20,28 @@ pub enum VariantCaller {
     Dragen {
         version: String,
     },
+    Strelka2 {
+        version: String,
+    },
+    Strelka2Somatic {
+        version: String,
+    },
+    Mutect2 {
+        version: String,
+    },
+    Bcftools {
+        version: String,
+    },
+    FreeBayes {
+        version: String,
+    },
+    Clair3 {
+        version: String,
+    },
+    Sentieon {
+        engine: String,
+        version: String,
+    },
     Other,
 }
 
@@ -31,6 +53,13 @@ impl VariantCaller {
             VariantCaller::GatkUnifiedGenotyper { .. } => "GatkUnifiedGenotyper",
             VariantCaller::Dragen { .. } => "Dragen",
             VariantCaller::Glnexus { .. } => "Glnexus",
+            VariantCaller::Strelka2 { .. } => "Strelka2",
+            VariantCaller::Strelka2Somatic { .. } => "Strelka2Somatic",
+            VariantCaller::Mutect2 { .. } => "Mutect2",
+            VariantCaller::Bcftools { .. } => "Bcftools",
+            VariantCaller::FreeBayes { .. } => "FreeBayes",
+            VariantCaller::Clair3 { .. } => "Clair3",
+            VariantCaller::Sentieon { .. } => "Sentieon",
             VariantCaller::Other => "Other",
         }
     }
@@ -42,11 +71,20 @@ impl VariantCaller {
 
         let mut glnexus_version: Option<String> = None;
         let mut glnexus_config_name: Option<String> = None;
+        let mut sources: Vec<String> = Vec::new();
+        let mut source_version: Option<String> = None;
+        let mut bcftools_version: Option<String> = None;
+        let mut clair3_version: Option<String> = None;
+        let mut content: Option<String> = None;
 
         for (other, collection) in header.other_records() {
-            if ["GATKCommandLine", "DRAGENCommandLine"]
-                .iter()
-                .any(|k| other.as_ref().starts_with(k))
+            if [
+                "GATKCommandLine",
+                "DRAGENCommandLine",
+                "SentieonCommandLine",
+            ]
+            .iter()
+            .any(|k| other.as_ref().starts_with(k))
             {
                 if let Collection::Structured(map) = collection {
                     for (key, values) in map.iter() {
@@ -57,9 +95,18 @@ impl VariantCaller {
                             ("UnifiedGenotyper", Some(version)) => {
                                 return Some(VariantCaller::GatkUnifiedGenotyper { version })
                             }
+                            ("Mutect2", Some(version)) => {
+                                return Some(VariantCaller::Mutect2 { version })
+                            }
                             ("dragen", Some(version)) => {
                                 return Some(VariantCaller::Dragen { version })
                             }
+                            (engine @ ("Haplotyper" | "DNAscope"), Some(version)) => {
+                                return Some(VariantCaller::Sentieon {
+                                    engine: engine.to_string(),
+                                    version,
+                                })
+                            }
                             _ => (),
                         }
                     }
@@ -72,6 +119,28 @@ impl VariantCaller {
                 if let Collection::Unstructured(values) = collection {
                     glnexus_config_name = Some(values[0].clone());
                 }
+            } else if other.as_ref() == "source" {
+                if let Collection::Unstructured(values) = collection {
+                    sources.extend(values.iter().cloned());
+                }
+            } else if other.as_ref() == "source_version" {
+                if let Collection::Unstructured(values) = collection {
+                    source_version = Some(values[0].clone());
+                }
+            } else if other.as_ref() == "content" {
+                if let Collection::Unstructured(values) = collection {
+                    content = Some(values[0].clone());
+                }
+            } else if other.as_ref() == "clair3_version" {
+                if let Collection::Unstructured(values) = collection {
+                    clair3_version = Some(values[0].clone());
+                }
+            } else if other.as_ref() == "bcftools_callVersion" {
+                if let Collection::Unstructured(values) = collection {
+                    bcftools_version = Some(values[0].clone());
+                }
+            } else if other.as_ref() == "bcftools_callCommand" && bcftools_version.is_none() {
+                bcftools_version = Some(String::new());
             }
         }
 
@@ -82,8 +151,72 @@ impl VariantCaller {
             });
         }
 
+        if let Some(version) = bcftools_version {
+            return Some(VariantCaller::Bcftools { version });
+        }
+
+        for source in &sources {
+            if source.starts_with("strelka")
+                && content
+                    .as_deref()
+                    .is_some_and(|content| content.starts_with("strelka somatic"))
+            {
+                return Some(VariantCaller::Strelka2Somatic {
+                    version: source_version.unwrap_or_default(),
+                });
+            } else if source.starts_with("strelka") {
+                return Some(VariantCaller::Strelka2 {
+                    version: source_version.unwrap_or_default(),
+                });
+            } else if let Some(version) = source.strip_prefix("freeBayes") {
+                return Some(VariantCaller::FreeBayes {
+                    version: version.trim().to_string(),
+                });
+            } else if source.starts_with("Clair3") {
+                return Some(VariantCaller::Clair3 {
+                    version: clair3_version.unwrap_or_default(),
+                });
+            }
+        }
+
         None
     }
+
+    /// Return whether the variant caller writes tumor/normal somatic calls.
+    pub fn is_somatic(&self) -> bool {
+        matches!(
+            self,
+            VariantCaller::Strelka2Somatic { .. } | VariantCaller::Mutect2 { .. }
+        )
+    }
+
+    /// Return the tumor and the optional normal sample of a somatic VCF `header`.
+    ///
+    /// Mutect2 writes the names to `##tumor_sample` and `##normal_sample` while
+    /// Strelka2 uses the fixed names `TUMOR` and `NORMAL`.
+    pub fn somatic_samples(&self, header: &vcf::Header) -> Option<(String, Option<String>)> {
+        use vcf::header::record::value::collection::Collection;
+
+        let unstructured = |name: &str| {
+            header
+                .other_records()
+                .iter()
+                .find(|(other, _)| other.as_ref() == name)
+                .and_then(|(_, collection)| match collection {
+                    Collection::Unstructured(values) => values.first().cloned(),
+                    _ => None,
+                })
+        };
+        match self {
+            VariantCaller::Mutect2 { .. } => {
+                Some((unstructured("tumor_sample")?, unstructured("normal_sample")))
+            }
+            VariantCaller::Strelka2Somatic { .. } => {
+                Some((String::from("TUMOR"), Some(String::from("NORMAL"))))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Add contigs for GRCh37.
@@ -204,7 +337,28 @@ fn add_contigs_38(builder: vcf::header::Builder) -> Result<vcf::header::Builder,
     Ok(builder)
 }
 
+/// Return canonical chromosome name without `chr` prefix and with `MT` for chrM.
+pub fn canonical_chrom(chrom: &str) -> String {
+    let chrom = chrom.strip_prefix("chr").unwrap_or(chrom);
+    if chrom == "M" {
+        "MT".to_string()
+    } else {
+        chrom.to_string()
+    }
+}
+
+/// Return the chromosome name as used in the output for the given genome release.
+pub fn release_chrom(chrom: &str, genome_release: GenomeRelease) -> String {
+    let chrom = canonical_chrom(chrom);
+    match genome_release {
+        GenomeRelease::Grch37 => chrom,
+        GenomeRelease::Grch38 if chrom == "MT" => "chrM".to_string(),
+        GenomeRelease::Grch38 => format!("chr{}", chrom),
+    }
+}
+
 /// Generate the output header from the input header.
+#[allow(clippy::too_many_arguments)]
 pub fn build_output_header(
     input_header: &vcf::Header,
     pedigree: &Option<mehari::ped::PedigreeByName>,
@@ -213,6 +367,7 @@ pub fn build_output_header(
     file_date: &str,
     case_uuid: &uuid::Uuid,
     worker_version: &str,
+    allow_unknown_caller: bool,
 ) -> Result<vcf::Header, anyhow::Error> {
     use noodles::vcf::header::record::value::map::info::Number;
     use vcf::header::record::value::{
@@ -473,7 +628,12 @@ pub fn build_output_header(
     use vcf::header::record::value::map::Other;
 
     let orig_caller = VariantCaller::guess(input_header)
-        .ok_or_else(|| anyhow::anyhow!("unable to guess original variant caller"))?;
+        .or_else(|| allow_unknown_caller.then_some(VariantCaller::Other))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "unable to guess original variant caller (use --allow-unknown-caller to ingest anyway)"
+            )
+        })?;
 
     let builder = builder
         .insert(
@@ -493,7 +653,13 @@ pub fn build_output_header(
     let builder = match &orig_caller {
         VariantCaller::GatkHaplotypeCaller { version }
         | VariantCaller::GatkUnifiedGenotyper { version }
-        | VariantCaller::Dragen { version } => builder.insert(
+        | VariantCaller::Dragen { version }
+        | VariantCaller::Strelka2 { version }
+        | VariantCaller::Strelka2Somatic { version }
+        | VariantCaller::Mutect2 { version }
+        | VariantCaller::Bcftools { version }
+        | VariantCaller::FreeBayes { version }
+        | VariantCaller::Clair3 { version } => builder.insert(
             "x-varfish-version".parse()?,
             vcf::header::record::Value::Map(
                 String::from("orig-caller"),
@@ -520,6 +686,17 @@ pub fn build_output_header(
                     .build()?,
             ),
         )?,
+        VariantCaller::Sentieon { engine, version } => builder.insert(
+            "x-varfish-version".parse()?,
+            vcf::header::record::Value::Map(
+                String::from("orig-caller"),
+                Map::<Other>::builder()
+                    .insert("Name".parse()?, orig_caller.name())
+                    .insert("Version".parse()?, version)
+                    .insert("Engine".parse()?, engine)
+                    .build()?,
+            ),
+        )?,
         VariantCaller::Other => builder.insert(
             "x-varfish-version".parse()?,
             vcf::header::record::Value::Map(
@@ -541,6 +718,20 @@ mod test {
     use rstest::rstest;
 
     use super::VariantCaller;
+    use crate::common::GenomeRelease;
+
+    #[rstest]
+    #[case::grch37_from_prefixed("chr1", GenomeRelease::Grch37, "1")]
+    #[case::grch37_mito("chrM", GenomeRelease::Grch37, "MT")]
+    #[case::grch38_from_plain("X", GenomeRelease::Grch38, "chrX")]
+    #[case::grch38_mito("MT", GenomeRelease::Grch38, "chrM")]
+    fn release_chrom(
+        #[case] chrom: &str,
+        #[case] genome_release: GenomeRelease,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(super::release_chrom(chrom, genome_release), expected);
+    }
 
     #[rstest]
     #[case("tests/seqvars/ingest/clair3_glnexus.vcf")]
@@ -548,6 +739,13 @@ mod test {
     #[case("tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.3.7-0.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf")]
+    #[case("tests/seqvars/ingest/example_strelka2.2.9.10.vcf")]
+    #[case("tests/seqvars/ingest/example_bcftools.1.17.vcf")]
+    #[case("tests/seqvars/ingest/example_freebayes.1.0.2.vcf")]
+    #[case("tests/seqvars/ingest/example_clair3.1.0.4.vcf")]
+    #[case("tests/seqvars/ingest/example_sentieon_dnascope.202308.vcf")]
+    #[case("tests/seqvars/ingest/example_mutect2.4.4.0.0.vcf")]
+    #[case("tests/seqvars/ingest/example_strelka2_somatic.2.9.10.vcf")]
     fn variant_caller_guess(#[case] path: &str) -> Result<(), anyhow::Error> {
         mehari::common::set_snapshot_suffix!("{}", path.split('/').last().unwrap());
 
@@ -560,12 +758,45 @@ mod test {
         Ok(())
     }
 
+    #[rstest]
+    #[case::germline("tests/seqvars/ingest/example_strelka2.2.9.10.vcf", None)]
+    #[case::mutect2(
+        "tests/seqvars/ingest/example_mutect2.4.4.0.0.vcf",
+        Some(("CASE_TUMOR", Some("CASE_NORMAL")))
+    )]
+    #[case::strelka2(
+        "tests/seqvars/ingest/example_strelka2_somatic.2.9.10.vcf",
+        Some(("TUMOR", Some("NORMAL")))
+    )]
+    fn somatic_samples(
+        #[case] path: &str,
+        #[case] expected: Option<(&str, Option<&str>)>,
+    ) -> Result<(), anyhow::Error> {
+        let vcf_header = noodles::vcf::io::reader::Builder::default()
+            .build_from_path(path)?
+            .read_header()?;
+        let caller = VariantCaller::guess(&vcf_header).expect("could not guess caller");
+
+        assert_eq!(caller.is_somatic(), expected.is_some());
+        assert_eq!(
+            caller.somatic_samples(&vcf_header),
+            expected.map(|(tumor, normal)| (tumor.to_string(), normal.map(String::from)))
+        );
+
+        Ok(())
+    }
+
     #[rstest]
     #[case("tests/seqvars/ingest/clair3_glnexus.vcf")]
     #[case("tests/seqvars/ingest/example_dragen.07.021.624.3.10.4.vcf")]
     #[case("tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.3.7-0.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf")]
+    #[case("tests/seqvars/ingest/example_strelka2.2.9.10.vcf")]
+    #[case("tests/seqvars/ingest/example_bcftools.1.17.vcf")]
+    #[case("tests/seqvars/ingest/example_freebayes.1.0.2.vcf")]
+    #[case("tests/seqvars/ingest/example_clair3.1.0.4.vcf")]
+    #[case("tests/seqvars/ingest/example_sentieon_dnascope.202308.vcf")]
     fn build_output_header_37(#[case] path: &str) -> Result<(), anyhow::Error> {
         mehari::common::set_snapshot_suffix!("{}", path.split('/').last().unwrap());
         let tmpdir = temp_testdir::TempDir::default();
@@ -583,6 +814,7 @@ mod test {
             "20230421",
             &uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             "x.y.z",
+            false,
         )?;
 
         // Work around glnexus issue with RNC.
@@ -609,6 +841,11 @@ mod test {
     #[case("tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.3.7-0.vcf")]
     #[case("tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf")]
+    #[case("tests/seqvars/ingest/example_strelka2.2.9.10.vcf")]
+    #[case("tests/seqvars/ingest/example_bcftools.1.17.vcf")]
+    #[case("tests/seqvars/ingest/example_freebayes.1.0.2.vcf")]
+    #[case("tests/seqvars/ingest/example_clair3.1.0.4.vcf")]
+    #[case("tests/seqvars/ingest/example_sentieon_dnascope.202308.vcf")]
     fn build_output_header_38(#[case] path: &str) -> Result<(), anyhow::Error> {
         mehari::common::set_snapshot_suffix!("{}", path.split('/').last().unwrap());
         let tmpdir = temp_testdir::TempDir::default();
@@ -626,6 +863,7 @@ mod test {
             "20230421",
             &uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             "x.y.z",
+            false,
         )?;
 
         // Work around glnexus issue with RNC.
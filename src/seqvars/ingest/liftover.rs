@@ -0,0 +1,225 @@
+//! Liftover of variants between genome releases using UCSC chain files.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+
+use super::header::canonical_chrom;
+
+/// One ungapped block of an alignment chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    /// Canonical name of the target chromosome, e.g., `1` or `MT`.
+    target_chrom: String,
+    /// 0-based start position of the block on the target chromosome, on the strand of
+    /// the chain.
+    target_start: u64,
+    /// Size of the target chromosome.
+    target_size: u64,
+    /// Whether the block maps to the reverse strand of the target.
+    is_reverse: bool,
+}
+
+/// Result of lifting a variant to the target genome release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lifted {
+    /// Canonical name of the target chromosome.
+    pub chrom: String,
+    /// 0-based start position on the forward strand of the target chromosome.
+    pub start: u64,
+    /// Whether the variant maps to the reverse strand, i.e., its alleles must be
+    /// reverse-complemented.
+    pub is_reverse: bool,
+}
+
+/// Reason for rejecting a variant in the liftover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display)]
+pub enum RejectReason {
+    /// The variant does not fall into one aligned block.
+    #[strum(serialize = "unmapped")]
+    Unmapped,
+    /// The variant maps to more than one target position.
+    #[strum(serialize = "ambiguous")]
+    Ambiguous,
+    /// The reference allele differs from the reference of the target.
+    #[strum(serialize = "ref-mismatch")]
+    RefMismatch,
+}
+
+/// Liftover based on the ungapped blocks of a chain file.
+#[derive(Debug, Default)]
+pub struct Chains {
+    /// Interval trees of source positions to blocks, by canonical source chromosome.
+    trees: HashMap<String, ArrayBackedIntervalTree<u64, Block>>,
+}
+
+impl Chains {
+    /// Load the chain file from `path`, may be gzip-compressed.
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        let reader = mehari::common::io::std::open_read_maybe_gz(path)
+            .map_err(|e| anyhow::anyhow!("could not open chain file {}: {}", path, e))?;
+        Self::from_reader(reader)
+    }
+
+    /// Load the chain file from `reader`.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        // Current source chromosome, source and target position, and block template.
+        let mut current: Option<(String, u64, u64, Block)> = None;
+        for line in reader.lines() {
+            let line = line?;
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                [] => current = None,
+                ["chain", _score, t_name, _t_size, _t_strand, t_start, _t_end, q_name, q_size, q_strand, q_start, _q_end, ..] =>
+                {
+                    current = Some((
+                        canonical_chrom(t_name),
+                        t_start.parse()?,
+                        q_start.parse()?,
+                        Block {
+                            target_chrom: canonical_chrom(q_name),
+                            target_start: 0,
+                            target_size: q_size.parse()?,
+                            is_reverse: *q_strand == "-",
+                        },
+                    ));
+                }
+                [size, gaps @ ..] if gaps.is_empty() || gaps.len() == 2 => {
+                    let Some((source_chrom, source_pos, target_pos, block)) = current.as_mut()
+                    else {
+                        anyhow::bail!("alignment data line before chain header: {}", &line);
+                    };
+                    let size: u64 = size.parse()?;
+                    result
+                        .trees
+                        .entry(source_chrom.clone())
+                        .or_insert_with(ArrayBackedIntervalTree::new)
+                        .insert(
+                            *source_pos..(*source_pos + size),
+                            Block {
+                                target_start: *target_pos,
+                                ..block.clone()
+                            },
+                        );
+                    *source_pos += size;
+                    *target_pos += size;
+                    if let [dt, dq] = gaps {
+                        *source_pos += dt.parse::<u64>()?;
+                        *target_pos += dq.parse::<u64>()?;
+                    }
+                }
+                _ => anyhow::bail!("invalid line in chain file: {}", &line),
+            }
+        }
+        for tree in result.trees.values_mut() {
+            tree.index();
+        }
+        Ok(result)
+    }
+
+    /// Lift the 0-based half-open interval `start..end` on `chrom`.
+    pub fn lift(&self, chrom: &str, start: u64, end: u64) -> Result<Lifted, RejectReason> {
+        let Some(tree) = self.trees.get(&canonical_chrom(chrom)) else {
+            return Err(RejectReason::Unmapped);
+        };
+        let entries = tree
+            .find(start..end)
+            .into_iter()
+            .filter(|entry| entry.interval().start <= start && end <= entry.interval().end)
+            .collect::<Vec<_>>();
+        match entries.as_slice() {
+            [] => Err(RejectReason::Unmapped),
+            [entry] => {
+                let block = entry.data();
+                let target_start = block.target_start + (start - entry.interval().start);
+                Ok(Lifted {
+                    chrom: block.target_chrom.clone(),
+                    // Chain coordinates on the reverse strand count from the chromosome end.
+                    start: if block.is_reverse {
+                        block.target_size - (target_start + (end - start))
+                    } else {
+                        target_start
+                    },
+                    is_reverse: block.is_reverse,
+                })
+            }
+            _ => Err(RejectReason::Ambiguous),
+        }
+    }
+}
+
+/// Return the reverse complement of `bases`, keeping characters other than nucleotides.
+pub fn reverse_complement(bases: &str) -> String {
+    bases
+        .chars()
+        .rev()
+        .map(|base| match base {
+            'A' => 'T',
+            'C' => 'G',
+            'G' => 'C',
+            'T' => 'A',
+            'a' => 't',
+            'c' => 'g',
+            'g' => 'c',
+            't' => 'a',
+            _ => base,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Chains, Lifted, RejectReason};
+
+    /// Chain with two blocks on chr1 separated by a 10bp source / 5bp target gap,
+    /// and a reverse-strand chain on chr2.
+    const CHAIN: &str = "\
+chain 1000 chr1 1000 + 100 230 chr1 1000 + 200 325 1
+50 10 5
+70
+
+chain 1000 chr2 1000 + 0 100 chr2 1000 - 0 100 2
+100
+";
+
+    #[rstest::rstest]
+    #[case::first_block("1", 100, 101, Ok(("1", 200, false)))]
+    #[case::first_block_end("chr1", 149, 150, Ok(("1", 249, false)))]
+    #[case::second_block("1", 160, 162, Ok(("1", 255, false)))]
+    #[case::in_gap("1", 155, 156, Err(RejectReason::Unmapped))]
+    #[case::spans_gap("1", 148, 162, Err(RejectReason::Unmapped))]
+    #[case::before_chain("1", 10, 11, Err(RejectReason::Unmapped))]
+    #[case::unknown_chrom("3", 100, 101, Err(RejectReason::Unmapped))]
+    #[case::reverse_strand("2", 10, 11, Ok(("2", 989, true)))]
+    #[case::reverse_strand_interval("2", 10, 13, Ok(("2", 987, true)))]
+    fn lift(
+        #[case] chrom: &str,
+        #[case] start: u64,
+        #[case] end: u64,
+        #[case] expected: Result<(&str, u64, bool), RejectReason>,
+    ) -> Result<(), anyhow::Error> {
+        let chains = Chains::from_reader(CHAIN.as_bytes())?;
+
+        assert_eq!(
+            chains.lift(chrom, start, end),
+            expected.map(|(chrom, start, is_reverse)| Lifted {
+                chrom: chrom.to_string(),
+                start,
+                is_reverse,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::snv("A", "T")]
+    #[case::indel("GATC", "GATC")]
+    #[case::mixed_case("aCgN", "NcGt")]
+    #[case::spanning_deletion("*", "*")]
+    fn reverse_complement(#[case] bases: &str, #[case] expected: &str) {
+        assert_eq!(super::reverse_complement(bases), expected);
+    }
+}
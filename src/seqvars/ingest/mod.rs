@@ -2,21 +2,47 @@
 
 use std::sync::{Arc, OnceLock};
 
-use crate::{
-    common::{self, genotype_to_string, strip_gt_leading_slash, worker_version, GenomeRelease},
-    flush_and_shutdown,
+use crate::common::{
+    self, genotype_to_string,
+    noodles::{open_variant_writer, VariantWriter},
+    strip_gt_leading_slash, worker_version, Chrom, GenomeRelease,
 };
 use futures::TryStreamExt as _;
-use mehari::common::noodles::{open_vcf_writer, AsyncVcfWriter};
 use mehari::{
     annotate::seqvars::provider::Provider as MehariProvider,
     common::noodles::{NoodlesVariantReader as _, VariantReader},
 };
 use noodles::vcf;
 use thousands::Separable;
-use tokio::io::AsyncWriteExt;
 
+pub mod audit;
 pub mod header;
+pub mod liftover;
+pub mod normalize;
+pub mod sex_check;
+pub mod shards;
+pub mod sort;
+
+/// Handling of records on contigs outside of the canonical set of the genome release.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownContigs {
+    /// Skip the records, e.g., on ALT scaffolds, decoys, or HLA contigs.
+    #[default]
+    Skip,
+    /// Stop with an error.
+    Error,
+}
+
+/// Handling of genotypes failing the `--min-gq`, `--min-dp`, or `--min-ab` filters.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FailedGenotypes {
+    /// Set the genotypes to no-call.
+    #[default]
+    NoCall,
+    /// Set the genotypes to no-call and drop records where no sample carries the
+    /// alternative allele anymore.
+    Drop,
+}
 
 /// Command line arguments for `seqvars ingest` subcommand.
 #[derive(Debug, clap::Parser)]
@@ -35,13 +61,13 @@ pub struct Args {
     /// The path to the mehari database.
     #[clap(long)]
     pub path_mehari_db: String,
-    /// Path to the pedigree file.
+    /// Path to the pedigree file, may contain multiple families.
     #[clap(long)]
     pub path_ped: String,
-    /// Path to input file.
+    /// Path to input file, `-` for stdin.
     #[clap(long)]
     pub path_in: String,
-    /// Path to output file.
+    /// Path to output file, `-` for stdout.
     #[clap(long)]
     pub path_out: String,
 
@@ -51,6 +77,109 @@ pub struct Args {
     /// Per-file identifier mapping, either a JSON or @-prefixed path to JSON.
     #[clap(long)]
     pub id_mapping: Option<String>,
+    /// Whether to keep `FORMAT/PL`, e.g., for the de novo probability in queries.
+    #[clap(long)]
+    pub keep_pl: bool,
+    /// Whether to ingest VCF files from unknown variant callers.
+    #[clap(long)]
+    pub allow_unknown_caller: bool,
+    /// Optional path to reference FASTA (with `.fai` index) for trimming and
+    /// left-aligning the variant alleles, of the target release when lifting over.
+    /// Required for liftover; lifted variants with a mismatching reference allele
+    /// are rejected.
+    #[clap(long)]
+    pub path_reference: Option<String>,
+    /// Optional genome release to lift the variants over to from `--genomebuild`.
+    #[clap(long)]
+    pub target_genome_release: Option<GenomeRelease>,
+    /// Path to the UCSC chain file for the liftover, may be gzip-compressed.
+    #[clap(long)]
+    pub path_chain: Option<String>,
+    /// Optional path to VCF file for the records that could not be lifted over.
+    #[clap(long)]
+    pub path_rejects: Option<String>,
+    /// Whether to check the pedigree sex against the chrX/chrY genotypes; this
+    /// reads the input file twice.
+    #[clap(long)]
+    pub sex_check: bool,
+    /// Optional path to write the sex check results to as JSON.
+    #[clap(long, requires = "sex_check")]
+    pub path_sex_check_json: Option<String>,
+    /// How to handle records on contigs not in the canonical set of the genome release.
+    #[clap(long, value_enum, default_value_t = UnknownContigs::Skip)]
+    pub unknown_contigs: UnknownContigs,
+    /// Rename input samples before matching with the pedigree, either `old=new` or
+    /// @-prefixed path to TSV file with old and new name; may be given multiple times.
+    #[clap(long)]
+    pub sample_rename: Vec<String>,
+    /// Only ingest the given input samples, e.g., for one family of a joint VCF.
+    #[clap(long, value_delimiter = ',', conflicts_with = "exclude_samples")]
+    pub keep_samples: Vec<String>,
+    /// Do not ingest the given input samples.
+    #[clap(long, value_delimiter = ',')]
+    pub exclude_samples: Vec<String>,
+    /// Number of contigs to process in parallel; values above one require a
+    /// tabix-indexed input file and a `.vcf.gz` output file.
+    #[clap(long, default_value_t = 1)]
+    pub num_threads: usize,
+    /// Do not write the TBI/CSI index next to compressed output files.
+    #[clap(long)]
+    pub no_index: bool,
+    /// Minimal genotype quality (`FORMAT/GQ`) of called genotypes.
+    #[clap(long)]
+    pub min_gq: Option<i32>,
+    /// Minimal read depth (`FORMAT/DP`) of called genotypes.
+    #[clap(long)]
+    pub min_dp: Option<i32>,
+    /// Minimal fraction of alternative reads (from `FORMAT/AD`) of heterozygous genotypes.
+    #[clap(long)]
+    pub min_ab: Option<f32>,
+    /// How to handle genotypes failing `--min-gq`, `--min-dp`, or `--min-ab`.
+    #[clap(long, value_enum, default_value_t = FailedGenotypes::NoCall)]
+    pub failed_genotypes: FailedGenotypes,
+    /// Family of the pedigree file to ingest; by default, the family of the input
+    /// samples.  Samples of other families are not ingested.
+    #[clap(long)]
+    pub family_id: Option<String>,
+    /// Ingest tumor/normal calls of Mutect2 or Strelka2; `FORMAT/AF` is kept and the
+    /// tumor and normal samples are written to the header.
+    #[clap(long)]
+    pub somatic: bool,
+    /// Keep selected DRAGEN-specific `INFO` and `FORMAT` fields under `dragen_`-prefixed
+    /// keys and the `FILTER` definitions of the DRAGEN hard filters.
+    #[clap(long)]
+    pub keep_dragen_annos: bool,
+    /// Write the heteroplasmy fraction of the alternative allele from `FORMAT/AD` and
+    /// `FORMAT/DP` as `FORMAT/HF` for chrMT variants.
+    #[clap(long)]
+    pub mt_heteroplasmy: bool,
+    /// Optional path to annonars dbSNP RocksDB for writing the rsIDs to the `ID` column.
+    #[clap(long)]
+    pub path_dbsnp: Option<String>,
+    /// Optional path to TSV file for the records that were dropped or rewritten,
+    /// with reason codes.
+    #[clap(long)]
+    pub path_audit: Option<String>,
+    /// Write one bgzip-compressed VCF file per chromosome and a `manifest.json` to the
+    /// directory `--path-out`.
+    #[clap(long)]
+    pub shard_by_chrom: bool,
+    /// Tuning of the frequency and ClinVar RocksDBs.
+    #[clap(flatten)]
+    pub rocksdb: crate::common::rocksdb_tuning::RocksdbArgs,
+}
+
+impl Args {
+    /// Return the genome release of the output, i.e., after the optional liftover.
+    pub fn output_genomebuild(&self) -> GenomeRelease {
+        self.target_genome_release.unwrap_or(self.genomebuild)
+    }
+
+    /// Return whether the output records must be sorted again, i.e., when
+    /// left-aligning against the reference or lifting over.
+    pub fn sorts_output(&self) -> bool {
+        self.path_reference.is_some() || self.output_genomebuild() != self.genomebuild
+    }
 }
 
 /// Return path component fo rth egiven assembly.
@@ -103,6 +232,32 @@ impl Default for KnownFormatKeys {
 }
 
 impl KnownFormatKeys {
+    /// Constructor that also keeps `FORMAT/PL`.
+    pub fn with_pl() -> Self {
+        let mut result = Self::default();
+        result.output_keys.push("PL".to_string());
+        result.known_keys.push("PL".to_string());
+        result
+    }
+
+    /// Constructor for somatic calls that also keeps `FORMAT/AF`.
+    pub fn with_af() -> Self {
+        let mut result = Self::default();
+        result.output_keys.push("AF".to_string());
+        result.known_keys.push("AF".to_string());
+        result
+    }
+
+    /// Also keep the DRAGEN-specific `FORMAT` keys under their namespaced output keys.
+    pub fn add_dragen(&mut self) {
+        for (key, output_key) in DRAGEN_FORMAT_KEYS {
+            self.output_keys.push(output_key.to_string());
+            self.known_keys.push(key.to_string());
+            self.known_to_output_map
+                .insert(key.to_string(), output_key.to_string());
+        }
+    }
+
     /// Map from known to output key.
     pub fn known_to_output(&self, key: &str) -> String {
         self.known_to_output_map
@@ -112,8 +267,406 @@ impl KnownFormatKeys {
     }
 }
 
-/// The known `FORMAT` keys.
-static KNOWN_FORMAT_KEYS: OnceLock<KnownFormatKeys> = OnceLock::new();
+/// DRAGEN-specific `INFO` keys kept with `--keep-dragen-annos`, with output key and description.
+const DRAGEN_INFO_KEYS: &[(&str, &str, &str)] = &[(
+    "FractionInformativeReads",
+    "dragen_fraction_informative_reads",
+    "DRAGEN: fraction of informative reads out of the total reads",
+)];
+
+/// DRAGEN-specific `FORMAT` keys kept with `--keep-dragen-annos` and their output keys.
+///
+/// Both have one value per allele and are subset to the reference and current allele.
+const DRAGEN_FORMAT_KEYS: &[(&str, &str)] = &[("F1R2", "dragen_f1r2"), ("F2R1", "dragen_f2r1")];
+
+/// Subset the `FORMAT/PL` values to the genotypes of the reference and `allele_no`.
+///
+/// The result is normalized such that the smallest value is zero.
+fn subset_pl(pl_values: &[Option<i32>], allele_no: usize) -> Vec<Option<i32>> {
+    let het = allele_no * (allele_no + 1) / 2;
+    let values = [0, het, het + allele_no]
+        .map(|idx| pl_values.get(idx).copied().flatten())
+        .to_vec();
+    let min = values.iter().flatten().min().copied().unwrap_or_default();
+    values
+        .into_iter()
+        .map(|value| value.map(|value| value - min))
+        .collect()
+}
+
+/// Derive `FORMAT/AD` for all `alleles` from per-base read counts.
+///
+/// Returns `None` if any of the alleles is not a single base.
+fn ad_from_base_counts<F>(alleles: &[String], base_count: F) -> Option<Vec<Option<i32>>>
+where
+    F: Fn(&str) -> Option<i32>,
+{
+    alleles
+        .iter()
+        .map(|allele| match allele.as_str() {
+            "A" => Some(base_count("AU")),
+            "C" => Some(base_count("CU")),
+            "G" => Some(base_count("GU")),
+            "T" => Some(base_count("TU")),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Normalize the Strelka2-specific `FORMAT` fields to the well-known keys.
+///
+/// `GQ` is taken from the empirically calibrated `GQX`, `DP` from `DPI` for indels,
+/// and `AD` is derived from the tier 1 counts in `AU`, `CU`, `GU`, and `TU` if missing.
+fn normalize_strelka_format(record_buf: &mut vcf::variant::RecordBuf) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+    let mut output_keys: Vec<String> = Vec::new();
+    for key in record_buf.samples().keys().as_ref().iter() {
+        let key = match key.as_str() {
+            "GQX" => "GQ",
+            "DPI" => "DP",
+            "AU" | "CU" | "GU" | "TU" => "AD",
+            key => key,
+        };
+        if !output_keys.iter().any(|k| k == key) {
+            output_keys.push(key.to_string());
+        }
+    }
+
+    let alleles = std::iter::once(record_buf.reference_bases().to_string())
+        .chain(record_buf.alternate_bases().as_ref().iter().cloned())
+        .collect::<Vec<_>>();
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            let get = |key: &str| sample.get(key).flatten().cloned();
+            output_keys
+                .iter()
+                .map(|key| match key.as_str() {
+                    "GQ" => get("GQX").or_else(|| get("GQ")),
+                    "DP" => get("DP").or_else(|| get("DPI")),
+                    "AD" => get("AD").or_else(|| {
+                        ad_from_base_counts(&alleles, |key| match get(key) {
+                            Some(Value::Array(Array::Integer(counts))) => {
+                                counts.first().copied().flatten()
+                            }
+                            _ => None,
+                        })
+                        .map(|ad| Value::Array(Array::Integer(ad)))
+                    }),
+                    key => get(key),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() =
+        vcf::variant::record_buf::samples::Samples::new(output_keys.into_iter().collect(), values);
+}
+
+/// Normalize the Strelka2 somatic `FORMAT` fields to the well-known keys.
+///
+/// Strelka2 does not write `FORMAT/GT` for somatic calls, so the sample at `tumor_idx`
+/// is set to heterozygous and the normal sample is set from `INFO/NT`.  `AD` is derived
+/// from the tier 1 counts in `AU`, `CU`, `GU`, and `TU` for SNVs and in `TAR` and `TIR`
+/// for indels, and `AF` is computed from `AD`.
+fn normalize_strelka_somatic_format(record_buf: &mut vcf::variant::RecordBuf, tumor_idx: usize) {
+    use vcf::variant::record_buf::info::field::Value as InfoValue;
+    use vcf::variant::record_buf::samples::sample::value::{Array, Genotype, Value};
+
+    let normal_gt = match record_buf.info().get("NT") {
+        Some(Some(InfoValue::String(nt))) => match nt.as_str() {
+            "ref" => "0/0",
+            "het" => "0/1",
+            "hom" => "1/1",
+            _ => "./.",
+        },
+        _ => "./.",
+    };
+    let is_indel = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .any(|key| key == "TIR");
+
+    let alleles = std::iter::once(record_buf.reference_bases().to_string())
+        .chain(record_buf.alternate_bases().as_ref().iter().cloned())
+        .collect::<Vec<_>>();
+    let values = record_buf
+        .samples()
+        .values()
+        .enumerate()
+        .map(|(idx, sample)| {
+            let get = |key: &str| sample.get(key).flatten().cloned();
+            let tier1 = |key: &str| match get(key) {
+                Some(Value::Array(Array::Integer(counts))) => counts.first().copied().flatten(),
+                _ => None,
+            };
+            let ad = if is_indel {
+                Some(vec![tier1("TAR"), tier1("TIR")])
+            } else {
+                ad_from_base_counts(&alleles, tier1)
+            };
+            let af = ad.as_ref().and_then(|ad| {
+                let total: i32 = ad.iter().flatten().sum();
+                (total > 0).then(|| {
+                    ad[1..]
+                        .iter()
+                        .map(|count| count.map(|count| count as f32 / total as f32))
+                        .collect::<Vec<_>>()
+                })
+            });
+            let gt = if idx == tumor_idx { "0/1" } else { normal_gt };
+            vec![
+                gt.parse::<Genotype>().ok().map(Value::Genotype),
+                get("DP"),
+                ad.map(|ad| Value::Array(Array::Integer(ad))),
+                af.map(|af| Value::Array(Array::Float(af))),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() = vcf::variant::record_buf::samples::Samples::new(
+        ["GT", "DP", "AD", "AF"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        values,
+    );
+}
+
+/// Derive `FORMAT/GQ` from `FORMAT/PL` as the difference of the two smallest values.
+///
+/// The result is capped at 99, as done by GATK.
+fn gq_from_pl(pl_values: &[Option<i32>]) -> Option<i32> {
+    let mut values = pl_values.iter().flatten().copied().collect::<Vec<_>>();
+    values.sort_unstable();
+    match values.as_slice() {
+        [first, second, ..] => Some((second - first).min(99)),
+        _ => None,
+    }
+}
+
+/// Normalize the bcftools/samtools `FORMAT` fields to the well-known keys.
+///
+/// `GQ` is derived from `PL` if missing, `AD` from the legacy samtools `DV` field
+/// for bi-allelic sites, and `DP` from the sum of `AD` if missing.
+fn normalize_bcftools_format(record_buf: &mut vcf::variant::RecordBuf) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+    let input_keys = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let has_key = |key: &str| input_keys.iter().any(|k| k == key);
+    let mut output_keys = input_keys
+        .iter()
+        .map(|key| match key.as_str() {
+            "DV" if !has_key("AD") => "AD".to_string(),
+            key => key.to_string(),
+        })
+        .collect::<Vec<_>>();
+    if !has_key("GQ") && has_key("PL") {
+        output_keys.push("GQ".to_string());
+    }
+    if !has_key("DP") && output_keys.iter().any(|k| k == "AD") {
+        output_keys.push("DP".to_string());
+    }
+
+    let is_biallelic = record_buf.alternate_bases().as_ref().len() == 1;
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            let get = |key: &str| sample.get(key).flatten().cloned();
+            let ad = get("AD").or_else(|| match (get("DP"), get("DV")) {
+                (Some(Value::Integer(dp)), Some(Value::Integer(dv))) if is_biallelic => {
+                    Some(Value::Array(Array::Integer(vec![Some(dp - dv), Some(dv)])))
+                }
+                _ => None,
+            });
+            output_keys
+                .iter()
+                .map(|key| match key.as_str() {
+                    "GQ" => get("GQ").or_else(|| match get("PL") {
+                        Some(Value::Array(Array::Integer(pl_values))) => {
+                            gq_from_pl(&pl_values).map(Value::Integer)
+                        }
+                        _ => None,
+                    }),
+                    "AD" => ad.clone(),
+                    "DP" => get("DP").or_else(|| match &ad {
+                        Some(Value::Array(Array::Integer(ad_values))) => {
+                            Some(Value::Integer(ad_values.iter().flatten().sum()))
+                        }
+                        _ => None,
+                    }),
+                    key => get(key),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() =
+        vcf::variant::record_buf::samples::Samples::new(output_keys.into_iter().collect(), values);
+}
+
+/// Normalize the FreeBayes `FORMAT` fields to the well-known keys.
+///
+/// `AD` is derived from `RO` and `AO` and `GQ` from the log10-scaled `GL` if missing.
+fn normalize_freebayes_format(record_buf: &mut vcf::variant::RecordBuf) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+    let input_keys = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let has_key = |key: &str| input_keys.iter().any(|k| k == key);
+    let mut output_keys = input_keys
+        .iter()
+        .filter(|key| key.as_str() != "AO" || has_key("AD"))
+        .map(|key| match key.as_str() {
+            "RO" if !has_key("AD") => "AD".to_string(),
+            key => key.to_string(),
+        })
+        .collect::<Vec<_>>();
+    if !has_key("GQ") && has_key("GL") {
+        output_keys.push("GQ".to_string());
+    }
+
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            let get = |key: &str| sample.get(key).flatten().cloned();
+            output_keys
+                .iter()
+                .map(|key| match key.as_str() {
+                    "AD" => get("AD").or_else(|| match (get("RO"), get("AO")) {
+                        (Some(Value::Integer(ro)), Some(Value::Array(Array::Integer(ao)))) => {
+                            Some(Value::Array(Array::Integer(
+                                std::iter::once(Some(ro)).chain(ao).collect(),
+                            )))
+                        }
+                        _ => None,
+                    }),
+                    "GQ" => get("GQ").or_else(|| match get("GL") {
+                        Some(Value::Array(Array::Float(gl_values))) => gq_from_pl(
+                            &gl_values
+                                .iter()
+                                .map(|gl| gl.map(|gl| (-10.0 * gl).round() as i32))
+                                .collect::<Vec<_>>(),
+                        )
+                        .map(Value::Integer),
+                        _ => None,
+                    }),
+                    key => get(key),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() =
+        vcf::variant::record_buf::samples::Samples::new(output_keys.into_iter().collect(), values);
+}
+
+/// Normalize the `FORMAT` fields of long-read callers such as Clair3.
+///
+/// `AD` is derived from the variant allele fraction in `AF` (Clair3) or `VAF`
+/// (DeepVariant/PEPPER) and `DP` for bi-allelic sites if missing.
+fn normalize_long_read_format(record_buf: &mut vcf::variant::RecordBuf) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+    let input_keys = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    if input_keys.iter().any(|k| k == "AD") {
+        return;
+    }
+    let mut output_keys: Vec<String> = Vec::new();
+    for key in &input_keys {
+        let key = match key.as_str() {
+            "AF" | "VAF" => "AD",
+            key => key,
+        };
+        if !output_keys.iter().any(|k| k == key) {
+            output_keys.push(key.to_string());
+        }
+    }
+
+    let is_biallelic = record_buf.alternate_bases().as_ref().len() == 1;
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            let get = |key: &str| sample.get(key).flatten().cloned();
+            output_keys
+                .iter()
+                .map(|key| match key.as_str() {
+                    "AD" => {
+                        let vaf = match get("AF").or_else(|| get("VAF")) {
+                            Some(Value::Float(vaf)) => Some(vaf),
+                            Some(Value::Array(Array::Float(vafs))) => {
+                                vafs.first().copied().flatten()
+                            }
+                            _ => None,
+                        };
+                        match (get("DP"), vaf) {
+                            (Some(Value::Integer(dp)), Some(vaf)) if is_biallelic => {
+                                let alt = (dp as f32 * vaf).round() as i32;
+                                Some(Value::Array(Array::Integer(vec![
+                                    Some(dp - alt),
+                                    Some(alt),
+                                ])))
+                            }
+                            _ => None,
+                        }
+                    }
+                    key => get(key),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() =
+        vcf::variant::record_buf::samples::Samples::new(output_keys.into_iter().collect(), values);
+}
+
+/// Normalize the `FORMAT` fields of VCF files from unknown callers.
+///
+/// Applies the derivations of the supported callers and returns the keys of the
+/// well-known fields that had to be synthesized.
+fn normalize_generic_format(record_buf: &mut vcf::variant::RecordBuf) -> Vec<String> {
+    let input_keys = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    normalize_long_read_format(record_buf);
+    normalize_bcftools_format(record_buf);
+    record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .filter(|key| ["GQ", "DP", "AD"].contains(&key.as_str()) && !input_keys.contains(key))
+        .cloned()
+        .collect()
+}
 
 /// Regular expression for parsing `GT` values.
 static GT_RE: OnceLock<regex::Regex> = OnceLock::new();
@@ -157,6 +710,11 @@ fn transform_format_value(
                 if ["./.", ".|.", "."].contains(&gt.as_str()) {
                     // no need to transform no-call
                     vcf::variant::record_buf::samples::sample::value::Value::String(gt)
+                } else if !gt.contains(['/', '|']) {
+                    // haploid genotype, e.g., on chrMT as written by long-read callers
+                    vcf::variant::record_buf::samples::sample::value::Value::String(
+                        transform_allele(&gt, &curr_allele).to_string(),
+                    )
                 } else {
                     // transform all others
                     let gt_captures = gt_re
@@ -203,6 +761,39 @@ fn transform_format_value(
                     _ => return None, // unreachable!("FORMAT/AD must be array of integer"),
                 }
             }
+            "PL" => match *value {
+                vcf::variant::record_buf::samples::sample::value::Value::Array(
+                    vcf::variant::record_buf::samples::sample::value::Array::Integer(pl_values),
+                ) => vcf::variant::record_buf::samples::sample::value::Value::Array(
+                    vcf::variant::record_buf::samples::sample::value::Array::Integer(subset_pl(
+                        pl_values, allele_no,
+                    )),
+                ),
+                _ => return None,
+            },
+            "F1R2" | "F2R1" => match *value {
+                vcf::variant::record_buf::samples::sample::value::Value::Array(
+                    vcf::variant::record_buf::samples::sample::value::Array::Integer(counts),
+                ) => vcf::variant::record_buf::samples::sample::value::Value::Array(
+                    vcf::variant::record_buf::samples::sample::value::Array::Integer(vec![
+                        counts.first().copied().flatten(),
+                        counts.get(allele_no).copied().flatten(),
+                    ]),
+                ),
+                _ => return None,
+            },
+            "AF" => match *value {
+                vcf::variant::record_buf::samples::sample::value::Value::Array(
+                    vcf::variant::record_buf::samples::sample::value::Array::Float(af_values),
+                ) => match af_values.get(allele_no - 1).copied().flatten() {
+                    Some(af) => vcf::variant::record_buf::samples::sample::value::Value::Float(af),
+                    None => return Some(None),
+                },
+                vcf::variant::record_buf::samples::sample::value::Value::Float(af) => {
+                    vcf::variant::record_buf::samples::sample::value::Value::Float(*af)
+                }
+                _ => return None,
+            },
             "SQ" => {
                 // SQ is written as AD.
                 match *value {
@@ -282,190 +873,1041 @@ fn copy_format(
     Ok(builder.set_samples(genotypes))
 }
 
-/// Process the variants from `input_reader` to `output_writer`.
-async fn process_variants(
-    output_writer: &mut AsyncVcfWriter,
-    input_reader: &mut VariantReader,
-    output_header: &vcf::Header,
-    input_header: &vcf::Header,
-    id_mapping: &Option<indexmap::IndexMap<String, String>>,
-    args: &Args,
-) -> Result<(), anyhow::Error> {
-    // Open the frequency RocksDB database in read only mode.
-    tracing::info!("Opening frequency database");
-    let rocksdb_path = format!(
-        "{}/{}/seqvars/freqs/rocksdb",
-        &args.path_mehari_db,
-        path_component(args.genomebuild)
-    );
-    tracing::debug!("RocksDB path = {}", &rocksdb_path);
-    let options = rocksdb::Options::default();
-    let db_freq = rocksdb::DB::open_cf_for_read_only(
-        &options,
-        &rocksdb_path,
-        ["meta", "autosomal", "gonosomal", "mitochondrial"],
-        false,
-    )?;
-    let freq_anno = mehari::annotate::seqvars::FrequencyAnnotator::new(db_freq);
-
-    // Open the ClinVar RocksDB database in read only mode.
-    tracing::info!("Opening ClinVar database");
-    let rocksdb_path = format!(
-        "{}/{}/seqvars/clinvar/rocksdb",
-        &args.path_mehari_db,
-        path_component(args.genomebuild)
-    );
-    tracing::debug!("RocksDB path = {}", &rocksdb_path);
-    let options = rocksdb::Options::default();
-    let db_clinvar =
-        rocksdb::DB::open_cf_for_read_only(&options, &rocksdb_path, ["meta", "clinvar"], false)?;
-    let clinvar_anno = mehari::annotate::seqvars::ClinvarAnnotator::new(db_clinvar);
-
-    // Open the serialized transcripts.
-    tracing::info!("Opening transcript database");
-    let tx_db = mehari::annotate::seqvars::load_tx_db(format!(
-        "{}/{}/txs.bin.zst",
-        &args.path_mehari_db,
-        path_component(args.genomebuild)
-    ))?;
-    tracing::info!("Building transcript interval trees ...");
-    let assembly = if args.genomebuild == GenomeRelease::Grch37 {
-        biocommons_bioutils::assemblies::Assembly::Grch37p10
-    } else {
-        biocommons_bioutils::assemblies::Assembly::Grch38
-    };
-    let provider = Arc::new(MehariProvider::new(tx_db, assembly, Default::default()));
-    let predictor = mehari::annotate::seqvars::csq::ConsequencePredictor::new(
-        provider,
-        assembly,
-        Default::default(),
-    );
-    tracing::info!("... done building transcript interval trees");
-
-    // Build mapping from output sample index to input sample index.
-    let idx_output_to_input = {
-        let output_sample_to_idx = output_header
-            .sample_names()
-            .iter()
-            .enumerate()
-            .map(|(idx, name)| (name, idx))
-            .collect::<std::collections::HashMap<_, _>>();
-        let mut res = vec![usize::MAX; output_header.sample_names().len()];
-        for (input_idx, sample) in input_header.sample_names().iter().enumerate() {
-            let sample = if let Some(id_mapping) = id_mapping {
-                id_mapping.get(sample).expect("checked earlier")
-            } else {
-                sample
-            };
-            res[output_sample_to_idx[sample]] = input_idx;
+/// Set the called genotypes of `record_buf` failing the thresholds to no-call.
+///
+/// Genotypes lacking the value for a threshold pass the respective filter.  Returns
+/// the number of genotypes set to no-call and whether any sample still carries the
+/// alternative allele.
+fn filter_genotypes(
+    record_buf: &mut vcf::variant::RecordBuf,
+    min_gq: Option<i32>,
+    min_dp: Option<i32>,
+    min_ab: Option<f32>,
+) -> (usize, bool) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+    let keys = record_buf.samples().keys().clone();
+    let mut failed = 0;
+    let mut has_alt = false;
+    let mut values = Vec::new();
+    for sample in record_buf.samples().values() {
+        let get_int = |key: &str| match sample.get(key).flatten() {
+            Some(Value::Integer(value)) => Some(*value),
+            _ => None,
+        };
+        let gt = match sample.get("GT").flatten() {
+            Some(Value::String(gt)) => strip_gt_leading_slash(gt).to_string(),
+            _ => String::from("."),
+        };
+        let alleles = gt.split(['/', '|']).collect::<Vec<_>>();
+        let is_het = alleles.contains(&"0") && alleles.contains(&"1");
+        let ab = match sample.get("AD").flatten() {
+            Some(Value::Array(Array::Integer(ad))) => match ad.as_slice() {
+                [Some(ref_ad), Some(alt_ad)] if ref_ad + alt_ad > 0 => {
+                    Some(*alt_ad as f32 / (ref_ad + alt_ad) as f32)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let below = |value: Option<i32>, min: Option<i32>| matches!((value, min), (Some(value), Some(min)) if value < min);
+        let fails = !alleles.contains(&".")
+            && (below(get_int("GQ"), min_gq)
+                || below(get_int("DP"), min_dp)
+                || (is_het && matches!((ab, min_ab), (Some(ab), Some(min_ab)) if ab < min_ab)));
+
+        if fails {
+            failed += 1;
+        } else {
+            has_alt = has_alt || alleles.contains(&"1");
         }
-        res
-    };
-
-    // Read through input file, construct output records, and annotate these.
-    let start = std::time::Instant::now();
-    let mut prev = std::time::Instant::now();
-    let mut total_written = 0usize;
-    let known_format_keys = KNOWN_FORMAT_KEYS.get_or_init(Default::default);
-    let mut records = input_reader.records(input_header).await;
-    while let Some(input_record) = records.try_next().await? {
-        for (allele_no, alt_allele) in input_record.alternate_bases().as_ref().iter().enumerate() {
-            let allele_no = allele_no + 1;
-            // Construct record with first few fields describing one variant allele.
-            let builder = noodles::vcf::variant::RecordBuf::builder()
-                .set_reference_sequence_name(input_record.reference_sequence_name())
-                .set_variant_start(
-                    input_record
-                        .variant_start()
-                        .ok_or_else(|| anyhow::anyhow!("missing start position"))?,
-                )
-                .set_reference_bases(input_record.reference_bases())
-                .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
-                    alt_allele.clone(),
-                ]));
-
-            // Copy over the well-known FORMAT fields and construct output record.
-            let builder = copy_format(
-                &input_record,
-                builder,
-                &idx_output_to_input,
-                allele_no,
-                known_format_keys,
-            )?;
+        let no_call = if alleles.len() == 1 { "." } else { "./." };
+        values.push(
+            keys.as_ref()
+                .iter()
+                .map(|key| {
+                    if fails && key == "GT" {
+                        Some(Value::String(no_call.to_string()))
+                    } else {
+                        sample.get(key).flatten().cloned()
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
 
-            // Build the output `RecordBuf`.
-            let mut output_record = builder.build();
+    *record_buf.samples_mut() = vcf::variant::record_buf::samples::Samples::new(keys, values);
+    (failed, has_alt)
+}
 
-            // Obtain annonars variant key from current allele for RocksDB lookup.
-            let vcf_var = annonars::common::keys::Var::from_vcf_allele(&output_record, 0);
+/// Write haploid genotypes of `record_buf` in the PAR of chrX/chrY as diploid.
+///
+/// Some callers write haploid genotypes on the gonosomes for male samples, also in the
+/// PAR where two copies are present.  Returns the number of changed genotypes.
+fn diploidize_par_genotypes(
+    record_buf: &mut vcf::variant::RecordBuf,
+    genomebuild: GenomeRelease,
+) -> Result<usize, anyhow::Error> {
+    use vcf::variant::record_buf::samples::sample::value::Value;
+
+    let chrom: Chrom =
+        annonars::common::cli::canonicalize(record_buf.reference_sequence_name()).parse()?;
+    let start: usize = record_buf
+        .variant_start()
+        .ok_or_else(|| anyhow::anyhow!("missing start position"))?
+        .get();
+    if !crate::seqvars::aggregate::is_par(chrom, start, genomebuild) {
+        return Ok(0);
+    }
 
-            // Skip records with a deletion as alternative allele.
-            if vcf_var.alternative == "*" {
-                continue;
-            }
+    let keys = record_buf.samples().keys().clone();
+    let mut changed = 0;
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            keys.as_ref()
+                .iter()
+                .map(|key| match sample.get(key).flatten() {
+                    Some(Value::String(gt))
+                        if key == "GT" && !gt.contains(['/', '|']) && gt != "." =>
+                    {
+                        changed += 1;
+                        Some(Value::String(format!("{}/{}", gt, gt)))
+                    }
+                    value => value.cloned(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-            if prev.elapsed().as_secs() >= 60 {
-                tracing::info!("at {:?}", &vcf_var);
-                prev = std::time::Instant::now();
-            }
+    *record_buf.samples_mut() = vcf::variant::record_buf::samples::Samples::new(keys, values);
+    Ok(changed)
+}
 
-            // Only attempt lookups into RocksDB for canonical contigs.
-            if annonars::common::cli::is_canonical(vcf_var.chrom.as_str()) {
-                // Build key for RocksDB database from `vcf_var`.
-                let key: Vec<u8> = vcf_var.clone().into();
+/// Add the heteroplasmy fraction as `FORMAT/HF` to all samples of `record_buf`.
+///
+/// The fraction is computed from the alternative read count in `FORMAT/AD` and the
+/// total depth in `FORMAT/DP` and is missing if either is missing or the depth is zero.
+fn add_heteroplasmy_fraction(record_buf: &mut vcf::variant::RecordBuf) {
+    use vcf::variant::record_buf::samples::sample::value::{Array, Value};
 
-                // Annotate with frequency.
-                if mehari::annotate::seqvars::CHROM_AUTO.contains(vcf_var.chrom.as_str()) {
-                    freq_anno.annotate_record_auto(&key, &mut output_record)?;
-                } else if mehari::annotate::seqvars::CHROM_XY.contains(vcf_var.chrom.as_str()) {
-                    freq_anno.annotate_record_xy(&key, &mut output_record)?;
-                } else if mehari::annotate::seqvars::CHROM_MT.contains(vcf_var.chrom.as_str()) {
-                    freq_anno.annotate_record_mt(&key, &mut output_record)?;
+    let keys = record_buf
+        .samples()
+        .keys()
+        .as_ref()
+        .iter()
+        .filter(|key| *key != "HF")
+        .cloned()
+        .collect::<Vec<_>>();
+    let values = record_buf
+        .samples()
+        .values()
+        .map(|sample| {
+            let dp = match sample.get("DP").flatten() {
+                Some(Value::Integer(dp)) => Some(*dp),
+                _ => None,
+            };
+            let alt_ad = match sample.get("AD").flatten() {
+                Some(Value::Array(Array::Integer(ad))) => ad.get(1).copied().flatten(),
+                _ => None,
+            };
+            let hf = match (alt_ad, dp) {
+                (Some(alt_ad), Some(dp)) if dp > 0 => {
+                    Some(Value::Float((alt_ad as f32 / dp as f32).min(1.0)))
+                }
+                _ => None,
+            };
+            keys.iter()
+                .map(|key| sample.get(key).flatten().cloned())
+                .chain(std::iter::once(hf))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    *record_buf.samples_mut() = vcf::variant::record_buf::samples::Samples::new(
+        keys.into_iter()
+            .chain(std::iter::once("HF".to_string()))
+            .collect(),
+        values,
+    );
+}
+
+/// Load the sample renames from `old=new` values or @-prefixed paths to TSV files.
+fn load_sample_renames(
+    values: &[String],
+) -> Result<indexmap::IndexMap<String, String>, anyhow::Error> {
+    let mut result = indexmap::IndexMap::new();
+    let mut insert = |line: &str, sep: char| -> Result<(), anyhow::Error> {
+        let (old, new) = line
+            .split_once(sep)
+            .ok_or_else(|| anyhow::anyhow!("invalid sample rename: {:?}", line))?;
+        if result
+            .insert(old.trim().to_string(), new.trim().to_string())
+            .is_some()
+        {
+            anyhow::bail!("sample {:?} renamed more than once", old.trim());
+        }
+        Ok(())
+    };
+    for value in values {
+        if let Some(path) = value.strip_prefix('@') {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("could not read sample renames {}: {}", path, e))?;
+            for line in contents.lines() {
+                if !line.trim().is_empty() && !line.starts_with('#') {
+                    insert(line, '\t')?;
+                }
+            }
+        } else {
+            insert(value, '=')?;
+        }
+    }
+    Ok(result)
+}
+
+/// Compose the sample `renames` and the `id_mapping` into one mapping for all input samples.
+///
+/// The keys of `id_mapping` refer to the renamed samples.
+fn compose_sample_renames(
+    renames: &indexmap::IndexMap<String, String>,
+    id_mapping: Option<indexmap::IndexMap<String, String>>,
+    sample_names: &vcf::header::SampleNames,
+) -> Result<Option<indexmap::IndexMap<String, String>>, anyhow::Error> {
+    if renames.is_empty() {
+        return Ok(id_mapping);
+    }
+    if let Some(old) = renames.keys().find(|old| !sample_names.contains(*old)) {
+        anyhow::bail!("sample {} to rename not found in input", old);
+    }
+    sample_names
+        .iter()
+        .map(|name| {
+            let renamed = renames.get(name).unwrap_or(name);
+            let mapped = if let Some(id_mapping) = id_mapping.as_ref() {
+                id_mapping.get(renamed).ok_or_else(|| {
+                    anyhow::anyhow!("mapping given but sample {} not found", renamed)
+                })?
+            } else {
+                renamed
+            };
+            Ok((name.clone(), mapped.clone()))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Return the input samples to ingest given `keep` and `exclude` samples.
+fn subset_samples(
+    keep: &[String],
+    exclude: &[String],
+    sample_names: &vcf::header::SampleNames,
+) -> Result<vcf::header::SampleNames, anyhow::Error> {
+    if let Some(name) = keep
+        .iter()
+        .chain(exclude.iter())
+        .find(|name| !sample_names.contains(*name))
+    {
+        anyhow::bail!("sample {} to keep or exclude not found in input", name);
+    }
+    let result = sample_names
+        .iter()
+        .filter(|name| keep.is_empty() || keep.contains(*name))
+        .filter(|name| !exclude.contains(*name))
+        .cloned()
+        .collect::<vcf::header::SampleNames>();
+    if result.is_empty() {
+        anyhow::bail!("no samples left after applying --keep-samples/--exclude-samples");
+    }
+    Ok(result)
+}
+
+/// Restrict `pedigree` to one family, given by `family_id` or as the family of `samples`.
+///
+/// Pedigrees with only one family are returned unchanged.
+fn select_family(
+    pedigree: mehari::ped::PedigreeByName,
+    family_id: Option<&str>,
+    samples: &[String],
+) -> Result<mehari::ped::PedigreeByName, anyhow::Error> {
+    let families = pedigree
+        .individuals
+        .values()
+        .map(|individual| individual.family.clone())
+        .collect::<indexmap::IndexSet<_>>();
+    let family = if let Some(family_id) = family_id {
+        if !families.contains(family_id) {
+            anyhow::bail!("family {} not found in pedigree", family_id);
+        }
+        family_id.to_string()
+    } else if families.len() <= 1 {
+        return Ok(pedigree);
+    } else {
+        let sample_families = samples
+            .iter()
+            .filter_map(|sample| pedigree.individuals.get(sample))
+            .map(|individual| individual.family.clone())
+            .collect::<indexmap::IndexSet<_>>();
+        match sample_families.len() {
+            0 => anyhow::bail!("none of the input samples found in pedigree"),
+            1 => sample_families[0].clone(),
+            _ => anyhow::bail!(
+                "input samples belong to families {:?}, select one with --family-id",
+                &sample_families
+            ),
+        }
+    };
+    tracing::info!("using family {} of pedigree", &family);
+
+    Ok(mehari::ped::PedigreeByName {
+        individuals: pedigree
+            .individuals
+            .into_iter()
+            .filter(|(_, individual)| individual.family == family)
+            .collect(),
+    })
+}
+
+/// Result of processing one input record.
+enum Processed {
+    /// The annotated output records, one per alternate allele.
+    Records(Vec<vcf::variant::RecordBuf>),
+    /// The input record was rejected in the liftover.
+    Rejected(vcf::variant::RecordBuf),
+    /// The input record was skipped.
+    Skipped,
+}
+
+/// Mutable state and counters when processing input records.
+#[derive(Default)]
+struct ProcessorState {
+    /// Reference for trimming and left-alignment, with its own block cache.
+    reference: Option<normalize::Reference>,
+    /// Number of records with synthesized `FORMAT` fields, by key.
+    synthesized_keys: indexmap::IndexMap<String, usize>,
+    /// Number of records rejected in the liftover, by reason.
+    rejected: indexmap::IndexMap<liftover::RejectReason, usize>,
+    /// Number of records skipped, by unknown contig.
+    skipped_contigs: indexmap::IndexMap<String, usize>,
+    /// Number of genotypes set to no-call by the quality filters.
+    failed_genotypes: usize,
+    /// Number of output records dropped by the quality filters.
+    dropped_records: usize,
+    /// Number of haploid genotypes in the PAR written as diploid.
+    diploidized_genotypes: usize,
+    /// Audit log entries not written yet, if the audit log is enabled.
+    audit: Option<Vec<audit::Entry>>,
+}
+
+impl ProcessorState {
+    /// Create new state, opening the reference if given in `args`.
+    fn new(args: &Args) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            reference: args
+                .path_reference
+                .as_deref()
+                .map(normalize::Reference::from_path)
+                .transpose()?,
+            audit: args.path_audit.is_some().then(Vec::new),
+            ..Default::default()
+        })
+    }
+
+    /// Add the entry from `entry` to the audit log if enabled.
+    fn audit<F>(&mut self, entry: F)
+    where
+        F: FnOnce() -> audit::Entry,
+    {
+        if let Some(audit) = self.audit.as_mut() {
+            audit.push(entry());
+        }
+    }
+
+    /// Add the counters of `other` to `self`.
+    fn merge(&mut self, other: Self) {
+        for (key, count) in other.synthesized_keys {
+            *self.synthesized_keys.entry(key).or_default() += count;
+        }
+        for (reason, count) in other.rejected {
+            *self.rejected.entry(reason).or_default() += count;
+        }
+        for (contig, count) in other.skipped_contigs {
+            *self.skipped_contigs.entry(contig).or_default() += count;
+        }
+        self.failed_genotypes += other.failed_genotypes;
+        self.dropped_records += other.dropped_records;
+        self.diploidized_genotypes += other.diploidized_genotypes;
+        if let (Some(audit), Some(other_audit)) = (self.audit.as_mut(), other.audit) {
+            audit.extend(other_audit);
+        }
+    }
+
+    /// Log the counters.
+    fn log(&self) {
+        for (key, count) in &self.synthesized_keys {
+            tracing::warn!(
+                "synthesized FORMAT/{} for {} input records of unknown caller",
+                key,
+                count.separate_with_commas()
+            );
+        }
+        for (contig, count) in &self.skipped_contigs {
+            tracing::warn!(
+                "skipped {} input records on unknown contig {}",
+                count.separate_with_commas(),
+                contig
+            );
+        }
+        for (reason, count) in &self.rejected {
+            tracing::warn!(
+                "rejected {} input records in liftover: {}",
+                count.separate_with_commas(),
+                reason
+            );
+        }
+        if self.failed_genotypes > 0 {
+            tracing::info!(
+                "set {} genotypes failing the quality filters to no-call, dropped {} records",
+                self.failed_genotypes.separate_with_commas(),
+                self.dropped_records.separate_with_commas()
+            );
+        }
+        if self.diploidized_genotypes > 0 {
+            tracing::info!(
+                "wrote {} haploid genotypes in the PAR as diploid",
+                self.diploidized_genotypes.separate_with_commas()
+            );
+        }
+    }
+}
+
+/// Conversion of input records into annotated output records.
+struct Processor<'a> {
+    /// The command line arguments.
+    args: &'a Args,
+    /// The guessed variant caller of the input file.
+    variant_caller: Option<header::VariantCaller>,
+    /// The `FORMAT` keys to copy.
+    known_format_keys: KnownFormatKeys,
+    /// Mapping from output sample index to input sample index.
+    idx_output_to_input: Vec<usize>,
+    /// Index of the tumor sample in the input for somatic calls.
+    tumor_idx: Option<usize>,
+    /// Chains for the optional liftover.
+    chains: Option<liftover::Chains>,
+    /// Annotation with population frequencies.
+    freq_anno: mehari::annotate::seqvars::FrequencyAnnotator,
+    /// Annotation with ClinVar information.
+    clinvar_anno: mehari::annotate::seqvars::ClinvarAnnotator,
+    /// Optional annonars dbSNP database and metadata for the rsIDs.
+    dbsnp: Option<(
+        Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+        annonars::dbsnp::cli::query::Meta,
+    )>,
+    /// Prediction of the variant effects.
+    predictor: mehari::annotate::seqvars::csq::ConsequencePredictor,
+}
+
+impl<'a> Processor<'a> {
+    /// Open the databases and prepare the processing.
+    fn new(
+        output_header: &vcf::Header,
+        input_header: &vcf::Header,
+        id_mapping: &Option<indexmap::IndexMap<String, String>>,
+        args: &'a Args,
+    ) -> Result<Self, anyhow::Error> {
+        // Open the frequency RocksDB database in read only mode.
+        tracing::info!("Opening frequency database");
+        let rocksdb_path = format!(
+            "{}/{}/seqvars/freqs/rocksdb",
+            &args.path_mehari_db,
+            path_component(args.output_genomebuild())
+        );
+        tracing::debug!("RocksDB path = {}", &rocksdb_path);
+        let options = args.rocksdb.apply(
+            rocksdb::Options::default(),
+            crate::common::rocksdb_tuning::Profile::Query,
+        );
+        let db_freq = rocksdb::DB::open_cf_for_read_only(
+            &options,
+            &rocksdb_path,
+            ["meta", "autosomal", "gonosomal", "mitochondrial"],
+            false,
+        )?;
+        let freq_anno = mehari::annotate::seqvars::FrequencyAnnotator::new(db_freq);
+
+        // Open the ClinVar RocksDB database in read only mode.
+        tracing::info!("Opening ClinVar database");
+        let rocksdb_path = format!(
+            "{}/{}/seqvars/clinvar/rocksdb",
+            &args.path_mehari_db,
+            path_component(args.output_genomebuild())
+        );
+        tracing::debug!("RocksDB path = {}", &rocksdb_path);
+        let options = args.rocksdb.apply(
+            rocksdb::Options::default(),
+            crate::common::rocksdb_tuning::Profile::Query,
+        );
+        let db_clinvar = rocksdb::DB::open_cf_for_read_only(
+            &options,
+            &rocksdb_path,
+            ["meta", "clinvar"],
+            false,
+        )?;
+        let clinvar_anno = mehari::annotate::seqvars::ClinvarAnnotator::new(db_clinvar);
+
+        // Open the optional dbSNP RocksDB database in read only mode.
+        let dbsnp = args
+            .path_dbsnp
+            .as_ref()
+            .map(|path_dbsnp| {
+                tracing::info!("Opening dbSNP database");
+                annonars::dbsnp::cli::query::open_rocksdb(
+                    path_dbsnp,
+                    "dbsnp_data",
+                    "meta",
+                    "dbsnp_by_rsid",
+                )
+                .map_err(|e| {
+                    anyhow::anyhow!("problem opening dbSNP database at {}: {}", path_dbsnp, e)
+                })
+            })
+            .transpose()?;
+
+        // Open the serialized transcripts.
+        tracing::info!("Opening transcript database");
+        let tx_db = mehari::annotate::seqvars::load_tx_db(format!(
+            "{}/{}/txs.bin.zst",
+            &args.path_mehari_db,
+            path_component(args.output_genomebuild())
+        ))?;
+        tracing::info!("Building transcript interval trees ...");
+        let assembly = if args.output_genomebuild() == GenomeRelease::Grch37 {
+            biocommons_bioutils::assemblies::Assembly::Grch37p10
+        } else {
+            biocommons_bioutils::assemblies::Assembly::Grch38
+        };
+        let provider = Arc::new(MehariProvider::new(tx_db, assembly, Default::default()));
+        let predictor = mehari::annotate::seqvars::csq::ConsequencePredictor::new(
+            provider,
+            assembly,
+            Default::default(),
+        );
+        tracing::info!("... done building transcript interval trees");
+
+        // Build mapping from output sample index to input sample index.
+        let idx_output_to_input = {
+            let output_sample_to_idx = output_header
+                .sample_names()
+                .iter()
+                .enumerate()
+                .map(|(idx, name)| (name, idx))
+                .collect::<std::collections::HashMap<_, _>>();
+            let mut res = vec![usize::MAX; output_header.sample_names().len()];
+            for (input_idx, sample) in input_header.sample_names().iter().enumerate() {
+                let sample = if let Some(id_mapping) = id_mapping {
+                    id_mapping.get(sample)
                 } else {
-                    tracing::debug!(
-                        "Record @{:?} on non-canonical chromosome, skipping.",
-                        &vcf_var
-                    );
+                    Some(sample)
+                };
+                // Skip samples removed with `--keep-samples` or `--exclude-samples`.
+                if let Some(output_idx) = sample.and_then(|sample| output_sample_to_idx.get(sample))
+                {
+                    res[*output_idx] = input_idx;
+                }
+            }
+            res
+        };
+
+        let variant_caller = header::VariantCaller::guess(input_header);
+        let tumor_idx = variant_caller
+            .as_ref()
+            .and_then(|caller| caller.somatic_samples(input_header))
+            .and_then(|(tumor, _)| input_header.sample_names().get_index_of(&tumor));
+
+        let mut known_format_keys = if args.somatic {
+            KnownFormatKeys::with_af()
+        } else if args.keep_pl {
+            KnownFormatKeys::with_pl()
+        } else {
+            KnownFormatKeys::default()
+        };
+        if args.keep_dragen_annos {
+            known_format_keys.add_dragen();
+        }
+        let chains = if args.output_genomebuild() != args.genomebuild {
+            tracing::info!("Loading chain file for liftover");
+            Some(liftover::Chains::from_path(
+                args.path_chain.as_deref().expect("checked in run()"),
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            args,
+            variant_caller,
+            known_format_keys,
+            idx_output_to_input,
+            tumor_idx,
+            chains,
+            freq_anno,
+            clinvar_anno,
+            dbsnp,
+            predictor,
+        })
+    }
+
+    /// Convert `input_record` into annotated output records.
+    fn process(
+        &self,
+        mut input_record: vcf::variant::RecordBuf,
+        state: &mut ProcessorState,
+    ) -> Result<Processed, anyhow::Error> {
+        let args = self.args;
+        match &self.variant_caller {
+            Some(header::VariantCaller::Strelka2 { .. }) => {
+                normalize_strelka_format(&mut input_record)
+            }
+            Some(header::VariantCaller::Strelka2Somatic { .. }) => {
+                normalize_strelka_somatic_format(
+                    &mut input_record,
+                    self.tumor_idx.expect("checked in run()"),
+                )
+            }
+            Some(header::VariantCaller::Bcftools { .. }) => {
+                normalize_bcftools_format(&mut input_record)
+            }
+            Some(header::VariantCaller::FreeBayes { .. }) => {
+                normalize_freebayes_format(&mut input_record)
+            }
+            Some(header::VariantCaller::Clair3 { .. }) => {
+                normalize_long_read_format(&mut input_record)
+            }
+            None => {
+                let keys = normalize_generic_format(&mut input_record);
+                if !keys.is_empty() {
+                    state.audit(|| {
+                        audit::Entry::for_record(
+                            &input_record,
+                            audit::Action::Rewritten,
+                            audit::Reason::SynthesizedFormat,
+                        )
+                    });
+                }
+                for key in keys {
+                    *state.synthesized_keys.entry(key).or_default() += 1;
+                }
+            }
+            _ => (),
+        }
+        // Harmonize the contig name with the genome release or handle unknown contigs.
+        let chrom = input_record.reference_sequence_name();
+        if annonars::common::cli::is_canonical(chrom) {
+            *input_record.reference_sequence_name_mut() =
+                header::release_chrom(chrom, args.genomebuild);
+        } else if args.unknown_contigs == UnknownContigs::Error {
+            anyhow::bail!(
+                "record on unknown contig {} (use --unknown-contigs skip to skip)",
+                chrom
+            );
+        } else {
+            *state.skipped_contigs.entry(chrom.to_string()).or_default() += 1;
+            state.audit(|| {
+                audit::Entry::for_record(
+                    &input_record,
+                    audit::Action::Dropped,
+                    audit::Reason::UnknownContig,
+                )
+            });
+            return Ok(Processed::Skipped);
+        }
+        // Lift over the record to the target genome release if requested.
+        if let Some(chains) = self.chains.as_ref() {
+            let start = input_record
+                .variant_start()
+                .ok_or_else(|| anyhow::anyhow!("missing start position"))?
+                .get() as u64
+                - 1;
+            let end = start + input_record.reference_bases().len() as u64;
+            let lifted = match chains.lift(input_record.reference_sequence_name(), start, end) {
+                Ok(lifted) => {
+                    let chrom = header::release_chrom(&lifted.chrom, args.output_genomebuild());
+                    let pos = lifted.start as usize + 1;
+                    let reference_bases = if lifted.is_reverse {
+                        liftover::reverse_complement(input_record.reference_bases())
+                    } else {
+                        input_record.reference_bases().to_string()
+                    };
+                    // Check the reference allele against the reference of the target release.
+                    let reference = state.reference.as_mut().expect("checked in run()");
+                    if reference.matches(&chrom, pos, &reference_bases)? {
+                        Ok((chrom, pos, lifted.is_reverse))
+                    } else {
+                        Err(liftover::RejectReason::RefMismatch)
+                    }
+                }
+                Err(reason) => Err(reason),
+            };
+            match lifted {
+                Ok((chrom, pos, is_reverse)) => {
+                    *input_record.reference_sequence_name_mut() = chrom;
+                    *input_record.variant_start_mut() =
+                        Some(noodles::core::Position::try_from(pos)?);
+                    // Reverse-complement the alleles on the reverse strand; the anchor base of
+                    // indels is then the last base and moved to the front in the normalization.
+                    if is_reverse {
+                        let reference_bases = input_record.reference_bases_mut();
+                        *reference_bases = liftover::reverse_complement(reference_bases);
+                        for alt_allele in input_record.alternate_bases_mut().as_mut().iter_mut() {
+                            if !alt_allele.starts_with('<') {
+                                *alt_allele = liftover::reverse_complement(alt_allele);
+                            }
+                        }
+                    }
+                }
+                Err(reason) => {
+                    *state.rejected.entry(reason).or_default() += 1;
+                    state.audit(|| {
+                        audit::Entry::for_record(
+                            &input_record,
+                            audit::Action::Dropped,
+                            reason.into(),
+                        )
+                    });
+                    return Ok(Processed::Rejected(input_record));
+                }
+            }
+        }
+
+        let mut output_records = Vec::new();
+        for (allele_no, alt_allele) in input_record.alternate_bases().as_ref().iter().enumerate() {
+            let allele_no = allele_no + 1;
+            // Skip symbolic "any other allele" as written by bcftools and GATK in gVCF mode.
+            if ["<*>", "<X>", "<NON_REF>"].contains(&alt_allele.as_str()) {
+                state.audit(|| {
+                    audit::Entry::new(
+                        &input_record,
+                        alt_allele,
+                        audit::Action::Dropped,
+                        audit::Reason::SymbolicAllele,
+                    )
+                });
+                continue;
+            }
+            let mut allele = normalize::Allele {
+                pos: input_record
+                    .variant_start()
+                    .ok_or_else(|| anyhow::anyhow!("missing start position"))?
+                    .get(),
+                reference: input_record.reference_bases().to_string(),
+                alternative: alt_allele.clone(),
+            };
+            // Trim and left-align the allele if a reference has been given.
+            if let Some(reference) = state.reference.as_mut() {
+                if normalize::is_normalizable(&allele) {
+                    let chrom = input_record.reference_sequence_name();
+                    let normalized =
+                        normalize::normalize(&allele, |pos| reference.base(chrom, pos))?;
+                    if normalized != allele {
+                        state.audit(|| {
+                            audit::Entry::new(
+                                &input_record,
+                                alt_allele,
+                                audit::Action::Rewritten,
+                                audit::Reason::Normalized,
+                            )
+                        });
+                    }
+                    allele = normalized;
+                }
+            }
+            // Construct record with first few fields describing one variant allele.
+            let builder = noodles::vcf::variant::RecordBuf::builder()
+                .set_reference_sequence_name(input_record.reference_sequence_name())
+                .set_variant_start(noodles::core::Position::try_from(allele.pos)?)
+                .set_reference_bases(allele.reference)
+                .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                    allele.alternative,
+                ]))
+                .set_filters(input_record.filters().clone());
+
+            // Copy over the well-known FORMAT fields and construct output record.
+            let builder = copy_format(
+                &input_record,
+                builder,
+                &self.idx_output_to_input,
+                allele_no,
+                &self.known_format_keys,
+            )?;
+
+            // Build the output `RecordBuf`.
+            let mut output_record = builder.build();
+
+            // Copy over the DRAGEN-specific INFO fields under their namespaced keys.
+            if args.keep_dragen_annos {
+                for (key, output_key, _) in DRAGEN_INFO_KEYS {
+                    if let Some(Some(value)) = input_record.info().get(key) {
+                        output_record
+                            .info_mut()
+                            .insert(output_key.to_string(), Some(value.clone()));
+                    }
+                }
+            }
+
+            // Skip records with a deletion as alternative allele.
+            if annonars::common::keys::Var::from_vcf_allele(&output_record, 0).alternative == "*" {
+                state.audit(|| {
+                    audit::Entry::new(
+                        &input_record,
+                        alt_allele,
+                        audit::Action::Dropped,
+                        audit::Reason::SpanningDeletion,
+                    )
+                });
+                continue;
+            }
+
+            // Write haploid genotypes in the PAR as diploid.
+            let diploidized =
+                diploidize_par_genotypes(&mut output_record, args.output_genomebuild())?;
+            if diploidized > 0 {
+                state.diploidized_genotypes += diploidized;
+                state.audit(|| {
+                    audit::Entry::new(
+                        &input_record,
+                        alt_allele,
+                        audit::Action::Rewritten,
+                        audit::Reason::DiploidizedPar,
+                    )
+                });
+            }
+
+            // Apply the genotype quality filters.
+            if args.min_gq.is_some() || args.min_dp.is_some() || args.min_ab.is_some() {
+                let (failed, has_alt) =
+                    filter_genotypes(&mut output_record, args.min_gq, args.min_dp, args.min_ab);
+                state.failed_genotypes += failed;
+                if !has_alt && args.failed_genotypes == FailedGenotypes::Drop {
+                    state.dropped_records += 1;
+                    state.audit(|| {
+                        audit::Entry::new(
+                            &input_record,
+                            alt_allele,
+                            audit::Action::Dropped,
+                            audit::Reason::FailedGenotypes,
+                        )
+                    });
+                    continue;
+                } else if failed > 0 {
+                    state.audit(|| {
+                        audit::Entry::new(
+                            &input_record,
+                            alt_allele,
+                            audit::Action::Rewritten,
+                            audit::Reason::NoCallGenotypes,
+                        )
+                    });
                 }
+            }
+
+            // Write out the heteroplasmy fraction for chrMT variants.
+            if args.mt_heteroplasmy
+                && annonars::common::cli::canonicalize(output_record.reference_sequence_name())
+                    == "MT"
+            {
+                add_heteroplasmy_fraction(&mut output_record);
+            }
+
+            self.annotate(&mut output_record)?;
+            output_records.push(output_record);
+        }
+
+        Ok(Processed::Records(output_records))
+    }
 
-                // Annotate with ClinVar information.
-                clinvar_anno.annotate_record_clinvar(&key, &mut output_record)?;
+    /// Annotate `output_record` with frequencies, ClinVar, and variant effects.
+    fn annotate(&self, output_record: &mut vcf::variant::RecordBuf) -> Result<(), anyhow::Error> {
+        // Obtain annonars variant key from current allele for RocksDB lookup.
+        let vcf_var = annonars::common::keys::Var::from_vcf_allele(output_record, 0);
+
+        // Only attempt lookups into RocksDB for canonical contigs.
+        if annonars::common::cli::is_canonical(vcf_var.chrom.as_str()) {
+            // Build key for RocksDB database from `vcf_var`.
+            let key: Vec<u8> = vcf_var.clone().into();
+
+            // Annotate with frequency.
+            if mehari::annotate::seqvars::CHROM_AUTO.contains(vcf_var.chrom.as_str()) {
+                self.freq_anno.annotate_record_auto(&key, output_record)?;
+            } else if mehari::annotate::seqvars::CHROM_XY.contains(vcf_var.chrom.as_str()) {
+                self.freq_anno.annotate_record_xy(&key, output_record)?;
+            } else if mehari::annotate::seqvars::CHROM_MT.contains(vcf_var.chrom.as_str()) {
+                self.freq_anno.annotate_record_mt(&key, output_record)?;
+            } else {
+                tracing::debug!(
+                    "Record @{:?} on non-canonical chromosome, skipping.",
+                    &vcf_var
+                );
             }
 
-            let annonars::common::keys::Var {
-                chrom,
-                pos,
-                reference,
-                alternative,
-            } = vcf_var;
+            // Annotate with ClinVar information.
+            self.clinvar_anno
+                .annotate_record_clinvar(&key, output_record)?;
+
+            // Write the rsID from dbSNP to the `ID` column.
+            if let Some((db_dbsnp, meta_dbsnp)) = self.dbsnp.as_ref() {
+                let cf_data = db_dbsnp
+                    .cf_handle("dbsnp_data")
+                    .ok_or_else(|| anyhow::anyhow!("could not get dbsnp_data column family"))?;
+                let variant = annonars::common::spdi::Var::new(
+                    vcf_var.chrom.clone(),
+                    vcf_var.pos,
+                    vcf_var.reference.clone(),
+                    vcf_var.alternative.clone(),
+                );
+                if let Some(record) = annonars::dbsnp::cli::query::query_for_variant(
+                    &variant, meta_dbsnp, db_dbsnp, &cf_data,
+                )
+                .map_err(|e| anyhow::anyhow!("problem querying dbSNP database: {}", e))?
+                {
+                    output_record
+                        .ids_mut()
+                        .as_mut()
+                        .insert(format!("rs{}", record.rs_id));
+                }
+            }
+        }
 
-            // Annotate with variant effect.
-            if let Some(ann_fields) =
-                predictor.predict(&mehari::annotate::seqvars::csq::VcfVariant {
+        let annonars::common::keys::Var {
+            chrom,
+            pos,
+            reference,
+            alternative,
+        } = vcf_var;
+
+        // Annotate with variant effect.
+        if let Some(ann_fields) =
+            self.predictor
+                .predict(&mehari::annotate::seqvars::csq::VcfVariant {
                     chromosome: chrom,
                     position: pos,
                     reference,
                     alternative,
                 })?
-            {
-                if !ann_fields.is_empty() {
-                    output_record.info_mut().insert(
-                        "ANN".parse()?,
-                        Some(vcf::variant::record_buf::info::field::Value::Array(
-                            vcf::variant::record_buf::info::field::value::Array::String(
-                                ann_fields.iter().map(|ann| Some(ann.to_string())).collect(),
-                            ),
-                        )),
-                    );
-                }
+        {
+            if !ann_fields.is_empty() {
+                output_record.info_mut().insert(
+                    "ANN".parse()?,
+                    Some(vcf::variant::record_buf::info::field::Value::Array(
+                        vcf::variant::record_buf::info::field::value::Array::String(
+                            ann_fields.iter().map(|ann| Some(ann.to_string())).collect(),
+                        ),
+                    )),
+                );
             }
+        }
 
-            // Write out the record.
-            output_writer
-                .write_variant_record(output_header, &output_record)
-                .await?;
-            total_written += 1;
+        Ok(())
+    }
+}
+
+/// Destination of the output records.
+enum OutputSink {
+    /// Write all records to one file.
+    Single(VariantWriter),
+    /// Write the records to one file per chromosome.
+    Sharded(shards::Shards),
+}
+
+impl OutputSink {
+    /// Write the `record` given the `header`.
+    async fn write_variant_record(
+        &mut self,
+        header: &vcf::Header,
+        record: &vcf::variant::RecordBuf,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            OutputSink::Single(writer) => Ok(writer.write_variant_record(header, record).await?),
+            OutputSink::Sharded(shards) => shards.write_variant_record(record).await,
+        }
+    }
+}
+
+/// Process the variants from `input_reader` to `output_writer`.
+async fn process_variants(
+    output_writer: &mut OutputSink,
+    input_reader: &mut VariantReader,
+    output_header: &vcf::Header,
+    input_header: &vcf::Header,
+    id_mapping: &Option<indexmap::IndexMap<String, String>>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    let processor = Processor::new(output_header, input_header, id_mapping, args)?;
+    let mut state = ProcessorState::new(args)?;
+    let mut audit_writer = args
+        .path_audit
+        .as_deref()
+        .map(audit::Writer::from_path)
+        .transpose()?;
+    let mut rejects_writer = if let Some(path_rejects) = args.path_rejects.as_deref() {
+        let mut writer = open_variant_writer(path_rejects).await?;
+        writer
+            .write_header(input_header)
+            .await
+            .map_err(|e| anyhow::anyhow!("problem writing rejects header: {}", e))?;
+        Some(writer)
+    } else {
+        None
+    };
+
+    // Records moved by left-alignment are written after sorting by coordinate.
+    let mut sort_stage = if args.sorts_output() {
+        Some(sort::Stage::new()?)
+    } else {
+        None
+    };
+
+    // Read through input file, construct output records, and annotate these.
+    let start = std::time::Instant::now();
+    let mut prev = std::time::Instant::now();
+    let mut total_written = 0usize;
+    let mut records = input_reader.records(input_header).await;
+    while let Some(input_record) = records.try_next().await? {
+        if prev.elapsed().as_secs() >= 60 {
+            tracing::info!(
+                "at {}:{:?}",
+                input_record.reference_sequence_name(),
+                input_record.variant_start()
+            );
+            prev = std::time::Instant::now();
+        }
+
+        match processor.process(input_record, &mut state)? {
+            Processed::Records(output_records) => {
+                for output_record in output_records {
+                    if let Some(sort_stage) = sort_stage.as_mut() {
+                        sort_stage.push(output_header, &output_record)?;
+                    } else {
+                        output_writer
+                            .write_variant_record(output_header, &output_record)
+                            .await?;
+                    }
+                    total_written += 1;
+                }
+            }
+            Processed::Rejected(input_record) => {
+                if let Some(rejects_writer) = rejects_writer.as_mut() {
+                    rejects_writer
+                        .write_variant_record(input_header, &input_record)
+                        .await?;
+                }
+            }
+            Processed::Skipped => (),
+        }
+        if let (Some(audit_writer), Some(audit)) = (audit_writer.as_mut(), state.audit.as_mut()) {
+            audit_writer.write(audit.drain(..))?;
         }
+
         if let Some(max_var_count) = args.max_var_count {
             if total_written >= max_var_count {
                 tracing::warn!(
@@ -481,18 +1923,176 @@ async fn process_variants(
         total_written.separate_with_commas(),
         start.elapsed()
     );
+    if let Some(mut sort_stage) = sort_stage {
+        tracing::info!("sorting output records by coordinate...");
+        for output_record in sort_stage.sorted(output_header)? {
+            output_writer
+                .write_variant_record(output_header, &output_record?)
+                .await?;
+        }
+        tracing::info!("... done sorting output records");
+    }
+    state.log();
+    if let Some(rejects_writer) = rejects_writer {
+        rejects_writer.shutdown().await?;
+    }
+    if let Some(mut audit_writer) = audit_writer {
+        audit_writer.flush()?;
+    }
 
     Ok(())
 }
 
-/// Main entry point for `seqvars ingest` sub command.
-pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
-    let before_anything = std::time::Instant::now();
-    tracing::info!("args_common = {:#?}", &args_common);
-    tracing::info!("args = {:#?}", &args);
+/// The BGZF end-of-file marker block.
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Process the contigs of the tabix-indexed input file in parallel.
+///
+/// Each contig is written to a block-gzipped segment and the segments are appended
+/// in the order of the index to `path_out` which already contains the header.
+fn process_variants_parallel(
+    path_out: &str,
+    output_header: &vcf::Header,
+    input_header: &vcf::Header,
+    id_mapping: &Option<indexmap::IndexMap<String, String>>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    use noodles::vcf::variant::io::Write as _;
+    use rayon::prelude::*;
+    use std::io::Write as _;
+
+    let index = noodles::tabix::fs::read(format!("{}.tbi", &args.path_in))
+        .map_err(|e| anyhow::anyhow!("could not read tabix index of {}: {}", &args.path_in, e))?;
+    let contigs = index
+        .header()
+        .ok_or_else(|| anyhow::anyhow!("tabix index of {} has no header", &args.path_in))?
+        .reference_sequence_names()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let processor = Processor::new(output_header, input_header, id_mapping, args)?;
+    let tmpdir = tempfile::TempDir::new()?;
+    let segment_path = |idx: usize| tmpdir.path().join(format!("segment-{}.vcf.gz", idx));
+
+    let start = std::time::Instant::now();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.num_threads)
+        .build()?;
+    let states = pool.install(|| {
+        contigs
+            .par_iter()
+            .enumerate()
+            .map(|(idx, contig)| -> Result<ProcessorState, anyhow::Error> {
+                tracing::info!("processing contig {}", contig);
+                let mut state = ProcessorState::new(args)?;
+                let mut reader =
+                    vcf::io::indexed_reader::Builder::default().build_from_path(&args.path_in)?;
+                let mut writer = vcf::io::Writer::new(noodles::bgzf::Writer::new(
+                    std::fs::File::create(segment_path(idx))?,
+                ));
+                let mut sort_stage = if args.sorts_output() {
+                    Some(sort::Stage::new()?)
+                } else {
+                    None
+                };
+                let region = noodles::core::Region::new(contig.as_str(), ..);
+                for result in reader.query(input_header, &region)? {
+                    let input_record =
+                        vcf::variant::RecordBuf::try_from_variant_record(input_header, &result?)?;
+                    if let Processed::Records(output_records) =
+                        processor.process(input_record, &mut state)?
+                    {
+                        for output_record in output_records {
+                            if let Some(sort_stage) = sort_stage.as_mut() {
+                                sort_stage.push(output_header, &output_record)?;
+                            } else {
+                                writer.write_variant_record(output_header, &output_record)?;
+                            }
+                        }
+                    }
+                }
+                if let Some(mut sort_stage) = sort_stage {
+                    for output_record in sort_stage.sorted(output_header)? {
+                        writer.write_variant_record(output_header, &output_record?)?;
+                    }
+                }
+                writer.into_inner().finish()?;
+                Ok(state)
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    // Replace the end-of-file marker of the header by the segments.
+    let mut output_file = std::fs::OpenOptions::new().append(true).open(path_out)?;
+    let len = output_file.metadata()?.len();
+    if std::fs::read(path_out)?.ends_with(&BGZF_EOF) {
+        output_file.set_len(len - BGZF_EOF.len() as u64)?;
+    }
+    for idx in 0..contigs.len() {
+        let segment = std::fs::read(segment_path(idx))?;
+        output_file.write_all(segment.strip_suffix(&BGZF_EOF).unwrap_or(&segment))?;
+    }
+    output_file.write_all(&BGZF_EOF)?;
+    output_file.flush()?;
+
+    tracing::info!(
+        "... annotated {} contigs in {:?}",
+        contigs.len().separate_with_commas(),
+        start.elapsed()
+    );
+    let mut total_state = ProcessorState {
+        audit: args.path_audit.is_some().then(Vec::new),
+        ..Default::default()
+    };
+    for state in states {
+        total_state.merge(state);
+    }
+    total_state.log();
+    if let (Some(path_audit), Some(audit)) = (args.path_audit.as_deref(), total_state.audit) {
+        let mut audit_writer = audit::Writer::from_path(path_audit)?;
+        audit_writer.write(audit)?;
+        audit_writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Main entry point for `seqvars ingest` sub command.
+pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    let before_anything = std::time::Instant::now();
+    tracing::info!("args_common = {:#?}", &args_common);
+    tracing::info!("args = {:#?}", &args);
 
     common::trace_rss_now();
 
+    if args.output_genomebuild() != args.genomebuild && args.path_chain.is_none() {
+        anyhow::bail!("--path-chain is required for liftover with --target-genome-release");
+    }
+    if args.output_genomebuild() != args.genomebuild && args.path_reference.is_none() {
+        anyhow::bail!("--path-reference is required for liftover with --target-genome-release");
+    }
+    if args.sex_check && args.path_in == "-" {
+        anyhow::bail!("--sex-check cannot be used when reading from stdin");
+    }
+    if args.shard_by_chrom && (args.path_out == "-" || args.num_threads > 1) {
+        anyhow::bail!("--shard-by-chrom cannot be used with stdout or --num-threads");
+    }
+    if args.num_threads > 1 {
+        if args.path_in == "-" || !args.path_out.ends_with(".vcf.gz") {
+            anyhow::bail!("--num-threads requires indexed input file and .vcf.gz output file");
+        }
+        if args.max_var_count.is_some() || args.path_rejects.is_some() {
+            anyhow::bail!("--num-threads cannot be used with --max-var-count or --path-rejects");
+        }
+        if args.output_genomebuild() != args.genomebuild {
+            anyhow::bail!("--num-threads cannot be used with --target-genome-release");
+        }
+    }
+
     tracing::info!("loading pedigree...");
     let pedigree = mehari::ped::PedigreeByName::from_path(&args.path_ped)
         .map_err(|e| anyhow::anyhow!("problem parsing PED file: {}", e))?;
@@ -553,16 +2153,211 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         .read_header()
         .await
         .map_err(|e| anyhow::anyhow!("problem reading VCF header: {}", e))?;
-    let output_header = header::build_output_header(
-        &input_header,
+    let id_mapping = compose_sample_renames(
+        &load_sample_renames(&args.sample_rename)?,
+        id_mapping,
+        input_header.sample_names(),
+    )?;
+    let kept_samples = subset_samples(
+        &args.keep_samples,
+        &args.exclude_samples,
+        input_header.sample_names(),
+    )?;
+    let mapped_name = |name: &String| {
+        id_mapping
+            .as_ref()
+            .and_then(|id_mapping| id_mapping.get(name))
+            .unwrap_or(name)
+            .clone()
+    };
+    let pedigree = select_family(
+        pedigree,
+        args.family_id.as_deref(),
+        &kept_samples.iter().map(mapped_name).collect::<Vec<_>>(),
+    )?;
+    // Do not ingest the samples of other families, e.g., in a joint VCF file.
+    let kept_samples = if args.family_id.is_some() {
+        let kept_samples = kept_samples
+            .into_iter()
+            .filter(|name| pedigree.individuals.contains_key(&mapped_name(name)))
+            .collect::<vcf::header::SampleNames>();
+        if kept_samples.is_empty() {
+            anyhow::bail!("no input samples of family in pedigree");
+        }
+        kept_samples
+    } else {
+        kept_samples
+    };
+    let id_mapping = id_mapping.map(|id_mapping| {
+        id_mapping
+            .into_iter()
+            .filter(|(name, _)| kept_samples.contains(name))
+            .collect::<indexmap::IndexMap<_, _>>()
+    });
+    // Check that the somatic mode matches the variant caller.
+    let somatic_samples = match header::VariantCaller::guess(&input_header) {
+        Some(caller) if caller.is_somatic() => {
+            if !args.somatic {
+                anyhow::bail!(
+                    "input file has somatic calls of {:?}, use --somatic",
+                    caller
+                );
+            }
+            let (tumor, normal) = caller
+                .somatic_samples(&input_header)
+                .ok_or_else(|| anyhow::anyhow!("could not determine tumor sample from header"))?;
+            if !kept_samples.contains(&tumor) {
+                anyhow::bail!("tumor sample {} not found in ingested samples", &tumor);
+            }
+            Some((tumor, normal.filter(|normal| kept_samples.contains(normal))))
+        }
+        _ if args.somatic => {
+            anyhow::bail!("--somatic requires calls of Mutect2 or Strelka2 in somatic mode")
+        }
+        _ => None,
+    };
+    // The output header is built from the input header restricted to the kept samples.
+    let mut kept_header = input_header.clone();
+    *kept_header.sample_names_mut() = kept_samples.clone();
+
+    let sex_check_results = if args.sex_check {
+        tracing::info!("checking sex consistency...");
+        let results = sex_check::compute(&args.path_in, args.genomebuild)
+            .await?
+            .results(&pedigree, &id_mapping, &kept_samples)?;
+        if let Some(path_sex_check_json) = args.path_sex_check_json.as_ref() {
+            tracing::info!("Writing sex check results to {}", path_sex_check_json);
+            let file = std::fs::File::create(path_sex_check_json).map_err(|e| {
+                anyhow::anyhow!("could not create file {}: {}", path_sex_check_json, e)
+            })?;
+            serde_json::to_writer_pretty(std::io::BufWriter::new(file), &results)
+                .map_err(|e| anyhow::anyhow!("could not write sex check results: {}", e))?;
+        }
+        results
+    } else {
+        Vec::new()
+    };
+
+    let mut output_header = header::build_output_header(
+        &kept_header,
         &Some(pedigree),
         &id_mapping,
-        args.genomebuild,
+        args.output_genomebuild(),
         &args.file_date,
         &args.case_uuid,
         worker_version(),
+        args.allow_unknown_caller,
     )
     .map_err(|e| anyhow::anyhow!("problem building output header: {}", e))?;
+    for result in sex_check_results
+        .iter()
+        .filter(|result| !result.is_consistent)
+    {
+        use vcf::header::record::value::{map::Other, Map};
+
+        tracing::warn!(
+            "sex of {} is {} in pedigree but genotypes suggest {}",
+            &result.sample,
+            &result.pedigree_sex,
+            result.inferred_sex
+        );
+        let fmt_ratio = |ratio: Option<f64>| {
+            ratio
+                .map(|ratio| format!("{:.3}", ratio))
+                .unwrap_or_else(|| ".".to_string())
+        };
+        output_header.insert(
+            "x-varfish-sex-check-warning".parse()?,
+            vcf::header::record::Value::Map(
+                result.sample.clone(),
+                Map::<Other>::builder()
+                    .insert("PedigreeSex".parse()?, result.pedigree_sex.clone())
+                    .insert("InferredSex".parse()?, result.inferred_sex.to_string())
+                    .insert("XHetRatio".parse()?, fmt_ratio(result.x_het_ratio))
+                    .insert(
+                        "YCoverageRatio".parse()?,
+                        fmt_ratio(result.y_coverage_ratio),
+                    )
+                    .build()?,
+            ),
+        )?;
+    }
+    if args.keep_pl {
+        output_header.formats_mut().insert(
+            "PL".to_string(),
+            vcf::header::record::value::Map::<vcf::header::record::value::map::Format>::from("PL"),
+        );
+    }
+    if args.mt_heteroplasmy {
+        use vcf::header::record::value::map::{format, Format};
+
+        output_header.formats_mut().insert(
+            "HF".to_string(),
+            vcf::header::record::value::Map::<Format>::new(
+                format::Number::Count(1),
+                format::Type::Float,
+                "Heteroplasmy fraction of the alternative allele on chrMT",
+            ),
+        );
+    }
+    if args.keep_dragen_annos {
+        use vcf::header::record::value::map::{format, info, Format, Info};
+        use vcf::header::record::value::Map;
+
+        for (_, output_key, description) in DRAGEN_INFO_KEYS {
+            output_header.infos_mut().insert(
+                output_key.to_string(),
+                Map::<Info>::new(info::Number::Count(1), info::Type::Float, *description),
+            );
+        }
+        for (key, output_key) in DRAGEN_FORMAT_KEYS {
+            output_header.formats_mut().insert(
+                output_key.to_string(),
+                Map::<Format>::new(
+                    format::Number::ReferenceAlternateBases,
+                    format::Type::Integer,
+                    format!(
+                        "DRAGEN: count of reads in {} pair orientation supporting each allele",
+                        key
+                    ),
+                ),
+            );
+        }
+        for (id, filter) in input_header.filters() {
+            if id.starts_with("DRAGEN") {
+                output_header
+                    .filters_mut()
+                    .insert(id.clone(), filter.clone());
+            }
+        }
+    }
+    if let Some((tumor, normal)) = somatic_samples.as_ref() {
+        use vcf::header::record::value::map::{format, Format};
+
+        output_header.formats_mut().insert(
+            "AF".to_string(),
+            vcf::header::record::value::Map::<Format>::new(
+                format::Number::Count(1),
+                format::Type::Float,
+                "Allele fraction of the alternative allele",
+            ),
+        );
+        for (key, name) in [
+            ("x-varfish-tumor-sample", Some(tumor)),
+            ("x-varfish-normal-sample", normal.as_ref()),
+        ] {
+            if let Some(name) = name {
+                let name = id_mapping
+                    .as_ref()
+                    .and_then(|id_mapping| id_mapping.get(name))
+                    .unwrap_or(name);
+                output_header.insert(
+                    key.parse()?,
+                    vcf::header::record::Value::from(name.as_str()),
+                )?;
+            }
+        }
+    }
 
     // Work around glnexus issue with RNC.
     if let Some(format) = input_header.formats_mut().get_mut("RNC") {
@@ -570,62 +2365,625 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         *format.type_mut() = vcf::header::record::value::map::format::Type::String;
     }
 
+    if args.shard_by_chrom {
+        let mut output_sink =
+            OutputSink::Sharded(shards::Shards::new(&args.path_out, &output_header)?);
+        process_variants(
+            &mut output_sink,
+            &mut input_reader,
+            &output_header,
+            &input_header,
+            &id_mapping,
+            args,
+        )
+        .await?;
+        if let OutputSink::Sharded(shards) = output_sink {
+            let manifest = shards.finish(!args.no_index).await?;
+            tracing::info!("wrote {} per-chromosome files", manifest.shards.len());
+        }
+
+        tracing::info!(
+            "All of `seqvars ingest` completed in {:?}",
+            before_anything.elapsed()
+        );
+        return Ok(());
+    }
+
     // Use output file helper.
     let out_path_helper = crate::common::s3::OutputPathHelper::new(&args.path_out)?;
 
     {
-        let mut output_writer = open_vcf_writer(out_path_helper.path_out()).await?;
+        let mut output_writer = open_variant_writer(out_path_helper.path_out()).await?;
         output_writer
             .write_header(&output_header)
             .await
             .map_err(|e| anyhow::anyhow!("problem writing header: {}", e))?;
 
-        process_variants(
-            &mut output_writer,
-            &mut input_reader,
-            &output_header,
-            &input_header,
-            &id_mapping,
-            args,
-        )
-        .await?;
+        if args.num_threads > 1 {
+            output_writer.shutdown().await?;
+
+            process_variants_parallel(
+                out_path_helper.path_out(),
+                &output_header,
+                &input_header,
+                &id_mapping,
+                args,
+            )?;
+        } else {
+            let mut output_sink = OutputSink::Single(output_writer);
+            process_variants(
+                &mut output_sink,
+                &mut input_reader,
+                &output_header,
+                &input_header,
+                &id_mapping,
+                args,
+            )
+            .await?;
+
+            if let OutputSink::Single(output_writer) = output_sink {
+                output_writer.shutdown().await?;
+            }
+        }
+    }
+
+    if !args.no_index {
+        out_path_helper.create_index().await?;
+    }
+    out_path_helper.upload_for_s3().await?;
+    out_path_helper.copy_to_stdout().await?;
+
+    tracing::info!(
+        "All of `seqvars ingest` completed in {:?}",
+        before_anything.elapsed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use rstest::rstest;
+
+    use crate::common::GenomeRelease;
+
+    #[rstest]
+    #[case::clair3_glnexus("tests/seqvars/ingest/clair3_glnexus.vcf")]
+    #[case::dragen_07_021_624_3_10_4("tests/seqvars/ingest/example_dragen.07.021.624.3.10.4.vcf")]
+    #[case::dragen_07_021_624_3_10_9("tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf")]
+    #[case::gatk_hc_3_7("tests/seqvars/ingest/example_gatk_hc.3.7-0.vcf")]
+    #[case::gatk_hc_4_4_0_0("tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf")]
+    #[case::strelka2_2_9_10("tests/seqvars/ingest/example_strelka2.2.9.10.vcf")]
+    #[case::bcftools_1_17("tests/seqvars/ingest/example_bcftools.1.17.vcf")]
+    #[case::freebayes_1_0_2("tests/seqvars/ingest/example_freebayes.1.0.2.vcf")]
+    #[case::clair3_1_0_4("tests/seqvars/ingest/example_clair3.1.0.4.vcf")]
+    #[case::sentieon_dnascope_202308("tests/seqvars/ingest/example_sentieon_dnascope.202308.vcf")]
+    #[case::dragen_na12787("tests/seqvars/ingest/NA12878_dragen.vcf")]
+    #[case::gatk_hc_case_1("tests/seqvars/ingest/Case_1.vcf")]
+    #[case::chr_prefix("tests/seqvars/ingest/example_chr_prefix.vcf")]
+    #[tokio::test]
+    async fn result_snapshot_test(#[case] path: &str) -> Result<(), anyhow::Error> {
+        mehari::common::set_snapshot_suffix!(
+            "{}",
+            path.split('/').last().unwrap().replace('.', "_")
+        );
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+
+        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_snapshot_test_unknown_caller() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/example_unknown_caller.vcf";
+
+        let args_common = Default::default();
+        let mut args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        assert!(super::run(&args_common, &args).await.is_err());
+
+        args.allow_unknown_caller = true;
+        super::run(&args_common, &args).await?;
+
+        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_snapshot_test_keep_samples() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/Case_1.vcf";
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".father_only.ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: vec!["Case_1_father-N1-DNA1-WGS1".into()],
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+
+        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_snapshot_test_keep_dragen_annos() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf";
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: true,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+
+        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_multi_family_ped() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/Case_1.vcf";
+
+        let args_common = Default::default();
+        let mut args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".multi_family.ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+        let output = std::fs::read_to_string(&args.path_out)?;
+
+        // Explicitly selecting the family yields the same result.
+        args.family_id = Some("FAM".into());
+        super::run(&args_common, &args).await?;
+        assert_eq!(std::fs::read_to_string(&args.path_out)?, output);
+
+        // No input samples in the other family.
+        args.family_id = Some("FAM2".into());
+        assert!(super::run(&args_common, &args).await.is_err());
+
+        insta::assert_snapshot!(output);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_unknown_contigs_error() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/example_chr_prefix.vcf";
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Error,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        let err = super::run(&args_common, &args).await.unwrap_err();
+
+        assert!(err.to_string().contains("hs37d5"), "{}", err);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_audit_log() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/example_chr_prefix.vcf";
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: Some(
+                tmpdir
+                    .join("audit.tsv")
+                    .to_str()
+                    .expect("invalid path")
+                    .into(),
+            ),
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
 
-        flush_and_shutdown!(output_writer);
-    }
+        assert_eq!(
+            std::fs::read_to_string(tmpdir.join("audit.tsv"))?,
+            "chrom\tpos\tref\talt\taction\treason\nhs37d5\t100\tA\tG\tdropped\tunknown-contig\n"
+        );
 
-    out_path_helper.create_tbi_for_bgzf().await?;
-    out_path_helper.upload_for_s3().await?;
+        Ok(())
+    }
 
-    tracing::info!(
-        "All of `seqvars ingest` completed in {:?}",
-        before_anything.elapsed()
-    );
-    Ok(())
-}
+    /// A deletion left-aligned with `--path-reference` crosses the preceding SNV and
+    /// the output is sorted again.
+    #[tokio::test]
+    async fn result_left_aligned_indel_sorted() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        // The deletion of one `A` from the homopolymer left-aligns to position 3.
+        std::fs::write(tmpdir.join("ref.fa"), ">1\nGACAAAAATGCATGCATGCA\n")?;
+        std::fs::write(tmpdir.join("ref.fa.fai"), "1\t20\t3\t20\t21\n")?;
+        std::fs::write(
+            tmpdir.join("in.vcf"),
+            "##fileformat=VCFv4.2\n\
+            ##FILTER=<ID=PASS,Description=\"All filters passed\">\n\
+            ##contig=<ID=1,length=249250621>\n\
+            ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+            ##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"Allelic depths\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tCASE\n\
+            1\t5\t.\tA\tG\t50\t.\t.\tGT:AD\t0/1:10,10\n\
+            1\t7\t.\tAA\tA\t50\t.\t.\tGT:AD\t0/1:10,10\n",
+        )?;
+        let path = |name: &str| {
+            tmpdir
+                .join(name)
+                .to_str()
+                .expect("invalid path")
+                .to_string()
+        };
 
-#[cfg(test)]
-mod test {
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: "tests/seqvars/ingest/example_unknown_caller.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path("in.vcf"),
+            path_out: path("out.vcf"),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: true,
+            path_reference: Some(path("ref.fa")),
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
 
-    use rstest::rstest;
+        let output = std::fs::read_to_string(&args.path_out)?;
+        let variants = output
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split('\t').take(5).collect::<Vec<_>>().join("\t"))
+            .collect::<Vec<_>>();
+        assert_eq!(variants, vec!["1\t3\t.\tCA\tC", "1\t5\t.\tA\tG"]);
 
-    use crate::common::GenomeRelease;
+        Ok(())
+    }
 
-    #[rstest]
-    #[case::clair3_glnexus("tests/seqvars/ingest/clair3_glnexus.vcf")]
-    #[case::dragen_07_021_624_3_10_4("tests/seqvars/ingest/example_dragen.07.021.624.3.10.4.vcf")]
-    #[case::dragen_07_021_624_3_10_9("tests/seqvars/ingest/example_dragen.07.021.624.3.10.9.vcf")]
-    #[case::gatk_hc_3_7("tests/seqvars/ingest/example_gatk_hc.3.7-0.vcf")]
-    #[case::gatk_hc_4_4_0_0("tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf")]
-    #[case::dragen_na12787("tests/seqvars/ingest/NA12878_dragen.vcf")]
-    #[case::gatk_hc_case_1("tests/seqvars/ingest/Case_1.vcf")]
     #[tokio::test]
-    async fn result_snapshot_test(#[case] path: &str) -> Result<(), anyhow::Error> {
-        mehari::common::set_snapshot_suffix!(
-            "{}",
-            path.split('/').last().unwrap().replace('.', "_")
-        );
+    async fn result_liftover_sorted() -> Result<(), anyhow::Error> {
+        use std::io::Read as _;
 
         let tmpdir = temp_testdir::TempDir::default();
+        // Swap chr1:100-200 and chr2:100-200 to chr2:1000-1100 and chr1:500-600.
+        std::fs::write(
+            tmpdir.join("chain.txt"),
+            "chain 1000 chr1 1000 + 100 200 chr2 2000 + 1000 1100 1\n100\n\n\
+            chain 1000 chr2 1000 + 100 200 chr1 1000 + 500 600 2\n100\n",
+        )?;
+        // The target reference consists of `A` only.
+        let line = format!("{}\n", "A".repeat(100));
+        std::fs::write(
+            tmpdir.join("ref.fa"),
+            format!(">chr1\n{}>chr2\n{}", line.repeat(10), line.repeat(20)),
+        )?;
+        std::fs::write(
+            tmpdir.join("ref.fa.fai"),
+            "chr1\t1000\t6\t100\t101\nchr2\t2000\t1022\t100\t101\n",
+        )?;
+        std::fs::write(
+            tmpdir.join("in.vcf"),
+            "##fileformat=VCFv4.2\n\
+            ##FILTER=<ID=PASS,Description=\"All filters passed\">\n\
+            ##contig=<ID=1,length=249250621>\n\
+            ##contig=<ID=2,length=243199373>\n\
+            ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+            ##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"Allelic depths\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tCASE\n\
+            1\t151\t.\tA\tG\t50\t.\t.\tGT:AD\t0/1:10,10\n\
+            1\t161\t.\tC\tT\t50\t.\t.\tGT:AD\t0/1:10,10\n\
+            2\t151\t.\tA\tG\t50\t.\t.\tGT:AD\t0/1:10,10\n",
+        )?;
+        let path = |name: &str| {
+            tmpdir
+                .join(name)
+                .to_str()
+                .expect("invalid path")
+                .to_string()
+        };
 
         let args_common = Default::default();
         let args = super::Args {
@@ -633,19 +2991,153 @@ mod test {
             case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
             max_var_count: None,
             path_mehari_db: "tests/seqvars/ingest/db".into(),
-            path_ped: path.replace(".vcf", ".ped"),
+            path_ped: "tests/seqvars/ingest/example_unknown_caller.ped".into(),
             genomebuild: GenomeRelease::Grch37,
-            path_in: path.into(),
-            path_out: tmpdir
-                .join("out.vcf")
+            path_in: path("in.vcf"),
+            path_out: path("out.vcf.gz"),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: true,
+            path_reference: Some(path("ref.fa")),
+            target_genome_release: Some(GenomeRelease::Grch38),
+            path_chain: Some(path("chain.txt")),
+            path_rejects: Some(path("rejects.vcf")),
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+
+        let variants = |output: &str| {
+            output
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .map(|line| line.split('\t').take(5).collect::<Vec<_>>().join("\t"))
+                .collect::<Vec<_>>()
+        };
+        let mut output = String::new();
+        noodles::bgzf::Reader::new(std::fs::File::open(&args.path_out)?)
+            .read_to_string(&mut output)?;
+        // The lifted records are sorted, the record from chr2 is lifted to chr1 and comes first.
+        assert_eq!(
+            variants(&output),
+            vec!["chr1\t551\t.\tA\tG", "chr2\t1051\t.\tA\tG"]
+        );
+        assert!(std::path::Path::new(&format!("{}.tbi", &args.path_out)).exists());
+        // The record with the mismatching reference allele is rejected.
+        assert_eq!(
+            variants(&std::fs::read_to_string(path("rejects.vcf"))?),
+            vec!["1\t161\t.\tC\tT"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_liftover_reverse_strand() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        // Map chr1:1-100 to the reverse strand of chr1:1-100.
+        std::fs::write(
+            tmpdir.join("chain.txt"),
+            "chain 1000 chr1 1000 + 0 100 chr1 100 - 0 100 1\n100\n",
+        )?;
+        std::fs::write(
+            tmpdir.join("ref.fa"),
+            ">chr1\nTCTCGCCCTCCGAGCCGTTCGAGAGGCTCGCCCCGGGTGGGGCTCCTTGCACAGG\
+            TCGGGACCGTGTCTTGAGTGTTGCTGGTCATCATATCCTGATCGT\n",
+        )?;
+        std::fs::write(tmpdir.join("ref.fa.fai"), "chr1\t100\t6\t100\t101\n")?;
+        std::fs::write(
+            tmpdir.join("in.vcf"),
+            "##fileformat=VCFv4.2\n\
+            ##FILTER=<ID=PASS,Description=\"All filters passed\">\n\
+            ##contig=<ID=1,length=249250621>\n\
+            ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+            ##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"Allelic depths\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tCASE\n\
+            1\t11\t.\tT\tC\t50\t.\t.\tGT:AD\t0/1:10,10\n\
+            1\t21\t.\tAG\tA\t50\t.\t.\tGT:AD\t0/1:10,10\n",
+        )?;
+        let path = |name: &str| {
+            tmpdir
+                .join(name)
                 .to_str()
                 .expect("invalid path")
-                .into(),
+                .to_string()
+        };
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: "tests/seqvars/ingest/example_unknown_caller.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path("in.vcf"),
+            path_out: path("out.vcf"),
             id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: true,
+            path_reference: Some(path("ref.fa")),
+            target_genome_release: Some(GenomeRelease::Grch38),
+            path_chain: Some(path("chain.txt")),
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
         };
         super::run(&args_common, &args).await?;
 
-        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+        // The alleles are reverse-complemented and the deletion is anchored on the left again.
+        let output = std::fs::read_to_string(&args.path_out)?;
+        let variants = output
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split('\t').take(5).collect::<Vec<_>>().join("\t"))
+            .collect::<Vec<_>>();
+        assert_eq!(variants, vec!["chr1\t78\t.\tGC\tG", "chr1\t90\t.\tA\tG"]);
+
+        // The reference allele cannot be checked without the target reference.
+        let args = super::Args {
+            path_reference: None,
+            ..args
+        };
+        assert!(super::run(&args_common, &args).await.is_err());
 
         Ok(())
     }
@@ -672,6 +3164,32 @@ mod test {
             path_in,
             path_out,
             id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
         };
         super::run(&args_common, &args).await?;
 
@@ -679,6 +3197,256 @@ mod test {
         hxdmp::hexdump(&crate::common::read_to_bytes(&args.path_out)?, &mut buffer)?;
         insta::assert_snapshot!(String::from_utf8_lossy(&buffer));
 
+        // The TBI index is written next to the output unless disabled.
+        assert!(std::path::Path::new(&format!("{}.tbi", &args.path_out)).exists());
+        let args = super::Args {
+            path_out: tmpdir
+                .join("out-no-index.vcf.gz")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            no_index: true,
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+        assert!(!std::path::Path::new(&format!("{}.tbi", &args.path_out)).exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_shard_by_chrom() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/Case_1.vcf";
+
+        let args_common = Default::default();
+        let mut args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: String::new(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        args.path_out = tmpdir
+            .join("out.vcf")
+            .to_str()
+            .expect("invalid path")
+            .into();
+        super::run(&args_common, &args).await?;
+        let mut expected = indexmap::IndexMap::<String, usize>::new();
+        for line in std::fs::read_to_string(&args.path_out)?.lines() {
+            if let Some((chrom, _)) = line.split_once('\t').filter(|_| !line.starts_with('#')) {
+                *expected.entry(chrom.to_string()).or_default() += 1;
+            }
+        }
+
+        args.path_out = tmpdir.join("shards").to_str().expect("invalid path").into();
+        args.shard_by_chrom = true;
+        super::run(&args_common, &args).await?;
+        let manifest: super::shards::Manifest = serde_json::from_str(&std::fs::read_to_string(
+            tmpdir.join("shards").join(super::shards::MANIFEST_NAME),
+        )?)?;
+
+        assert_eq!(
+            manifest
+                .shards
+                .iter()
+                .map(|shard| (shard.chrom.clone(), shard.records))
+                .collect::<indexmap::IndexMap<_, _>>(),
+            expected
+        );
+        for shard in &manifest.shards {
+            let path_shard = tmpdir.join("shards").join(&shard.path);
+            assert!(path_shard.exists());
+            assert!(std::path::Path::new(&format!("{}.tbi", path_shard.display())).exists());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_bcf_equals_vcf() -> Result<(), anyhow::Error> {
+        use futures::TryStreamExt as _;
+        use mehari::common::noodles::NoodlesVariantReader as _;
+
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "tests/seqvars/ingest/example_gatk_hc.4.4.0.0.vcf";
+
+        let args_common = Default::default();
+        let mut args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: path.replace(".vcf", ".ped"),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: path.into(),
+            path_out: String::new(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        let mut outputs = Vec::new();
+        for name in ["out.vcf", "out.bcf"] {
+            args.path_out = tmpdir.join(name).to_str().expect("invalid path").into();
+            super::run(&args_common, &args).await?;
+
+            // Copy the file such that BCF is detected by the magic bytes.
+            if name.ends_with(".bcf") {
+                assert!(std::path::Path::new(&format!("{}.csi", &args.path_out)).exists());
+            }
+
+            let path_copy = tmpdir.join(format!("{}.copy", name));
+            std::fs::copy(&args.path_out, &path_copy)?;
+            let path_copy = path_copy.to_str().expect("invalid path");
+            assert_eq!(
+                crate::common::noodles::is_bcf(path_copy),
+                name.ends_with(".bcf")
+            );
+
+            let mut reader = crate::common::noodles::open_vcf_reader(path_copy).await?;
+            let header = reader.read_header().await?;
+            let records = reader
+                .records(&header)
+                .await
+                .map_ok(|record| {
+                    format!(
+                        "{}:{:?}:{}:{:?}",
+                        record.reference_sequence_name(),
+                        record.variant_start(),
+                        record.reference_bases(),
+                        record.alternate_bases()
+                    )
+                })
+                .try_collect::<Vec<_>>()
+                .await?;
+            outputs.push(records);
+        }
+
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(outputs[0].len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn result_parallel_equals_sequential() -> Result<(), anyhow::Error> {
+        use std::io::Read as _;
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let path_in: String = "tests/seqvars/ingest/NA12878_dragen.vcf.gz".into();
+        let path_ped = path_in.replace(".vcf.gz", ".ped");
+        let args_common = Default::default();
+        let mut args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped,
+            genomebuild: GenomeRelease::Grch37,
+            path_in,
+            path_out: String::new(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        let mut outputs = Vec::new();
+        for num_threads in [1, 2] {
+            args.num_threads = num_threads;
+            args.path_out = tmpdir
+                .join(format!("out-{}.vcf.gz", num_threads))
+                .to_str()
+                .expect("invalid path")
+                .into();
+            super::run(&args_common, &args).await?;
+
+            let mut output = String::new();
+            noodles::bgzf::Reader::new(std::fs::File::open(&args.path_out)?)
+                .read_to_string(&mut output)?;
+            outputs.push(output);
+        }
+
+        assert_eq!(outputs[0], outputs[1]);
+
         Ok(())
     }
 
@@ -745,6 +3513,32 @@ mod test {
                 "#
                 .to_string(),
             ),
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: None,
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
         };
         super::run(&args_common, &args).await?;
 
@@ -752,4 +3546,430 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::hom_alt(&[Some(370), Some(235), Some(0)], Some(99))]
+    #[case::het(&[Some(40), Some(0), Some(25)], Some(25))]
+    #[case::missing(&[Some(0), None, None], None)]
+    fn gq_from_pl(#[case] pl_values: &[Option<i32>], #[case] expected: Option<i32>) {
+        assert_eq!(super::gq_from_pl(pl_values), expected);
+    }
+
+    #[rstest]
+    #[case::snv(&["C", "T"], Some(vec![Some(10), Some(5)]))]
+    #[case::multiallelic(&["C", "T", "A"], Some(vec![Some(10), Some(5), Some(1)]))]
+    #[case::indel(&["CA", "C"], None)]
+    fn ad_from_base_counts(#[case] alleles: &[&str], #[case] expected: Option<Vec<Option<i32>>>) {
+        let alleles = alleles.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let base_count = |key: &str| match key {
+            "AU" => Some(1),
+            "CU" => Some(10),
+            "GU" => None,
+            "TU" => Some(5),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(super::ad_from_base_counts(&alleles, base_count), expected);
+    }
+
+    #[rstest]
+    #[case::snv("C", "T", "DP:AU:CU:GU:TU", "ref", ["0/0", "0/1"], [vec![Some(60), Some(0)], vec![Some(42), Some(18)]])]
+    #[case::indel("CA", "C", "DP:TAR:TIR", "het", ["0/1", "0/1"], [vec![Some(50), Some(10)], vec![Some(42), Some(18)]])]
+    fn normalize_strelka_somatic_format(
+        #[case] reference: &str,
+        #[case] alternative: &str,
+        #[case] format: &str,
+        #[case] nt: &str,
+        #[case] expected_gts: [&str; 2],
+        #[case] expected_ads: [Vec<Option<i32>>; 2],
+    ) -> Result<(), anyhow::Error> {
+        use noodles::vcf::variant::record_buf::samples::sample::value::{Array, Value};
+
+        let sample = |ref_count: i32, alt_count: i32| match format {
+            "DP:TAR:TIR" => format!("60:{},{}:{},{}", ref_count, ref_count, alt_count, alt_count),
+            _ => format!(
+                "60:0,0:{},{}:0,0:{},{}",
+                ref_count, ref_count, alt_count, alt_count
+            ),
+        };
+        let (normal, tumor) = match nt {
+            "ref" => (sample(60, 0), sample(42, 18)),
+            _ => (sample(50, 10), sample(42, 18)),
+        };
+        let line = format!(
+            "17\t41256074\t.\t{}\t{}\t.\tPASS\tSOMATIC;NT={}\t{}\t{}\t{}",
+            reference, alternative, nt, format, normal, tumor
+        );
+        let header: noodles::vcf::Header = "##fileformat=VCFv4.1\n\
+            ##INFO=<ID=SOMATIC,Number=0,Type=Flag,Description=\"Somatic mutation\">\n\
+            ##INFO=<ID=NT,Number=1,Type=String,Description=\"Genotype of the normal\">\n\
+            ##FORMAT=<ID=DP,Number=1,Type=Integer,Description=\"Read depth\">\n\
+            ##FORMAT=<ID=AU,Number=2,Type=Integer,Description=\"A alleles\">\n\
+            ##FORMAT=<ID=CU,Number=2,Type=Integer,Description=\"C alleles\">\n\
+            ##FORMAT=<ID=GU,Number=2,Type=Integer,Description=\"G alleles\">\n\
+            ##FORMAT=<ID=TU,Number=2,Type=Integer,Description=\"T alleles\">\n\
+            ##FORMAT=<ID=TAR,Number=2,Type=Integer,Description=\"Reads of ref\">\n\
+            ##FORMAT=<ID=TIR,Number=2,Type=Integer,Description=\"Reads of indel\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tNORMAL\tTUMOR\n"
+            .parse()?;
+        let mut reader = noodles::vcf::io::Reader::new(line.as_bytes());
+        let mut record = noodles::vcf::variant::RecordBuf::default();
+        reader.read_record_buf(&header, &mut record)?;
+
+        super::normalize_strelka_somatic_format(&mut record, 1);
+
+        for (sample, (expected_gt, expected_ad)) in record
+            .samples()
+            .values()
+            .zip(expected_gts.into_iter().zip(expected_ads))
+        {
+            match sample.get("GT").flatten() {
+                Some(Value::Genotype(gt)) => {
+                    assert_eq!(
+                        crate::common::genotype_to_string(gt)?,
+                        format!("/{}", expected_gt)
+                    )
+                }
+                _ => panic!("missing FORMAT/GT"),
+            }
+            assert_eq!(
+                sample.get("AD").flatten(),
+                Some(&Value::Array(Array::Integer(expected_ad)))
+            );
+        }
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::biallelic(vec![Some(30), Some(0), Some(300)], 1, vec![Some(30), Some(0), Some(300)])]
+    #[case::second_alt(
+        vec![Some(50), Some(20), Some(200), Some(10), Some(0), Some(100)],
+        2,
+        vec![Some(40), Some(0), Some(90)]
+    )]
+    #[case::missing(vec![Some(10), None], 1, vec![Some(0), None, None])]
+    fn subset_pl(
+        #[case] pl_values: Vec<Option<i32>>,
+        #[case] allele_no: usize,
+        #[case] expected: Vec<Option<i32>>,
+    ) {
+        assert_eq!(super::subset_pl(&pl_values, allele_no), expected);
+    }
+
+    #[test]
+    fn load_sample_renames() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_tsv = tmpdir.join("renames.tsv");
+        std::fs::write(&path_tsv, "# old\tnew\nS2\tfather\nS3\tmother\n")?;
+
+        let renames = super::load_sample_renames(&[
+            "S1=index".to_string(),
+            format!("@{}", path_tsv.display()),
+        ])?;
+
+        assert_eq!(
+            renames.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("S1".to_string(), "index".to_string()),
+                ("S2".to_string(), "father".to_string()),
+                ("S3".to_string(), "mother".to_string()),
+            ]
+        );
+        assert!(super::load_sample_renames(&["S1".to_string()]).is_err());
+        assert!(super::load_sample_renames(&["S1=a".to_string(), "S1=b".to_string()]).is_err());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::renames_only(None, Some(vec![("S1", "index"), ("S2", "S2")]))]
+    #[case::with_id_mapping(
+        Some(vec![("index", "ID1"), ("S2", "ID2")]),
+        Some(vec![("S1", "ID1"), ("S2", "ID2")])
+    )]
+    fn compose_sample_renames(
+        #[case] id_mapping: Option<Vec<(&str, &str)>>,
+        #[case] expected: Option<Vec<(&str, &str)>>,
+    ) -> Result<(), anyhow::Error> {
+        let to_map = |pairs: Vec<(&str, &str)>| {
+            pairs
+                .into_iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect::<indexmap::IndexMap<_, _>>()
+        };
+        let sample_names = ["S1", "S2"].into_iter().map(String::from).collect();
+        let renames = to_map(vec![("S1", "index")]);
+
+        let result =
+            super::compose_sample_renames(&renames, id_mapping.map(to_map), &sample_names)?;
+
+        assert_eq!(result, expected.map(to_map));
+        assert!(
+            super::compose_sample_renames(&to_map(vec![("S3", "index")]), None, &sample_names)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::passes(None, None, None, vec!["0/1", "1/1", "./."], 0, true)]
+    #[case::min_gq(Some(30), None, None, vec!["./.", "1/1", "./."], 1, true)]
+    #[case::min_dp(None, Some(15), None, vec!["./.", "./.", "./."], 2, false)]
+    #[case::min_ab(None, None, Some(0.3), vec!["./.", "1/1", "./."], 1, true)]
+    fn filter_genotypes(
+        #[case] min_gq: Option<i32>,
+        #[case] min_dp: Option<i32>,
+        #[case] min_ab: Option<f32>,
+        #[case] expected_gts: Vec<&str>,
+        #[case] expected_failed: usize,
+        #[case] expected_has_alt: bool,
+    ) -> Result<(), anyhow::Error> {
+        use noodles::vcf::variant::record_buf::samples::{
+            sample::value::{Array, Value},
+            Samples,
+        };
+
+        // Heterozygous with GQ=20, DP=10, AB=0.2; hom. alt. with GQ=40, DP=12; no-call.
+        let sample = |gt: &str, gq: i32, dp: i32, ad: [i32; 2]| {
+            vec![
+                Some(Value::String(gt.to_string())),
+                Some(Value::Integer(gq)),
+                Some(Value::Integer(dp)),
+                Some(Value::Array(Array::Integer(ad.map(Some).to_vec()))),
+            ]
+        };
+        let mut record = noodles::vcf::variant::RecordBuf::builder()
+            .set_samples(Samples::new(
+                ["GT", "GQ", "DP", "AD"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                vec![
+                    sample("0/1", 20, 10, [8, 2]),
+                    sample("1/1", 40, 12, [0, 12]),
+                    sample("./.", 0, 0, [0, 0]),
+                ],
+            ))
+            .build();
+
+        let (failed, has_alt) = super::filter_genotypes(&mut record, min_gq, min_dp, min_ab);
+
+        let gts = record
+            .samples()
+            .values()
+            .map(|sample| match sample.get("GT").flatten() {
+                Some(Value::String(gt)) => gt.clone(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gts, expected_gts);
+        assert_eq!(failed, expected_failed);
+        assert_eq!(has_alt, expected_has_alt);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::par1_grch37("X", 100_000, GenomeRelease::Grch37, vec!["1/1", "0/0", "0/1", "."], 2)]
+    #[case::par1_grch38("chrY", 20_000, GenomeRelease::Grch38, vec!["1/1", "0/0", "0/1", "."], 2)]
+    #[case::non_par("X", 5_000_000, GenomeRelease::Grch37, vec!["1", "0", "0/1", "."], 0)]
+    #[case::autosome("1", 100_000, GenomeRelease::Grch37, vec!["1", "0", "0/1", "."], 0)]
+    fn diploidize_par_genotypes(
+        #[case] chrom: &str,
+        #[case] pos: usize,
+        #[case] genomebuild: GenomeRelease,
+        #[case] expected_gts: Vec<&str>,
+        #[case] expected_changed: usize,
+    ) -> Result<(), anyhow::Error> {
+        use noodles::vcf::variant::record_buf::samples::{sample::value::Value, Samples};
+
+        let mut record = noodles::vcf::variant::RecordBuf::builder()
+            .set_reference_sequence_name(chrom)
+            .set_variant_start(noodles::core::Position::try_from(pos)?)
+            .set_samples(Samples::new(
+                ["GT"].into_iter().map(String::from).collect(),
+                ["1", "0", "0/1", "."]
+                    .into_iter()
+                    .map(|gt| vec![Some(Value::String(gt.to_string()))])
+                    .collect(),
+            ))
+            .build();
+
+        let changed = super::diploidize_par_genotypes(&mut record, genomebuild)?;
+
+        let gts = record
+            .samples()
+            .values()
+            .map(|sample| match sample.get("GT").flatten() {
+                Some(Value::String(gt)) => gt.clone(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gts, expected_gts);
+        assert_eq!(changed, expected_changed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_heteroplasmy_fraction() {
+        use noodles::vcf::variant::record_buf::samples::{
+            sample::value::{Array, Value},
+            Samples,
+        };
+
+        let sample = |dp: Option<i32>, ad: Option<[i32; 2]>| {
+            vec![
+                Some(Value::String("0/1".to_string())),
+                dp.map(Value::Integer),
+                ad.map(|ad| Value::Array(Array::Integer(ad.map(Some).to_vec()))),
+            ]
+        };
+        let mut record = noodles::vcf::variant::RecordBuf::builder()
+            .set_samples(Samples::new(
+                ["GT", "DP", "AD"].into_iter().map(String::from).collect(),
+                vec![
+                    sample(Some(200), Some([150, 50])),
+                    sample(Some(0), Some([0, 0])),
+                    sample(None, Some([10, 10])),
+                ],
+            ))
+            .build();
+
+        super::add_heteroplasmy_fraction(&mut record);
+
+        let hfs = record
+            .samples()
+            .values()
+            .map(|sample| match sample.get("HF").flatten() {
+                Some(Value::Float(hf)) => Some(*hf),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(hfs, vec![Some(0.25), None, None]);
+        assert_eq!(record.samples().keys().as_ref().len(), 4);
+    }
+
+    #[rstest]
+    #[case::all(&[], &[], Some(vec!["S1", "S2", "S3"]))]
+    #[case::keep(&["S3", "S1"], &[], Some(vec!["S1", "S3"]))]
+    #[case::exclude(&[], &["S2"], Some(vec!["S1", "S3"]))]
+    #[case::unknown(&["S4"], &[], None)]
+    #[case::none_left(&[], &["S1", "S2", "S3"], None)]
+    fn subset_samples(
+        #[case] keep: &[&str],
+        #[case] exclude: &[&str],
+        #[case] expected: Option<Vec<&str>>,
+    ) {
+        let to_vec = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let sample_names = ["S1", "S2", "S3"].into_iter().map(String::from).collect();
+
+        let result = super::subset_samples(&to_vec(keep), &to_vec(exclude), &sample_names);
+
+        assert_eq!(
+            result
+                .ok()
+                .map(|names| names.into_iter().collect::<Vec<_>>()),
+            expected.map(|names| to_vec(&names))
+        );
+    }
+
+    #[tokio::test]
+    async fn result_dbsnp_ids() -> Result<(), anyhow::Error> {
+        use clap::Parser as _;
+        use std::io::Write as _;
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        // Build a minimal dbSNP database containing only the first variant of `Case_1.vcf`.
+        let path_dbsnp_vcf = tmpdir.join("dbsnp.vcf.gz");
+        let mut writer = noodles::bgzf::Writer::new(std::fs::File::create(&path_dbsnp_vcf)?);
+        writer.write_all(
+            b"##fileformat=VCFv4.2\n\
+              ##reference=GRCh37.p13\n\
+              ##dbSNP_BUILD_ID=156\n\
+              ##INFO=<ID=RS,Number=1,Type=Integer,Description=\"dbSNP ID\">\n\
+              ##contig=<ID=17,length=81195210>\n\
+              #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+              17\t41249263\trs1799965\tG\tA\t.\t.\tRS=1799965\n",
+        )?;
+        writer.finish()?;
+        crate::common::noodles::build_tbi(&path_dbsnp_vcf, tmpdir.join("dbsnp.vcf.gz.tbi")).await?;
+        let path_dbsnp = tmpdir.join("dbsnp.db");
+        annonars::dbsnp::cli::import::run(
+            &annonars::common::cli::Args::parse_from(["annonars"]),
+            &annonars::dbsnp::cli::import::Args {
+                genome_release: annonars::common::cli::GenomeRelease::Grch37,
+                path_in_vcf: path_dbsnp_vcf.to_str().expect("invalid path").into(),
+                path_out_rocksdb: path_dbsnp.to_str().expect("invalid path").into(),
+                tbi_window_size: 1_000_000,
+                cf_name: "dbsnp_data".into(),
+                cf_name_by_rsid: "dbsnp_by_rsid".into(),
+                path_wal_dir: None,
+            },
+        )?;
+
+        let args_common = Default::default();
+        let args = super::Args {
+            file_date: String::from("20230421"),
+            case_uuid: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap(),
+            max_var_count: None,
+            path_mehari_db: "tests/seqvars/ingest/db".into(),
+            path_ped: "tests/seqvars/ingest/Case_1.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_in: "tests/seqvars/ingest/Case_1.vcf".into(),
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            id_mapping: None,
+            keep_pl: false,
+            allow_unknown_caller: false,
+            path_reference: None,
+            target_genome_release: None,
+            path_chain: None,
+            path_rejects: None,
+            sex_check: false,
+            path_sex_check_json: None,
+            unknown_contigs: super::UnknownContigs::Skip,
+            sample_rename: Vec::new(),
+            keep_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+            num_threads: 1,
+            no_index: false,
+            min_gq: None,
+            min_dp: None,
+            min_ab: None,
+            failed_genotypes: super::FailedGenotypes::NoCall,
+            family_id: None,
+            somatic: false,
+            keep_dragen_annos: false,
+            mt_heteroplasmy: false,
+            path_dbsnp: Some(path_dbsnp.to_str().expect("invalid path").into()),
+            path_audit: None,
+            shard_by_chrom: false,
+            rocksdb: Default::default(),
+        };
+        super::run(&args_common, &args).await?;
+
+        let output = std::fs::read_to_string(&args.path_out)?;
+        let ids = output
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                (fields[1].to_string(), fields[2].to_string())
+            })
+            .collect::<Vec<_>>();
+        assert!(ids.contains(&("41249263".into(), "rs1799965".into())));
+        assert!(ids
+            .iter()
+            .filter(|(pos, _)| pos != "41249263")
+            .all(|(_, id)| id == "."));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,184 @@
+//! Normalization of variant alleles against the reference sequence.
+//!
+//! Implements the algorithm from Tan et al. (2015) as used by `vt normalize`: the
+//! alleles are trimmed on the right, extended to the left with the reference if
+//! they become empty, and finally trimmed on the left.  This yields the same
+//! representation as used for the annonars frequency keys.
+
+/// Size of the blocks that are read from the reference FASTA file.
+const BLOCK_SIZE: u64 = 65_536;
+
+/// Access to an indexed reference FASTA file with a simple block cache.
+pub struct Reference {
+    /// The indexed FASTA reader.
+    reader: bio::io::fasta::IndexedReader<std::fs::File>,
+    /// Length of the sequences, by name.
+    lengths: std::collections::HashMap<String, u64>,
+    /// The currently cached block as chromosome, 0-based start, and sequence.
+    block: Option<(String, u64, Vec<u8>)>,
+}
+
+impl Reference {
+    /// Open the reference FASTA file at `path`, the `.fai` index must exist.
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        let reader = bio::io::fasta::IndexedReader::from_file(&path)
+            .map_err(|e| anyhow::anyhow!("could not open reference {}: {}", path, e))?;
+        let lengths = reader
+            .index
+            .sequences()
+            .into_iter()
+            .map(|sequence| (sequence.name, sequence.len))
+            .collect();
+        Ok(Self {
+            reader,
+            lengths,
+            block: None,
+        })
+    }
+
+    /// Return the upper-case base at the 1-based position `pos` on `chrom`.
+    pub fn base(&mut self, chrom: &str, pos: usize) -> Result<u8, anyhow::Error> {
+        let offset = pos as u64 - 1;
+        let block_start = offset - offset % BLOCK_SIZE;
+        let is_cached = matches!(
+            &self.block,
+            Some((block_chrom, start, _)) if block_chrom == chrom && *start == block_start
+        );
+        if !is_cached {
+            // The last block of the sequence is shorter.
+            let block_end = self
+                .lengths
+                .get(chrom)
+                .map(|len| (block_start + BLOCK_SIZE).min(*len))
+                .unwrap_or(block_start + BLOCK_SIZE);
+            let mut seq = Vec::new();
+            self.reader
+                .fetch(chrom, block_start, block_end)
+                .and_then(|_| self.reader.read(&mut seq))
+                .map_err(|e| {
+                    anyhow::anyhow!("could not read reference at {}:{}: {}", chrom, pos, e)
+                })?;
+            self.block = Some((chrom.to_string(), block_start, seq));
+        }
+        let (_, _, seq) = self.block.as_ref().expect("block was just set");
+        seq.get((offset - block_start) as usize)
+            .map(|base| base.to_ascii_uppercase())
+            .ok_or_else(|| anyhow::anyhow!("position {}:{} outside of reference", chrom, pos))
+    }
+
+    /// Return whether `bases` match the reference at the 1-based position `pos` on
+    /// `chrom`, `N` matches any base.
+    pub fn matches(&mut self, chrom: &str, pos: usize, bases: &str) -> Result<bool, anyhow::Error> {
+        for (offset, base) in bases.bytes().map(|b| b.to_ascii_uppercase()).enumerate() {
+            let reference_base = self.base(chrom, pos + offset)?;
+            if base != b'N' && reference_base != b'N' && base != reference_base {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// A variant allele with 1-based position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allele {
+    /// 1-based position of the first reference base.
+    pub pos: usize,
+    /// Reference bases.
+    pub reference: String,
+    /// Alternative bases.
+    pub alternative: String,
+}
+
+/// Return whether the allele consists of bases only and can be normalized.
+pub fn is_normalizable(allele: &Allele) -> bool {
+    let is_bases = |s: &str| !s.is_empty() && s.bytes().all(|b| b"ACGTNacgtn".contains(&b));
+    is_bases(&allele.reference)
+        && is_bases(&allele.alternative)
+        && allele.reference != allele.alternative
+}
+
+/// Trim and left-align `allele`, `base` returns the reference base at a 1-based position.
+pub fn normalize<F>(allele: &Allele, mut base: F) -> Result<Allele, anyhow::Error>
+where
+    F: FnMut(usize) -> Result<u8, anyhow::Error>,
+{
+    let mut pos = allele.pos;
+    let mut reference = allele.reference.to_ascii_uppercase().into_bytes();
+    let mut alternative = allele.alternative.to_ascii_uppercase().into_bytes();
+
+    // Trim on the right and extend to the left until nothing changes anymore.
+    loop {
+        let mut changed = false;
+        if !reference.is_empty()
+            && !alternative.is_empty()
+            && reference.last() == alternative.last()
+        {
+            reference.pop();
+            alternative.pop();
+            changed = true;
+        }
+        if (reference.is_empty() || alternative.is_empty()) && pos > 1 {
+            pos -= 1;
+            let prev = base(pos)?;
+            reference.insert(0, prev);
+            alternative.insert(0, prev);
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Trim on the left while keeping at least one base.
+    while reference.len() > 1 && alternative.len() > 1 && reference[0] == alternative[0] {
+        reference.remove(0);
+        alternative.remove(0);
+        pos += 1;
+    }
+
+    Ok(Allele {
+        pos,
+        reference: String::from_utf8(reference)?,
+        alternative: String::from_utf8(alternative)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Allele;
+
+    /// Reference sequence for the tests, position 1 is `G`.
+    const REFERENCE: &str = "GATTACACACAGT";
+
+    fn allele(pos: usize, reference: &str, alternative: &str) -> Allele {
+        Allele {
+            pos,
+            reference: reference.into(),
+            alternative: alternative.into(),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::snv(allele(2, "A", "C"), allele(2, "A", "C"))]
+    #[case::trim_prefix(allele(2, "AT", "AC"), allele(3, "T", "C"))]
+    #[case::trim_suffix(allele(5, "ACA", "AGA"), allele(6, "C", "G"))]
+    #[case::left_align_deletion(allele(8, "CAC", "C"), allele(4, "TAC", "T"))]
+    #[case::left_align_insertion(allele(10, "C", "CAC"), allele(4, "T", "TAC"))]
+    #[case::decomposed_multiallelic(allele(3, "TTA", "TA"), allele(2, "AT", "A"))]
+    fn normalize(#[case] input: Allele, #[case] expected: Allele) {
+        let base = |pos: usize| Ok(REFERENCE.as_bytes()[pos - 1]);
+
+        assert!(super::is_normalizable(&input));
+        assert_eq!(super::normalize(&input, base).unwrap(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::symbolic(allele(2, "A", "<NON_REF>"), false)]
+    #[case::spanning_deletion(allele(2, "A", "*"), false)]
+    #[case::reference(allele(2, "A", "A"), false)]
+    #[case::bases(allele(2, "A", "ac"), true)]
+    fn is_normalizable(#[case] input: Allele, #[case] expected: bool) {
+        assert_eq!(super::is_normalizable(&input), expected);
+    }
+}
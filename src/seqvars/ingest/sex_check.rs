@@ -0,0 +1,283 @@
+//! Consistency check of the pedigree sex with the genotypes on chrX and chrY.
+//!
+//! The sex is inferred from the heterozygosity of the variant calls outside of
+//! the pseudoautosomal regions (PAR) on chrX and from the read depth of the
+//! calls on chrY relative to the autosomes as a proxy for the coverage.
+
+use futures::TryStreamExt as _;
+use mehari::common::noodles::NoodlesVariantReader as _;
+use noodles::vcf;
+
+use crate::common::{self, genotype_to_string, Chrom, GenomeRelease};
+
+/// Minimal number of non-PAR chrX calls for inferring the sex.
+const MIN_X_CALLS: usize = 20;
+/// Maximal heterozygous ratio of non-PAR chrX calls for male samples.
+const MAX_MALE_X_HET_RATIO: f64 = 0.1;
+/// Minimal heterozygous ratio of non-PAR chrX calls for female samples.
+const MIN_FEMALE_X_HET_RATIO: f64 = 0.25;
+/// Minimal number of non-PAR chrY calls for considering the chrY coverage.
+const MIN_Y_CALLS: usize = 5;
+/// Minimal ratio of chrY to autosomal depth for male samples.
+const MIN_MALE_Y_COVERAGE_RATIO: f64 = 0.2;
+
+/// Sex as inferred from the genotypes.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum InferredSex {
+    Male,
+    Female,
+    Unknown,
+}
+
+/// Metrics collected for one sample.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SampleMetrics {
+    /// Number of heterozygous non-PAR chrX calls.
+    pub x_het: usize,
+    /// Number of homozygous/hemizygous alternative non-PAR chrX calls.
+    pub x_hom_alt: usize,
+    /// Number of non-PAR chrY calls with read depth.
+    pub y_calls: usize,
+    /// Total read depth of the non-PAR chrY calls.
+    pub y_depth: u64,
+    /// Number of autosomal calls with read depth.
+    pub auto_calls: usize,
+    /// Total read depth of the autosomal calls.
+    pub auto_depth: u64,
+}
+
+impl SampleMetrics {
+    /// Return the fraction of heterozygous non-PAR chrX calls, if any.
+    pub fn x_het_ratio(&self) -> Option<f64> {
+        let total = self.x_het + self.x_hom_alt;
+        (total > 0).then(|| self.x_het as f64 / total as f64)
+    }
+
+    /// Return the mean chrY depth relative to the mean autosomal depth, if any.
+    pub fn y_coverage_ratio(&self) -> Option<f64> {
+        (self.auto_calls > 0 && self.auto_depth > 0).then(|| {
+            let y_mean = if self.y_calls > 0 {
+                self.y_depth as f64 / self.y_calls as f64
+            } else {
+                0.0
+            };
+            y_mean / (self.auto_depth as f64 / self.auto_calls as f64)
+        })
+    }
+
+    /// Infer the sex from the metrics.
+    ///
+    /// chrX heterozygosity is used primarily, chrY coverage is only used as
+    /// evidence for male samples as there may be spurious calls on chrY.
+    pub fn inferred_sex(&self) -> InferredSex {
+        let x_sex = match self.x_het_ratio() {
+            Some(_) if self.x_het + self.x_hom_alt < MIN_X_CALLS => InferredSex::Unknown,
+            Some(ratio) if ratio <= MAX_MALE_X_HET_RATIO => InferredSex::Male,
+            Some(ratio) if ratio >= MIN_FEMALE_X_HET_RATIO => InferredSex::Female,
+            _ => InferredSex::Unknown,
+        };
+        let y_is_male = self.y_calls >= MIN_Y_CALLS
+            && self
+                .y_coverage_ratio()
+                .map(|ratio| ratio >= MIN_MALE_Y_COVERAGE_RATIO)
+                .unwrap_or_default();
+        match (x_sex, y_is_male) {
+            (InferredSex::Female, true) => InferredSex::Unknown,
+            (InferredSex::Unknown, true) => InferredSex::Male,
+            (x_sex, _) => x_sex,
+        }
+    }
+}
+
+/// Result of the sex check for one sample.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SampleResult {
+    /// Name of the sample in the output.
+    pub sample: String,
+    /// Sex from the pedigree.
+    pub pedigree_sex: String,
+    /// Sex inferred from the genotypes.
+    pub inferred_sex: InferredSex,
+    /// Fraction of heterozygous non-PAR chrX calls.
+    pub x_het_ratio: Option<f64>,
+    /// Mean chrY depth relative to mean autosomal depth.
+    pub y_coverage_ratio: Option<f64>,
+    /// Whether pedigree and inferred sex are consistent.
+    pub is_consistent: bool,
+    /// The underlying metrics.
+    pub metrics: SampleMetrics,
+}
+
+/// Accumulates the metrics for all samples of a VCF file.
+#[derive(Debug)]
+pub struct SexCheck {
+    /// Genome release of the input for the PAR coordinates.
+    genomebuild: GenomeRelease,
+    /// Metrics for each input sample.
+    metrics: indexmap::IndexMap<String, SampleMetrics>,
+}
+
+impl SexCheck {
+    /// Create for the input samples.
+    pub fn new(sample_names: &vcf::header::SampleNames, genomebuild: GenomeRelease) -> Self {
+        Self {
+            genomebuild,
+            metrics: sample_names
+                .iter()
+                .map(|name| (name.clone(), Default::default()))
+                .collect(),
+        }
+    }
+
+    /// Add the genotypes and read depths of `record` to the metrics.
+    pub fn add_record(&mut self, record: &vcf::variant::RecordBuf) -> Result<(), anyhow::Error> {
+        use vcf::variant::record::samples::keys::key;
+        use vcf::variant::record_buf::samples::sample::value::Value;
+
+        let name = record.reference_sequence_name();
+        let canonical = annonars::common::cli::canonicalize(name);
+        if !annonars::common::cli::is_canonical(name) || canonical == "MT" {
+            return Ok(());
+        }
+        let chrom: Chrom = canonical.parse()?;
+        let start: usize = record
+            .variant_start()
+            .ok_or_else(|| anyhow::anyhow!("missing variant start in record {:?}", &record))?
+            .into();
+        if crate::seqvars::aggregate::is_par(chrom, start, self.genomebuild) {
+            return Ok(());
+        }
+
+        for (metrics, sample) in self.metrics.values_mut().zip(record.samples().values()) {
+            if let Some(Some(Value::Genotype(gt))) = sample.get(key::GENOTYPE) {
+                let gt = genotype_to_string(gt)?;
+                let alleles = common::strip_gt_leading_slash(&gt)
+                    .split(['/', '|'])
+                    .collect::<Vec<_>>();
+                if let (Chrom::X, false) = (chrom, alleles.contains(&".")) {
+                    match alleles.as_slice() {
+                        [a, b] if a != b => metrics.x_het += 1,
+                        [a, b] if a == b && *a != "0" => metrics.x_hom_alt += 1,
+                        [a] if *a != "0" => metrics.x_hom_alt += 1,
+                        _ => (),
+                    }
+                }
+            }
+            if let Some(Some(Value::Integer(dp))) = sample.get(key::READ_DEPTH) {
+                let dp = u64::try_from(*dp).unwrap_or_default();
+                match chrom {
+                    Chrom::Auto => {
+                        metrics.auto_calls += 1;
+                        metrics.auto_depth += dp;
+                    }
+                    Chrom::Y => {
+                        metrics.y_calls += 1;
+                        metrics.y_depth += dp;
+                    }
+                    Chrom::X => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compare the metrics of `kept_samples` with the pedigree, samples are renamed
+    /// with `id_mapping`.
+    pub fn results(
+        &self,
+        pedigree: &mehari::ped::PedigreeByName,
+        id_mapping: &Option<indexmap::IndexMap<String, String>>,
+        kept_samples: &vcf::header::SampleNames,
+    ) -> Result<Vec<SampleResult>, anyhow::Error> {
+        self.metrics
+            .iter()
+            .filter(|(name, _)| kept_samples.contains(*name))
+            .map(|(name, metrics)| {
+                let sample = id_mapping
+                    .as_ref()
+                    .and_then(|id_mapping| id_mapping.get(name))
+                    .unwrap_or(name);
+                let individual = pedigree.individuals.get(sample).ok_or_else(|| {
+                    anyhow::anyhow!("individual {} not found in pedigree", sample)
+                })?;
+                let inferred_sex = metrics.inferred_sex();
+                let is_consistent = !matches!(
+                    (individual.sex, inferred_sex),
+                    (mehari::ped::Sex::Male, InferredSex::Female)
+                        | (mehari::ped::Sex::Female, InferredSex::Male)
+                );
+                Ok(SampleResult {
+                    sample: sample.clone(),
+                    pedigree_sex: mehari::annotate::strucvars::vcf_header::sex_str(individual.sex)
+                        .to_string(),
+                    inferred_sex,
+                    x_het_ratio: metrics.x_het_ratio(),
+                    y_coverage_ratio: metrics.y_coverage_ratio(),
+                    is_consistent,
+                    metrics: metrics.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Compute the metrics with a separate pass over the VCF file at `path_in`.
+pub async fn compute(path_in: &str, genomebuild: GenomeRelease) -> Result<SexCheck, anyhow::Error> {
+    let mut reader = common::noodles::open_vcf_reader(path_in)
+        .await
+        .map_err(|e| anyhow::anyhow!("could not build VCF reader: {}", e))?;
+    let header = reader
+        .read_header()
+        .await
+        .map_err(|e| anyhow::anyhow!("problem reading VCF header: {}", e))?;
+    let mut sex_check = SexCheck::new(header.sample_names(), genomebuild);
+    let mut records = reader.records(&header).await;
+    while let Some(record) = records.try_next().await? {
+        sex_check.add_record(&record)?;
+    }
+    Ok(sex_check)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InferredSex, SampleMetrics};
+
+    fn metrics(x_het: usize, x_hom_alt: usize, y_calls: usize, y_depth: u64) -> SampleMetrics {
+        SampleMetrics {
+            x_het,
+            x_hom_alt,
+            y_calls,
+            y_depth,
+            auto_calls: 100,
+            auto_depth: 3000,
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::female(metrics(40, 60, 0, 0), InferredSex::Female)]
+    #[case::female_spurious_y(metrics(40, 60, 2, 60), InferredSex::Female)]
+    #[case::male(metrics(2, 98, 20, 300), InferredSex::Male)]
+    #[case::male_from_x(metrics(2, 98, 0, 0), InferredSex::Male)]
+    #[case::male_from_y(metrics(1, 5, 20, 300), InferredSex::Male)]
+    #[case::too_few_calls(metrics(1, 5, 0, 0), InferredSex::Unknown)]
+    #[case::intermediate(metrics(18, 82, 0, 0), InferredSex::Unknown)]
+    #[case::conflicting(metrics(40, 60, 20, 300), InferredSex::Unknown)]
+    fn inferred_sex(#[case] metrics: SampleMetrics, #[case] expected: InferredSex) {
+        assert_eq!(metrics.inferred_sex(), expected);
+    }
+
+    #[test]
+    fn ratios() {
+        let metrics = metrics(25, 75, 10, 150);
+
+        assert_eq!(metrics.x_het_ratio(), Some(0.25));
+        assert_eq!(metrics.y_coverage_ratio(), Some(0.5));
+        assert_eq!(SampleMetrics::default().x_het_ratio(), None);
+        assert_eq!(SampleMetrics::default().y_coverage_ratio(), None);
+    }
+}
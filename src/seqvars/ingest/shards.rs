@@ -0,0 +1,139 @@
+//! Output of one bgzip-compressed VCF file per chromosome with a manifest.
+
+use noodles::vcf;
+
+use crate::common::{
+    noodles::{open_variant_writer, VariantWriter},
+    s3::{s3_mode, upload_file, OutputPathHelper},
+};
+
+/// File name of the manifest in the output directory.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// One entry of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestEntry {
+    /// Name of the chromosome.
+    pub chrom: String,
+    /// Path of the file relative to the output directory.
+    pub path: String,
+    /// Number of records in the file.
+    pub records: usize,
+}
+
+/// The manifest listing the per-chromosome files in the order of the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    /// The per-chromosome files.
+    pub shards: Vec<ManifestEntry>,
+}
+
+/// Writer for one chromosome.
+struct Shard {
+    /// Helper for the index and S3 upload.
+    out_path_helper: OutputPathHelper,
+    /// The opened writer.
+    writer: VariantWriter,
+    /// Number of records written.
+    records: usize,
+}
+
+/// Writes the records to one file per chromosome in the output directory.
+pub struct Shards {
+    /// Path to the output directory or S3 prefix.
+    path_out: String,
+    /// The output header written to each file.
+    header: vcf::Header,
+    /// The writers by chromosome, in order of first occurrence.
+    shards: indexmap::IndexMap<String, Shard>,
+}
+
+impl Shards {
+    /// Prepare writing to the directory `path_out`, creating it if necessary.
+    pub fn new(path_out: &str, header: &vcf::Header) -> Result<Self, anyhow::Error> {
+        if !s3_mode() {
+            std::fs::create_dir_all(path_out).map_err(|e| {
+                anyhow::anyhow!("could not create output directory {}: {}", path_out, e)
+            })?;
+        }
+        Ok(Self {
+            path_out: path_out.trim_end_matches('/').to_string(),
+            header: header.clone(),
+            shards: Default::default(),
+        })
+    }
+
+    /// Return the file name for `chrom`.
+    fn file_name(chrom: &str) -> String {
+        format!("{}.vcf.gz", chrom)
+    }
+
+    /// Write `record` to the file of its chromosome, opening it on first use.
+    pub async fn write_variant_record(
+        &mut self,
+        record: &vcf::variant::RecordBuf,
+    ) -> Result<(), anyhow::Error> {
+        let chrom = record.reference_sequence_name();
+        if !self.shards.contains_key(chrom) {
+            let out_path_helper =
+                OutputPathHelper::new(&format!("{}/{}", &self.path_out, Self::file_name(chrom)))?;
+            let mut writer = open_variant_writer(out_path_helper.path_out()).await?;
+            writer
+                .write_header(&self.header)
+                .await
+                .map_err(|e| anyhow::anyhow!("problem writing header: {}", e))?;
+            self.shards.insert(
+                chrom.to_string(),
+                Shard {
+                    out_path_helper,
+                    writer,
+                    records: 0,
+                },
+            );
+        }
+        let shard = self.shards.get_mut(chrom).expect("inserted above");
+        shard
+            .writer
+            .write_variant_record(&self.header, record)
+            .await?;
+        shard.records += 1;
+        Ok(())
+    }
+
+    /// Close all files, write the indices if `create_index`, and write the manifest.
+    pub async fn finish(self, create_index: bool) -> Result<Manifest, anyhow::Error> {
+        let mut manifest = Manifest::default();
+        for (chrom, shard) in self.shards {
+            shard.writer.shutdown().await?;
+            if create_index {
+                shard.out_path_helper.create_index().await?;
+            }
+            shard.out_path_helper.upload_for_s3().await?;
+            manifest.shards.push(ManifestEntry {
+                path: Self::file_name(&chrom),
+                chrom,
+                records: shard.records,
+            });
+        }
+
+        let tmpdir = tempfile::TempDir::new()?;
+        let path_manifest = if s3_mode() {
+            tmpdir.path().join(MANIFEST_NAME)
+        } else {
+            std::path::Path::new(&self.path_out).join(MANIFEST_NAME)
+        };
+        let file = std::fs::File::create(&path_manifest)
+            .map_err(|e| anyhow::anyhow!("could not create {}: {}", path_manifest.display(), e))?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), &manifest)
+            .map_err(|e| anyhow::anyhow!("could not write manifest: {}", e))?;
+        if s3_mode() {
+            upload_file(
+                &path_manifest.to_string_lossy(),
+                &format!("{}/{}", &self.path_out, MANIFEST_NAME),
+            )
+            .await?;
+        }
+
+        Ok(manifest)
+    }
+}
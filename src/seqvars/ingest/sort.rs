@@ -0,0 +1,194 @@
+//! Sorting of the output records by coordinate.
+//!
+//! Left-alignment and liftover can move a record before records that have been
+//! written already.  In this case, the output records are staged in a temporary file and sorted with
+//! an external sort before writing them out.
+
+use std::io::{BufRead as _, Write as _};
+
+use ext_sort::{ExternalSorter, ExternalSorterBuilder, LimitedBufferBuilder};
+use noodles::vcf::{self, variant::io::Write as _};
+
+/// Number of records to sort in memory before writing a sorted chunk to disk.
+const BUFFER_SIZE: usize = 100_000;
+
+/// Output record as VCF line with the key for sorting by coordinate.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ByCoordinate {
+    /// Index of the contig in the output header.
+    pub contig_idx: usize,
+    /// 1-based position.
+    pub pos: usize,
+    /// Running number of the record, such that records at the same position keep
+    /// their order.
+    pub record_no: usize,
+    /// The record as VCF line.
+    pub line: String,
+}
+
+impl ByCoordinate {
+    /// Return the key for sorting.
+    fn key(&self) -> (usize, usize, usize) {
+        (self.contig_idx, self.pos, self.record_no)
+    }
+}
+
+impl PartialEq for ByCoordinate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for ByCoordinate {}
+
+impl PartialOrd for ByCoordinate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByCoordinate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Temporary file with the output records to sort by coordinate.
+pub struct Stage {
+    /// Temporary directory for the unsorted records and the sorted chunks.
+    tmp_dir: tempfile::TempDir,
+    /// Writer for the unsorted records.
+    writer: std::io::BufWriter<std::fs::File>,
+    /// Number of records written.
+    count: usize,
+    /// The sorter, kept alive while reading the sorted chunks.
+    sorter: Option<ExternalSorter<ByCoordinate, std::io::Error, LimitedBufferBuilder>>,
+}
+
+impl Stage {
+    /// Create the temporary file.
+    pub fn new() -> Result<Self, anyhow::Error> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let writer = std::fs::File::create(tmp_dir.path().join("unsorted.jsonl"))
+            .map(std::io::BufWriter::new)
+            .map_err(|e| anyhow::anyhow!("could not create temporary unsorted file: {}", e))?;
+        Ok(Self {
+            tmp_dir,
+            writer,
+            count: 0,
+            sorter: None,
+        })
+    }
+
+    /// Add the `record` to the temporary file.
+    pub fn push(
+        &mut self,
+        header: &vcf::Header,
+        record: &vcf::variant::RecordBuf,
+    ) -> Result<(), anyhow::Error> {
+        let mut line_writer = vcf::io::Writer::new(Vec::new());
+        line_writer.write_variant_record(header, record)?;
+        let by_coordinate = ByCoordinate {
+            contig_idx: header
+                .contigs()
+                .get_index_of(record.reference_sequence_name())
+                .unwrap_or(usize::MAX),
+            pos: record.variant_start().map(usize::from).unwrap_or_default(),
+            record_no: self.count,
+            line: String::from_utf8(line_writer.into_inner())?
+                .trim_end()
+                .to_string(),
+        };
+        writeln!(self.writer, "{}", serde_json::to_string(&by_coordinate)?)
+            .map_err(|e| anyhow::anyhow!("could not write temporary unsorted file: {}", e))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Return the records sorted by coordinate.
+    pub fn sorted<'a>(
+        &'a mut self,
+        header: &'a vcf::Header,
+    ) -> Result<
+        impl Iterator<Item = Result<vcf::variant::RecordBuf, anyhow::Error>> + 'a,
+        anyhow::Error,
+    > {
+        self.writer
+            .flush()
+            .map_err(|e| anyhow::anyhow!("could not flush temporary unsorted file: {}", e))?;
+        let reader = std::fs::File::open(self.tmp_dir.path().join("unsorted.jsonl"))
+            .map(std::io::BufReader::new)
+            .map_err(|e| anyhow::anyhow!("could not open temporary unsorted file: {}", e))?;
+
+        let sorter = self.sorter.insert(
+            ExternalSorterBuilder::new()
+                .with_tmp_dir(self.tmp_dir.path())
+                .with_buffer(LimitedBufferBuilder::new(BUFFER_SIZE, false))
+                .build()
+                .map_err(|e| anyhow::anyhow!("problem creating external sorter: {}", e))?,
+        );
+        let sorted_iter = sorter
+            .sort(reader.lines().map(|line| {
+                line.and_then(|line| serde_json::from_str(&line).map_err(std::io::Error::from))
+            }))
+            .map_err(|e| anyhow::anyhow!("problem sorting temporary unsorted file: {}", e))?;
+
+        Ok(sorted_iter.map(move |by_coordinate| {
+            let by_coordinate = by_coordinate
+                .map_err(|e| anyhow::anyhow!("problem reading record after sorting: {}", e))?;
+            let mut record = vcf::variant::RecordBuf::default();
+            vcf::io::Reader::new(by_coordinate.line.as_bytes())
+                .read_record_buf(header, &mut record)
+                .map_err(|e| anyhow::anyhow!("problem parsing record after sorting: {}", e))?;
+            Ok(record)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use noodles::vcf;
+
+    #[test]
+    fn sorted() -> Result<(), anyhow::Error> {
+        let header: vcf::Header = "##fileformat=VCFv4.4\n\
+            ##contig=<ID=1>\n\
+            ##contig=<ID=2>\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n"
+            .parse()?;
+        let record = |chrom: &str, pos: usize, alt: &str| -> Result<_, anyhow::Error> {
+            Ok(vcf::variant::RecordBuf::builder()
+                .set_reference_sequence_name(chrom)
+                .set_variant_start(noodles::core::Position::try_from(pos)?)
+                .set_reference_bases("A")
+                .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                    alt.to_string()
+                ]))
+                .build())
+        };
+        let records = vec![
+            record("2", 100, "C")?,
+            record("1", 200, "G")?,
+            record("1", 100, "T")?,
+            record("1", 200, "C")?,
+        ];
+
+        let mut stage = super::Stage::new()?;
+        for record in &records {
+            stage.push(&header, record)?;
+        }
+        let sorted = stage.sorted(&header)?.collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            sorted,
+            vec![
+                records[2].clone(),
+                records[1].clone(),
+                records[3].clone(),
+                records[0].clone(),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 pub mod aggregate;
+pub mod bench;
 pub mod ingest;
 pub mod prefilter;
 pub mod query;
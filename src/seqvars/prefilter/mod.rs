@@ -264,7 +264,7 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             flush_and_shutdown!(output_writer);
         }
         for out_path_helper in out_path_helpers.drain(..) {
-            out_path_helper.create_tbi_for_bgzf().await?;
+            out_path_helper.create_index().await?;
             out_path_helper.upload_for_s3().await?;
         }
     };
@@ -0,0 +1,317 @@
+//! Automated evaluation of computable ACMG criteria.
+//!
+//! Only the criteria that can be derived from the annotation of a single
+//! variant are considered (PVS1, PM2, PP3, BA1, BS1, BP4).  The resulting
+//! provisional class is a lower bound for curation and not a classification.
+
+use crate::pbs::varfish::v1::seqvars::output as pbs_output;
+use crate::pbs::varfish::v1::seqvars::query as pbs_query;
+
+/// Allele frequency above which BA1 is met.
+const BA1_MIN_AF: f32 = 0.05;
+/// Allele frequency above which BS1 is met.
+const BS1_MIN_AF: f32 = 0.01;
+/// Allele frequency up to which PM2 is met.
+const PM2_MAX_AF: f32 = 0.0001;
+/// Upper bound of LOEUF below which PVS1 is met for null variants.
+const PVS1_MAX_LOEUF: f32 = 0.35;
+/// REVEL thresholds for PP3 and BP4 (Pejaver et al., 2022).
+const REVEL_PP3_MIN: f64 = 0.644;
+const REVEL_BP4_MAX: f64 = 0.290;
+/// CADD PHRED thresholds for PP3 and BP4 (Pejaver et al., 2022).
+const CADD_PP3_MIN: f64 = 25.3;
+const CADD_BP4_MAX: f64 = 22.7;
+
+/// Consequences of null variants for PVS1.
+const NULL_CONSEQUENCES: &[pbs_query::Consequence] = &[
+    pbs_query::Consequence::TranscriptAblation,
+    pbs_query::Consequence::ExonLossVariant,
+    pbs_query::Consequence::SpliceAcceptorVariant,
+    pbs_query::Consequence::SpliceDonorVariant,
+    pbs_query::Consequence::StopGained,
+    pbs_query::Consequence::FrameshiftVariant,
+    pbs_query::Consequence::StartLost,
+];
+
+/// Strength of the evidence of a criterion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strength {
+    PathogenicVeryStrong,
+    PathogenicModerate,
+    PathogenicSupporting,
+    BenignStandAlone,
+    BenignStrong,
+    BenignSupporting,
+}
+
+impl Strength {
+    /// Return the default strength of the given criterion.
+    fn of(criterion: pbs_output::AcmgCriterion) -> Option<Self> {
+        match criterion {
+            pbs_output::AcmgCriterion::Unspecified => None,
+            pbs_output::AcmgCriterion::Pvs1 => Some(Strength::PathogenicVeryStrong),
+            pbs_output::AcmgCriterion::Pm2 => Some(Strength::PathogenicModerate),
+            pbs_output::AcmgCriterion::Pp3 => Some(Strength::PathogenicSupporting),
+            pbs_output::AcmgCriterion::Ba1 => Some(Strength::BenignStandAlone),
+            pbs_output::AcmgCriterion::Bs1 => Some(Strength::BenignStrong),
+            pbs_output::AcmgCriterion::Bp4 => Some(Strength::BenignSupporting),
+        }
+    }
+}
+
+/// Return the maximal population allele frequency of the variant.
+fn max_af(variant: &pbs_output::VariantRelatedAnnotation) -> f32 {
+    variant
+        .frequency
+        .as_ref()
+        .map(|frequency| {
+            [
+                frequency.gnomad_exomes.as_ref().map(|f| f.af),
+                frequency.gnomad_genomes.as_ref().map(|f| f.af),
+                frequency.gnomad_mtdna.as_ref().map(|f| f.af),
+                frequency.helixmtdb.as_ref().map(|f| f.af),
+            ]
+            .into_iter()
+            .flatten()
+            .fold(0f32, f32::max)
+        })
+        .unwrap_or_default()
+}
+
+/// Return the numeric score with the given `key`, if any.
+fn score(variant: &pbs_output::VariantRelatedAnnotation, key: &str) -> Option<f64> {
+    variant
+        .scores
+        .as_ref()?
+        .entries
+        .iter()
+        .find(|entry| entry.key == key)
+        .and_then(|entry| match entry.value.as_ref()?.kind.as_ref()? {
+            pbjson_types::value::Kind::NumberValue(value) => Some(*value),
+            _ => None,
+        })
+}
+
+/// Return whether the variant is a null variant in a LoF-intolerant gene.
+fn is_pvs1(gene: &pbs_output::GeneRelatedAnnotation) -> bool {
+    let is_null = gene
+        .consequences
+        .as_ref()
+        .map(|consequences| {
+            consequences.consequences.iter().any(|csq| {
+                pbs_query::Consequence::try_from(*csq)
+                    .map(|csq| NULL_CONSEQUENCES.contains(&csq))
+                    .unwrap_or_default()
+            })
+        })
+        .unwrap_or_default();
+    let loeuf = gene
+        .constraints
+        .as_ref()
+        .and_then(|constraints| constraints.gnomad.as_ref())
+        .map(|gnomad| gnomad.oe_lof_upper);
+    is_null
+        && loeuf
+            .map(|loeuf| loeuf < PVS1_MAX_LOEUF)
+            .unwrap_or_default()
+}
+
+/// Evaluate the computable criteria for the given annotation.
+pub fn criteria(
+    gene: &pbs_output::GeneRelatedAnnotation,
+    variant: &pbs_output::VariantRelatedAnnotation,
+) -> Vec<pbs_output::AcmgCriterion> {
+    let mut result = Vec::new();
+
+    let pvs1 = is_pvs1(gene);
+    if pvs1 {
+        result.push(pbs_output::AcmgCriterion::Pvs1);
+    }
+
+    let max_af = max_af(variant);
+    if max_af > BA1_MIN_AF {
+        result.push(pbs_output::AcmgCriterion::Ba1);
+    } else if max_af > BS1_MIN_AF {
+        result.push(pbs_output::AcmgCriterion::Bs1);
+    } else if max_af <= PM2_MAX_AF {
+        result.push(pbs_output::AcmgCriterion::Pm2);
+    }
+
+    // PP3 must not be combined with PVS1 as this would count the same evidence twice.
+    let in_silico = match (score(variant, "revel"), score(variant, "cadd_phred")) {
+        (Some(revel), _) => Some((revel >= REVEL_PP3_MIN, revel <= REVEL_BP4_MAX)),
+        (None, Some(cadd)) => Some((cadd >= CADD_PP3_MIN, cadd <= CADD_BP4_MAX)),
+        (None, None) => None,
+    };
+    match in_silico {
+        Some((true, _)) if !pvs1 => result.push(pbs_output::AcmgCriterion::Pp3),
+        Some((_, true)) => result.push(pbs_output::AcmgCriterion::Bp4),
+        _ => (),
+    }
+
+    result
+}
+
+/// Combine the criteria into a provisional class following Richards et al. (2015).
+pub fn provisional_class(criteria: &[pbs_output::AcmgCriterion]) -> pbs_output::AcmgClass {
+    let count = |strength: Strength| {
+        criteria
+            .iter()
+            .filter(|criterion| Strength::of(**criterion) == Some(strength))
+            .count()
+    };
+    let pvs = count(Strength::PathogenicVeryStrong);
+    let pm = count(Strength::PathogenicModerate);
+    let pp = count(Strength::PathogenicSupporting);
+    let ba = count(Strength::BenignStandAlone);
+    let bs = count(Strength::BenignStrong);
+    let bp = count(Strength::BenignSupporting);
+
+    let pathogenic = pvs >= 1 && (pm >= 2 || (pm == 1 && pp == 1) || pp >= 2);
+    let likely_pathogenic =
+        (pvs >= 1 && pm == 1) || pm >= 3 || (pm == 2 && pp >= 2) || (pm == 1 && pp >= 4);
+    let benign = ba >= 1 || bs >= 2;
+    let likely_benign = (bs == 1 && bp == 1) || bp >= 2;
+
+    let is_pathogenic = pathogenic || likely_pathogenic;
+    let is_benign = benign || likely_benign;
+    if is_pathogenic && is_benign {
+        pbs_output::AcmgClass::UncertainSignificance
+    } else if pathogenic {
+        pbs_output::AcmgClass::Pathogenic
+    } else if likely_pathogenic {
+        pbs_output::AcmgClass::LikelyPathogenic
+    } else if benign {
+        pbs_output::AcmgClass::Benign
+    } else if likely_benign {
+        pbs_output::AcmgClass::LikelyBenign
+    } else {
+        pbs_output::AcmgClass::UncertainSignificance
+    }
+}
+
+/// Construct the ACMG annotation from the gene- and variant-related annotation.
+pub fn annotation(
+    gene: &pbs_output::GeneRelatedAnnotation,
+    variant: &pbs_output::VariantRelatedAnnotation,
+) -> pbs_output::AcmgAnnotation {
+    let criteria = criteria(gene, variant);
+    pbs_output::AcmgAnnotation {
+        provisional_class: provisional_class(&criteria) as i32,
+        criteria: criteria
+            .into_iter()
+            .map(|criterion| criterion as i32)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pbs::varfish::v1::seqvars::output::{self as pbs_output, AcmgClass, AcmgCriterion};
+    use crate::pbs::varfish::v1::seqvars::query as pbs_query;
+
+    fn gene(
+        consequence: pbs_query::Consequence,
+        loeuf: Option<f32>,
+    ) -> pbs_output::GeneRelatedAnnotation {
+        pbs_output::GeneRelatedAnnotation {
+            consequences: Some(pbs_output::GeneRelatedConsequences {
+                consequences: vec![consequence as i32],
+                ..Default::default()
+            }),
+            constraints: Some(pbs_output::GeneRelatedConstraints {
+                gnomad: loeuf.map(|oe_lof_upper| pbs_output::GnomadConstraints {
+                    oe_lof_upper,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn variant(af: f32, scores: &[(&str, f64)]) -> pbs_output::VariantRelatedAnnotation {
+        pbs_output::VariantRelatedAnnotation {
+            frequency: Some(pbs_output::FrequencyAnnotation {
+                gnomad_exomes: Some(pbs_output::NuclearFrequency {
+                    af,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            scores: Some(pbs_output::ScoreAnnotations {
+                entries: scores
+                    .iter()
+                    .map(|(key, value)| pbs_output::ScoreEntry {
+                        key: key.to_string(),
+                        value: Some(pbjson_types::Value {
+                            kind: Some(pbjson_types::value::Kind::NumberValue(*value)),
+                        }),
+                    })
+                    .collect(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::null_constrained(
+        pbs_query::Consequence::StopGained,
+        Some(0.2),
+        0.0,
+        vec![("cadd_phred", 40.0)],
+        vec![AcmgCriterion::Pvs1, AcmgCriterion::Pm2]
+    )]
+    #[case::null_tolerant(
+        pbs_query::Consequence::StopGained,
+        Some(0.9),
+        0.0,
+        vec![("cadd_phred", 40.0)],
+        vec![AcmgCriterion::Pm2, AcmgCriterion::Pp3]
+    )]
+    #[case::missense_revel_over_cadd(
+        pbs_query::Consequence::MissenseVariant,
+        None,
+        0.00001,
+        vec![("cadd_phred", 30.0), ("revel", 0.1)],
+        vec![AcmgCriterion::Pm2, AcmgCriterion::Bp4]
+    )]
+    #[case::common(
+        pbs_query::Consequence::MissenseVariant,
+        None,
+        0.1,
+        vec![],
+        vec![AcmgCriterion::Ba1]
+    )]
+    #[case::frequent(
+        pbs_query::Consequence::MissenseVariant,
+        None,
+        0.02,
+        vec![("revel", 0.5)],
+        vec![AcmgCriterion::Bs1]
+    )]
+    fn criteria(
+        #[case] consequence: pbs_query::Consequence,
+        #[case] loeuf: Option<f32>,
+        #[case] af: f32,
+        #[case] scores: Vec<(&str, f64)>,
+        #[case] expected: Vec<AcmgCriterion>,
+    ) {
+        let gene = gene(consequence, loeuf);
+        let variant = variant(af, &scores);
+
+        assert_eq!(super::criteria(&gene, &variant), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::pvs1_pm2_pp3(vec![AcmgCriterion::Pvs1, AcmgCriterion::Pm2, AcmgCriterion::Pp3], AcmgClass::Pathogenic)]
+    #[case::pvs1_pm2(vec![AcmgCriterion::Pvs1, AcmgCriterion::Pm2], AcmgClass::LikelyPathogenic)]
+    #[case::pm2_pp3(vec![AcmgCriterion::Pm2, AcmgCriterion::Pp3], AcmgClass::UncertainSignificance)]
+    #[case::ba1(vec![AcmgCriterion::Ba1], AcmgClass::Benign)]
+    #[case::bs1_bp4(vec![AcmgCriterion::Bs1, AcmgCriterion::Bp4], AcmgClass::LikelyBenign)]
+    #[case::conflict(vec![AcmgCriterion::Pvs1, AcmgCriterion::Pm2, AcmgCriterion::Bs1, AcmgCriterion::Bp4], AcmgClass::UncertainSignificance)]
+    #[case::none(vec![], AcmgClass::UncertainSignificance)]
+    fn provisional_class(#[case] criteria: Vec<AcmgCriterion>, #[case] expected: AcmgClass) {
+        assert_eq!(super::provisional_class(&criteria), expected);
+    }
+}
@@ -0,0 +1,161 @@
+//! Code for the experimental digenic candidate pair mode.
+//!
+//! After per-gene filtering, pairs from a curated list of digenic gene
+//! interactions (e.g., DIDA) are reported where each gene carries at least
+//! one qualifying het. variant.
+
+use std::collections::HashMap;
+
+use super::schema::data::VariantRecord;
+use crate::common;
+
+/// A gene pair from the curated list of digenic interactions.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct GenePair {
+    /// HGNC ID of the first gene.
+    pub hgnc_id_a: String,
+    /// HGNC ID of the second gene.
+    pub hgnc_id_b: String,
+}
+
+/// A candidate gene pair written to the pair-oriented output file.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CandidatePair {
+    /// HGNC ID of the first gene.
+    pub hgnc_id_a: String,
+    /// HGNC ID of the second gene.
+    pub hgnc_id_b: String,
+    /// Number of qualifying het. variants in the first gene.
+    pub count_a: usize,
+    /// Number of qualifying het. variants in the second gene.
+    pub count_b: usize,
+}
+
+/// Load gene pairs from the headered TSV file at `path`.
+///
+/// The file must have the columns `hgnc_id_a` and `hgnc_id_b`.
+pub fn load_gene_pairs(path: &str) -> Result<Vec<GenePair>, anyhow::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b'\t')
+        .from_path(path)
+        .map_err(|e| anyhow::anyhow!("could not open digenic pairs file {}: {}", path, e))?;
+    reader
+        .deserialize()
+        .collect::<Result<Vec<GenePair>, _>>()
+        .map_err(|e| anyhow::anyhow!("could not parse digenic pairs file {}: {}", path, e))
+}
+
+/// Count the variants in `seqvars` that are het. in `sample` (or in any sample if `None`).
+pub fn count_het_variants(seqvars: &[VariantRecord], sample: Option<&str>) -> usize {
+    seqvars
+        .iter()
+        .filter(|seqvar| {
+            seqvar
+                .call_infos
+                .iter()
+                .filter(|(name, _)| sample.map(|sample| sample == *name).unwrap_or(true))
+                .any(|(_, call_info)| {
+                    call_info
+                        .genotype
+                        .as_ref()
+                        .and_then(|gt| gt.parse::<common::Genotype>().ok())
+                        == Some(common::Genotype::Het)
+                })
+        })
+        .count()
+}
+
+/// Return the gene pairs where both genes have at least one qualifying het. variant.
+pub fn candidate_pairs(
+    gene_pairs: &[GenePair],
+    het_counts: &HashMap<String, usize>,
+) -> Vec<CandidatePair> {
+    gene_pairs
+        .iter()
+        .filter_map(|pair| {
+            let count_a = het_counts.get(&pair.hgnc_id_a).cloned().unwrap_or_default();
+            let count_b = het_counts.get(&pair.hgnc_id_b).cloned().unwrap_or_default();
+            (count_a > 0 && count_b > 0).then(|| CandidatePair {
+                hgnc_id_a: pair.hgnc_id_a.clone(),
+                hgnc_id_b: pair.hgnc_id_b.clone(),
+                count_a,
+                count_b,
+            })
+        })
+        .collect()
+}
+
+/// Write the candidate pairs as headered TSV to `path`.
+pub fn write_candidate_pairs(path: &str, pairs: &[CandidatePair]) -> Result<(), anyhow::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(true)
+        .delimiter(b'\t')
+        .quote_style(csv::QuoteStyle::Never)
+        .from_path(path)
+        .map_err(|e| anyhow::anyhow!("could not open digenic output file {}: {}", path, e))?;
+    for pair in pairs {
+        writer
+            .serialize(pair)
+            .map_err(|e| anyhow::anyhow!("could not write digenic pair: {}", e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| anyhow::anyhow!("could not flush digenic output file: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::seqvars::query::schema::data::{CallInfo, VariantRecord};
+
+    use super::{CandidatePair, GenePair};
+
+    #[test]
+    fn count_het_variants() {
+        let seqvars = ["0/1", "1/1", "0/0", "1|0"]
+            .iter()
+            .map(|gt| VariantRecord {
+                call_infos: indexmap::indexmap! {
+                    String::from("index") => CallInfo {
+                        sample: String::from("index"),
+                        genotype: Some(gt.to_string()),
+                        ..Default::default()
+                    },
+                },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(super::count_het_variants(&seqvars, Some("index")), 2);
+        assert_eq!(super::count_het_variants(&seqvars, None), 2);
+        assert_eq!(super::count_het_variants(&seqvars, Some("father")), 0);
+    }
+
+    #[test]
+    fn candidate_pairs() {
+        let gene_pairs = vec![
+            GenePair {
+                hgnc_id_a: "HGNC:1".into(),
+                hgnc_id_b: "HGNC:2".into(),
+            },
+            GenePair {
+                hgnc_id_a: "HGNC:1".into(),
+                hgnc_id_b: "HGNC:3".into(),
+            },
+        ];
+        let het_counts = HashMap::from([("HGNC:1".to_string(), 1), ("HGNC:2".to_string(), 2)]);
+
+        assert_eq!(
+            super::candidate_pairs(&gene_pairs, &het_counts),
+            vec![CandidatePair {
+                hgnc_id_a: "HGNC:1".into(),
+                hgnc_id_b: "HGNC:2".into(),
+                count_a: 1,
+                count_b: 2,
+            }]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::pbs::varfish::v1::seqvars::output::AggregateGermlineReviewStatus;
 use crate::seqvars::query::{
     annonars::Annotator,
     schema::{
@@ -35,13 +36,17 @@ pub fn passes(
         }
         let vcv_record = &record.records[0];
 
-        let description = vcv_record
+        let germline_classification = vcv_record
             .classifications
             .as_ref()
-            .and_then(|c| c.germline_classification.as_ref())
+            .and_then(|c| c.germline_classification.as_ref());
+        let description = germline_classification
             .and_then(|c| c.description.as_ref())
             .cloned()
             .unwrap_or_default();
+        let review_status = germline_classification
+            .map(|c| c.review_status)
+            .unwrap_or_default();
 
         use ClinvarGermlineAggregateDescription::*;
         let result = match description.to_lowercase().as_str() {
@@ -78,6 +83,8 @@ pub fn passes(
             }
         };
 
+        let result = result && passes_review_stars(query.clinvar.min_review_stars, review_status);
+
         if !result {
             tracing::trace!(
                 "variant {:?} present in ClinVar but fails clinvar filter from query {:?}",
@@ -96,3 +103,73 @@ pub fn passes(
         Ok(false)
     }
 }
+
+/// Return the number of review status stars for the given aggregate germline review status.
+fn review_stars(review_status: i32) -> u32 {
+    use AggregateGermlineReviewStatus::*;
+    match AggregateGermlineReviewStatus::try_from(review_status).unwrap_or(Unspecified) {
+        CriteriaProvidedSingleSubmitter | CriteriaProvidedConflictingClassifications => 1,
+        CriteriaProvidedMultipleSubmittersNoConflicts => 2,
+        ReviewedByExpertPanel => 3,
+        PracticeGuideline => 4,
+        _ => 0,
+    }
+}
+
+/// Determine whether `review_status` has at least `min_review_stars` stars, if given.
+fn passes_review_stars(min_review_stars: Option<u32>, review_status: i32) -> bool {
+    min_review_stars
+        .map(|min_review_stars| review_stars(review_status) >= min_review_stars)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pbs::varfish::v1::seqvars::output::AggregateGermlineReviewStatus;
+
+    #[rstest::rstest]
+    #[case(None, AggregateGermlineReviewStatus::NoAssertionCriteriaProvided, true)]
+    #[case(
+        Some(0),
+        AggregateGermlineReviewStatus::NoAssertionCriteriaProvided,
+        true
+    )]
+    #[case(
+        Some(1),
+        AggregateGermlineReviewStatus::NoAssertionCriteriaProvided,
+        false
+    )]
+    #[case(
+        Some(1),
+        AggregateGermlineReviewStatus::CriteriaProvidedSingleSubmitter,
+        true
+    )]
+    #[case(
+        Some(2),
+        AggregateGermlineReviewStatus::CriteriaProvidedSingleSubmitter,
+        false
+    )]
+    #[case(
+        Some(2),
+        AggregateGermlineReviewStatus::CriteriaProvidedConflictingClassifications,
+        false
+    )]
+    #[case(
+        Some(2),
+        AggregateGermlineReviewStatus::CriteriaProvidedMultipleSubmittersNoConflicts,
+        true
+    )]
+    #[case(Some(3), AggregateGermlineReviewStatus::ReviewedByExpertPanel, true)]
+    #[case(Some(4), AggregateGermlineReviewStatus::ReviewedByExpertPanel, false)]
+    #[case(Some(4), AggregateGermlineReviewStatus::PracticeGuideline, true)]
+    fn passes_review_stars(
+        #[case] min_review_stars: Option<u32>,
+        #[case] review_status: AggregateGermlineReviewStatus,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            super::passes_review_stars(min_review_stars, review_status as i32),
+            expected
+        );
+    }
+}
@@ -1,11 +1,30 @@
 use mehari::annotate::seqvars::ann;
 
-use crate::seqvars::query::schema::{data::VariantRecord, query::CaseQuery};
+use crate::seqvars::query::schema::{
+    data::VariantRecord,
+    query::{CaseQuery, TranscriptType},
+};
+
+/// Consequences that indicate that the variant is located in an intron or in
+/// the splice region next to an exon.
+///
+/// Annotations with these consequences are kept if they are within the maximal
+/// distance to exon from the query, regardless of the selected consequences.
+const INTRONIC_CONSEQUENCES: &[ann::Consequence] = &[
+    ann::Consequence::SpliceDonorFifthBaseVariant,
+    ann::Consequence::SpliceRegionVariant,
+    ann::Consequence::SpliceDonorRegionVariant,
+    ann::Consequence::SplicePolypyrimidineTractVariant,
+    ann::Consequence::FivePrimeUtrIntronVariant,
+    ann::Consequence::ThreePrimeUtrIntronVariant,
+    ann::Consequence::NonCodingTranscriptIntronVariant,
+    ann::Consequence::IntronVariant,
+];
 
 /// Determine whether the `VariantRecord` passes the consequences filter.
 pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow::Error> {
-    // If no consequences are specified, the variant passes.
-    if query.consequence.consequences.is_empty() {
+    // If no consequences and no maximal distance are specified, the variant passes.
+    if query.consequence.consequences.is_empty() && query.consequence.max_dist_to_exon.is_none() {
         return Ok(true);
     }
     // Variants on chrMT always pass.
@@ -23,6 +42,32 @@ pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow:
             .map(|c| c.into()),
     );
     for ann_field in &seqvar.ann_fields {
+        if query.consequence.mane_transcripts_only && !is_mane_transcript(ann_field) {
+            continue;
+        }
+        if !passes_transcript_type(&query.consequence.transcript_types, ann_field) {
+            continue;
+        }
+        let within_dist = passes_dist_to_exon(query.consequence.max_dist_to_exon, ann_field);
+        if !within_dist {
+            continue;
+        }
+
+        // When a maximal distance is given, intronic annotations within the distance pass
+        // even if the consequence has not been selected.
+        if query.consequence.max_dist_to_exon.is_some()
+            && ann_field
+                .consequences
+                .iter()
+                .any(|csq| INTRONIC_CONSEQUENCES.contains(csq))
+        {
+            return Ok(true);
+        }
+
+        // Otherwise, the consequence must have been selected (or no consequence at all).
+        if query_csq.is_empty() {
+            return Ok(true);
+        }
         let seqvar_csq: indexmap::IndexSet<ann::Consequence> =
             indexmap::IndexSet::from_iter(ann_field.consequences.iter().cloned());
         let intersection_csq = query_csq.intersection(&seqvar_csq);
@@ -39,6 +84,44 @@ pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow:
     Ok(false)
 }
 
+/// Determine whether the annotation is within `max_dist_to_exon` of the next exon.
+///
+/// Annotations without a distance (exonic variants) always pass, as well as all
+/// annotations if no maximal distance is given.
+fn passes_dist_to_exon(max_dist_to_exon: Option<i32>, ann_field: &ann::AnnField) -> bool {
+    match (max_dist_to_exon, ann_field.distance) {
+        (Some(max_dist_to_exon), Some(distance)) => distance.abs() <= max_dist_to_exon,
+        _ => true,
+    }
+}
+
+/// Determine whether the annotation is on a MANE Select / MANE Plus Clinical transcript.
+fn is_mane_transcript(ann_field: &ann::AnnField) -> bool {
+    ann_field.feature_biotype.iter().any(|biotype| {
+        matches!(
+            biotype,
+            ann::FeatureBiotype::ManeSelect | ann::FeatureBiotype::ManePlusClinical
+        )
+    })
+}
+
+/// Determine whether the annotation's feature biotype is among the selected transcript types.
+///
+/// All annotations pass if no transcript type is selected.
+fn passes_transcript_type(transcript_types: &[TranscriptType], ann_field: &ann::AnnField) -> bool {
+    transcript_types.is_empty()
+        || ann_field
+            .feature_biotype
+            .iter()
+            .any(|biotype| match biotype {
+                ann::FeatureBiotype::Coding => transcript_types.contains(&TranscriptType::Coding),
+                ann::FeatureBiotype::Noncoding => {
+                    transcript_types.contains(&TranscriptType::NonCoding)
+                }
+                _ => false,
+            })
+}
+
 #[cfg(test)]
 mod test {
     use mehari::annotate::seqvars::ann;
@@ -47,7 +130,7 @@ mod test {
 
     use crate::seqvars::query::schema::{
         data::{VariantRecord, VcfVariant},
-        query::{CaseQuery, QuerySettingsConsequence},
+        query::{CaseQuery, QuerySettingsConsequence, TranscriptType},
     };
 
     #[rstest]
@@ -96,4 +179,178 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::intron_within_dist(ann::Consequence::IntronVariant, Some(40), Some(50), vec![], true)]
+    #[case::intron_beyond_dist(ann::Consequence::IntronVariant, Some(60), Some(50), vec![], false)]
+    #[case::intron_negative_within_dist(
+        ann::Consequence::IntronVariant,
+        Some(-40),
+        Some(50),
+        vec![],
+        true
+    )]
+    #[case::intron_no_max_dist(ann::Consequence::IntronVariant, Some(40), None, vec![], true)]
+    #[case::intron_beyond_dist_selected(
+        ann::Consequence::IntronVariant,
+        Some(60),
+        Some(50),
+        vec![crate::seqvars::query::schema::query::Consequence::IntronVariant],
+        false
+    )]
+    #[case::missense_no_dist(
+        ann::Consequence::MissenseVariant,
+        None,
+        Some(50),
+        vec![crate::seqvars::query::schema::query::Consequence::MissenseVariant],
+        true
+    )]
+    #[case::missense_not_selected(
+        ann::Consequence::MissenseVariant,
+        None,
+        Some(50),
+        vec![crate::seqvars::query::schema::query::Consequence::StopGained],
+        false
+    )]
+    #[case::upstream_beyond_dist(
+        ann::Consequence::UpstreamGeneVariant,
+        Some(1000),
+        Some(50),
+        vec![],
+        false
+    )]
+    fn passes_max_dist_to_exon(
+        #[case] csq: ann::Consequence,
+        #[case] distance: Option<i32>,
+        #[case] max_dist_to_exon: Option<i32>,
+        #[case] consequences: Vec<crate::seqvars::query::schema::query::Consequence>,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        let query = CaseQuery {
+            consequence: QuerySettingsConsequence {
+                consequences,
+                max_dist_to_exon,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let seq_var = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: "1".into(),
+                pos: 1,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            ann_fields: vec![ann::AnnField {
+                allele: mehari::annotate::seqvars::ann::Allele::Alt {
+                    alternative: "A".into(),
+                },
+                consequences: vec![csq],
+                distance,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(super::passes(&query, &seq_var)?, expected);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(vec![ann::FeatureBiotype::Coding], false, true)]
+    #[case(vec![ann::FeatureBiotype::Coding], true, false)]
+    #[case(vec![ann::FeatureBiotype::Coding, ann::FeatureBiotype::ManeSelect], true, true)]
+    #[case(
+        vec![ann::FeatureBiotype::Coding, ann::FeatureBiotype::ManePlusClinical],
+        true,
+        true
+    )]
+    fn passes_mane_transcripts_only(
+        #[case] feature_biotype: Vec<ann::FeatureBiotype>,
+        #[case] mane_transcripts_only: bool,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        use crate::seqvars::query::schema::query::Consequence;
+
+        let query = CaseQuery {
+            consequence: QuerySettingsConsequence {
+                consequences: vec![Consequence::MissenseVariant],
+                mane_transcripts_only,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let seq_var = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: "1".into(),
+                pos: 1,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            ann_fields: vec![ann::AnnField {
+                allele: mehari::annotate::seqvars::ann::Allele::Alt {
+                    alternative: "A".into(),
+                },
+                consequences: vec![ann::Consequence::MissenseVariant],
+                feature_biotype,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(super::passes(&query, &seq_var)?, expected);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(ann::FeatureBiotype::Coding, vec![], true)]
+    #[case(ann::FeatureBiotype::Noncoding, vec![], true)]
+    #[case(ann::FeatureBiotype::Coding, vec![TranscriptType::Coding], true)]
+    #[case(ann::FeatureBiotype::Noncoding, vec![TranscriptType::Coding], false)]
+    #[case(ann::FeatureBiotype::Coding, vec![TranscriptType::NonCoding], false)]
+    #[case(ann::FeatureBiotype::Noncoding, vec![TranscriptType::NonCoding], true)]
+    #[case(
+        ann::FeatureBiotype::Noncoding,
+        vec![TranscriptType::Coding, TranscriptType::NonCoding],
+        true
+    )]
+    fn passes_transcript_types(
+        #[case] feature_biotype: ann::FeatureBiotype,
+        #[case] transcript_types: Vec<TranscriptType>,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        use crate::seqvars::query::schema::query::Consequence;
+
+        let query = CaseQuery {
+            consequence: QuerySettingsConsequence {
+                consequences: vec![Consequence::MissenseVariant],
+                transcript_types,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let seq_var = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: "1".into(),
+                pos: 1,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            ann_fields: vec![ann::AnnField {
+                allele: mehari::annotate::seqvars::ann::Allele::Alt {
+                    alternative: "A".into(),
+                },
+                consequences: vec![ann::Consequence::MissenseVariant],
+                feature_biotype: vec![feature_biotype],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(super::passes(&query, &seq_var)?, expected);
+
+        Ok(())
+    }
 }
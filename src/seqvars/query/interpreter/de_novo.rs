@@ -0,0 +1,201 @@
+//! Trio de novo posterior probability from genotype likelihoods.
+//!
+//! The model follows DeNovoGear in spirit: parental genotypes have a
+//! Hardy-Weinberg prior, alleles are transmitted with Mendelian probabilities
+//! and mutate with a small rate, and the genotype likelihoods of all three
+//! samples are combined.  The posterior probability of a de novo event is the
+//! mass of all configurations that are not explained by Mendelian inheritance.
+
+use crate::seqvars::query::schema::{
+    data::{CallInfo, VariantRecord},
+    query::CaseQuery,
+};
+
+/// Prior probability of the alternate allele in the parents.
+const THETA: f64 = 1e-3;
+/// Per-allele mutation rate.
+const MU: f64 = 1e-8;
+
+/// Convert the phred-scaled likelihoods of `call_info` into likelihoods.
+fn likelihoods(call_info: &CallInfo) -> Option<[f64; 3]> {
+    match call_info.pl.as_deref() {
+        Some([rr, ra, aa]) => Some([*rr, *ra, *aa].map(|pl| 10f64.powf(-(pl as f64) / 10.0))),
+        _ => None,
+    }
+}
+
+/// Probability of transmitting the alternate allele for genotype index `gt`.
+fn transmission(gt: usize, mu: f64) -> f64 {
+    let p = gt as f64 / 2.0;
+    p * (1.0 - mu) + (1.0 - p) * mu
+}
+
+/// Probability of child genotype `child` given the parental genotypes.
+fn child_prob(child: usize, father: usize, mother: usize, mu: f64) -> f64 {
+    let a = transmission(father, mu);
+    let b = transmission(mother, mu);
+    match child {
+        0 => (1.0 - a) * (1.0 - b),
+        1 => a * (1.0 - b) + (1.0 - a) * b,
+        _ => a * b,
+    }
+}
+
+/// Compute the de novo posterior probability from the trio likelihoods.
+pub fn posterior(index: [f64; 3], father: [f64; 3], mother: [f64; 3]) -> f64 {
+    let prior = [
+        (1.0 - THETA) * (1.0 - THETA),
+        2.0 * THETA * (1.0 - THETA),
+        THETA * THETA,
+    ];
+    let mut total = 0.0;
+    let mut de_novo = 0.0;
+    for f in 0..3 {
+        for m in 0..3 {
+            for c in 0..3 {
+                let p = prior[f]
+                    * father[f]
+                    * prior[m]
+                    * mother[m]
+                    * child_prob(c, f, m, MU)
+                    * index[c];
+                total += p;
+                if child_prob(c, f, m, 0.0) == 0.0 {
+                    de_novo += p;
+                }
+            }
+        }
+    }
+    if total > 0.0 {
+        de_novo / total
+    } else {
+        0.0
+    }
+}
+
+/// Return the de novo posterior probability of the `VariantRecord`.
+///
+/// Returns `None` if the de novo mode is disabled or the likelihoods are not
+/// available for all samples of the trio.
+pub fn probability(query: &CaseQuery, seqvar: &VariantRecord) -> Option<f32> {
+    let de_novo = &query.de_novo;
+    if !de_novo.enabled {
+        return None;
+    }
+    let likelihoods_of = |sample: &str| seqvar.call_infos.get(sample).and_then(likelihoods);
+    Some(posterior(
+        likelihoods_of(&de_novo.index_sample)?,
+        likelihoods_of(&de_novo.father_sample)?,
+        likelihoods_of(&de_novo.mother_sample)?,
+    ) as f32)
+}
+
+/// Determine whether the `VariantRecord` passes the de novo filter.
+///
+/// Records without likelihoods fail if a minimal probability is given.
+pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> bool {
+    let de_novo = &query.de_novo;
+    let Some(min_probability) = de_novo.min_probability.filter(|_| de_novo.enabled) else {
+        return true;
+    };
+    let res = probability(query, seqvar).unwrap_or_default() >= min_probability;
+    if !res {
+        tracing::trace!(
+            "variant {:?} fails de novo filter {}",
+            seqvar,
+            min_probability
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::seqvars::query::schema::{
+        data::{CallInfo, VariantRecord},
+        query::{CaseQuery, QuerySettingsDeNovo},
+    };
+
+    fn seqvar(pls: [Option<[i32; 3]>; 3]) -> VariantRecord {
+        VariantRecord {
+            call_infos: ["index", "father", "mother"]
+                .into_iter()
+                .zip(pls)
+                .map(|(sample, pl)| {
+                    (
+                        sample.to_string(),
+                        CallInfo {
+                            sample: sample.to_string(),
+                            pl: pl.map(|pl| pl.to_vec()),
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn query(min_probability: Option<f32>) -> CaseQuery {
+        CaseQuery {
+            de_novo: QuerySettingsDeNovo {
+                enabled: true,
+                index_sample: "index".into(),
+                father_sample: "father".into(),
+                mother_sample: "mother".into(),
+                min_probability,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::confident_de_novo([Some([300, 0, 300]), Some([0, 300, 3000]), Some([0, 300, 3000])], 0.99, 1.0)]
+    #[case::inherited([Some([300, 0, 300]), Some([300, 0, 300]), Some([0, 300, 3000])], 0.0, 0.01)]
+    #[case::weak_parents([Some([300, 0, 300]), Some([0, 10, 100]), Some([0, 10, 100])], 0.0, 0.5)]
+    fn probability(
+        #[case] pls: [Option<[i32; 3]>; 3],
+        #[case] expected_min: f32,
+        #[case] expected_max: f32,
+    ) {
+        let probability = super::probability(&query(None), &seqvar(pls)).unwrap();
+
+        assert!(
+            (expected_min..=expected_max).contains(&probability),
+            "{}",
+            probability
+        );
+    }
+
+    #[test]
+    fn probability_missing_pl() {
+        let seqvar = seqvar([Some([300, 0, 300]), None, Some([0, 300, 3000])]);
+
+        assert_eq!(super::probability(&query(None), &seqvar), None);
+        assert!(!super::passes(&query(Some(0.5)), &seqvar));
+        assert!(super::passes(&query(None), &seqvar));
+    }
+
+    #[test]
+    fn probability_disabled() {
+        let seqvar = seqvar([
+            Some([300, 0, 300]),
+            Some([0, 300, 3000]),
+            Some([0, 300, 3000]),
+        ]);
+
+        assert_eq!(super::probability(&CaseQuery::default(), &seqvar), None);
+        assert!(super::passes(&CaseQuery::default(), &seqvar));
+    }
+
+    #[test]
+    fn passes_min_probability() {
+        let seqvar = seqvar([
+            Some([300, 0, 300]),
+            Some([0, 300, 3000]),
+            Some([0, 300, 3000]),
+        ]);
+
+        assert!(super::passes(&query(Some(0.9)), &seqvar));
+    }
+}
@@ -0,0 +1,623 @@
+//! User-defined filter expressions over variant fields.
+//!
+//! The expression language is deliberately small.  Expressions are built from
+//! literals (numbers, double-quoted strings, `true`, `false`, `null`), fields,
+//! and per-sample functions, combined with comparisons (`==`, `!=`, `<`, `<=`,
+//! `>`, `>=`, `contains`) and the boolean operators `&&`/`and`, `||`/`or`, and
+//! `!`/`not`, e.g.:
+//!
+//! ```text
+//! gnomad_exomes.af < 0.001 && (consequences contains "missense_variant" || gq("index") >= 30)
+//! ```
+//!
+//! The available fields are `chrom`, `pos`, `ref`, `alt`, `consequences`,
+//! `gene_symbol`, `hgnc_id`, and `<db>.<count>` for the population frequency
+//! databases (`gnomad_exomes`, `gnomad_genomes`, `gnomad_mtdna`, `helixmtdb`,
+//! `inhouse`) with the counts `an`, `het`, `hom`, `hemi`, `af`, and `carriers`.
+//! The functions `gt(sample)`, `gq(sample)`, `dp(sample)`, and `ad(sample)`
+//! give access to the call information.  Comparisons involving missing values
+//! (`null`) evaluate to `false`.
+
+use crate::seqvars::query::schema::data::{Af as _, Carriers as _, VariantRecord};
+
+/// Names of the population frequency databases.
+const FREQUENCY_DBS: &[&str] = &[
+    "gnomad_exomes",
+    "gnomad_genomes",
+    "gnomad_mtdna",
+    "helixmtdb",
+    "inhouse",
+];
+
+/// Names of the counts for the population frequency databases.
+const FREQUENCY_COUNTS: &[&str] = &["an", "het", "hom", "hemi", "af", "carriers"];
+
+/// Names of the plain fields.
+const FIELDS: &[&str] = &[
+    "chrom",
+    "pos",
+    "ref",
+    "alt",
+    "consequences",
+    "gene_symbol",
+    "hgnc_id",
+];
+
+/// Names of the per-sample functions.
+const FUNCTIONS: &[&str] = &["gt", "gq", "dp", "ad"];
+
+/// A value that an expression evaluates to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Missing value.
+    Null,
+    /// Boolean value.
+    Bool(bool),
+    /// Numeric value.
+    Number(f64),
+    /// String value.
+    String(String),
+    /// List of strings.
+    List(Vec<String>),
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `contains`
+    Contains,
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A literal value.
+    Literal(Value),
+    /// A (dotted) field name.
+    Field(String),
+    /// A per-sample function call with the sample name.
+    Call(String, String),
+    /// Boolean negation.
+    Not(Box<Expr>),
+    /// Boolean conjunction.
+    And(Box<Expr>, Box<Expr>),
+    /// Boolean disjunction.
+    Or(Box<Expr>, Box<Expr>),
+    /// Comparison.
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+/// Supporting code for `Expr`.
+pub(crate) mod expr {
+    /// Error type for `Expr::parse()` and `Expr::eval()`.
+    #[derive(thiserror::Error, Debug, Clone, PartialEq)]
+    pub enum Error {
+        #[error("Unexpected character {1:?} at position {0}")]
+        UnexpectedChar(usize, char),
+        #[error("Unterminated string literal")]
+        UnterminatedString,
+        #[error("Invalid number: {0}")]
+        InvalidNumber(String),
+        #[error("Unexpected end of expression")]
+        UnexpectedEnd,
+        #[error("Unexpected token: {0}")]
+        UnexpectedToken(String),
+        #[error("Unknown field: {0}")]
+        UnknownField(String),
+        #[error("Unknown function: {0}")]
+        UnknownFunction(String),
+        #[error("Invalid comparison: {0:?} {1:?} {2:?}")]
+        InvalidComparison(super::Value, super::CmpOp, super::Value),
+        #[error("Expected boolean value but got: {0:?}")]
+        NotBoolean(super::Value),
+    }
+}
+
+/// Tokens of the expression language.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+/// Split `input` into tokens.
+fn tokenize(input: &str) -> Result<Vec<Token>, expr::Error> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|c| *c == '"')
+                .ok_or(expr::Error::UnterminatedString)?;
+            tokens.push(Token::String(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || ".+-".contains(chars[i]))
+            {
+                // Only allow sign directly after exponent.
+                if "+-".contains(chars[i]) && !"eE".contains(chars[i - 1]) {
+                    break;
+                }
+                i += 1;
+            }
+            let s = chars[start..i].iter().collect::<String>();
+            tokens.push(Token::Number(
+                s.parse()
+                    .map_err(|_| expr::Error::InvalidNumber(s.clone()))?,
+            ));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_.".contains(chars[i])) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let two = chars[i..(i + 2).min(chars.len())]
+                .iter()
+                .collect::<String>();
+            let op = match two.as_str() {
+                "&&" => Some("&&"),
+                "||" => Some("||"),
+                "==" => Some("=="),
+                "!=" => Some("!="),
+                "<=" => Some("<="),
+                ">=" => Some(">="),
+                _ => None,
+            };
+            if let Some(op) = op {
+                tokens.push(Token::Op(op));
+                i += 2;
+            } else {
+                let op = match c {
+                    '<' => "<",
+                    '>' => ">",
+                    '!' => "!",
+                    ',' => ",",
+                    _ => return Err(expr::Error::UnexpectedChar(i, c)),
+                };
+                tokens.push(Token::Op(op));
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, expr::Error> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token.ok_or(expr::Error::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), expr::Error> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(expr::Error::UnexpectedToken(format!("{:?}", token)))
+        }
+    }
+
+    /// Check whether the next token is the operator or keyword `op`/`keyword` and consume it.
+    fn accept(&mut self, op: &str, keyword: &str) -> bool {
+        let matches = match self.peek() {
+            Some(Token::Op(o)) => *o == op,
+            Some(Token::Ident(ident)) => ident == keyword,
+            _ => false,
+        };
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, expr::Error> {
+        let mut lhs = self.parse_and()?;
+        while self.accept("||", "or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, expr::Error> {
+        let mut lhs = self.parse_not()?;
+        while self.accept("&&", "and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.parse_not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, expr::Error> {
+        if self.accept("!", "not") {
+            Ok(Expr::Not(Box::new(self.parse_not()?)))
+        } else {
+            self.parse_cmp()
+        }
+    }
+
+    fn parse_cmp(&mut self) -> Result<Expr, expr::Error> {
+        let lhs = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => Some(CmpOp::Eq),
+            Some(Token::Op("!=")) => Some(CmpOp::Ne),
+            Some(Token::Op("<")) => Some(CmpOp::Lt),
+            Some(Token::Op("<=")) => Some(CmpOp::Le),
+            Some(Token::Op(">")) => Some(CmpOp::Gt),
+            Some(Token::Op(">=")) => Some(CmpOp::Ge),
+            Some(Token::Ident(ident)) if ident == "contains" => Some(CmpOp::Contains),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.pos += 1;
+            let rhs = self.parse_primary()?;
+            Ok(Expr::Cmp(op, Box::new(lhs), Box::new(rhs)))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, expr::Error> {
+        match self.next()? {
+            Token::Number(value) => Ok(Expr::Literal(Value::Number(value))),
+            Token::String(value) => Ok(Expr::Literal(Value::String(value))),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.peek() == Some(&Token::LParen) => {
+                    if !FUNCTIONS.contains(&ident.as_str()) {
+                        return Err(expr::Error::UnknownFunction(ident));
+                    }
+                    self.expect(Token::LParen)?;
+                    let sample = match self.next()? {
+                        Token::String(sample) => sample,
+                        token => return Err(expr::Error::UnexpectedToken(format!("{:?}", token))),
+                    };
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Call(ident, sample))
+                }
+                _ => {
+                    let is_known = FIELDS.contains(&ident.as_str())
+                        || ident.split_once('.').is_some_and(|(db, count)| {
+                            FREQUENCY_DBS.contains(&db) && FREQUENCY_COUNTS.contains(&count)
+                        });
+                    if is_known {
+                        Ok(Expr::Field(ident))
+                    } else {
+                        Err(expr::Error::UnknownField(ident))
+                    }
+                }
+            },
+            token => Err(expr::Error::UnexpectedToken(format!("{:?}", token))),
+        }
+    }
+}
+
+impl Expr {
+    /// Parse the expression from the given string.
+    pub fn parse(input: &str) -> Result<Self, expr::Error> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(expr::Error::UnexpectedToken(format!("{:?}", token)));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluate the expression for the given `seqvar`.
+    pub fn eval(&self, seqvar: &VariantRecord) -> Result<Value, expr::Error> {
+        Ok(match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Field(name) => field_value(name, seqvar),
+            Expr::Call(name, sample) => call_value(name, sample, seqvar),
+            Expr::Not(expr) => Value::Bool(!expr.eval_bool(seqvar)?),
+            Expr::And(lhs, rhs) => Value::Bool(lhs.eval_bool(seqvar)? && rhs.eval_bool(seqvar)?),
+            Expr::Or(lhs, rhs) => Value::Bool(lhs.eval_bool(seqvar)? || rhs.eval_bool(seqvar)?),
+            Expr::Cmp(op, lhs, rhs) => {
+                Value::Bool(compare(*op, lhs.eval(seqvar)?, rhs.eval(seqvar)?)?)
+            }
+        })
+    }
+
+    /// Evaluate the expression for the given `seqvar` to a boolean, `null` is `false`.
+    fn eval_bool(&self, seqvar: &VariantRecord) -> Result<bool, expr::Error> {
+        match self.eval(seqvar)? {
+            Value::Bool(value) => Ok(value),
+            Value::Null => Ok(false),
+            value => Err(expr::Error::NotBoolean(value)),
+        }
+    }
+}
+
+/// Return value of the field with the given `name`, validated at parse time.
+fn field_value(name: &str, seqvar: &VariantRecord) -> Value {
+    let first_ann = seqvar.ann_fields.first();
+    match name {
+        "chrom" => Value::String(seqvar.vcf_variant.chrom.clone()),
+        "pos" => Value::Number(seqvar.vcf_variant.pos as f64),
+        "ref" => Value::String(seqvar.vcf_variant.ref_allele.clone()),
+        "alt" => Value::String(seqvar.vcf_variant.alt_allele.clone()),
+        "consequences" => Value::List(
+            seqvar
+                .ann_fields
+                .iter()
+                .flat_map(|ann| ann.consequences.iter().map(|csq| csq.to_string()))
+                .collect(),
+        ),
+        "gene_symbol" => first_ann
+            .map(|ann| Value::String(ann.gene_symbol.clone()))
+            .unwrap_or(Value::Null),
+        "hgnc_id" => first_ann
+            .map(|ann| Value::String(ann.gene_id.clone()))
+            .unwrap_or(Value::Null),
+        _ => {
+            let freqs = &seqvar.population_frequencies;
+            let (db, count) = name.split_once('.').expect("validated at parse time");
+            let (an, het, hom, hemi, af, carriers) = match db {
+                "gnomad_exomes" => {
+                    let f = &freqs.gnomad_exomes;
+                    (f.an, f.het, f.hom, f.hemi, f.af(), f.carriers())
+                }
+                "gnomad_genomes" => {
+                    let f = &freqs.gnomad_genomes;
+                    (f.an, f.het, f.hom, f.hemi, f.af(), f.carriers())
+                }
+                "gnomad_mtdna" => {
+                    let f = &freqs.gnomad_mtdna;
+                    (f.an, f.het, f.hom, 0, f.af(), f.carriers())
+                }
+                "helixmtdb" => {
+                    let f = &freqs.helixmtdb;
+                    (f.an, f.het, f.hom, 0, f.af(), f.carriers())
+                }
+                "inhouse" => {
+                    let f = &freqs.inhouse;
+                    (f.an, f.het, f.hom, f.hemi, f.af(), f.carriers())
+                }
+                _ => unreachable!("validated at parse time"),
+            };
+            Value::Number(match count {
+                "an" => an as f64,
+                "het" => het as f64,
+                "hom" => hom as f64,
+                "hemi" => hemi as f64,
+                "af" => af as f64,
+                "carriers" => carriers as f64,
+                _ => unreachable!("validated at parse time"),
+            })
+        }
+    }
+}
+
+/// Return value of the per-sample function `name` for `sample`.
+fn call_value(name: &str, sample: &str, seqvar: &VariantRecord) -> Value {
+    let Some(call_info) = seqvar.call_infos.get(sample) else {
+        return Value::Null;
+    };
+    let number = |value: Option<f64>| value.map(Value::Number).unwrap_or(Value::Null);
+    match name {
+        "gt" => call_info
+            .genotype
+            .clone()
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+        "gq" => number(call_info.gq.map(|gq| gq as f64)),
+        "dp" => number(call_info.dp.map(|dp| dp as f64)),
+        "ad" => number(call_info.ad.map(|ad| ad as f64)),
+        _ => unreachable!("validated at parse time"),
+    }
+}
+
+/// Compare the two values with the given operator.
+fn compare(op: CmpOp, lhs: Value, rhs: Value) -> Result<bool, expr::Error> {
+    Ok(match (&lhs, &rhs) {
+        (Value::Null, _) | (_, Value::Null) => false,
+        (Value::Number(l), Value::Number(r)) => match op {
+            CmpOp::Eq => l == r,
+            CmpOp::Ne => l != r,
+            CmpOp::Lt => l < r,
+            CmpOp::Le => l <= r,
+            CmpOp::Gt => l > r,
+            CmpOp::Ge => l >= r,
+            CmpOp::Contains => return Err(expr::Error::InvalidComparison(lhs, op, rhs)),
+        },
+        (Value::String(l), Value::String(r)) => match op {
+            CmpOp::Eq => l == r,
+            CmpOp::Ne => l != r,
+            CmpOp::Contains => l.contains(r.as_str()),
+            _ => return Err(expr::Error::InvalidComparison(lhs, op, rhs)),
+        },
+        (Value::Bool(l), Value::Bool(r)) => match op {
+            CmpOp::Eq => l == r,
+            CmpOp::Ne => l != r,
+            _ => return Err(expr::Error::InvalidComparison(lhs, op, rhs)),
+        },
+        (Value::List(l), Value::String(r)) if op == CmpOp::Contains => l.contains(r),
+        _ => return Err(expr::Error::InvalidComparison(lhs, op, rhs)),
+    })
+}
+
+/// Determine whether the `VariantRecord` passes the filter expression, if any.
+pub fn passes(
+    filter_expression: Option<&Expr>,
+    seqvar: &VariantRecord,
+) -> Result<bool, anyhow::Error> {
+    let Some(filter_expression) = filter_expression else {
+        return Ok(true);
+    };
+    let result = filter_expression
+        .eval_bool(seqvar)
+        .map_err(|e| anyhow::anyhow!("problem evaluating filter expression: {}", e))?;
+    if !result {
+        tracing::trace!(
+            "variant {:?} fails filter expression {:?}",
+            seqvar,
+            filter_expression
+        );
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::annotate::seqvars::ann;
+
+    use crate::seqvars::query::schema::data::{
+        CallInfo, NuclearFrequencies, PopulationFrequencies, VariantRecord, VcfVariant,
+    };
+
+    use super::{expr, CmpOp, Expr, Value};
+
+    fn seqvar() -> VariantRecord {
+        VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: "1".into(),
+                pos: 100,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            filters: vec![],
+            call_infos: indexmap::indexmap! {
+                String::from("index") => CallInfo {
+                    sample: String::from("index"),
+                    genotype: Some("0/1".into()),
+                    gq: Some(40.0),
+                    dp: Some(30),
+                    ..Default::default()
+                },
+            },
+            ann_fields: vec![ann::AnnField {
+                allele: ann::Allele::Alt {
+                    alternative: "A".into(),
+                },
+                consequences: vec![ann::Consequence::MissenseVariant],
+                gene_symbol: "GENE".into(),
+                gene_id: "HGNC:1".into(),
+                ..Default::default()
+            }],
+            population_frequencies: PopulationFrequencies {
+                gnomad_exomes: NuclearFrequencies {
+                    an: 1000,
+                    het: 1,
+                    hom: 0,
+                    hemi: 0,
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Expr::parse("pos >= 10 && !(gq(\"index\") < 5)").unwrap(),
+            Expr::And(
+                Box::new(Expr::Cmp(
+                    CmpOp::Ge,
+                    Box::new(Expr::Field("pos".into())),
+                    Box::new(Expr::Literal(Value::Number(10.0)))
+                )),
+                Box::new(Expr::Not(Box::new(Expr::Cmp(
+                    CmpOp::Lt,
+                    Box::new(Expr::Call("gq".into(), "index".into())),
+                    Box::new(Expr::Literal(Value::Number(5.0)))
+                ))))
+            )
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("foo > 1", expr::Error::UnknownField("foo".into()))]
+    #[case("gnomad_exomes.xyz > 1", expr::Error::UnknownField("gnomad_exomes.xyz".into()))]
+    #[case("foo(\"index\") > 1", expr::Error::UnknownFunction("foo".into()))]
+    #[case("pos > ", expr::Error::UnexpectedEnd)]
+    #[case("pos > 1 )", expr::Error::UnexpectedToken("RParen".into()))]
+    #[case("gt(\"index) == 1", expr::Error::UnterminatedString)]
+    #[case("pos # 1", expr::Error::UnexpectedChar(4, '#'))]
+    fn parse_error(#[case] input: &str, #[case] expected: expr::Error) {
+        assert_eq!(Expr::parse(input).unwrap_err(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case("true", true)]
+    #[case("chrom == \"1\" and pos == 100", true)]
+    #[case("ref == \"G\" && alt != \"A\"", false)]
+    #[case("gnomad_exomes.af < 0.001", true)]
+    #[case("gnomad_exomes.af < 1e-4", false)]
+    #[case("gnomad_exomes.het <= 1 && gnomad_exomes.carriers == 1", true)]
+    #[case("consequences contains \"missense_variant\"", true)]
+    #[case("consequences contains \"stop_gained\"", false)]
+    #[case("gene_symbol == \"GENE\" && hgnc_id == \"HGNC:1\"", true)]
+    #[case("gq(\"index\") >= 30 and dp(\"index\") > 20", true)]
+    #[case("gt(\"index\") == \"0/1\"", true)]
+    #[case("ad(\"index\") > 0", false)]
+    #[case("not (gq(\"father\") >= 30)", true)]
+    #[case("gq(\"father\") >= 30 || pos > 99", true)]
+    fn passes(#[case] input: &str, #[case] expected: bool) -> Result<(), anyhow::Error> {
+        let expr = Expr::parse(input)?;
+        assert_eq!(super::passes(Some(&expr), &seqvar())?, expected);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("pos")]
+    #[case("pos contains 1")]
+    #[case("chrom < \"2\"")]
+    fn passes_error(#[case] input: &str) -> Result<(), anyhow::Error> {
+        let expr = Expr::parse(input)?;
+        assert!(super::passes(Some(&expr), &seqvar()).is_err());
+
+        Ok(())
+    }
+}
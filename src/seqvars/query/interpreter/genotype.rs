@@ -1,15 +1,22 @@
-use crate::seqvars::query::schema::{
-    data::VariantRecord,
-    query::{
-        considered_no_call, CaseQuery, GenotypeChoice, MatchesGenotypeStr as _,
-        QuerySettingsGenotype, RecessiveMode, RecessiveParents,
+use crate::{
+    common::{Chrom, GenomeRelease},
+    seqvars::query::schema::{
+        data::VariantRecord,
+        query::{
+            CaseQuery, GenotypeChoice, MatchesGenotypeStr as _, QuerySettingsGenotype,
+            RecessiveMode, RecessiveParents, ResolvedGenotype,
+        },
     },
 };
 
 /// Determine whether the `VariantRecord` passes the genotype filter.
-pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow::Error> {
+pub fn passes(
+    query: &CaseQuery,
+    seqvar: &VariantRecord,
+    genome_release: GenomeRelease,
+) -> Result<bool, anyhow::Error> {
     let result = if query.genotype.recessive_mode != RecessiveMode::Disabled {
-        passes_recessive_modes(&query.genotype, seqvar)?
+        passes_recessive_modes(&query.genotype, seqvar, genome_release)?
     } else {
         passes_non_recessive_mode(&query.genotype, seqvar)?
     };
@@ -29,16 +36,18 @@ pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow:
 /// This means
 ///
 /// - fail on chrMT/chrY
-/// - in case of chrX, require het./hom./hemi. in the index, het. in the mother and
-///   hom. ref. in the father
-/// - in case of autosomal chromosomes, require het. in index and exactly one parent
-///   and hom. ref. in other parent OR require hom. in index and het. in both parents
+/// - in case of chrX outside of the PAR, require het./hom./hemi. in the index, het. in
+///   the mother and hom. ref. in the father
+/// - in case of autosomal chromosomes and the chrX PAR, require het. in index and exactly
+///   one parent and hom. ref. in other parent OR require hom. in index and het. in both
+///   parents
 ///
 /// In the future, we could also provide the sex of the index here and include cases
 /// of X inactivation where mother is het., father is hom. ref. and index is het.
 fn passes_recessive_modes(
     query_genotype: &QuerySettingsGenotype,
     seqvar: &VariantRecord,
+    genome_release: GenomeRelease,
 ) -> Result<bool, anyhow::Error> {
     // Is/must never be called with disabled recessive mode.
     assert_ne!(query_genotype.recessive_mode, RecessiveMode::Disabled);
@@ -57,17 +66,43 @@ fn passes_recessive_modes(
     // Extract genotypes of index and potentially mother/father.
     let (index_gt, father_gt, mother_gt) = extract_trio_genotypes(query_genotype, seqvar)?;
 
-    // Branch into X-linked and autosomal recessive mode.
-    Ok(if normalized_chrom == "X" {
-        passes_recessive_mode_x_linked(index_gt, father_gt, mother_gt)
-    } else {
-        passes_recessive_mode_autosomal(
-            index_gt,
-            father_gt,
-            mother_gt,
-            query_genotype.recessive_mode,
-        )
-    })
+    // Apply the no-call strategy; no-calls that are left in are not matched by any
+    // genotype choice.  Parents compatible with any genotype are handled as missing.
+    let strategy = query_genotype.no_call_strategy;
+    let index_gt = match strategy.resolve(index_gt) {
+        ResolvedGenotype::Called(gt) | ResolvedGenotype::NoCall(gt) => gt,
+        ResolvedGenotype::Any => return Ok(true),
+        ResolvedGenotype::Drop => return Ok(false),
+    };
+    let mut parent_gts = [father_gt, mother_gt];
+    for parent_gt in parent_gts.iter_mut() {
+        if let Some(gt) = *parent_gt {
+            *parent_gt = match strategy.resolve(gt) {
+                ResolvedGenotype::Called(gt) | ResolvedGenotype::NoCall(gt) => Some(gt),
+                ResolvedGenotype::Any => None,
+                ResolvedGenotype::Drop => return Ok(false),
+            };
+        }
+    }
+    let [father_gt, mother_gt] = parent_gts;
+
+    // Branch into X-linked and autosomal recessive mode, the PAR is inherited like the
+    // autosomes.
+    let pos = usize::try_from(seqvar.vcf_variant.pos).unwrap_or_default();
+    Ok(
+        if normalized_chrom == "X"
+            && !crate::seqvars::aggregate::is_par(Chrom::X, pos, genome_release)
+        {
+            passes_recessive_mode_x_linked(index_gt, father_gt, mother_gt)
+        } else {
+            passes_recessive_mode_autosomal(
+                index_gt,
+                father_gt,
+                mother_gt,
+                query_genotype.recessive_mode,
+            )
+        },
+    )
 }
 
 /// Extract genotypes of index and potentially mother/father.
@@ -341,11 +376,12 @@ fn passes_non_recessive_mode(
             return Ok(false);
         };
 
-        if considered_no_call(genotype) {
-            // Handle case of nocall genotype.
-            if genotype_choice.include_no_call {
+        match query_genotype.no_call_strategy.resolve(genotype) {
+            ResolvedGenotype::NoCall(_) if genotype_choice.include_no_call => {
+                // Handle case of nocall genotype that is included for the sample.
                 continue;
-            } else {
+            }
+            ResolvedGenotype::NoCall(_) | ResolvedGenotype::Drop => {
                 tracing::trace!(
                     "variant {:?} fails genotype filter {:?} on sample {} (no call)",
                     seqvar,
@@ -354,17 +390,22 @@ fn passes_non_recessive_mode(
                 );
                 return Ok(false);
             }
-        } else if !genotype_choice.genotype.matches(genotype)? {
-            // Handle case of non-nocall genotype.
-            tracing::trace!(
-                "variant {:?} fails genotype filter {:?} on sample {}",
-                seqvar,
-                &query_genotype,
-                sample_name
-            );
-            return Ok(false);
-        } else {
-            // All good, check next.
+            ResolvedGenotype::Any => {
+                // No-call is compatible with any genotype, check next.
+                continue;
+            }
+            ResolvedGenotype::Called(genotype) => {
+                if !genotype_choice.genotype.matches(genotype)? {
+                    // Handle case of non-nocall genotype.
+                    tracing::trace!(
+                        "variant {:?} fails genotype filter {:?} on sample {}",
+                        seqvar,
+                        &query_genotype,
+                        sample_name
+                    );
+                    return Ok(false);
+                }
+            }
         }
     }
 
@@ -373,10 +414,11 @@ fn passes_non_recessive_mode(
 
 #[cfg(test)]
 mod test {
+    use crate::common::GenomeRelease;
     use crate::seqvars::query::schema::data::{CallInfo, VariantRecord, VcfVariant};
     use crate::seqvars::query::schema::query::{
         GenotypeChoice::{self, *},
-        QuerySettingsGenotype, RecessiveMode, SampleGenotypeChoice,
+        NoCallStrategy, QuerySettingsGenotype, RecessiveMode, SampleGenotypeChoice,
     };
 
     static INDEX_NAME: &str = "sample";
@@ -509,6 +551,8 @@ mod test {
                     ..Default::default()
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
 
         let seq_var = VariantRecord {
@@ -590,6 +634,8 @@ mod test {
                     ..Default::default()
                 },
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let sample_gts = sample_gts
             .split(',')
@@ -648,6 +694,8 @@ mod test {
                     ..Default::default()
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let seq_var = VariantRecord {
             call_infos: indexmap::indexmap! {
@@ -725,6 +773,8 @@ mod test {
                     ..Default::default()
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let seq_var = VariantRecord {
             call_infos: indexmap::indexmap! {
@@ -738,7 +788,7 @@ mod test {
         };
 
         assert_eq!(
-            super::passes_recessive_modes(&query_genotype, &seq_var)?,
+            super::passes_recessive_modes(&query_genotype, &seq_var, GenomeRelease::Grch37)?,
             expected,
             "sample_gt = {}, recessive_mode = {:?}, expected = {}",
             sample_gt,
@@ -810,6 +860,8 @@ mod test {
                     ..Default::default()
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let seq_var = VariantRecord {
             vcf_variant: VcfVariant {
@@ -827,7 +879,7 @@ mod test {
         };
 
         assert_eq!(
-            super::passes_recessive_modes(&query_genotype, &seq_var)?,
+            super::passes_recessive_modes(&query_genotype, &seq_var, GenomeRelease::Grch37)?,
             expected,
             "sample_gt = {}, recessive_mode = {:?}, expected = {}",
             sample_gt,
@@ -838,6 +890,55 @@ mod test {
         Ok(())
     }
 
+    #[rstest::rstest]
+    #[case::par1_grch37(100_000, GenomeRelease::Grch37, true)]
+    #[case::par2_grch37(155_000_000, GenomeRelease::Grch37, true)]
+    #[case::non_par_grch37(5_000_000, GenomeRelease::Grch37, false)]
+    #[case::par1_grch38(20_000, GenomeRelease::Grch38, true)]
+    #[case::par1_grch37_on_grch38(2_750_000, GenomeRelease::Grch37, false)]
+    #[case::par1_grch38_on_grch38(2_750_000, GenomeRelease::Grch38, true)]
+    fn passes_recessive_modes_x_par_singleton(
+        #[case] pos: i32,
+        #[case] genome_release: GenomeRelease,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        let query_genotype = QuerySettingsGenotype {
+            recessive_mode: RecessiveMode::CompoundHeterozygous,
+            sample_genotypes: indexmap::indexmap! {
+                String::from(INDEX_NAME) => SampleGenotypeChoice {
+                    sample: String::from(INDEX_NAME),
+                    genotype: GenotypeChoice::RecessiveIndex,
+                    ..Default::default()
+                }
+            },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
+        };
+        // Heterozygous calls are only compatible with the recessive mode inside the PAR.
+        let seq_var = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: "X".to_string(),
+                pos,
+                ..Default::default()
+            },
+            call_infos: indexmap::indexmap! {
+                INDEX_NAME.into() =>
+                CallInfo {
+                    genotype: Some("0/1".into()),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::passes_recessive_modes(&query_genotype, &seq_var, genome_release)?,
+            expected
+        );
+
+        Ok(())
+    }
+
     #[rstest::rstest]
     #[case::chry_any_fail("Y", "0/1", RecessiveMode::Any)]
     #[case::chry_any_fail("Y", "1/1", RecessiveMode::Homozygous)]
@@ -859,6 +960,8 @@ mod test {
                     ..Default::default()
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let seq_var = VariantRecord {
             vcf_variant: VcfVariant {
@@ -876,7 +979,7 @@ mod test {
         };
 
         assert!(
-            !(super::passes_recessive_modes(&query_genotype, &seq_var)?),
+            !(super::passes_recessive_modes(&query_genotype, &seq_var, GenomeRelease::Grch37)?),
             "sample_gt = {}, recessive_mode = {:?}",
             sample_gt,
             recessive_mode,
@@ -1223,6 +1326,8 @@ mod test {
                     ..Default::default()
                 },
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let sample_gts = sample_gts
             .split(',')
@@ -1250,7 +1355,7 @@ mod test {
         };
 
         assert_eq!(
-            super::passes_recessive_modes(&query_genotype, &seq_var)?,
+            super::passes_recessive_modes(&query_genotype, &seq_var, GenomeRelease::Grch37)?,
             expected,
             "sample_gt = {:?}, query_gt_index = {:?}, query_gt_father = {:?}, \
             query_gt_mother = {:?}, recessive_mode = {:?}, expected = {}",
@@ -1264,4 +1369,115 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case::per_sample_exclude("./.", Variant, false, NoCallStrategy::PerSample, false)]
+    #[case::per_sample_include("./.", Variant, true, NoCallStrategy::PerSample, true)]
+    #[case::as_ref_pass("./.", Ref, false, NoCallStrategy::TreatAsRef, true)]
+    #[case::as_ref_nopass("./.", Variant, true, NoCallStrategy::TreatAsRef, false)]
+    #[case::as_any_pass(".", Hom, false, NoCallStrategy::TreatAsAny, true)]
+    #[case::drop_nopass("./.", Any, true, NoCallStrategy::DropVariant, false)]
+    #[case::drop_called_pass("0/1", Het, false, NoCallStrategy::DropVariant, true)]
+    fn passes_non_recessive_mode_no_call_strategy(
+        #[case] sample_gt: &str,
+        #[case] query_gt: GenotypeChoice,
+        #[case] include_no_call: bool,
+        #[case] no_call_strategy: NoCallStrategy,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        let query_genotype = QuerySettingsGenotype {
+            sample_genotypes: indexmap::indexmap! {
+                String::from(INDEX_NAME) => SampleGenotypeChoice {
+                    sample: String::from(INDEX_NAME),
+                    genotype: query_gt,
+                    include_no_call,
+                    ..Default::default()
+                }
+            },
+            no_call_strategy,
+            ..Default::default()
+        };
+        let seq_var = VariantRecord {
+            call_infos: indexmap::indexmap! {
+                INDEX_NAME.into() =>
+                CallInfo {
+                    genotype: Some(sample_gt.into()),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::passes_non_recessive_mode(&query_genotype, &seq_var)?,
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::per_sample_parent("1/1,./.,0/1", NoCallStrategy::PerSample, false)]
+    #[case::as_ref_parent("0/1,./.,0/1", NoCallStrategy::TreatAsRef, true)]
+    #[case::as_ref_index("./.,0/0,0/1", NoCallStrategy::TreatAsRef, false)]
+    #[case::as_any_parent("1/1,./.,0/1", NoCallStrategy::TreatAsAny, true)]
+    #[case::as_any_index("./.,0/0,0/1", NoCallStrategy::TreatAsAny, true)]
+    #[case::drop_parent("1/1,./.,0/1", NoCallStrategy::DropVariant, false)]
+    #[case::drop_called("1/1,0/1,0/1", NoCallStrategy::DropVariant, true)]
+    fn passes_recessive_modes_no_call_strategy(
+        #[case] sample_gts: &str,
+        #[case] no_call_strategy: NoCallStrategy,
+        #[case] expected: bool,
+    ) -> Result<(), anyhow::Error> {
+        let query_genotype = QuerySettingsGenotype {
+            recessive_mode: RecessiveMode::Any,
+            sample_genotypes: indexmap::indexmap! {
+                String::from(INDEX_NAME) => SampleGenotypeChoice {
+                    sample: String::from(INDEX_NAME),
+                    genotype: RecessiveIndex,
+                    ..Default::default()
+                },
+                String::from(FATHER_NAME) => SampleGenotypeChoice {
+                    sample: String::from(FATHER_NAME),
+                    genotype: RecessiveFather,
+                    ..Default::default()
+                },
+                String::from(MOTHER_NAME) => SampleGenotypeChoice {
+                    sample: String::from(MOTHER_NAME),
+                    genotype: RecessiveMother,
+                    ..Default::default()
+                },
+            },
+            no_call_strategy,
+            ..Default::default()
+        };
+        let sample_gts = sample_gts.split(',').collect::<Vec<_>>();
+        let seq_var = VariantRecord {
+            call_infos: indexmap::indexmap! {
+                String::from(INDEX_NAME) =>
+                CallInfo {
+                    genotype: Some(sample_gts[0].into()),
+                    ..Default::default()
+                },
+                String::from(FATHER_NAME) =>
+                CallInfo {
+                    genotype: Some(sample_gts[1].into()),
+                    ..Default::default()
+                },
+                String::from(MOTHER_NAME) =>
+                CallInfo {
+                    genotype: Some(sample_gts[2].into()),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::passes_recessive_modes(&query_genotype, &seq_var, GenomeRelease::Grch37)?,
+            expected
+        );
+
+        Ok(())
+    }
 }
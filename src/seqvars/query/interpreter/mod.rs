@@ -2,27 +2,84 @@
 
 use std::collections::HashSet;
 
+use indexmap::IndexMap;
+
 mod clinvar;
 mod consequences;
+pub mod de_novo;
+pub mod filter_expression;
 mod frequency;
 mod genes_allowlist;
 mod genotype;
+pub mod problematic_regions;
 mod quality;
 mod regions_allowlist;
+pub mod regulatory;
+pub mod somatic;
+mod vcf_filter;
 
 use super::{
     annonars::Annotator,
     schema::{data::VariantRecord, query::CaseQuery},
 };
+use crate::common::{build_chrom_map, GenomeRelease};
+use crate::pbs::varfish::v1::seqvars::output as pbs_output;
+use crate::strucvars::query::{masked::MaskedDbBundle, regulatory::RegulatoryDb};
 
 /// Hold data structures that support the interpretation of one `CaseQuery`
 /// to multiple `StructuralVariant` records.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct QueryInterpreter {
     /// The case query settings.
     pub query: CaseQuery,
+    /// The genome release of the variants, e.g., for the PAR coordinates.
+    pub genome_release: GenomeRelease,
     /// Gene allowlist with HGNC IDs.
     pub hgnc_allowlist: HashSet<String>,
+    /// The parsed filter expression, if any.
+    pub filter_expression: Option<filter_expression::Expr>,
+    /// The problematic region tracks.
+    pub masked: MaskedDbBundle,
+    /// The regulatory elements.
+    pub regulatory: RegulatoryDb,
+    /// Mapping from chromosome name to index into the tracks of `masked` and `regulatory`.
+    pub chrom_map: IndexMap<String, usize>,
+}
+
+/// Filter stages that a record can fail, used for statistics.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterStage {
+    /// Population frequency filter.
+    Frequency,
+    /// Consequence filter.
+    Consequences,
+    /// Call quality filter.
+    Quality,
+    /// VCF `FILTER` column filter.
+    VcfFilter,
+    /// Gene allowlist filter.
+    GenesAllowlist,
+    /// Genomic regions allowlist filter.
+    RegionsAllowlist,
+    /// Genotype filter.
+    Genotype,
+    /// Tumor/normal somatic filter.
+    Somatic,
+    /// Trio de novo probability filter.
+    DeNovo,
+    /// Problematic regions filter.
+    ProblematicRegions,
+    /// Regulatory element filter.
+    Regulatory,
+    /// ClinVar filter.
+    Clinvar,
+    /// User-defined filter expression.
+    FilterExpression,
+    /// Recessive mode filter, applied per gene after the other filters.
+    Recessive,
 }
 
 /// Result type for `QueryInterpreter::passes_genotype()`.
@@ -30,15 +87,64 @@ pub struct QueryInterpreter {
 pub struct PassesResult {
     /// Whether genotype passes for all samples.
     pub pass_all: bool,
+    /// The first filter stage that the record failed, if any.
+    pub failed_stage: Option<FilterStage>,
 }
 
 impl QueryInterpreter {
     /// Construct new `QueryInterpreter` with the given query settings.
-    pub fn new(query: CaseQuery, hgnc_allowlist: HashSet<String>) -> Self {
-        QueryInterpreter {
+    pub fn new(
+        query: CaseQuery,
+        hgnc_allowlist: HashSet<String>,
+        genome_release: GenomeRelease,
+        masked: MaskedDbBundle,
+        regulatory: RegulatoryDb,
+    ) -> Result<Self, anyhow::Error> {
+        let filter_expression = query
+            .filter_expression
+            .as_ref()
+            .map(|expr| filter_expression::Expr::parse(expr))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("invalid filter expression: {}", e))?;
+        Ok(QueryInterpreter {
             query,
+            genome_release,
             hgnc_allowlist,
+            filter_expression,
+            masked,
+            regulatory,
+            chrom_map: build_chrom_map(),
+        })
+    }
+
+    /// Return the problematic regions annotation for `seqvar` if enabled in the query.
+    pub fn problematic_regions(
+        &self,
+        seqvar: &VariantRecord,
+    ) -> Option<pbs_output::ProblematicRegionsAnnotation> {
+        self.query
+            .problematic_regions
+            .annotate
+            .then(|| problematic_regions::annotation(&self.masked, &self.chrom_map, seqvar))
+    }
+
+    /// Return the regulatory elements overlapping `seqvar`, empty unless enabled in the query.
+    pub fn regulatory_elements(
+        &self,
+        seqvar: &VariantRecord,
+    ) -> Vec<pbs_output::RegulatoryElement> {
+        if !self.query.regulatory.annotate {
+            return Vec::new();
         }
+        regulatory::overlapping_records(&self.regulatory, &self.chrom_map, seqvar)
+            .into_iter()
+            .map(|record| pbs_output::RegulatoryElement {
+                id: record.id,
+                source: record.source.to_string(),
+                element_type: record.element_type,
+                linked_genes: record.linked_genes,
+            })
+            .collect()
     }
 
     /// Determine whether the annotated `VariantRecord` passes all criteria.
@@ -51,21 +157,46 @@ impl QueryInterpreter {
         let pass_frequency = frequency::passes(&self.query, seqvar)?;
         let pass_consequences = consequences::passes(&self.query, seqvar)?;
         let pass_quality = quality::passes(&self.query, seqvar)?;
+        let pass_vcf_filter = vcf_filter::passes(&self.query, seqvar);
         let pass_genes_allowlist = genes_allowlist::passes(&self.hgnc_allowlist, seqvar);
         let pass_regions_allowlist = regions_allowlist::passes(&self.query, seqvar);
-        let pass_genotype = genotype::passes(&self.query, seqvar)?;
-        if !pass_frequency
-            || !pass_consequences
-            || !pass_quality
-            || !pass_genes_allowlist
-            || !pass_regions_allowlist
-            || !pass_genotype
-        {
-            return Ok(PassesResult { pass_all: false });
+        let pass_genotype = genotype::passes(&self.query, seqvar, self.genome_release)?;
+        let pass_somatic = somatic::passes(&self.query, seqvar)?;
+        let pass_de_novo = de_novo::passes(&self.query, seqvar);
+        let pass_problematic_regions =
+            problematic_regions::passes(&self.query, &self.masked, &self.chrom_map, seqvar);
+        let pass_regulatory =
+            regulatory::passes(&self.query, &self.regulatory, &self.chrom_map, seqvar);
+        let pass_filter_expression =
+            filter_expression::passes(self.filter_expression.as_ref(), seqvar)?;
+        let failed_stage = [
+            (pass_frequency, FilterStage::Frequency),
+            (pass_consequences, FilterStage::Consequences),
+            (pass_quality, FilterStage::Quality),
+            (pass_vcf_filter, FilterStage::VcfFilter),
+            (pass_genes_allowlist, FilterStage::GenesAllowlist),
+            (pass_regions_allowlist, FilterStage::RegionsAllowlist),
+            (pass_genotype, FilterStage::Genotype),
+            (pass_somatic, FilterStage::Somatic),
+            (pass_de_novo, FilterStage::DeNovo),
+            (pass_problematic_regions, FilterStage::ProblematicRegions),
+            (pass_regulatory, FilterStage::Regulatory),
+            (pass_filter_expression, FilterStage::FilterExpression),
+        ]
+        .into_iter()
+        .find(|(pass, _)| !pass)
+        .map(|(_, stage)| stage);
+        if failed_stage.is_some() {
+            return Ok(PassesResult {
+                pass_all: false,
+                failed_stage,
+            });
         }
         // If we passed until here, check the presence in ClinVar which needs a database lookup.
+        let pass_clinvar = clinvar::passes(&self.query, annotator, seqvar)?;
         Ok(PassesResult {
-            pass_all: clinvar::passes(&self.query, annotator, seqvar)?,
+            pass_all: pass_clinvar,
+            failed_stage: (!pass_clinvar).then_some(FilterStage::Clinvar),
         })
     }
 }
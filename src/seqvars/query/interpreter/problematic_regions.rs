@@ -0,0 +1,120 @@
+use indexmap::IndexMap;
+
+use crate::pbs::varfish::v1::seqvars::output as pbs_output;
+use crate::seqvars::query::schema::{
+    data::VariantRecord,
+    query::{CaseQuery, ProblematicRegionType},
+};
+use crate::strucvars::query::masked::MaskedDbBundle;
+
+/// Compute the overlap of the `VariantRecord` with the problematic region tracks.
+pub fn annotation(
+    masked: &MaskedDbBundle,
+    chrom_map: &IndexMap<String, usize>,
+    seqvar: &VariantRecord,
+) -> pbs_output::ProblematicRegionsAnnotation {
+    let chrom = &seqvar.vcf_variant.chrom;
+    let begin = seqvar.vcf_variant.pos - 1;
+    let range = begin..(begin + (seqvar.vcf_variant.ref_allele.len() as i32).max(1));
+    pbs_output::ProblematicRegionsAnnotation {
+        low_complexity: masked
+            .low_complexity
+            .overlaps(chrom_map, chrom, range.clone()),
+        segdup: masked.segdup.overlaps(chrom_map, chrom, range.clone()),
+        blacklist: masked.blacklist.overlaps(chrom_map, chrom, range),
+    }
+}
+
+/// Determine whether the `VariantRecord` passes the problematic regions filter.
+pub fn passes(
+    query: &CaseQuery,
+    masked: &MaskedDbBundle,
+    chrom_map: &IndexMap<String, usize>,
+    seqvar: &VariantRecord,
+) -> bool {
+    let exclude = &query.problematic_regions.exclude;
+    if exclude.is_empty() {
+        return true;
+    }
+
+    let annotation = annotation(masked, chrom_map, seqvar);
+    let res = !exclude.iter().any(|region_type| match region_type {
+        ProblematicRegionType::LowComplexity => annotation.low_complexity,
+        ProblematicRegionType::Segdup => annotation.segdup,
+        ProblematicRegionType::Blacklist => annotation.blacklist,
+    });
+    if !res {
+        tracing::trace!(
+            "variant {:?} fails problematic regions filter {:?}",
+            seqvar,
+            exclude
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+
+    use crate::common::build_chrom_map;
+    use crate::seqvars::query::schema::{
+        data::{VariantRecord, VcfVariant},
+        query::{CaseQuery, ProblematicRegionType, QuerySettingsProblematicRegions},
+    };
+    use crate::strucvars::query::masked::{MaskedDb, MaskedDbBundle, MaskedDbRecord};
+
+    /// Masked database with one region on chr1 at the 0-based half-open `begin..end`.
+    fn masked_db(begin: i32, end: i32) -> MaskedDb {
+        let mut result = MaskedDb::empty();
+        let mut tree = ArrayBackedIntervalTree::new();
+        tree.insert(begin..end, 0);
+        tree.index();
+        result.trees[0] = tree;
+        result.records[0] = vec![MaskedDbRecord { begin, end }];
+        result
+    }
+
+    #[rstest::rstest]
+    #[case::no_exclude(&[], "1", 150, true)]
+    #[case::low_complexity(&[ProblematicRegionType::LowComplexity], "1", 150, false)]
+    #[case::low_complexity_chr(&[ProblematicRegionType::LowComplexity], "chr1", 150, false)]
+    #[case::outside(&[ProblematicRegionType::LowComplexity], "1", 250, true)]
+    #[case::other_chrom(&[ProblematicRegionType::LowComplexity], "2", 150, true)]
+    #[case::segdup(&[ProblematicRegionType::Segdup], "1", 150, true)]
+    #[case::blacklist(&[ProblematicRegionType::Blacklist], "1", 1050, false)]
+    fn passes(
+        #[case] exclude: &[ProblematicRegionType],
+        #[case] chrom: &str,
+        #[case] pos: i32,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            problematic_regions: QuerySettingsProblematicRegions {
+                annotate: false,
+                exclude: exclude.to_vec(),
+            },
+            ..Default::default()
+        };
+        let masked = MaskedDbBundle {
+            repeat: MaskedDb::empty(),
+            segdup: MaskedDb::empty(),
+            low_complexity: masked_db(99, 200),
+            blacklist: masked_db(999, 2000),
+        };
+        let seqvar = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: chrom.into(),
+                pos,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::passes(&query, &masked, &build_chrom_map(), &seqvar),
+            expected
+        );
+    }
+}
@@ -154,6 +154,7 @@ mod test {
                         ..Default::default()
                     },
                 },
+                allowed_filters: vec![],
             },
             ..Default::default()
         };
@@ -0,0 +1,100 @@
+use indexmap::IndexMap;
+
+use crate::seqvars::query::schema::{data::VariantRecord, query::CaseQuery};
+use crate::strucvars::query::regulatory::{Record, RegulatoryDb};
+
+/// Return the regulatory elements overlapping the `VariantRecord`.
+pub fn overlapping_records(
+    regulatory: &RegulatoryDb,
+    chrom_map: &IndexMap<String, usize>,
+    seqvar: &VariantRecord,
+) -> Vec<Record> {
+    let begin = seqvar.vcf_variant.pos - 1;
+    let end = begin + (seqvar.vcf_variant.ref_allele.len() as i32).max(1);
+    regulatory.records_in_range(chrom_map, &seqvar.vcf_variant.chrom, begin..end)
+}
+
+/// Determine whether the `VariantRecord` passes the regulatory element filter.
+pub fn passes(
+    query: &CaseQuery,
+    regulatory: &RegulatoryDb,
+    chrom_map: &IndexMap<String, usize>,
+    seqvar: &VariantRecord,
+) -> bool {
+    if !query.regulatory.require_overlap {
+        return true;
+    }
+
+    let res = !overlapping_records(regulatory, chrom_map, seqvar).is_empty();
+    if !res {
+        tracing::trace!(
+            "variant {:?} fails regulatory filter, no overlapping element",
+            seqvar
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+
+    use crate::common::{build_chrom_map, CHROMS};
+    use crate::seqvars::query::schema::{
+        data::{VariantRecord, VcfVariant},
+        query::{CaseQuery, QuerySettingsRegulatory},
+    };
+    use crate::strucvars::query::regulatory::{Record, RegulatoryDb, Source};
+
+    #[rstest::rstest]
+    #[case::not_required(false, "1", 50, true)]
+    #[case::overlap(true, "1", 150, true)]
+    #[case::overlap_chr(true, "chr1", 101, true)]
+    #[case::no_overlap(true, "1", 100, false)]
+    #[case::other_chrom(true, "2", 150, false)]
+    fn passes(
+        #[case] require_overlap: bool,
+        #[case] chrom: &str,
+        #[case] pos: i32,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            regulatory: QuerySettingsRegulatory {
+                annotate: false,
+                require_overlap,
+            },
+            ..Default::default()
+        };
+        let mut regulatory = RegulatoryDb {
+            records: CHROMS.iter().map(|_| Vec::new()).collect(),
+            trees: CHROMS
+                .iter()
+                .map(|_| ArrayBackedIntervalTree::new())
+                .collect(),
+        };
+        regulatory.trees[0].insert(100..200, 0);
+        regulatory.records[0].push(Record {
+            begin: 100,
+            end: 200,
+            id: "ENST1".into(),
+            source: Source::Promoter,
+            element_type: "promoter".into(),
+            linked_genes: vec!["GENE1".into()],
+        });
+        regulatory.trees.iter_mut().for_each(|tree| tree.index());
+        let seqvar = VariantRecord {
+            vcf_variant: VcfVariant {
+                chrom: chrom.into(),
+                pos,
+                ref_allele: "G".into(),
+                alt_allele: "A".into(),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::passes(&query, &regulatory, &build_chrom_map(), &seqvar),
+            expected
+        );
+    }
+}
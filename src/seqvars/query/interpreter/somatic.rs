@@ -0,0 +1,155 @@
+use crate::pbs::varfish::v1::seqvars::output::SomaticStatus;
+use crate::seqvars::query::schema::{
+    data::{CallInfo, VariantRecord},
+    query::CaseQuery,
+};
+
+/// Return the variant allele fraction of the call, if AD and DP are available.
+fn vaf(call_info: &CallInfo) -> Option<f32> {
+    match (call_info.ad, call_info.dp) {
+        (Some(ad), Some(dp)) if dp > 0 => Some(ad as f32 / dp as f32),
+        _ => None,
+    }
+}
+
+/// Return the somatic status of the `VariantRecord` in the somatic query mode.
+///
+/// Returns `None` if the somatic mode is disabled.  Without a normal sample
+/// or without VAF in the normal sample, the variant is considered somatic.
+pub fn somatic_status(query: &CaseQuery, seqvar: &VariantRecord) -> Option<SomaticStatus> {
+    let somatic = &query.somatic;
+    if !somatic.enabled {
+        return None;
+    }
+
+    let normal_vaf = somatic
+        .normal_sample
+        .as_ref()
+        .and_then(|normal_sample| seqvar.call_infos.get(normal_sample))
+        .and_then(vaf);
+    let max_normal_vaf = somatic.max_normal_vaf.unwrap_or(0.0);
+    Some(match normal_vaf {
+        Some(normal_vaf) if normal_vaf > max_normal_vaf => SomaticStatus::Germline,
+        _ => SomaticStatus::Somatic,
+    })
+}
+
+/// Determine whether the `VariantRecord` passes the somatic filter.
+pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> Result<bool, anyhow::Error> {
+    let somatic = &query.somatic;
+    if !somatic.enabled {
+        return Ok(true);
+    }
+
+    let tumor_call_info = seqvar
+        .call_infos
+        .get(&somatic.tumor_sample)
+        .ok_or_else(|| anyhow::anyhow!("tumor sample {} not found", &somatic.tumor_sample))?;
+    if let Some(min_tumor_vaf) = somatic.min_tumor_vaf {
+        if vaf(tumor_call_info).unwrap_or_default() < min_tumor_vaf {
+            tracing::trace!(
+                "variant {:?} fails tumor VAF filter {}",
+                seqvar,
+                min_tumor_vaf
+            );
+            return Ok(false);
+        }
+    }
+
+    let res =
+        somatic.include_germline || somatic_status(query, seqvar) == Some(SomaticStatus::Somatic);
+    if !res {
+        tracing::trace!("variant {:?} fails somatic filter (germline)", seqvar);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pbs::varfish::v1::seqvars::output::SomaticStatus;
+    use crate::seqvars::query::schema::{
+        data::{CallInfo, VariantRecord},
+        query::{CaseQuery, QuerySettingsSomatic},
+    };
+
+    fn seqvar(tumor_ad_dp: (i32, i32), normal_ad_dp: Option<(i32, i32)>) -> VariantRecord {
+        let mut call_infos = indexmap::indexmap! {
+            String::from("tumor") => CallInfo {
+                sample: String::from("tumor"),
+                ad: Some(tumor_ad_dp.0),
+                dp: Some(tumor_ad_dp.1),
+                ..Default::default()
+            },
+        };
+        if let Some((ad, dp)) = normal_ad_dp {
+            call_infos.insert(
+                String::from("normal"),
+                CallInfo {
+                    sample: String::from("normal"),
+                    ad: Some(ad),
+                    dp: Some(dp),
+                    ..Default::default()
+                },
+            );
+        }
+        VariantRecord {
+            call_infos,
+            ..Default::default()
+        }
+    }
+
+    fn query(include_germline: bool) -> CaseQuery {
+        CaseQuery {
+            somatic: QuerySettingsSomatic {
+                enabled: true,
+                tumor_sample: String::from("tumor"),
+                normal_sample: Some(String::from("normal")),
+                min_tumor_vaf: Some(0.05),
+                max_normal_vaf: Some(0.02),
+                include_germline,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case((10, 100), Some((0, 100)), false, true, Some(SomaticStatus::Somatic))]
+    #[case((1, 100), Some((0, 100)), false, false, Some(SomaticStatus::Somatic))]
+    #[case((50, 100), Some((50, 100)), false, false, Some(SomaticStatus::Germline))]
+    #[case((50, 100), Some((50, 100)), true, true, Some(SomaticStatus::Germline))]
+    #[case((50, 100), None, false, true, Some(SomaticStatus::Somatic))]
+    fn passes(
+        #[case] tumor_ad_dp: (i32, i32),
+        #[case] normal_ad_dp: Option<(i32, i32)>,
+        #[case] include_germline: bool,
+        #[case] expected_passes: bool,
+        #[case] expected_status: Option<SomaticStatus>,
+    ) -> Result<(), anyhow::Error> {
+        let query = query(include_germline);
+        let seqvar = seqvar(tumor_ad_dp, normal_ad_dp);
+
+        assert_eq!(super::passes(&query, &seqvar)?, expected_passes);
+        assert_eq!(super::somatic_status(&query, &seqvar), expected_status);
+
+        Ok(())
+    }
+
+    #[test]
+    fn passes_disabled() -> Result<(), anyhow::Error> {
+        let query = CaseQuery::default();
+        let seqvar = seqvar((0, 100), None);
+
+        assert!(super::passes(&query, &seqvar)?);
+        assert_eq!(super::somatic_status(&query, &seqvar), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn passes_missing_tumor() {
+        let query = query(false);
+        let seqvar = VariantRecord::default();
+
+        assert!(super::passes(&query, &seqvar).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::seqvars::query::schema::{data::VariantRecord, query::CaseQuery};
+
+/// Determine whether the `VariantRecord` passes the VCF `FILTER` column filter.
+///
+/// All values of the `FILTER` column must be acceptable.  Records without a
+/// `FILTER` value (`.`) always pass.
+pub fn passes(query: &CaseQuery, seqvar: &VariantRecord) -> bool {
+    let allowed_filters = &query.quality.allowed_filters;
+    if allowed_filters.is_empty() {
+        return true;
+    }
+
+    let res = seqvar
+        .filters
+        .iter()
+        .all(|filter| allowed_filters.contains(filter));
+    if !res {
+        tracing::trace!(
+            "variant {:?} fails VCF filter {:?}",
+            seqvar,
+            allowed_filters
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::seqvars::query::schema::{
+        data::VariantRecord,
+        query::{CaseQuery, QuerySettingsQuality},
+    };
+
+    #[rstest::rstest]
+    #[case(&[], &[], true)]
+    #[case(&[], &["LowQual"], true)]
+    #[case(&["PASS"], &[], true)]
+    #[case(&["PASS"], &["PASS"], true)]
+    #[case(&["PASS"], &["LowQual"], false)]
+    #[case(&["PASS", "DRAGENHardQUAL"], &["DRAGENHardQUAL"], true)]
+    #[case(&["PASS", "DRAGENHardQUAL"], &["DRAGENHardQUAL", "LowDepth"], false)]
+    fn passes(#[case] allowed_filters: &[&str], #[case] filters: &[&str], #[case] expected: bool) {
+        let query = CaseQuery {
+            quality: QuerySettingsQuality {
+                allowed_filters: allowed_filters.iter().map(|s| s.to_string()).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let seqvar = VariantRecord {
+            filters: filters.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(super::passes(&query, &seqvar), expected);
+    }
+}
@@ -1,12 +1,16 @@
 //! Code implementing the "seqvars query" sub command.
 
+pub mod acmg;
 pub mod annonars;
+pub mod digenic;
 pub mod hpo;
 pub mod interpreter;
+pub mod pedigree;
+pub mod ranking;
 pub mod schema;
 pub mod sorting;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::{BufRead, Write};
 use std::time::Instant;
 
@@ -20,7 +24,10 @@ use mehari::annotate::seqvars::CHROM_TO_CHROM_NO;
 use mehari::common::noodles::NoodlesVariantReader as _;
 use rand_core::{RngCore, SeedableRng};
 use schema::data::{TryFromVcf as _, VariantRecord};
-use schema::query::{CaseQuery, GenotypeChoice, RecessiveMode, SampleGenotypeChoice};
+use schema::query::{
+    CaseQuery, GenotypeChoice, QuerySettingsGenotype, RecessiveMode, ResolvedGenotype,
+    SampleGenotypeChoice,
+};
 use thousands::Separable;
 use tokio::io::AsyncWriteExt as _;
 use uuid::Uuid;
@@ -33,6 +40,16 @@ use crate::{common::trace_rss_now, common::GenomeRelease};
 use self::annonars::Annotator;
 use self::sorting::{ByCoordinate, ByHgncId};
 
+/// Strategy for handling variants with annotations for more than one gene.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GeneStrategy {
+    /// Only consider the gene of the first annotation.
+    #[default]
+    First,
+    /// Write one record per affected gene, with the worst annotation of the gene.
+    PerGene,
+}
+
 /// Command line arguments for `seqvars query` sub command.
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Run query for seqvars", long_about = None)]
@@ -46,25 +63,63 @@ pub struct Args {
     /// The case UUID.
     #[arg(long)]
     pub case_uuid: Option<uuid::Uuid>,
-    /// Path to worker database to use for querying.
+    /// Path to worker database to use for querying, may be a remote location.
     #[arg(long)]
     pub path_db: String,
-    /// Path to inhouse rocksdb folder.
+    /// Path to inhouse rocksdb folder, may be a remote location.
     #[arg(long)]
     pub path_inhouse_db: Option<String>,
     /// Path to query JSON file.
     #[arg(long)]
     pub path_query_json: String,
-    /// Path to input TSV file.
+    /// Optional path to PLINK PED file.
+    ///
+    /// Used for deriving the genotype settings with `--inheritance-model` and for
+    /// checking recessive inheritance with grandparents and unaffected siblings.
+    #[arg(long)]
+    pub path_ped: Option<String>,
+    /// Inheritance model to derive the genotype settings with from `--path-ped`.
+    #[arg(long, value_enum, requires = "path_ped")]
+    pub inheritance_model: Option<pedigree::InheritanceModel>,
+    /// Path to input TSV file, `-` for stdin.
     #[arg(long)]
     pub path_input: String,
-    /// Path to the output TSV file.
+    /// Path to the output TSV file, `-` for stdout.
     #[arg(long)]
     pub path_output: String,
 
     /// Optional maximal number of total records to write out.
+    ///
+    /// If there are more records, the top records by ClinVar status, consequence
+    /// impact, and frequency are kept.
     #[arg(long)]
     pub max_results: Option<usize>,
+    /// Optional path to write records beyond `--max-results` to, same format as the output.
+    #[arg(long, requires = "max_results")]
+    pub path_output_spillover: Option<String>,
+    /// Optional path to headered TSV file with digenic gene pairs (`hgnc_id_a`, `hgnc_id_b`).
+    ///
+    /// Experimental, enables the digenic candidate pair mode.
+    #[arg(long, requires = "path_output_digenic")]
+    pub path_digenic_pairs: Option<String>,
+    /// Optional path to the TSV file to write digenic candidate pairs to.
+    #[arg(long, requires = "path_digenic_pairs")]
+    pub path_output_digenic: Option<String>,
+    /// Optional path to write the query statistics to as JSON.
+    #[arg(long)]
+    pub path_stats_output: Option<String>,
+    /// Strategy for variants with annotations for more than one gene.
+    #[arg(long, value_enum, default_value_t = GeneStrategy::First)]
+    pub gene_strategy: GeneStrategy,
+    /// Approximate memory to use for the in-memory buffers of the external sorting.
+    #[arg(long, default_value = "64MiB")]
+    pub sort_memory: bytesize::ByteSize,
+    /// Tuning of the in-house RocksDB.
+    #[clap(flatten)]
+    pub rocksdb: crate::common::rocksdb_tuning::RocksdbArgs,
+    /// Number of threads to use for the external sorting, defaults to number of cores.
+    #[arg(long)]
+    pub num_threads: Option<usize>,
     /// Optional seed for RNG.
     #[arg(long)]
     pub rng_seed: Option<u64>,
@@ -74,24 +129,74 @@ pub struct Args {
 }
 
 /// Utility struct to store statistics about counts.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, serde::Serialize)]
 struct QueryStats {
     pub count_passed: usize,
     pub count_total: usize,
     pub passed_by_consequences:
         indexmap::IndexMap<mehari::annotate::seqvars::ann::Consequence, usize>,
+    pub dropped_by_filter: indexmap::IndexMap<interpreter::FilterStage, usize>,
+    /// Whether the output was truncated to `--max-results` by ranking.
+    pub truncated: bool,
+}
+
+/// Result of checking the variants of a gene against the recessive mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GenePasses {
+    /// The gene does not pass.
+    Fails,
+    /// The gene passes.
+    Passes,
+    /// Only a single qualifying het. variant has been found in compound heterozygous mode.
+    PartialHit,
+}
+
+/// Resolve the genotype of `sample` in `seqvar` according to the no-call strategy.
+///
+/// Returns `None` if the variant is to be dropped and `Some(None)` if the
+/// genotype is compatible with any genotype.
+fn resolve_genotype(
+    query_genotype: &QuerySettingsGenotype,
+    seqvar: &VariantRecord,
+    sample: &str,
+) -> Result<Option<Option<common::Genotype>>, anyhow::Error> {
+    let gt_str = seqvar
+        .call_infos
+        .get(sample)
+        .and_then(|call_info| call_info.genotype.as_deref())
+        .ok_or_else(|| anyhow::anyhow!("no GT for sample {} in {:?}", sample, seqvar))?;
+    Ok(match query_genotype.no_call_strategy.resolve(gt_str) {
+        ResolvedGenotype::Called(gt_str) => {
+            Some(Some(gt_str.parse().map_err(|e| {
+                anyhow::anyhow!("could not parse genotype of {}: {}", sample, e)
+            })?))
+        }
+        ResolvedGenotype::NoCall(_) => Some(Some(common::Genotype::WithNoCall)),
+        ResolvedGenotype::Any => Some(None),
+        ResolvedGenotype::Drop => None,
+    })
 }
 
 /// Checks whether the variants pass through the query interpreter.
-fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bool, anyhow::Error> {
+///
+/// The `relatives` of the index beyond the parents are used to exclude hom. alt.
+/// variants that are also hom. alt. in an unaffected sibling and compound
+/// heterozygous candidates where both variants are inherited from the same side
+/// or are both carried by an unaffected sibling.
+fn passes_for_gene(
+    query: &CaseQuery,
+    seqvars: &Vec<VariantRecord>,
+    relatives: &pedigree::Relatives,
+) -> Result<GenePasses, anyhow::Error> {
     // Short-circuit in case of disabled recessive mode.
     if query.genotype.recessive_mode == RecessiveMode::Disabled {
-        return Ok(true);
+        return Ok(GenePasses::Passes);
     }
 
     // Extract family information for recessive mode.
-    let (index, parents) = {
+    let (index, father, parents) = {
         let mut index = String::new();
+        let mut father = None;
         let mut parents = Vec::new();
         for (sample_name, SampleGenotypeChoice { genotype, .. }) in
             query.genotype.sample_genotypes.iter()
@@ -100,13 +205,17 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
                 GenotypeChoice::RecessiveIndex => {
                     index.clone_from(sample_name);
                 }
-                GenotypeChoice::RecessiveFather | GenotypeChoice::RecessiveMother => {
+                GenotypeChoice::RecessiveFather => {
+                    father = Some(sample_name.clone());
+                    parents.push(sample_name.clone());
+                }
+                GenotypeChoice::RecessiveMother => {
                     parents.push(sample_name.clone());
                 }
                 _ => (),
             }
         }
-        (index, parents)
+        (index, father, parents)
     };
     tracing::debug!("index = {}, parents ={:?}", &index, &parents);
 
@@ -115,54 +224,43 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
     let mut seen_het_parents = BTreeSet::new();
     let mut seen_ref_parents = BTreeSet::new();
     let mut seen_index_het: usize = 0;
+    let mut het_variants = Vec::new();
 
     // Go over all variants and try to find single variant compatible with hom. recessive
     // mode or at least two variants compatible with compound heterozygous mode.
-    for seqvar in seqvars {
-        // Get parsed index genotype.
-        let index_gt: common::Genotype = seqvar
-            .call_infos
-            .get(&index)
-            .expect("no call info for index")
-            .genotype
-            .as_ref()
-            .expect("no GT for index")
-            .parse()
-            .map_err(|e| anyhow::anyhow!("could not parse index genotype: {}", e))?;
+    'seqvars: for seqvar in seqvars {
+        // Get parsed index genotype, skip variant if the index has a no-call.
+        let index_gt = match resolve_genotype(&query.genotype, seqvar, &index)? {
+            Some(Some(gt)) => gt,
+            Some(None) | None => continue,
+        };
 
         tracing::debug!("seqvar = {:?}, index_gt = {:?}", &seqvar, &index_gt);
 
-        // Get parent genotypes and count hom. alt parents and het. parents.
-        let parent_gts = parents
-            .iter()
-            .map(|parent_name| {
-                seqvar
-                    .call_infos
-                    .get(parent_name)
-                    .expect("no call info for parent")
-                    .genotype
-                    .as_ref()
-                    .expect("no GT for parent")
-                    .parse::<common::Genotype>()
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        let homalt_parents = parents
+        // Get parent genotypes and count hom. alt parents and het. parents.  Parents
+        // with no-calls compatible with any genotype are ignored for this variant.
+        let mut parent_gts = Vec::new();
+        for parent_name in parents.iter() {
+            match resolve_genotype(&query.genotype, seqvar, parent_name)? {
+                Some(Some(gt)) => parent_gts.push((parent_name, gt)),
+                Some(None) => (),
+                None => continue 'seqvars,
+            }
+        }
+        let homalt_parents = parent_gts
             .iter()
-            .zip(parent_gts.iter())
-            .filter(|(_, gt)| **gt == common::Genotype::HomAlt)
-            .map(|(name, _)| name.clone())
+            .filter(|(_, gt)| *gt == common::Genotype::HomAlt)
+            .map(|(name, _)| (*name).clone())
             .collect::<Vec<_>>();
-        let het_parents = parents
+        let het_parents = parent_gts
             .iter()
-            .zip(parent_gts.iter())
-            .filter(|(_, gt)| **gt == common::Genotype::Het)
-            .map(|(name, _)| name.clone())
+            .filter(|(_, gt)| *gt == common::Genotype::Het)
+            .map(|(name, _)| (*name).clone())
             .collect::<Vec<_>>();
-        let ref_parents = parents
+        let ref_parents = parent_gts
             .iter()
-            .zip(parent_gts.iter())
-            .filter(|(_, gt)| **gt == common::Genotype::HomRef)
-            .map(|(name, _)| name.clone())
+            .filter(|(_, gt)| *gt == common::Genotype::HomRef)
+            .map(|(name, _)| (*name).clone())
             .collect::<Vec<_>>();
         tracing::debug!(
             "seqvar = {:?}, homalt_parents = {:?}, het_parents = {:?}, ref_parents = {:?}",
@@ -190,9 +288,12 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
                 if het_parents.len() != parent_gts.len() {
                     // Skip this variant, any given parent must be het.
                     continue;
+                } else if relatives.sibling_hom_alt(seqvar) {
+                    // Skip this variant, unaffected sibling is hom. alt.
+                    continue;
                 } else {
                     // All good, this variant supports the recessive mode for the gene.
-                    return Ok(true);
+                    return Ok(GenePasses::Passes);
                 }
             }
         } else if index_gt == common::Genotype::Het {
@@ -201,6 +302,13 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
                 RecessiveMode::CompoundHeterozygous | RecessiveMode::Any
             ) {
                 // Case 2: index het, one parent het./other. ref.?
+                let side = het_parents.first().map(|name| {
+                    if Some(name) == father.as_ref() {
+                        pedigree::Side::Paternal
+                    } else {
+                        pedigree::Side::Maternal
+                    }
+                });
                 match parent_gts.len() {
                     0 => {
                         // No parents, all good.
@@ -233,6 +341,7 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
                     _ => unreachable!("More than two parents?"),
                 }
                 seen_index_het += 1;
+                het_variants.push(relatives.het_variant(seqvar, side));
             }
         } else {
             // Skip this variant, index is ref.
@@ -246,12 +355,21 @@ fn passes_for_gene(query: &CaseQuery, seqvars: &Vec<VariantRecord>) -> Result<bo
             RecessiveMode::CompoundHeterozygous | RecessiveMode::Any
         ) {
             // Check recessive condition.  We need to have at least two variants and all parents must
-            // have been seen as het. and hom. ref.
-            seen_index_het >= 2
+            // have been seen as het. and hom. ref.  Further, there must be a pair of variants that
+            // is compatible with the relatives.
+            if seen_index_het >= 2
                 && seen_het_parents.len() == parents.len()
                 && seen_ref_parents.len() == parents.len()
+                && pedigree::has_comphet_pair(&het_variants)
+            {
+                GenePasses::Passes
+            } else if query.genotype.report_partial_hits && seen_index_het == 1 {
+                GenePasses::PartialHit
+            } else {
+                GenePasses::Fails
+            }
         } else {
-            false
+            GenePasses::Fails
         },
     )
 }
@@ -264,6 +382,7 @@ async fn run_query(
     args: &Args,
     annotator: &annonars::Annotator,
     inhouse: &Option<inhouse::Dbs>,
+    relatives: &pedigree::Relatives,
     rng: &mut rand::rngs::StdRng,
 ) -> Result<QueryStats, anyhow::Error> {
     let start_time = common::now_as_pbjson_timestamp();
@@ -274,6 +393,16 @@ async fn run_query(
 
     // Buffer for generating UUIDs.
     let mut uuid_buf = [0u8; 16];
+    // HGNC IDs of the genes that are partial hits in compound heterozygous mode.
+    let mut partial_hit_hgnc_ids = HashSet::new();
+    // Digenic gene pairs and number of qualifying het. variants per gene, if enabled.
+    let digenic_pairs = args
+        .path_digenic_pairs
+        .as_ref()
+        .map(|path| digenic::load_gene_pairs(path))
+        .transpose()?;
+    let digenic_index = interpreter.query.genotype.recessive_index().ok();
+    let mut het_counts = HashMap::new();
 
     // Open VCF file, create reader, and read header.
     let mut input_reader = common::noodles::open_vcf_reader(&args.path_input)
@@ -287,6 +416,7 @@ async fn run_query(
     let path_by_hgnc = tmp_dir.path().join("by_hgnc_filtered.jsonl");
     let path_by_coord = tmp_dir.path().join("by_coord.jsonl");
     let path_noheader = tmp_dir.path().join("noheader.jsonl");
+    let path_noheader_spillover = tmp_dir.path().join("noheader_spillover.jsonl");
 
     // Read through input records using the query interpreter as a filter and write to
     // temporary file for unsorted records.
@@ -311,23 +441,36 @@ async fn run_query(
                 record_seqvar
             };
 
-            if interpreter.passes(&record_seqvar, annotator)?.pass_all {
+            let passes = interpreter.passes(&record_seqvar, annotator)?;
+            if let Some(failed_stage) = passes.failed_stage {
+                *stats.dropped_by_filter.entry(failed_stage).or_default() += 1;
+            }
+            if passes.pass_all {
                 stats.count_passed += 1;
-                if let Some(ann) = record_seqvar.ann_fields.first() {
-                    ann.consequences.iter().for_each(|csq| {
+                record_seqvar
+                    .ann_fields
+                    .iter()
+                    .flat_map(|ann| ann.consequences.iter())
+                    .unique()
+                    .for_each(|csq| {
                         stats
                             .passed_by_consequences
                             .entry(*csq)
                             .and_modify(|e| *e += 1)
                             .or_insert(1);
-                    })
+                    });
+                let record_seqvars = match args.gene_strategy {
+                    GeneStrategy::First => vec![record_seqvar],
+                    GeneStrategy::PerGene => sorting::split_by_gene(record_seqvar),
+                };
+                for record_seqvar in record_seqvars {
+                    writeln!(
+                        tmp_unsorted,
+                        "{}",
+                        serde_json::to_string(&sorting::ByHgncId::from(record_seqvar))?
+                    )
+                    .map_err(|e| anyhow::anyhow!("could not write record to unsorted: {}", e))?;
                 }
-                writeln!(
-                    tmp_unsorted,
-                    "{}",
-                    serde_json::to_string(&sorting::ByHgncId::from(record_seqvar))?
-                )
-                .map_err(|e| anyhow::anyhow!("could not write record to unsorted: {}", e))?;
             }
         }
         tmp_unsorted.into_inner()?.sync_all().map_err(|e| {
@@ -335,7 +478,15 @@ async fn run_query(
         })?;
     }
 
-    let elem_count = 10_000; // at most 10k records in memory
+    // Estimate the number of records to keep in memory from the size of the unsorted file.
+    let elem_count = sort_elem_count(
+        args.sort_memory.as_u64(),
+        std::fs::metadata(&path_unsorted)
+            .map_err(|e| anyhow::anyhow!("could not get size of temporary unsorted file: {}", e))?
+            .len(),
+        stats.count_passed,
+    );
+    tracing::debug!("using up to {} records in memory for sorting", elem_count);
 
     // Now:
     //
@@ -353,10 +504,14 @@ async fn run_query(
                 anyhow::anyhow!("could not create temporary by_hgnc_filtered file: {}", e)
             })?;
 
+        let mut builder = ExternalSorterBuilder::new()
+            .with_tmp_dir(tmp_dir.as_ref())
+            .with_buffer(LimitedBufferBuilder::new(elem_count, false));
+        if let Some(num_threads) = args.num_threads {
+            builder = builder.with_threads_number(num_threads);
+        }
         let sorter: ExternalSorter<sorting::ByHgncId, std::io::Error, LimitedBufferBuilder> =
-            ExternalSorterBuilder::new()
-                .with_tmp_dir(tmp_dir.as_ref())
-                .with_buffer(LimitedBufferBuilder::new(elem_count, false))
+            builder
                 .build()
                 .map_err(|e| anyhow::anyhow!("problem creating external sorter: {}", e))?;
         let sorted_iter = sorter
@@ -370,12 +525,37 @@ async fn run_query(
             .map(|res| res.expect("problem reading line after sorting by HGNC ID"))
             .chunk_by(|by_hgnc_id| by_hgnc_id.hgnc_id.clone())
             .into_iter()
-            .map(|(_, group)| {
-                group
-                    .map(|ByHgncId { seqvar, .. }| seqvar)
-                    .collect::<Vec<_>>()
+            .map(|(hgnc_id, group)| {
+                (
+                    hgnc_id,
+                    group
+                        .map(|ByHgncId { seqvar, .. }| seqvar)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter_map(|(hgnc_id, seqvars)| {
+                let passes = passes_for_gene(&interpreter.query, &seqvars, relatives).unwrap();
+                if digenic_pairs.is_some() && passes != GenePasses::Fails {
+                    het_counts.insert(
+                        hgnc_id.clone(),
+                        digenic::count_het_variants(&seqvars, digenic_index.as_deref()),
+                    );
+                }
+                match passes {
+                    GenePasses::Passes => Some(seqvars),
+                    GenePasses::PartialHit => {
+                        partial_hit_hgnc_ids.insert(hgnc_id);
+                        Some(seqvars)
+                    }
+                    GenePasses::Fails => {
+                        *stats
+                            .dropped_by_filter
+                            .entry(interpreter::FilterStage::Recessive)
+                            .or_default() += seqvars.len();
+                        None
+                    }
+                }
             })
-            .filter(|seqvars| passes_for_gene(&interpreter.query, seqvars).unwrap())
             .for_each(|seqvars| {
                 seqvars.into_iter().for_each(|seqvar| {
                     writeln!(
@@ -394,9 +574,23 @@ async fn run_query(
         })?;
     }
 
+    // Write out the digenic candidate pairs, if enabled.
+    if let (Some(digenic_pairs), Some(path_output_digenic)) =
+        (digenic_pairs.as_ref(), args.path_output_digenic.as_ref())
+    {
+        let candidate_pairs = digenic::candidate_pairs(digenic_pairs, &het_counts);
+        tracing::info!(
+            "writing {} digenic candidate pairs to {}",
+            candidate_pairs.len(),
+            path_output_digenic
+        );
+        digenic::write_candidate_pairs(path_output_digenic, &candidate_pairs)?;
+    }
+
     // Finally:
     // - sort surviving records by coordinate
     // - generate payload with annotations
+    let mut count_by_coord = 0usize;
     {
         let tmp_by_hgnc_filtered = std::fs::File::open(&path_by_hgnc)
             .map(std::io::BufReader::new)
@@ -407,10 +601,14 @@ async fn run_query(
             .map(std::io::BufWriter::new)
             .map_err(|e| anyhow::anyhow!("could not create temporary by_coord file: {}", e))?;
 
+        let mut builder = ExternalSorterBuilder::new()
+            .with_tmp_dir(tmp_dir.as_ref())
+            .with_buffer(LimitedBufferBuilder::new(elem_count, false));
+        if let Some(num_threads) = args.num_threads {
+            builder = builder.with_threads_number(num_threads);
+        }
         let sorter: ExternalSorter<sorting::ByCoordinate, std::io::Error, LimitedBufferBuilder> =
-            ExternalSorterBuilder::new()
-                .with_tmp_dir(tmp_dir.as_ref())
-                .with_buffer(LimitedBufferBuilder::new(elem_count, false))
+            builder
                 .build()
                 .map_err(|e| anyhow::anyhow!("problem creating external sorter: {}", e))?;
         let sorted_iter = sorter
@@ -423,6 +621,7 @@ async fn run_query(
         sorted_iter
             .map(|res| res.expect("problem reading line after sorting by HGNC ID"))
             .for_each(|ByCoordinate { seqvar, .. }| {
+                count_by_coord += 1;
                 writeln!(tmp_by_coord, "{}", serde_json::to_string(&seqvar).unwrap())
                     .expect("could not write record to by_coord");
             });
@@ -435,23 +634,41 @@ async fn run_query(
         })?;
     }
 
-    // Perform the annotation and write into file without header.
+    // When the records exceed `args.max_results`, rank them and keep the top ones.
+    let is_top = match args.max_results {
+        Some(max_results) if count_by_coord > max_results => {
+            tracing::info!(
+                "ranking {} records to keep the top {}",
+                count_by_coord.separate_with_commas(),
+                max_results.separate_with_commas()
+            );
+            stats.truncated = true;
+            Some(ranking::select_top_in_file(
+                &path_by_coord,
+                annotator,
+                max_results,
+            )?)
+        }
+        _ => None,
+    };
+
+    // Perform the annotation and write into file without header.  Records not among the
+    // top `args.max_results` go to the spillover file, if any, and are dropped otherwise.
     {
         tracing::debug!("writing noheader file {}", path_noheader.display());
-        let writer = tokio::fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(&path_noheader)
-            .await
-            .map_err(|e| anyhow::anyhow!("could not open output file: {}", e))?;
-        let mut writer = tokio::io::BufWriter::new(writer);
+        let mut writer = open_noheader_writer(&path_noheader).await?;
+        let mut spillover_writer = if args.path_output_spillover.is_some() {
+            Some(open_noheader_writer(&path_noheader_spillover).await?)
+        } else {
+            None
+        };
+        let mut count_dropped = 0usize;
         // Open reader for temporary by-coordinate file.
         let tmp_by_coord = std::fs::File::open(&path_by_coord)
             .map(std::io::BufReader::new)
             .map_err(|e| anyhow::anyhow!("could not open temporary by_coord file: {}", e))?;
         // Iterate through the temporary by-coordinate file, generate and write output records.
-        for line in tmp_by_coord.lines() {
+        for (idx, line) in tmp_by_coord.lines().enumerate() {
             // get next line into a String
             let line = if let Ok(line) = line {
                 line
@@ -466,11 +683,26 @@ async fn run_query(
                 )
             })?;
 
+            let writer = if is_top.as_ref().map(|is_top| is_top[idx]).unwrap_or(true) {
+                &mut writer
+            } else if let Some(spillover_writer) = spillover_writer.as_mut() {
+                spillover_writer
+            } else {
+                count_dropped += 1;
+                continue;
+            };
+
+            let problematic_regions = interpreter.problematic_regions(&seqvar);
+            let regulatory_elements = interpreter.regulatory_elements(&seqvar);
             create_and_write_record(
                 seqvar,
+                &interpreter.query,
+                problematic_regions,
+                regulatory_elements,
                 annotator,
                 chrom_to_chrom_no,
-                &mut writer,
+                &partial_hit_hgnc_ids,
+                writer,
                 args,
                 rng,
                 &mut uuid_buf,
@@ -478,18 +710,86 @@ async fn run_query(
             .await?;
         }
 
-        // Properly flush the output file, so upload to S3 can be done if necessary.
+        if count_dropped > 0 {
+            tracing::warn!(
+                "dropped {} records beyond --max-results, consider --path-output-spillover",
+                count_dropped.separate_with_commas()
+            );
+        }
+
+        // Properly flush the output files, so upload to S3 can be done if necessary.
         writer
             .flush()
             .await
             .map_err(|e| anyhow::anyhow!("could not flush output file before closing: {}", e))?;
+        if let Some(mut spillover_writer) = spillover_writer {
+            spillover_writer.flush().await.map_err(|e| {
+                anyhow::anyhow!("could not flush spillover file before closing: {}", e)
+            })?;
+        }
     }
 
-    // Finally, write out records in JSONL format in JSONL format.  The first line will contain the
-    // header, the rest the records.
-    //
-    // Use output helper for semi-transparent upload to S3.
-    let out_path_helper = crate::common::s3::OutputPathHelper::new(&args.path_output)?;
+    // Finally, write out records in JSONL format.  The first line will contain the header, the
+    // rest the records.
+    write_with_header(
+        &args.path_output,
+        &path_noheader,
+        args,
+        pb_query,
+        &stats,
+        start_time.clone(),
+    )
+    .await?;
+    if let Some(path_output_spillover) = args.path_output_spillover.as_ref() {
+        write_with_header(
+            path_output_spillover,
+            &path_noheader_spillover,
+            args,
+            pb_query,
+            &stats,
+            start_time,
+        )
+        .await?;
+    }
+
+    Ok(stats)
+}
+
+/// Return the number of records to keep in memory during external sorting.
+///
+/// The memory usage per record is approximated by the average size of the `count`
+/// records in the temporary file of `total_bytes` size.
+fn sort_elem_count(sort_memory: u64, total_bytes: u64, count: usize) -> usize {
+    let bytes_per_record = (total_bytes / count.max(1) as u64).max(1);
+    ((sort_memory / bytes_per_record) as usize).max(1)
+}
+
+/// Open a writer for a temporary file with output records but without header.
+async fn open_noheader_writer(
+    path: &std::path::Path,
+) -> Result<tokio::io::BufWriter<tokio::fs::File>, anyhow::Error> {
+    let writer = tokio::fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("could not open output file: {}", e))?;
+    Ok(tokio::io::BufWriter::new(writer))
+}
+
+/// Write header and the records from `path_noheader` to `path_output`.
+///
+/// Uses the output helper for semi-transparent upload to S3.
+async fn write_with_header(
+    path_output: &str,
+    path_noheader: &std::path::Path,
+    args: &Args,
+    pb_query: &pbs_query::CaseQuery,
+    stats: &QueryStats,
+    start_time: pbjson_types::Timestamp,
+) -> Result<(), anyhow::Error> {
+    let out_path_helper = crate::common::s3::OutputPathHelper::new(path_output)?;
     {
         tracing::debug!("writing file {}", out_path_helper.path_out());
         // Open output file for writing (potentially temporary, then uploaded to S3 via helper).
@@ -500,9 +800,9 @@ async fn run_query(
             .open(out_path_helper.path_out())
             .map_err(|e| anyhow::anyhow!("could not open output file: {}", e))?;
         let mut writer = std::io::BufWriter::new(file);
-        write_header(args, pb_query, &stats, start_time, &mut writer)?;
+        write_header(args, pb_query, stats, start_time, &mut writer)?;
         // Open reader for file without header.
-        let mut reader = std::fs::File::open(&path_noheader)
+        let mut reader = std::fs::File::open(path_noheader)
             .map(std::io::BufReader::new)
             .map_err(|e| anyhow::anyhow!("could not open temporary no_header file: {}", e))?;
         // Append the temporary file to the output file.
@@ -518,8 +818,9 @@ async fn run_query(
         .upload_for_s3()
         .await
         .map_err(|e| anyhow::anyhow!("could not upload output file to S3: {}", e))?;
+    out_path_helper.copy_to_stdout().await?;
 
-    Ok(stats)
+    Ok(())
 }
 
 /// Write the header to the output file.
@@ -836,6 +1137,20 @@ mod gene_related_annotation {
                     .into_iter()
                     .map(|moi| Into::<pbs_output::ModeOfInheritance>::into(moi) as i32)
                     .collect::<Vec<_>>(),
+                orphanet_disorders: gene_record
+                    .orpha
+                    .as_ref()
+                    .map(|orpha| {
+                        orpha
+                            .orpha_diseases
+                            .iter()
+                            .map(|disease| pbs_output::OrphanetDisorder {
+                                orpha_id: disease.orpha_id.clone(),
+                                label: disease.label.clone(),
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default(),
             })
     }
 
@@ -1107,6 +1422,9 @@ mod variant_related_annotation {
             frequency: frequency(seqvar),
             clinvar: clinvar(seqvar, annotator)?,
             scores: scores(seqvar, annotator)?,
+            acmg: None,
+            problematic_regions: None,
+            regulatory_elements: Vec::new(),
         })
     }
 
@@ -1541,62 +1859,86 @@ impl WithSeqvarAndAnnotator for pbs_output::CallRelatedAnnotation {
                     ad: call_info.ad,
                     gq: call_info.gq,
                     ps: call_info.ps,
+                    hf: call_info.hf,
                 })
                 .collect(),
+            recessive_partial_hit: false,
+            somatic_status: None,
+            de_novo_probability: None,
         })
     }
 }
 
 /// Create output payload and write the record to the output file.
+#[allow(clippy::too_many_arguments)]
 async fn create_and_write_record(
     seqvar: VariantRecord,
+    query: &CaseQuery,
+    problematic_regions: Option<pbs_output::ProblematicRegionsAnnotation>,
+    regulatory_elements: Vec<pbs_output::RegulatoryElement>,
     annotator: &Annotator,
     chrom_to_chrom_no: &std::collections::HashMap<String, u32>,
+    partial_hit_hgnc_ids: &HashSet<String>,
     writer: &mut tokio::io::BufWriter<tokio::fs::File>,
     args: &Args,
     rng: &mut rand::rngs::StdRng,
     uuid_buf: &mut [u8; 16],
 ) -> Result<(), anyhow::Error> {
+    let recessive_partial_hit = seqvar
+        .ann_fields
+        .first()
+        .map(|ann| partial_hit_hgnc_ids.contains(&ann.gene_id))
+        .unwrap_or_default();
+    let somatic_status = interpreter::somatic::somatic_status(query, &seqvar)
+        .map(|somatic_status| somatic_status as i32);
+    let de_novo_probability = interpreter::de_novo::probability(query, &seqvar);
+    let gene = pbs_output::GeneRelatedAnnotation::with_seqvar_and_annotator(&seqvar, annotator)
+        .map_err(|e| anyhow::anyhow!("problem creating gene-related annotation: {}", e))?;
+    let variant =
+        pbs_output::VariantRelatedAnnotation::with_seqvar_and_annotator(&seqvar, annotator)
+            .map_err(|e| anyhow::anyhow!("problem creating variant-related annotation: {}", e))?;
+    let variant = pbs_output::VariantRelatedAnnotation {
+        acmg: Some(acmg::annotation(&gene, &variant)),
+        problematic_regions,
+        regulatory_elements,
+        ..variant
+    };
     // Build the output record protobuf.
-    let record = pbs_output::OutputRecord {
-        uuid: Uuid::from_bytes({
-            rng.fill_bytes(uuid_buf);
-            *uuid_buf
-        })
-        .to_string(),
-        case_uuid: args.case_uuid.unwrap_or_default().to_string(),
-        vcf_variant: Some(pbs_output::VcfVariant {
-            genome_release: Into::<pbs_output::GenomeRelease>::into(args.genome_release) as i32,
-            chrom: seqvar.vcf_variant.chrom.clone(),
-            chrom_no: chrom_to_chrom_no
-                .get(&seqvar.vcf_variant.chrom)
-                .cloned()
-                .unwrap_or_default() as i32,
-            pos: seqvar.vcf_variant.pos,
-            ref_allele: seqvar.vcf_variant.ref_allele.clone(),
-            alt_allele: seqvar.vcf_variant.alt_allele.clone(),
-        }),
-        variant_annotation: Some(pbs_output::VariantAnnotation {
-            gene: Some(
-                pbs_output::GeneRelatedAnnotation::with_seqvar_and_annotator(&seqvar, annotator)
-                    .map_err(|e| {
-                        anyhow::anyhow!("problem creating gene-related annotation: {}", e)
-                    })?,
-            ),
-            variant: Some(
-                pbs_output::VariantRelatedAnnotation::with_seqvar_and_annotator(&seqvar, annotator)
-                    .map_err(|e| {
-                        anyhow::anyhow!("problem creating variant-related annotation: {}", e)
-                    })?,
-            ),
-            call: Some(
-                pbs_output::CallRelatedAnnotation::with_seqvar_and_annotator(&seqvar, annotator)
+    let record =
+        pbs_output::OutputRecord {
+            uuid: Uuid::from_bytes({
+                rng.fill_bytes(uuid_buf);
+                *uuid_buf
+            })
+            .to_string(),
+            case_uuid: args.case_uuid.unwrap_or_default().to_string(),
+            vcf_variant: Some(pbs_output::VcfVariant {
+                genome_release: Into::<pbs_output::GenomeRelease>::into(args.genome_release) as i32,
+                chrom: seqvar.vcf_variant.chrom.clone(),
+                chrom_no: chrom_to_chrom_no
+                    .get(&seqvar.vcf_variant.chrom)
+                    .cloned()
+                    .unwrap_or_default() as i32,
+                pos: seqvar.vcf_variant.pos,
+                ref_allele: seqvar.vcf_variant.ref_allele.clone(),
+                alt_allele: seqvar.vcf_variant.alt_allele.clone(),
+            }),
+            variant_annotation: Some(pbs_output::VariantAnnotation {
+                gene: Some(gene),
+                variant: Some(variant),
+                call: Some(pbs_output::CallRelatedAnnotation {
+                    recessive_partial_hit,
+                    somatic_status,
+                    de_novo_probability,
+                    ..pbs_output::CallRelatedAnnotation::with_seqvar_and_annotator(
+                        &seqvar, annotator,
+                    )
                     .map_err(|e| {
                         anyhow::anyhow!("problem creating call-related annotation: {}", e)
-                    })?,
-            ),
-        }),
-    };
+                    })?
+                }),
+            }),
+        };
 
     // Write out the record to JSONL.
 
@@ -1617,7 +1959,7 @@ async fn create_and_write_record(
 pub(crate) mod inhouse {
     use std::{path::Path, sync::Arc};
 
-    use crate::seqvars::aggregate::ds::Counts;
+    use crate::seqvars::aggregate::{ds::Counts, parse_schema_version, SCHEMA_VERSION};
 
     use super::schema::data::{InHouseFrequencies, PopulationFrequencies, VariantRecord};
 
@@ -1625,6 +1967,8 @@ pub(crate) mod inhouse {
     pub struct Meta {
         /// Genome release.
         pub genome_release: String,
+        /// Version of the database schema.
+        pub schema_version: u32,
     }
 
     /// Container for the database structures.
@@ -1641,13 +1985,14 @@ pub(crate) mod inhouse {
         path_rocksdb: P,
         cf_data: &str,
         cf_meta: &str,
+        options: &rocksdb::Options,
     ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
         tracing::info!("Opening RocksDB database ...");
         let before_open = std::time::Instant::now();
         let cf_names = &[cf_meta, cf_data];
         let resolved_path_rocksdb = annonars::common::readlink_f(&path_rocksdb)?;
         let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
-            &rocksdb::Options::default(),
+            options,
             resolved_path_rocksdb,
             cf_names,
             true,
@@ -1659,12 +2004,23 @@ pub(crate) mod inhouse {
                 db.get_cf(&cf_meta, "genome-release")?
                     .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
             )?;
+            let meta_schema_version =
+                parse_schema_version(db.get_cf(&cf_meta, "schema-version")?.as_deref())?;
             Meta {
                 genome_release: meta_genome_release,
+                schema_version: meta_schema_version,
             }
         };
 
         tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+        tracing::info!("  meta:schema-version = {}", &meta.schema_version);
+        if meta.schema_version > SCHEMA_VERSION {
+            anyhow::bail!(
+                "schema version of the in-house database is {}, this worker supports up to {}",
+                meta.schema_version,
+                SCHEMA_VERSION
+            );
+        }
         tracing::info!(
             "... opening RocksDB database took {:?}",
             before_open.elapsed()
@@ -1678,9 +2034,10 @@ pub(crate) mod inhouse {
         pub fn with_path<P: AsRef<Path>>(
             path: P,
             genome_release: &str,
+            options: &rocksdb::Options,
         ) -> Result<Self, anyhow::Error> {
-            let (inhouse_db, inhouse_meta) =
-                open_rocksdb(&path, "counts", "meta").map_err(|e| {
+            let (inhouse_db, inhouse_meta) = open_rocksdb(&path, "counts", "meta", options)
+                .map_err(|e| {
                     anyhow::anyhow!(
                         "problem opening {} metadata at {}: {}",
                         "counts",
@@ -1727,6 +2084,7 @@ pub(crate) mod inhouse {
         ) -> Result<VariantRecord, anyhow::Error> {
             let VariantRecord {
                 vcf_variant,
+                filters,
                 call_infos,
                 ann_fields,
                 population_frequencies,
@@ -1766,6 +2124,7 @@ pub(crate) mod inhouse {
 
             Ok(VariantRecord {
                 vcf_variant,
+                filters,
                 call_infos,
                 ann_fields,
                 population_frequencies: PopulationFrequencies {
@@ -1795,19 +2154,42 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
     };
 
     tracing::info!("Loading query... {}", args.path_query_json);
-    let pb_query: pbs_query::CaseQuery =
+    let mut pb_query: pbs_query::CaseQuery =
         serde_json::from_reader(std::fs::File::open(&args.path_query_json)?)?;
+    let pedigree = args
+        .path_ped
+        .as_ref()
+        .map(|path_ped| {
+            mehari::ped::PedigreeByName::from_path(path_ped)
+                .map_err(|e| anyhow::anyhow!("problem parsing PED file {}: {}", path_ped, e))
+        })
+        .transpose()?;
+    if let (Some(pedigree), Some(inheritance_model)) = (pedigree.as_ref(), args.inheritance_model) {
+        tracing::info!("Deriving genotype settings for {:?}...", inheritance_model);
+        pb_query.genotype = Some(pedigree::derive_genotype_settings(
+            pedigree,
+            inheritance_model,
+            pb_query.genotype.unwrap_or_default(),
+        )?);
+    }
     let query = CaseQuery::try_from(pb_query.clone())?;
 
     tracing::info!(
         "... done loading query = {}",
         &serde_json::to_string(&query)?
     );
+    let relatives = pedigree
+        .as_ref()
+        .zip(query.genotype.recessive_index().ok())
+        .map(|(pedigree, index)| pedigree::Relatives::new(pedigree, &index))
+        .unwrap_or_default();
+    tracing::debug!("relatives = {:?}", &relatives);
 
     tracing::info!("Loading worker databases...");
     let before_loading = Instant::now();
-    let path_worker_db = format!("{}/worker", &args.path_db);
-    let in_memory_dbs = crate::strucvars::query::load_databases(
+    let path_db = crate::common::remote::localize(&args.path_db).await?;
+    let path_worker_db = format!("{}/worker", &path_db);
+    let mut in_memory_dbs = crate::strucvars::query::load_databases(
         &path_worker_db,
         args.genome_release,
         args.max_tad_distance,
@@ -1819,11 +2201,23 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             e
         )
     })?;
-    let annotator = annonars::Annotator::with_path(&args.path_db, args.genome_release)?;
-    let inhouse_db = args
-        .path_inhouse_db
+    let annotator = annonars::Annotator::with_path(&path_db, args.genome_release)?;
+    let path_inhouse_db = match args.path_inhouse_db.as_ref() {
+        Some(path) => Some(crate::common::remote::localize(path).await?),
+        None => None,
+    };
+    let inhouse_db = path_inhouse_db
         .as_ref()
-        .map(|path| inhouse::Dbs::with_path(path, &format!("{}", args.genome_release)))
+        .map(|path| {
+            inhouse::Dbs::with_path(
+                path,
+                &format!("{}", args.genome_release),
+                &args.rocksdb.apply(
+                    rocksdb::Options::default(),
+                    crate::common::rocksdb_tuning::Profile::Query,
+                ),
+            )
+        })
         .transpose()?;
     tracing::info!(
         "...done loading databases in {:?}",
@@ -1835,15 +2229,24 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
     tracing::info!("Translating gene allow list...");
     let hgnc_allowlist =
         crate::strucvars::query::translate_genes(&query.locus.genes, &in_memory_dbs);
+    let masked = std::mem::take(&mut in_memory_dbs.masked);
+    let regulatory = std::mem::take(&mut in_memory_dbs.regulatory);
 
     tracing::info!("Running queries...");
     let before_query = Instant::now();
     let query_stats = run_query(
-        &interpreter::QueryInterpreter::new(query, hgnc_allowlist),
+        &interpreter::QueryInterpreter::new(
+            query,
+            hgnc_allowlist,
+            args.genome_release,
+            masked,
+            regulatory,
+        )?,
         &pb_query.clone(),
         args,
         &annotator,
         &inhouse_db,
+        &relatives,
         &mut rng,
     )
     .await?;
@@ -1857,6 +2260,19 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
     for (effect, count) in query_stats.passed_by_consequences.iter() {
         tracing::info!("{:?} -- {}", effect, count);
     }
+    tracing::info!("dropped records by filter");
+    for (stage, count) in query_stats.dropped_by_filter.iter() {
+        tracing::info!("{:?} -- {}", stage, count);
+    }
+
+    if let Some(path_stats_output) = args.path_stats_output.as_ref() {
+        tracing::info!("Writing query statistics to {}", path_stats_output);
+        let file = std::fs::File::create(path_stats_output).map_err(|e| {
+            anyhow::anyhow!("could not create stats file {}: {}", path_stats_output, e)
+        })?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), &query_stats)
+            .map_err(|e| anyhow::anyhow!("could not write query statistics: {}", e))?;
+    }
 
     trace_rss_now();
 
@@ -1872,7 +2288,9 @@ mod test {
     use rstest::rstest;
 
     use super::schema::data::{CallInfo, VariantRecord};
-    use crate::seqvars::query::schema::query::{CaseQuery, GenotypeChoice, RecessiveMode};
+    use crate::seqvars::query::schema::query::{
+        CaseQuery, GenotypeChoice, NoCallStrategy, RecessiveMode,
+    };
 
     #[rstest]
     #[case::comphet_het_het_ref_fails(
@@ -1925,6 +2343,8 @@ mod test {
                     String::from("father") => SampleGenotypeChoice { sample: String::from("father"), genotype: GenotypeChoice::RecessiveFather, ..Default::default() },
                     String::from("mother") => SampleGenotypeChoice { sample: String::from("mother"), genotype: GenotypeChoice::RecessiveMother, ..Default::default() },
                 },
+                report_partial_hits: false,
+                no_call_strategy: Default::default(),
             },
             ..Default::default()
         };
@@ -1956,11 +2376,256 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        assert_eq!(super::passes_for_gene(&query, &seqvars)?, passes);
+        assert_eq!(
+            super::passes_for_gene(&query, &seqvars, &Default::default())?
+                == super::GenePasses::Passes,
+            passes
+        );
 
         Ok(())
     }
 
+    #[rstest::rstest]
+    #[case::single_het_no_report_fails(vec!["0/1,0/1,0/0"], false, super::GenePasses::Fails)]
+    #[case::single_het_report_partial(vec!["0/1,0/1,0/0"], true, super::GenePasses::PartialHit)]
+    #[case::single_het_incompatible_report_fails(
+        vec!["0/1,0/1,0/1"],
+        true,
+        super::GenePasses::Fails
+    )]
+    #[case::two_het_report_passes(
+        vec!["0/1,0/1,0/0","0/1,0/0,0/1"],
+        true,
+        super::GenePasses::Passes
+    )]
+    fn passes_for_gene_partial_hits(
+        #[case] trio_gts: Vec<&str>,
+        #[case] report_partial_hits: bool,
+        #[case] expected: super::GenePasses,
+    ) -> Result<(), anyhow::Error> {
+        use crate::seqvars::query::schema::query::{QuerySettingsGenotype, SampleGenotypeChoice};
+
+        let query = CaseQuery {
+            genotype: QuerySettingsGenotype {
+                recessive_mode: RecessiveMode::CompoundHeterozygous,
+                sample_genotypes: indexmap::indexmap! {
+                    String::from("index") => SampleGenotypeChoice { sample: String::from("index"), genotype: GenotypeChoice::RecessiveIndex, ..Default::default() },
+                    String::from("father") => SampleGenotypeChoice { sample: String::from("father"), genotype: GenotypeChoice::RecessiveFather, ..Default::default() },
+                    String::from("mother") => SampleGenotypeChoice { sample: String::from("mother"), genotype: GenotypeChoice::RecessiveMother, ..Default::default() },
+                },
+                report_partial_hits,
+                no_call_strategy: Default::default(),
+            },
+            ..Default::default()
+        };
+        let seqvars = trio_gts
+            .iter()
+            .map(|gts| {
+                let gts: Vec<&str> = gts.split(',').collect();
+                VariantRecord {
+                    call_infos: indexmap::indexmap! {
+                        String::from("index") =>
+                            CallInfo {
+                                sample: String::from("index"),
+                                genotype: Some(gts[0].into()),
+                                ..Default::default()
+                            },
+                        String::from("father") =>
+                            CallInfo {
+                                genotype: Some(gts[1].into()),
+                                ..Default::default()
+                            },
+                        String::from("mother") =>
+                            CallInfo {
+                                genotype: Some(gts[2].into()),
+                                ..Default::default()
+                            },
+                    },
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            super::passes_for_gene(&query, &seqvars, &Default::default())?,
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::per_sample_nocall_parent(
+        NoCallStrategy::PerSample,
+        vec!["1/1,./.,0/1"],
+        super::GenePasses::Fails
+    )]
+    #[case::per_sample_nocall_index(
+        NoCallStrategy::PerSample,
+        vec![".,0/1,0/1"],
+        super::GenePasses::Fails
+    )]
+    #[case::as_any_nocall_parent(
+        NoCallStrategy::TreatAsAny,
+        vec!["1/1,./.,0/1"],
+        super::GenePasses::Passes
+    )]
+    #[case::as_ref_nocall_parent(
+        NoCallStrategy::TreatAsRef,
+        vec!["0/1,./.,0/1","0/1,0/1,0/0"],
+        super::GenePasses::Passes
+    )]
+    #[case::drop_nocall_parent(
+        NoCallStrategy::DropVariant,
+        vec!["0/1,./.,0/1","0/1,0/1,0/0"],
+        super::GenePasses::Fails
+    )]
+    fn passes_for_gene_no_call_strategy(
+        #[case] no_call_strategy: NoCallStrategy,
+        #[case] trio_gts: Vec<&str>,
+        #[case] expected: super::GenePasses,
+    ) -> Result<(), anyhow::Error> {
+        use crate::seqvars::query::schema::query::{QuerySettingsGenotype, SampleGenotypeChoice};
+
+        let query = CaseQuery {
+            genotype: QuerySettingsGenotype {
+                recessive_mode: RecessiveMode::Any,
+                sample_genotypes: indexmap::indexmap! {
+                    String::from("index") => SampleGenotypeChoice { sample: String::from("index"), genotype: GenotypeChoice::RecessiveIndex, ..Default::default() },
+                    String::from("father") => SampleGenotypeChoice { sample: String::from("father"), genotype: GenotypeChoice::RecessiveFather, ..Default::default() },
+                    String::from("mother") => SampleGenotypeChoice { sample: String::from("mother"), genotype: GenotypeChoice::RecessiveMother, ..Default::default() },
+                },
+                no_call_strategy,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let seqvars = trio_gts
+            .iter()
+            .map(|gts| {
+                let gts: Vec<&str> = gts.split(',').collect();
+                VariantRecord {
+                    call_infos: indexmap::indexmap! {
+                        String::from("index") =>
+                            CallInfo {
+                                genotype: Some(gts[0].into()),
+                                ..Default::default()
+                            },
+                        String::from("father") =>
+                            CallInfo {
+                                genotype: Some(gts[1].into()),
+                                ..Default::default()
+                            },
+                        String::from("mother") =>
+                            CallInfo {
+                                genotype: Some(gts[2].into()),
+                                ..Default::default()
+                            },
+                    },
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            super::passes_for_gene(&query, &seqvars, &Default::default())?,
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::comphet_sibling_carries_both(
+        RecessiveMode::CompoundHeterozygous,
+        vec!["0/1,0/1,0/0,0/1","0/1,0/0,0/1,0/1"],
+        super::GenePasses::Fails
+    )]
+    #[case::comphet_sibling_carries_one(
+        RecessiveMode::CompoundHeterozygous,
+        vec!["0/1,0/1,0/0,0/1","0/1,0/0,0/1,0/0"],
+        super::GenePasses::Passes
+    )]
+    #[case::hom_sibling_hom(
+        RecessiveMode::Homozygous,
+        vec!["1/1,0/1,0/1,1/1"],
+        super::GenePasses::Fails
+    )]
+    #[case::hom_sibling_het(
+        RecessiveMode::Homozygous,
+        vec!["1/1,0/1,0/1,0/1"],
+        super::GenePasses::Passes
+    )]
+    fn passes_for_gene_relatives(
+        #[case] recessive_mode: RecessiveMode,
+        #[case] family_gts: Vec<&str>,
+        #[case] expected: super::GenePasses,
+    ) -> Result<(), anyhow::Error> {
+        use crate::seqvars::query::schema::query::{QuerySettingsGenotype, SampleGenotypeChoice};
+
+        let query = CaseQuery {
+            genotype: QuerySettingsGenotype {
+                recessive_mode,
+                sample_genotypes: indexmap::indexmap! {
+                    String::from("index") => SampleGenotypeChoice { sample: String::from("index"), genotype: GenotypeChoice::RecessiveIndex, ..Default::default() },
+                    String::from("father") => SampleGenotypeChoice { sample: String::from("father"), genotype: GenotypeChoice::RecessiveFather, ..Default::default() },
+                    String::from("mother") => SampleGenotypeChoice { sample: String::from("mother"), genotype: GenotypeChoice::RecessiveMother, ..Default::default() },
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let relatives = super::pedigree::Relatives {
+            unaffected_siblings: vec![String::from("sibling")],
+            ..Default::default()
+        };
+        let seqvars = family_gts
+            .iter()
+            .map(|gts| {
+                let gts: Vec<&str> = gts.split(',').collect();
+                VariantRecord {
+                    call_infos: ["index", "father", "mother", "sibling"]
+                        .iter()
+                        .zip(gts.iter())
+                        .map(|(sample, gt)| {
+                            (
+                                sample.to_string(),
+                                CallInfo {
+                                    genotype: Some(gt.to_string()),
+                                    ..Default::default()
+                                },
+                            )
+                        })
+                        .collect(),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            super::passes_for_gene(&query, &seqvars, &relatives)?,
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(1024, 0, 0, 1024)]
+    #[case(1024, 100, 10, 102)]
+    #[case(1024, 10_000, 10, 1)]
+    fn sort_elem_count(
+        #[case] sort_memory: u64,
+        #[case] total_bytes: u64,
+        #[case] count: usize,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(
+            super::sort_elem_count(sort_memory, total_bytes, count),
+            expected
+        );
+    }
+
     #[tracing_test::traced_test]
     #[rstest::rstest]
     #[case::case_1_ingested_vcf_with_inhouse("tests/seqvars/query/Case_1.ingested.vcf", true)]
@@ -2000,9 +2665,19 @@ mod test {
                 None
             },
             path_query_json,
+            path_ped: None,
+            inheritance_model: None,
             path_input,
             path_output,
             max_results: None,
+            path_output_spillover: None,
+            path_digenic_pairs: None,
+            path_output_digenic: None,
+            path_stats_output: None,
+            gene_strategy: super::GeneStrategy::First,
+            sort_memory: bytesize::ByteSize::mib(64),
+            rocksdb: Default::default(),
+            num_threads: None,
             rng_seed: Some(42),
             max_tad_distance: 10_000,
             result_set_id: None,
@@ -2014,4 +2689,171 @@ mod test {
 
         Ok(())
     }
+
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn max_results_with_spillover() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_input = String::from("tests/seqvars/query/Case_1.ingested.vcf");
+        let args_common = Default::default();
+        let args = super::Args {
+            genome_release: crate::common::GenomeRelease::Grch37,
+            path_db: "tests/seqvars/query/db".into(),
+            path_inhouse_db: None,
+            path_query_json: path_input.replace(".ingested.vcf", ".query.json"),
+            path_ped: None,
+            inheritance_model: None,
+            path_input,
+            path_output: format!("{}/full.tsv", tmpdir.to_string_lossy()),
+            max_results: None,
+            path_output_spillover: None,
+            path_digenic_pairs: None,
+            path_output_digenic: None,
+            path_stats_output: None,
+            gene_strategy: super::GeneStrategy::First,
+            sort_memory: bytesize::ByteSize::mib(64),
+            rocksdb: Default::default(),
+            num_threads: None,
+            rng_seed: Some(42),
+            max_tad_distance: 10_000,
+            result_set_id: None,
+            case_uuid: None,
+        };
+        super::run(&args_common, &args).await?;
+        let count_hits = std::fs::read_to_string(&args.path_output)?.lines().count() - 1;
+
+        let max_results = 5;
+        assert!(count_hits > max_results);
+        let args = super::Args {
+            path_output: format!("{}/out.tsv", tmpdir.to_string_lossy()),
+            max_results: Some(max_results),
+            path_output_spillover: Some(format!("{}/spillover.tsv", tmpdir.to_string_lossy())),
+            ..args
+        };
+        super::run(&args_common, &args).await?;
+
+        let output = std::fs::read_to_string(&args.path_output)?;
+        let spillover = std::fs::read_to_string(args.path_output_spillover.as_ref().unwrap())?;
+        let output_lines = output.lines().collect::<Vec<_>>();
+        let spillover_lines = spillover.lines().collect::<Vec<_>>();
+        // Both files start with the same header, followed by the records.
+        assert!(output_lines[0].starts_with("{\"genomeRelease\":"));
+        assert_eq!(output_lines[0], spillover_lines[0]);
+        assert_eq!(output_lines.len() - 1, max_results);
+        assert_eq!(spillover_lines.len() - 1, count_hits - max_results);
+
+        Ok(())
+    }
+
+    #[tracing_test::traced_test]
+    #[rstest::rstest]
+    #[case::no_recessive(
+        "RECESSIVE_MODE_DISABLED",
+        ["GENOTYPE_CHOICE_VARIANT", "GENOTYPE_CHOICE_ANY", "GENOTYPE_CHOICE_ANY"],
+        serde_json::json!({"frequency": 1, "genotype": 1}),
+        2
+    )]
+    #[case::comphet(
+        "RECESSIVE_MODE_COMPOUND_HETEROZYGOUS",
+        [
+            "GENOTYPE_CHOICE_RECESSIVE_INDEX",
+            "GENOTYPE_CHOICE_RECESSIVE_FATHER",
+            "GENOTYPE_CHOICE_RECESSIVE_MOTHER",
+        ],
+        serde_json::json!({"frequency": 1, "genotype": 1, "recessive": 2}),
+        0
+    )]
+    #[tokio::test]
+    async fn stats_output(
+        #[case] recessive_mode: &str,
+        #[case] genotypes: [&str; 3],
+        #[case] expected_dropped: serde_json::Value,
+        #[case] expected_output: usize,
+    ) -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+
+        // Use four records in BRCA1: a common variant, two rare intronic variants that
+        // the index inherited from the mother, and one that the index does not carry.
+        let positions = ["41249263", "41252332", "41252691", "41252693"];
+        let path_input = format!("{}/input.vcf", tmpdir.to_string_lossy());
+        let input = std::fs::read_to_string("tests/seqvars/query/Case_1.ingested.vcf")?
+            .lines()
+            .filter(|line| {
+                line.starts_with('#') || {
+                    let mut fields = line.split('\t');
+                    fields.next() == Some("17")
+                        && fields.next().is_some_and(|pos| positions.contains(&pos))
+                }
+            })
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        std::fs::write(&path_input, input)?;
+
+        // Only keep intronic variants and disable the quality filter.
+        let mut query: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            "tests/seqvars/query/Case_1.query.json",
+        )?)?;
+        query["genotype"]["recessiveMode"] = serde_json::json!(recessive_mode);
+        for (sample_genotype, genotype) in query["genotype"]["sampleGenotypes"]
+            .as_array_mut()
+            .expect("array in query")
+            .iter_mut()
+            .zip(genotypes)
+        {
+            sample_genotype["genotype"] = serde_json::json!(genotype);
+        }
+        for sample_quality in query["quality"]["sampleQualities"]
+            .as_array_mut()
+            .expect("array in query")
+        {
+            sample_quality["filterActive"] = serde_json::json!(false);
+        }
+        query["consequence"]["consequences"] = serde_json::json!(["CONSEQUENCE_INTRON_VARIANT"]);
+        let path_query_json = format!("{}/query.json", tmpdir.to_string_lossy());
+        std::fs::write(&path_query_json, serde_json::to_string(&query)?)?;
+
+        let args_common = Default::default();
+        let args = super::Args {
+            genome_release: crate::common::GenomeRelease::Grch37,
+            path_db: "tests/seqvars/query/db".into(),
+            path_inhouse_db: None,
+            path_query_json,
+            path_ped: None,
+            inheritance_model: None,
+            path_input,
+            path_output: format!("{}/out.tsv", tmpdir.to_string_lossy()),
+            max_results: None,
+            path_output_spillover: None,
+            path_digenic_pairs: None,
+            path_output_digenic: None,
+            path_stats_output: Some(format!("{}/stats.json", tmpdir.to_string_lossy())),
+            gene_strategy: super::GeneStrategy::First,
+            sort_memory: bytesize::ByteSize::mib(64),
+            rocksdb: Default::default(),
+            num_threads: None,
+            rng_seed: Some(42),
+            max_tad_distance: 10_000,
+            result_set_id: None,
+            case_uuid: None,
+        };
+        super::run(&args_common, &args).await?;
+
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            args.path_stats_output.as_ref().expect("set above"),
+        )?)?;
+        assert_eq!(
+            stats,
+            serde_json::json!({
+                "count_passed": 2,
+                "count_total": 4,
+                "passed_by_consequences": {"intron_variant": 2},
+                "dropped_by_filter": expected_dropped,
+                "truncated": false,
+            })
+        );
+        let output = std::fs::read_to_string(&args.path_output)?;
+        assert_eq!(output.lines().count() - 1, expected_output);
+
+        Ok(())
+    }
 }
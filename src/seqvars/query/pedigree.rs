@@ -0,0 +1,494 @@
+//! Derivation of the genotype settings from a pedigree and an inheritance model.
+//!
+//! This allows to pass a PLINK PED file and an inheritance model name instead
+//! of enumerating the per-sample genotype choices in the query JSON.  The
+//! pedigree structure beyond the trio (grandparents, unaffected siblings) is
+//! also used for refining the recessive inheritance checks.
+
+use std::collections::BTreeSet;
+
+use mehari::ped::{Disease, Individual, PedigreeByName, Sex};
+
+use super::schema::data::VariantRecord;
+use crate::common;
+use crate::pbs::varfish::v1::seqvars::query as pb_query;
+
+/// Inheritance model for deriving the genotype settings.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InheritanceModel {
+    /// Autosomal recessive (hom. or compound het.) in the index.
+    Ar,
+    /// Autosomal dominant, het. in affected and hom. ref. in unaffected individuals.
+    Ad,
+    /// X-linked recessive, hom./hemi. in affected individuals.
+    Xlr,
+    /// De novo, het. in the index and hom. ref. in the parents.
+    DeNovo,
+}
+
+/// Select the index from the affected individuals in `pedigree`.
+///
+/// The first affected individual with most parents in the pedigree is used.
+fn select_index(pedigree: &PedigreeByName) -> Result<&Individual, anyhow::Error> {
+    pedigree
+        .individuals
+        .values()
+        .filter(|individual| matches!(individual.disease, Disease::Affected))
+        .min_by_key(|individual| {
+            let parent_count = [&individual.father, &individual.mother]
+                .into_iter()
+                .filter(|parent| {
+                    parent
+                        .as_ref()
+                        .map(|parent| pedigree.individuals.contains_key(parent))
+                        .unwrap_or(false)
+                })
+                .count();
+            std::cmp::Reverse(parent_count)
+        })
+        .ok_or_else(|| anyhow::anyhow!("no affected individual in pedigree"))
+}
+
+/// Derive the genotype settings for `model` from `pedigree`.
+///
+/// The recessive mode and sample genotype choices of `settings` are replaced,
+/// the remaining settings are kept.
+pub fn derive_genotype_settings(
+    pedigree: &PedigreeByName,
+    model: InheritanceModel,
+    settings: pb_query::QuerySettingsGenotype,
+) -> Result<pb_query::QuerySettingsGenotype, anyhow::Error> {
+    let index = select_index(pedigree)?;
+    let is_parent =
+        |name: &str| index.father.as_deref() == Some(name) || index.mother.as_deref() == Some(name);
+
+    let recessive_mode = if model == InheritanceModel::Ar {
+        pb_query::RecessiveMode::Any
+    } else {
+        pb_query::RecessiveMode::Disabled
+    };
+    let sample_genotypes = pedigree
+        .individuals
+        .values()
+        .map(|individual| {
+            let name = individual.name.as_str();
+            let genotype = match model {
+                InheritanceModel::Ar => {
+                    if name == index.name {
+                        pb_query::GenotypeChoice::RecessiveIndex
+                    } else if index.father.as_deref() == Some(name) {
+                        pb_query::GenotypeChoice::RecessiveFather
+                    } else if index.mother.as_deref() == Some(name) {
+                        pb_query::GenotypeChoice::RecessiveMother
+                    } else {
+                        pb_query::GenotypeChoice::Any
+                    }
+                }
+                InheritanceModel::Ad => match &individual.disease {
+                    Disease::Affected => pb_query::GenotypeChoice::Het,
+                    Disease::Unaffected => pb_query::GenotypeChoice::Ref,
+                    _ => pb_query::GenotypeChoice::Any,
+                },
+                InheritanceModel::Xlr => match (&individual.disease, &individual.sex) {
+                    (Disease::Affected, _) => pb_query::GenotypeChoice::Hom,
+                    (Disease::Unaffected, Sex::Male) => pb_query::GenotypeChoice::Ref,
+                    (Disease::Unaffected, _) => pb_query::GenotypeChoice::NonHom,
+                    _ => pb_query::GenotypeChoice::Any,
+                },
+                InheritanceModel::DeNovo => {
+                    if name == index.name {
+                        pb_query::GenotypeChoice::Het
+                    } else if is_parent(name) || matches!(individual.disease, Disease::Unaffected) {
+                        pb_query::GenotypeChoice::Ref
+                    } else {
+                        pb_query::GenotypeChoice::Any
+                    }
+                }
+            };
+            pb_query::SampleGenotypeChoice {
+                sample: individual.name.clone(),
+                genotype: genotype as i32,
+                include_no_call: false,
+                enabled: true,
+            }
+        })
+        .collect();
+
+    Ok(pb_query::QuerySettingsGenotype {
+        recessive_mode: recessive_mode as i32,
+        sample_genotypes,
+        ..settings
+    })
+}
+
+/// Parental side that a het. variant of the index was inherited from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Inherited from the father.
+    Paternal,
+    /// Inherited from the mother.
+    Maternal,
+}
+
+/// A qualifying het. variant of the index in compound heterozygous mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HetVariant {
+    /// Parental side the variant was inherited from, if known.
+    pub side: Option<Side>,
+    /// Unaffected siblings carrying the variant.
+    pub sibling_carriers: BTreeSet<String>,
+}
+
+/// Relatives of the index beyond the parents for multi-generation checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Relatives {
+    /// Unaffected siblings of the index.
+    pub unaffected_siblings: Vec<String>,
+    /// Parents of the father of the index.
+    pub paternal_grandparents: Vec<String>,
+    /// Parents of the mother of the index.
+    pub maternal_grandparents: Vec<String>,
+}
+
+impl Relatives {
+    /// Extract the relatives of `index` from `pedigree`.
+    pub fn new(pedigree: &PedigreeByName, index: &str) -> Self {
+        let Some(index) = pedigree.individuals.get(index) else {
+            return Self::default();
+        };
+        let parents_of = |name: &Option<String>| -> Vec<String> {
+            name.as_ref()
+                .and_then(|name| pedigree.individuals.get(name))
+                .map(|individual| {
+                    [&individual.father, &individual.mother]
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let unaffected_siblings = pedigree
+            .individuals
+            .values()
+            .filter(|individual| {
+                individual.name != index.name
+                    && matches!(individual.disease, Disease::Unaffected)
+                    && (index.father.is_some() || index.mother.is_some())
+                    && individual.father == index.father
+                    && individual.mother == index.mother
+            })
+            .map(|individual| individual.name.clone())
+            .collect();
+
+        Self {
+            unaffected_siblings,
+            paternal_grandparents: parents_of(&index.father),
+            maternal_grandparents: parents_of(&index.mother),
+        }
+    }
+
+    /// Return the samples among `samples` that carry `seqvar` (het. or hom. alt.).
+    fn carriers(samples: &[String], seqvar: &VariantRecord) -> BTreeSet<String> {
+        samples
+            .iter()
+            .filter(|sample| {
+                matches!(
+                    genotype(seqvar, sample),
+                    Some(common::Genotype::Het | common::Genotype::HomAlt)
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Return whether any unaffected sibling is hom. alt. for `seqvar`.
+    pub fn sibling_hom_alt(&self, seqvar: &VariantRecord) -> bool {
+        self.unaffected_siblings
+            .iter()
+            .any(|sibling| genotype(seqvar, sibling) == Some(common::Genotype::HomAlt))
+    }
+
+    /// Construct the `HetVariant` for `seqvar`.
+    ///
+    /// If the side cannot be determined from the parents, the grandparents are
+    /// used: the variant is assigned to a side if it is only carried by the
+    /// grandparents of this side.
+    pub fn het_variant(&self, seqvar: &VariantRecord, parental_side: Option<Side>) -> HetVariant {
+        let side = parental_side.or_else(|| {
+            let paternal = !Self::carriers(&self.paternal_grandparents, seqvar).is_empty();
+            let maternal = !Self::carriers(&self.maternal_grandparents, seqvar).is_empty();
+            match (paternal, maternal) {
+                (true, false) => Some(Side::Paternal),
+                (false, true) => Some(Side::Maternal),
+                _ => None,
+            }
+        });
+        HetVariant {
+            side,
+            sibling_carriers: Self::carriers(&self.unaffected_siblings, seqvar),
+        }
+    }
+}
+
+/// Return the parsed genotype of `sample` in `seqvar`, if any.
+fn genotype(seqvar: &VariantRecord, sample: &str) -> Option<common::Genotype> {
+    seqvar
+        .call_infos
+        .get(sample)
+        .and_then(|call_info| call_info.genotype.as_ref())
+        .and_then(|gt| gt.parse().ok())
+}
+
+/// Return whether there is a pair of het. variants compatible with compound
+/// heterozygous inheritance.
+///
+/// The variants of a pair must not be inherited from the same side and must
+/// not both be carried by the same unaffected sibling.
+pub fn has_comphet_pair(het_variants: &[HetVariant]) -> bool {
+    het_variants.iter().enumerate().any(|(i, a)| {
+        het_variants[(i + 1)..].iter().any(|b| {
+            let same_side = a.side.is_some() && a.side == b.side;
+            let same_sibling = !a.sibling_carriers.is_disjoint(&b.sibling_carriers);
+            !same_side && !same_sibling
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::ped::{Disease, Individual, PedigreeByName, Sex};
+
+    use crate::pbs::varfish::v1::seqvars::query as pb_query;
+    use crate::seqvars::query::schema::data::{CallInfo, VariantRecord};
+
+    use super::{HetVariant, InheritanceModel, Relatives, Side};
+
+    fn individual(
+        name: &str,
+        parents: Option<(&str, &str)>,
+        sex: Sex,
+        disease: Disease,
+    ) -> (String, Individual) {
+        (
+            name.into(),
+            Individual {
+                family: "FAM".into(),
+                name: name.into(),
+                father: parents.map(|(father, _)| father.into()),
+                mother: parents.map(|(_, mother)| mother.into()),
+                sex,
+                disease,
+            },
+        )
+    }
+
+    fn pedigree() -> PedigreeByName {
+        PedigreeByName {
+            individuals: [
+                individual("father", None, Sex::Male, Disease::Unaffected),
+                individual("mother", None, Sex::Female, Disease::Unaffected),
+                individual(
+                    "sibling",
+                    Some(("father", "mother")),
+                    Sex::Male,
+                    Disease::Unknown,
+                ),
+                individual(
+                    "index",
+                    Some(("father", "mother")),
+                    Sex::Male,
+                    Disease::Affected,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(InheritanceModel::Ar, pb_query::RecessiveMode::Any, [
+        pb_query::GenotypeChoice::RecessiveFather,
+        pb_query::GenotypeChoice::RecessiveMother,
+        pb_query::GenotypeChoice::Any,
+        pb_query::GenotypeChoice::RecessiveIndex,
+    ])]
+    #[case(InheritanceModel::Ad, pb_query::RecessiveMode::Disabled, [
+        pb_query::GenotypeChoice::Ref,
+        pb_query::GenotypeChoice::Ref,
+        pb_query::GenotypeChoice::Any,
+        pb_query::GenotypeChoice::Het,
+    ])]
+    #[case(InheritanceModel::Xlr, pb_query::RecessiveMode::Disabled, [
+        pb_query::GenotypeChoice::Ref,
+        pb_query::GenotypeChoice::NonHom,
+        pb_query::GenotypeChoice::Any,
+        pb_query::GenotypeChoice::Hom,
+    ])]
+    #[case(InheritanceModel::DeNovo, pb_query::RecessiveMode::Disabled, [
+        pb_query::GenotypeChoice::Ref,
+        pb_query::GenotypeChoice::Ref,
+        pb_query::GenotypeChoice::Any,
+        pb_query::GenotypeChoice::Het,
+    ])]
+    fn derive_genotype_settings(
+        #[case] model: InheritanceModel,
+        #[case] expected_mode: pb_query::RecessiveMode,
+        #[case] expected_genotypes: [pb_query::GenotypeChoice; 4],
+    ) -> Result<(), anyhow::Error> {
+        let settings = pb_query::QuerySettingsGenotype {
+            report_partial_hits: true,
+            ..Default::default()
+        };
+
+        let settings = super::derive_genotype_settings(&pedigree(), model, settings)?;
+
+        assert_eq!(settings.recessive_mode, expected_mode as i32);
+        assert_eq!(
+            settings
+                .sample_genotypes
+                .iter()
+                .map(|choice| (choice.sample.as_str(), choice.genotype))
+                .collect::<Vec<_>>(),
+            ["father", "mother", "sibling", "index"]
+                .into_iter()
+                .zip(expected_genotypes.into_iter().map(|gt| gt as i32))
+                .collect::<Vec<_>>()
+        );
+        assert!(settings.report_partial_hits);
+
+        Ok(())
+    }
+
+    #[test]
+    fn derive_genotype_settings_no_affected() {
+        let pedigree = PedigreeByName {
+            individuals: [individual("index", None, Sex::Male, Disease::Unknown)]
+                .into_iter()
+                .collect(),
+        };
+
+        assert!(super::derive_genotype_settings(
+            &pedigree,
+            InheritanceModel::Ad,
+            Default::default()
+        )
+        .is_err());
+    }
+
+    fn pedigree_three_generations() -> PedigreeByName {
+        PedigreeByName {
+            individuals: [
+                individual("grandfather", None, Sex::Male, Disease::Unaffected),
+                individual("grandmother", None, Sex::Female, Disease::Unaffected),
+                individual(
+                    "father",
+                    Some(("grandfather", "grandmother")),
+                    Sex::Male,
+                    Disease::Unaffected,
+                ),
+                individual("mother", None, Sex::Female, Disease::Unaffected),
+                individual(
+                    "sibling",
+                    Some(("father", "mother")),
+                    Sex::Female,
+                    Disease::Unaffected,
+                ),
+                individual(
+                    "index",
+                    Some(("father", "mother")),
+                    Sex::Male,
+                    Disease::Affected,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn relatives_new() {
+        assert_eq!(
+            Relatives::new(&pedigree_three_generations(), "index"),
+            Relatives {
+                unaffected_siblings: vec!["sibling".into()],
+                paternal_grandparents: vec!["grandfather".into(), "grandmother".into()],
+                maternal_grandparents: vec![],
+            }
+        );
+        assert_eq!(
+            Relatives::new(&pedigree_three_generations(), "unknown"),
+            Relatives::default()
+        );
+    }
+
+    fn seqvar(gts: &[(&str, &str)]) -> VariantRecord {
+        VariantRecord {
+            call_infos: gts
+                .iter()
+                .map(|(sample, gt)| {
+                    (
+                        sample.to_string(),
+                        CallInfo {
+                            sample: sample.to_string(),
+                            genotype: Some(gt.to_string()),
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(&[("grandfather", "0/1"), ("sibling", "0/0")], None, Some(Side::Paternal), 0)]
+    #[case(&[("grandfather", "0/0"), ("sibling", "0/1")], None, None, 1)]
+    #[case(&[("grandfather", "0/1"), ("sibling", "1/1")], Some(Side::Maternal), Some(Side::Maternal), 1)]
+    fn relatives_het_variant(
+        #[case] gts: &[(&str, &str)],
+        #[case] parental_side: Option<Side>,
+        #[case] expected_side: Option<Side>,
+        #[case] expected_sibling_carriers: usize,
+    ) {
+        let relatives = Relatives::new(&pedigree_three_generations(), "index");
+
+        let het_variant = relatives.het_variant(&seqvar(gts), parental_side);
+
+        assert_eq!(het_variant.side, expected_side);
+        assert_eq!(
+            het_variant.sibling_carriers.len(),
+            expected_sibling_carriers
+        );
+    }
+
+    #[test]
+    fn relatives_sibling_hom_alt() {
+        let relatives = Relatives::new(&pedigree_three_generations(), "index");
+
+        assert!(relatives.sibling_hom_alt(&seqvar(&[("sibling", "1/1")])));
+        assert!(!relatives.sibling_hom_alt(&seqvar(&[("sibling", "0/1")])));
+    }
+
+    fn het_variant(side: Option<Side>, sibling_carriers: &[&str]) -> HetVariant {
+        HetVariant {
+            side,
+            sibling_carriers: sibling_carriers.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(vec![het_variant(None, &[]), het_variant(None, &[])], true)]
+    #[case(vec![het_variant(Some(Side::Paternal), &[]), het_variant(Some(Side::Maternal), &[])], true)]
+    #[case(vec![het_variant(Some(Side::Paternal), &[]), het_variant(Some(Side::Paternal), &[])], false)]
+    #[case(vec![het_variant(None, &["sibling"]), het_variant(None, &["sibling"])], false)]
+    #[case(vec![
+        het_variant(Some(Side::Paternal), &["sibling"]),
+        het_variant(Some(Side::Maternal), &["sibling"]),
+        het_variant(Some(Side::Maternal), &[]),
+    ], true)]
+    #[case(vec![het_variant(None, &[])], false)]
+    fn has_comphet_pair(#[case] het_variants: Vec<HetVariant>, #[case] expected: bool) {
+        assert_eq!(super::has_comphet_pair(&het_variants), expected);
+    }
+}
@@ -0,0 +1,212 @@
+//! Ranking of records for truncation with `--max-results`.
+//!
+//! When the number of records exceeds `--max-results`, we keep the top-N
+//! records by ClinVar status, consequence impact, and population frequency
+//! rather than the first ones in coordinate order.
+
+use std::io::BufRead as _;
+
+use mehari::annotate::seqvars::ann::PutativeImpact;
+
+use super::{
+    annonars::Annotator,
+    schema::data::{Af as _, VariantRecord},
+};
+
+/// ClinVar status for ranking, most relevant first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClinvarRank {
+    /// Pathogenic or likely pathogenic.
+    Pathogenic,
+    /// Uncertain significance or conflicting classifications.
+    Uncertain,
+    /// Not in ClinVar or unknown classification.
+    Absent,
+    /// Benign or likely benign.
+    Benign,
+}
+
+impl ClinvarRank {
+    /// Construct from the ClinVar germline classification description.
+    pub fn from_description(description: &str) -> Self {
+        match description.to_lowercase().as_str() {
+            "pathogenic" | "pathogenic/likely pathogenic" | "likely pathogenic" => Self::Pathogenic,
+            "uncertain significance" | "conflicting classifications of pathogenicity" => {
+                Self::Uncertain
+            }
+            "benign" | "benign/likely benign" | "likely benign" => Self::Benign,
+            _ => Self::Absent,
+        }
+    }
+}
+
+/// Rank of the putative impact, 0 is highest.
+pub fn impact_rank(putative_impact: PutativeImpact) -> u8 {
+    match putative_impact {
+        PutativeImpact::High => 0,
+        PutativeImpact::Moderate => 1,
+        PutativeImpact::Low => 2,
+        PutativeImpact::Modifier => 3,
+    }
+}
+
+/// Key for ranking a record, smaller is more relevant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankKey {
+    /// ClinVar status.
+    pub clinvar: ClinvarRank,
+    /// Most severe putative impact, 0 is highest.
+    pub impact: u8,
+    /// Maximal allele frequency in gnomAD exomes and genomes.
+    pub max_af: f32,
+}
+
+impl RankKey {
+    /// Construct from `seqvar` with the given ClinVar status.
+    pub fn new(seqvar: &VariantRecord, clinvar: ClinvarRank) -> Self {
+        let impact = seqvar
+            .ann_fields
+            .iter()
+            .map(|ann| impact_rank(ann.putative_impact))
+            .min()
+            .unwrap_or(4);
+        let freqs = &seqvar.population_frequencies;
+        Self {
+            clinvar,
+            impact,
+            max_af: freqs.gnomad_exomes.af().max(freqs.gnomad_genomes.af()),
+        }
+    }
+
+    /// Construct from `seqvar`, looking up the ClinVar status with `annotator`.
+    pub fn with_annotator(
+        seqvar: &VariantRecord,
+        annotator: &Annotator,
+    ) -> Result<Self, anyhow::Error> {
+        let description = annotator
+            .query_clinvar_minimal(seqvar)
+            .map_err(|e| anyhow::anyhow!("problem querying clinvar-minimal: {}", e))?
+            .and_then(|record| record.records.into_iter().next())
+            .and_then(|vcv_record| vcv_record.classifications)
+            .and_then(|c| c.germline_classification)
+            .and_then(|c| c.description)
+            .unwrap_or_default();
+        Ok(Self::new(
+            seqvar,
+            ClinvarRank::from_description(&description),
+        ))
+    }
+
+    /// Compare two keys, smaller is more relevant.
+    pub fn cmp_relevance(&self, other: &Self) -> std::cmp::Ordering {
+        self.clinvar
+            .cmp(&other.clinvar)
+            .then(self.impact.cmp(&other.impact))
+            .then(self.max_af.total_cmp(&other.max_af))
+    }
+}
+
+/// Return flags for each of `keys` whether it is among the top `max_results`.
+///
+/// Ties are broken by the original order.
+pub fn select_top(keys: &[RankKey], max_results: usize) -> Vec<bool> {
+    let mut idxs = (0..keys.len()).collect::<Vec<_>>();
+    idxs.sort_by(|a, b| keys[*a].cmp_relevance(&keys[*b]).then(a.cmp(b)));
+    let mut result = vec![false; keys.len()];
+    for idx in idxs.into_iter().take(max_results) {
+        result[idx] = true;
+    }
+    result
+}
+
+/// Rank the JSONL records in the file at `path` and return the top-N flags.
+pub fn select_top_in_file(
+    path: &std::path::Path,
+    annotator: &Annotator,
+    max_results: usize,
+) -> Result<Vec<bool>, anyhow::Error> {
+    let reader = std::fs::File::open(path)
+        .map(std::io::BufReader::new)
+        .map_err(|e| {
+            anyhow::anyhow!("could not open file {} for ranking: {}", path.display(), e)
+        })?;
+    let keys = reader
+        .lines()
+        .map(|line| {
+            let line = line.map_err(|e| anyhow::anyhow!("could not read line: {}", e))?;
+            let seqvar: VariantRecord = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("could not parse record for ranking: {}", e))?;
+            RankKey::with_annotator(&seqvar, annotator)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(select_top(&keys, max_results))
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::annotate::seqvars::ann;
+
+    use crate::seqvars::query::schema::data::{
+        NuclearFrequencies, PopulationFrequencies, VariantRecord,
+    };
+
+    use super::{ClinvarRank, RankKey};
+
+    #[rstest::rstest]
+    #[case("Pathogenic", ClinvarRank::Pathogenic)]
+    #[case("Pathogenic/Likely pathogenic", ClinvarRank::Pathogenic)]
+    #[case("Uncertain significance", ClinvarRank::Uncertain)]
+    #[case("Likely benign", ClinvarRank::Benign)]
+    #[case("", ClinvarRank::Absent)]
+    fn clinvar_rank_from_description(#[case] description: &str, #[case] expected: ClinvarRank) {
+        assert_eq!(ClinvarRank::from_description(description), expected);
+    }
+
+    fn seqvar(putative_impact: ann::PutativeImpact, het: i32) -> VariantRecord {
+        VariantRecord {
+            ann_fields: vec![ann::AnnField {
+                putative_impact,
+                ..Default::default()
+            }],
+            population_frequencies: PopulationFrequencies {
+                gnomad_exomes: NuclearFrequencies {
+                    an: 1000,
+                    het,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rank_key_new() {
+        let key = RankKey::new(
+            &seqvar(ann::PutativeImpact::Moderate, 10),
+            ClinvarRank::Absent,
+        );
+        assert_eq!(key.impact, 1);
+        assert_eq!(key.max_af, 0.01);
+    }
+
+    #[test]
+    fn select_top() {
+        let keys = [
+            RankKey::new(&seqvar(ann::PutativeImpact::Low, 0), ClinvarRank::Absent),
+            RankKey::new(&seqvar(ann::PutativeImpact::High, 10), ClinvarRank::Absent),
+            RankKey::new(&seqvar(ann::PutativeImpact::High, 1), ClinvarRank::Absent),
+            RankKey::new(
+                &seqvar(ann::PutativeImpact::Low, 0),
+                ClinvarRank::Pathogenic,
+            ),
+            RankKey::new(&seqvar(ann::PutativeImpact::High, 1), ClinvarRank::Benign),
+        ];
+
+        assert_eq!(
+            super::select_top(&keys, 2),
+            vec![false, false, true, true, false]
+        );
+        assert_eq!(super::select_top(&keys, 10), vec![true; 5]);
+    }
+}
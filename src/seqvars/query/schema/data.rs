@@ -45,6 +45,12 @@ pub struct CallInfo {
     pub ad: Option<i32>,
     /// Physical phasing ID for this sample.
     pub ps: Option<i32>,
+    /// Phred-scaled genotype likelihoods (hom. ref., het., hom. alt.), if available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pl: Option<Vec<i32>>,
+    /// Heteroplasmy fraction of the alternative allele on chrMT, if available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hf: Option<f32>,
 }
 
 impl Eq for CallInfo {}
@@ -440,6 +446,33 @@ impl TryFromVcf for CallInfos {
             } else {
                 None
             };
+            // Use `FORMAT/PL` if present, falling back to `FORMAT/GL`.
+            let pl = match (sample.get("PL"), sample.get("GL")) {
+                (
+                    Some(Some(vcf::variant::record_buf::samples::sample::value::Value::Array(
+                        vcf::variant::record_buf::samples::sample::value::Array::Integer(pl),
+                    ))),
+                    _,
+                ) => pl.iter().copied().collect::<Option<Vec<_>>>(),
+                (
+                    _,
+                    Some(Some(vcf::variant::record_buf::samples::sample::value::Value::Array(
+                        vcf::variant::record_buf::samples::sample::value::Array::Float(gl),
+                    ))),
+                ) => gl
+                    .iter()
+                    .map(|gl| gl.map(|gl| (-10.0 * gl).round() as i32))
+                    .collect::<Option<Vec<_>>>(),
+                _ => None,
+            };
+            let hf = if let Some(Some(
+                vcf::variant::record_buf::samples::sample::value::Value::Float(hf),
+            )) = sample.get("HF")
+            {
+                Some(*hf)
+            } else {
+                None
+            };
 
             result.insert(
                 name.clone(),
@@ -450,6 +483,8 @@ impl TryFromVcf for CallInfos {
                     dp,
                     ad,
                     ps: phase_set,
+                    pl,
+                    hf,
                 },
             );
         }
@@ -511,6 +546,9 @@ impl TryFromVcf for AnnFields {
 pub struct VariantRecord {
     /// VCF-style variant.
     pub vcf_variant: VcfVariant,
+    /// Values of the VCF `FILTER` column, empty if missing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
     /// Call information.
     pub call_infos: indexmap::IndexMap<String, CallInfo>,
     /// Annotation fields.
@@ -553,12 +591,14 @@ impl TryFromVcf for VariantRecord {
         header: &vcf::Header,
     ) -> Result<Self, Self::Error> {
         let vcf_variant = VcfVariant::try_from_vcf(record, header)?;
+        let filters = record.filters().as_ref().iter().cloned().collect();
         let CallInfos { call_infos } = CallInfos::try_from_vcf(record, header)?;
         let AnnFields { ann_fields } = AnnFields::try_from_vcf(record, header)?;
         let population_frequencies = PopulationFrequencies::try_from_vcf(record, header)?;
 
         Ok(Self {
             vcf_variant,
+            filters,
             call_infos,
             ann_fields,
             population_frequencies,
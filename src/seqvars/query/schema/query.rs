@@ -5,6 +5,7 @@
 //! here.
 
 use crate::pbs::varfish::v1::seqvars::query as pb_query;
+use crate::seqvars::query::interpreter::filter_expression::Expr;
 
 /// Enumeration for recessvive mode queries.
 #[derive(
@@ -58,6 +59,71 @@ impl TryFrom<pb_query::RecessiveMode> for RecessiveMode {
     }
 }
 
+/// Strategy for handling no-call genotypes.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum NoCallStrategy {
+    /// Use the per-sample `include_no_call` settings.
+    #[default]
+    PerSample,
+    /// Treat no-call genotypes as hom. ref.
+    TreatAsRef,
+    /// Treat no-call genotypes as compatible with any genotype.
+    TreatAsAny,
+    /// Drop variants with no-call genotypes.
+    DropVariant,
+}
+
+impl From<pb_query::NoCallStrategy> for NoCallStrategy {
+    fn from(value: pb_query::NoCallStrategy) -> Self {
+        match value {
+            pb_query::NoCallStrategy::Unspecified => NoCallStrategy::PerSample,
+            pb_query::NoCallStrategy::TreatAsRef => NoCallStrategy::TreatAsRef,
+            pb_query::NoCallStrategy::TreatAsAny => NoCallStrategy::TreatAsAny,
+            pb_query::NoCallStrategy::DropVariant => NoCallStrategy::DropVariant,
+        }
+    }
+}
+
+/// Genotype after applying the `NoCallStrategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedGenotype<'a> {
+    /// Called genotype (or no-call treated as hom. ref.).
+    Called(&'a str),
+    /// No-call genotype to be handled by the per-sample settings.
+    NoCall(&'a str),
+    /// No-call genotype compatible with any genotype.
+    Any,
+    /// No-call genotype, the variant is to be dropped.
+    Drop,
+}
+
+impl NoCallStrategy {
+    /// Apply the strategy to the genotype string `gt_str`.
+    pub fn resolve(self, gt_str: &str) -> ResolvedGenotype<'_> {
+        if !considered_no_call(gt_str) {
+            return ResolvedGenotype::Called(gt_str);
+        }
+        match self {
+            NoCallStrategy::PerSample => ResolvedGenotype::NoCall(gt_str),
+            NoCallStrategy::TreatAsRef => ResolvedGenotype::Called("0/0"),
+            NoCallStrategy::TreatAsAny => ResolvedGenotype::Any,
+            NoCallStrategy::DropVariant => ResolvedGenotype::Drop,
+        }
+    }
+}
+
 /// Enumeration type for genotype choice.
 #[derive(
     Debug,
@@ -262,6 +328,11 @@ pub struct QuerySettingsGenotype {
     pub recessive_mode: RecessiveMode,
     /// Mapping from sample name to sample genotype choice.
     pub sample_genotypes: indexmap::IndexMap<String, SampleGenotypeChoice>,
+    /// Whether to report genes with a single qualifying het. variant in
+    /// compound heterozygous mode.
+    pub report_partial_hits: bool,
+    /// How to handle no-call genotypes.
+    pub no_call_strategy: NoCallStrategy,
 }
 
 /// Support code for `QuerySettingsGenotype`.
@@ -295,6 +366,8 @@ pub(crate) mod query_settings_genotype {
         DuplicateSample(String),
         #[error("Invalid sample genotype choice: {0}")]
         InvalidSampleGenotypeChoice(#[from] super::sample_genotype_choice::Error),
+        #[error("Cannot convert i32 into protobuf NoCallStrategy: {0}")]
+        UnknownNoCallStrategyInt(i32),
     }
 }
 
@@ -413,10 +486,15 @@ impl TryFrom<pb_query::QuerySettingsGenotype> for QuerySettingsGenotype {
             }
             sample_genotypes.insert(sample_genotype.sample.clone(), sample_genotype);
         }
+        let no_call_strategy = pb_query::NoCallStrategy::try_from(value.no_call_strategy)
+            .map_err(|_| Self::Error::UnknownNoCallStrategyInt(value.no_call_strategy))?
+            .into();
 
         Ok(Self {
             recessive_mode,
             sample_genotypes,
+            report_partial_hits: value.report_partial_hits,
+            no_call_strategy,
         })
     }
 }
@@ -464,6 +542,8 @@ impl From<pb_query::SampleQualitySettings> for SampleQualitySettings {
 pub struct QuerySettingsQuality {
     /// Mapping from sample name to sample quality settings.
     pub sample_qualities: indexmap::IndexMap<String, SampleQualitySettings>,
+    /// Acceptable values of the VCF `FILTER` column; empty allows all.
+    pub allowed_filters: Vec<String>,
 }
 
 /// Supporting code for `QuerySettingsQuality`.
@@ -488,7 +568,10 @@ impl TryFrom<pb_query::QuerySettingsQuality> for QuerySettingsQuality {
             }
             sample_qualities.insert(sample_quality.sample.clone(), sample_quality);
         }
-        Ok(Self { sample_qualities })
+        Ok(Self {
+            sample_qualities,
+            allowed_filters: value.allowed_filters,
+        })
     }
 }
 
@@ -1050,12 +1133,19 @@ impl From<Consequence> for mehari::annotate::seqvars::ann::Consequence {
 pub struct QuerySettingsConsequence {
     /// Variant types.
     pub variant_types: Vec<VariantType>,
-    /// Transcript types.
+    /// Transcript types, based on `ANN` feature biotype.
     pub transcript_types: Vec<TranscriptType>,
     /// Consequences to consider.
     pub consequences: Vec<Consequence>,
-    /// Maximal distance to next exon.
+    /// Maximal distance to next exon, based on `ANN` distance.
     pub max_dist_to_exon: Option<i32>,
+    /// Whether to only consider MANE Select / MANE Plus Clinical transcripts.
+    ///
+    /// MANE membership is taken from the `ManeSelect` / `ManePlusClinical` feature
+    /// biotype of the `ANN` annotation and not looked up in the genes database.  The
+    /// input must thus be annotated with a transcript database that includes the MANE
+    /// tags (e.g., mehari); otherwise, no annotation passes.
+    pub mane_transcripts_only: bool,
 }
 
 /// Supporting code for `QuerySettingsConsequence`.
@@ -1117,6 +1207,7 @@ impl TryFrom<pb_query::QuerySettingsConsequence> for QuerySettingsConsequence {
             transcript_types,
             consequences,
             max_dist_to_exon: value.max_dist_to_exon,
+            mane_transcripts_only: value.mane_transcripts_only,
         })
     }
 }
@@ -1232,6 +1323,8 @@ pub struct QuerySettingsClinVar {
     pub germline_descriptions: Vec<ClinvarGermlineAggregateDescription>,
     /// Whether to include conflicting interpretation ClinVar variants.
     pub allow_conflicting_interpretations: bool,
+    /// Minimal number of review status stars (0-4), if any.
+    pub min_review_stars: Option<u32>,
 }
 
 /// Supporting code for `QuerySettingsClinVar`.
@@ -1245,6 +1338,8 @@ pub(crate) mod query_settings_clinvar {
         UnknownClinvarGermlineAggregateDescriptionValue(
             super::pb_query::ClinvarGermlineAggregateDescription,
         ),
+        #[error("Invalid number of review stars (must be 0-4): {0}")]
+        InvalidMinReviewStars(u32),
     }
 }
 
@@ -1267,15 +1362,238 @@ impl TryFrom<pb_query::QuerySettingsClinVar> for QuerySettingsClinVar {
                     .map_err(|_| Self::Error::UnknownClinvarGermlineAggregateDescriptionValue(v))
             })
             .collect::<Result<Vec<_>, _>>()?;
+        if let Some(min_review_stars) = value.min_review_stars {
+            if min_review_stars > 4 {
+                return Err(Self::Error::InvalidMinReviewStars(min_review_stars));
+            }
+        }
 
         Ok(Self {
             presence_required: value.presence_required,
             germline_descriptions,
             allow_conflicting_interpretations: value.allow_conflicting_interpretations,
+            min_review_stars: value.min_review_stars,
+        })
+    }
+}
+
+/// Tumor/normal somatic query settings.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QuerySettingsSomatic {
+    /// Whether to enable the somatic query mode.
+    pub enabled: bool,
+    /// Name of the tumor sample.
+    pub tumor_sample: String,
+    /// Name of the matched normal sample, if any.
+    pub normal_sample: Option<String>,
+    /// Minimal variant allele fraction in the tumor sample.
+    pub min_tumor_vaf: Option<f32>,
+    /// Maximal variant allele fraction in the normal sample for somatic status.
+    pub max_normal_vaf: Option<f32>,
+    /// Whether to also report candidates with germline status.
+    pub include_germline: bool,
+}
+
+impl Eq for QuerySettingsSomatic {}
+
+/// Supporting code for `QuerySettingsSomatic`.
+pub(crate) mod query_settings_somatic {
+    /// Error type for `QuerySettingsSomatic::try_from()`.
+    #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        #[error("Somatic mode enabled but no tumor sample given")]
+        MissingTumorSample,
+        #[error("Tumor and normal sample must differ: {0}")]
+        SameTumorAndNormal(String),
+    }
+}
+
+impl TryFrom<pb_query::QuerySettingsSomatic> for QuerySettingsSomatic {
+    type Error = query_settings_somatic::Error;
+
+    fn try_from(value: pb_query::QuerySettingsSomatic) -> Result<Self, Self::Error> {
+        if value.enabled {
+            if value.tumor_sample.is_empty() {
+                return Err(Self::Error::MissingTumorSample);
+            }
+            if value.normal_sample.as_ref() == Some(&value.tumor_sample) {
+                return Err(Self::Error::SameTumorAndNormal(value.tumor_sample));
+            }
+        }
+
+        Ok(Self {
+            enabled: value.enabled,
+            tumor_sample: value.tumor_sample,
+            normal_sample: value.normal_sample,
+            min_tumor_vaf: value.min_tumor_vaf,
+            max_normal_vaf: value.max_normal_vaf,
+            include_germline: value.include_germline,
+        })
+    }
+}
+
+/// Trio de novo query settings.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QuerySettingsDeNovo {
+    /// Whether to compute the de novo posterior probability.
+    pub enabled: bool,
+    /// Name of the index sample.
+    pub index_sample: String,
+    /// Name of the father sample.
+    pub father_sample: String,
+    /// Name of the mother sample.
+    pub mother_sample: String,
+    /// Minimal de novo posterior probability.
+    pub min_probability: Option<f32>,
+}
+
+impl Eq for QuerySettingsDeNovo {}
+
+/// Supporting code for `QuerySettingsDeNovo`.
+pub(crate) mod query_settings_de_novo {
+    /// Error type for `QuerySettingsDeNovo::try_from()`.
+    #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        #[error("De novo mode enabled but index, father, or mother sample missing")]
+        MissingSample,
+        #[error("Trio samples must differ: {0}")]
+        SameSample(String),
+    }
+}
+
+impl TryFrom<pb_query::QuerySettingsDeNovo> for QuerySettingsDeNovo {
+    type Error = query_settings_de_novo::Error;
+
+    fn try_from(value: pb_query::QuerySettingsDeNovo) -> Result<Self, Self::Error> {
+        if value.enabled {
+            let samples = [
+                &value.index_sample,
+                &value.father_sample,
+                &value.mother_sample,
+            ];
+            if samples.iter().any(|sample| sample.is_empty()) {
+                return Err(Self::Error::MissingSample);
+            }
+            if let Some(sample) = samples
+                .iter()
+                .enumerate()
+                .find(|(i, sample)| samples[(i + 1)..].contains(sample))
+                .map(|(_, sample)| sample)
+            {
+                return Err(Self::Error::SameSample(sample.to_string()));
+            }
+        }
+
+        Ok(Self {
+            enabled: value.enabled,
+            index_sample: value.index_sample,
+            father_sample: value.father_sample,
+            mother_sample: value.mother_sample,
+            min_probability: value.min_probability,
+        })
+    }
+}
+
+/// Problematic region tracks.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum ProblematicRegionType {
+    /// Low-complexity region.
+    LowComplexity,
+    /// Segmental duplication.
+    Segdup,
+    /// ENCODE blacklist region.
+    Blacklist,
+}
+
+/// Supporting code for `ProblematicRegionType`.
+pub(crate) mod problematic_region_type {
+    /// Error type for `ProblematicRegionType::try_from()`.
+    #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        #[error("Cannot convert protobuf ProblematicRegionType: {0:?}")]
+        UnknownProblematicRegionTypeValue(super::pb_query::ProblematicRegionType),
+    }
+}
+
+impl TryFrom<pb_query::ProblematicRegionType> for ProblematicRegionType {
+    type Error = problematic_region_type::Error;
+
+    fn try_from(value: pb_query::ProblematicRegionType) -> Result<Self, Self::Error> {
+        match value {
+            pb_query::ProblematicRegionType::LowComplexity => {
+                Ok(ProblematicRegionType::LowComplexity)
+            }
+            pb_query::ProblematicRegionType::Segdup => Ok(ProblematicRegionType::Segdup),
+            pb_query::ProblematicRegionType::Blacklist => Ok(ProblematicRegionType::Blacklist),
+            _ => Err(problematic_region_type::Error::UnknownProblematicRegionTypeValue(value)),
+        }
+    }
+}
+
+/// Problematic region query settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QuerySettingsProblematicRegions {
+    /// Whether to annotate the results with the overlapping problematic regions.
+    pub annotate: bool,
+    /// Exclude variants overlapping problematic regions of these types.
+    pub exclude: Vec<ProblematicRegionType>,
+}
+
+/// Supporting code for `QuerySettingsProblematicRegions`.
+pub(crate) mod query_settings_problematic_regions {
+    /// Error type for `QuerySettingsProblematicRegions::try_from()`.
+    #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        #[error("Cannot convert i32 into protobuf ProblematicRegionType: {0}")]
+        ProblematicRegionTypeInt(i32),
+        #[error("Cannot convert protobuf ProblematicRegionType: {0:?}")]
+        ProblematicRegionTypeValue(super::pb_query::ProblematicRegionType),
+    }
+}
+
+impl TryFrom<pb_query::QuerySettingsProblematicRegions> for QuerySettingsProblematicRegions {
+    type Error = query_settings_problematic_regions::Error;
+
+    fn try_from(value: pb_query::QuerySettingsProblematicRegions) -> Result<Self, Self::Error> {
+        let exclude = value
+            .exclude
+            .into_iter()
+            .map(|v| {
+                let v = pb_query::ProblematicRegionType::try_from(v).map_err(|_| {
+                    query_settings_problematic_regions::Error::ProblematicRegionTypeInt(v)
+                })?;
+                ProblematicRegionType::try_from(v).map_err(|_| {
+                    query_settings_problematic_regions::Error::ProblematicRegionTypeValue(v)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            annotate: value.annotate,
+            exclude,
         })
     }
 }
 
+/// Regulatory element query settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QuerySettingsRegulatory {
+    /// Whether to annotate the results with the overlapping regulatory elements.
+    pub annotate: bool,
+    /// Whether to only keep variants overlapping a regulatory element.
+    pub require_overlap: bool,
+}
+
+impl From<pb_query::QuerySettingsRegulatory> for QuerySettingsRegulatory {
+    fn from(value: pb_query::QuerySettingsRegulatory) -> Self {
+        Self {
+            annotate: value.annotate,
+            require_overlap: value.require_overlap,
+        }
+    }
+}
+
 /// Query settings for one case.
 #[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct CaseQuery {
@@ -1291,6 +1609,16 @@ pub struct CaseQuery {
     pub locus: QuerySettingsLocus,
     /// ClinVar query settings.
     pub clinvar: QuerySettingsClinVar,
+    /// Optional user-defined filter expression, see `interpreter::filter_expression`.
+    pub filter_expression: Option<String>,
+    /// Tumor/normal somatic query settings.
+    pub somatic: QuerySettingsSomatic,
+    /// Trio de novo query settings.
+    pub de_novo: QuerySettingsDeNovo,
+    /// Problematic region query settings.
+    pub problematic_regions: QuerySettingsProblematicRegions,
+    /// Regulatory element query settings.
+    pub regulatory: QuerySettingsRegulatory,
 }
 
 /// Supporting code for `CaseQuery`.
@@ -1306,6 +1634,14 @@ pub(crate) mod case_query {
         Consequence(#[from] super::query_settings_consequence::Error),
         #[error("Problem converting protobuf for clinvar: {0}")]
         Clinvar(#[from] super::query_settings_clinvar::Error),
+        #[error("Invalid filter expression: {0}")]
+        FilterExpression(String),
+        #[error("Problem converting protobuf for somatic: {0}")]
+        Somatic(#[from] super::query_settings_somatic::Error),
+        #[error("Problem converting protobuf for de novo: {0}")]
+        DeNovo(#[from] super::query_settings_de_novo::Error),
+        #[error("Problem converting protobuf for problematic regions: {0}")]
+        ProblematicRegions(#[from] super::query_settings_problematic_regions::Error),
     }
 }
 
@@ -1320,6 +1656,11 @@ impl TryFrom<pb_query::CaseQuery> for CaseQuery {
             consequence,
             locus,
             clinvar,
+            filter_expression,
+            somatic,
+            de_novo,
+            problematic_regions,
+            regulatory,
         } = value;
 
         let genotype = QuerySettingsGenotype::try_from(genotype.unwrap_or(Default::default()))
@@ -1333,6 +1674,19 @@ impl TryFrom<pb_query::CaseQuery> for CaseQuery {
         let locus = QuerySettingsLocus::from(locus.unwrap_or(Default::default()));
         let clinvar = QuerySettingsClinVar::try_from(clinvar.unwrap_or(Default::default()))
             .map_err(Self::Error::Clinvar)?;
+        if let Some(filter_expression) = filter_expression.as_ref() {
+            Expr::parse(filter_expression)
+                .map_err(|e| Self::Error::FilterExpression(e.to_string()))?;
+        }
+        let somatic = QuerySettingsSomatic::try_from(somatic.unwrap_or(Default::default()))
+            .map_err(Self::Error::Somatic)?;
+        let de_novo = QuerySettingsDeNovo::try_from(de_novo.unwrap_or(Default::default()))
+            .map_err(Self::Error::DeNovo)?;
+        let problematic_regions = QuerySettingsProblematicRegions::try_from(
+            problematic_regions.unwrap_or(Default::default()),
+        )
+        .map_err(Self::Error::ProblematicRegions)?;
+        let regulatory = QuerySettingsRegulatory::from(regulatory.unwrap_or(Default::default()));
 
         Ok(Self {
             genotype,
@@ -1341,6 +1695,11 @@ impl TryFrom<pb_query::CaseQuery> for CaseQuery {
             consequence,
             locus,
             clinvar,
+            filter_expression,
+            somatic,
+            de_novo,
+            problematic_regions,
+            regulatory,
         })
     }
 }
@@ -1496,11 +1855,27 @@ mod tests {
         }
     }
 
+    #[rstest::rstest]
+    #[case(NoCallStrategy::PerSample, "0/1", ResolvedGenotype::Called("0/1"))]
+    #[case(NoCallStrategy::PerSample, "./.", ResolvedGenotype::NoCall("./."))]
+    #[case(NoCallStrategy::TreatAsRef, "./.", ResolvedGenotype::Called("0/0"))]
+    #[case(NoCallStrategy::TreatAsAny, ".", ResolvedGenotype::Any)]
+    #[case(NoCallStrategy::DropVariant, "0/.", ResolvedGenotype::Drop)]
+    fn test_no_call_strategy_resolve(
+        #[case] strategy: NoCallStrategy,
+        #[case] gt_str: &str,
+        #[case] expected: ResolvedGenotype<'static>,
+    ) {
+        assert_eq!(strategy.resolve(gt_str), expected);
+    }
+
     #[test]
     fn test_query_settings_genotype_recessive_index_none() {
         let query_settings_genotype = QuerySettingsGenotype {
             recessive_mode: RecessiveMode::CompoundHeterozygous,
             sample_genotypes: Default::default(),
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
 
         assert_eq!(
@@ -1521,6 +1896,8 @@ mod tests {
                     enabled: true,
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
 
         assert_eq!(
@@ -1547,6 +1924,8 @@ mod tests {
                     enabled: true,
                 }
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
 
         assert_eq!(
@@ -1568,6 +1947,8 @@ mod tests {
                 include_no_call: true,
                 enabled: true,
             }],
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         let query_settings_genotype = QuerySettingsGenotype {
             recessive_mode: RecessiveMode::Disabled,
@@ -1584,6 +1965,8 @@ mod tests {
                 );
                 map
             },
+            report_partial_hits: false,
+            no_call_strategy: Default::default(),
         };
         assert_eq!(
             QuerySettingsGenotype::try_from(pb_query_settings_genotype).unwrap(),
@@ -1632,6 +2015,7 @@ mod tests {
                 min_ad: Some(40),
                 max_ad: Some(50),
             }],
+            allowed_filters: vec![],
         };
         let query_settings_quality = QuerySettingsQuality {
             sample_qualities: {
@@ -1651,6 +2035,7 @@ mod tests {
                 );
                 map
             },
+            allowed_filters: vec![],
         };
         assert_eq!(
             QuerySettingsQuality::try_from(pb_query_settings_quality).unwrap(),
@@ -2011,6 +2396,7 @@ mod tests {
                 pb_query::Consequence::StartLost as i32,
             ],
             max_dist_to_exon: Some(10),
+            mane_transcripts_only: false,
         };
         let query_settings_consequence = QuerySettingsConsequence {
             variant_types: vec![
@@ -2031,6 +2417,7 @@ mod tests {
                 Consequence::StartLost,
             ],
             max_dist_to_exon: Some(10),
+            mane_transcripts_only: false,
         };
         assert_eq!(
             QuerySettingsConsequence::try_from(pb_query_settings_consequence).unwrap(),
@@ -2132,6 +2519,7 @@ mod tests {
                 pb_query::ClinvarGermlineAggregateDescription::LikelyPathogenic as i32,
             ],
             allow_conflicting_interpretations: true,
+            min_review_stars: None,
         };
         let query_settings_clinvar = QuerySettingsClinVar {
             presence_required: true,
@@ -2140,6 +2528,7 @@ mod tests {
                 ClinvarGermlineAggregateDescription::LikelyPathogenic,
             ],
             allow_conflicting_interpretations: true,
+            min_review_stars: None,
         };
         assert_eq!(
             QuerySettingsClinVar::try_from(pb_query_settings_clinvar).unwrap(),
@@ -2158,6 +2547,8 @@ mod tests {
                     include_no_call: true,
                     enabled: true,
                 }],
+                report_partial_hits: false,
+                no_call_strategy: Default::default(),
             }),
             quality: Some(pb_query::QuerySettingsQuality {
                 sample_qualities: vec![pb_query::SampleQualitySettings {
@@ -2170,6 +2561,7 @@ mod tests {
                     min_ad: Some(40),
                     max_ad: Some(50),
                 }],
+                allowed_filters: vec![],
             }),
             frequency: Some(pb_query::QuerySettingsFrequency {
                 gnomad_exomes: Some(pb_query::NuclearFrequencySettings {
@@ -2228,6 +2620,7 @@ mod tests {
                     pb_query::Consequence::StartLost as i32,
                 ],
                 max_dist_to_exon: Some(10),
+                mane_transcripts_only: false,
             }),
             locus: Some(pb_query::QuerySettingsLocus {
                 genes: vec!["gene".to_string()],
@@ -2243,7 +2636,13 @@ mod tests {
                     pb_query::ClinvarGermlineAggregateDescription::LikelyPathogenic as i32,
                 ],
                 allow_conflicting_interpretations: true,
+                min_review_stars: None,
             }),
+            filter_expression: None,
+            somatic: None,
+            de_novo: None,
+            problematic_regions: None,
+            regulatory: None,
         };
         let case_query = CaseQuery {
             genotype: QuerySettingsGenotype {
@@ -2261,6 +2660,8 @@ mod tests {
                     );
                     map
                 },
+                report_partial_hits: false,
+                no_call_strategy: Default::default(),
             },
             quality: QuerySettingsQuality {
                 sample_qualities: {
@@ -2280,6 +2681,7 @@ mod tests {
                     );
                     map
                 },
+                allowed_filters: vec![],
             },
             frequency: QuerySettingsFrequency {
                 gnomad_exomes: NuclearFrequencySettings {
@@ -2335,6 +2737,7 @@ mod tests {
                     Consequence::StartLost,
                 ],
                 max_dist_to_exon: Some(10),
+                mane_transcripts_only: false,
             },
             locus: QuerySettingsLocus {
                 genes: vec!["gene".to_string()],
@@ -2350,11 +2753,102 @@ mod tests {
                     ClinvarGermlineAggregateDescription::LikelyPathogenic,
                 ],
                 allow_conflicting_interpretations: true,
+                min_review_stars: None,
             },
+            filter_expression: None,
+            somatic: Default::default(),
+            de_novo: Default::default(),
+            problematic_regions: Default::default(),
+            regulatory: Default::default(),
         };
         assert_eq!(CaseQuery::try_from(pb_case_query).unwrap(), case_query);
     }
 
+    #[rstest::rstest]
+    #[case(false, "", None, true)]
+    #[case(true, "tumor", Some("normal"), true)]
+    #[case(true, "tumor", None, true)]
+    #[case(true, "", Some("normal"), false)]
+    #[case(true, "tumor", Some("tumor"), false)]
+    fn test_query_settings_somatic_try_from(
+        #[case] enabled: bool,
+        #[case] tumor_sample: &str,
+        #[case] normal_sample: Option<&str>,
+        #[case] expected_ok: bool,
+    ) {
+        let pb_query_settings_somatic = pb_query::QuerySettingsSomatic {
+            enabled,
+            tumor_sample: tumor_sample.to_string(),
+            normal_sample: normal_sample.map(|s| s.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            QuerySettingsSomatic::try_from(pb_query_settings_somatic).is_ok(),
+            expected_ok
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(false, "", "", "", true)]
+    #[case(true, "index", "father", "mother", true)]
+    #[case(true, "index", "", "mother", false)]
+    #[case(true, "index", "index", "mother", false)]
+    #[case(true, "index", "father", "father", false)]
+    fn test_query_settings_de_novo_try_from(
+        #[case] enabled: bool,
+        #[case] index_sample: &str,
+        #[case] father_sample: &str,
+        #[case] mother_sample: &str,
+        #[case] expected_ok: bool,
+    ) {
+        let pb_query_settings_de_novo = pb_query::QuerySettingsDeNovo {
+            enabled,
+            index_sample: index_sample.to_string(),
+            father_sample: father_sample.to_string(),
+            mother_sample: mother_sample.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            QuerySettingsDeNovo::try_from(pb_query_settings_de_novo).is_ok(),
+            expected_ok
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("gnomad_exomes.af < 0.001", true)]
+    #[case("gnomad_exomes.xyz < 0.001", false)]
+    #[case("pos >", false)]
+    fn test_case_query_try_from_filter_expression(
+        #[case] filter_expression: &str,
+        #[case] expected_ok: bool,
+    ) {
+        let pb_case_query = pb_query::CaseQuery {
+            filter_expression: Some(filter_expression.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(CaseQuery::try_from(pb_case_query).is_ok(), expected_ok);
+    }
+
+    #[rstest::rstest]
+    #[case::empty(vec![], true)]
+    #[case::valid(vec![pb_query::ProblematicRegionType::Segdup as i32, pb_query::ProblematicRegionType::Blacklist as i32], true)]
+    #[case::unspecified(vec![pb_query::ProblematicRegionType::Unspecified as i32], false)]
+    #[case::invalid(vec![42], false)]
+    fn test_query_settings_problematic_regions_try_from(
+        #[case] exclude: Vec<i32>,
+        #[case] expected_ok: bool,
+    ) {
+        let pb_query_settings_problematic_regions = pb_query::QuerySettingsProblematicRegions {
+            annotate: true,
+            exclude,
+        };
+        assert_eq!(
+            QuerySettingsProblematicRegions::try_from(pb_query_settings_problematic_regions)
+                .is_ok(),
+            expected_ok
+        );
+    }
+
     #[rstest::rstest]
     #[case::empty("tests/seqvars/query/empty")]
     // #[case::full("tests/seqvars/query/full")]
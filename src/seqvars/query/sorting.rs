@@ -1,6 +1,36 @@
 //! Code for sorting `VariantRecord` records by HGNC ID or coordinate.
 
-use super::schema::data::VariantRecord;
+use mehari::annotate::seqvars::ann::AnnField;
+
+use super::{ranking, schema::data::VariantRecord};
+
+/// Split `seqvar` into one record per affected gene.
+///
+/// The annotations of each record are restricted to the gene and ordered by
+/// putative impact, such that the worst one comes first.  Records without
+/// annotations are returned as is.
+pub fn split_by_gene(seqvar: VariantRecord) -> Vec<VariantRecord> {
+    if seqvar.ann_fields.is_empty() {
+        return vec![seqvar];
+    }
+    let mut by_gene: indexmap::IndexMap<String, Vec<AnnField>> = indexmap::IndexMap::new();
+    for ann in seqvar.ann_fields.iter() {
+        by_gene
+            .entry(ann.gene_id.clone())
+            .or_default()
+            .push(ann.clone());
+    }
+    by_gene
+        .into_values()
+        .map(|mut ann_fields| {
+            ann_fields.sort_by_key(|ann| ranking::impact_rank(ann.putative_impact));
+            VariantRecord {
+                ann_fields,
+                ..seqvar.clone()
+            }
+        })
+        .collect()
+}
 
 /// Helper wrapper that allows to sort `VariantRecord` by HGNC ID.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -77,3 +107,53 @@ impl Ord for ByCoordinate {
         self.coordinate.cmp(&other.coordinate)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use mehari::annotate::seqvars::ann::{AnnField, PutativeImpact};
+
+    use crate::seqvars::query::schema::data::VariantRecord;
+
+    fn ann(gene_id: &str, putative_impact: PutativeImpact) -> AnnField {
+        AnnField {
+            gene_id: gene_id.into(),
+            putative_impact,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn split_by_gene() {
+        let seqvar = VariantRecord {
+            ann_fields: vec![
+                ann("HGNC:1", PutativeImpact::Modifier),
+                ann("HGNC:2", PutativeImpact::Low),
+                ann("HGNC:1", PutativeImpact::High),
+            ],
+            ..Default::default()
+        };
+
+        let records = super::split_by_gene(seqvar);
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.ann_fields.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    ann("HGNC:1", PutativeImpact::High),
+                    ann("HGNC:1", PutativeImpact::Modifier)
+                ],
+                vec![ann("HGNC:2", PutativeImpact::Low)],
+            ]
+        );
+    }
+
+    #[test]
+    fn split_by_gene_no_ann() {
+        let records = super::split_by_gene(VariantRecord::default());
+
+        assert_eq!(records.len(), 1);
+    }
+}
@@ -12,7 +12,7 @@ use bio::data_structures::interval_tree::IntervalTree;
 use clap::{command, Parser};
 use futures::TryStreamExt as _;
 use mehari::common::{
-    io::std::{open_write_maybe_bgzf, read_lines},
+    io::std::{open_read_maybe_gz, open_write_maybe_bgzf, read_lines},
     noodles::NoodlesVariantReader as _,
 };
 
@@ -20,11 +20,99 @@ use serde_json::to_writer;
 use strum::IntoEnumIterator;
 use thousands::Separable;
 
+use super::manifest::Manifest;
 use crate::{
     common::{self, build_chrom_map, trace_rss_now, GenomeRelease, CHROMS},
     strucvars::query::schema::SvType,
 };
 
+/// Header of the output TSV file.
+const HEADER: [&str; 10] = [
+    "#chromosome",
+    "begin",
+    "chromosome2",
+    "end",
+    "pe_orientation",
+    "sv_type",
+    "carriers",
+    "carriers_het",
+    "carriers_hom",
+    "carriers_hemi",
+];
+
+/// Prefix of the comment line with the genome release in the output TSV file.
+const GENOME_RELEASE_PREFIX: &str = "##genome-release=";
+
+/// Whether `path` is an in-house database TSV file rather than a VCF file.
+fn is_inhouse_tsv(path: &str) -> bool {
+    path.ends_with(".tsv") || path.ends_with(".tsv.gz")
+}
+
+/// Open an in-house database TSV file for writing and write the header.
+pub(crate) fn open_inhouse_tsv_writer(
+    path: &Path,
+    genome_release: GenomeRelease,
+) -> Result<csv::Writer<impl Write>, anyhow::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .flexible(true)
+        .from_writer(
+            open_write_maybe_bgzf(path)
+                .map_err(|e| anyhow::anyhow!("Cannot open {:?} for writing: {:?}", &path, e))?,
+        );
+
+    // Write genome release and header as comments.
+    writer.write_record([format!("{}{}", GENOME_RELEASE_PREFIX, genome_release)])?;
+    writer.write_record(HEADER)?;
+
+    Ok(writer)
+}
+
+/// Read the records of an in-house database TSV file, e.g., of another site.
+///
+/// The header must match the one written by this version and the genome
+/// release, if given in the file, must be `genome_release`.
+pub(crate) fn read_inhouse_tsv(
+    path: &str,
+    genome_release: GenomeRelease,
+) -> Result<Vec<super::output::Record>, anyhow::Error> {
+    let mut seen_header = false;
+    for line in open_read_maybe_gz(path)?.lines() {
+        let line = line?;
+        if let Some(value) = line.strip_prefix(GENOME_RELEASE_PREFIX) {
+            if value != genome_release.to_string() {
+                anyhow::bail!(
+                    "genome release of {} is {}, expected {}",
+                    path,
+                    value,
+                    genome_release
+                );
+            }
+        } else if line.starts_with("#chromosome") {
+            if line.split('\t').ne(HEADER) {
+                anyhow::bail!("incompatible header in {}: {}", path, line);
+            }
+            seen_header = true;
+        } else if !line.starts_with('#') {
+            break;
+        }
+    }
+    if !seen_header {
+        anyhow::bail!("missing header in {}", path);
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(b'\t')
+        .comment(Some(b'#'))
+        .from_reader(open_read_maybe_gz(path)?);
+    reader
+        .deserialize()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))
+}
+
 /// Create one file with records for each chromosome and SV type.
 fn create_tmp_files(
     tmp_dir: &tempfile::TempDir,
@@ -43,28 +131,119 @@ fn create_tmp_files(
     Ok(files)
 }
 
+/// Write `record` from `path` to the temporary file for its chromosome and SV type.
+///
+/// Returns `false` if the record is on a contig that is not in `CHROMS`, e.g., a
+/// decoy, and has been skipped.
+fn write_to_tmp_file(
+    tmp_files: &mut HashMap<(usize, SvType), BufWriter<File>>,
+    chrom_map: &indexmap::IndexMap<String, usize>,
+    path: &str,
+    record: &super::output::Record,
+) -> Result<bool, anyhow::Error> {
+    let Some(chrom_no) = chrom_map.get(&record.chromosome) else {
+        tracing::debug!(
+            "skipping record on unknown contig in {}: {:?}",
+            path,
+            record
+        );
+        return Ok(false);
+    };
+    let mut tmp_file = tmp_files
+        .get_mut(&(*chrom_no, record.sv_type))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no temporary file for {}/{:?} of record in {}: {:?}",
+                &record.chromosome,
+                record.sv_type,
+                path,
+                record
+            )
+        })?;
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    to_writer(&mut tmp_file, record)?;
+    tmp_file.write_all(b"\n")?;
+    Ok(true)
+}
+
 /// Split the input into one file in `tmp_dir` for each chromosome and SV type.
 ///
+/// Input files ending in `.tsv` or `.tsv.gz` are read as in-house databases,
+/// e.g., of other sites, such that their carrier counts are merged.  Records on
+/// contigs that are not in `CHROMS` are skipped with a warning.
+///
+/// If a `manifest` is given, the records of its cases are used as well, VCF files
+/// of cases in the manifest are skipped, and the cases of the other VCF files are
+/// added to it.
+///
 /// Async I/O is used here because we support reading from S3.
 async fn split_input_by_chrom_and_sv_type(
     tmp_dir: &tempfile::TempDir,
     input_vcf_paths: Vec<String>,
     genome_release: GenomeRelease,
+    mut manifest: Option<&mut Manifest>,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("parse all input files and split them up");
     let mut tmp_files = create_tmp_files(tmp_dir)?;
     let chrom_map = build_chrom_map();
     let before_parsing = Instant::now();
     let mut count_files = 0;
+    if let Some(manifest) = manifest.as_deref() {
+        for case in manifest.cases.values() {
+            for record in &case.records {
+                write_to_tmp_file(&mut tmp_files, &chrom_map, &case.path, record)?;
+            }
+        }
+        tracing::debug!(
+            "read {} cases from manifest",
+            manifest.cases.len().separate_with_commas()
+        );
+    }
     for path_input in &input_vcf_paths {
         tracing::debug!("parsing {:?}", &path_input);
+        if is_inhouse_tsv(path_input) {
+            let records = read_inhouse_tsv(path_input, genome_release)?;
+            tracing::debug!(
+                "read {} in-house records",
+                records.len().separate_with_commas()
+            );
+            let mut count_skipped = 0;
+            for record in records {
+                if !write_to_tmp_file(&mut tmp_files, &chrom_map, path_input, &record)? {
+                    count_skipped += 1;
+                }
+            }
+            if count_skipped > 0 {
+                tracing::warn!(
+                    "skipped {} in-house records on unknown contigs in {}",
+                    count_skipped.separate_with_commas(),
+                    path_input
+                );
+            }
+            count_files += 1;
+            continue;
+        }
+
         let mut input_reader = common::noodles::open_vcf_reader(path_input).await?;
         let input_header = input_reader.read_header().await?;
 
-        let (pedigree, _) = common::extract_pedigree_and_case_uuid(&input_header)?;
+        let (pedigree, case_uuid) = common::extract_pedigree_and_case_uuid(&input_header)?;
+        let mut case_records = match manifest.as_deref() {
+            Some(manifest) if manifest.cases.contains_key(&case_uuid) => {
+                tracing::warn!(
+                    "skipping {} as case {} is already in the manifest",
+                    path_input,
+                    &case_uuid
+                );
+                continue;
+            }
+            Some(_) => Some(Vec::new()),
+            None => None,
+        };
         let mut prev = std::time::Instant::now();
         let before_parsing = Instant::now();
         let mut count_records = 0;
+        let mut count_skipped = 0;
 
         let mut records = input_reader.records(&input_header).await;
         while let Some(input_record) = records.try_next().await? {
@@ -75,16 +254,11 @@ async fn split_input_by_chrom_and_sv_type(
                 &pedigree,
             )?;
 
-            let chrom_no = *chrom_map
-                .get(&input_record.chromosome)
-                .expect("unknown chromosome");
-            let sv_type = input_record.sv_type;
-            let mut tmp_file = tmp_files
-                .get_mut(&(chrom_no, sv_type))
-                .expect("no file for chrom/sv_type");
-            #[allow(clippy::needless_borrows_for_generic_args)]
-            to_writer(&mut tmp_file, &input_record)?;
-            tmp_file.write_all(b"\n")?;
+            if !write_to_tmp_file(&mut tmp_files, &chrom_map, path_input, &input_record)? {
+                count_skipped += 1;
+            } else if let Some(case_records) = case_records.as_mut() {
+                case_records.push(input_record.clone());
+            }
 
             // Write out progress indicator every 60 seconds.
             if prev.elapsed().as_secs() >= 60 {
@@ -95,6 +269,13 @@ async fn split_input_by_chrom_and_sv_type(
             count_records += 1;
         }
 
+        if count_skipped > 0 {
+            tracing::warn!(
+                "skipped {} records on unknown contigs in {}",
+                count_skipped.separate_with_commas(),
+                path_input
+            );
+        }
         trace_rss_now();
         tracing::debug!(
             "total time spent parsing {} records: {:?}",
@@ -102,6 +283,17 @@ async fn split_input_by_chrom_and_sv_type(
             before_parsing.elapsed()
         );
 
+        if let (Some(manifest), Some(records)) = (manifest.as_deref_mut(), case_records) {
+            manifest.cases.insert(
+                case_uuid,
+                super::manifest::Case {
+                    case_uuid,
+                    path: path_input.clone(),
+                    records,
+                },
+            );
+        }
+
         count_files += 1;
     }
     tracing::info!(
@@ -229,26 +421,7 @@ fn merge_split_files(
     path_output_tsv: &Path,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("merge all files to {:?}...", &path_output_tsv);
-    let mut writer = csv::WriterBuilder::new()
-        .delimiter(b'\t')
-        .has_headers(false)
-        .from_writer(open_write_maybe_bgzf(path_output_tsv).map_err(|e| {
-            anyhow::anyhow!("Cannot open {:?} for writing: {:?}", &path_output_tsv, e)
-        })?);
-
-    // Write header as comment.
-    writer.write_record([
-        "#chromosome",
-        "begin",
-        "chromosome2",
-        "end",
-        "pe_orientation",
-        "sv_type",
-        "carriers",
-        "carriers_het",
-        "carriers_hom",
-        "carriers_hemi",
-    ])?;
+    let mut writer = open_inhouse_tsv_writer(path_output_tsv, args.genome_release)?;
 
     let mut out_records = 0;
     for chrom in CHROMS {
@@ -280,8 +453,17 @@ pub struct Args {
     #[arg(long)]
     pub path_output: PathBuf,
     /// Input files to cluster, prefix with `@` to file with line-wise paths.
-    #[arg(required = true)]
+    ///
+    /// Files ending in `.tsv` or `.tsv.gz` are in-house databases to merge.
+    #[arg(required_unless_present = "path_manifest")]
     pub path_input: Vec<String>,
+    /// Path to the manifest of the cases in the database, read if it exists and
+    /// updated with the cases of the input VCF files.
+    #[arg(long)]
+    pub path_manifest: Option<PathBuf>,
+    /// UUID of a case to remove from the manifest and thus from the database.
+    #[arg(long, requires = "path_manifest")]
+    pub remove_case: Vec<String>,
 
     /// Minimal reciprocal overlap to use (slightly more strict that the normal
     /// query value of 0.75).
@@ -323,16 +505,46 @@ pub async fn run(common_args: &common::Args, args: &Args) -> Result<(), anyhow::
 
     trace_rss_now();
 
+    // Read the manifest and remove cases from it
+    let mut manifest = args
+        .path_manifest
+        .as_deref()
+        .map(Manifest::from_path)
+        .transpose()?;
+    if !args.remove_case.is_empty() {
+        let manifest = manifest
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("--remove-case requires --path-manifest"))?;
+        for case_uuid in &args.remove_case {
+            let case_uuid = uuid::Uuid::parse_str(case_uuid)
+                .map_err(|e| anyhow::anyhow!("invalid case UUID {}: {}", case_uuid, e))?;
+            if manifest.cases.shift_remove(&case_uuid).is_none() {
+                anyhow::bail!("case {} is not in the manifest", case_uuid);
+            }
+            tracing::info!("removed case {}", &case_uuid);
+        }
+    }
+
     // Read all input files and write all records by chromosome and SV type
     let tmp_dir = tempfile::TempDir::new()?;
     tracing::debug!("using tmpdir={:?}", &tmp_dir);
-    split_input_by_chrom_and_sv_type(&tmp_dir, input_vcf_paths, args.genome_release).await?;
+    split_input_by_chrom_and_sv_type(
+        &tmp_dir,
+        input_vcf_paths,
+        args.genome_release,
+        manifest.as_mut(),
+    )
+    .await?;
 
     // Read the output of the previous step by chromosome and SV type, perform
     // overlapping and merge such "compressed" data set to the final output
     // file.
     tracing::info!("Merging to output TSV file...");
     merge_split_files(&tmp_dir, args, &args.path_output)?;
+    if let (Some(manifest), Some(path_manifest)) = (&manifest, &args.path_manifest) {
+        manifest.write_path(path_manifest)?;
+        tracing::info!("wrote manifest with {} cases", manifest.cases.len());
+    }
     tracing::info!("... done - don't forget to convert to binary");
 
     Ok(())
@@ -358,6 +570,8 @@ mod tests {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            path_manifest: None,
+            remove_case: vec![],
         };
 
         run(&common_args, &args).await?;
@@ -384,6 +598,8 @@ mod tests {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            path_manifest: None,
+            remove_case: vec![],
         };
 
         run(&common_args, &args).await?;
@@ -407,6 +623,8 @@ mod tests {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            path_manifest: None,
+            remove_case: vec![],
         };
 
         run(&common_args, &args).await?;
@@ -416,4 +634,162 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn run_merge_inhouse_tsv() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common_args = CommonArgs {
+            verbose: Verbosity::new(0, 0),
+        };
+        let args = Args {
+            genome_release: GenomeRelease::Grch37,
+            path_output: tmp_dir.join("site.tsv"),
+            path_input: vec![String::from("tests/strucvars/aggregate/oneline.vcf")],
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            path_manifest: None,
+            remove_case: vec![],
+        };
+        run(&common_args, &args).await?;
+
+        // Merging the database of another site is the same as importing its files.
+        let args = Args {
+            path_output: tmp_dir.join("merged.tsv"),
+            path_input: vec![
+                tmp_dir.join("site.tsv").to_string_lossy().to_string(),
+                String::from("tests/strucvars/aggregate/oneline.vcf"),
+            ],
+            ..args
+        };
+        run(&common_args, &args).await?;
+
+        let output = std::fs::read_to_string(tmp_dir.join("merged.tsv"))?;
+        assert_eq!(
+            output.lines().last(),
+            Some("1\t586411\t1\t586439\t3to5\tDEL\t6\t6\t0\t0")
+        );
+
+        // Databases of another genome release are refused.
+        let args = Args {
+            genome_release: GenomeRelease::Grch38,
+            path_output: tmp_dir.join("grch38.tsv"),
+            path_input: vec![tmp_dir.join("site.tsv").to_string_lossy().to_string()],
+            ..args
+        };
+        assert!(run(&common_args, &args).await.is_err());
+
+        Ok(())
+    }
+
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn run_inhouse_tsv_unknown_contig() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common_args = CommonArgs {
+            verbose: Verbosity::new(0, 0),
+        };
+        std::fs::write(
+            tmp_dir.join("site.tsv"),
+            "##genome-release=grch37\n\
+            #chromosome\tbegin\tchromosome2\tend\tpe_orientation\tsv_type\tcarriers\tcarriers_het\tcarriers_hom\tcarriers_hemi\n\
+            1\t586411\t1\t586439\t3to5\tDEL\t3\t3\t0\t0\n\
+            GL000192.1\t10000\tGL000192.1\t20000\t3to5\tDEL\t1\t1\t0\t0\n",
+        )?;
+        let args = Args {
+            genome_release: GenomeRelease::Grch37,
+            path_output: tmp_dir.join("out.tsv"),
+            path_input: vec![tmp_dir.join("site.tsv").to_string_lossy().to_string()],
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            path_manifest: None,
+            remove_case: vec![],
+        };
+
+        // The record on the decoy contig is skipped with a warning.
+        run(&common_args, &args).await?;
+
+        let output = std::fs::read_to_string(tmp_dir.join("out.tsv"))?;
+        assert_eq!(
+            output
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .collect::<Vec<_>>(),
+            vec!["1\t586411\t1\t586439\t3to5\tDEL\t3\t3\t0\t0"]
+        );
+        assert!(logs_contain(
+            "skipped 1 in-house records on unknown contigs"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_manifest() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common_args = CommonArgs {
+            verbose: Verbosity::new(0, 0),
+        };
+        let vcf = std::fs::read_to_string("tests/strucvars/aggregate/oneline.vcf")?;
+        let path_case1 = tmp_dir.join("case1.vcf").to_string_lossy().to_string();
+        let path_case2 = tmp_dir.join("case2.vcf").to_string_lossy().to_string();
+        std::fs::write(&path_case1, &vcf)?;
+        std::fs::write(
+            &path_case2,
+            vcf.replace(
+                "d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c",
+                "f0a5b7c2-5d2e-4a0b-9d8e-7c6b5a4f3e2d",
+            ),
+        )?;
+        let last_line = || -> Result<String, anyhow::Error> {
+            Ok(std::fs::read_to_string(tmp_dir.join("out.tsv"))?
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .to_string())
+        };
+        let args = Args {
+            genome_release: GenomeRelease::Grch37,
+            path_output: tmp_dir.join("out.tsv"),
+            path_input: vec![path_case1.clone()],
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            path_manifest: Some(tmp_dir.join("manifest.jsonl.gz")),
+            remove_case: vec![],
+        };
+        run(&common_args, &args).await?;
+        assert_eq!(last_line()?, "1\t586411\t1\t586439\t3to5\tDEL\t3\t3\t0\t0");
+
+        // The case in the manifest is skipped, the new case is added.
+        let args = Args {
+            path_input: vec![path_case1.clone(), path_case2.clone()],
+            ..args
+        };
+        run(&common_args, &args).await?;
+        assert_eq!(last_line()?, "1\t586411\t1\t586439\t3to5\tDEL\t6\t6\t0\t0");
+        assert_eq!(
+            super::Manifest::from_path(&tmp_dir.join("manifest.jsonl.gz"))?
+                .cases
+                .len(),
+            2
+        );
+
+        // Cases are removed without reading their VCF files again.
+        std::fs::remove_file(&path_case1)?;
+        std::fs::remove_file(&path_case2)?;
+        let args = Args {
+            path_input: vec![],
+            remove_case: vec![String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c")],
+            ..args
+        };
+        run(&common_args, &args).await?;
+        assert_eq!(last_line()?, "1\t586411\t1\t586439\t3to5\tDEL\t3\t3\t0\t0");
+
+        // Removing a case that is not in the manifest fails.
+        assert!(run(&common_args, &args).await.is_err());
+
+        Ok(())
+    }
 }
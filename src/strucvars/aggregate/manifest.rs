@@ -0,0 +1,80 @@
+//! Manifest of the cases in the in-house database.
+//!
+//! The manifest lists the cases imported from VCF files together with their records,
+//! i.e., their contribution before clustering.  The in-house database can thus be
+//! updated with new cases and cases can be removed without reading the VCF files of
+//! all previous cases again.
+
+use std::{
+    io::{BufRead as _, Write as _},
+    path::Path,
+};
+
+use indexmap::IndexMap;
+use mehari::common::io::std::{open_read_maybe_gz, open_write_maybe_bgzf};
+
+use super::output::Record;
+
+/// One case of the manifest.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Case {
+    /// UUID of the case.
+    pub case_uuid: uuid::Uuid,
+    /// Path of the imported VCF file.
+    pub path: String,
+    /// The records of the case.
+    pub records: Vec<Record>,
+}
+
+/// The manifest with the cases by UUID.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    /// The cases in the order of import.
+    pub cases: IndexMap<uuid::Uuid, Case>,
+}
+
+impl Manifest {
+    /// Read the manifest from the JSON lines file at `path`, empty if it does not exist.
+    pub fn from_path(path: &Path) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        if !path.exists() {
+            return Ok(result);
+        }
+        for line in open_read_maybe_gz(path)?.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let case: Case = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("problem parsing manifest {:?}: {}", path, e))?;
+            result.cases.insert(case.case_uuid, case);
+        }
+        Ok(result)
+    }
+
+    /// Write the manifest to `path` as JSON lines.
+    ///
+    /// The manifest is written to a temporary file next to `path` first, such that an
+    /// existing manifest is only replaced when complete.
+    pub fn write_path(&self, path: &Path) -> Result<(), anyhow::Error> {
+        // Keep the extension such that the temporary file is compressed as well.
+        let mut file_name = std::ffi::OsString::from("tmp.");
+        file_name.push(
+            path.file_name()
+                .ok_or_else(|| anyhow::anyhow!("invalid manifest path {:?}", path))?,
+        );
+        let tmp_path = path.with_file_name(file_name);
+        {
+            let mut writer = open_write_maybe_bgzf(&tmp_path)
+                .map_err(|e| anyhow::anyhow!("cannot open {:?} for writing: {}", &tmp_path, e))?;
+            for case in self.cases.values() {
+                serde_json::to_writer(&mut writer, case)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        std::fs::rename(&tmp_path, path)
+            .map_err(|e| anyhow::anyhow!("cannot move manifest to {:?}: {}", path, e))?;
+        Ok(())
+    }
+}
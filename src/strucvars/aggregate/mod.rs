@@ -1,4 +1,5 @@
 //! Code supporting the `db mk-inhouse` sub command.
 
 pub mod cli;
+pub mod manifest;
 pub mod output;
@@ -0,0 +1,316 @@
+//! Implementation of `strucvars compound-het` subcommand.
+//!
+//! Combines the output of `seqvars query` and `strucvars query` for the same case
+//! and reports genes with a qualifying het. small variant and an overlapping het.
+//! deletion or duplication as compound heterozygous candidates.
+
+use std::{collections::BTreeMap, io::BufRead, time::Instant};
+
+use clap::{command, Parser};
+use mehari::common::io::std::open_read_maybe_gz;
+
+use crate::{
+    common,
+    pbs::varfish::v1::{seqvars::output as pbs_seqvars, strucvars::output as pbs_strucvars},
+};
+
+use super::query::schema::SvType;
+
+/// Command line arguments for `strucvars compound-het` sub command.
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Combine seqvars and strucvars query results to compound het. candidates",
+    long_about = None
+)]
+pub struct Args {
+    /// Path to the JSONL output of `seqvars query`.
+    #[arg(long)]
+    pub path_seqvars_input: String,
+    /// Path to the TSV output of `strucvars query`.
+    #[arg(long)]
+    pub path_strucvars_input: String,
+    /// Path to the TSV file to write the candidates to.
+    #[arg(long)]
+    pub path_output: String,
+    /// Name of the sample to require the het. genotypes in, any sample if not given.
+    #[arg(long)]
+    pub sample: Option<String>,
+}
+
+/// A qualifying het. small variant in a gene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seqvar {
+    /// UUID of the output record.
+    pub uuid: String,
+    /// Variant description, e.g., `17-41197708-T-G`.
+    pub description: String,
+}
+
+/// A qualifying het. deletion or duplication overlapping with a gene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Strucvar {
+    /// UUID of the output record.
+    pub uuid: String,
+    /// SV type.
+    pub sv_type: SvType,
+    /// SV description, e.g., `17:41190000-41200000`.
+    pub description: String,
+}
+
+/// A compound het. candidate written to the output file.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Candidate {
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// UUID of the small variant record.
+    pub seqvar_uuid: String,
+    /// Description of the small variant.
+    pub seqvar: String,
+    /// UUID of the structural variant record.
+    pub strucvar_uuid: String,
+    /// Type of the structural variant.
+    pub sv_type: SvType,
+    /// Description of the structural variant.
+    pub strucvar: String,
+}
+
+/// Record of the `strucvars query` output, only the fields used here.
+#[derive(Debug, serde::Deserialize)]
+struct StrucvarRecord {
+    sodar_uuid: String,
+    chromosome: String,
+    start: i32,
+    end: i32,
+    sv_type: SvType,
+    payload: String,
+}
+
+/// Return whether the genotype string `gt` is het.
+fn is_het(gt: Option<&String>) -> bool {
+    gt.and_then(|gt| gt.parse::<common::Genotype>().ok()) == Some(common::Genotype::Het)
+}
+
+/// Return whether any of the `(sample, genotype)` pairs is het. in `sample` (or any sample
+/// if `None`).
+fn any_het<'a>(
+    genotypes: impl Iterator<Item = (&'a String, Option<&'a String>)>,
+    sample: Option<&str>,
+) -> bool {
+    genotypes
+        .filter(|(name, _)| sample.map_or(true, |sample| sample == name.as_str()))
+        .any(|(_, gt)| is_het(gt))
+}
+
+/// Read the het. small variants by HGNC ID from the `seqvars query` output.
+fn load_seqvars(
+    reader: Box<dyn BufRead>,
+    sample: Option<&str>,
+) -> Result<BTreeMap<String, Vec<Seqvar>>, anyhow::Error> {
+    let mut result: BTreeMap<String, Vec<Seqvar>> = BTreeMap::new();
+    // The first line is the output header.
+    for line in reader.lines().skip(1) {
+        let line = line?;
+        let record: pbs_seqvars::OutputRecord = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("could not parse seqvars record: {}", e))?;
+        let annotation = record.variant_annotation.unwrap_or_default();
+        let Some(hgnc_id) = annotation
+            .gene
+            .and_then(|gene| gene.identity)
+            .map(|identity| identity.hgnc_id)
+            .filter(|hgnc_id| !hgnc_id.is_empty())
+        else {
+            continue;
+        };
+        let call_infos = annotation.call.unwrap_or_default().call_infos;
+        if !any_het(
+            call_infos
+                .iter()
+                .map(|ci| (&ci.sample, ci.genotype.as_ref())),
+            sample,
+        ) {
+            continue;
+        }
+        let vcf_variant = record.vcf_variant.unwrap_or_default();
+        result.entry(hgnc_id).or_default().push(Seqvar {
+            uuid: record.uuid,
+            description: format!(
+                "{}-{}-{}-{}",
+                vcf_variant.chrom, vcf_variant.pos, vcf_variant.ref_allele, vcf_variant.alt_allele
+            ),
+        });
+    }
+    Ok(result)
+}
+
+/// Read the het. deletions and duplications by overlapping HGNC ID from the
+/// `strucvars query` output.
+fn load_strucvars(
+    reader: Box<dyn BufRead>,
+    sample: Option<&str>,
+) -> Result<BTreeMap<String, Vec<Strucvar>>, anyhow::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b'\t')
+        .quoting(false)
+        .from_reader(reader);
+    let mut result: BTreeMap<String, Vec<Strucvar>> = BTreeMap::new();
+    for record in reader.deserialize() {
+        let record: StrucvarRecord = record?;
+        if !matches!(record.sv_type, SvType::Del | SvType::Dup) {
+            continue;
+        }
+        let annotation: pbs_strucvars::VariantAnnotation = serde_json::from_str(&record.payload)
+            .map_err(|e| anyhow::anyhow!("could not parse strucvars payload: {}", e))?;
+        let call_infos = annotation.call.unwrap_or_default().call_infos;
+        if !any_het(
+            call_infos
+                .iter()
+                .map(|ci| (&ci.sample, ci.genotype.as_ref())),
+            sample,
+        ) {
+            continue;
+        }
+        let strucvar = Strucvar {
+            uuid: record.sodar_uuid,
+            sv_type: record.sv_type,
+            description: format!("{}:{}-{}", record.chromosome, record.start, record.end),
+        };
+        for gene in annotation.gene.unwrap_or_default().ovl_genes {
+            if let Some(hgnc_id) = gene.hgnc_id {
+                result.entry(hgnc_id).or_default().push(strucvar.clone());
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Return the candidates for all genes with both a het. small variant and a het.
+/// deletion or duplication.
+pub fn candidates(
+    seqvars: &BTreeMap<String, Vec<Seqvar>>,
+    strucvars: &BTreeMap<String, Vec<Strucvar>>,
+) -> Vec<Candidate> {
+    let mut result = Vec::new();
+    for (hgnc_id, gene_seqvars) in seqvars {
+        let Some(gene_strucvars) = strucvars.get(hgnc_id) else {
+            continue;
+        };
+        for seqvar in gene_seqvars {
+            for strucvar in gene_strucvars {
+                result.push(Candidate {
+                    hgnc_id: hgnc_id.clone(),
+                    seqvar_uuid: seqvar.uuid.clone(),
+                    seqvar: seqvar.description.clone(),
+                    strucvar_uuid: strucvar.uuid.clone(),
+                    sv_type: strucvar.sv_type,
+                    strucvar: strucvar.description.clone(),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Main entry point for `strucvars compound-het` sub command.
+pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    let before_anything = Instant::now();
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading seqvars query results...");
+    let seqvars = load_seqvars(
+        open_read_maybe_gz(&args.path_seqvars_input)?,
+        args.sample.as_deref(),
+    )?;
+    tracing::info!("... done loading het. variants in {} genes", seqvars.len());
+    tracing::info!("Loading strucvars query results...");
+    let strucvars = load_strucvars(
+        open_read_maybe_gz(&args.path_strucvars_input)?,
+        args.sample.as_deref(),
+    )?;
+    tracing::info!("... done loading het. SVs in {} genes", strucvars.len());
+
+    let candidates = candidates(&seqvars, &strucvars);
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(true)
+        .delimiter(b'\t')
+        .quote_style(csv::QuoteStyle::Never)
+        .from_path(&args.path_output)
+        .map_err(|e| anyhow::anyhow!("could not open output file: {}", e))?;
+    for candidate in &candidates {
+        writer
+            .serialize(candidate)
+            .map_err(|e| anyhow::anyhow!("could not write candidate: {}", e))?;
+    }
+    writer.flush()?;
+
+    tracing::info!(
+        "wrote {} candidates in {:?}",
+        candidates.len(),
+        before_anything.elapsed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{Seqvar, Strucvar, SvType};
+
+    #[rstest::rstest]
+    #[case(Some("index"), true)]
+    #[case(Some("father"), false)]
+    #[case(None, true)]
+    fn any_het(#[case] sample: Option<&str>, #[case] expected: bool) {
+        let genotypes = [
+            (String::from("index"), Some(String::from("0/1"))),
+            (String::from("father"), Some(String::from("0/0"))),
+        ];
+
+        assert_eq!(
+            super::any_het(
+                genotypes.iter().map(|(name, gt)| (name, gt.as_ref())),
+                sample
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn candidates() {
+        let seqvar = |uuid: &str| Seqvar {
+            uuid: uuid.into(),
+            description: String::from("1-100-A-T"),
+        };
+        let strucvar = |uuid: &str| Strucvar {
+            uuid: uuid.into(),
+            sv_type: SvType::Del,
+            description: String::from("1:50-200"),
+        };
+        let seqvars = BTreeMap::from([
+            (String::from("HGNC:1"), vec![seqvar("s1"), seqvar("s2")]),
+            (String::from("HGNC:2"), vec![seqvar("s3")]),
+        ]);
+        let strucvars = BTreeMap::from([
+            (String::from("HGNC:1"), vec![strucvar("v1")]),
+            (String::from("HGNC:3"), vec![strucvar("v2")]),
+        ]);
+
+        let candidates = super::candidates(&seqvars, &strucvars);
+
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|c| (
+                    c.hgnc_id.as_str(),
+                    c.seqvar_uuid.as_str(),
+                    c.strucvar_uuid.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("HGNC:1", "s1", "v1"), ("HGNC:1", "s2", "v1")]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Assembly of break-end pairs into linear SVs and translocations.
+//!
+//! Break-end only callers (e.g., GRIDSS) describe deletions, duplications, and
+//! inversions as pairs of break-ends.  The type of the linear SV follows from the
+//! orientation of the break-end in the `ALT` allele as given in the VCF
+//! specification.  Inter-chromosomal pairs are translocations, of which only one
+//! mate is kept.
+
+use noodles::vcf;
+
+/// Linear SV type assembled from a break-end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinearSvType {
+    /// Deletion.
+    Del,
+    /// Tandem duplication.
+    Dup,
+    /// Inversion.
+    Inv,
+}
+
+impl LinearSvType {
+    /// Return the symbolic `ALT` allele.
+    pub fn symbolic_allele(&self) -> &'static str {
+        match self {
+            LinearSvType::Del => "<DEL>",
+            LinearSvType::Dup => "<DUP>",
+            LinearSvType::Inv => "<INV>",
+        }
+    }
+}
+
+/// A linear SV assembled from a break-end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinearSv {
+    /// The SV type.
+    pub sv_type: LinearSvType,
+    /// 1-based start position.
+    pub start: usize,
+    /// 1-based end position.
+    pub end: usize,
+}
+
+/// A translocation from a pair of inter-chromosomal break-end mates.
+///
+/// The break-ends are ordered by chromosome and position, so both mates yield
+/// the same value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Translocation {
+    /// Canonical name and position of the first break-end.
+    pub first: (String, usize),
+    /// Canonical name and position of the second break-end.
+    pub second: (String, usize),
+}
+
+/// The mate of a break-end as parsed from the `ALT` allele.
+struct Mate {
+    /// Whether the sequence is before the brackets, e.g., `t[p[`.
+    sequence_before: bool,
+    /// The bracket character.
+    bracket: char,
+    /// Canonical name of the mate's chromosome.
+    chrom: String,
+    /// Position of the mate.
+    pos: usize,
+}
+
+/// Parse the mate from the break-end `alt` allele.
+fn parse_mate(alt: &str) -> Option<Mate> {
+    let bracket = alt.chars().find(|c| *c == '[' || *c == ']')?;
+    let mut parts = alt.split(bracket);
+    let (before, mate, _) = (parts.next()?, parts.next()?, parts.next()?);
+    let (mate_chrom, mate_pos) = mate.rsplit_once(':')?;
+    Some(Mate {
+        sequence_before: !before.is_empty(),
+        bracket,
+        chrom: annonars::common::cli::canonicalize(mate_chrom),
+        pos: mate_pos.parse::<usize>().ok()?,
+    })
+}
+
+/// Sort key of a canonical chromosome name, unknown chromosomes come last.
+fn chrom_rank(chrom: &str) -> (usize, &str) {
+    let idx = annonars::common::cli::CANONICAL
+        .iter()
+        .position(|canonical| *canonical == chrom)
+        .unwrap_or(usize::MAX);
+    (idx, chrom)
+}
+
+/// Return the translocation for the break-end at `chrom:pos` with the `alt` allele.
+///
+/// Returns `None` if `alt` is not a break-end or the mate is on the same
+/// chromosome.
+pub fn translocation(chrom: &str, pos: usize, alt: &str) -> Option<Translocation> {
+    let mate = parse_mate(alt)?;
+    let chrom = annonars::common::cli::canonicalize(chrom);
+    if mate.chrom == chrom {
+        return None;
+    }
+    let (this, other) = ((chrom, pos), (mate.chrom, mate.pos));
+    let (first, second) = if (chrom_rank(&this.0), this.1) <= (chrom_rank(&other.0), other.1) {
+        (this, other)
+    } else {
+        (other, this)
+    };
+    Some(Translocation { first, second })
+}
+
+/// Assemble the break-end at `chrom:pos` with the `alt` allele into a linear SV.
+///
+/// Returns `None` if `alt` is not a break-end or the mate is on a different
+/// chromosome.
+pub fn assemble(chrom: &str, pos: usize, alt: &str) -> Option<LinearSv> {
+    let Mate {
+        sequence_before,
+        bracket,
+        chrom: mate_chrom,
+        pos: mate_pos,
+    } = parse_mate(alt)?;
+    if mate_chrom != annonars::common::cli::canonicalize(chrom) {
+        return None;
+    }
+    let (start, end) = (pos.min(mate_pos), pos.max(mate_pos));
+
+    // `t[p[` joins the sequence right of `p` after `t`, `]p]t` joins the sequence left
+    // of `p` before `t`, the other two forms join the reverse complement.
+    let sv_type = match (sequence_before, bracket) {
+        (true, '[') if mate_pos > pos => LinearSvType::Del,
+        (true, '[') => LinearSvType::Dup,
+        (false, ']') if mate_pos > pos => LinearSvType::Dup,
+        (false, ']') => LinearSvType::Del,
+        _ => LinearSvType::Inv,
+    };
+    Some(LinearSv {
+        sv_type,
+        start,
+        end,
+    })
+}
+
+/// Convert the break-end `record` into a record of the assembled linear SV, if
+/// possible.
+///
+/// For the mate at the end of the linear SV, `CIPOS` and `CIEND` are swapped.
+pub fn assemble_record(
+    record: &vcf::variant::RecordBuf,
+) -> Result<Option<(LinearSv, vcf::variant::RecordBuf)>, anyhow::Error> {
+    let Some(alt) = record.alternate_bases().as_ref().iter().next() else {
+        return Ok(None);
+    };
+    let pos: usize = record
+        .variant_start()
+        .ok_or_else(|| anyhow::anyhow!("missing start position"))?
+        .into();
+    let Some(linear_sv) = assemble(record.reference_sequence_name(), pos, alt) else {
+        return Ok(None);
+    };
+
+    let mut result = record.clone();
+    *result.variant_start_mut() = Some(noodles::core::Position::try_from(linear_sv.start)?);
+    *result.reference_bases_mut() = "N".to_string();
+    *result.alternate_bases_mut() = vcf::variant::record_buf::AlternateBases::from(vec![linear_sv
+        .sv_type
+        .symbolic_allele()
+        .to_string()]);
+    result.info_mut().insert(
+        vcf::variant::record::info::field::key::END_POSITION.to_string(),
+        Some(vcf::variant::record_buf::info::field::Value::Integer(
+            linear_sv.end as i32,
+        )),
+    );
+    if linear_sv.start != pos {
+        use vcf::variant::record::info::field::key;
+
+        let info = result.info_mut().as_mut();
+        let ci_pos = info.swap_remove(key::POSITION_CONFIDENCE_INTERVALS);
+        let ci_end = info.swap_remove(key::END_CONFIDENCE_INTERVALS);
+        if let Some(ci_end) = ci_end {
+            info.insert(key::POSITION_CONFIDENCE_INTERVALS.to_string(), ci_end);
+        }
+        if let Some(ci_pos) = ci_pos {
+            info.insert(key::END_CONFIDENCE_INTERVALS.to_string(), ci_pos);
+        }
+    }
+    Ok(Some((linear_sv, result)))
+}
+
+/// Return the translocation for the break-end `record`, if any.
+pub fn record_translocation(
+    record: &vcf::variant::RecordBuf,
+) -> Result<Option<Translocation>, anyhow::Error> {
+    let Some(alt) = record.alternate_bases().as_ref().iter().next() else {
+        return Ok(None);
+    };
+    let pos: usize = record
+        .variant_start()
+        .ok_or_else(|| anyhow::anyhow!("missing start position"))?
+        .into();
+    Ok(translocation(record.reference_sequence_name(), pos, alt))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LinearSv, LinearSvType, Translocation};
+
+    #[rstest::rstest]
+    #[case::del("N[1:2000[", 1000, Some((LinearSvType::Del, 1000, 2000)))]
+    #[case::del_mate("]1:1000]N", 2000, Some((LinearSvType::Del, 1000, 2000)))]
+    #[case::dup("]chr1:2000]N", 1000, Some((LinearSvType::Dup, 1000, 2000)))]
+    #[case::dup_mate("N[1:1000[", 2000, Some((LinearSvType::Dup, 1000, 2000)))]
+    #[case::inv("N]1:2000]", 1000, Some((LinearSvType::Inv, 1000, 2000)))]
+    #[case::inv_mate("[1:1000[N", 2000, Some((LinearSvType::Inv, 1000, 2000)))]
+    #[case::translocation("N[2:2000[", 1000, None)]
+    #[case::symbolic("<DEL>", 1000, None)]
+    fn assemble(
+        #[case] alt: &str,
+        #[case] pos: usize,
+        #[case] expected: Option<(LinearSvType, usize, usize)>,
+    ) {
+        assert_eq!(
+            super::assemble("1", pos, alt),
+            expected.map(|(sv_type, start, end)| LinearSv {
+                sv_type,
+                start,
+                end
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::first("1", 1000, "N[chr2:2000[", Some((("1", 1000), ("2", 2000))))]
+    #[case::mate("2", 2000, "]1:1000]N", Some((("1", 1000), ("2", 2000))))]
+    #[case::sex_chrom("X", 500, "N[3:100[", Some((("3", 100), ("X", 500))))]
+    #[case::intra("1", 1000, "N[1:2000[", None)]
+    #[case::symbolic("1", 1000, "<DEL>", None)]
+    fn translocation(
+        #[case] chrom: &str,
+        #[case] pos: usize,
+        #[case] alt: &str,
+        #[case] expected: Option<((&str, usize), (&str, usize))>,
+    ) {
+        assert_eq!(
+            super::translocation(chrom, pos, alt),
+            expected.map(|(first, second)| Translocation {
+                first: (first.0.to_string(), first.1),
+                second: (second.0.to_string(), second.1),
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::first("N[1:2000[", 1000, (-10, 10), (-20, 20))]
+    #[case::mate("]1:1000]N", 2000, (-20, 20), (-10, 10))]
+    fn assemble_record(
+        #[case] alt: &str,
+        #[case] pos: usize,
+        #[case] expected_ci_pos: (i32, i32),
+        #[case] expected_ci_end: (i32, i32),
+    ) -> Result<(), anyhow::Error> {
+        use noodles::vcf::variant::record::info::field::key;
+        use noodles::vcf::variant::record_buf::info::field::{value::Array, Value};
+
+        let ci = |(left, right): (i32, i32)| {
+            Some(Value::Array(Array::Integer(vec![Some(left), Some(right)])))
+        };
+        let record = noodles::vcf::variant::RecordBuf::builder()
+            .set_reference_sequence_name("1")
+            .set_variant_start(noodles::core::Position::try_from(pos)?)
+            .set_reference_bases("N")
+            .set_alternate_bases(noodles::vcf::variant::record_buf::AlternateBases::from(
+                vec![alt.to_string()],
+            ))
+            .set_info(
+                [
+                    (
+                        key::POSITION_CONFIDENCE_INTERVALS.to_string(),
+                        ci((-10, 10)),
+                    ),
+                    (key::END_CONFIDENCE_INTERVALS.to_string(), ci((-20, 20))),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .build();
+
+        let (_, assembled) = super::assemble_record(&record)?.expect("must assemble");
+
+        assert_eq!(
+            assembled.info().get(key::POSITION_CONFIDENCE_INTERVALS),
+            Some(ci(expected_ci_pos).as_ref())
+        );
+        assert_eq!(
+            assembled.info().get(key::END_CONFIDENCE_INTERVALS),
+            Some(ci(expected_ci_end).as_ref())
+        );
+
+        Ok(())
+    }
+}
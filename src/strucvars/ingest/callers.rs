@@ -0,0 +1,352 @@
+//! Conversion of VCF files from SV callers that are not supported by mehari.
+//!
+//! The records of these callers are converted into output records directly, like the
+//! copy number segments, and are thus not merged with the calls of other callers.
+
+use futures::StreamExt as _;
+use mehari::annotate::strucvars::bnd::Breakend;
+use mehari::common::noodles::{NoodlesVariantReader as _, VariantReader};
+use noodles::vcf;
+
+use crate::common::GenomeRelease;
+
+/// Names of the callers as written to `INFO/callers`.
+pub const CALLER_NAMES: &[&str] = &["Cnvnator", "Cnvpytor", "CuteSv", "Pbsv"];
+
+/// Name of the `INFO` field with the inline sequence of insertions.
+pub const INSERTION_SEQUENCE: &str = "SVINSSEQ";
+
+/// SV caller that is not supported by mehari.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// CNVnator read depth CNV caller.
+    Cnvnator { version: String },
+    /// CNVpytor read depth CNV caller.
+    Cnvpytor { version: String },
+    /// cuteSV long read SV caller.
+    CuteSv { version: String },
+    /// pbsv long read SV caller.
+    Pbsv { version: String },
+}
+
+impl Caller {
+    /// Guess the caller from the `##source` line of `header`, or the `##pbsvVersion`
+    /// line for pbsv.
+    pub fn from_header(header: &vcf::Header) -> Option<Self> {
+        let other_record = |name: &str| {
+            header
+                .other_records()
+                .iter()
+                .find_map(|(key, values)| match values {
+                    vcf::header::record::value::Collection::Unstructured(values)
+                        if key.as_ref() == name =>
+                    {
+                        values.first().cloned()
+                    }
+                    _ => None,
+                })
+        };
+        if let Some(pbsv_version) = other_record("pbsvVersion") {
+            return Some(Caller::Pbsv {
+                version: pbsv_version
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("unknown")
+                    .to_string(),
+            });
+        }
+        let source = other_record("source")?;
+        // Return the version following the caller name in `source`, if any.
+        let version = |name: &str| {
+            let version = source[name.len()..].trim_start_matches(['-', '_', ' ', 'v']);
+            if version.is_empty() {
+                "unknown".to_string()
+            } else {
+                version.to_string()
+            }
+        };
+
+        let source_lower = source.to_lowercase();
+        if source_lower.starts_with("cnvnator") {
+            Some(Caller::Cnvnator {
+                version: version("cnvnator"),
+            })
+        } else if source_lower.starts_with("cnvpytor") {
+            Some(Caller::Cnvpytor {
+                version: version("cnvpytor"),
+            })
+        } else if source_lower.starts_with("cutesv") {
+            Some(Caller::CuteSv {
+                version: version("cutesv"),
+            })
+        } else if source_lower.starts_with("pbsv") {
+            Some(Caller::Pbsv {
+                version: version("pbsv"),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Return the name of the caller, as written to `INFO/callers`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Caller::Cnvnator { .. } => "Cnvnator",
+            Caller::Cnvpytor { .. } => "Cnvpytor",
+            Caller::CuteSv { .. } => "CuteSv",
+            Caller::Pbsv { .. } => "Pbsv",
+        }
+    }
+
+    /// Return the version of the caller.
+    pub fn version(&self) -> &str {
+        match self {
+            Caller::Cnvnator { version }
+            | Caller::Cnvpytor { version }
+            | Caller::CuteSv { version }
+            | Caller::Pbsv { version } => version,
+        }
+    }
+}
+
+/// Return the SV type of `record` from the symbolic or break-end `ALT` allele, or from
+/// `INFO/SVTYPE` for sequence-resolved alleles.
+fn sv_type(record: &vcf::variant::RecordBuf) -> Option<String> {
+    use vcf::variant::record_buf::info::field::Value;
+
+    let alt = record.alternate_bases().as_ref().first()?;
+    let sv_type = if alt.contains('[') || alt.contains(']') {
+        "BND".to_string()
+    } else if let Some(symbolic) = alt.strip_prefix('<') {
+        symbolic.trim_end_matches('>').to_string()
+    } else if let Some(Some(Value::String(sv_type))) = record
+        .info()
+        .get(vcf::variant::record::info::field::key::SV_TYPE)
+    {
+        sv_type.clone()
+    } else {
+        return None;
+    };
+    let sv_type = sv_type.split(':').next().unwrap_or_default().to_uppercase();
+    ["DEL", "DUP", "INS", "INV", "BND", "CNV"]
+        .contains(&sv_type.as_str())
+        .then_some(sv_type)
+}
+
+/// Convert the input `record` of `caller` into a record with the samples in `sample_names`.
+///
+/// The samples of the input record are named as in `input_sample_names`.  Returns
+/// `None` for records of unsupported SV type.  The inline sequence of insertions is
+/// kept in `INFO/SVINSSEQ`.
+pub fn convert_record(
+    caller: &Caller,
+    record: &vcf::variant::RecordBuf,
+    input_sample_names: &vcf::header::SampleNames,
+    sample_names: &vcf::header::SampleNames,
+    genomebuild: GenomeRelease,
+) -> Result<Option<vcf::variant::RecordBuf>, anyhow::Error> {
+    use vcf::variant::record::info::field::key;
+    use vcf::variant::record_buf::{
+        info::field::{value::Array as InfoArray, Value as InfoValue},
+        samples::sample::{value::Array, Value},
+    };
+
+    let (Some(alt), Some(sv_type)) = (record.alternate_bases().as_ref().first(), sv_type(record))
+    else {
+        tracing::warn!(
+            "skipping {} record with unsupported ALT {:?} at {}:{:?}",
+            caller.name(),
+            record.alternate_bases(),
+            record.reference_sequence_name(),
+            record.variant_start()
+        );
+        return Ok(None);
+    };
+    let start = record
+        .variant_start()
+        .ok_or_else(|| anyhow::anyhow!("missing start position"))?;
+    let reference = record.reference_bases();
+    // The end is the mate position for break-ends, and derived from the reference allele
+    // for sequence-resolved alleles without `INFO/END`.
+    let end = if sv_type == "BND" {
+        Breakend::from_ref_alt_str(reference, alt)?.pos
+    } else if let Some(Some(InfoValue::Integer(end))) = record.info().get(key::END_POSITION) {
+        *end
+    } else if sv_type == "INS" {
+        start.get() as i32
+    } else if !alt.starts_with('<') {
+        (start.get() + reference.len() - 1) as i32
+    } else {
+        anyhow::bail!(
+            "missing END in {} record at {}:{}",
+            caller.name(),
+            record.reference_sequence_name(),
+            start
+        );
+    };
+
+    let chrom = annonars::common::cli::canonicalize(record.reference_sequence_name());
+    let chrom = match genomebuild {
+        GenomeRelease::Grch37 => chrom,
+        GenomeRelease::Grch38 => format!("chr{}", chrom),
+    };
+    let values = sample_names
+        .iter()
+        .map(|name| {
+            let sample = input_sample_names
+                .get_index_of(name)
+                .and_then(|idx| record.samples().get_index(idx));
+            let get = |key: &str| sample.as_ref().and_then(|sample| sample.get(key)).flatten();
+            let integer = |key: &str| match get(key) {
+                Some(Value::Integer(value)) => Some(*value),
+                Some(Value::Float(value)) => Some(value.round() as i32),
+                _ => None,
+            };
+            // Variant and total read support from `DR`/`DV` (cuteSV) or `AD` (pbsv).
+            let (ref_reads, var_reads) = match get("AD") {
+                Some(Value::Array(Array::Integer(ad))) if ad.len() == 2 => (ad[0], ad[1]),
+                _ => (integer("DR"), integer("DV")),
+            };
+            let src = match (ref_reads, var_reads) {
+                (None, None) => None,
+                (ref_reads, var_reads) => {
+                    Some(ref_reads.unwrap_or_default() + var_reads.unwrap_or_default())
+                }
+            };
+            vec![
+                get(vcf::variant::record::samples::keys::key::GENOTYPE).cloned(),
+                integer(vcf::variant::record::samples::keys::key::CONDITIONAL_GENOTYPE_QUALITY)
+                    .map(Value::Integer),
+                src.map(Value::Integer),
+                var_reads.map(Value::Integer),
+                integer("CN").map(Value::Integer),
+            ]
+        })
+        .collect();
+
+    let mut info: vcf::variant::record_buf::Info = Default::default();
+    info.insert(key::END_POSITION.to_string(), Some(InfoValue::Integer(end)));
+    for key in [
+        key::POSITION_CONFIDENCE_INTERVALS,
+        key::END_CONFIDENCE_INTERVALS,
+    ] {
+        if let Some(Some(InfoValue::Array(InfoArray::Integer(values)))) = record.info().get(key) {
+            if let [Some(left), Some(right)] = values.as_slice() {
+                info.insert(
+                    key.to_string(),
+                    Some(InfoValue::Array(InfoArray::Integer(vec![
+                        Some(*left),
+                        Some(*right),
+                    ]))),
+                );
+            }
+        }
+    }
+    // Keep the inline sequence of insertions without the padding base.
+    if sv_type == "INS" && !alt.starts_with('<') {
+        let sequence = alt.strip_prefix(reference).unwrap_or(alt);
+        if !sequence.is_empty() {
+            info.insert(
+                INSERTION_SEQUENCE.to_string(),
+                Some(InfoValue::String(sequence.to_string())),
+            );
+        }
+    }
+    info.insert(
+        "callers".to_string(),
+        Some(InfoValue::String(caller.name().to_string())),
+    );
+
+    let alt = if sv_type == "BND" {
+        alt.clone()
+    } else {
+        format!("<{}>", sv_type)
+    };
+    Ok(Some(
+        vcf::variant::record_buf::builder::Builder::default()
+            .set_reference_sequence_name(chrom)
+            .set_variant_start(start)
+            .set_reference_bases("N")
+            .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![alt]))
+            .set_info(info)
+            .set_samples(vcf::variant::record_buf::samples::Samples::new(
+                ["GT", "GQ", "src", "srv", "cn"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                values,
+            ))
+            .build(),
+    ))
+}
+
+/// Read and convert all records of `caller` from `reader` with the (mapped) `header`.
+pub async fn read_records(
+    caller: &Caller,
+    reader: &mut VariantReader,
+    header: &vcf::Header,
+    sample_names: &vcf::header::SampleNames,
+    genomebuild: GenomeRelease,
+) -> Result<Vec<vcf::variant::RecordBuf>, anyhow::Error> {
+    let mut result = Vec::new();
+    let mut records = reader.records(header).await;
+    while let Some(record) = records.next().await {
+        let record = record.map_err(|e| anyhow::anyhow!("problem reading record: {}", e))?;
+        if let Some(record) = convert_record(
+            caller,
+            &record,
+            header.sample_names(),
+            sample_names,
+            genomebuild,
+        )? {
+            result.push(record);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use noodles::vcf;
+
+    use super::Caller;
+
+    #[rstest::rstest]
+    #[case::cnvnator("CNVnator", Some(Caller::Cnvnator { version: "unknown".into() }))]
+    #[case::cnvpytor("CNVpytor v1.3.1", Some(Caller::Cnvpytor { version: "1.3.1".into() }))]
+    #[case::cutesv("cuteSV-2.0.3", Some(Caller::CuteSv { version: "2.0.3".into() }))]
+    #[case::manta("GenerateSVCandidates 1.6.0", None)]
+    fn from_header(
+        #[case] source: &str,
+        #[case] expected: Option<Caller>,
+    ) -> Result<(), anyhow::Error> {
+        let header: vcf::Header = format!(
+            "##fileformat=VCFv4.2\n\
+            ##source={}\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n",
+            source
+        )
+        .parse()?;
+
+        assert_eq!(Caller::from_header(&header), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_header_pbsv() -> Result<(), anyhow::Error> {
+        let header: vcf::Header = "##fileformat=VCFv4.2\n\
+            ##pbsvVersion=2.9.0 (commit v2.9.0)\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n"
+            .parse()?;
+
+        assert_eq!(
+            Caller::from_header(&header),
+            Some(Caller::Pbsv {
+                version: "2.9.0".into()
+            })
+        );
+
+        Ok(())
+    }
+}
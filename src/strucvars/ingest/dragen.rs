@@ -0,0 +1,157 @@
+//! Copy number quality of DRAGEN CNV calls.
+//!
+//! The conversion of DRAGEN CNV records in mehari does not keep `FORMAT/CNQ`.  It is
+//! thus read from the input files separately and written as `FORMAT/cnq` for the
+//! clustered records that have the same coordinates as the DRAGEN CNV call.
+
+use futures::StreamExt as _;
+use mehari::common::noodles::NoodlesVariantReader as _;
+use noodles::vcf;
+
+/// Key of a call by canonical chromosome, 1-based start, and 1-based end.
+type CallKey = (String, usize, i32);
+
+/// Return the key of `record`, if it has an `END`.
+fn call_key(record: &vcf::variant::RecordBuf) -> Option<CallKey> {
+    let start = record.variant_start()?;
+    match record
+        .info()
+        .get(vcf::variant::record::info::field::key::END_POSITION)
+    {
+        Some(Some(vcf::variant::record_buf::info::field::Value::Integer(end))) => Some((
+            annonars::common::cli::canonicalize(record.reference_sequence_name()),
+            start.get(),
+            *end,
+        )),
+        _ => None,
+    }
+}
+
+/// Copy number qualities of the DRAGEN CNV calls, one value per output sample.
+#[derive(Debug, Default)]
+pub struct CopyNumberQualities {
+    /// The copy number qualities by call.
+    values: std::collections::HashMap<CallKey, Vec<Option<f32>>>,
+}
+
+impl CopyNumberQualities {
+    /// Read `FORMAT/CNQ` from the DRAGEN CNV file at `path`.
+    ///
+    /// The samples of the file are named as in `mapped_header` and the values are
+    /// stored in the order of `sample_names` from the output header.
+    pub async fn read_path(
+        &mut self,
+        path: &str,
+        mapped_header: &vcf::Header,
+        sample_names: &vcf::header::SampleNames,
+    ) -> Result<(), anyhow::Error> {
+        use vcf::variant::record_buf::samples::sample::Value;
+
+        let sample_idxs = sample_names
+            .iter()
+            .map(|name| mapped_header.sample_names().get_index_of(name))
+            .collect::<Vec<_>>();
+
+        let mut reader = crate::common::noodles::open_vcf_reader(path).await?;
+        let header = reader
+            .read_header()
+            .await
+            .map_err(|e| anyhow::anyhow!("problem reading header of {}: {}", path, e))?;
+        let mut records = reader.records(&header).await;
+        while let Some(record) = records.next().await {
+            let record = record.map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+            let Some(key) = call_key(&record) else {
+                continue;
+            };
+            let values = sample_idxs
+                .iter()
+                .map(|idx| {
+                    let sample = record.samples().get_index((*idx)?)?;
+                    match sample.get("CNQ") {
+                        Some(Some(Value::Integer(cnq))) => Some(*cnq as f32),
+                        Some(Some(Value::Float(cnq))) => Some(*cnq),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            if values.iter().any(Option::is_some) {
+                self.values.insert(key, values);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add `FORMAT/cnq` to `record` if there is a DRAGEN CNV call with the same coordinates.
+    pub fn annotate(&self, record: &mut vcf::variant::RecordBuf) {
+        use vcf::variant::record_buf::samples::sample::Value;
+
+        let Some(cnqs) = call_key(record).and_then(|key| self.values.get(&key)) else {
+            return;
+        };
+        let mut keys = record.samples().keys().clone();
+        keys.as_mut().insert("cnq".to_string());
+        let values = record
+            .samples()
+            .values()
+            .zip(cnqs.iter())
+            .map(|(sample, cnq)| {
+                let mut values = sample.values().to_vec();
+                values.push(cnq.map(Value::Float));
+                values
+            })
+            .collect();
+        *record.samples_mut() = vcf::variant::record_buf::Samples::new(keys, values);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use noodles::vcf;
+
+    #[tokio::test]
+    async fn read_path_and_annotate() -> Result<(), anyhow::Error> {
+        let path = "tests/strucvars/ingest/dragen-cnv-cnq-min.vcf";
+        let header = vcf::io::reader::Builder::default()
+            .build_from_path(path)?
+            .read_header()?;
+        let mut cnqs = super::CopyNumberQualities::default();
+        cnqs.read_path(path, &header, header.sample_names()).await?;
+
+        let mut record = vcf::variant::RecordBuf::builder()
+            .set_reference_sequence_name("chr1")
+            .set_variant_start(noodles::core::Position::try_from(1283844)?)
+            .set_reference_bases("N")
+            .set_info(
+                [(
+                    String::from("END"),
+                    Some(vcf::variant::record_buf::info::field::Value::Integer(
+                        1284844,
+                    )),
+                )]
+                .into_iter()
+                .collect(),
+            )
+            .set_samples(vcf::variant::record_buf::Samples::new(
+                [String::from("GT")].into_iter().collect(),
+                vec![vec![Some(
+                    vcf::variant::record_buf::samples::sample::Value::String("0/1".into()),
+                )]],
+            ))
+            .build();
+        cnqs.annotate(&mut record);
+
+        assert_eq!(
+            record
+                .samples()
+                .get_index(0)
+                .and_then(|sample| sample.get("cnq"))
+                .flatten(),
+            Some(&vcf::variant::record_buf::samples::sample::Value::Float(
+                27.0
+            ))
+        );
+
+        Ok(())
+    }
+}
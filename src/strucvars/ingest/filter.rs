@@ -0,0 +1,198 @@
+//! Removal of noisy calls in `strucvars ingest`.
+//!
+//! `QUAL` and `FILTER` are not kept when converting the input records for
+//! clustering, so the input files are filtered on these before.  The minimal
+//! sizes are applied to the clustered records.
+
+use futures::TryStreamExt as _;
+use mehari::common::noodles::{open_vcf_writer, NoodlesVariantReader as _};
+use noodles::vcf;
+use tokio::io::AsyncWriteExt as _;
+
+use crate::flush_and_shutdown;
+
+/// Settings for removing calls, built from the command line arguments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordFilter {
+    /// Minimal `QUAL` of input records, records without `QUAL` are kept.
+    pub min_qual: Option<f32>,
+    /// Whether to require `FILTER` to be `PASS` (or `.`) for input records.
+    pub require_pass: bool,
+    /// Minimal size of deletions.
+    pub min_size_del: Option<usize>,
+    /// Minimal size of duplications.
+    pub min_size_dup: Option<usize>,
+    /// Minimal size of inversions.
+    pub min_size_inv: Option<usize>,
+    /// Minimal size of CNVs.
+    pub min_size_cnv: Option<usize>,
+}
+
+impl RecordFilter {
+    /// Construct from the command line arguments.
+    pub fn from_args(args: &super::Args) -> Self {
+        Self {
+            min_qual: args.min_qual,
+            require_pass: args.require_pass,
+            min_size_del: args.min_size_del,
+            min_size_dup: args.min_size_dup,
+            min_size_inv: args.min_size_inv,
+            min_size_cnv: args.min_size_cnv,
+        }
+    }
+
+    /// Whether input records are to be filtered on `QUAL` or `FILTER`.
+    pub fn filters_input(&self) -> bool {
+        self.min_qual.is_some() || self.require_pass
+    }
+
+    /// Whether the input `record` passes the `QUAL` and `FILTER` criteria.
+    pub fn passes_input(&self, record: &vcf::variant::RecordBuf) -> bool {
+        let pass_qual = match (self.min_qual, record.quality_score()) {
+            (Some(min_qual), Some(quality)) => quality >= min_qual,
+            _ => true,
+        };
+        let pass_filter = !self.require_pass
+            || record
+                .filters()
+                .as_ref()
+                .iter()
+                .all(|filter| filter == "PASS");
+        pass_qual && pass_filter
+    }
+
+    /// Whether the clustered `record` passes the minimal size for its type.
+    pub fn passes_size(&self, record: &vcf::variant::RecordBuf) -> bool {
+        let Some(alt) = record.alternate_bases().as_ref().iter().next() else {
+            return true;
+        };
+        let min_size = match alt.split(':').next() {
+            Some("<DEL") | Some("<DEL>") => self.min_size_del,
+            Some("<DUP") | Some("<DUP>") => self.min_size_dup,
+            Some("<INV") | Some("<INV>") => self.min_size_inv,
+            Some("<CNV") | Some("<CNV>") => self.min_size_cnv,
+            _ => None,
+        };
+        let Some(min_size) = min_size else {
+            return true;
+        };
+
+        let pos: usize = record.variant_start().map(Into::into).unwrap_or_default();
+        match record
+            .info()
+            .get(vcf::variant::record::info::field::key::END_POSITION)
+        {
+            Some(Some(vcf::variant::record_buf::info::field::Value::Integer(end))) => {
+                (*end as usize + 1).saturating_sub(pos) >= min_size
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Write the records of the VCF file at `path_in` that pass `record_filter` to `path_out`.
+///
+/// Returns the number of records read and written.
+pub async fn write_filtered_input(
+    path_in: &str,
+    path_out: &str,
+    record_filter: &RecordFilter,
+) -> Result<(usize, usize), anyhow::Error> {
+    let mut reader = crate::common::noodles::open_vcf_reader(path_in).await?;
+    let header = reader
+        .read_header()
+        .await
+        .map_err(|e| anyhow::anyhow!("problem reading header of {}: {}", path_in, e))?;
+
+    let mut writer = open_vcf_writer(path_out).await?;
+    writer
+        .write_header(&header)
+        .await
+        .map_err(|e| anyhow::anyhow!("problem writing header: {}", e))?;
+
+    let (mut count_read, mut count_written) = (0, 0);
+    let mut records = reader.records(&header).await;
+    while let Some(record) = records.try_next().await? {
+        count_read += 1;
+        if record_filter.passes_input(&record) {
+            count_written += 1;
+            writer
+                .write_variant_record(&header, &record)
+                .await
+                .map_err(|e| anyhow::anyhow!("problem writing record: {}", e))?;
+        }
+    }
+    flush_and_shutdown!(writer);
+
+    Ok((count_read, count_written))
+}
+
+#[cfg(test)]
+mod test {
+    use noodles::vcf;
+
+    use super::RecordFilter;
+
+    fn record(
+        alt: &str,
+        pos: usize,
+        end: i32,
+        quality: Option<f32>,
+        filter: &str,
+    ) -> vcf::variant::RecordBuf {
+        let mut info: vcf::variant::record_buf::Info = Default::default();
+        info.insert(
+            vcf::variant::record::info::field::key::END_POSITION.to_string(),
+            Some(vcf::variant::record_buf::info::field::Value::Integer(end)),
+        );
+        let builder = vcf::variant::record_buf::builder::Builder::default()
+            .set_reference_sequence_name("1")
+            .set_variant_start(noodles::core::Position::try_from(pos).unwrap())
+            .set_reference_bases("N")
+            .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                alt.to_string()
+            ]))
+            .set_filters(
+                [filter.to_string()]
+                    .into_iter()
+                    .collect::<vcf::variant::record_buf::Filters>(),
+            )
+            .set_info(info);
+        match quality {
+            Some(quality) => builder.set_quality_score(quality).build(),
+            None => builder.build(),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::no_filter(RecordFilter::default(), Some(5.0), "LowQual", true)]
+    #[case::qual_pass(RecordFilter { min_qual: Some(20.0), ..Default::default() }, Some(30.0), "PASS", true)]
+    #[case::qual_fail(RecordFilter { min_qual: Some(20.0), ..Default::default() }, Some(5.0), "PASS", false)]
+    #[case::qual_missing(RecordFilter { min_qual: Some(20.0), ..Default::default() }, None, "PASS", true)]
+    #[case::require_pass(RecordFilter { require_pass: true, ..Default::default() }, None, "PASS", true)]
+    #[case::require_pass_fail(RecordFilter { require_pass: true, ..Default::default() }, None, "LowQual", false)]
+    fn passes_input(
+        #[case] record_filter: RecordFilter,
+        #[case] quality: Option<f32>,
+        #[case] filter: &str,
+        #[case] expected: bool,
+    ) {
+        let record = record("<DEL>", 1000, 2000, quality, filter);
+        assert_eq!(record_filter.passes_input(&record), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::del_pass("<DEL>", 1001, true)]
+    #[case::del_fail("<DEL>", 1000, false)]
+    #[case::del_sub_type("<DEL:ME:ALU>", 1000, false)]
+    #[case::dup_unfiltered("<DUP>", 10, true)]
+    #[case::bnd("N[1:2000[", 1, true)]
+    fn passes_size(#[case] alt: &str, #[case] size: i32, #[case] expected: bool) {
+        let record_filter = RecordFilter {
+            min_size_del: Some(1001),
+            ..Default::default()
+        };
+        let record = record(alt, 1000, 1000 + size - 1, None, "PASS");
+        assert_eq!(record_filter.passes_size(&record), expected);
+    }
+}
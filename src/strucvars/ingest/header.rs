@@ -40,6 +40,7 @@ fn caller_version(sv_caller: &mehari::annotate::strucvars::SvCaller) -> String {
 pub fn build_output_header(
     input_sample_names: &SampleNames,
     input_sv_callers: &[&mehari::annotate::strucvars::SvCaller],
+    input_other_callers: &[&super::callers::Caller],
     id_mapping: Option<&indexmap::IndexMap<String, String>>,
     pedigree: Option<&mehari::ped::PedigreeByName>,
     genomebuild: GenomeRelease,
@@ -65,6 +66,22 @@ pub fn build_output_header(
         .add_info(key::SV_TYPE, Map::<Info>::from(key::SV_TYPE))
         .add_info(key::SV_LENGTHS, Map::<Info>::from(key::SV_LENGTHS))
         .add_info(key::SV_CLAIM, Map::<Info>::from(key::SV_CLAIM))
+        .add_info(
+            key::POSITION_CONFIDENCE_INTERVALS,
+            Map::<Info>::from(key::POSITION_CONFIDENCE_INTERVALS),
+        )
+        .add_info(
+            key::END_CONFIDENCE_INTERVALS,
+            Map::<Info>::from(key::END_CONFIDENCE_INTERVALS),
+        )
+        .add_info(
+            super::callers::INSERTION_SEQUENCE,
+            Map::<Info>::new(
+                Number::Count(1),
+                info::Type::String,
+                "Sequence of insertion",
+            ),
+        )
         .add_info(
             "callers",
             Map::<Info>::new(
@@ -147,6 +164,14 @@ pub fn build_output_header(
                 "Copy number of the variant in the sample",
             ),
         )
+        .add_format(
+            "cnq",
+            Map::<Format>::new(
+                noodles::vcf::header::record::value::map::format::Number::Count(1),
+                format::Type::Float,
+                "Copy number quality",
+            ),
+        )
         .add_format(
             "anc",
             Map::<Format>::new(
@@ -287,6 +312,18 @@ pub fn build_output_header(
             ),
         )?;
     }
+    for caller in input_other_callers.iter() {
+        builder = builder.insert(
+            "x-varfish-version".parse()?,
+            vcf::header::record::Value::Map(
+                caller.name().into(),
+                Map::<Other>::builder()
+                    .insert("Name".parse()?, caller.name())
+                    .insert("Version".parse()?, caller.version())
+                    .build()?,
+            ),
+        )?;
+    }
 
     Ok(builder.build())
 }
@@ -325,6 +362,7 @@ mod test {
         let output_vcf_header = super::build_output_header(
             input_vcf_header.sample_names(),
             &sv_caller_refs,
+            &[],
             None,
             Some(&pedigree),
             crate::common::GenomeRelease::Grch37,
@@ -372,6 +410,7 @@ mod test {
         let output_vcf_header = super::build_output_header(
             input_vcf_header.sample_names(),
             &sv_caller_refs,
+            &[],
             None,
             Some(&pedigree),
             crate::common::GenomeRelease::Grch38,
@@ -0,0 +1,199 @@
+//! Handling of Manta VCF files.
+//!
+//! Manta files are recognized from the `##source=GenerateSVCandidates` header line.  The
+//! conversion in mehari maps `FORMAT/GT`, `FORMAT/GQ`, and the ref/alt counts of
+//! `FORMAT/PR` and `FORMAT/SR` to `pec`/`pev` and `src`/`srv` and the confidence intervals
+//! are written in `cluster_to_vcf_record()`.  The conversion drops `INFO/IMPRECISE`, so it
+//! is read from the input files separately and set on the clustered records that have the
+//! same coordinates as an imprecise Manta call.
+
+use futures::StreamExt as _;
+use mehari::annotate::strucvars::{bnd::Breakend, SvCaller};
+use mehari::common::noodles::NoodlesVariantReader as _;
+use noodles::vcf;
+
+/// Prefix of the `##source` header line written by Manta.
+const SOURCE_PREFIX: &str = "GenerateSVCandidates";
+
+/// Return the Manta caller from the `##source` line of `header`, if any.
+///
+/// This does not require the `INFO`/`FORMAT` header lines checked by mehari's
+/// `guess_sv_caller()`, so files with a reduced header are recognized as well.
+pub fn caller_from_header(header: &vcf::Header) -> Option<SvCaller> {
+    let source = header
+        .other_records()
+        .iter()
+        .find_map(|(key, values)| match values {
+            vcf::header::record::value::Collection::Unstructured(values)
+                if key.as_ref() == "source" =>
+            {
+                values.first().cloned()
+            }
+            _ => None,
+        })?;
+    let version = source.strip_prefix(SOURCE_PREFIX)?.trim();
+    Some(SvCaller::Manta {
+        version: if version.is_empty() {
+            "unknown".to_string()
+        } else {
+            version.to_string()
+        },
+    })
+}
+
+/// Key of a call by canonical chromosome, 1-based start, and 1-based end.
+///
+/// The end is the mate position for break-ends.
+type CallKey = (String, usize, i32);
+
+/// Return the key of `record`, if it has an `END` or is a break-end.
+fn call_key(record: &vcf::variant::RecordBuf) -> Option<CallKey> {
+    let start = record.variant_start()?;
+    let alt = record.alternate_bases().as_ref().first()?;
+    let end = if alt.contains('[') || alt.contains(']') {
+        Breakend::from_ref_alt_str(record.reference_bases(), alt)
+            .ok()?
+            .pos
+    } else {
+        match record
+            .info()
+            .get(vcf::variant::record::info::field::key::END_POSITION)
+        {
+            Some(Some(vcf::variant::record_buf::info::field::Value::Integer(end))) => *end,
+            _ => return None,
+        }
+    };
+    Some((
+        annonars::common::cli::canonicalize(record.reference_sequence_name()),
+        start.get(),
+        end,
+    ))
+}
+
+/// The imprecise Manta calls.
+#[derive(Debug, Default)]
+pub struct ImpreciseCalls {
+    /// The keys of the calls with `INFO/IMPRECISE`.
+    calls: std::collections::HashSet<CallKey>,
+}
+
+impl ImpreciseCalls {
+    /// Read the calls with `INFO/IMPRECISE` from the Manta file at `path`.
+    pub async fn read_path(&mut self, path: &str) -> Result<(), anyhow::Error> {
+        let mut reader = crate::common::noodles::open_vcf_reader(path).await?;
+        let header = reader
+            .read_header()
+            .await
+            .map_err(|e| anyhow::anyhow!("problem reading header of {}: {}", path, e))?;
+        let mut records = reader.records(&header).await;
+        while let Some(record) = records.next().await {
+            let record = record.map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+            if !matches!(
+                record
+                    .info()
+                    .get(vcf::variant::record::info::field::key::IS_IMPRECISE),
+                Some(Some(vcf::variant::record_buf::info::field::Value::Flag))
+            ) {
+                continue;
+            }
+            if let Some(key) = call_key(&record) {
+                self.calls.insert(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set `INFO/IMPRECISE` on `record` if there is an imprecise Manta call with the same
+    /// coordinates.
+    pub fn annotate(&self, record: &mut vcf::variant::RecordBuf) {
+        if call_key(record).is_some_and(|key| self.calls.contains(&key)) {
+            record.info_mut().insert(
+                vcf::variant::record::info::field::key::IS_IMPRECISE.to_string(),
+                Some(vcf::variant::record_buf::info::field::Value::Flag),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::annotate::strucvars::SvCaller;
+    use noodles::vcf;
+
+    #[rstest::rstest]
+    #[case::manta("GenerateSVCandidates 1.6.0", Some("1.6.0"))]
+    #[case::no_version("GenerateSVCandidates", Some("unknown"))]
+    #[case::delly("DELLY", None)]
+    fn caller_from_header(
+        #[case] source: &str,
+        #[case] expected: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let header: vcf::Header = format!(
+            "##fileformat=VCFv4.1\n\
+            ##source={}\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n",
+            source
+        )
+        .parse()?;
+
+        assert_eq!(
+            super::caller_from_header(&header),
+            expected.map(|version| SvCaller::Manta {
+                version: version.to_string()
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_path_and_annotate() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = tmpdir.join("manta.vcf");
+        std::fs::write(
+            &path,
+            "##fileformat=VCFv4.1\n\
+            ##source=GenerateSVCandidates 1.6.0\n\
+            ##INFO=<ID=IMPRECISE,Number=0,Type=Flag,Description=\"Imprecise structural variation\">\n\
+            ##INFO=<ID=END,Number=1,Type=Integer,Description=\"End position of the variant described in this record\">\n\
+            ##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"Type of structural variant\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            1\t1000\tMantaDEL:1:0:0:0:0:0\tA\t<DEL>\t.\tPASS\tEND=5000;SVTYPE=DEL;IMPRECISE\n\
+            1\t8000\tMantaDEL:2:0:0:0:0:0\tA\t<DEL>\t.\tPASS\tEND=9000;SVTYPE=DEL\n\
+            1\t10000\tMantaBND:3:0:1:0:0:0:0\tA\tA[2:20000[\t.\tPASS\tSVTYPE=BND;IMPRECISE\n",
+        )?;
+        let mut calls = super::ImpreciseCalls::default();
+        calls
+            .read_path(path.to_str().expect("invalid path"))
+            .await?;
+
+        let is_imprecise = |chrom: &str, start: usize, alt: &str, end: Option<i32>| {
+            let mut info = vcf::variant::record_buf::Info::default();
+            if let Some(end) = end {
+                info.insert(
+                    String::from("END"),
+                    Some(vcf::variant::record_buf::info::field::Value::Integer(end)),
+                );
+            }
+            let mut record = vcf::variant::RecordBuf::builder()
+                .set_reference_sequence_name(chrom)
+                .set_variant_start(noodles::core::Position::try_from(start).unwrap())
+                .set_reference_bases("N")
+                .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                    alt.to_string()
+                ]))
+                .set_info(info)
+                .build();
+            calls.annotate(&mut record);
+            record.info().get("IMPRECISE").is_some()
+        };
+
+        assert!(is_imprecise("chr1", 1000, "<DEL>", Some(5000)));
+        assert!(!is_imprecise("1", 8000, "<DEL>", Some(9000)));
+        assert!(is_imprecise("1", 10000, "N[2:20000[", Some(20000)));
+        assert!(!is_imprecise("1", 10000, "N[2:20500[", Some(20500)));
+
+        Ok(())
+    }
+}
@@ -13,7 +13,13 @@ use noodles::vcf;
 use rand_core::SeedableRng;
 use tokio::io::AsyncWriteExt;
 
+pub mod bnd;
+pub mod callers;
+pub mod dragen;
+pub mod filter;
 pub mod header;
+pub mod manta;
+pub mod segments;
 
 /// Command line arguments for `strucvars ingest` subcommand.
 #[derive(Debug, clap::Parser)]
@@ -38,6 +44,10 @@ pub struct Args {
     /// Path to coverage VCF files from maelstrom; optional.
     #[clap(long)]
     pub path_cov_vcf: Vec<String>,
+    /// Path to copy number segment files (`.seg` or `.bed`, optionally gzip-ed) to
+    /// convert into deletions and duplications; optional.
+    #[clap(long)]
+    pub path_segments: Vec<String>,
     /// Path to output file.
     #[clap(long)]
     pub path_out: String,
@@ -52,6 +62,25 @@ pub struct Args {
     #[arg(long, default_value_t = 50)]
     pub slack_ins: i32,
 
+    /// Minimal `QUAL` of input records; records without `QUAL` are kept.
+    #[arg(long)]
+    pub min_qual: Option<f32>,
+    /// Only keep input records with `FILTER` set to `PASS` (or `.`).
+    #[arg(long)]
+    pub require_pass: bool,
+    /// Minimal size of deletions to write out.
+    #[arg(long)]
+    pub min_size_del: Option<usize>,
+    /// Minimal size of duplications to write out.
+    #[arg(long)]
+    pub min_size_dup: Option<usize>,
+    /// Minimal size of inversions to write out.
+    #[arg(long)]
+    pub min_size_inv: Option<usize>,
+    /// Minimal size of CNVs to write out.
+    #[arg(long)]
+    pub min_size_cnv: Option<usize>,
+
     /// Seed for random number generator (UUIDs), if any.
     #[arg(long)]
     pub rng_seed: Option<u64>,
@@ -61,6 +90,14 @@ pub struct Args {
     /// Per-file identifier mapping, either a JSON or @-prefixed path to JSON.
     #[clap(long)]
     pub id_mapping: Option<String>,
+    /// Do not write the TBI/CSI index next to compressed output files.
+    #[clap(long)]
+    pub no_index: bool,
+    /// Assemble intra-chromosomal break-ends into deletions, duplications, and inversions,
+    /// and write only one mate of inter-chromosomal break-end pairs, e.g., for break-end
+    /// only callers.
+    #[clap(long)]
+    pub assemble_bnd: bool,
 }
 
 async fn write_ingest_record(
@@ -235,6 +272,18 @@ async fn write_ingest_record(
         }
     }
 
+    // copy over imprecise flag, confidence intervals, and insertion sequence, if any
+    for key in [
+        vcf::variant::record::info::field::key::IS_IMPRECISE,
+        vcf::variant::record::info::field::key::POSITION_CONFIDENCE_INTERVALS,
+        vcf::variant::record::info::field::key::END_CONFIDENCE_INTERVALS,
+        callers::INSERTION_SEQUENCE,
+    ] {
+        if let Some(Some(value)) = input_record.info().get(key) {
+            info.insert(key.to_string(), Some(value.clone()));
+        }
+    }
+
     fn map_caller(caller: &str) -> Result<Option<String>, anyhow::Error> {
         if caller.starts_with("DELLYv") {
             Ok(Some("Delly".to_string()))
@@ -252,6 +301,10 @@ async fn write_ingest_record(
             Ok(Some("Melt".to_string()))
         } else if caller.starts_with("SNIFFLESv") {
             Ok(Some("Sniffles".to_string()))
+        } else if caller == segments::SEGMENTS_CALLER {
+            Ok(Some("Segments".to_string()))
+        } else if callers::CALLER_NAMES.contains(&caller) {
+            Ok(Some(caller.to_string()))
         } else {
             anyhow::bail!("unknown caller: {}", caller)
         }
@@ -296,14 +349,52 @@ async fn write_ingest_record(
         .map_err(|e| anyhow::anyhow!("Error writing VCF record: {}", e))
 }
 
+/// Convert the clustered `record` into a VCF record.
+///
+/// The conversion in mehari drops the confidence intervals, so `CIPOS` and `CIEND` are
+/// set here unless they are `0,0`.
+fn cluster_to_vcf_record(
+    record: mehari::annotate::strucvars::VarFishStrucvarTsvRecord,
+) -> Result<vcf::variant::RecordBuf, anyhow::Error> {
+    use vcf::variant::record::info::field::key;
+    use vcf::variant::record_buf::info::field::{value::Array, Value};
+
+    let cis = [
+        (
+            key::POSITION_CONFIDENCE_INTERVALS,
+            record.start_ci_left,
+            record.start_ci_right,
+        ),
+        (
+            key::END_CONFIDENCE_INTERVALS,
+            record.end_ci_left,
+            record.end_ci_right,
+        ),
+    ];
+    let mut result: vcf::variant::RecordBuf = record.try_into()?;
+    for (key, left, right) in cis {
+        if (left, right) != (0, 0) {
+            result.info_mut().insert(
+                key.to_string(),
+                Some(Value::Array(Array::Integer(vec![Some(left), Some(right)]))),
+            );
+        }
+    }
+    Ok(result)
+}
+
 /// Write out variants from input files.
+#[allow(clippy::too_many_arguments)]
 async fn process_variants(
     pedigree: &mehari::ped::PedigreeByName,
     output_header: &vcf::Header,
     output_writer: &mut AsyncVcfWriter,
     input_readers: Vec<VariantReader>,
     input_header: &[vcf::Header],
-    input_sv_callers: &[mehari::annotate::strucvars::SvCaller],
+    input_sv_callers: &[Option<mehari::annotate::strucvars::SvCaller>],
+    input_other_callers: &[Option<callers::Caller>],
+    copy_number_qualities: &dragen::CopyNumberQualities,
+    imprecise_calls: &manta::ImpreciseCalls,
     args: &Args,
 ) -> Result<(), anyhow::Error> {
     // Initialize the random number generator from command line seed if given or local entropy
@@ -318,29 +409,83 @@ async fn process_variants(
     // seriealized `VarFishStrucvarTsvRecord`s) for each SV type and contig.
     let tmp_dir = tempfile::TempDir::new()?;
 
+    // Records that are not clustered by contig number, i.e., the records of callers not
+    // supported by mehari and the copy number segments.
+    let mut direct_records = std::collections::HashMap::<usize, Vec<_>>::new();
+    // Return the contig number of `chrom`, if canonical.
+    let canonical_contig_no = |chrom: &str| {
+        let chrom = annonars::common::cli::canonicalize(chrom);
+        annonars::common::cli::CANONICAL
+            .iter()
+            .position(|canonical| *canonical == chrom)
+            .map(|contig_idx| contig_idx + 1)
+    };
+
     // Read through input VCF files and write out to temporary files.
     tracing::info!("converting input VCF files to temporary files...");
     let mut input_readers = input_readers;
-    for (mut reader, sv_caller, header) in itertools::izip!(
+    for (mut reader, sv_caller, other_caller, header) in itertools::izip!(
         input_readers.drain(..),
         input_sv_callers.iter(),
+        input_other_callers.iter(),
         input_header.iter()
     ) {
-        mehari::annotate::strucvars::run_vcf_to_jsonl(
-            pedigree,
-            &mut reader,
-            header,
-            sv_caller,
-            &tmp_dir,
-            &mut std::collections::HashMap::new(),
-            &mut rng,
-        )
-        .await?;
+        if let Some(sv_caller) = sv_caller {
+            mehari::annotate::strucvars::run_vcf_to_jsonl(
+                pedigree,
+                &mut reader,
+                header,
+                sv_caller,
+                &tmp_dir,
+                &mut std::collections::HashMap::new(),
+                &mut rng,
+            )
+            .await?;
+        } else if let Some(other_caller) = other_caller {
+            for record in callers::read_records(
+                other_caller,
+                &mut reader,
+                header,
+                output_header.sample_names(),
+                args.genomebuild,
+            )
+            .await?
+            {
+                let Some(contig_no) = canonical_contig_no(record.reference_sequence_name()) else {
+                    tracing::warn!(
+                        "skipping {} record on unknown chromosome: {}",
+                        other_caller.name(),
+                        record.reference_sequence_name()
+                    );
+                    continue;
+                };
+                direct_records.entry(contig_no).or_default().push(record);
+            }
+        }
     }
     tracing::info!("... done converting input files");
 
+    // Convert copy number segments into records by contig number.
+    for path in &args.path_segments {
+        tracing::info!("converting segment file {}...", path);
+        for segment in segments::read_segments(path)? {
+            let Some(contig_no) = canonical_contig_no(&segment.chrom) else {
+                tracing::warn!("skipping segment on unknown chromosome: {}", &segment.chrom);
+                continue;
+            };
+            if let Some(record) =
+                segments::segment_record(&segment, output_header.sample_names(), args.genomebuild)?
+            {
+                direct_records.entry(contig_no).or_default().push(record);
+            }
+        }
+    }
+
     tracing::info!("clustering SVs to output...");
+    let record_filter = filter::RecordFilter::from_args(args);
     // Read through temporary files by contig, cluster by overlap as configured, and write to `writer`.
+    // Mates of translocations are on different contigs, only the first one is written.
+    let mut seen_translocations = std::collections::HashSet::new();
     for contig_no in 1..=25 {
         tracing::info!(
             "  contig: {}",
@@ -353,8 +498,42 @@ async fn process_variants(
             args.slack_bnd,
             args.min_overlap,
         )?;
+        // Mates of the same break-end pair are assembled into the same linear SV.
+        let mut seen_assembled = std::collections::HashSet::new();
+        let mut records = Vec::new();
         for record in clusters {
-            write_ingest_record(output_header, output_writer, &record.try_into()?).await?;
+            let mut record = cluster_to_vcf_record(record)?;
+            copy_number_qualities.annotate(&mut record);
+            imprecise_calls.annotate(&mut record);
+            let record = if args.assemble_bnd {
+                match bnd::assemble_record(&record)? {
+                    Some((linear_sv, assembled)) => {
+                        if !seen_assembled.insert(linear_sv) {
+                            continue;
+                        }
+                        assembled
+                    }
+                    None => match bnd::record_translocation(&record)? {
+                        Some(translocation) if !seen_translocations.insert(translocation) => {
+                            continue;
+                        }
+                        _ => record,
+                    },
+                }
+            } else {
+                record
+            };
+            records.push(record);
+        }
+        if let Some(contig_direct_records) = direct_records.remove(&contig_no) {
+            records.extend(contig_direct_records);
+            records.sort_by_key(|record| record.variant_start());
+        }
+        for record in records
+            .iter()
+            .filter(|record| record_filter.passes_size(record))
+        {
+            write_ingest_record(output_header, output_writer, record).await?;
         }
     }
     tracing::info!("... done clustering SVs to output");
@@ -375,8 +554,35 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         .map_err(|e| anyhow::anyhow!("problem parsing PED file: {}", e))?;
     tracing::info!("pedigre = {:#?}", &pedigree);
 
+    // Filter the input files on QUAL and FILTER into a temporary directory, if configured.
+    let tmp_dir_filtered = tempfile::TempDir::new()?;
+    let record_filter = filter::RecordFilter::from_args(args);
+    let paths_in = if record_filter.filters_input() {
+        tracing::info!("filtering input files...");
+        let mut paths_in = Vec::new();
+        for (i, path_in) in args.path_in.iter().enumerate() {
+            let path_out = tmp_dir_filtered
+                .path()
+                .join(format!("filtered-{}.vcf", i))
+                .to_string_lossy()
+                .to_string();
+            let (count_read, count_written) =
+                filter::write_filtered_input(path_in, &path_out, &record_filter).await?;
+            tracing::info!(
+                "  kept {} of {} records from {}",
+                count_written,
+                count_read,
+                path_in
+            );
+            paths_in.push(path_out);
+        }
+        paths_in
+    } else {
+        args.path_in.clone()
+    };
+
     tracing::info!("opening input file...");
-    let mut input_readers = open_vcf_readers(&args.path_in).await?;
+    let mut input_readers = open_vcf_readers(&paths_in).await?;
 
     tracing::info!("loading file identifier mappings...");
     let id_mappings = args
@@ -423,13 +629,26 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         .transpose()?;
 
     tracing::info!("guessing SV callers...");
-    let input_sv_callers = {
-        let mut sv_callers = Vec::new();
-        for mut reader in open_vcf_readers(&args.path_in).await? {
-            sv_callers.push(guess_sv_caller(&mut reader).await?);
-        }
-        sv_callers
-    };
+    // The callers that are not supported by mehari and Manta are recognized from the
+    // header first.
+    let mut input_sv_callers = Vec::new();
+    let mut input_other_callers = Vec::new();
+    for (path_in, mut reader) in paths_in.iter().zip(open_vcf_readers(&paths_in).await?) {
+        let header = reader
+            .read_header()
+            .await
+            .map_err(|e| anyhow::anyhow!("problem reading header of {}: {}", path_in, e))?;
+        let other_caller = callers::Caller::from_header(&header);
+        input_sv_callers.push(if other_caller.is_some() {
+            None
+        } else if let Some(manta) = manta::caller_from_header(&header) {
+            Some(manta)
+        } else {
+            let mut reader = crate::common::noodles::open_vcf_reader(path_in).await?;
+            Some(guess_sv_caller(&mut reader).await?)
+        });
+        input_other_callers.push(other_caller);
+    }
 
     tracing::info!("processing header...");
     let input_headers = join_all(
@@ -496,7 +715,8 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
     }
     let output_header = header::build_output_header(
         orig_sample_names,
-        &input_sv_callers.iter().collect::<Vec<_>>(),
+        &input_sv_callers.iter().flatten().collect::<Vec<_>>(),
+        &input_other_callers.iter().flatten().collect::<Vec<_>>(),
         id_mappings.as_ref().map(|id_mappings| {
             id_mappings
                 .mapping_for_file(args.path_in.first().expect("count checked above"))
@@ -538,6 +758,30 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             input_headers.clone()
         };
 
+        // Read the copy number qualities of DRAGEN CNV calls and the imprecise flag of
+        // Manta calls that mehari does not keep.
+        let mut copy_number_qualities = dragen::CopyNumberQualities::default();
+        let mut imprecise_calls = manta::ImpreciseCalls::default();
+        for ((path_in, sv_caller), mapped_input_header) in paths_in
+            .iter()
+            .zip(input_sv_callers.iter())
+            .zip(mapped_input_headers.iter())
+        {
+            if matches!(
+                sv_caller,
+                Some(mehari::annotate::strucvars::SvCaller::DragenCnv { .. })
+            ) {
+                copy_number_qualities
+                    .read_path(path_in, mapped_input_header, output_header.sample_names())
+                    .await?;
+            } else if matches!(
+                sv_caller,
+                Some(mehari::annotate::strucvars::SvCaller::Manta { .. })
+            ) {
+                imprecise_calls.read_path(path_in).await?;
+            }
+        }
+
         // Perform actual writing
         let mut output_writer = open_vcf_writer(out_path_helper.path_out()).await?;
         output_writer
@@ -552,6 +796,9 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
             input_readers,
             &mapped_input_headers,
             &input_sv_callers,
+            &input_other_callers,
+            &copy_number_qualities,
+            &imprecise_calls,
             args,
         )
         .await?;
@@ -559,7 +806,9 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         flush_and_shutdown!(output_writer);
     }
 
-    out_path_helper.create_tbi_for_bgzf().await?;
+    if !args.no_index {
+        out_path_helper.create_index().await?;
+    }
     out_path_helper.upload_for_s3().await?;
 
     tracing::info!(
@@ -586,6 +835,7 @@ mod test {
                 String::from("tests/strucvars/ingest/popdel-min.vcf"),
             ],
             path_cov_vcf: vec![],
+            path_segments: vec![],
             path_ped: "tests/strucvars/ingest/delly2-min.ped".into(),
             genomebuild: GenomeRelease::Grch37,
             path_out: tmpdir
@@ -596,10 +846,18 @@ mod test {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
             rng_seed: Some(42),
             file_date: String::from("20230421"),
             case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
             id_mapping: None,
+            no_index: false,
+            assemble_bnd: false,
         };
         super::run(&args_common, &args).await?;
 
@@ -624,6 +882,7 @@ mod test {
                 String::from("tests/strucvars/ingest/sniffles2-min.vcf"),
             ],
             path_cov_vcf: vec![],
+            path_segments: vec![],
             path_ped: "tests/strucvars/ingest/dragen-cnv-min.ped".into(),
             genomebuild: GenomeRelease::Grch37,
             path_out: tmpdir
@@ -634,10 +893,18 @@ mod test {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
             rng_seed: Some(42),
             file_date: String::from("20230421"),
             case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
             id_mapping: None,
+            no_index: false,
+            assemble_bnd: false,
         };
         super::run(&args_common, &args).await?;
 
@@ -659,6 +926,7 @@ mod test {
                 String::from("tests/strucvars/ingest/popdel-min.vcf.gz"),
             ],
             path_cov_vcf: vec![],
+            path_segments: vec![],
             path_ped: "tests/strucvars/ingest/delly2-min.ped".into(),
             genomebuild: GenomeRelease::Grch37,
             path_out: tmpdir
@@ -669,10 +937,18 @@ mod test {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
             rng_seed: Some(42),
             file_date: String::from("20230421"),
             case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
             id_mapping: None,
+            no_index: false,
+            assemble_bnd: false,
         };
         super::run(&args_common, &args).await?;
 
@@ -699,6 +975,7 @@ mod test {
                 String::from("tests/strucvars/ingest/sniffles2-min.vcf.gz"),
             ],
             path_cov_vcf: vec![],
+            path_segments: vec![],
             path_ped: "tests/strucvars/ingest/dragen-cnv-min.ped".into(),
             genomebuild: GenomeRelease::Grch37,
             path_out: tmpdir
@@ -709,10 +986,18 @@ mod test {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
             rng_seed: Some(42),
             file_date: String::from("20230421"),
             case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
             id_mapping: None,
+            no_index: false,
+            assemble_bnd: false,
         };
         super::run(&args_common, &args).await?;
 
@@ -739,6 +1024,7 @@ mod test {
                 String::from("tests/strucvars/ingest/sniffles2-min.vcf.gz"),
             ],
             path_cov_vcf: vec![],
+            path_segments: vec![],
             path_ped: "tests/strucvars/ingest/dragen-cnv-min.custom_id.ped".into(),
             genomebuild: GenomeRelease::Grch37,
             path_out: tmpdir
@@ -749,6 +1035,12 @@ mod test {
             min_overlap: 0.8,
             slack_bnd: 50,
             slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
             rng_seed: Some(42),
             file_date: String::from("20230421"),
             case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
@@ -815,6 +1107,8 @@ mod test {
                 "#
                 .into(),
             ),
+            no_index: false,
+            assemble_bnd: false,
         };
         super::run(&args_common, &args).await?;
 
@@ -822,4 +1116,356 @@ mod test {
 
         Ok(())
     }
+
+    /// The confidence intervals written by ingest widen the breakpoint windows in query.
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn confidence_intervals_ingest_then_query() -> Result<(), anyhow::Error> {
+        use crate::strucvars::query::schema::StructuralVariant;
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            max_var_count: None,
+            path_in: vec![String::from("tests/strucvars/ingest/delly2-min.vcf")],
+            path_cov_vcf: vec![],
+            path_segments: vec![],
+            path_ped: "tests/strucvars/ingest/delly2-min.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
+            rng_seed: Some(42),
+            file_date: String::from("20230421"),
+            case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
+            id_mapping: None,
+            no_index: true,
+            assemble_bnd: false,
+        };
+        super::run(&args_common, &args).await?;
+
+        let mut reader = mehari::common::noodles::open_vcf_reader(&args.path_out).await?;
+        let header = reader.read_header().await?;
+        let mut svs = Vec::new();
+        let mut record_buf = noodles::vcf::variant::RecordBuf::default();
+        while reader.read_record_buf(&header, &mut record_buf).await? != 0 {
+            svs.push(StructuralVariant::from_vcf(&record_buf, &header)?);
+        }
+
+        assert_eq!(svs.len(), 2);
+        // The deletion has `CIPOS=-20,20;CIEND=-20,20`.
+        assert_eq!((svs[0].pos, svs[0].end), (586412, 586439));
+        assert_eq!(svs[0].ci_pos, Some((-20, 20)));
+        assert_eq!(svs[0].ci_end, Some((-20, 20)));
+        assert_eq!(svs[0].pos_window(10), 586392..586432);
+        assert_eq!(svs[0].end_window(50), 586389..586489);
+        // The break-end has no confidence intervals.
+        assert_eq!(svs[1].ci_pos, None);
+        assert_eq!(svs[1].ci_end, None);
+        assert_eq!(svs[1].pos_window(50), 321631..321731);
+
+        Ok(())
+    }
+
+    /// The DRAGEN CNV copy number quality is written as `FORMAT/cnq`.
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn dragen_cnv_cnq() -> Result<(), anyhow::Error> {
+        use crate::strucvars::query::schema::{SampleEvidenceFilter, StructuralVariant};
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            max_var_count: None,
+            path_in: vec![String::from(
+                "tests/strucvars/ingest/dragen-cnv-cnq-min.vcf",
+            )],
+            path_cov_vcf: vec![],
+            path_segments: vec![],
+            path_ped: "tests/strucvars/ingest/dragen-cnv-cnq-min.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
+            rng_seed: Some(42),
+            file_date: String::from("20230421"),
+            case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
+            id_mapping: None,
+            no_index: true,
+            assemble_bnd: false,
+        };
+        super::run(&args_common, &args).await?;
+
+        let mut reader = mehari::common::noodles::open_vcf_reader(&args.path_out).await?;
+        let header = reader.read_header().await?;
+        let mut svs = Vec::new();
+        let mut record_buf = noodles::vcf::variant::RecordBuf::default();
+        while reader.read_record_buf(&header, &mut record_buf).await? != 0 {
+            svs.push(StructuralVariant::from_vcf(&record_buf, &header)?);
+        }
+
+        assert_eq!(svs.len(), 1);
+        assert_eq!(svs[0].callers, vec!["DragenCnv".to_string()]);
+        let call_info = &svs[0].call_info["SAMPLE"];
+        assert_eq!(call_info.copy_number, Some(1));
+        assert_eq!(call_info.copy_number_quality, Some(27.0));
+
+        // The copy number quality is used by the `min_cnq` evidence filter of the query.
+        let filter = |min_cnq| SampleEvidenceFilter {
+            min_cnq: Some(min_cnq),
+            ..Default::default()
+        };
+        assert!(filter(20.0).is_call_info_pass(call_info));
+        assert!(!filter(30.0).is_call_info_pass(call_info));
+
+        Ok(())
+    }
+
+    /// Manta calls are recognized from the header and their evidence is mapped.
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn smoke_test_manta() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            max_var_count: None,
+            path_in: vec![String::from("tests/strucvars/ingest/manta-min.vcf.gz")],
+            path_cov_vcf: vec![],
+            path_segments: vec![],
+            path_ped: "tests/strucvars/ingest/manta-min.ped".into(),
+            genomebuild: GenomeRelease::Grch37,
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
+            rng_seed: Some(42),
+            file_date: String::from("20230421"),
+            case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
+            id_mapping: None,
+            no_index: true,
+            assemble_bnd: false,
+        };
+        super::run(&args_common, &args).await?;
+
+        insta::assert_snapshot!(std::fs::read_to_string(&args.path_out)?);
+
+        Ok(())
+    }
+
+    /// The records of callers not supported by mehari are converted directly.
+    #[rstest::rstest]
+    #[case::cnvnator("cnvnator-min", "Cnvnator")]
+    #[case::cnvpytor("cnvpytor-min", "Cnvpytor")]
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn read_depth_callers(
+        #[case] name: &str,
+        #[case] caller: &str,
+    ) -> Result<(), anyhow::Error> {
+        use crate::strucvars::query::schema::{StructuralVariant, SvType};
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            max_var_count: None,
+            path_in: vec![format!("tests/strucvars/ingest/{}.vcf", name)],
+            path_cov_vcf: vec![],
+            path_segments: vec![],
+            path_ped: format!("tests/strucvars/ingest/{}.ped", name),
+            genomebuild: GenomeRelease::Grch37,
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
+            rng_seed: Some(42),
+            file_date: String::from("20230421"),
+            case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
+            id_mapping: None,
+            no_index: true,
+            assemble_bnd: false,
+        };
+        super::run(&args_common, &args).await?;
+
+        let mut reader = mehari::common::noodles::open_vcf_reader(&args.path_out).await?;
+        let header = reader.read_header().await?;
+        let mut svs = Vec::new();
+        let mut record_buf = noodles::vcf::variant::RecordBuf::default();
+        while reader.read_record_buf(&header, &mut record_buf).await? != 0 {
+            svs.push(StructuralVariant::from_vcf(&record_buf, &header)?);
+        }
+
+        // The record on the decoy contig is skipped.
+        assert_eq!(
+            svs.iter()
+                .map(|sv| (sv.sv_type, sv.pos, sv.end))
+                .collect::<Vec<_>>(),
+            vec![
+                (SvType::Del, 1283845, 1294844),
+                (SvType::Dup, 2000001, 2050000)
+            ]
+        );
+        for (sv, copy_number) in svs.iter().zip([1, 3]) {
+            assert_eq!(sv.callers, vec![caller.to_string()]);
+            assert_eq!(sv.call_info["SAMPLE"].genotype.as_deref(), Some("0/1"));
+            assert_eq!(sv.call_info["SAMPLE"].copy_number, Some(copy_number));
+        }
+        assert!(std::fs::read_to_string(&args.path_out)?.contains(&format!(
+            "##x-varfish-version=<ID={},Name={}",
+            caller, caller
+        )));
+
+        Ok(())
+    }
+
+    /// Sequence-resolved long read calls are converted keeping the insertion sequence.
+    #[rstest::rstest]
+    #[case::cutesv("cutesv-min", "CuteSv")]
+    #[case::pbsv("pbsv-min", "Pbsv")]
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn long_read_callers(
+        #[case] name: &str,
+        #[case] caller: &str,
+    ) -> Result<(), anyhow::Error> {
+        use crate::strucvars::query::schema::{StructuralVariant, SvType};
+
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let args_common = Default::default();
+        let args = super::Args {
+            max_var_count: None,
+            path_in: vec![format!("tests/strucvars/ingest/{}.vcf", name)],
+            path_cov_vcf: vec![],
+            path_segments: vec![],
+            path_ped: format!("tests/strucvars/ingest/{}.ped", name),
+            genomebuild: GenomeRelease::Grch37,
+            path_out: tmpdir
+                .join("out.vcf")
+                .to_str()
+                .expect("invalid path")
+                .into(),
+            min_overlap: 0.8,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_qual: None,
+            require_pass: false,
+            min_size_del: None,
+            min_size_dup: None,
+            min_size_inv: None,
+            min_size_cnv: None,
+            rng_seed: Some(42),
+            file_date: String::from("20230421"),
+            case_uuid: String::from("d2bad2ec-a75d-44b9-bd0a-83a3f1331b7c"),
+            id_mapping: None,
+            no_index: true,
+            assemble_bnd: false,
+        };
+        super::run(&args_common, &args).await?;
+
+        let mut reader = mehari::common::noodles::open_vcf_reader(&args.path_out).await?;
+        let header = reader.read_header().await?;
+        let mut records = Vec::new();
+        let mut record_buf = noodles::vcf::variant::RecordBuf::default();
+        while reader.read_record_buf(&header, &mut record_buf).await? != 0 {
+            records.push(record_buf.clone());
+        }
+        let svs = records
+            .iter()
+            .map(|record| StructuralVariant::from_vcf(record, &header))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let expected = if caller == "CuteSv" {
+            vec![
+                (SvType::Ins, 1283844, 1283844),
+                (SvType::Del, 1290001, 1290053),
+                (SvType::Bnd, 1300001, 321682),
+            ]
+        } else {
+            vec![
+                (SvType::Ins, 1283844, 1283844),
+                (SvType::Del, 1290001, 1290053),
+                (SvType::Inv, 1400001, 1405000),
+            ]
+        };
+        assert_eq!(
+            svs.iter()
+                .map(|sv| (sv.sv_type, sv.pos, sv.end))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            records[0].info().get(super::callers::INSERTION_SEQUENCE),
+            Some(Some(
+                &noodles::vcf::variant::record_buf::info::field::Value::String(
+                    "CAGATTTTCATATTATGCAGAAAATCTACTTCGCCTGATACGAGTCGGTTAT".into()
+                )
+            ))
+        );
+        assert_eq!(
+            records[1].info().get(super::callers::INSERTION_SEQUENCE),
+            None
+        );
+        for sv in &svs {
+            assert_eq!(sv.callers, vec![caller.to_string()]);
+            assert_eq!(sv.call_info["SAMPLE"].genotype.as_deref(), Some("0/1"));
+        }
+        let call_info = &svs[0].call_info["SAMPLE"];
+        assert_eq!(call_info.split_read_var, Some(12));
+        assert_eq!(call_info.split_read_cov, Some(25));
+        assert_eq!(
+            call_info.quality,
+            if caller == "CuteSv" { Some(48.0) } else { None }
+        );
+        if caller == "CuteSv" {
+            assert_eq!(svs[2].chrom2.as_deref(), Some("2"));
+        }
+
+        Ok(())
+    }
 }
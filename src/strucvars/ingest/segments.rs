@@ -0,0 +1,286 @@
+//! Conversion of copy number segment files into deletions and duplications.
+//!
+//! Array-CGH and read depth tools (e.g., ExomeDepth) report copy number segments
+//! as SEG or BED files.  Segments with a copy number below or above two are
+//! converted into `<DEL>` and `<DUP>` records so they can be queried alongside
+//! the calls from the VCF files.
+
+use std::io::BufRead as _;
+
+use mehari::common::io::std::open_read_maybe_gz;
+use noodles::vcf;
+
+use crate::common::GenomeRelease;
+
+/// Name of the caller written to `INFO/callers` for segment records.
+pub const SEGMENTS_CALLER: &str = "SEGMENTS";
+
+/// Format of a segment file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentFormat {
+    /// IGV SEG file with the columns `ID`, `chrom`, `loc.start`, `loc.end`,
+    /// `num.mark`, `seg.mean` (log2 ratio), and an optional copy number.
+    Seg,
+    /// BED file with the columns `chrom`, `start`, `end`, `sample`, `log2`, and an
+    /// optional copy number.
+    Bed,
+}
+
+impl SegmentFormat {
+    /// Guess the format from the file extension of `path`.
+    pub fn from_path(path: &str) -> Result<Self, anyhow::Error> {
+        let path = path.trim_end_matches(".gz");
+        if path.ends_with(".seg") {
+            Ok(SegmentFormat::Seg)
+        } else if path.ends_with(".bed") {
+            Ok(SegmentFormat::Bed)
+        } else {
+            anyhow::bail!("cannot guess segment file format from extension: {}", path)
+        }
+    }
+}
+
+/// A copy number segment of one sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Name of the sample.
+    pub sample: String,
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based start position.
+    pub start: usize,
+    /// 1-based end position.
+    pub end: usize,
+    /// The log2 copy ratio, if given.
+    pub log2_ratio: Option<f32>,
+    /// The absolute copy number, if given.
+    pub copy_number: Option<i32>,
+}
+
+impl Segment {
+    /// Return the copy number, derived from the log2 ratio if not given.
+    pub fn copy_number(&self) -> Option<i32> {
+        self.copy_number.or_else(|| {
+            self.log2_ratio
+                .filter(|log2_ratio| log2_ratio.is_finite())
+                .map(|log2_ratio| (2.0 * 2f32.powf(log2_ratio)).round() as i32)
+        })
+    }
+
+    /// Return the SV type and genotype, if the segment is not copy neutral.
+    pub fn sv_type_and_genotype(&self) -> Option<(&'static str, &'static str)> {
+        match self.copy_number()? {
+            0 => Some(("DEL", "1/1")),
+            1 => Some(("DEL", "0/1")),
+            2 => None,
+            3 => Some(("DUP", "0/1")),
+            _ => Some(("DUP", "1/1")),
+        }
+    }
+}
+
+/// Parse the optional `value`, treating "NA" and "." as missing.
+fn parse_optional<T: std::str::FromStr>(value: Option<&str>) -> Result<Option<T>, anyhow::Error>
+where
+    T::Err: std::fmt::Display,
+{
+    match value {
+        None | Some("") | Some("NA") | Some(".") => Ok(None),
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("invalid value {}: {}", value, e)),
+    }
+}
+
+/// Parse one data line of a segment file in the given `format`.
+pub fn parse_line(line: &str, format: SegmentFormat) -> Result<Segment, anyhow::Error> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    let parse_pos = |value: &str| {
+        value
+            .parse::<usize>()
+            .map_err(|e| anyhow::anyhow!("invalid position {}: {}", value, e))
+    };
+    match format {
+        SegmentFormat::Seg => {
+            if fields.len() < 6 {
+                anyhow::bail!("too few columns in SEG line: {}", line);
+            }
+            Ok(Segment {
+                sample: fields[0].to_string(),
+                chrom: fields[1].to_string(),
+                start: parse_pos(fields[2])?,
+                end: parse_pos(fields[3])?,
+                log2_ratio: parse_optional(Some(fields[5]))?,
+                copy_number: parse_optional(fields.get(6).copied())?,
+            })
+        }
+        SegmentFormat::Bed => {
+            if fields.len() < 5 {
+                anyhow::bail!("too few columns in BED line: {}", line);
+            }
+            Ok(Segment {
+                sample: fields[3].to_string(),
+                chrom: fields[0].to_string(),
+                start: parse_pos(fields[1])? + 1,
+                end: parse_pos(fields[2])?,
+                log2_ratio: parse_optional(Some(fields[4]))?,
+                copy_number: parse_optional(fields.get(5).copied())?,
+            })
+        }
+    }
+}
+
+/// Read all segments from the file at `path`.
+pub fn read_segments(path: &str) -> Result<Vec<Segment>, anyhow::Error> {
+    let format = SegmentFormat::from_path(path)?;
+    let mut result = Vec::new();
+    // SEG files start with a line of column headings.
+    let mut skip_header = format == SegmentFormat::Seg;
+    for line in open_read_maybe_gz(path)?.lines() {
+        let line = line?;
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("track")
+            || line.starts_with("browser")
+        {
+            continue;
+        } else if skip_header {
+            skip_header = false;
+            continue;
+        }
+        result.push(
+            parse_line(&line, format)
+                .map_err(|e| anyhow::anyhow!("problem parsing {}: {}", path, e))?,
+        );
+    }
+    Ok(result)
+}
+
+/// Convert `segment` into a VCF record with the samples in `sample_names`.
+///
+/// Returns `None` for copy neutral segments.
+pub fn segment_record(
+    segment: &Segment,
+    sample_names: &vcf::header::SampleNames,
+    genomebuild: GenomeRelease,
+) -> Result<Option<vcf::variant::RecordBuf>, anyhow::Error> {
+    use vcf::variant::record_buf::{
+        info::field::Value as InfoValue,
+        samples::sample::value::{Genotype, Value},
+    };
+
+    let Some((sv_type, genotype)) = segment.sv_type_and_genotype() else {
+        return Ok(None);
+    };
+    if !sample_names.contains(&segment.sample) {
+        anyhow::bail!("unknown sample in segment file: {}", &segment.sample);
+    }
+
+    let chrom = annonars::common::cli::canonicalize(&segment.chrom);
+    let chrom = match genomebuild {
+        GenomeRelease::Grch37 => chrom,
+        GenomeRelease::Grch38 => format!("chr{}", chrom),
+    };
+    let values = sample_names
+        .iter()
+        .map(|sample| {
+            if *sample == segment.sample {
+                vec![
+                    genotype.parse::<Genotype>().ok().map(Value::Genotype),
+                    segment.copy_number().map(Value::Integer),
+                ]
+            } else {
+                vec![None, None]
+            }
+        })
+        .collect();
+
+    let mut info: vcf::variant::record_buf::Info = Default::default();
+    info.insert(
+        vcf::variant::record::info::field::key::END_POSITION.to_string(),
+        Some(InfoValue::Integer(segment.end as i32)),
+    );
+    info.insert(
+        "callers".to_string(),
+        Some(InfoValue::String(SEGMENTS_CALLER.to_string())),
+    );
+
+    Ok(Some(
+        vcf::variant::record_buf::builder::Builder::default()
+            .set_reference_sequence_name(chrom)
+            .set_variant_start(noodles::core::Position::try_from(segment.start)?)
+            .set_reference_bases("N")
+            .set_alternate_bases(vcf::variant::record_buf::AlternateBases::from(vec![
+                format!("<{}>", sv_type),
+            ]))
+            .set_info(info)
+            .set_samples(vcf::variant::record_buf::samples::Samples::new(
+                ["GT", "cn"].into_iter().map(String::from).collect(),
+                values,
+            ))
+            .build(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Segment, SegmentFormat};
+
+    #[test]
+    fn parse_seg_line() -> Result<(), anyhow::Error> {
+        let line = "index\tchr1\t1000001\t2000000\t120\t-0.95";
+        let segment = super::parse_line(line, SegmentFormat::Seg)?;
+
+        assert_eq!(
+            segment,
+            Segment {
+                sample: "index".into(),
+                chrom: "chr1".into(),
+                start: 1000001,
+                end: 2000000,
+                log2_ratio: Some(-0.95),
+                copy_number: None,
+            }
+        );
+        assert_eq!(segment.sv_type_and_genotype(), Some(("DEL", "0/1")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bed_line() -> Result<(), anyhow::Error> {
+        let line = "1\t1000000\t2000000\tindex\t0.58\t3";
+        let segment = super::parse_line(line, SegmentFormat::Bed)?;
+
+        assert_eq!((segment.start, segment.end), (1000001, 2000000));
+        assert_eq!(segment.copy_number(), Some(3));
+        assert_eq!(segment.sv_type_and_genotype(), Some(("DUP", "0/1")));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(Some(-5.0), None, Some(("DEL", "1/1")))]
+    #[case(Some(-1.0), None, Some(("DEL", "0/1")))]
+    #[case(Some(0.1), None, None)]
+    #[case(Some(0.58), None, Some(("DUP", "0/1")))]
+    #[case(Some(-1.0), Some(2), None)]
+    #[case(None, Some(4), Some(("DUP", "1/1")))]
+    #[case(None, None, None)]
+    fn sv_type_and_genotype(
+        #[case] log2_ratio: Option<f32>,
+        #[case] copy_number: Option<i32>,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        let segment = Segment {
+            sample: "index".into(),
+            chrom: "1".into(),
+            start: 1,
+            end: 1000,
+            log2_ratio,
+            copy_number,
+        };
+        assert_eq!(segment.sv_type_and_genotype(), expected);
+    }
+}
@@ -0,0 +1,411 @@
+//! Implementation of the `strucvars lift` subcommand.
+//!
+//! Lifts an in-house database TSV file written by `strucvars aggregate` to
+//! another genome release with a UCSC chain file, such that the counts are kept
+//! when a site migrates from GRCh37 to GRCh38.
+
+use std::{collections::HashMap, io::BufRead, io::Write, path::PathBuf};
+
+use clap::Parser;
+use mehari::common::io::std::{open_read_maybe_gz, open_write_maybe_bgzf};
+
+use crate::{
+    common::GenomeRelease,
+    strucvars::{
+        aggregate::{
+            cli::{open_inhouse_tsv_writer, read_inhouse_tsv},
+            output::Record,
+        },
+        query::schema::SvType,
+    },
+};
+
+/// Command line arguments for `strucvars lift` sub command.
+#[derive(Parser, Debug)]
+#[command(about = "Lift in-house database to another genome release", long_about = None)]
+pub struct Args {
+    /// Genome release of the input file.
+    #[arg(long, value_enum, default_value_t = GenomeRelease::Grch37)]
+    pub genome_release_from: GenomeRelease,
+    /// Genome release of the output file.
+    #[arg(long, value_enum, default_value_t = GenomeRelease::Grch38)]
+    pub genome_release_to: GenomeRelease,
+    /// Path to the UCSC chain file, e.g., `hg19ToHg38.over.chain.gz`.
+    #[arg(long)]
+    pub path_chain: String,
+    /// Path to the in-house database TSV file from `strucvars aggregate`.
+    #[arg(long)]
+    pub path_input: String,
+    /// Path to the lifted in-house database TSV file.
+    #[arg(long)]
+    pub path_output: PathBuf,
+    /// Optional path to a TSV file with the lifted coordinates and confidence of each record.
+    #[arg(long)]
+    pub path_report: Option<PathBuf>,
+    /// Minimal confidence of lifted records to write out.
+    #[arg(long, default_value_t = 0.9)]
+    pub min_confidence: f32,
+}
+
+/// One ungapped alignment block of a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainBlock {
+    /// 0-based start in the source release.
+    pub t_start: u64,
+    /// 0-based, exclusive end in the source release.
+    pub t_end: u64,
+    /// Chromosome in the target release.
+    pub q_chrom: String,
+    /// 0-based start in the target release, on the strand of the chain.
+    pub q_start: u64,
+    /// Size of the chromosome in the target release.
+    pub q_size: u64,
+    /// Whether the chain maps to the reverse strand.
+    pub q_reverse: bool,
+    /// ID of the chain.
+    pub chain_id: u64,
+}
+
+impl ChainBlock {
+    /// Map the 0-based position `pos` in the block to the forward strand of the target.
+    fn map(&self, pos: u64) -> u64 {
+        let q_pos = self.q_start + (pos - self.t_start);
+        if self.q_reverse {
+            self.q_size - 1 - q_pos
+        } else {
+            q_pos
+        }
+    }
+}
+
+/// The blocks of a chain file by source chromosome, sorted by start.
+#[derive(Debug, Clone, Default)]
+pub struct Chain {
+    /// Blocks by canonical chromosome name.
+    pub blocks: HashMap<String, Vec<ChainBlock>>,
+}
+
+/// Parse the chain file from `reader`.
+pub fn read_chain(reader: impl BufRead) -> Result<Chain, anyhow::Error> {
+    let mut result = Chain::default();
+    // Source chromosome and current positions of the chain being read.
+    let mut current: Option<(String, u64, u64, ChainBlock)> = None;
+    for line in reader.lines() {
+        let line = line?;
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.is_empty() || fields[0].starts_with('#') {
+            continue;
+        } else if fields[0] == "chain" {
+            if fields.len() < 12 {
+                anyhow::bail!("invalid chain header: {}", line);
+            }
+            let parse = |value: &str| {
+                value
+                    .parse::<u64>()
+                    .map_err(|e| anyhow::anyhow!("invalid number {} in chain file: {}", value, e))
+            };
+            let template = ChainBlock {
+                t_start: 0,
+                t_end: 0,
+                q_chrom: annonars::common::cli::canonicalize(fields[7]),
+                q_start: 0,
+                q_size: parse(fields[8])?,
+                q_reverse: fields[9] == "-",
+                chain_id: fields.get(12).map(|id| parse(id)).transpose()?.unwrap_or(0),
+            };
+            current = Some((
+                annonars::common::cli::canonicalize(fields[2]),
+                parse(fields[5])?,
+                parse(fields[10])?,
+                template,
+            ));
+        } else {
+            let (t_chrom, t_pos, q_pos, template) = current
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("alignment data before chain header"))?;
+            let numbers = fields
+                .iter()
+                .map(|value| value.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| anyhow::anyhow!("invalid alignment data {}: {}", line, e))?;
+            let size = numbers[0];
+            result
+                .blocks
+                .entry(t_chrom.clone())
+                .or_default()
+                .push(ChainBlock {
+                    t_start: *t_pos,
+                    t_end: *t_pos + size,
+                    q_start: *q_pos,
+                    ..template.clone()
+                });
+            if numbers.len() >= 3 {
+                *t_pos += size + numbers[1];
+                *q_pos += size + numbers[2];
+            } else {
+                current = None;
+            }
+        }
+    }
+    for blocks in result.blocks.values_mut() {
+        blocks.sort_by_key(|block| block.t_start);
+    }
+    Ok(result)
+}
+
+impl Chain {
+    /// Return the block containing the 0-based position `pos` on `chrom`.
+    fn block(&self, chrom: &str, pos: u64) -> Option<&ChainBlock> {
+        let blocks = self
+            .blocks
+            .get(&annonars::common::cli::canonicalize(chrom))?;
+        // The blocks of liftOver chain files do not overlap in the source release.
+        let idx = blocks.partition_point(|block| block.t_start <= pos);
+        blocks[..idx].last().filter(|block| pos < block.t_end)
+    }
+
+    /// Lift the 1-based position `pos` on `chrom`, returning the chromosome,
+    /// position, and chain ID.
+    pub fn lift_pos(&self, chrom: &str, pos: u64) -> Option<(String, u64, u64)> {
+        let block = self.block(chrom, pos.checked_sub(1)?)?;
+        Some((
+            block.q_chrom.clone(),
+            block.map(pos - 1) + 1,
+            block.chain_id,
+        ))
+    }
+
+    /// Return the number of bases of the 1-based interval `begin..=end` on
+    /// `chrom` aligned in the chain with `chain_id`.
+    fn aligned_bases(&self, chrom: &str, begin: u64, end: u64, chain_id: u64) -> u64 {
+        self.blocks
+            .get(&annonars::common::cli::canonicalize(chrom))
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|block| block.chain_id == chain_id)
+                    .map(|block| {
+                        block
+                            .t_end
+                            .min(end)
+                            .saturating_sub(block.t_start.max(begin - 1))
+                    })
+                    .sum()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Result of lifting one record.
+#[derive(Debug, Clone)]
+pub struct LiftResult {
+    /// The lifted record.
+    pub record: Record,
+    /// Confidence between 0 and 1, the aligned fraction of the interval and the
+    /// ratio of the lengths.
+    pub confidence: f32,
+}
+
+/// Name `chrom` in the style of `like`, i.e., with or without "chr" prefix.
+fn chrom_like(chrom: &str, like: &str) -> String {
+    if like.starts_with("chr") {
+        format!("chr{}", chrom)
+    } else {
+        chrom.to_string()
+    }
+}
+
+/// Lift `record` with `chain`, `None` if a breakpoint cannot be lifted.
+pub fn lift_record(chain: &Chain, record: &Record) -> Option<LiftResult> {
+    let begin = u64::try_from(record.begin).ok()?;
+    let end = u64::try_from(record.end).ok()?;
+    let (chrom, lifted_begin, chain_id) = chain.lift_pos(&record.chromosome, begin)?;
+    let (chrom2, lifted_end, chain_id2) = chain.lift_pos(&record.chromosome2, end)?;
+
+    let mut result = record.clone();
+    result.chromosome = chrom_like(&chrom, &record.chromosome);
+    result.chromosome2 = chrom_like(&chrom2, &record.chromosome2);
+    let confidence = if matches!(record.sv_type, SvType::Bnd | SvType::Ins) {
+        result.begin = lifted_begin as i32;
+        result.end = lifted_end as i32;
+        1.0
+    } else {
+        if chrom != chrom2 || chain_id != chain_id2 {
+            return None;
+        }
+        result.begin = lifted_begin.min(lifted_end) as i32;
+        result.end = lifted_begin.max(lifted_end) as i32;
+        let len = (end + 1).saturating_sub(begin).max(1) as f32;
+        let lifted_len = (result.end - result.begin + 1) as f32;
+        let aligned = chain.aligned_bases(&record.chromosome, begin, end, chain_id) as f32 / len;
+        aligned.min(len / lifted_len).min(lifted_len / len)
+    };
+
+    Some(LiftResult {
+        record: result,
+        confidence,
+    })
+}
+
+/// Main entry point for `strucvars lift` sub command.
+pub fn run(common_args: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting `strucvars lift`");
+    tracing::info!("  common_args = {:?}", &common_args);
+    tracing::info!("  args = {:?}", &args);
+
+    tracing::info!("Loading chain file...");
+    let chain = read_chain(open_read_maybe_gz(&args.path_chain)?)?;
+    tracing::info!("... done loading chain file");
+
+    let records = read_inhouse_tsv(&args.path_input, args.genome_release_from)?;
+    let mut writer = open_inhouse_tsv_writer(&args.path_output, args.genome_release_to)?;
+    let mut report = args
+        .path_report
+        .as_ref()
+        .map(|path| -> Result<_, anyhow::Error> {
+            let mut report = open_write_maybe_bgzf(path)?;
+            writeln!(
+                report,
+                "chromosome\tbegin\tchromosome2\tend\tsv_type\t\
+                lifted_chromosome\tlifted_begin\tlifted_chromosome2\tlifted_end\tconfidence"
+            )?;
+            Ok(report)
+        })
+        .transpose()?;
+
+    let (mut count_written, mut count_unmapped) = (0, 0);
+    for record in &records {
+        let lifted = lift_record(&chain, record);
+        if let Some(report) = report.as_mut() {
+            let (lifted_coords, confidence) = match &lifted {
+                Some(lifted) => (
+                    format!(
+                        "{}\t{}\t{}\t{}",
+                        lifted.record.chromosome,
+                        lifted.record.begin,
+                        lifted.record.chromosome2,
+                        lifted.record.end
+                    ),
+                    format!("{:.3}", lifted.confidence),
+                ),
+                None => (".\t.\t.\t.".to_string(), ".".to_string()),
+            };
+            writeln!(
+                report,
+                "{}\t{}\t{}\t{}\t{:?}\t{}\t{}",
+                record.chromosome,
+                record.begin,
+                record.chromosome2,
+                record.end,
+                record.sv_type,
+                lifted_coords,
+                confidence
+            )?;
+        }
+        match lifted {
+            Some(lifted) if lifted.confidence >= args.min_confidence => {
+                writer.serialize(&lifted.record)?;
+                count_written += 1;
+            }
+            Some(_) => (),
+            None => count_unmapped += 1,
+        }
+    }
+    writer.flush()?;
+    if let Some(mut report) = report {
+        report.flush()?;
+    }
+
+    tracing::info!(
+        "lifted {} of {} records ({} could not be mapped, {} below confidence)",
+        count_written,
+        records.len(),
+        count_unmapped,
+        records.len() - count_written - count_unmapped
+    );
+    tracing::info!("... done - don't forget to convert to binary");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::annotate::strucvars::csq::interface::StrandOrientation;
+
+    use crate::strucvars::{aggregate::output::Record, query::schema::SvType};
+
+    /// Chain mapping chr1:1,000-2,000 and chr1:2,100-3,000 (0-based) to chr1
+    /// with an offset of 500, and chr2:1,000-2,000 to the reverse strand.
+    const CHAIN: &str = "\
+chain 1000 chr1 249250621 + 1000 3000 chr1 248956422 + 1500 3500 1
+1000 100 100
+900
+
+chain 1000 chr2 243199373 + 1000 2000 chr2 242193529 - 5000 6000 2
+1000
+";
+
+    fn record(chrom: &str, begin: i32, end: i32, sv_type: SvType) -> Record {
+        Record {
+            chromosome: chrom.into(),
+            begin,
+            chromosome2: chrom.into(),
+            end,
+            pe_orientation: StrandOrientation::ThreeToFive,
+            sv_type,
+            carriers: 1,
+            carriers_het: 1,
+            carriers_hom: 0,
+            carriers_hemi: 0,
+        }
+    }
+
+    #[rstest::rstest]
+    #[case("1", 1001, Some(("1".to_string(), 1501, 1)))]
+    #[case("chr1", 2000, Some(("1".to_string(), 2500, 1)))]
+    #[case("1", 2050, None)]
+    #[case("1", 2101, Some(("1".to_string(), 2601, 1)))]
+    #[case("2", 1001, Some(("2".to_string(), 242193529 - 5000, 2)))]
+    #[case("3", 1001, None)]
+    fn lift_pos(
+        #[case] chrom: &str,
+        #[case] pos: u64,
+        #[case] expected: Option<(String, u64, u64)>,
+    ) {
+        let chain = super::read_chain(CHAIN.as_bytes()).unwrap();
+
+        assert_eq!(chain.lift_pos(chrom, pos), expected);
+    }
+
+    #[test]
+    fn lift_record() -> Result<(), anyhow::Error> {
+        let chain = super::read_chain(CHAIN.as_bytes())?;
+
+        let lifted = super::lift_record(&chain, &record("chr1", 1101, 1900, SvType::Del)).unwrap();
+        assert_eq!(
+            (
+                lifted.record.chromosome.as_str(),
+                lifted.record.begin,
+                lifted.record.end
+            ),
+            ("chr1", 1601, 2400)
+        );
+        assert_eq!(lifted.confidence, 1.0);
+
+        // The gap between the blocks lowers the confidence.
+        let lifted = super::lift_record(&chain, &record("1", 1901, 2300, SvType::Del)).unwrap();
+        assert_eq!((lifted.record.begin, lifted.record.end), (2401, 2800));
+        assert_eq!(lifted.confidence, 0.75);
+
+        // Records on the reverse strand are flipped.
+        let lifted = super::lift_record(&chain, &record("2", 1101, 1200, SvType::Dup)).unwrap();
+        assert_eq!(
+            (lifted.record.begin, lifted.record.end),
+            (242193529 - 5000 - 199, 242193529 - 5000 - 100)
+        );
+
+        assert!(super::lift_record(&chain, &record("1", 500, 1200, SvType::Del)).is_none());
+
+        Ok(())
+    }
+}
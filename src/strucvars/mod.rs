@@ -1,4 +1,6 @@
 pub mod aggregate;
+pub mod comphet;
 pub mod ingest;
+pub mod lift;
 pub mod query;
 pub mod txt_to_bin;
@@ -12,11 +12,12 @@ use tracing::info;
 use crate::{
     common::{trace_rss_now, GenomeRelease, CHROMS},
     pbs::varfish::v1::strucvars::bgdb,
+    strucvars::txt_to_bin::{info::check_schema_version, vardbs},
 };
 
 use super::{
     schema::ChromRange,
-    schema::{CaseQuery, StructuralVariant, SvType},
+    schema::{CaseQuery, StructuralVariant, SvSubType, SvType},
 };
 
 pub trait BeginEnd {
@@ -83,11 +84,27 @@ impl BgDb {
         slack_bnd: i32,
         sv: &StructuralVariant,
     ) -> u32 {
+        self.overlapping_records(chrom_map, enabled, min_overlap, slack_ins, slack_bnd, sv)
+            .iter()
+            .map(|record| record.count)
+            .sum::<u32>()
+    }
+
+    /// Return the records matching `sv` with the given overlap criteria.
+    pub fn overlapping_records(
+        &self,
+        chrom_map: &IndexMap<String, usize>,
+        enabled: bool,
+        min_overlap: Option<f32>,
+        slack_ins: i32,
+        slack_bnd: i32,
+        sv: &StructuralVariant,
+    ) -> Vec<&BgDbRecord> {
         let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
         let range = if sv.sv_type == SvType::Ins {
-            (sv.pos - slack_ins)..(sv.pos + slack_ins)
+            sv.pos_window(slack_ins)
         } else if sv.sv_type == SvType::Bnd {
-            (sv.pos - slack_bnd)..(sv.pos + slack_bnd)
+            sv.pos_window(slack_bnd)
         } else {
             (sv.pos - 1)..sv.end
         };
@@ -105,8 +122,7 @@ impl BgDb {
                             (reciprocal_overlap(*record, &range)) >= min_overlap
                         }))
             })
-            .map(|record| record.count)
-            .sum::<u32>()
+            .collect()
     }
 }
 
@@ -121,6 +137,24 @@ pub struct BgDbRecord {
     pub sv_type: SvType,
     /// Count associated with the record.
     pub count: u32,
+    /// Allele count, if available.
+    pub ac: Option<u32>,
+    /// Allele number, if available.
+    pub an: Option<u32>,
+    /// Number of homozygous alternative carriers, if available.
+    pub n_homalt: Option<u32>,
+    /// Mobile element insertion sub type, if any.
+    pub mei_type: Option<SvSubType>,
+}
+
+impl BgDbRecord {
+    /// Return the allele frequency, if available.
+    pub fn af(&self) -> Option<f32> {
+        match (self.ac, self.an) {
+            (Some(ac), Some(an)) if an > 0 => Some(ac as f32 / an as f32),
+            _ => None,
+        }
+    }
 }
 
 impl BeginEnd for BgDbRecord {
@@ -149,6 +183,7 @@ pub fn load_bg_db_records(path: &Path) -> Result<BgDb, anyhow::Error> {
         std::fs::read(path).map_err(|e| anyhow::anyhow!("error reading {:?}: {}", &path, e))?;
     let bg_db = bgdb::BackgroundDatabase::decode(std::io::Cursor::new(fcontents))
         .map_err(|e| anyhow::anyhow!("error decoding {:?}: {}", &path, e))?;
+    check_schema_version(path, bg_db.provenance.as_ref(), vardbs::SCHEMA_VERSION)?;
     let record_count = bg_db.records.len();
 
     for record in bg_db.records.into_iter() {
@@ -179,6 +214,16 @@ pub fn load_bg_db_records(path: &Path) -> Result<BgDb, anyhow::Error> {
                 bgdb::SvType::Cnv => SvType::Cnv,
             },
             count: record.count,
+            ac: record.ac,
+            an: record.an,
+            n_homalt: record.n_homalt,
+            mei_type: match bgdb::MeiType::try_from(record.mei_type).expect("invalid mei_type") {
+                bgdb::MeiType::Unspecified => None,
+                bgdb::MeiType::Me => Some(SvSubType::InsMe),
+                bgdb::MeiType::Alu => Some(SvSubType::InsMeAlu),
+                bgdb::MeiType::Line1 => Some(SvSubType::InsMeL1),
+                bgdb::MeiType::Sva => Some(SvSubType::InsMeSva),
+            },
         });
     }
     tracing::debug!(
@@ -232,6 +277,17 @@ pub struct BgDbOverlaps {
     pub gnomad_genomes: u32,
     pub gnomad_exomes: u32,
     pub inhouse: u32,
+    /// Maximal allele frequency of the overlapping gnomAD genomes records, if available.
+    pub gnomad_genomes_af: Option<f32>,
+    /// Maximal number of hom. alt. carriers of the overlapping gnomAD genomes records, if
+    /// available.
+    pub gnomad_genomes_n_homalt: Option<u32>,
+    /// Number of carriers of overlapping gnomAD genomes records of the same mobile element
+    /// family, only computed for mobile element insertions.
+    pub gnomad_genomes_mei: u32,
+    /// Maximal allele frequency of overlapping gnomAD genomes records of the same mobile
+    /// element family, if available.
+    pub gnomad_genomes_mei_af: Option<f32>,
 }
 
 impl BgDbBundle {
@@ -283,12 +339,45 @@ impl BgDbBundle {
         slack_ins: i32,
         slack_bnd: i32,
     ) -> BgDbOverlaps {
+        let sv_type_overlap = &query.svdb_sv_type_overlap;
+        let slack_ins = sv_type_overlap.slack_ins.unwrap_or(slack_ins);
+        let slack_bnd = sv_type_overlap.slack_bnd.unwrap_or(slack_bnd);
+        let min_overlap = |db_min_overlap: Option<f32>| {
+            sv_type_overlap.min_overlap(sv.sv_type).or(db_min_overlap)
+        };
+        let gnomad_genomes_records = self
+            .gnomad_genomes
+            .as_ref()
+            .map(|gnomad_genomes| {
+                gnomad_genomes.overlapping_records(
+                    chrom_map,
+                    query.svdb_gnomad_genomes_enabled,
+                    min_overlap(query.svdb_gnomad_genomes_min_overlap),
+                    slack_ins,
+                    slack_bnd,
+                    sv,
+                )
+            })
+            .unwrap_or_default();
+        let gnomad_genomes_mei_records = if sv.sv_sub_type.is_ins_me() {
+            gnomad_genomes_records
+                .iter()
+                .filter(|record| {
+                    record
+                        .mei_type
+                        .is_some_and(|mei_type| sv.sv_sub_type.is_compatible_me(mei_type))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
         BgDbOverlaps {
             dbvar: self.dbvar.as_ref().map_or(0, |dbvar| {
                 dbvar.count_overlaps(
                     chrom_map,
                     query.svdb_dbvar_enabled,
-                    query.svdb_dbvar_min_overlap,
+                    min_overlap(query.svdb_dbvar_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
@@ -298,7 +387,7 @@ impl BgDbBundle {
                 dgv.count_overlaps(
                     chrom_map,
                     query.svdb_dgv_enabled,
-                    query.svdb_dgv_min_overlap,
+                    min_overlap(query.svdb_dgv_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
@@ -308,7 +397,7 @@ impl BgDbBundle {
                 dgv_gs.count_overlaps(
                     chrom_map,
                     query.svdb_dgv_gs_enabled,
-                    query.svdb_dgv_gs_min_overlap,
+                    min_overlap(query.svdb_dgv_gs_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
@@ -318,7 +407,7 @@ impl BgDbBundle {
                 g1k.count_overlaps(
                     chrom_map,
                     query.svdb_g1k_enabled,
-                    query.svdb_g1k_min_overlap,
+                    min_overlap(query.svdb_g1k_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
@@ -328,27 +417,37 @@ impl BgDbBundle {
                 gnomad_exomes.count_overlaps(
                     chrom_map,
                     query.svdb_gnomad_exomes_enabled,
-                    query.svdb_gnomad_exomes_min_overlap,
-                    slack_ins,
-                    slack_bnd,
-                    sv,
-                )
-            }),
-            gnomad_genomes: self.gnomad_genomes.as_ref().map_or(0, |gnomad_genomes| {
-                gnomad_genomes.count_overlaps(
-                    chrom_map,
-                    query.svdb_gnomad_genomes_enabled,
-                    query.svdb_gnomad_genomes_min_overlap,
+                    min_overlap(query.svdb_gnomad_exomes_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
                 )
             }),
+            gnomad_genomes: gnomad_genomes_records
+                .iter()
+                .map(|record| record.count)
+                .sum(),
+            gnomad_genomes_af: gnomad_genomes_records
+                .iter()
+                .filter_map(|record| record.af())
+                .reduce(f32::max),
+            gnomad_genomes_n_homalt: gnomad_genomes_records
+                .iter()
+                .filter_map(|record| record.n_homalt)
+                .max(),
+            gnomad_genomes_mei: gnomad_genomes_mei_records
+                .iter()
+                .map(|record| record.count)
+                .sum(),
+            gnomad_genomes_mei_af: gnomad_genomes_mei_records
+                .iter()
+                .filter_map(|record| record.af())
+                .reduce(f32::max),
             inhouse: self.inhouse.as_ref().map_or(0, |inhouse| {
                 inhouse.count_overlaps(
                     chrom_map,
                     query.svdb_inhouse_enabled,
-                    query.svdb_inhouse_min_overlap,
+                    min_overlap(query.svdb_inhouse_min_overlap),
                     slack_ins,
                     slack_bnd,
                     sv,
@@ -6,7 +6,11 @@ use prost::Message;
 use thousands::Separable;
 use tracing::{info, warn};
 
-use crate::common::{reciprocal_overlap, GenomeRelease, CHROMS};
+use crate::{
+    common::{reciprocal_overlap, GenomeRelease, CHROMS},
+    pbs::varfish::v1::strucvars::clinvar,
+    strucvars::txt_to_bin::info::check_schema_version,
+};
 
 use super::{
     schema::ChromRange,
@@ -16,6 +20,20 @@ use super::{
 /// Alias for the interval tree that we use.
 type IntervalTree = ArrayBackedIntervalTree<i32, u32>;
 
+/// Return the protobuf pathogenicity value for the minimal pathogenicity `min_patho`.
+fn min_patho_pb(min_patho: Option<Pathogenicity>) -> i32 {
+    clinvar::Pathogenicity::from(min_patho.unwrap_or(Pathogenicity::Benign)) as i32
+}
+
+/// A ClinVar SV overlapping with a structural variant.
+#[derive(Debug, Clone, Copy)]
+pub struct ClinvarSvMatch {
+    /// The matching ClinVar record.
+    pub record: clinvar::SvRecord,
+    /// The reciprocal overlap of the record with the structural variant.
+    pub overlap: f32,
+}
+
 /// Code for known Clinvar SV database.
 ///
 /// NB that the records have 1-based coordinates whereas the trees use 0-based coordinates.
@@ -44,20 +62,18 @@ impl ClinvarSv {
             .find(range)
             .iter()
             .map(|e| self.records[chrom_idx][*e.data() as usize])
-            .filter(|record| {
-                record.pathogenicity >= min_patho.unwrap_or(Pathogenicity::Benign) as i32
-            })
+            .filter(|record| record.pathogenicity >= min_patho_pb(min_patho))
             .collect()
     }
 
-    /// Returns the overlapping RCVs
-    pub fn overlapping_rcvs(
+    /// Returns the overlapping records with their reciprocal overlap.
+    pub fn overlapping_records(
         &self,
         sv: &StructuralVariant,
         chrom_map: &IndexMap<String, usize>,
         min_patho: Option<Pathogenicity>,
         min_overlap: Option<f32>,
-    ) -> Vec<u32> {
+    ) -> Vec<ClinvarSvMatch> {
         if sv.sv_type == SvType::Ins || sv.sv_type == SvType::Bnd {
             return Vec::new();
         }
@@ -68,17 +84,32 @@ impl ClinvarSv {
         self.trees[chrom_idx]
             .find(range)
             .into_iter()
-            .map(|e| &self.records[chrom_idx][*e.data() as usize])
-            .filter(|record| {
-                min_overlap.map_or(true, |min_overlap| {
-                    reciprocal_overlap((record.start - 1)..record.stop, (sv.pos - 1)..sv.end)
-                        >= min_overlap
-                })
+            .map(|e| {
+                let record = self.records[chrom_idx][*e.data() as usize];
+                ClinvarSvMatch {
+                    record,
+                    overlap: reciprocal_overlap(
+                        (record.start - 1)..record.stop,
+                        (sv.pos - 1)..sv.end,
+                    ),
+                }
             })
-            .filter(|record| {
-                record.pathogenicity >= min_patho.unwrap_or(Pathogenicity::Benign) as i32
-            })
-            .map(|record| record.rcv)
+            .filter(|m| min_overlap.map_or(true, |min_overlap| m.overlap >= min_overlap))
+            .filter(|m| m.record.pathogenicity >= min_patho_pb(min_patho))
+            .collect()
+    }
+
+    /// Returns the overlapping RCVs
+    pub fn overlapping_rcvs(
+        &self,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+        min_patho: Option<Pathogenicity>,
+        min_overlap: Option<f32>,
+    ) -> Vec<u32> {
+        self.overlapping_records(sv, chrom_map, min_patho, min_overlap)
+            .into_iter()
+            .map(|m| m.record.rcv)
             .collect()
     }
 }
@@ -106,6 +137,11 @@ pub fn load_clinvar_sv(
         std::io::Cursor::new(fcontents),
     )
     .map_err(|e| anyhow::anyhow!("error decoding {:?}: {}", &path, e))?;
+    check_schema_version(
+        &path,
+        bg_db.provenance.as_ref(),
+        crate::strucvars::txt_to_bin::clinvar::SCHEMA_VERSION,
+    )?;
 
     let mut total_count = 0;
     for record in bg_db.records.into_iter() {
@@ -0,0 +1,238 @@
+//! Code for annotating disrupted protein domains.
+//!
+//! Protein domains (e.g., from Pfam or InterPro) are projected onto the genome
+//! and stored as BED-style records with the HGNC ID of the gene.  Deletions,
+//! duplications, and CNVs disrupt all domains that they overlap with while for
+//! the other SV types, only domains containing a breakpoint are disrupted.
+
+use std::path::Path;
+
+use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+use indexmap::IndexMap;
+use mehari::common::io::std::open_read_maybe_gz;
+use serde::Serialize;
+use tracing::info;
+
+use crate::common::{build_chrom_map, GenomeRelease, CHROMS};
+
+use super::schema::{StructuralVariant, SvType};
+
+/// Alias for the interval tree that we use.
+type IntervalTree = ArrayBackedIntervalTree<i32, u32>;
+
+/// Information to store for a protein domain region.
+#[derive(Default, Debug, Serialize, Clone, PartialEq)]
+pub struct Record {
+    /// 0-based begin position.
+    pub begin: i32,
+    /// End position.
+    pub end: i32,
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// Domain accession, e.g., `PF00069` or `IPR000719`.
+    pub domain_id: String,
+    /// Domain name, e.g., `Protein kinase domain`.
+    pub name: String,
+}
+
+/// Code for protein domain overlappers.
+#[derive(Default, Debug)]
+pub struct ProteinDomainDb {
+    /// Records, stored by chromosome.
+    pub records: Vec<Vec<Record>>,
+    /// Interval trees, stored by chromosome.
+    pub trees: Vec<IntervalTree>,
+}
+
+impl ProteinDomainDb {
+    /// Return the records in the 0-based half-open `range` on chromosome `chrom_idx`.
+    fn find(&self, chrom_idx: usize, range: std::ops::Range<i32>) -> Vec<&Record> {
+        self.trees[chrom_idx]
+            .find(range)
+            .iter()
+            .map(|e| &self.records[chrom_idx][*e.data() as usize])
+            .collect()
+    }
+
+    /// Return the domains disrupted by `sv`, sorted by HGNC ID and domain.
+    pub fn disrupted_domains(
+        &self,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+    ) -> Vec<Record> {
+        if self.trees.is_empty() {
+            return Vec::new();
+        }
+
+        let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
+        let mut result = match sv.sv_type {
+            SvType::Del | SvType::Dup | SvType::Cnv => {
+                self.find(chrom_idx, sv.pos.saturating_sub(1)..sv.end)
+            }
+            SvType::Inv => {
+                let mut records = self.find(chrom_idx, sv.pos.saturating_sub(1)..sv.pos);
+                records.extend(self.find(chrom_idx, sv.end.saturating_sub(1)..sv.end));
+                records
+            }
+            SvType::Ins | SvType::Bnd => self.find(chrom_idx, sv.pos.saturating_sub(1)..sv.pos),
+        }
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+        result.sort_by(|a, b| (&a.hgnc_id, &a.domain_id).cmp(&(&b.hgnc_id, &b.domain_id)));
+        result.dedup_by(|a, b| a.hgnc_id == b.hgnc_id && a.domain_id == b.domain_id);
+        result
+    }
+}
+
+/// Module with code for loading data from input.
+mod input {
+    use serde::Deserialize;
+
+    /// Type for record structs from input.
+    #[derive(Deserialize, Debug)]
+    pub struct Record {
+        /// Chromosome name
+        pub chrom: String,
+        /// 0-based begin position from BED.
+        pub begin: i32,
+        /// 0-based end position from BED.
+        pub end: i32,
+        /// HGNC ID of the gene.
+        pub hgnc_id: String,
+        /// Domain accession.
+        pub domain_id: String,
+        /// Domain name.
+        pub name: String,
+    }
+}
+
+#[tracing::instrument]
+fn load_protein_domain_db_records(path: &Path) -> Result<ProteinDomainDb, anyhow::Error> {
+    tracing::debug!("loading protein domain records from {:?}...", path);
+    let chrom_map = build_chrom_map();
+
+    let mut result = ProteinDomainDb::default();
+    for _ in CHROMS {
+        result.records.push(Vec::new());
+        result.trees.push(IntervalTree::new());
+    }
+
+    // Setup CSV reader for BED file - header is written as comment and must be
+    // ignored.
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false) // BED has no header
+        .comment(Some(b'#'))
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: input::Record = record?;
+        let chrom_idx = *chrom_map.get(&record.chrom).expect("invalid chromosome");
+
+        let key = record.begin..record.end;
+        result.trees[chrom_idx].insert(key, result.records[chrom_idx].len() as u32);
+        result.records[chrom_idx].push(Record {
+            begin: record.begin,
+            end: record.end,
+            hgnc_id: record.hgnc_id,
+            domain_id: record.domain_id,
+            name: record.name,
+        });
+
+        total_count += 1;
+    }
+    result.trees.iter_mut().for_each(|tree| tree.index());
+    tracing::debug!(
+        "... done loading {} records and building trees",
+        total_count
+    );
+
+    Ok(result)
+}
+
+/// Load the protein domains from the gene database, if present.
+#[tracing::instrument]
+pub fn load_protein_domain_db(
+    path_db: &str,
+    genome_release: GenomeRelease,
+) -> Result<ProteinDomainDb, anyhow::Error> {
+    info!("Loading protein domain db");
+    let path = Path::new(path_db).join(format!("{}/genes/protein_domains.bed", genome_release));
+    if path.exists() {
+        load_protein_domain_db_records(&path)
+    } else {
+        info!("  no protein domains at {:?}", &path);
+        Ok(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+    use mehari::annotate::strucvars::csq::interface::StrandOrientation;
+
+    use super::{ProteinDomainDb, Record};
+    use crate::{
+        common::{build_chrom_map, CHROMS},
+        strucvars::query::schema::{StructuralVariant, SvSubType, SvType},
+    };
+
+    fn protein_domain_db() -> ProteinDomainDb {
+        let mut db = ProteinDomainDb {
+            records: vec![Vec::new(); CHROMS.len()],
+            trees: CHROMS.iter().map(|_| super::IntervalTree::new()).collect(),
+        };
+        let chrom_idx = *build_chrom_map().get("1").unwrap();
+        for (idx, (begin, end, domain_id)) in [(1000, 1100, "PF00001"), (2000, 2100, "PF00002")]
+            .into_iter()
+            .enumerate()
+        {
+            db.trees[chrom_idx].insert(begin..end, idx as u32);
+            db.records[chrom_idx].push(Record {
+                begin,
+                end,
+                hgnc_id: "HGNC:1".into(),
+                domain_id: domain_id.into(),
+                name: domain_id.into(),
+            });
+        }
+        db.trees.iter_mut().for_each(|tree| tree.index());
+        db
+    }
+
+    #[rstest::rstest]
+    #[case::del_both(SvType::Del, 1051, 2050, vec!["PF00001", "PF00002"])]
+    #[case::del_none(SvType::Del, 1201, 1900, vec![])]
+    #[case::inv_spanning(SvType::Inv, 501, 3000, vec![])]
+    #[case::inv_breakpoint(SvType::Inv, 501, 2050, vec!["PF00002"])]
+    #[case::bnd(SvType::Bnd, 1051, 1051, vec!["PF00001"])]
+    fn disrupted_domains(
+        #[case] sv_type: SvType,
+        #[case] pos: i32,
+        #[case] end: i32,
+        #[case] expected: Vec<&str>,
+    ) {
+        let sv = StructuralVariant {
+            chrom: "1".to_owned(),
+            pos,
+            sv_type,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end,
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            callers: Vec::new(),
+            call_info: IndexMap::new(),
+        };
+
+        let domain_ids = protein_domain_db()
+            .disrupted_domains(&sv, &build_chrom_map())
+            .into_iter()
+            .map(|record| record.domain_id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(domain_ids, expected);
+    }
+}
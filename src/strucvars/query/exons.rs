@@ -0,0 +1,167 @@
+//! Per-exon impact annotation of deletions and duplications.
+//!
+//! For each transcript overlapping a linear CNV, the affected exons are reported
+//! with their number in transcript direction and the impact is classified as
+//! affecting the complete gene, a single exon, multiple exons, or only introns.
+
+use std::collections::HashMap;
+
+use mehari::{
+    annotate::seqvars::provider::TxIntervalTrees,
+    pbs::txs::{GenomeAlignment, Strand, TxSeqDatabase},
+};
+
+use super::{genes::GeneDb, resolve_hgvs_id, schema::StructuralVariant, Gene};
+
+/// Impact of a CNV on the exons of a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExonImpact {
+    /// All exons and both transcript ends are affected.
+    CompleteGene,
+    /// More than one but not all exons are affected.
+    Partial,
+    /// Exactly one exon is affected.
+    SingleExon,
+    /// No exon is affected, the CNV is within an intron.
+    IntronicOnly,
+}
+
+/// Affected exons of one transcript.
+#[derive(Debug)]
+pub(crate) struct TranscriptExonImpact {
+    /// The gene of the transcript.
+    pub gene: Gene,
+    /// Accession of the transcript.
+    pub tx_ac: String,
+    /// Number of exons of the transcript.
+    pub exon_count: u32,
+    /// The 1-based numbers of the affected exons in transcript direction.
+    pub affected_exons: Vec<u32>,
+    /// The impact classification.
+    pub impact: ExonImpact,
+}
+
+/// Return the impact and the affected exon numbers of the CNV at the 1-based
+/// `pos..=end` on the transcript with `alignment`, or `None` if there is no
+/// overlap with the transcript.
+fn exon_impact(alignment: &GenomeAlignment, pos: i32, end: i32) -> Option<(ExonImpact, Vec<u32>)> {
+    let tx_start = alignment.exons.iter().map(|exon| exon.alt_start_i).min()?;
+    let tx_end = alignment.exons.iter().map(|exon| exon.alt_end_i).max()?;
+    if end < tx_start || tx_end < pos {
+        return None;
+    }
+
+    // Number exons by genomic position and flip for the minus strand.
+    let mut exons = alignment
+        .exons
+        .iter()
+        .map(|exon| (exon.alt_start_i, exon.alt_end_i))
+        .collect::<Vec<_>>();
+    exons.sort();
+    let exon_count = exons.len() as u32;
+    let mut affected_exons = exons
+        .iter()
+        .enumerate()
+        .filter(|(_, (start, stop))| *start <= end && pos <= *stop)
+        .map(|(idx, _)| {
+            if alignment.strand == Strand::Minus as i32 {
+                exon_count - idx as u32
+            } else {
+                idx as u32 + 1
+            }
+        })
+        .collect::<Vec<_>>();
+    affected_exons.sort();
+
+    let impact = if pos <= tx_start && tx_end <= end {
+        ExonImpact::CompleteGene
+    } else {
+        match affected_exons.len() {
+            0 => ExonImpact::IntronicOnly,
+            1 => ExonImpact::SingleExon,
+            _ => ExonImpact::Partial,
+        }
+    };
+    Some((impact, affected_exons))
+}
+
+/// Compute the affected exons of all transcripts overlapping the linear `sv`.
+pub(crate) fn exon_impacts(
+    sv: &StructuralVariant,
+    mehari_tx_db: &TxSeqDatabase,
+    mehari_tx_idx: &TxIntervalTrees,
+    gene_db: &GeneDb,
+    chrom_to_acc: &HashMap<String, String>,
+) -> Vec<TranscriptExonImpact> {
+    let tx_db = mehari_tx_db
+        .tx_db
+        .as_ref()
+        .expect("transcripts must be present");
+    let Some(idx) = chrom_to_acc
+        .get(&annonars::common::cli::canonicalize(&sv.chrom))
+        .and_then(|acc| mehari_tx_idx.contig_to_idx.get(acc))
+    else {
+        return Vec::new();
+    };
+
+    let mut result = mehari_tx_idx.trees[*idx]
+        .find((sv.pos - 1)..sv.end)
+        .iter()
+        .map(|it| &tx_db.transcripts[*it.data() as usize])
+        .filter(|tx| !tx.genome_alignments.is_empty())
+        .filter_map(|tx| {
+            let (impact, affected_exons) = exon_impact(&tx.genome_alignments[0], sv.pos, sv.end)?;
+            Some(TranscriptExonImpact {
+                gene: resolve_hgvs_id(gene_db, &tx.gene_id)
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default(),
+                tx_ac: tx.id.clone(),
+                exon_count: tx.genome_alignments[0].exons.len() as u32,
+                affected_exons,
+                impact,
+            })
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| a.tx_ac.cmp(&b.tx_ac));
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::pbs::txs::{ExonAlignment, GenomeAlignment, Strand};
+
+    use super::ExonImpact;
+
+    /// Alignment with three exons.
+    fn alignment(strand: Strand) -> GenomeAlignment {
+        GenomeAlignment {
+            strand: strand as i32,
+            exons: [(101, 120), (201, 211), (301, 320)]
+                .into_iter()
+                .map(|(alt_start_i, alt_end_i)| ExonAlignment {
+                    alt_start_i,
+                    alt_end_i,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::complete(Strand::Plus, 50, 400, Some((ExonImpact::CompleteGene, vec![1, 2, 3])))]
+    #[case::partial(Strand::Plus, 150, 400, Some((ExonImpact::Partial, vec![2, 3])))]
+    #[case::single_plus(Strand::Plus, 150, 250, Some((ExonImpact::SingleExon, vec![2])))]
+    #[case::single_minus(Strand::Minus, 50, 150, Some((ExonImpact::SingleExon, vec![3])))]
+    #[case::intronic(Strand::Plus, 130, 190, Some((ExonImpact::IntronicOnly, vec![])))]
+    #[case::outside(Strand::Plus, 400, 500, None)]
+    fn exon_impact(
+        #[case] strand: Strand,
+        #[case] pos: i32,
+        #[case] end: i32,
+        #[case] expected: Option<(ExonImpact, Vec<u32>)>,
+    ) {
+        assert_eq!(super::exon_impact(&alignment(strand), pos, end), expected);
+    }
+}
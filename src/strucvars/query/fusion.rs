@@ -0,0 +1,246 @@
+//! Annotation of candidate gene fusions at break-ends.
+//!
+//! A break-end joins the kept side of its first position to the kept side of its
+//! second position as given by the strand orientation.  A transcript at a position
+//! is the 5' partner of the fusion if the kept side contains its 5' end and the 3'
+//! partner otherwise.  The fusion is considered to be in frame if both positions are
+//! in coding introns and the number of upstream coding bases of both partners has
+//! the same phase.
+
+use std::collections::{BTreeMap, HashMap};
+
+use mehari::{
+    annotate::{seqvars::provider::TxIntervalTrees, strucvars::csq::interface::StrandOrientation},
+    pbs::txs::{GenomeAlignment, Strand, Transcript, TxSeqDatabase},
+};
+
+use super::{genes::GeneDb, resolve_hgvs_id, schema::StructuralVariant, Gene};
+
+/// A candidate gene fusion from a break-end.
+#[derive(Debug, Default)]
+pub(crate) struct FusionCandidate {
+    /// The gene at the 5' end of the fusion.
+    pub gene_5p: Gene,
+    /// The gene at the 3' end of the fusion.
+    pub gene_3p: Gene,
+    /// Whether any transcript pair of the two genes yields an in-frame fusion.
+    pub in_frame: bool,
+}
+
+/// Role of a transcript in a fusion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Partner {
+    /// The transcript contributes its 5' end.
+    FivePrime,
+    /// The transcript contributes its 3' end.
+    ThreePrime,
+}
+
+/// Return for both positions of a break-end whether the sequence left of the position is kept.
+fn kept_left(orientation: StrandOrientation) -> Option<(bool, bool)> {
+    match orientation {
+        StrandOrientation::ThreeToFive => Some((true, false)),
+        StrandOrientation::FiveToThree => Some((false, true)),
+        StrandOrientation::ThreeToThree => Some((true, true)),
+        StrandOrientation::FiveToFive => Some((false, false)),
+        _ => None,
+    }
+}
+
+/// Return the role of the transcript with `alignment` if the side left of the break-end is
+/// kept if `keeps_left`.
+fn partner(alignment: &GenomeAlignment, keeps_left: bool) -> Partner {
+    if keeps_left == (alignment.strand == Strand::Plus as i32) {
+        Partner::FivePrime
+    } else {
+        Partner::ThreePrime
+    }
+}
+
+/// Return the phase of the coding bases upstream of `pos` in transcript direction if `pos`
+/// is in an intron between two coding exons.
+fn coding_phase(alignment: &GenomeAlignment, pos: i32) -> Option<i32> {
+    let (cds_start, cds_end) = (alignment.cds_start?, alignment.cds_end?);
+    if alignment
+        .exons
+        .iter()
+        .any(|exon| exon.alt_start_i <= pos && pos <= exon.alt_end_i)
+    {
+        return None;
+    }
+
+    let coding_bases = |begin: i32, end: i32| (end.min(cds_end) - begin.max(cds_start) + 1).max(0);
+    let left = alignment
+        .exons
+        .iter()
+        .filter(|exon| exon.alt_end_i < pos)
+        .map(|exon| coding_bases(exon.alt_start_i, exon.alt_end_i))
+        .sum::<i32>();
+    let right = alignment
+        .exons
+        .iter()
+        .filter(|exon| exon.alt_start_i > pos)
+        .map(|exon| coding_bases(exon.alt_start_i, exon.alt_end_i))
+        .sum::<i32>();
+    if left == 0 || right == 0 {
+        None
+    } else if alignment.strand == Strand::Plus as i32 {
+        Some(left % 3)
+    } else {
+        Some(right % 3)
+    }
+}
+
+/// Return the transcripts overlapping with the 1-based position `pos` on `chrom`.
+fn overlapping_txs<'a>(
+    mehari_tx_db: &'a TxSeqDatabase,
+    mehari_tx_idx: &TxIntervalTrees,
+    chrom_to_acc: &HashMap<String, String>,
+    chrom: &str,
+    pos: i32,
+) -> Vec<&'a Transcript> {
+    let tx_db = mehari_tx_db
+        .tx_db
+        .as_ref()
+        .expect("transcripts must be present");
+    let Some(idx) = chrom_to_acc
+        .get(&annonars::common::cli::canonicalize(chrom))
+        .and_then(|acc| mehari_tx_idx.contig_to_idx.get(acc))
+    else {
+        return Vec::new();
+    };
+    mehari_tx_idx.trees[*idx]
+        .find((pos - 1)..pos)
+        .iter()
+        .map(|it| &tx_db.transcripts[*it.data() as usize])
+        .filter(|tx| !tx.genome_alignments.is_empty())
+        .collect()
+}
+
+/// Compute the candidate gene fusions for the break-end `sv`.
+pub(crate) fn fusion_candidates(
+    sv: &StructuralVariant,
+    mehari_tx_db: &TxSeqDatabase,
+    mehari_tx_idx: &TxIntervalTrees,
+    gene_db: &GeneDb,
+    chrom_to_acc: &HashMap<String, String>,
+) -> Vec<FusionCandidate> {
+    let Some((keeps_left, keeps_left2)) = kept_left(sv.strand_orientation) else {
+        return Vec::new();
+    };
+    let txs = overlapping_txs(mehari_tx_db, mehari_tx_idx, chrom_to_acc, &sv.chrom, sv.pos);
+    let txs2 = overlapping_txs(
+        mehari_tx_db,
+        mehari_tx_idx,
+        chrom_to_acc,
+        sv.chrom2.as_ref().unwrap_or(&sv.chrom),
+        sv.end,
+    );
+
+    // Whether any transcript pair is in frame, by HGNC IDs of the 5' and 3' gene.
+    let mut in_frame_by_genes: BTreeMap<(String, String), bool> = BTreeMap::new();
+    for tx in &txs {
+        let alignment = &tx.genome_alignments[0];
+        for tx2 in &txs2 {
+            let alignment2 = &tx2.genome_alignments[0];
+            if tx.gene_id == tx2.gene_id {
+                continue;
+            }
+            let (tx_5p, phase_5p, tx_3p, phase_3p) = match (
+                partner(alignment, keeps_left),
+                partner(alignment2, keeps_left2),
+            ) {
+                (Partner::FivePrime, Partner::ThreePrime) => (
+                    tx,
+                    coding_phase(alignment, sv.pos),
+                    tx2,
+                    coding_phase(alignment2, sv.end),
+                ),
+                (Partner::ThreePrime, Partner::FivePrime) => (
+                    tx2,
+                    coding_phase(alignment2, sv.end),
+                    tx,
+                    coding_phase(alignment, sv.pos),
+                ),
+                _ => continue,
+            };
+            let in_frame = matches!((phase_5p, phase_3p), (Some(a), Some(b)) if a == b);
+            *in_frame_by_genes
+                .entry((tx_5p.gene_id.clone(), tx_3p.gene_id.clone()))
+                .or_default() |= in_frame;
+        }
+    }
+
+    in_frame_by_genes
+        .into_iter()
+        .map(|((hgnc_5p, hgnc_3p), in_frame)| FusionCandidate {
+            gene_5p: resolve_hgvs_id(gene_db, &hgnc_5p)
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            gene_3p: resolve_hgvs_id(gene_db, &hgnc_3p)
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            in_frame,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use mehari::{
+        annotate::strucvars::csq::interface::StrandOrientation,
+        pbs::txs::{ExonAlignment, GenomeAlignment, Strand},
+    };
+
+    use super::Partner;
+
+    /// Alignment with three exons of which the CDS covers 10 bases of the first,
+    /// all 11 of the second, and 10 of the third.
+    fn alignment(strand: Strand) -> GenomeAlignment {
+        GenomeAlignment {
+            strand: strand as i32,
+            cds_start: Some(111),
+            cds_end: Some(310),
+            exons: [(101, 120), (201, 211), (301, 320)]
+                .into_iter()
+                .map(|(alt_start_i, alt_end_i)| ExonAlignment {
+                    alt_start_i,
+                    alt_end_i,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::plus_first_intron(Strand::Plus, 150, Some(1))]
+    #[case::plus_second_intron(Strand::Plus, 250, Some(0))]
+    #[case::minus_first_intron(Strand::Minus, 150, Some(0))]
+    #[case::minus_second_intron(Strand::Minus, 250, Some(1))]
+    #[case::exon(Strand::Plus, 205, None)]
+    #[case::upstream(Strand::Plus, 50, None)]
+    fn coding_phase(#[case] strand: Strand, #[case] pos: i32, #[case] expected: Option<i32>) {
+        assert_eq!(super::coding_phase(&alignment(strand), pos), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(Strand::Plus, true, Partner::FivePrime)]
+    #[case(Strand::Plus, false, Partner::ThreePrime)]
+    #[case(Strand::Minus, true, Partner::ThreePrime)]
+    #[case(Strand::Minus, false, Partner::FivePrime)]
+    fn partner(#[case] strand: Strand, #[case] keeps_left: bool, #[case] expected: Partner) {
+        assert_eq!(super::partner(&alignment(strand), keeps_left), expected);
+    }
+
+    #[test]
+    fn kept_left() {
+        assert_eq!(
+            super::kept_left(StrandOrientation::ThreeToFive),
+            Some((true, false))
+        );
+        assert_eq!(super::kept_left(StrandOrientation::NotApplicable), None);
+    }
+}
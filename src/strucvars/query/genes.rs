@@ -1,13 +1,23 @@
 //! Code for supporting annotation with overlapping genes.
 
-use std::{collections::HashSet, path::Path, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::Instant,
+};
 
 use mehari::common::io::std::open_read_maybe_gz;
 use prost::Message;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use crate::{common::GenomeRelease, pbs};
+use crate::{
+    common::{GenomeRelease, Genotype as CallGenotype},
+    pbs,
+    strucvars::txt_to_bin::{info::check_schema_version, xlink},
+};
+
+use super::domains::{load_protein_domain_db, ProteinDomainDb};
 
 /// Information to store for the interlink table.
 #[derive(Default, Debug)]
@@ -43,6 +53,7 @@ fn load_xlink_db(path: &Path) -> Result<XlinkDb, anyhow::Error> {
     let xlink_db =
         pbs::varfish::v1::strucvars::bgdb::XlinkDatabase::decode(std::io::Cursor::new(fcontents))
             .map_err(|e| anyhow::anyhow!("error decoding {:?}: {}", &path, e))?;
+    check_schema_version(path, xlink_db.provenance.as_ref(), xlink::SCHEMA_VERSION)?;
 
     let mut total_count = 0;
     for record in xlink_db.records.into_iter() {
@@ -171,12 +182,544 @@ fn load_mim2gene_db(path: &Path) -> Result<OmimDb, anyhow::Error> {
     Ok(result)
 }
 
+/// Gene constraint, dosage sensitivity, and essentiality scores.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct GeneScoreRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// gnomAD LOEUF score, if available.
+    pub loeuf: Option<f32>,
+    /// ClinGen haploinsufficiency score, if available.
+    pub clingen_hi: Option<u32>,
+    /// ClinGen triplosensitivity score, if available.
+    pub clingen_ts: Option<u32>,
+    /// DOMINO probability of dominant inheritance, if available.
+    #[serde(default)]
+    pub domino: Option<f32>,
+    /// Selection coefficient against heterozygous loss-of-function (sHet), if available.
+    #[serde(default)]
+    pub shet: Option<f32>,
+    /// Fraction of DepMap cell lines in which the gene is essential, if available.
+    #[serde(default)]
+    pub depmap_essential_fraction: Option<f32>,
+}
+
+impl GeneScoreRecord {
+    /// Return whether there is sufficient evidence for haploinsufficiency.
+    pub fn is_haploinsufficient(&self) -> bool {
+        self.clingen_hi == Some(3)
+    }
+
+    /// Return whether there is sufficient evidence for triplosensitivity.
+    pub fn is_triplosensitive(&self) -> bool {
+        self.clingen_ts == Some(3)
+    }
+}
+
+/// Container for gene scores by HGNC ID.
+#[derive(Default, Clone, Debug)]
+pub struct GeneScoreDb {
+    pub records: HashMap<String, GeneScoreRecord>,
+}
+
+impl GeneScoreDb {
+    pub fn get(&self, hgnc_id: &str) -> Option<&GeneScoreRecord> {
+        self.records.get(hgnc_id)
+    }
+}
+
+#[tracing::instrument]
+fn load_gene_score_db(path: &Path) -> Result<GeneScoreDb, anyhow::Error> {
+    tracing::debug!("loading gene score TSV records from {:?}...", path);
+
+    let before_loading = Instant::now();
+    let mut result = GeneScoreDb::default();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: GeneScoreRecord = record?;
+        result.records.insert(record.hgnc_id.clone(), record);
+        total_count += 1;
+    }
+    tracing::debug!(
+        "... done loading {} records in {:?}",
+        total_count,
+        before_loading.elapsed(),
+    );
+
+    Ok(result)
+}
+
+/// A per-gene record of an optional gene annotation file.
+pub trait GeneRecord: serde::de::DeserializeOwned {
+    /// Return the HGNC ID of the gene.
+    fn hgnc_id(&self) -> &str;
+}
+
+/// Container for per-gene records by HGNC ID.
+#[derive(Clone, Debug)]
+pub struct GeneRecordDb<T> {
+    pub records: HashMap<String, Vec<T>>,
+}
+
+impl<T> Default for GeneRecordDb<T> {
+    fn default() -> Self {
+        Self {
+            records: HashMap::new(),
+        }
+    }
+}
+
+impl<T> GeneRecordDb<T> {
+    /// Return the records of the gene with `hgnc_id`.
+    pub fn get(&self, hgnc_id: &str) -> &[T] {
+        self.records
+            .get(hgnc_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Load the per-gene records from the TSV file at `path`.
+fn load_gene_record_db<T: GeneRecord>(path: &Path) -> Result<GeneRecordDb<T>, anyhow::Error> {
+    tracing::debug!("loading gene TSV records from {:?}...", path);
+
+    let before_loading = Instant::now();
+    let mut result = GeneRecordDb::default();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: T = record?;
+        result
+            .records
+            .entry(record.hgnc_id().to_string())
+            .or_insert_with(Vec::new)
+            .push(record);
+        total_count += 1;
+    }
+    tracing::debug!(
+        "... done loading {} records in {:?}",
+        total_count,
+        before_loading.elapsed(),
+    );
+
+    Ok(result)
+}
+
+/// Load the per-gene records from the optional TSV file at `path`, empty if not present.
+fn load_optional_gene_record_db<T: GeneRecord>(
+    path: &Path,
+) -> Result<GeneRecordDb<T>, anyhow::Error> {
+    path.exists()
+        .then(|| load_gene_record_db(path))
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("error loading {:?}: {}", path, e))
+        .map(Option::unwrap_or_default)
+}
+
+/// Gene-disease validity classification of ClinGen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClingenClassification {
+    /// Definitive evidence.
+    Definitive,
+    /// Strong evidence.
+    Strong,
+    /// Moderate evidence.
+    Moderate,
+    /// Limited evidence.
+    Limited,
+    /// Disputed gene-disease relationship.
+    Disputed,
+    /// Refuted gene-disease relationship.
+    Refuted,
+    /// No known disease relationship.
+    #[serde(
+        alias = "No Known Disease Relationship",
+        alias = "No Reported Evidence"
+    )]
+    NoKnownDiseaseRelationship,
+}
+
+/// A gene-disease validity curation of ClinGen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClingenRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// Label of the disease.
+    pub disease_label: String,
+    /// MONDO ID of the disease.
+    pub mondo_id: String,
+    /// Mode of inheritance, e.g., "AD".
+    pub moi: String,
+    /// The validity classification.
+    pub classification: ClingenClassification,
+}
+
+/// Container for ClinGen gene-disease validity curations by HGNC ID.
+pub type ClingenDb = GeneRecordDb<ClingenRecord>;
+
+impl GeneRecord for ClingenRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+#[tracing::instrument]
+fn load_clingen_db(path: &Path) -> Result<ClingenDb, anyhow::Error> {
+    let mut result: ClingenDb = load_gene_record_db(path)?;
+    for records in result.records.values_mut() {
+        records.sort_by_key(|record| record.classification);
+    }
+    Ok(result)
+}
+
+/// Deserialize a list separated by commas or semicolons, empty or `.` if none.
+fn deserialize_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(value
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty() && *entry != ".")
+        .map(String::from)
+        .collect())
+}
+
+/// A gene-phenotype relationship of OMIM.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OmimPhenotypeRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// MIM number of the phenotype.
+    pub omim_id: String,
+    /// Label of the phenotype.
+    pub label: String,
+    /// Modes of inheritance of the phenotype, e.g., "Autosomal dominant".
+    #[serde(deserialize_with = "deserialize_list")]
+    pub inheritance: Vec<String>,
+}
+
+impl GeneRecord for OmimPhenotypeRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for OMIM gene-phenotype relationships by HGNC ID.
+pub type OmimPhenotypeDb = GeneRecordDb<OmimPhenotypeRecord>;
+
+/// A gene-disorder association of Orphanet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrphanetRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// ORPHA code of the disorder, e.g., "ORPHA:558".
+    pub orpha_id: String,
+    /// Label of the disorder.
+    pub label: String,
+    /// Type of the association, e.g., "Disease-causing germline mutation(s) in".
+    pub association_type: String,
+    /// Prevalence class of the disorder, e.g., "1-9 / 100 000", if known.
+    pub prevalence_class: Option<String>,
+}
+
+impl GeneRecord for OrphanetRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for Orphanet gene-disorder associations by HGNC ID.
+pub type OrphanetDb = GeneRecordDb<OrphanetRecord>;
+
+/// A gene-disease record of the DDG2P panel of gene2phenotype.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ddg2pRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// Name of the disease.
+    pub disease_name: String,
+    /// Confidence of the gene-disease relationship, e.g., "definitive".
+    pub confidence: String,
+    /// Allelic requirement, e.g., "biallelic_autosomal" or "monoallelic_X_hemizygous".
+    pub allelic_requirement: String,
+    /// Mutation consequences, e.g., "absent gene product".
+    #[serde(deserialize_with = "deserialize_list")]
+    pub mutation_consequence: Vec<String>,
+}
+
+impl Ddg2pRecord {
+    /// Return whether two affected alleles are required.
+    pub fn is_biallelic(&self) -> bool {
+        self.allelic_requirement
+            .to_ascii_lowercase()
+            .starts_with("biallelic")
+    }
+
+    /// Return whether `genotype` is consistent with the allelic requirement.
+    ///
+    /// Biallelic requirements need a hom. alt. genotype, all others a carrier.
+    pub fn is_consistent_with(&self, genotype: CallGenotype) -> bool {
+        match genotype {
+            CallGenotype::HomAlt => true,
+            CallGenotype::Het => !self.is_biallelic(),
+            CallGenotype::HomRef | CallGenotype::WithNoCall => false,
+        }
+    }
+}
+
+impl GeneRecord for Ddg2pRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for DDG2P gene-disease records by HGNC ID.
+pub type Ddg2pDb = GeneRecordDb<Ddg2pRecord>;
+
+/// Membership of a gene in a PanelApp panel of Genomics England or Australia.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PanelAppRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// ID of the panel, e.g., "285".
+    pub panel_id: String,
+    /// Name of the panel.
+    pub panel_name: String,
+    /// Version of the panel, e.g., "3.21".
+    pub panel_version: String,
+    /// Confidence level of the gene on the panel, 3 (green), 2 (amber), or 1 (red).
+    pub confidence_level: u32,
+}
+
+impl GeneRecord for PanelAppRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for PanelApp panel memberships by HGNC ID.
+pub type PanelAppDb = GeneRecordDb<PanelAppRecord>;
+
+impl PanelAppDb {
+    /// Return the HGNC IDs of the genes on the panels with `panel_ids` with at least
+    /// `min_confidence`.
+    pub fn panel_hgnc_ids(&self, panel_ids: &[String], min_confidence: u32) -> HashSet<String> {
+        self.records
+            .iter()
+            .filter(|(_, records)| {
+                records.iter().any(|record| {
+                    panel_ids.contains(&record.panel_id)
+                        && record.confidence_level >= min_confidence
+                })
+            })
+            .map(|(hgnc_id, _)| hgnc_id.clone())
+            .collect()
+    }
+}
+
+/// Median expression of a gene in a GTEx tissue.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GtexRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// Name of the tissue, e.g., "Muscle - Skeletal".
+    pub tissue: String,
+    /// Median TPM of the gene in the tissue.
+    pub median_tpm: f32,
+}
+
+impl GeneRecord for GtexRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for GTEx median expression by tissue by HGNC ID.
+pub type GtexDb = GeneRecordDb<GtexRecord>;
+
+/// Entry of the HPO `genes_to_phenotype.txt` file, the other columns are ignored.
+#[derive(Deserialize, Debug)]
+struct GenesToPhenotypeRecord {
+    /// NCBI gene ID.
+    ncbi_gene_id: u32,
+    /// HPO term ID.
+    hpo_id: String,
+}
+
+/// Genes with the query HPO terms annotated to them.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HpoGeneMatch {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// The query HPO terms annotated to the gene.
+    pub hpo_ids: Vec<String>,
+}
+
+/// HPO terms annotated to the genes.
+#[derive(Default, Clone, Debug)]
+pub struct HpoGeneDb {
+    /// HPO term IDs by HGNC ID.
+    pub terms: HashMap<String, HashSet<String>>,
+}
+
+impl HpoGeneDb {
+    /// Return the genes of `hgnc_ids` with any of `hpo_terms` annotated to them.
+    pub fn matches(&self, hgnc_ids: &[String], hpo_terms: &[String]) -> Vec<HpoGeneMatch> {
+        hgnc_ids
+            .iter()
+            .filter_map(|hgnc_id| {
+                let terms = self.terms.get(hgnc_id)?;
+                let hpo_ids = hpo_terms
+                    .iter()
+                    .filter(|hpo_id| terms.contains(*hpo_id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!hpo_ids.is_empty()).then(|| HpoGeneMatch {
+                    hgnc_id: hgnc_id.clone(),
+                    hpo_ids,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Load the HPO `genes_to_phenotype.txt` file at `path`, mapping the genes to HGNC
+/// IDs with `xlink`.
+#[tracing::instrument(skip(xlink))]
+fn load_hpo_gene_db(path: &Path, xlink: &XlinkDb) -> Result<HpoGeneDb, anyhow::Error> {
+    tracing::debug!("loading HPO genes_to_phenotype records from {:?}...", path);
+
+    let before_loading = Instant::now();
+    let mut result = HpoGeneDb::default();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: GenesToPhenotypeRecord = record?;
+        for idx in xlink
+            .from_entrez
+            .get_vec(&record.ncbi_gene_id)
+            .into_iter()
+            .flatten()
+        {
+            result
+                .terms
+                .entry(xlink.records[*idx as usize].hgnc_id.clone())
+                .or_default()
+                .insert(record.hpo_id.clone());
+        }
+        total_count += 1;
+    }
+    tracing::debug!(
+        "... done loading {} records in {:?}",
+        total_count,
+        before_loading.elapsed(),
+    );
+
+    Ok(result)
+}
+
+/// A phenotype of mouse knockouts of the orthologous gene from MGI or IMPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MousePhenotypeRecord {
+    /// HGNC gene ID of the human gene.
+    pub hgnc_id: String,
+    /// MGI ID of the mouse gene, e.g., "MGI:87853".
+    pub mgi_id: String,
+    /// Mammalian Phenotype Ontology term ID, e.g., "MP:0011100".
+    pub mp_id: String,
+    /// Label of the phenotype term.
+    pub mp_label: String,
+    /// Source of the annotation, e.g., "MGI" or "IMPC".
+    pub source: String,
+}
+
+impl GeneRecord for MousePhenotypeRecord {
+    fn hgnc_id(&self) -> &str {
+        &self.hgnc_id
+    }
+}
+
+/// Container for MGI/IMPC mouse knockout phenotypes by HGNC ID.
+pub type MousePhenotypeDb = GeneRecordDb<MousePhenotypeRecord>;
+
 /// Bundle of gene region DBs and the xlink info packaged with VarFish.
 #[derive(Default, Debug)]
 pub struct GeneDb {
     pub xlink: XlinkDb,
     pub acmg: AcmgDb,
     pub mim2gene: OmimDb,
+    /// Gene constraint and dosage sensitivity scores, empty if not present.
+    pub scores: GeneScoreDb,
+    /// ClinGen gene-disease validity curations, empty if not present.
+    pub clingen: ClingenDb,
+    /// OMIM gene-phenotype relationships, empty if not present.
+    pub omim_phenotypes: OmimPhenotypeDb,
+    /// Orphanet gene-disorder associations, empty if not present.
+    pub orphanet: OrphanetDb,
+    /// DDG2P gene-disease records, empty if not present.
+    pub ddg2p: Ddg2pDb,
+    /// PanelApp panel memberships, empty if not present.
+    pub panelapp: PanelAppDb,
+    /// GTEx median expression by tissue, empty if not present.
+    pub gtex: GtexDb,
+    /// HPO terms annotated to the genes, empty if not present.
+    pub hpo: HpoGeneDb,
+    /// MGI/IMPC mouse knockout phenotypes, empty if not present.
+    pub mouse_phenotypes: MousePhenotypeDb,
+    /// Protein domains projected onto the genome, empty if not present.
+    pub domains: ProteinDomainDb,
+}
+
+/// Per-gene annotation from the optional gene annotation files.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GeneAnnotations {
+    /// Gene constraint, dosage sensitivity, and essentiality scores.
+    pub scores: Option<GeneScoreRecord>,
+    /// ClinGen gene-disease validity curations, strongest first.
+    pub clingen: Vec<ClingenRecord>,
+    /// OMIM gene-phenotype relationships.
+    pub omim_phenotypes: Vec<OmimPhenotypeRecord>,
+    /// Orphanet gene-disorder associations.
+    pub orphanet: Vec<OrphanetRecord>,
+    /// DDG2P gene-disease records.
+    pub ddg2p: Vec<Ddg2pRecord>,
+    /// PanelApp panel memberships.
+    pub panelapp: Vec<PanelAppRecord>,
+    /// GTEx median expression by tissue.
+    pub gtex: Vec<GtexRecord>,
+    /// MGI/IMPC mouse knockout phenotypes.
+    pub mouse_phenotypes: Vec<MousePhenotypeRecord>,
+}
+
+impl GeneDb {
+    /// Return the annotation of the gene with `hgnc_id`.
+    pub fn annotations(&self, hgnc_id: &str) -> GeneAnnotations {
+        GeneAnnotations {
+            scores: self.scores.get(hgnc_id).cloned(),
+            clingen: self.clingen.get(hgnc_id).to_vec(),
+            omim_phenotypes: self.omim_phenotypes.get(hgnc_id).to_vec(),
+            orphanet: self.orphanet.get(hgnc_id).to_vec(),
+            ddg2p: self.ddg2p.get(hgnc_id).to_vec(),
+            panelapp: self.panelapp.get(hgnc_id).to_vec(),
+            gtex: self.gtex.get(hgnc_id).to_vec(),
+            mouse_phenotypes: self.mouse_phenotypes.get(hgnc_id).to_vec(),
+        }
+    }
 }
 
 // Load all gene information, such as region, id mapping and symbols.
@@ -184,9 +727,20 @@ pub struct GeneDb {
 pub fn load_gene_db(path_db: &str, genome_release: GenomeRelease) -> Result<GeneDb, anyhow::Error> {
     info!("Loading gene dbs");
 
+    let path_scores = Path::new(path_db).join("noref/genes/gene_scores.tsv");
+    let path_clingen = Path::new(path_db).join("noref/genes/clingen_gene_validity.tsv");
+    let path_hpo = Path::new(path_db).join("noref/genes/genes_to_phenotype.txt");
+    let xlink = load_xlink_db(Path::new(path_db).join("noref/genes/xlink.bin").as_path())
+        .map_err(|e| anyhow::anyhow!("error loading Xlink DB: {}", e))?;
+    let hpo = path_hpo
+        .exists()
+        .then(|| load_hpo_gene_db(path_hpo.as_path(), &xlink))
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("error loading HPO genes_to_phenotype DB: {}", e))?
+        .unwrap_or_default();
     let result = GeneDb {
-        xlink: load_xlink_db(Path::new(path_db).join("noref/genes/xlink.bin").as_path())
-            .map_err(|e| anyhow::anyhow!("error loading Xlink DB: {}", e))?,
+        xlink,
+        hpo,
         acmg: load_acmg_db(Path::new(path_db).join("noref/genes/acmg.tsv").as_path())
             .map_err(|e| anyhow::anyhow!("error loading ACMG DB: {}", e))?,
         mim2gene: load_mim2gene_db(
@@ -195,7 +749,251 @@ pub fn load_gene_db(path_db: &str, genome_release: GenomeRelease) -> Result<Gene
                 .as_path(),
         )
         .map_err(|e| anyhow::anyhow!("error loading OMIM DB: {}", e))?,
+        scores: path_scores
+            .exists()
+            .then(|| load_gene_score_db(path_scores.as_path()))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("error loading gene score DB: {}", e))?
+            .unwrap_or_default(),
+        clingen: path_clingen
+            .exists()
+            .then(|| load_clingen_db(path_clingen.as_path()))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("error loading ClinGen gene validity DB: {}", e))?
+            .unwrap_or_default(),
+        omim_phenotypes: load_optional_gene_record_db(
+            Path::new(path_db)
+                .join("noref/genes/omim_phenotypes.tsv")
+                .as_path(),
+        )?,
+        orphanet: load_optional_gene_record_db(
+            Path::new(path_db)
+                .join("noref/genes/orphanet.tsv")
+                .as_path(),
+        )?,
+        ddg2p: load_optional_gene_record_db(
+            Path::new(path_db).join("noref/genes/ddg2p.tsv").as_path(),
+        )?,
+        panelapp: load_optional_gene_record_db(
+            Path::new(path_db)
+                .join("noref/genes/panelapp.tsv")
+                .as_path(),
+        )?,
+        gtex: load_optional_gene_record_db(
+            Path::new(path_db).join("noref/genes/gtex.tsv").as_path(),
+        )?,
+        mouse_phenotypes: load_optional_gene_record_db(
+            Path::new(path_db)
+                .join("noref/genes/mouse_phenotypes.tsv")
+                .as_path(),
+        )?,
+        domains: load_protein_domain_db(path_db, genome_release)
+            .map_err(|e| anyhow::anyhow!("error loading protein domain DB: {}", e))?,
     };
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use serde::Serialize;
+
+    use super::{
+        GeneRecord, GeneRecordDb, GtexRecord, HpoGeneDb, HpoGeneMatch, MousePhenotypeRecord,
+        OmimPhenotypeRecord, OrphanetRecord, PanelAppDb, PanelAppRecord,
+    };
+
+    /// Return the records of `HGNC:1` in `db` as JSON, `HGNC:2` has no records.
+    fn records_json<T: Serialize>(db: GeneRecordDb<T>) -> serde_json::Value {
+        assert!(db.get("HGNC:2").is_empty());
+        serde_json::to_value(db.get("HGNC:1")).expect("could not serialize records")
+    }
+
+    /// Load the per-gene records of type `T` from `path`.
+    fn load_records<T: GeneRecord + Serialize>(
+        path: &Path,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        Ok(records_json(super::load_gene_record_db::<T>(path)?))
+    }
+
+    /// Load the ClinGen records from `path`, strongest classification first.
+    fn load_clingen_records(path: &Path) -> Result<serde_json::Value, anyhow::Error> {
+        Ok(records_json(super::load_clingen_db(path)?))
+    }
+
+    #[rstest::rstest]
+    #[case::clingen(
+        "hgnc_id\tdisease_label\tmondo_id\tmoi\tclassification\n\
+         HGNC:1\tdisease A\tMONDO:0000001\tAD\tNo Known Disease Relationship\n\
+         HGNC:1\tdisease B\tMONDO:0000002\tAR\tDefinitive\n",
+        load_clingen_records,
+        serde_json::json!([
+            {
+                "hgnc_id": "HGNC:1",
+                "disease_label": "disease B",
+                "mondo_id": "MONDO:0000002",
+                "moi": "AR",
+                "classification": "Definitive",
+            },
+            {
+                "hgnc_id": "HGNC:1",
+                "disease_label": "disease A",
+                "mondo_id": "MONDO:0000001",
+                "moi": "AD",
+                "classification": "NoKnownDiseaseRelationship",
+            },
+        ]),
+    )]
+    #[case::omim_phenotypes(
+        "hgnc_id\tomim_id\tlabel\tinheritance\n\
+         HGNC:1\t100100\tphenotype A\tAutosomal dominant, Autosomal recessive\n\
+         HGNC:1\t100200\tphenotype B\t.\n",
+        load_records::<OmimPhenotypeRecord>,
+        serde_json::json!([
+            {
+                "hgnc_id": "HGNC:1",
+                "omim_id": "100100",
+                "label": "phenotype A",
+                "inheritance": ["Autosomal dominant", "Autosomal recessive"],
+            },
+            {
+                "hgnc_id": "HGNC:1",
+                "omim_id": "100200",
+                "label": "phenotype B",
+                "inheritance": [],
+            },
+        ]),
+    )]
+    #[case::orphanet(
+        "hgnc_id\torpha_id\tlabel\tassociation_type\tprevalence_class\n\
+         HGNC:1\tORPHA:558\tdisorder A\tDisease-causing germline mutation(s) in\t1-9 / 100 000\n\
+         HGNC:1\tORPHA:559\tdisorder B\tCandidate gene tested in\t\n",
+        load_records::<OrphanetRecord>,
+        serde_json::json!([
+            {
+                "hgnc_id": "HGNC:1",
+                "orpha_id": "ORPHA:558",
+                "label": "disorder A",
+                "association_type": "Disease-causing germline mutation(s) in",
+                "prevalence_class": "1-9 / 100 000",
+            },
+            {
+                "hgnc_id": "HGNC:1",
+                "orpha_id": "ORPHA:559",
+                "label": "disorder B",
+                "association_type": "Candidate gene tested in",
+                "prevalence_class": null,
+            },
+        ]),
+    )]
+    #[case::gtex(
+        "hgnc_id\ttissue\tmedian_tpm\n\
+         HGNC:1\tMuscle - Skeletal\t12.5\n\
+         HGNC:1\tBrain - Cortex\t0\n",
+        load_records::<GtexRecord>,
+        serde_json::json!([
+            {"hgnc_id": "HGNC:1", "tissue": "Muscle - Skeletal", "median_tpm": 12.5},
+            {"hgnc_id": "HGNC:1", "tissue": "Brain - Cortex", "median_tpm": 0.0},
+        ]),
+    )]
+    #[case::mouse_phenotypes(
+        "hgnc_id\tmgi_id\tmp_id\tmp_label\tsource\n\
+         HGNC:1\tMGI:87853\tMP:0011100\tpreweaning lethality, complete penetrance\tIMPC\n",
+        load_records::<MousePhenotypeRecord>,
+        serde_json::json!([
+            {
+                "hgnc_id": "HGNC:1",
+                "mgi_id": "MGI:87853",
+                "mp_id": "MP:0011100",
+                "mp_label": "preweaning lethality, complete penetrance",
+                "source": "IMPC",
+            },
+        ]),
+    )]
+    fn load_gene_record_db(
+        #[case] contents: &str,
+        #[case] load: fn(&Path) -> Result<serde_json::Value, anyhow::Error>,
+        #[case] expected: serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = tmpdir.join("records.tsv");
+        std::fs::write(&path, contents)?;
+
+        assert_eq!(load(&path)?, expected);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(&["285"], 3, &["HGNC:1"])]
+    #[case(&["285"], 2, &["HGNC:1", "HGNC:2"])]
+    #[case(&["285", "486"], 3, &["HGNC:1", "HGNC:3"])]
+    #[case(&["999"], 1, &[])]
+    fn panel_hgnc_ids(
+        #[case] panel_ids: &[&str],
+        #[case] min_confidence: u32,
+        #[case] expected: &[&str],
+    ) {
+        let record = |hgnc_id: &str, panel_id: &str, confidence_level: u32| PanelAppRecord {
+            hgnc_id: hgnc_id.into(),
+            panel_id: panel_id.into(),
+            panel_name: format!("panel {}", panel_id),
+            panel_version: "1.0".into(),
+            confidence_level,
+        };
+        let mut db = PanelAppDb::default();
+        for record in [
+            record("HGNC:1", "285", 3),
+            record("HGNC:2", "285", 2),
+            record("HGNC:3", "486", 3),
+        ] {
+            db.records
+                .entry(record.hgnc_id.clone())
+                .or_default()
+                .push(record);
+        }
+
+        let panel_ids = panel_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        let mut hgnc_ids = db
+            .panel_hgnc_ids(&panel_ids, min_confidence)
+            .into_iter()
+            .collect::<Vec<_>>();
+        hgnc_ids.sort();
+
+        assert_eq!(hgnc_ids, expected);
+    }
+
+    #[test]
+    fn hpo_gene_matches() {
+        let db = HpoGeneDb {
+            terms: [
+                (
+                    "HGNC:1".to_string(),
+                    ["HP:0001250", "HP:0001263"].map(String::from).into(),
+                ),
+                (
+                    "HGNC:2".to_string(),
+                    ["HP:0000365"].map(String::from).into(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let hgnc_ids = ["HGNC:1", "HGNC:2", "HGNC:3"].map(String::from);
+        let hpo_terms = ["HP:0001263", "HP:0001250"].map(String::from);
+
+        assert_eq!(
+            db.matches(&hgnc_ids, &hpo_terms),
+            vec![HpoGeneMatch {
+                hgnc_id: "HGNC:1".into(),
+                hpo_ids: hpo_terms.to_vec(),
+            }]
+        );
+        assert!(db.matches(&hgnc_ids, &[]).is_empty());
+    }
+}
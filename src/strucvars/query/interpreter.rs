@@ -7,11 +7,13 @@ use tracing::{trace, warn};
 
 use super::{
     bgdbs::BgDbOverlaps,
+    genes::Ddg2pRecord,
     masked::MaskedBreakpointCount,
     schema::{
         CaseQuery, Genotype, GenotypeChoice, Range, StructuralVariant, SvSubType, SvType,
         TranscriptEffect,
     },
+    tads::TadDisruption,
 };
 
 /// Slack around break-end positions
@@ -169,14 +171,14 @@ impl QueryInterpreter {
 
         let sv_size = sv.size();
         let (pass_sv_size_min, pass_sv_size_max) = if let Some(sv_size) = sv_size {
-            let pass_sv_size_min = self
-                .query
-                .sv_size_min
-                .map_or(true, |sv_size_min| sv_size >= sv_size_min);
-            let pass_sv_size_max = self
-                .query
-                .sv_size_max
-                .map_or(true, |sv_size_max| sv_size <= sv_size_max);
+            let (sv_size_min, sv_size_max) =
+                if let Some(range) = self.query.sv_size_by_type.get(&sv.sv_type) {
+                    (range.min, range.max)
+                } else {
+                    (self.query.sv_size_min, self.query.sv_size_max)
+                };
+            let pass_sv_size_min = sv_size_min.map_or(true, |sv_size_min| sv_size >= sv_size_min);
+            let pass_sv_size_max = sv_size_max.map_or(true, |sv_size_max| sv_size <= sv_size_max);
             (pass_sv_size_min, pass_sv_size_max)
         } else {
             (true, true)
@@ -198,38 +200,37 @@ impl QueryInterpreter {
             }
 
             if sv.sv_type == SvType::Ins || sv.sv_sub_type.is_ins() {
-                // handle case of insertions: overlap position with `INS_SLACK` and region
+                // handle case of insertions: overlap position with `INS_SLACK` (or the
+                // confidence interval) and region
                 for region in regions {
                     // as for all others, the range matches if `None` (whole chrom) or has overlap
                     let range_matches = match region.range {
                         None => true,
                         Some(Range { start, end }) => {
-                            overlaps(start - 1, end, sv.pos - INS_SLACK, sv.pos + INS_SLACK)
+                            let window = sv.pos_window(INS_SLACK);
+                            overlaps(start - 1, end, window.start, window.end)
                         }
                     };
                     any_match = any_match || (region.chrom.eq(&sv.chrom) && range_matches);
                 }
             } else if sv.sv_type == SvType::Bnd || sv.sv_sub_type == SvSubType::Bnd {
-                // for break-ends, test both ends and use `BND_SLACK`
+                // for break-ends, test both ends and use `BND_SLACK` (or the confidence
+                // intervals)
                 for region in regions {
                     // as for all others, the range matches if `None` (whole chrom) or has overlap
                     let range_matches_chrom = match region.range {
                         None => true,
-                        Some(Range { start, end }) => overlaps(
-                            start.saturating_sub(1),
-                            end,
-                            sv.pos.saturating_sub(BND_SLACK),
-                            sv.pos + BND_SLACK,
-                        ),
+                        Some(Range { start, end }) => {
+                            let window = sv.pos_window(BND_SLACK);
+                            overlaps(start.saturating_sub(1), end, window.start, window.end)
+                        }
                     };
                     let range_matches_chrom2 = match region.range {
                         None => true,
-                        Some(Range { start, end }) => overlaps(
-                            start.saturating_sub(1),
-                            end,
-                            sv.end.saturating_sub(BND_SLACK),
-                            sv.end + BND_SLACK,
-                        ),
+                        Some(Range { start, end }) => {
+                            let window = sv.end_window(BND_SLACK);
+                            overlaps(start.saturating_sub(1), end, window.start, window.end)
+                        }
                     };
                     any_match = any_match
                         || (region.chrom.eq(&sv.chrom) && range_matches_chrom)
@@ -264,9 +265,9 @@ impl QueryInterpreter {
         }
     }
 
-    /// Determine whether an SV record with the given overlap counts passes
-    /// the criteria.
-    pub fn passes_counts(&self, counts: &BgDbOverlaps) -> bool {
+    /// Determine whether an SV record of type `sv_type` with the given overlap
+    /// counts passes the criteria.
+    pub fn passes_counts(&self, sv_type: SvType, counts: &BgDbOverlaps) -> bool {
         // We simply check for each database separately and pass if the check has not
         // been enabled or no minimal carrier / allele count is given
         let passes_dgv = !self.query.svdb_dgv_enabled
@@ -274,11 +275,33 @@ impl QueryInterpreter {
         let passes_dgv_gs = !self.query.svdb_dgv_gs_enabled
             || counts.dgv_gs <= self.query.svdb_dgv_gs_max_count.unwrap_or(counts.dgv_gs);
         let passes_gnomad_genomes = !self.query.svdb_gnomad_genomes_enabled
-            || counts.gnomad_genomes
+            || (counts.gnomad_genomes
                 <= self
                     .query
                     .svdb_gnomad_genomes_max_count
-                    .unwrap_or(counts.gnomad_genomes);
+                    .unwrap_or(counts.gnomad_genomes)
+                && self
+                    .query
+                    .svdb_gnomad_genomes_max_af
+                    .get(&sv_type)
+                    .zip(counts.gnomad_genomes_af)
+                    .map_or(true, |(max_af, af)| af <= *max_af)
+                && self
+                    .query
+                    .svdb_gnomad_genomes_max_homalt
+                    .get(&sv_type)
+                    .zip(counts.gnomad_genomes_n_homalt)
+                    .map_or(true, |(max_homalt, n_homalt)| n_homalt <= *max_homalt)
+                && counts.gnomad_genomes_mei
+                    <= self
+                        .query
+                        .svdb_gnomad_genomes_mei_max_count
+                        .unwrap_or(counts.gnomad_genomes_mei)
+                && self
+                    .query
+                    .svdb_gnomad_genomes_mei_max_af
+                    .zip(counts.gnomad_genomes_mei_af)
+                    .map_or(true, |(max_af, af)| af <= max_af));
         let passes_gnomad_exomes = !self.query.svdb_gnomad_exomes_enabled
             || counts.gnomad_exomes
                 <= self
@@ -313,6 +336,111 @@ impl QueryInterpreter {
             && passes_inhouse
     }
 
+    /// Determine whether an SV record with the given number of disrupted regulatory
+    /// elements passes the criteria.
+    pub fn passes_regulatory(&self, regulatory_count: usize) -> bool {
+        !self.query.regulatory_require_overlap || regulatory_count > 0
+    }
+
+    /// Determine whether an SV record with the given number of matching ClinVar SVs
+    /// passes the criteria.
+    pub fn passes_clinvar(&self, clinvar_count: usize) -> bool {
+        !self.query.clinvar_sv_require_overlap || clinvar_count > 0
+    }
+
+    /// Determine whether `sv` passes the per-sample evidence filters.
+    pub fn passes_sample_evidence(&self, sv: &StructuralVariant) -> bool {
+        self.query
+            .sample_evidence
+            .iter()
+            .filter(|(_, filter)| filter.is_applicable_to(&sv.callers))
+            .all(|(sample, filter)| {
+                sv.call_info
+                    .get(sample)
+                    .map_or(true, |call_info| filter.is_call_info_pass(call_info))
+            })
+    }
+
+    /// Determine whether `sv` passes the mosaic CNV criteria, if any.
+    ///
+    /// Only SVs with an estimated mosaicism fraction in range for any of the
+    /// selected samples pass.
+    pub fn passes_mosaic(&self, sv: &StructuralVariant) -> bool {
+        let Some(mosaic) = self.query.mosaic.as_ref() else {
+            return true;
+        };
+        sv.call_info
+            .iter()
+            .filter(|(sample, _)| mosaic.samples.is_empty() || mosaic.samples.contains(sample))
+            .filter_map(|(_, call_info)| call_info.estimate_mosaic_fraction(sv.sv_type))
+            .any(|fraction| mosaic.is_fraction_pass(fraction))
+    }
+
+    /// Determine whether `sv` passes the de novo criteria, if any.
+    pub fn passes_de_novo(&self, sv: &StructuralVariant) -> bool {
+        self.query
+            .de_novo
+            .as_ref()
+            .map_or(true, |de_novo| de_novo.is_de_novo(sv))
+    }
+
+    /// Return the genotype of the DDG2P index in `sv`, if any.
+    fn ddg2p_index_genotype(&self, sv: &StructuralVariant) -> Option<crate::common::Genotype> {
+        self.query
+            .ddg2p_index
+            .as_ref()
+            .and_then(|index| sv.call_info.get(index))
+            .and_then(|call_info| call_info.genotype.as_ref())
+            .and_then(|gt| gt.parse::<crate::common::Genotype>().ok())
+    }
+
+    /// Determine whether the genotype of the DDG2P index in `sv` is consistent with the
+    /// allelic requirement of any of the DDG2P `records` of the overlapping genes.
+    ///
+    /// SVs not overlapping any DDG2P gene pass.  Unless `ddg2p_strict` is set, a het.
+    /// genotype also passes for biallelic genes as the second hit may be another variant.
+    pub fn passes_allelic_requirement(
+        &self,
+        sv: &StructuralVariant,
+        records: &[&Ddg2pRecord],
+    ) -> bool {
+        if self.query.ddg2p_index.is_none() || records.is_empty() {
+            return true;
+        }
+        let Some(genotype) = self.ddg2p_index_genotype(sv) else {
+            return false;
+        };
+        records.iter().any(|record| {
+            record.is_consistent_with(genotype)
+                || (!self.query.ddg2p_strict && genotype == crate::common::Genotype::Het)
+        })
+    }
+
+    /// Determine whether the DDG2P index in `sv` is het. while all of the DDG2P `records`
+    /// of the overlapping genes are biallelic, i.e., a second hit is needed.
+    pub fn requires_ddg2p_second_hit(
+        &self,
+        sv: &StructuralVariant,
+        records: &[&Ddg2pRecord],
+    ) -> bool {
+        !records.is_empty()
+            && self.ddg2p_index_genotype(sv) == Some(crate::common::Genotype::Het)
+            && records.iter().all(|record| record.is_biallelic())
+    }
+
+    /// Determine whether an SV record with the given TAD disruption passes the criteria.
+    pub fn passes_tad_disruption(&self, tad_disruption: &TadDisruption) -> bool {
+        self.query
+            .tad_min_disruption_score
+            .map_or(true, |min_score| tad_disruption.score() >= min_score)
+    }
+
+    /// Determine whether an SV record with the given number of overlapping genes
+    /// matching the query HPO terms passes the criteria.
+    pub fn passes_hpo(&self, hpo_match_count: usize) -> bool {
+        !self.query.hpo_require_match || hpo_match_count > 0
+    }
+
     /// Determine whether the `sv` passes the gene allow list filter.
     pub fn passes_genes(&self, ovl_hgvs_ids: &[String]) -> bool {
         if let Some(hgvs_allowlist) = self.hgvs_allowlist.as_ref() {
@@ -341,6 +469,20 @@ impl QueryInterpreter {
                 .any(|effect| self.query.tx_effects.contains(effect))
     }
 
+    /// Determine whether the `StructuralVariant` passes the problematic regions filter.
+    pub fn passes_problematic_regions(&self, masked_count: &MaskedBreakpointCount) -> bool {
+        let pass_low_complexity = self
+            .query
+            .max_brk_low_complexity
+            .map_or(true, |max_brk| masked_count.low_complexity <= max_brk);
+        let pass_blacklist = self
+            .query
+            .max_brk_blacklist
+            .map_or(true, |max_brk| masked_count.blacklist <= max_brk);
+
+        pass_low_complexity && pass_blacklist
+    }
+
     /// Determine whether the annotated `StructuralVariant` passes all criteria.
     pub fn passes<CountBg, CountMasked, OvlHgvsIds, TxEffects>(
         &self,
@@ -364,13 +506,26 @@ impl QueryInterpreter {
             return Ok(Default::default());
         }
 
-        let passes_result = self.passes_genotype(sv, &count_masked(sv))?;
+        let masked_count = count_masked(sv);
+        let passes_result = self.passes_genotype(sv, &masked_count)?;
         if !passes_result.pass_all {
             Ok(Default::default())
+        } else if !self.passes_problematic_regions(&masked_count) {
+            trace!("... SV does not pass problematic regions filter");
+            Ok(Default::default())
+        } else if !self.passes_sample_evidence(sv) {
+            trace!("... SV does not pass sample evidence filter");
+            Ok(Default::default())
+        } else if !self.passes_mosaic(sv) {
+            trace!("... SV does not pass mosaic filter");
+            Ok(Default::default())
+        } else if !self.passes_de_novo(sv) {
+            trace!("... SV does not pass de novo filter");
+            Ok(Default::default())
         } else if !self.passes_genes(&ovl_hgvs_ids(sv)) {
             trace!("... SV does not gene allow list filter");
             Ok(Default::default())
-        } else if !self.passes_counts(&count_bg(sv)) {
+        } else if !self.passes_counts(sv.sv_type, &count_bg(sv)) {
             trace!("... SV does not pass bg counts filter");
             Ok(Default::default())
         } else if !self.passes_effects(&tx_effects(sv)) {
@@ -389,7 +544,8 @@ mod tests {
     use mehari::annotate::strucvars::csq::interface::StrandOrientation;
 
     use crate::strucvars::query::schema::{
-        CallInfo, GenomicRegion, GenotypeChoice, GenotypeCriteria,
+        CallInfo, DeNovoCriteria, DeNovoEvidence, GenomicRegion, GenotypeChoice, GenotypeCriteria,
+        MosaicCriteria, SampleEvidenceFilter, SvSizeRange,
     };
 
     use super::*;
@@ -427,6 +583,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -450,6 +608,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -473,6 +633,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -497,6 +659,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -520,12 +684,66 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
         assert!(!interpreter.passes_selection(&sv_fail));
     }
 
+    #[rstest::rstest]
+    #[case::del_in_range(SvType::Del, 100, 200, true)]
+    #[case::del_too_large(SvType::Del, 100, 5_000, false)]
+    #[case::dup_too_small(SvType::Dup, 100, 200, false)]
+    #[case::dup_large(SvType::Dup, 100, 20_000, true)]
+    #[case::inv_global(SvType::Inv, 100, 5_000, true)]
+    #[case::inv_global_too_small(SvType::Inv, 100, 120, false)]
+    fn test_query_interpreter_passes_size_by_type(
+        #[case] sv_type: SvType,
+        #[case] pos: i32,
+        #[case] end: i32,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            sv_size_min: Some(50),
+            sv_size_by_type: IndexMap::from([
+                (
+                    SvType::Del,
+                    SvSizeRange {
+                        min: Some(50),
+                        max: Some(1_000),
+                    },
+                ),
+                (
+                    SvType::Dup,
+                    SvSizeRange {
+                        min: Some(10_000),
+                        max: None,
+                    },
+                ),
+            ]),
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos,
+            sv_type,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end,
+            callers: Vec::new(),
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            call_info: IndexMap::new(),
+        };
+
+        assert_eq!(interpreter.passes_selection(&sv), expected);
+    }
+
     #[test]
     fn test_query_interpreter_passes_simple_fail_size_max() {
         let query = CaseQuery {
@@ -543,6 +761,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -566,6 +786,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -589,6 +811,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -612,6 +836,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -635,6 +861,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -658,6 +886,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -681,6 +911,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -704,6 +936,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -727,6 +961,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -750,6 +986,8 @@ mod tests {
             end: 1000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -773,6 +1011,8 @@ mod tests {
             end: 1000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -796,6 +1036,8 @@ mod tests {
             end: 100,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -819,6 +1061,8 @@ mod tests {
             end: 1000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -842,6 +1086,8 @@ mod tests {
             end: 1000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
@@ -865,12 +1111,74 @@ mod tests {
             end: 1000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
 
         assert!(!interpreter.passes_genomic_region(&sv_fail));
     }
 
+    #[rstest::rstest]
+    #[case(false, 0, true)]
+    #[case(true, 0, false)]
+    #[case(true, 2, true)]
+    fn test_query_interpreter_passes_regulatory(
+        #[case] regulatory_require_overlap: bool,
+        #[case] regulatory_count: usize,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            regulatory_require_overlap,
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        assert_eq!(interpreter.passes_regulatory(regulatory_count), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(false, 0, true)]
+    #[case(true, 0, false)]
+    #[case(true, 1, true)]
+    fn test_query_interpreter_passes_clinvar(
+        #[case] clinvar_sv_require_overlap: bool,
+        #[case] clinvar_count: usize,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            clinvar_sv_require_overlap,
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        assert_eq!(interpreter.passes_clinvar(clinvar_count), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(None, 0, 0, true)]
+    #[case(Some(1), 0, 3, false)]
+    #[case(Some(1), 1, 0, true)]
+    #[case(Some(3), 1, 1, false)]
+    #[case(Some(3), 2, 2, true)]
+    fn test_query_interpreter_passes_tad_disruption(
+        #[case] tad_min_disruption_score: Option<u32>,
+        #[case] boundaries_spanned: u32,
+        #[case] tad_genes: usize,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            tad_min_disruption_score,
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        assert_eq!(
+            interpreter.passes_tad_disruption(&TadDisruption::new(boundaries_spanned, tad_genes)),
+            expected
+        );
+    }
+
     #[test]
     fn test_query_interpreter_passes_counts_pass() {
         let query = CaseQuery {
@@ -900,9 +1208,10 @@ mod tests {
             g1k: 5,
             inhouse: 5,
             dbvar: 5,
+            ..Default::default()
         };
 
-        assert!(interpreter.passes_counts(&counts_pass));
+        assert!(interpreter.passes_counts(SvType::Del, &counts_pass));
     }
 
     #[test]
@@ -934,9 +1243,69 @@ mod tests {
             g1k: 11,
             inhouse: 11,
             dbvar: 11,
+            ..Default::default()
+        };
+
+        assert!(!interpreter.passes_counts(SvType::Del, &counts_fail));
+    }
+
+    #[rstest::rstest]
+    #[case::below_af(SvType::Del, Some(0.005), None, true)]
+    #[case::above_af(SvType::Del, Some(0.02), None, false)]
+    #[case::above_af_other_type(SvType::Dup, Some(0.02), None, true)]
+    #[case::below_homalt(SvType::Dup, None, Some(2), true)]
+    #[case::above_homalt(SvType::Dup, None, Some(3), false)]
+    #[case::no_frequency(SvType::Del, None, None, true)]
+    fn test_query_interpreter_passes_counts_gnomad_genomes_frequency(
+        #[case] sv_type: SvType,
+        #[case] gnomad_genomes_af: Option<f32>,
+        #[case] gnomad_genomes_n_homalt: Option<u32>,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            svdb_gnomad_genomes_enabled: true,
+            svdb_gnomad_genomes_max_af: IndexMap::from([(SvType::Del, 0.01)]),
+            svdb_gnomad_genomes_max_homalt: IndexMap::from([(SvType::Dup, 2)]),
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let counts = BgDbOverlaps {
+            gnomad_genomes: 5,
+            gnomad_genomes_af,
+            gnomad_genomes_n_homalt,
+            ..Default::default()
+        };
+
+        assert_eq!(interpreter.passes_counts(sv_type, &counts), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::below_count(3, None, true)]
+    #[case::above_count(6, None, false)]
+    #[case::below_af(0, Some(0.005), true)]
+    #[case::above_af(0, Some(0.02), false)]
+    fn test_query_interpreter_passes_counts_gnomad_genomes_mei(
+        #[case] gnomad_genomes_mei: u32,
+        #[case] gnomad_genomes_mei_af: Option<f32>,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            svdb_gnomad_genomes_enabled: true,
+            svdb_gnomad_genomes_mei_max_count: Some(5),
+            svdb_gnomad_genomes_mei_max_af: Some(0.01),
+            ..CaseQuery::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let counts = BgDbOverlaps {
+            gnomad_genomes: 10,
+            gnomad_genomes_mei,
+            gnomad_genomes_mei_af,
+            ..Default::default()
         };
 
-        assert!(!interpreter.passes_counts(&counts_fail));
+        assert_eq!(interpreter.passes_counts(SvType::Ins, &counts), expected);
     }
 
     #[test]
@@ -987,6 +1356,8 @@ mod tests {
             end: 2000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::from([("sample".to_owned(), call_info.clone())]),
         };
 
@@ -1123,6 +1494,8 @@ mod tests {
             end: 12345,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::NotApplicable,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::from([(
                 "sample".to_owned(),
                 CallInfo {
@@ -1198,6 +1571,8 @@ mod tests {
             end: 2000,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::from([("sample".to_owned(), call_info)]),
         };
 
@@ -1209,6 +1584,270 @@ mod tests {
         Ok(())
     }
 
+    #[rstest::rstest]
+    #[case::pass(&["Manta"], Some(10), Some(30.0), true)]
+    #[case::low_srpr(&["Manta"], Some(2), Some(30.0), false)]
+    #[case::low_gq(&["Manta"], Some(10), Some(5.0), false)]
+    #[case::missing(&["Manta"], None, None, true)]
+    #[case::other_caller(&["Delly"], Some(2), Some(5.0), true)]
+    fn test_query_interpreter_passes_sample_evidence(
+        #[case] callers: &[&str],
+        #[case] split_read_var: Option<u32>,
+        #[case] quality: Option<f32>,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            sample_evidence: IndexMap::from([(
+                "index".to_owned(),
+                SampleEvidenceFilter {
+                    callers: vec!["Manta".to_owned()],
+                    min_gq: Some(20.0),
+                    min_srpr_var: Some(5),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos: 100,
+            sv_type: SvType::Del,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end: 200,
+            callers: callers.iter().map(|caller| caller.to_string()).collect(),
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            call_info: IndexMap::from([(
+                "index".to_owned(),
+                CallInfo {
+                    genotype: Some("0/1".to_owned()),
+                    quality,
+                    split_read_var,
+                    ..Default::default()
+                },
+            )]),
+        };
+
+        assert_eq!(interpreter.passes_sample_evidence(&sv), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::del_mosaic(SvType::Del, Some(0.8), true)]
+    #[case::del_constitutional(SvType::Del, Some(0.5), false)]
+    #[case::dup_mosaic(SvType::Dup, Some(1.2), true)]
+    #[case::dup_wrong_direction(SvType::Dup, Some(0.8), false)]
+    #[case::cnv_mosaic(SvType::Cnv, Some(0.8), true)]
+    #[case::missing(SvType::Del, None, false)]
+    fn test_query_interpreter_passes_mosaic(
+        #[case] sv_type: SvType,
+        #[case] average_normalized_cov: Option<f32>,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            mosaic: Some(MosaicCriteria {
+                samples: vec!["index".to_owned()],
+                min_fraction: Some(0.1),
+                max_fraction: Some(0.9),
+            }),
+            ..Default::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos: 100,
+            sv_type,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end: 200,
+            callers: Vec::new(),
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            call_info: IndexMap::from([
+                (
+                    "index".to_owned(),
+                    CallInfo {
+                        average_normalized_cov,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "father".to_owned(),
+                    CallInfo {
+                        average_normalized_cov: Some(0.8),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+        };
+
+        assert_eq!(interpreter.passes_mosaic(&sv), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::de_novo("0/1", "0/0", 10, &[], true)]
+    #[case::parent_carrier("0/1", "0/1", 10, &[], false)]
+    #[case::index_non_carrier("0/0", "0/0", 10, &[], false)]
+    #[case::low_parent_ref("0/1", "0/0", 4, &[], false)]
+    #[case::caller_specific("0/1", "0/0", 4, &["Delly"], true)]
+    fn test_query_interpreter_passes_de_novo(
+        #[case] index_gt: &str,
+        #[case] parent_gt: &str,
+        #[case] parent_pr_cov: u32,
+        #[case] callers: &[&str],
+        #[case] expected: bool,
+    ) {
+        let evidence = DeNovoEvidence {
+            min_parent_pr_ref: Some(5),
+            ..Default::default()
+        };
+        let query = CaseQuery {
+            de_novo: Some(DeNovoCriteria {
+                index: "index".to_owned(),
+                parents: vec!["father".to_owned(), "mother".to_owned()],
+                evidence,
+                caller_evidence: IndexMap::from([(
+                    "Delly".to_owned(),
+                    DeNovoEvidence {
+                        min_parent_pr_ref: Some(2),
+                        ..Default::default()
+                    },
+                )]),
+            }),
+            ..Default::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let call_info = |gt: &str, pr_cov: u32| CallInfo {
+            genotype: Some(gt.to_owned()),
+            paired_end_cov: Some(pr_cov),
+            paired_end_var: Some(0),
+            ..Default::default()
+        };
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos: 100,
+            sv_type: SvType::Del,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end: 200,
+            callers: callers.iter().map(|caller| caller.to_string()).collect(),
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            call_info: IndexMap::from([
+                ("index".to_owned(), call_info(index_gt, 10)),
+                ("father".to_owned(), call_info(parent_gt, parent_pr_cov)),
+                ("mother".to_owned(), call_info("0/0", 10)),
+            ]),
+        };
+
+        assert_eq!(interpreter.passes_de_novo(&sv), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::unset(None, false, "0/1", &["biallelic_autosomal"], true, false)]
+    #[case::het_monoallelic(Some("index"), false, "0/1", &["monoallelic_autosomal"], true, false)]
+    #[case::het_biallelic(Some("index"), false, "0/1", &["biallelic_autosomal"], true, true)]
+    #[case::het_biallelic_strict(Some("index"), true, "0/1", &["biallelic_autosomal"], false, true)]
+    #[case::hom_biallelic(Some("index"), true, "1/1", &["biallelic_autosomal"], true, false)]
+    #[case::het_any_strict(Some("index"), true, "0/1", &["biallelic_autosomal", "monoallelic_autosomal"], true, false)]
+    #[case::hom_ref(Some("index"), false, "0/0", &["monoallelic_autosomal"], false, false)]
+    #[case::no_gene(Some("index"), true, "0/0", &[], true, false)]
+    #[case::other_sample(Some("father"), false, "1/1", &["monoallelic_autosomal"], false, false)]
+    fn test_query_interpreter_passes_allelic_requirement(
+        #[case] ddg2p_index: Option<&str>,
+        #[case] ddg2p_strict: bool,
+        #[case] index_gt: &str,
+        #[case] allelic_requirements: &[&str],
+        #[case] expected: bool,
+        #[case] expected_second_hit: bool,
+    ) {
+        let query = CaseQuery {
+            ddg2p_index: ddg2p_index.map(String::from),
+            ddg2p_strict,
+            ..Default::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos: 100,
+            sv_type: SvType::Del,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end: 200,
+            callers: Vec::new(),
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            call_info: IndexMap::from([(
+                "index".to_owned(),
+                CallInfo {
+                    genotype: Some(index_gt.to_owned()),
+                    ..Default::default()
+                },
+            )]),
+        };
+        let records = allelic_requirements
+            .iter()
+            .map(|allelic_requirement| Ddg2pRecord {
+                hgnc_id: "HGNC:1".into(),
+                disease_name: "disease".into(),
+                confidence: "definitive".into(),
+                allelic_requirement: allelic_requirement.to_string(),
+                mutation_consequence: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        let records = records.iter().collect::<Vec<_>>();
+        assert_eq!(
+            interpreter.passes_allelic_requirement(&sv, &records),
+            expected
+        );
+        assert_eq!(
+            interpreter.requires_ddg2p_second_hit(&sv, &records),
+            expected_second_hit
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::unset(None, None, 2, 2, true)]
+    #[case::low_complexity_pass(Some(1), None, 1, 2, true)]
+    #[case::low_complexity_fail(Some(1), None, 2, 0, false)]
+    #[case::blacklist_pass(None, Some(0), 2, 0, true)]
+    #[case::blacklist_fail(None, Some(0), 0, 1, false)]
+    fn test_query_interpreter_passes_problematic_regions(
+        #[case] max_brk_low_complexity: Option<u32>,
+        #[case] max_brk_blacklist: Option<u32>,
+        #[case] low_complexity: u32,
+        #[case] blacklist: u32,
+        #[case] expected: bool,
+    ) {
+        let query = CaseQuery {
+            max_brk_low_complexity,
+            max_brk_blacklist,
+            ..Default::default()
+        };
+        let interpreter = QueryInterpreter::new(query, None);
+
+        let masked_count = MaskedBreakpointCount {
+            low_complexity,
+            blacklist,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            interpreter.passes_problematic_regions(&masked_count),
+            expected
+        );
+    }
+
     #[test]
     fn test_query_interpreter_passes_smoke() -> Result<(), anyhow::Error> {
         let query = CaseQuery::default();
@@ -1223,6 +1862,8 @@ mod tests {
             end: 200,
             callers: Vec::new(),
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: IndexMap::new(),
         };
         let counts_pass = BgDbOverlaps {
@@ -1233,6 +1874,7 @@ mod tests {
             g1k: 5,
             inhouse: 5,
             dbvar: 5,
+            ..Default::default()
         };
 
         assert!(
@@ -0,0 +1,333 @@
+//! Karyotype-style summary of large CNVs.
+//!
+//! The passing deletions, duplications, and CNVs above a minimal size are
+//! collected per sample and written as one JSONL record per sample with the
+//! events in ISCN-like microarray notation, e.g., `arr[GRCh37] 22(18631366_21861651)x1`,
+//! and a per-chromosome overview of the lost and gained sequence that allows
+//! to quickly spot aneuploidies.
+
+use std::{collections::HashMap, io::Write};
+
+use biocommons_bioutils::assemblies::ASSEMBLY_INFOS;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::common::{self, build_chrom_map, GenomeRelease, CHROMS};
+
+use super::schema::{CallInfo, StructuralVariant, SvType};
+
+/// Fraction of a chromosome that must be lost or gained for a likely aneuploidy.
+pub const MIN_ANEUPLOIDY_FRACTION: f64 = 0.8;
+
+/// A large CNV event of one sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnvEvent {
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based start position.
+    pub start: i32,
+    /// 1-based end position.
+    pub end: i32,
+    /// Copy number of the sample.
+    pub copy_number: u32,
+}
+
+/// Summary of lost and gained sequence on one chromosome.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChromosomeSummary {
+    /// Chromosome name.
+    pub chrom: String,
+    /// Length of the chromosome.
+    pub length: u64,
+    /// Number of bases covered by losses.
+    pub loss_bp: u64,
+    /// Number of bases covered by gains.
+    pub gain_bp: u64,
+    /// Fraction of the chromosome covered by losses.
+    pub loss_fraction: f64,
+    /// Fraction of the chromosome covered by gains.
+    pub gain_fraction: f64,
+    /// Whether losses or gains cover most of the chromosome.
+    pub likely_aneuploid: bool,
+}
+
+/// Karyotype summary of one sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SampleSummary {
+    /// Sample name.
+    pub sample: String,
+    /// The large CNVs in ISCN-like notation.
+    pub iscn: String,
+    /// Overview of the chromosomes with losses or gains.
+    pub chromosomes: Vec<ChromosomeSummary>,
+}
+
+/// Return the copy number of the sample with `call_info` for a CNV of `sv_type`.
+///
+/// The copy number estimate of the caller is used if available, otherwise it is
+/// derived from the genotype of deletions and duplications.
+pub fn copy_number(sv_type: SvType, call_info: &CallInfo) -> Option<u32> {
+    if call_info.copy_number.is_some() {
+        return call_info.copy_number;
+    }
+    let genotype = call_info
+        .genotype
+        .as_ref()
+        .and_then(|gt| gt.parse::<common::Genotype>().ok())?;
+    match (sv_type, genotype) {
+        (SvType::Del, common::Genotype::Het) => Some(1),
+        (SvType::Del, common::Genotype::HomAlt) => Some(0),
+        (SvType::Dup, common::Genotype::Het) => Some(3),
+        (SvType::Dup, common::Genotype::HomAlt) => Some(4),
+        _ => None,
+    }
+}
+
+/// Collection of the large CNV events by sample.
+#[derive(Debug, Default)]
+pub struct Karyotype {
+    /// Minimal size of CNVs to collect.
+    pub min_size: i32,
+    /// The collected events by sample.
+    pub events: IndexMap<String, Vec<CnvEvent>>,
+}
+
+impl Karyotype {
+    /// Construct with the given minimal CNV size.
+    pub fn new(min_size: i32) -> Self {
+        Self {
+            min_size,
+            ..Default::default()
+        }
+    }
+
+    /// Register the samples of `sv` and collect it if it is a large CNV with a
+    /// copy number other than two.
+    pub fn add(&mut self, sv: &StructuralVariant, call_info: &IndexMap<String, CallInfo>) {
+        let is_large = matches!(sv.sv_type, SvType::Del | SvType::Dup | SvType::Cnv)
+            && sv.end - sv.pos + 1 >= self.min_size;
+        for (sample, call_info) in call_info {
+            let events = self.events.entry(sample.clone()).or_default();
+            let Some(copy_number) = copy_number(sv.sv_type, call_info) else {
+                continue;
+            };
+            if is_large && copy_number != 2 {
+                events.push(CnvEvent {
+                    chrom: sv.chrom.clone(),
+                    start: sv.pos,
+                    end: sv.end,
+                    copy_number,
+                });
+            }
+        }
+    }
+
+    /// Build the summaries of all samples.
+    pub fn summaries(&self, genome_release: GenomeRelease) -> Vec<SampleSummary> {
+        let chrom_map = build_chrom_map();
+        let chrom_lengths = ASSEMBLY_INFOS[genome_release.into()]
+            .sequences
+            .iter()
+            .map(|record| {
+                (
+                    annonars::common::cli::canonicalize(&record.name),
+                    record.length as u64,
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.events
+            .iter()
+            .map(|(sample, events)| {
+                let mut events = events.clone();
+                events.sort_by_key(|event| (chrom_map.get(&event.chrom).copied(), event.start));
+                SampleSummary {
+                    sample: sample.clone(),
+                    iscn: iscn(genome_release, &events),
+                    chromosomes: chromosome_summaries(&events, &chrom_lengths),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Return the ISCN-like notation of the sorted `events`.
+fn iscn(genome_release: GenomeRelease, events: &[CnvEvent]) -> String {
+    let release = match genome_release {
+        GenomeRelease::Grch37 => "GRCh37",
+        GenomeRelease::Grch38 => "GRCh38",
+    };
+    if events.is_empty() {
+        format!("arr[{}] (1-22)x2", release)
+    } else {
+        let events = events
+            .iter()
+            .map(|event| {
+                format!(
+                    "{}({}_{})x{}",
+                    event.chrom, event.start, event.end, event.copy_number
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("arr[{}] {}", release, events.join(","))
+    }
+}
+
+/// Return the overview of the chromosomes affected by the sorted `events`.
+fn chromosome_summaries(
+    events: &[CnvEvent],
+    chrom_lengths: &HashMap<String, u64>,
+) -> Vec<ChromosomeSummary> {
+    CHROMS
+        .iter()
+        .filter(|chrom| events.iter().any(|event| event.chrom == **chrom))
+        .map(|chrom| {
+            let length = chrom_lengths.get(*chrom).copied().unwrap_or_default();
+            let (mut loss_bp, mut gain_bp) = (0, 0);
+            for event in events.iter().filter(|event| event.chrom == *chrom) {
+                let size = (event.end - event.start + 1) as u64;
+                if event.copy_number < 2 {
+                    loss_bp += size;
+                } else {
+                    gain_bp += size;
+                }
+            }
+            let fraction = |bp: u64| {
+                if length == 0 {
+                    0.0
+                } else {
+                    (bp as f64 / length as f64).min(1.0)
+                }
+            };
+            ChromosomeSummary {
+                chrom: chrom.to_string(),
+                length,
+                loss_bp,
+                gain_bp,
+                loss_fraction: fraction(loss_bp),
+                gain_fraction: fraction(gain_bp),
+                likely_aneuploid: fraction(loss_bp) >= MIN_ANEUPLOIDY_FRACTION
+                    || fraction(gain_bp) >= MIN_ANEUPLOIDY_FRACTION,
+            }
+        })
+        .collect()
+}
+
+/// Write the `summaries` as JSONL to `path`.
+pub fn write_summaries(path: &str, summaries: &[SampleSummary]) -> Result<(), anyhow::Error> {
+    let mut writer = std::io::BufWriter::new(
+        std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("could not create karyotype file {}: {}", path, e))?,
+    );
+    for summary in summaries {
+        writeln!(writer, "{}", serde_json::to_string(summary)?)
+            .map_err(|e| anyhow::anyhow!("could not write karyotype summary: {}", e))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+    use mehari::annotate::strucvars::csq::interface::StrandOrientation;
+
+    use super::{CnvEvent, Karyotype};
+    use crate::{
+        common::GenomeRelease,
+        strucvars::query::schema::{CallInfo, StructuralVariant, SvSubType, SvType},
+    };
+
+    #[rstest::rstest]
+    #[case(SvType::Del, Some("0/1"), None, Some(1))]
+    #[case(SvType::Del, Some("1/1"), None, Some(0))]
+    #[case(SvType::Dup, Some("0/1"), None, Some(3))]
+    #[case(SvType::Dup, Some("0/0"), None, None)]
+    #[case(SvType::Cnv, Some("0/1"), None, None)]
+    #[case(SvType::Cnv, None, Some(5), Some(5))]
+    fn copy_number(
+        #[case] sv_type: SvType,
+        #[case] genotype: Option<&str>,
+        #[case] copy_number: Option<u32>,
+        #[case] expected: Option<u32>,
+    ) {
+        let call_info = CallInfo {
+            genotype: genotype.map(String::from),
+            copy_number,
+            ..Default::default()
+        };
+
+        assert_eq!(super::copy_number(sv_type, &call_info), expected);
+    }
+
+    fn sv(chrom: &str, pos: i32, end: i32, sv_type: SvType, genotype: &str) -> StructuralVariant {
+        StructuralVariant {
+            chrom: chrom.into(),
+            pos,
+            sv_type,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end,
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            callers: Vec::new(),
+            call_info: IndexMap::from([(
+                "index".to_string(),
+                CallInfo {
+                    genotype: Some(genotype.into()),
+                    ..Default::default()
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn summaries() {
+        let mut karyotype = Karyotype::new(1_000_000);
+        for sv in [
+            sv("22", 18_631_366, 21_861_651, SvType::Del, "0/1"),
+            sv("1", 1, 2_000_000, SvType::Dup, "0/1"),
+            sv("21", 1, 48_129_895, SvType::Dup, "0/1"),
+            sv("2", 1_000, 10_000, SvType::Del, "0/1"),
+        ] {
+            karyotype.add(&sv, &sv.call_info);
+        }
+
+        let summaries = karyotype.summaries(GenomeRelease::Grch37);
+
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(
+            summaries[0].iscn,
+            "arr[GRCh37] 1(1_2000000)x3,21(1_48129895)x3,22(18631366_21861651)x1"
+        );
+        assert_eq!(
+            summaries[0]
+                .chromosomes
+                .iter()
+                .map(|c| (c.chrom.as_str(), c.likely_aneuploid))
+                .collect::<Vec<_>>(),
+            vec![("1", false), ("21", true), ("22", false)]
+        );
+    }
+
+    #[test]
+    fn iscn_normal() {
+        assert_eq!(
+            super::iscn(GenomeRelease::Grch38, &[]),
+            "arr[GRCh38] (1-22)x2"
+        );
+        assert_eq!(
+            super::iscn(
+                GenomeRelease::Grch38,
+                &[CnvEvent {
+                    chrom: "X".into(),
+                    start: 1,
+                    end: 100,
+                    copy_number: 0
+                }]
+            ),
+            "arr[GRCh38] X(1_100)x0"
+        );
+    }
+}
@@ -10,6 +10,7 @@ use tracing::info;
 use crate::{
     common::{trace_rss_now, GenomeRelease, CHROMS},
     pbs,
+    strucvars::txt_to_bin::{info::check_schema_version, masked},
 };
 
 use super::{
@@ -40,6 +41,35 @@ pub struct FetchRecordsResult {
 }
 
 impl MaskedDb {
+    /// Construct an empty database, e.g., for a missing optional track.
+    pub fn empty() -> Self {
+        let mut trees = CHROMS
+            .iter()
+            .map(|_| IntervalTree::new())
+            .collect::<Vec<_>>();
+        trees.iter_mut().for_each(|tree| tree.index());
+        Self {
+            records: CHROMS.iter().map(|_| Vec::new()).collect(),
+            trees,
+        }
+    }
+
+    /// Whether the 0-based half-open `range` on `chrom` overlaps with a masked region.
+    ///
+    /// Unknown chromosomes do not overlap.
+    pub fn overlaps(
+        &self,
+        chrom_map: &IndexMap<String, usize>,
+        chrom: &str,
+        range: std::ops::Range<i32>,
+    ) -> bool {
+        chrom_map
+            .get(chrom)
+            .and_then(|chrom_idx| self.trees.get(*chrom_idx))
+            .map(|tree| !tree.find(range).is_empty())
+            .unwrap_or_default()
+    }
+
     /// Fetch records that overlap with `genomic_region`.
     ///
     /// # Arguments
@@ -144,6 +174,7 @@ pub fn load_masked_db_records(path: &Path) -> Result<MaskedDb, anyhow::Error> {
     let masked_db =
         pbs::varfish::v1::strucvars::bgdb::MaskedDatabase::decode(std::io::Cursor::new(fcontents))
             .map_err(|e| anyhow::anyhow!("error decoding {:?}: {}", &path, e))?;
+    check_schema_version(path, masked_db.provenance.as_ref(), masked::SCHEMA_VERSION)?;
 
     for record in masked_db.records.into_iter() {
         let chrom_no = record.chrom_no as usize;
@@ -169,10 +200,24 @@ pub fn load_masked_db_records(path: &Path) -> Result<MaskedDb, anyhow::Error> {
     Ok(result)
 }
 
+/// Load the masked regions from `path` if it exists and return an empty database otherwise.
+///
+/// Used for the tracks that older database directories do not have.
+pub fn load_optional_masked_db_records(path: &Path) -> Result<MaskedDb, anyhow::Error> {
+    if path.exists() {
+        load_masked_db_records(path)
+    } else {
+        tracing::info!("optional masked db {:?} does not exist, skipping", path);
+        Ok(MaskedDb::empty())
+    }
+}
+
 /// Enumeration of all masked region databases.
 pub enum MaskedRegionType {
     Repeat,
     SegDup,
+    LowComplexity,
+    Blacklist,
 }
 
 /// Bundle of all masked region databases (including in-house).
@@ -180,6 +225,10 @@ pub enum MaskedRegionType {
 pub struct MaskedDbBundle {
     pub repeat: MaskedDb,
     pub segdup: MaskedDb,
+    /// Low-complexity regions, optional.
+    pub low_complexity: MaskedDb,
+    /// ENCODE blacklist regions, optional.
+    pub blacklist: MaskedDb,
 }
 
 /// Store masked region database counts for a structural variant.
@@ -187,6 +236,17 @@ pub struct MaskedDbBundle {
 pub struct MaskedBreakpointCount {
     pub repeat: u32,
     pub segdup: u32,
+    /// Breakpoints in low-complexity regions, omitted if zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub low_complexity: u32,
+    /// Breakpoints in ENCODE blacklist regions, omitted if zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub blacklist: u32,
+}
+
+/// Helper for skipping zero counts in serialization.
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 impl MaskedDbBundle {
@@ -199,6 +259,10 @@ impl MaskedDbBundle {
         match db_type {
             MaskedRegionType::Repeat => self.repeat.fetch_records(genome_range, chrom_map),
             MaskedRegionType::SegDup => self.segdup.fetch_records(genome_range, chrom_map),
+            MaskedRegionType::LowComplexity => {
+                self.low_complexity.fetch_records(genome_range, chrom_map)
+            }
+            MaskedRegionType::Blacklist => self.blacklist.fetch_records(genome_range, chrom_map),
         }
     }
 
@@ -210,6 +274,8 @@ impl MaskedDbBundle {
         MaskedBreakpointCount {
             repeat: self.repeat.masked_breakpoint_count(chrom_map, sv),
             segdup: self.segdup.masked_breakpoint_count(chrom_map, sv),
+            low_complexity: self.low_complexity.masked_breakpoint_count(chrom_map, sv),
+            blacklist: self.blacklist.masked_breakpoint_count(chrom_map, sv),
         }
     }
 }
@@ -232,6 +298,19 @@ pub fn load_masked_dbs(
                 .join(format!("{}/features/masked_segdup.bin", genome_release))
                 .as_path(),
         )?,
+        low_complexity: load_optional_masked_db_records(
+            Path::new(path_db)
+                .join(format!(
+                    "{}/features/masked_low_complexity.bin",
+                    genome_release
+                ))
+                .as_path(),
+        )?,
+        blacklist: load_optional_masked_db_records(
+            Path::new(path_db)
+                .join(format!("{}/features/masked_blacklist.bin", genome_release))
+                .as_path(),
+        )?,
     };
 
     Ok(result)
@@ -304,6 +383,8 @@ mod test {
             callers: Vec::new(),
             strand_orientation:
                 mehari::annotate::strucvars::csq::interface::StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             call_info: Default::default(),
         };
 
@@ -323,6 +404,7 @@ mod test {
                 start: 1,
                 stop: 2,
             }],
+            provenance: None,
         };
 
         let mut buf = Vec::with_capacity(data.encoded_len());
@@ -2,11 +2,19 @@
 
 pub mod bgdbs;
 pub mod clinvar;
+pub mod domains;
+pub mod exons;
+pub mod fusion;
 pub mod genes;
 pub mod interpreter;
+pub mod karyotype;
 pub mod masked;
+pub mod output;
 pub mod pathogenic;
+pub mod ranking;
+pub mod regulatory;
 pub mod schema;
+pub mod syndromes;
 pub mod tads;
 
 use std::{
@@ -37,6 +45,7 @@ use uuid::Uuid;
 use crate::{
     common::{build_chrom_map, numeric_gene_id, trace_rss_now},
     common::{GenomeRelease, TadSet as TadSetChoice},
+    pbs::varfish::v1::strucvars::output as pbs_output,
     strucvars::query::{
         interpreter::QueryInterpreter, pathogenic::Record as KnownPathogenicRecord,
         schema::CaseQuery, schema::StructuralVariant,
@@ -47,10 +56,14 @@ use self::{
     bgdbs::{load_bg_dbs, BgDbBundle, BgDbOverlaps},
     clinvar::{load_clinvar_sv, ClinvarSv},
     genes::{load_gene_db, GeneDb},
+    karyotype::Karyotype,
     masked::{load_masked_dbs, MaskedBreakpointCount, MaskedDbBundle},
+    output::WithResultPayload,
     pathogenic::{load_patho_dbs, PathoDbBundle},
+    regulatory::{load_regulatory_db, RegulatoryDb},
     schema::{CallInfo, SvSubType, SvType, TranscriptEffect},
-    tads::{load_tads, TadSetBundle},
+    syndromes::{load_syndrome_db, SyndromeDb},
+    tads::{load_tads, TadDisruption, TadSetBundle},
 };
 
 /// Length of the upstream/downstream region.
@@ -63,18 +76,32 @@ pub struct Args {
     /// Genome release to assume.
     #[arg(long, value_enum)]
     pub genome_release: GenomeRelease,
-    /// Path to worker database to use for querying.
+    /// Result set ID.
+    #[arg(long)]
+    pub result_set_id: Option<String>,
+    /// The case UUID.
+    #[arg(long)]
+    pub case_uuid: Option<uuid::Uuid>,
+    /// Path to worker database to use for querying, may be a remote location.
     #[arg(long, required = true)]
     pub path_db: String,
     /// Path to query JSON file.
-    #[arg(long, required = true)]
-    pub path_query_json: String,
+    #[arg(long, required_unless_present = "path_cases")]
+    pub path_query_json: Option<String>,
     /// Path to input TSV file.
-    #[arg(long, required = true)]
-    pub path_input: String,
+    #[arg(long, required_unless_present = "path_cases")]
+    pub path_input: Option<String>,
     /// Path to the output TSV file.
-    #[arg(long, required = true)]
-    pub path_output: String,
+    #[arg(long, required_unless_present = "path_cases")]
+    pub path_output: Option<String>,
+    /// Path to TSV file with the columns `case_uuid`, `path_query_json`, `path_input`, and
+    /// `path_output` (and optionally `path_karyotype`) for querying multiple cases with a
+    /// single pass of database loading.
+    #[arg(
+        long,
+        conflicts_with_all = ["case_uuid", "path_query_json", "path_input", "path_output", "path_karyotype"]
+    )]
+    pub path_cases: Option<String>,
 
     /// Optional maximal number of total records to write out.
     #[arg(long)]
@@ -92,14 +119,71 @@ pub struct Args {
     /// Maximal distance to TAD to consider.
     #[arg(long, default_value_t = 10_000)]
     pub max_tad_distance: i32,
+    /// Optional path to JSONL file to write the karyotype-style summary of large CNVs to.
+    #[arg(long)]
+    pub path_karyotype: Option<String>,
+    /// Minimal size of CNVs to include in the karyotype-style summary.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub karyotype_min_size: i32,
     /// Optional seed for RNG.
     #[arg(long)]
     pub rng_seed: Option<u64>,
 }
 
+/// Query input and output of one case.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct CaseInput {
+    /// The case UUID.
+    pub case_uuid: Option<uuid::Uuid>,
+    /// Path to query JSON file.
+    pub path_query_json: String,
+    /// Path to input VCF file.
+    pub path_input: String,
+    /// Path to the output TSV file.
+    pub path_output: String,
+    /// Optional path to the karyotype-style summary JSONL file.
+    #[serde(default)]
+    pub path_karyotype: Option<String>,
+}
+
+impl CaseInput {
+    /// Return the cases to query from the command line `args`.
+    pub fn load_all(args: &Args) -> Result<Vec<Self>, anyhow::Error> {
+        if let Some(path_cases) = &args.path_cases {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(true)
+                .delimiter(b'\t')
+                .from_path(path_cases)
+                .map_err(|e| anyhow::anyhow!("could not open cases file {}: {}", path_cases, e))?;
+            reader
+                .deserialize()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| anyhow::anyhow!("could not parse cases file {}: {}", path_cases, e))
+        } else {
+            let missing = |name: &str| anyhow::anyhow!("missing argument --{}", name);
+            Ok(vec![Self {
+                case_uuid: args.case_uuid,
+                path_query_json: args
+                    .path_query_json
+                    .clone()
+                    .ok_or_else(|| missing("path-query-json"))?,
+                path_input: args
+                    .path_input
+                    .clone()
+                    .ok_or_else(|| missing("path-input"))?,
+                path_output: args
+                    .path_output
+                    .clone()
+                    .ok_or_else(|| missing("path-output"))?,
+                path_karyotype: args.path_karyotype.clone(),
+            }])
+        }
+    }
+}
+
 /// Gene information.
 #[derive(Debug, Default, Serialize)]
-struct Gene {
+pub(crate) struct Gene {
     /// Gene symbol
     symbol: Option<String>,
     /// ENSEMBL gene ID
@@ -112,24 +196,32 @@ struct Gene {
     is_acmg: bool,
     /// Whether the gene is linked to an OMIM disease.
     is_disease_gene: bool,
+    /// Annotation from the optional gene annotation files.
+    #[serde(flatten)]
+    annotations: genes::GeneAnnotations,
 }
 
 /// Explanation of transcript effect per individual gene.
 #[derive(Debug, Default, Serialize)]
-struct GeneTranscriptEffects {
+pub(crate) struct GeneTranscriptEffects {
     /// Identifying gene.
     gene: Gene,
     /// Transcript effects for the gene.
     transcript_effects: Vec<TranscriptEffect>,
 }
 
-/// The structured result information of the result record.
-#[derive(Debug, Default, Serialize)]
-struct ResultPayload {
+/// The result information collected while processing a record.
+///
+/// This is converted into the protobuf `VariantAnnotation` for output, see
+/// the `output` module.
+#[derive(Debug, Default)]
+pub(crate) struct ResultPayload {
     /// The name of the calling tool.
     callers: Vec<String>,
     /// The overlapping RCVs
     clinvar_ovl_rcvs: Vec<String>,
+    /// The overlapping ClinVar SVs with match information.
+    clinvar_matches: Vec<clinvar::ClinvarSvMatch>,
     /// The directly overlapping genes.
     ovl_genes: Vec<Gene>,
     /// Genes that are not directly overlapping but contained in overlapping
@@ -137,6 +229,8 @@ struct ResultPayload {
     tad_genes: Vec<Gene>,
     /// Overlapping known pathogenic SV records.
     known_pathogenic: Vec<KnownPathogenicRecord>,
+    /// Matching recurrent microdeletion/microduplication syndromes.
+    cnv_syndromes: Vec<syndromes::SyndromeMatch>,
     /// Information about the call support from the structural variant.
     call_info: IndexMap<String, CallInfo>,
     /// Whether there is an overlap with a disease gene in the overlap.
@@ -153,12 +247,32 @@ struct ResultPayload {
     tad_boundary_distance: Option<u32>,
     /// Effects on the transcripts per gene.
     tx_effects: Vec<GeneTranscriptEffects>,
+    /// Genes at the second position of break-ends.
+    bnd_partner_genes: Vec<Gene>,
+    /// Candidate gene fusions of break-ends.
+    fusion_candidates: Vec<fusion::FusionCandidate>,
+    /// Affected exons per transcript of deletions and duplications.
+    exon_impacts: Vec<exons::TranscriptExonImpact>,
+    /// Disrupted protein domains of the overlapping genes.
+    disrupted_domains: Vec<domains::Record>,
+    /// Overlapping genes with the query HPO terms annotated.
+    hpo_matches: Vec<genes::HpoGeneMatch>,
+    /// Disrupted regulatory elements.
+    regulatory_elements: Vec<regulatory::Record>,
+    /// Disruption of TAD boundaries.
+    tad_disruption: TadDisruption,
+    /// Whether the DDG2P index is het. and all overlapping DDG2P genes are biallelic.
+    ddg2p_second_hit_required: bool,
+    /// AnnotSV-style ranking of the SV.
+    ranking: ranking::Ranking,
 }
 
 /// A result record from the query.
 #[derive(Debug, Default, Serialize)]
 struct ResultRecord {
     sodar_uuid: Uuid,
+    case_uuid: Uuid,
+    set_id: Option<String>,
     release: String,
     chromosome: String,
     chromosome_no: i32,
@@ -188,6 +302,7 @@ fn resolve_hgvs_id(gene_db: &GeneDb, hgvs_id: &str) -> Vec<Gene> {
                     hgnc_id: Some(record.hgnc_id.clone()),
                     is_acmg: gene_db.acmg.contains(record.entrez_id),
                     is_disease_gene: gene_db.mim2gene.contains(record.entrez_id),
+                    annotations: gene_db.annotations(&record.hgnc_id),
                 }
             })
             .collect()
@@ -199,6 +314,7 @@ fn resolve_hgvs_id(gene_db: &GeneDb, hgvs_id: &str) -> Vec<Gene> {
             hgnc_id: Some(hgvs_id.to_string()),
             is_acmg: false,
             is_disease_gene: false,
+            annotations: gene_db.annotations(hgvs_id),
         }]
     }
 }
@@ -211,11 +327,13 @@ struct QueryStats {
     pub by_sv_type: BTreeMap<SvType, usize>,
 }
 
-/// Run the `args.path_input` VCF file and run through the given `interpreter` writing to
-/// `args.path_output`.
+/// Run the `case.path_input` VCF file and run through the given `interpreter` writing to
+/// `case.path_output`.
+#[allow(clippy::too_many_arguments)]
 async fn run_query(
     interpreter: &QueryInterpreter,
     args: &Args,
+    case: &CaseInput,
     dbs: &InMemoryDbs,
     mehari_tx_db: &TxSeqDatabase,
     mehari_tx_idx: &TxIntervalTrees,
@@ -225,9 +343,13 @@ async fn run_query(
     let chrom_to_chrom_no = &CHROM_TO_CHROM_NO;
     let chrom_map = build_chrom_map();
     let mut stats = QueryStats::default();
+    let mut karyotype = case
+        .path_karyotype
+        .as_ref()
+        .map(|_| Karyotype::new(args.karyotype_min_size));
 
     // Open VCF file, create reader, and read header.
-    let mut input_reader = open_vcf_reader(&args.path_input).await?;
+    let mut input_reader = open_vcf_reader(&case.path_input).await?;
     let input_header = input_reader.read_header().await?;
 
     // Create output TSV writer.
@@ -235,7 +357,7 @@ async fn run_query(
         .has_headers(true)
         .delimiter(b'\t')
         .quote_style(csv::QuoteStyle::Never)
-        .from_path(&args.path_output)?;
+        .from_path(&case.path_output)?;
 
     // Read through input records using the query interpreter as a filter
     let mut record_buf = vcf::variant::RecordBuf::default();
@@ -243,7 +365,7 @@ async fn run_query(
         let bytes_read = input_reader
             .read_record_buf(&input_header, &mut record_buf)
             .await
-            .map_err(|e| anyhow::anyhow!("problem reading VCF file {}: {}", &args.path_input, e))?;
+            .map_err(|e| anyhow::anyhow!("problem reading VCF file {}: {}", &case.path_input, e))?;
         if bytes_read == 0 {
             break; // EOF
         }
@@ -314,7 +436,76 @@ async fn run_query(
             },
         )?;
 
-        if passes.pass_all {
+        let passes_regulatory = passes.pass_all && {
+            result_payload.regulatory_elements = dbs.regulatory.overlapping_records(
+                &record_sv,
+                &chrom_map,
+                interpreter.query.regulatory_overlap,
+                interpreter.query.regulatory_vista_validation,
+            );
+            interpreter.passes_regulatory(result_payload.regulatory_elements.len())
+        };
+
+        // Get genes in overlapping TADs and the disruption of TAD boundaries
+        let tad_hgnc_ids = if passes_regulatory {
+            let hgnc_ids: HashSet<_> = HashSet::from_iter(ovl_hgnc_ids.iter());
+            let tads = dbs
+                .tad_sets
+                .overlapping_tads(TadSetChoice::Hesc, &record_sv, &chrom_map);
+            let mut tad_hgvs_ids = Vec::new();
+            tads.iter()
+                .map(|tad| {
+                    overlapping_hgnc_ids(
+                        mehari_tx_db,
+                        mehari_tx_idx,
+                        chrom_idx,
+                        (tad.begin - 1)..tad.end,
+                    )
+                })
+                .for_each(|mut v| tad_hgvs_ids.append(&mut v));
+            let tad_hgvs_ids: HashSet<_> = HashSet::from_iter(tad_hgvs_ids.into_iter());
+            let mut tad_hgvs_ids = Vec::from_iter(tad_hgvs_ids);
+            tad_hgvs_ids.retain(|hgvs_id| !hgnc_ids.contains(hgvs_id));
+            tad_hgvs_ids.sort();
+            tad_hgvs_ids
+        } else {
+            Vec::new()
+        };
+        let passes_tads = passes_regulatory && {
+            result_payload.tad_disruption = TadDisruption::new(
+                dbs.tad_sets
+                    .spanned_boundaries(TadSetChoice::Hesc, &record_sv, &chrom_map),
+                tad_hgnc_ids.len(),
+            );
+            interpreter.passes_tad_disruption(&result_payload.tad_disruption)
+        };
+        let passes_clinvar = passes_tads && {
+            result_payload.clinvar_matches = dbs.clinvar_sv.overlapping_records(
+                &record_sv,
+                &chrom_map,
+                interpreter.query.clinvar_sv_min_pathogenicity,
+                interpreter.query.clinvar_sv_min_overlap,
+            );
+            interpreter.passes_clinvar(result_payload.clinvar_matches.len())
+        };
+        let passes_ddg2p = passes_clinvar && {
+            let ddg2p_records = ovl_hgnc_ids
+                .iter()
+                .flat_map(|hgnc_id| dbs.genes.ddg2p.get(hgnc_id))
+                .collect::<Vec<_>>();
+            result_payload.ddg2p_second_hit_required =
+                interpreter.requires_ddg2p_second_hit(&record_sv, &ddg2p_records);
+            interpreter.passes_allelic_requirement(&record_sv, &ddg2p_records)
+        };
+        let passes_hpo = passes_ddg2p && {
+            result_payload.hpo_matches = dbs
+                .genes
+                .hpo
+                .matches(&ovl_hgnc_ids, &interpreter.query.hpo_terms);
+            interpreter.passes_hpo(result_payload.hpo_matches.len())
+        };
+
+        if passes_hpo {
             if record_sv.sv_type != SvType::Ins && record_sv.sv_type != SvType::Bnd {
                 result_payload.sv_length = Some((record_sv.end - record_sv.pos + 1) as u32);
             }
@@ -328,49 +519,39 @@ async fn run_query(
                 call_info.effective_genotype = *passes.effective.get(sample).expect("must exist");
                 call_info.matched_gt_criteria = Some(compatible.clone());
             }
+            // Collect large CNVs for the karyotype summary if requested.
+            if let Some(karyotype) = karyotype.as_mut() {
+                karyotype.add(&record_sv, &result_payload.call_info);
+            }
+            // Flag mosaic CNVs if requested.
+            if interpreter.query.mosaic.is_some() {
+                for call_info in result_payload.call_info.values_mut() {
+                    call_info.mosaic_fraction =
+                        call_info.estimate_mosaic_fraction(record_sv.sv_type);
+                }
+            }
 
             // Count passing record in statistics
             stats.count_passed += 1;
             *stats.by_sv_type.entry(record_sv.sv_type).or_default() += 1;
 
-            // Get overlaps with known pathogenic SVs and ClinVar SVs
-            result_payload.known_pathogenic =
-                dbs.patho_dbs.overlapping_records(&record_sv, &chrom_map);
-            result_payload.clinvar_ovl_rcvs = dbs
-                .clinvar_sv
-                .overlapping_rcvs(
-                    &record_sv,
-                    &chrom_map,
-                    interpreter.query.clinvar_sv_min_pathogenicity,
-                    interpreter.query.clinvar_sv_min_overlap,
-                )
-                .into_iter()
-                .map(|rcv| format!("RCV{rcv:09}"))
+            // Get overlaps with known pathogenic SVs and RCVs of the ClinVar SVs
+            result_payload.known_pathogenic = dbs.patho_dbs.overlapping_records(
+                &record_sv,
+                &chrom_map,
+                &interpreter.query.svdb_sv_type_overlap,
+            );
+            if let Some(min_overlap) = interpreter.query.cnv_syndrome_min_overlap {
+                result_payload.cnv_syndromes =
+                    dbs.syndromes
+                        .matching_records(&record_sv, &chrom_map, min_overlap);
+            }
+            result_payload.clinvar_ovl_rcvs = result_payload
+                .clinvar_matches
+                .iter()
+                .map(|m| format!("RCV{:09}", m.record.rcv))
                 .collect();
 
-            // Get genes in overlapping TADs
-            let tad_hgnc_ids = {
-                let hgnc_ids: HashSet<_> = HashSet::from_iter(ovl_hgnc_ids.iter());
-                let tads =
-                    dbs.tad_sets
-                        .overlapping_tads(TadSetChoice::Hesc, &record_sv, &chrom_map);
-                let mut tad_hgvs_ids = Vec::new();
-                tads.iter()
-                    .map(|tad| {
-                        overlapping_hgnc_ids(
-                            mehari_tx_db,
-                            mehari_tx_idx,
-                            chrom_idx,
-                            (tad.begin - 1)..tad.end,
-                        )
-                    })
-                    .for_each(|mut v| tad_hgvs_ids.append(&mut v));
-                let tad_hgvs_ids: HashSet<_> = HashSet::from_iter(tad_hgvs_ids.into_iter());
-                let mut tad_hgvs_ids = Vec::from_iter(tad_hgvs_ids);
-                tad_hgvs_ids.retain(|hgvs_id| !hgnc_ids.contains(hgvs_id));
-                tad_hgvs_ids.sort();
-                tad_hgvs_ids
-            };
             result_payload.tad_boundary_distance =
                 dbs.tad_sets
                     .boundary_dist(TadSetChoice::Hesc, &record_sv, &chrom_map);
@@ -394,6 +575,53 @@ async fn run_query(
                 .tad_genes
                 .iter()
                 .any(|gene| gene.is_disease_gene);
+            result_payload.disrupted_domains =
+                dbs.genes.domains.disrupted_domains(&record_sv, &chrom_map);
+
+            // Get genes at the partner position and candidate fusions of break-ends
+            if record_sv.sv_type == SvType::Bnd {
+                let chrom2 = chrom_to_acc
+                    .get(&annonars::common::cli::canonicalize(
+                        record_sv.chrom2.as_ref().unwrap_or(&record_sv.chrom),
+                    ))
+                    .and_then(|chrom2| mehari_tx_idx.contig_to_idx.get(chrom2));
+                if let Some(chrom_idx2) = chrom2 {
+                    let mut partner_hgnc_ids = overlapping_hgnc_ids(
+                        mehari_tx_db,
+                        mehari_tx_idx,
+                        *chrom_idx2,
+                        (record_sv.end - 1)..record_sv.end,
+                    );
+                    partner_hgnc_ids.sort();
+                    partner_hgnc_ids.dedup();
+                    partner_hgnc_ids.iter().for_each(|hgnc_id| {
+                        result_payload
+                            .bnd_partner_genes
+                            .append(&mut resolve_hgvs_id(&dbs.genes, hgnc_id))
+                    });
+                }
+                result_payload.fusion_candidates = fusion::fusion_candidates(
+                    &record_sv,
+                    mehari_tx_db,
+                    mehari_tx_idx,
+                    &dbs.genes,
+                    chrom_to_acc,
+                );
+            }
+
+            // Get affected exons of deletions and duplications
+            if matches!(record_sv.sv_type, SvType::Del | SvType::Dup | SvType::Cnv) {
+                result_payload.exon_impacts = exons::exon_impacts(
+                    &record_sv,
+                    mehari_tx_db,
+                    mehari_tx_idx,
+                    &dbs.genes,
+                    chrom_to_acc,
+                );
+            }
+
+            result_payload.ranking =
+                ranking::rank(record_sv.sv_type, &result_payload, &dbs.genes.scores);
 
             if let Some(max_results) = args.max_results {
                 if stats.count_total > max_results {
@@ -439,6 +667,8 @@ async fn run_query(
             csv_writer
                 .serialize(&ResultRecord {
                     sodar_uuid: Uuid::from_bytes(uuid_buf),
+                    case_uuid: case.case_uuid.unwrap_or_default(),
+                    set_id: args.result_set_id.clone(),
                     release: match args.genome_release {
                         GenomeRelease::Grch37 => "GRCh37".into(),
                         GenomeRelease::Grch38 => "GRCh38".into(),
@@ -462,13 +692,19 @@ async fn run_query(
                     pe_orientation: record_sv.strand_orientation,
                     sv_type: record_sv.sv_type,
                     sv_sub_type: record_sv.sv_sub_type,
-                    payload: serde_json::to_string(&result_payload)
-                        .map_err(|e| anyhow::anyhow!("could not serialize payload: {}", e))?,
+                    payload: serde_json::to_string(
+                        &pbs_output::VariantAnnotation::with_result_payload(&result_payload),
+                    )
+                    .map_err(|e| anyhow::anyhow!("could not serialize payload: {}", e))?,
                 })
                 .map_err(|e| anyhow::anyhow!("could not write record: {}", e))?;
         }
     }
 
+    if let (Some(karyotype), Some(path_karyotype)) = (karyotype, case.path_karyotype.as_ref()) {
+        karyotype::write_summaries(path_karyotype, &karyotype.summaries(args.genome_release))?;
+    }
+
     Ok(stats)
 }
 
@@ -487,6 +723,7 @@ fn construct_gene(entrez_id: u32, gene_db: &GeneDb) -> Gene {
         hgnc_id: Some(record.hgnc_id.clone()),
         is_acmg: gene_db.acmg.contains(record.entrez_id),
         is_disease_gene: gene_db.mim2gene.contains(record.entrez_id),
+        annotations: gene_db.annotations(&record.hgnc_id),
     }
 }
 
@@ -832,6 +1069,8 @@ pub struct InMemoryDbs {
     pub masked: MaskedDbBundle,
     pub genes: GeneDb,
     pub clinvar_sv: ClinvarSv,
+    pub regulatory: RegulatoryDb,
+    pub syndromes: SyndromeDb,
 }
 
 /// Translate gene allow list to gene identifiers from in-memory dbs.
@@ -910,6 +1149,8 @@ pub fn load_databases(
         masked: load_masked_dbs(path_worker_db, genome_release)?,
         genes: load_gene_db(path_worker_db, genome_release)?,
         clinvar_sv: load_clinvar_sv(path_worker_db, genome_release)?,
+        regulatory: load_regulatory_db(path_worker_db, genome_release)?,
+        syndromes: load_syndrome_db(path_worker_db, genome_release)?,
     })
 }
 
@@ -927,16 +1168,13 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
         rand::rngs::StdRng::from_entropy()
     };
 
-    tracing::info!("Loading query...");
-    let query: CaseQuery = serde_json::from_reader(File::open(&args.path_query_json)?)?;
-    tracing::info!(
-        "... done loading query = {}",
-        &serde_json::to_string(&query)?
-    );
+    let cases = CaseInput::load_all(args)?;
+    tracing::info!("Querying {} case(s)", cases.len());
 
     tracing::info!("Loading worker databases...");
     let before_loading = Instant::now();
-    let path_worker_db = format!("{}/worker", &args.path_db);
+    let path_db = crate::common::remote::localize(&args.path_db).await?;
+    let path_worker_db = format!("{}/worker", &path_db);
     let dbs = load_databases(&path_worker_db, args.genome_release, args.max_tad_distance)?;
     tracing::info!(
         "...done loading databases in {:?}",
@@ -949,7 +1187,7 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
     let before_loading = Instant::now();
     let path_mehari_tx_db = format!(
         "{}/mehari/{}/txs.bin.zst",
-        &args.path_db,
+        &path_db,
         &args.genome_release.to_string()
     );
     tracing::debug!("  path = {}", &path_mehari_tx_db);
@@ -978,41 +1216,63 @@ pub async fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), a
 
     trace_rss_now();
 
-    tracing::info!("Translating gene allow list...");
-    let hgvs_allowlist = if let Some(gene_allowlist) = &query.gene_allowlist {
-        if gene_allowlist.is_empty() {
-            None
+    for case in &cases {
+        tracing::info!("Loading query for {}...", &case.path_input);
+        let query: CaseQuery = serde_json::from_reader(File::open(&case.path_query_json)?)?;
+        tracing::info!(
+            "... done loading query = {}",
+            &serde_json::to_string(&query)?
+        );
+
+        tracing::info!("Translating gene allow list...");
+        let mut hgvs_allowlist = if let Some(gene_allowlist) = &query.gene_allowlist {
+            if gene_allowlist.is_empty() {
+                None
+            } else {
+                Some(translate_genes(gene_allowlist, &dbs))
+            }
         } else {
-            Some(translate_genes(gene_allowlist, &dbs))
+            None
+        };
+        if !query.gene_panels.is_empty() {
+            let panel_hgnc_ids = dbs
+                .genes
+                .panelapp
+                .panel_hgnc_ids(&query.gene_panels, query.gene_panel_min_confidence);
+            if panel_hgnc_ids.is_empty() {
+                warn!("no genes found for gene panels {:?}", &query.gene_panels);
+            }
+            hgvs_allowlist
+                .get_or_insert_with(HashSet::new)
+                .extend(panel_hgnc_ids);
         }
-    } else {
-        None
-    };
 
-    tracing::info!("Running queries...");
-    let before_query = Instant::now();
-    let query_stats = run_query(
-        &QueryInterpreter::new(query, hgvs_allowlist),
-        args,
-        &dbs,
-        &mehari_tx_db,
-        &mehari_tx_idx,
-        &chrom_to_acc,
-        &mut rng,
-    )
-    .await?;
-    tracing::info!("... done running query in {:?}", before_query.elapsed());
-    tracing::info!(
-        "summary: {} records passed out of {}",
-        query_stats.count_passed.separate_with_commas(),
-        query_stats.count_total.separate_with_commas()
-    );
-    tracing::info!("passing records by SV type");
-    for (sv_type, count) in query_stats.by_sv_type.iter() {
-        tracing::info!("{:?} -- {}", sv_type, count);
-    }
+        tracing::info!("Running queries...");
+        let before_query = Instant::now();
+        let query_stats = run_query(
+            &QueryInterpreter::new(query, hgvs_allowlist),
+            args,
+            case,
+            &dbs,
+            &mehari_tx_db,
+            &mehari_tx_idx,
+            &chrom_to_acc,
+            &mut rng,
+        )
+        .await?;
+        tracing::info!("... done running query in {:?}", before_query.elapsed());
+        tracing::info!(
+            "summary: {} records passed out of {}",
+            query_stats.count_passed.separate_with_commas(),
+            query_stats.count_total.separate_with_commas()
+        );
+        tracing::info!("passing records by SV type");
+        for (sv_type, count) in query_stats.by_sv_type.iter() {
+            tracing::info!("{:?} -- {}", sv_type, count);
+        }
 
-    trace_rss_now();
+        trace_rss_now();
+    }
 
     tracing::info!(
         "All of `strucvars query` completed in {:?}",
@@ -1032,20 +1292,273 @@ mod test {
         let args_common = Default::default();
         let args = super::Args {
             genome_release: crate::common::GenomeRelease::Grch37,
+            result_set_id: None,
+            case_uuid: None,
             path_db: "tests/strucvars/query/db".into(),
-            path_query_json: "tests/strucvars/query/Case_3.query.json".into(),
-            path_input: "tests/strucvars/query/Case_3.ingested.vcf".into(),
-            path_output,
+            path_query_json: Some("tests/strucvars/query/Case_3.query.json".into()),
+            path_input: Some("tests/strucvars/query/Case_3.ingested.vcf".into()),
+            path_output: Some(path_output.clone()),
+            path_cases: None,
             max_results: None,
             slack_bnd: 50,
             slack_ins: 50,
             min_overlap: 0.8,
             max_tad_distance: 10_000,
+            path_karyotype: None,
+            karyotype_min_size: 1_000_000,
             rng_seed: Some(42),
         };
         super::run(&args_common, &args).await?;
 
-        insta::assert_snapshot!(std::fs::read_to_string(args.path_output.as_str())?);
+        insta::assert_snapshot!(std::fs::read_to_string(path_output.as_str())?);
+
+        Ok(())
+    }
+
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn multiple_cases() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_cases = format!("{}/cases.tsv", tmpdir.to_string_lossy());
+        let paths_output = (1..=2)
+            .map(|i| format!("{}/out-{}.tsv", tmpdir.to_string_lossy(), i))
+            .collect::<Vec<_>>();
+        let mut cases = String::from("case_uuid\tpath_query_json\tpath_input\tpath_output\n");
+        for path_output in &paths_output {
+            cases.push_str(&format!(
+                "\ttests/strucvars/query/Case_3.query.json\t\
+                tests/strucvars/query/Case_3.ingested.vcf\t{}\n",
+                path_output
+            ));
+        }
+        std::fs::write(&path_cases, cases)?;
+
+        let args_common = Default::default();
+        let args = super::Args {
+            genome_release: crate::common::GenomeRelease::Grch37,
+            result_set_id: None,
+            case_uuid: None,
+            path_db: "tests/strucvars/query/db".into(),
+            path_query_json: None,
+            path_input: None,
+            path_output: None,
+            path_cases: Some(path_cases),
+            max_results: None,
+            slack_bnd: 50,
+            slack_ins: 50,
+            min_overlap: 0.8,
+            max_tad_distance: 10_000,
+            path_karyotype: None,
+            karyotype_min_size: 1_000_000,
+            rng_seed: Some(42),
+        };
+        super::run(&args_common, &args).await?;
+
+        let line_counts = paths_output
+            .iter()
+            .map(|path_output| Ok(std::fs::read_to_string(path_output)?.lines().count()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        assert_eq!(line_counts[0], line_counts[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn result_record_serialize() -> Result<(), anyhow::Error> {
+        use indexmap::IndexMap;
+        use mehari::annotate::strucvars::csq::interface::StrandOrientation;
+
+        use super::{
+            output::WithResultPayload as _, pbs_output, CallInfo, ResultPayload, ResultRecord,
+            SvSubType, SvType,
+        };
+
+        let result_payload = ResultPayload {
+            callers: vec!["Delly".into()],
+            sv_length: Some(1000),
+            call_info: IndexMap::from([(
+                "index".to_string(),
+                CallInfo {
+                    genotype: Some("0/1".into()),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let annotation = pbs_output::VariantAnnotation::with_result_payload(&result_payload);
+        let payload = serde_json::to_string(&annotation)?;
+
+        // The payload is the protobuf JSON representation and can be read back.
+        let value: serde_json::Value = serde_json::from_str(&payload)?;
+        assert_eq!(value["sv"]["callers"], serde_json::json!(["Delly"]));
+        assert_eq!(value["sv"]["svLength"], serde_json::json!(1000));
+        assert_eq!(
+            value["call"]["callInfos"][0]["sample"],
+            serde_json::json!("index")
+        );
+        assert_eq!(
+            serde_json::from_str::<pbs_output::VariantAnnotation>(&payload)?,
+            annotation
+        );
+
+        let mut csv_writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .delimiter(b'\t')
+            .quote_style(csv::QuoteStyle::Never)
+            .from_writer(Vec::new());
+        csv_writer.serialize(&ResultRecord {
+            sodar_uuid: uuid::Uuid::from_u128(1),
+            case_uuid: uuid::Uuid::from_u128(2),
+            set_id: Some("set-1".into()),
+            release: "GRCh37".into(),
+            chromosome: "1".into(),
+            chromosome_no: 1,
+            bin: 585,
+            chromosome2: "1".into(),
+            chromosome_no2: 1,
+            bin2: 0,
+            start: 100,
+            end: 1100,
+            pe_orientation: StrandOrientation::ThreeToFive,
+            sv_type: SvType::Del,
+            sv_sub_type: SvSubType::Del,
+            payload: payload.clone(),
+        })?;
+        let output = String::from_utf8(
+            csv_writer
+                .into_inner()
+                .map_err(|e| anyhow::anyhow!("could not flush output: {}", e))?,
+        )?;
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "sodar_uuid\tcase_uuid\tset_id\trelease\tchromosome\tchromosome_no\tbin\t\
+                 chromosome2\tchromosome_no2\tbin2\tstart\tend\tpe_orientation\tsv_type\t\
+                 sv_sub_type\tpayload"
+                    .to_string(),
+                format!(
+                    "00000000-0000-0000-0000-000000000001\t00000000-0000-0000-0000-000000000002\t\
+                     set-1\tGRCh37\t1\t1\t585\t1\t1\t0\t100\t1100\t3to5\tDEL\tDEL\t{}",
+                    payload
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// The annotations from the optional gene annotation files are written to the genes
+    /// in the output payload.
+    #[rstest::rstest]
+    #[case::clingen(
+        super::genes::GeneDb {
+            clingen: super::genes::ClingenDb {
+                records: [(
+                    "HGNC:1".to_string(),
+                    vec![super::genes::ClingenRecord {
+                        hgnc_id: "HGNC:1".into(),
+                        disease_label: "disease A".into(),
+                        mondo_id: "MONDO:0000001".into(),
+                        moi: "AD".into(),
+                        classification: super::genes::ClingenClassification::Definitive,
+                    }],
+                )]
+                .into(),
+            },
+            ..Default::default()
+        },
+        "clingen",
+        serde_json::json!([{
+            "diseaseLabel": "disease A",
+            "mondoId": "MONDO:0000001",
+            "moi": "AD",
+            "classification": "CLINGEN_CLASSIFICATION_DEFINITIVE",
+        }]),
+    )]
+    #[case::omim_phenotypes(
+        super::genes::GeneDb {
+            omim_phenotypes: super::genes::OmimPhenotypeDb {
+                records: [(
+                    "HGNC:1".to_string(),
+                    vec![super::genes::OmimPhenotypeRecord {
+                        hgnc_id: "HGNC:1".into(),
+                        omim_id: "100100".into(),
+                        label: "phenotype A".into(),
+                        inheritance: vec!["Autosomal dominant".into()],
+                    }],
+                )]
+                .into(),
+            },
+            ..Default::default()
+        },
+        "omimPhenotypes",
+        serde_json::json!([{
+            "omimId": "100100",
+            "label": "phenotype A",
+            "inheritance": ["Autosomal dominant"],
+        }]),
+    )]
+    #[case::gtex(
+        super::genes::GeneDb {
+            gtex: super::genes::GtexDb {
+                records: [(
+                    "HGNC:1".to_string(),
+                    vec![super::genes::GtexRecord {
+                        hgnc_id: "HGNC:1".into(),
+                        tissue: "Muscle - Skeletal".into(),
+                        median_tpm: 12.5,
+                    }],
+                )]
+                .into(),
+            },
+            ..Default::default()
+        },
+        "gtex",
+        serde_json::json!([{"tissue": "Muscle - Skeletal", "medianTpm": 12.5}]),
+    )]
+    #[case::mouse_phenotypes(
+        super::genes::GeneDb {
+            mouse_phenotypes: super::genes::MousePhenotypeDb {
+                records: [(
+                    "HGNC:1".to_string(),
+                    vec![super::genes::MousePhenotypeRecord {
+                        hgnc_id: "HGNC:1".into(),
+                        mgi_id: "MGI:87853".into(),
+                        mp_id: "MP:0011100".into(),
+                        mp_label: "preweaning lethality, complete penetrance".into(),
+                        source: "IMPC".into(),
+                    }],
+                )]
+                .into(),
+            },
+            ..Default::default()
+        },
+        "mousePhenotypes",
+        serde_json::json!([{
+            "mgiId": "MGI:87853",
+            "mpId": "MP:0011100",
+            "mpLabel": "preweaning lethality, complete penetrance",
+            "source": "IMPC",
+        }]),
+    )]
+    fn gene_annotations_payload(
+        #[case] gene_db: super::genes::GeneDb,
+        #[case] field: &str,
+        #[case] expected: serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        use super::{output::WithResultPayload as _, pbs_output, ResultPayload};
+
+        let result_payload = ResultPayload {
+            ovl_genes: super::resolve_hgvs_id(&gene_db, "HGNC:1"),
+            ..Default::default()
+        };
+        let annotation = pbs_output::VariantAnnotation::with_result_payload(&result_payload);
+        let value: serde_json::Value = serde_json::from_str(&serde_json::to_string(&annotation)?)?;
+
+        let gene = &value["gene"]["ovlGenes"][0];
+        assert_eq!(gene["hgncId"], serde_json::json!("HGNC:1"));
+        assert_eq!(gene[field], expected);
 
         Ok(())
     }
@@ -0,0 +1,422 @@
+//! Construction of the protobuf output payload for `strucvars query`.
+//!
+//! This mirrors the layout of the `seqvars query` output where the payload
+//! is split into gene-, variant-, and call-related annotation.
+
+use crate::pbs::varfish::v1::strucvars::output as pbs_output;
+
+use super::ResultPayload;
+
+/// Trait for constructing output annotation from the collected result payload.
+pub(crate) trait WithResultPayload: Sized {
+    /// Construct the record from the given `payload`.
+    fn with_result_payload(payload: &ResultPayload) -> Self;
+}
+
+impl WithResultPayload for pbs_output::VariantAnnotation {
+    fn with_result_payload(payload: &ResultPayload) -> Self {
+        Self {
+            gene: Some(pbs_output::GeneRelatedAnnotation::with_result_payload(
+                payload,
+            )),
+            sv: Some(pbs_output::SvRelatedAnnotation::with_result_payload(
+                payload,
+            )),
+            call: Some(pbs_output::CallRelatedAnnotation::with_result_payload(
+                payload,
+            )),
+        }
+    }
+}
+
+/// Gene-related annotation.
+pub(crate) mod gene_related {
+    use super::*;
+    use crate::strucvars::query::{
+        exons::ExonImpact,
+        genes::{
+            ClingenClassification, ClingenRecord, Ddg2pRecord, GeneScoreRecord, GtexRecord,
+            MousePhenotypeRecord, OmimPhenotypeRecord, OrphanetRecord, PanelAppRecord,
+        },
+        schema::TranscriptEffect,
+        Gene,
+    };
+
+    impl From<&Gene> for pbs_output::Gene {
+        fn from(value: &Gene) -> Self {
+            Self {
+                symbol: value.symbol.clone(),
+                ensembl_id: value.ensembl_id.clone(),
+                entrez_id: value.entrez_id,
+                hgnc_id: value.hgnc_id.clone(),
+                is_acmg: value.is_acmg,
+                is_disease_gene: value.is_disease_gene,
+                clingen: value.annotations.clingen.iter().map(Into::into).collect(),
+                omim_phenotypes: value
+                    .annotations
+                    .omim_phenotypes
+                    .iter()
+                    .map(Into::into)
+                    .collect(),
+                orphanet: value.annotations.orphanet.iter().map(Into::into).collect(),
+                ddg2p: value.annotations.ddg2p.iter().map(Into::into).collect(),
+                panelapp: value.annotations.panelapp.iter().map(Into::into).collect(),
+                scores: value.annotations.scores.as_ref().map(Into::into),
+                gtex: value.annotations.gtex.iter().map(Into::into).collect(),
+                mouse_phenotypes: value
+                    .annotations
+                    .mouse_phenotypes
+                    .iter()
+                    .map(Into::into)
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<&MousePhenotypeRecord> for pbs_output::MousePhenotype {
+        fn from(value: &MousePhenotypeRecord) -> Self {
+            Self {
+                mgi_id: value.mgi_id.clone(),
+                mp_id: value.mp_id.clone(),
+                mp_label: value.mp_label.clone(),
+                source: value.source.clone(),
+            }
+        }
+    }
+
+    impl From<&GtexRecord> for pbs_output::GtexExpression {
+        fn from(value: &GtexRecord) -> Self {
+            Self {
+                tissue: value.tissue.clone(),
+                median_tpm: value.median_tpm,
+            }
+        }
+    }
+
+    impl From<&PanelAppRecord> for pbs_output::PanelAppMembership {
+        fn from(value: &PanelAppRecord) -> Self {
+            Self {
+                panel_id: value.panel_id.clone(),
+                panel_name: value.panel_name.clone(),
+                panel_version: value.panel_version.clone(),
+                confidence_level: value.confidence_level,
+            }
+        }
+    }
+
+    impl From<&Ddg2pRecord> for pbs_output::Ddg2pRecord {
+        fn from(value: &Ddg2pRecord) -> Self {
+            Self {
+                disease_name: value.disease_name.clone(),
+                confidence: value.confidence.clone(),
+                allelic_requirement: value.allelic_requirement.clone(),
+                mutation_consequence: value.mutation_consequence.clone(),
+            }
+        }
+    }
+
+    impl From<&GeneScoreRecord> for pbs_output::GeneScores {
+        fn from(value: &GeneScoreRecord) -> Self {
+            Self {
+                loeuf: value.loeuf,
+                clingen_hi: value.clingen_hi,
+                clingen_ts: value.clingen_ts,
+                domino: value.domino,
+                shet: value.shet,
+                depmap_essential_fraction: value.depmap_essential_fraction,
+            }
+        }
+    }
+
+    impl From<&OrphanetRecord> for pbs_output::OrphanetDisorder {
+        fn from(value: &OrphanetRecord) -> Self {
+            Self {
+                orpha_id: value.orpha_id.clone(),
+                label: value.label.clone(),
+                association_type: value.association_type.clone(),
+                prevalence_class: value.prevalence_class.clone(),
+            }
+        }
+    }
+
+    impl From<ClingenClassification> for pbs_output::ClingenClassification {
+        fn from(value: ClingenClassification) -> Self {
+            match value {
+                ClingenClassification::Definitive => Self::Definitive,
+                ClingenClassification::Strong => Self::Strong,
+                ClingenClassification::Moderate => Self::Moderate,
+                ClingenClassification::Limited => Self::Limited,
+                ClingenClassification::Disputed => Self::Disputed,
+                ClingenClassification::Refuted => Self::Refuted,
+                ClingenClassification::NoKnownDiseaseRelationship => {
+                    Self::NoKnownDiseaseRelationship
+                }
+            }
+        }
+    }
+
+    impl From<&OmimPhenotypeRecord> for pbs_output::OmimPhenotype {
+        fn from(value: &OmimPhenotypeRecord) -> Self {
+            Self {
+                omim_id: value.omim_id.clone(),
+                label: value.label.clone(),
+                inheritance: value.inheritance.clone(),
+            }
+        }
+    }
+
+    impl From<&ClingenRecord> for pbs_output::ClingenRecord {
+        fn from(value: &ClingenRecord) -> Self {
+            Self {
+                disease_label: value.disease_label.clone(),
+                mondo_id: value.mondo_id.clone(),
+                moi: value.moi.clone(),
+                classification: pbs_output::ClingenClassification::from(value.classification)
+                    as i32,
+            }
+        }
+    }
+
+    impl From<TranscriptEffect> for pbs_output::TranscriptEffect {
+        fn from(value: TranscriptEffect) -> Self {
+            match value {
+                TranscriptEffect::TranscriptVariant => Self::TranscriptVariant,
+                TranscriptEffect::ExonVariant => Self::ExonVariant,
+                TranscriptEffect::SpliceRegionVariant => Self::SpliceRegionVariant,
+                TranscriptEffect::IntronVariant => Self::IntronVariant,
+                TranscriptEffect::UpstreamVariant => Self::UpstreamVariant,
+                TranscriptEffect::DownstreamVariant => Self::DownstreamVariant,
+                TranscriptEffect::IntergenicVariant => Self::IntergenicVariant,
+            }
+        }
+    }
+
+    impl From<ExonImpact> for pbs_output::ExonImpact {
+        fn from(value: ExonImpact) -> Self {
+            match value {
+                ExonImpact::CompleteGene => Self::CompleteGene,
+                ExonImpact::Partial => Self::Partial,
+                ExonImpact::SingleExon => Self::SingleExon,
+                ExonImpact::IntronicOnly => Self::IntronicOnly,
+            }
+        }
+    }
+
+    impl WithResultPayload for pbs_output::GeneRelatedAnnotation {
+        fn with_result_payload(payload: &ResultPayload) -> Self {
+            Self {
+                ovl_genes: payload.ovl_genes.iter().map(Into::into).collect(),
+                tad_genes: payload.tad_genes.iter().map(Into::into).collect(),
+                ovl_disease_gene: payload.ovl_disease_gene,
+                tad_disease_gene: payload.tad_disease_gene,
+                tx_effects: payload
+                    .tx_effects
+                    .iter()
+                    .map(|tx_effects| pbs_output::GeneTranscriptEffects {
+                        gene: Some((&tx_effects.gene).into()),
+                        transcript_effects: tx_effects
+                            .transcript_effects
+                            .iter()
+                            .map(|effect| pbs_output::TranscriptEffect::from(*effect) as i32)
+                            .collect(),
+                    })
+                    .collect(),
+                bnd_partner_genes: payload.bnd_partner_genes.iter().map(Into::into).collect(),
+                fusion_candidates: payload
+                    .fusion_candidates
+                    .iter()
+                    .map(|candidate| pbs_output::FusionCandidate {
+                        gene_5p: Some((&candidate.gene_5p).into()),
+                        gene_3p: Some((&candidate.gene_3p).into()),
+                        in_frame: candidate.in_frame,
+                    })
+                    .collect(),
+                exon_impacts: payload
+                    .exon_impacts
+                    .iter()
+                    .map(|impact| pbs_output::TranscriptExonImpact {
+                        gene: Some((&impact.gene).into()),
+                        tx_ac: impact.tx_ac.clone(),
+                        exon_count: impact.exon_count,
+                        affected_exons: impact.affected_exons.clone(),
+                        impact: pbs_output::ExonImpact::from(impact.impact) as i32,
+                    })
+                    .collect(),
+                disrupted_domains: payload
+                    .disrupted_domains
+                    .iter()
+                    .map(|domain| pbs_output::ProteinDomain {
+                        hgnc_id: domain.hgnc_id.clone(),
+                        domain_id: domain.domain_id.clone(),
+                        name: domain.name.clone(),
+                    })
+                    .collect(),
+                hpo_matches: payload
+                    .hpo_matches
+                    .iter()
+                    .map(|hpo_match| pbs_output::HpoGeneMatch {
+                        hgnc_id: hpo_match.hgnc_id.clone(),
+                        hpo_ids: hpo_match.hpo_ids.clone(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
+
+/// Variant-related annotation.
+pub(crate) mod sv_related {
+    use super::*;
+    use crate::pbs::varfish::v1::strucvars::bgdb::SvType as PbSvType;
+    use crate::strucvars::query::schema::SvType;
+
+    /// Convert the internal SV type into the protobuf one.
+    fn sv_type_to_pb(sv_type: SvType) -> PbSvType {
+        match sv_type {
+            SvType::Del => PbSvType::Del,
+            SvType::Dup => PbSvType::Dup,
+            SvType::Inv => PbSvType::Inv,
+            SvType::Ins => PbSvType::Ins,
+            SvType::Bnd => PbSvType::Bnd,
+            SvType::Cnv => PbSvType::Cnv,
+        }
+    }
+
+    impl WithResultPayload for pbs_output::SvRelatedAnnotation {
+        fn with_result_payload(payload: &ResultPayload) -> Self {
+            Self {
+                callers: payload.callers.clone(),
+                clinvar_ovl_rcvs: payload.clinvar_ovl_rcvs.clone(),
+                known_pathogenic: payload
+                    .known_pathogenic
+                    .iter()
+                    .map(|record| pbs_output::KnownPathogenicRecord {
+                        begin: record.begin,
+                        end: record.end,
+                        sv_type: sv_type_to_pb(record.sv_type) as i32,
+                        id: record.id.clone(),
+                    })
+                    .collect(),
+                sv_length: payload.sv_length,
+                overlap_counts: Some(pbs_output::BgDbOverlapCounts {
+                    dbvar: payload.overlap_counts.dbvar,
+                    dgv: payload.overlap_counts.dgv,
+                    dgv_gs: payload.overlap_counts.dgv_gs,
+                    g1k: payload.overlap_counts.g1k,
+                    gnomad_genomes: payload.overlap_counts.gnomad_genomes,
+                    gnomad_exomes: payload.overlap_counts.gnomad_exomes,
+                    inhouse: payload.overlap_counts.inhouse,
+                    gnomad_genomes_af: payload.overlap_counts.gnomad_genomes_af,
+                    gnomad_genomes_n_homalt: payload.overlap_counts.gnomad_genomes_n_homalt,
+                    gnomad_genomes_mei: payload.overlap_counts.gnomad_genomes_mei,
+                    gnomad_genomes_mei_af: payload.overlap_counts.gnomad_genomes_mei_af,
+                }),
+                masked_breakpoints: Some(pbs_output::MaskedBreakpointCounts {
+                    repeat: payload.masked_breakpoints.repeat,
+                    segdup: payload.masked_breakpoints.segdup,
+                    low_complexity: payload.masked_breakpoints.low_complexity,
+                    blacklist: payload.masked_breakpoints.blacklist,
+                }),
+                tad_boundary_distance: payload.tad_boundary_distance,
+                regulatory_elements: payload
+                    .regulatory_elements
+                    .iter()
+                    .map(|record| pbs_output::RegulatoryElement {
+                        begin: record.begin,
+                        end: record.end,
+                        id: record.id.clone(),
+                        source: record.source.to_string(),
+                        element_type: record.element_type.clone(),
+                        linked_genes: record.linked_genes.clone(),
+                    })
+                    .collect(),
+                tad_disruption: Some(pbs_output::TadDisruption {
+                    boundaries_spanned: payload.tad_disruption.boundaries_spanned,
+                    genes_reassigned: payload.tad_disruption.genes_reassigned,
+                    score: payload.tad_disruption.score(),
+                }),
+                clinvar_matches: payload
+                    .clinvar_matches
+                    .iter()
+                    .map(|m| pbs_output::ClinvarSvMatch {
+                        vcv: format!("VCV{:09}", m.record.vcv),
+                        rcv: format!("RCV{:09}", m.record.rcv),
+                        pathogenicity: m.record.pathogenicity,
+                        review_status: m.record.review_status,
+                        overlap: m.overlap,
+                    })
+                    .collect(),
+                cnv_syndromes: payload
+                    .cnv_syndromes
+                    .iter()
+                    .map(|m| pbs_output::CnvSyndromeMatch {
+                        name: m.record.name.clone(),
+                        sv_type: sv_type_to_pb(m.record.sv_type) as i32,
+                        overlap: m.overlap,
+                    })
+                    .collect(),
+                ddg2p_second_hit_required: payload.ddg2p_second_hit_required,
+                ranking: Some(pbs_output::Ranking {
+                    score: payload.ranking.score,
+                    rank_class: payload.ranking.rank_class,
+                }),
+            }
+        }
+    }
+}
+
+/// Call-related annotation.
+pub(crate) mod call_related {
+    use super::*;
+    use crate::strucvars::query::schema::Genotype;
+
+    impl From<Genotype> for pbs_output::Genotype {
+        fn from(value: Genotype) -> Self {
+            match value {
+                Genotype::Hom => Self::Hom,
+                Genotype::Het => Self::Het,
+                Genotype::Variant => Self::Variant,
+                Genotype::Ref => Self::Ref,
+                Genotype::NonVariant => Self::NonVariant,
+            }
+        }
+    }
+
+    impl WithResultPayload for pbs_output::CallRelatedAnnotation {
+        fn with_result_payload(payload: &ResultPayload) -> Self {
+            Self {
+                call_infos: payload
+                    .call_info
+                    .iter()
+                    .map(|(sample, call_info)| pbs_output::SampleCallInfo {
+                        sample: sample.clone(),
+                        genotype: call_info.genotype.clone(),
+                        effective_genotype: call_info
+                            .effective_genotype
+                            .map(|gt| pbs_output::Genotype::from(gt) as i32),
+                        matched_gt_criteria: call_info
+                            .matched_gt_criteria
+                            .as_ref()
+                            .map(|gts| {
+                                gts.iter()
+                                    .map(|gt| pbs_output::Genotype::from(*gt) as i32)
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        quality: call_info.quality,
+                        paired_end_cov: call_info.paired_end_cov,
+                        paired_end_var: call_info.paired_end_var,
+                        split_read_cov: call_info.split_read_cov,
+                        split_read_var: call_info.split_read_var,
+                        copy_number: call_info.copy_number,
+                        average_normalized_cov: call_info.average_normalized_cov,
+                        point_count: call_info.point_count,
+                        average_mapping_quality: call_info.average_mapping_quality,
+                        copy_number_quality: call_info.copy_number_quality,
+                        mosaic_fraction: call_info.mosaic_fraction,
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
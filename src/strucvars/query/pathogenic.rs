@@ -14,8 +14,9 @@ use crate::{
 };
 
 use super::{
+    bgdbs::{reciprocal_overlap, BeginEnd},
     schema::ChromRange,
-    schema::{StructuralVariant, SvType},
+    schema::{StructuralVariant, SvType, SvTypeOverlap},
 };
 
 /// Alias for the interval tree that we use.
@@ -34,6 +35,16 @@ pub struct Record {
     pub id: String,
 }
 
+impl BeginEnd for Record {
+    fn begin(&self) -> i32 {
+        self.begin
+    }
+
+    fn end(&self) -> i32 {
+        self.end
+    }
+}
+
 /// Code for known pathogenic SV database overlappers.
 #[derive(Default, Debug)]
 pub struct PathoDb {
@@ -62,22 +73,37 @@ impl PathoDb {
             .collect()
     }
 
+    /// Return the records overlapping with `sv`.
+    ///
+    /// Insertions and break-ends are only considered if a slack is set for them in
+    /// `sv_type_overlap`.  For the other SV types, the minimal reciprocal overlap
+    /// from `sv_type_overlap` is applied, if set.
     pub fn overlapping_records(
         &self,
         sv: &StructuralVariant,
         chrom_map: &IndexMap<String, usize>,
+        sv_type_overlap: &SvTypeOverlap,
     ) -> Vec<Record> {
-        if sv.sv_type == SvType::Ins || sv.sv_type == SvType::Bnd {
-            return Vec::new();
-        }
+        let range = if sv.sv_type == SvType::Ins || sv.sv_type == SvType::Bnd {
+            match sv_type_overlap.slack(sv.sv_type) {
+                Some(slack) => (sv.pos - slack)..(sv.pos + slack),
+                None => return Vec::new(),
+            }
+        } else {
+            sv.pos.saturating_sub(1)..sv.end
+        };
+        let min_overlap = sv_type_overlap.min_overlap(sv.sv_type);
 
         let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
-        let range = sv.pos.saturating_sub(1)..sv.end;
-
         self.trees[chrom_idx]
-            .find(range)
+            .find(range.clone())
             .iter()
             .map(|e| &self.records[chrom_idx][*e.data() as usize])
+            .filter(|record| {
+                min_overlap.map_or(true, |min_overlap| {
+                    reciprocal_overlap(*record, &range) >= min_overlap
+                })
+            })
             .cloned()
             .collect()
     }
@@ -102,8 +128,9 @@ impl PathoDbBundle {
         &self,
         sv: &StructuralVariant,
         chrom_map: &IndexMap<String, usize>,
+        sv_type_overlap: &SvTypeOverlap,
     ) -> Vec<Record> {
-        self.mms.overlapping_records(sv, chrom_map)
+        self.mms.overlapping_records(sv, chrom_map, sv_type_overlap)
     }
 }
 
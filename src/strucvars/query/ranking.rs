@@ -0,0 +1,283 @@
+//! AnnotSV-style ranking of structural variants.
+//!
+//! The ranking score follows a simplified version of the ACMG/ClinGen CNV scoring
+//! as implemented by AnnotSV.  Points are given for the overlap with known
+//! pathogenic SVs, dosage sensitive and constrained genes, and the number of
+//! affected coding genes, and subtracted for SVs not affecting coding sequence
+//! and common SVs.  The score is then mapped to the classes 1 (benign) to 5
+//! (pathogenic).
+
+use std::collections::HashSet;
+
+use super::{
+    genes::{GeneScoreDb, GeneScoreRecord},
+    schema::{SvType, TranscriptEffect},
+    ResultPayload,
+};
+
+/// LOEUF threshold below which a gene is considered loss-of-function constrained.
+pub const MAX_CONSTRAINED_LOEUF: f32 = 0.35;
+/// sHet threshold from which a gene is considered loss-of-function constrained.
+pub const MIN_CONSTRAINED_SHET: f32 = 0.1;
+/// Allele frequency in gnomAD-SV at which an SV is considered common.
+pub const MIN_COMMON_AF: f32 = 0.01;
+
+/// Ranking of a structural variant.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Ranking {
+    /// The ranking score.
+    pub score: f32,
+    /// The class from 1 (benign) to 5 (pathogenic).
+    pub rank_class: u32,
+}
+
+impl Ranking {
+    /// Construct the ranking from the given `score`.
+    pub fn from_score(score: f32) -> Self {
+        let rank_class = if score >= 0.99 {
+            5
+        } else if score >= 0.9 {
+            4
+        } else if score > -0.9 {
+            3
+        } else if score > -0.99 {
+            2
+        } else {
+            1
+        };
+        Self { score, rank_class }
+    }
+}
+
+/// Return whether the gene with `scores` is dosage sensitive for `sv_type`.
+fn is_dosage_sensitive(sv_type: SvType, scores: &GeneScoreRecord) -> bool {
+    match sv_type {
+        SvType::Dup => scores.is_triplosensitive(),
+        SvType::Cnv => scores.is_haploinsufficient() || scores.is_triplosensitive(),
+        _ => scores.is_haploinsufficient(),
+    }
+}
+
+/// Return whether the gene with `scores` is loss-of-function constrained by LOEUF or sHet.
+fn is_lof_constrained(scores: &GeneScoreRecord) -> bool {
+    scores
+        .loeuf
+        .is_some_and(|loeuf| loeuf < MAX_CONSTRAINED_LOEUF)
+        || scores.shet.is_some_and(|shet| shet >= MIN_CONSTRAINED_SHET)
+}
+
+/// Return the points for the number of affected coding genes.
+fn gene_count_points(sv_type: SvType, count: usize) -> f32 {
+    let (medium, high) = match sv_type {
+        SvType::Del | SvType::Cnv => (25, 35),
+        SvType::Dup => (35, 50),
+        _ => return 0.0,
+    };
+    if count >= high {
+        0.9
+    } else if count >= medium {
+        0.45
+    } else {
+        0.0
+    }
+}
+
+/// Compute the ranking of an SV of `sv_type` from the collected `payload`.
+pub(crate) fn rank(sv_type: SvType, payload: &ResultPayload, gene_scores: &GeneScoreDb) -> Ranking {
+    let mut score = 0.0;
+
+    // Genes with effect on the coding sequence and whether the full gene is affected.
+    let mut coding_genes = HashSet::new();
+    let mut full_genes = HashSet::new();
+    for tx_effect in &payload.tx_effects {
+        let Some(hgnc_id) = tx_effect.gene.hgnc_id.as_ref() else {
+            continue;
+        };
+        for effect in &tx_effect.transcript_effects {
+            match effect {
+                TranscriptEffect::TranscriptVariant => {
+                    coding_genes.insert(hgnc_id.as_str());
+                    full_genes.insert(hgnc_id.as_str());
+                }
+                TranscriptEffect::ExonVariant | TranscriptEffect::SpliceRegionVariant => {
+                    coding_genes.insert(hgnc_id.as_str());
+                }
+                _ => (),
+            }
+        }
+    }
+    // Genes with disrupted protein domains have their coding sequence affected.
+    for domain in &payload.disrupted_domains {
+        coding_genes.insert(domain.hgnc_id.as_str());
+    }
+
+    let known_pathogenic = !payload.known_pathogenic.is_empty()
+        || !payload.cnv_syndromes.is_empty()
+        || !payload.clinvar_ovl_rcvs.is_empty();
+    if known_pathogenic {
+        score += 1.0;
+    } else if coding_genes.is_empty() {
+        score -= 0.6;
+    }
+
+    // Dosage sensitive genes, full overlap scores higher than partial overlap.
+    let dosage_points = coding_genes
+        .iter()
+        .filter_map(|hgnc_id| gene_scores.get(hgnc_id).map(|scores| (hgnc_id, scores)))
+        .filter(|(_, scores)| is_dosage_sensitive(sv_type, scores))
+        .map(|(hgnc_id, _)| {
+            if full_genes.contains(*hgnc_id) {
+                1.0
+            } else {
+                0.9
+            }
+        })
+        .reduce(f32::max);
+    score += dosage_points.unwrap_or_default();
+
+    // Loss-of-function constrained genes.
+    if sv_type != SvType::Dup
+        && coding_genes
+            .iter()
+            .any(|hgnc_id| gene_scores.get(hgnc_id).is_some_and(is_lof_constrained))
+    {
+        score += 0.15;
+    }
+
+    score += gene_count_points(sv_type, coding_genes.len());
+
+    if payload
+        .overlap_counts
+        .gnomad_genomes_af
+        .is_some_and(|af| af >= MIN_COMMON_AF)
+    {
+        score -= 1.0;
+    }
+
+    Ranking::from_score(score)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::strucvars::query::{
+        bgdbs::BgDbOverlaps,
+        domains,
+        genes::{GeneScoreDb, GeneScoreRecord},
+        schema::{SvType, TranscriptEffect},
+        Gene, GeneTranscriptEffects, ResultPayload,
+    };
+
+    use super::Ranking;
+
+    #[rstest::rstest]
+    #[case(1.0, 5)]
+    #[case(0.9, 4)]
+    #[case(0.0, 3)]
+    #[case(-0.9, 2)]
+    #[case(-1.0, 1)]
+    fn ranking_from_score(#[case] score: f32, #[case] rank_class: u32) {
+        assert_eq!(Ranking::from_score(score).rank_class, rank_class);
+    }
+
+    fn payload(effect: TranscriptEffect, gnomad_genomes_af: Option<f32>) -> ResultPayload {
+        ResultPayload {
+            tx_effects: vec![GeneTranscriptEffects {
+                gene: Gene {
+                    hgnc_id: Some("HGNC:1".into()),
+                    ..Default::default()
+                },
+                transcript_effects: vec![effect],
+            }],
+            overlap_counts: BgDbOverlaps {
+                gnomad_genomes_af,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn gene_scores() -> GeneScoreDb {
+        GeneScoreDb {
+            records: [(
+                "HGNC:1".to_string(),
+                GeneScoreRecord {
+                    hgnc_id: "HGNC:1".into(),
+                    loeuf: Some(0.2),
+                    clingen_hi: Some(3),
+                    clingen_ts: None,
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::del_full_hi(SvType::Del, TranscriptEffect::TranscriptVariant, None, 1.15, 5)]
+    #[case::del_exon_hi(SvType::Del, TranscriptEffect::ExonVariant, None, 1.05, 5)]
+    #[case::dup_no_ts(SvType::Dup, TranscriptEffect::TranscriptVariant, None, 0.0, 3)]
+    #[case::del_intronic(SvType::Del, TranscriptEffect::IntronVariant, None, -0.6, 3)]
+    #[case::del_common(SvType::Del, TranscriptEffect::IntronVariant, Some(0.05), -1.6, 1)]
+    fn rank(
+        #[case] sv_type: SvType,
+        #[case] effect: TranscriptEffect,
+        #[case] gnomad_genomes_af: Option<f32>,
+        #[case] score: f32,
+        #[case] rank_class: u32,
+    ) {
+        let ranking = super::rank(sv_type, &payload(effect, gnomad_genomes_af), &gene_scores());
+
+        assert!((ranking.score - score).abs() < 1e-6);
+        assert_eq!(ranking.rank_class, rank_class);
+    }
+
+    #[rstest::rstest]
+    #[case::loeuf(Some(0.2), None, 0.15)]
+    #[case::shet(Some(0.9), Some(0.2), 0.15)]
+    #[case::unconstrained(Some(0.9), Some(0.01), 0.0)]
+    fn rank_lof_constrained(
+        #[case] loeuf: Option<f32>,
+        #[case] shet: Option<f32>,
+        #[case] score: f32,
+    ) {
+        let gene_scores = GeneScoreDb {
+            records: [(
+                "HGNC:1".to_string(),
+                GeneScoreRecord {
+                    hgnc_id: "HGNC:1".into(),
+                    loeuf,
+                    shet,
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+
+        let ranking = super::rank(
+            SvType::Del,
+            &payload(TranscriptEffect::ExonVariant, None),
+            &gene_scores,
+        );
+
+        assert!((ranking.score - score).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rank_disrupted_domain() {
+        let payload = ResultPayload {
+            disrupted_domains: vec![domains::Record {
+                hgnc_id: "HGNC:1".into(),
+                domain_id: "PF00069".into(),
+                ..Default::default()
+            }],
+            ..payload(TranscriptEffect::IntronVariant, None)
+        };
+
+        let ranking = super::rank(SvType::Del, &payload, &gene_scores());
+
+        assert!((ranking.score - 1.05).abs() < 1e-6);
+        assert_eq!(ranking.rank_class, 5);
+    }
+}
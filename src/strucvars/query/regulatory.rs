@@ -0,0 +1,301 @@
+//! Code for annotating with regulatory elements (ENCODE cCREs, VISTA enhancers, promoters).
+//!
+//! Also used by `seqvars query` for annotating variants outside of coding regions.
+
+use std::path::Path;
+
+use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+use indexmap::IndexMap;
+use mehari::common::io::std::open_read_maybe_gz;
+use serde::Serialize;
+use tracing::info;
+
+use crate::common::{build_chrom_map, GenomeRelease, CHROMS};
+
+use super::{
+    interpreter::{BND_SLACK, INS_SLACK},
+    schema::{StructuralVariant, SvType, VistaValidation},
+};
+
+/// Alias for the interval tree that we use.
+type IntervalTree = ArrayBackedIntervalTree<i32, u32>;
+
+/// Source of a regulatory element.
+#[derive(
+    Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Source {
+    /// ENCODE candidate cis-regulatory element.
+    #[default]
+    EncodeCcre,
+    /// VISTA enhancer.
+    Vista,
+    /// Promoter region, e.g., upstream of the transcription start sites.
+    Promoter,
+}
+
+/// Information to store for a regulatory element.
+#[derive(Default, Debug, Serialize, Clone)]
+pub struct Record {
+    /// 0-based begin position.
+    pub begin: i32,
+    /// End position.
+    pub end: i32,
+    /// Identifier of the element, e.g., `EH38E1310212` or `hs1`.
+    pub id: String,
+    /// Source of the element.
+    pub source: Source,
+    /// Element type, e.g., `dELS` for cCREs or `positive` for VISTA.
+    pub element_type: String,
+    /// Symbols of the genes linked to the element.
+    pub linked_genes: Vec<String>,
+}
+
+impl Record {
+    /// Return whether the element passes the VISTA `validation` setting.
+    pub fn passes_vista_validation(&self, validation: Option<VistaValidation>) -> bool {
+        match (self.source, validation) {
+            (Source::Vista, Some(VistaValidation::Positive)) => self.element_type == "positive",
+            (Source::Vista, Some(VistaValidation::Negative)) => self.element_type == "negative",
+            _ => true,
+        }
+    }
+}
+
+/// Code for regulatory element overlappers.
+#[derive(Default, Debug)]
+pub struct RegulatoryDb {
+    /// Records, stored by chromosome.
+    pub records: Vec<Vec<Record>>,
+    /// Interval trees, stored by chromosome.
+    pub trees: Vec<IntervalTree>,
+}
+
+impl RegulatoryDb {
+    /// Return the elements overlapping the 0-based half-open `range` on `chrom`.
+    ///
+    /// Unknown chromosomes have no elements.
+    pub fn records_in_range(
+        &self,
+        chrom_map: &IndexMap<String, usize>,
+        chrom: &str,
+        range: std::ops::Range<i32>,
+    ) -> Vec<Record> {
+        chrom_map
+            .get(chrom)
+            .and_then(|chrom_idx| {
+                self.trees
+                    .get(*chrom_idx)
+                    .map(|tree| (&self.records[*chrom_idx], tree))
+            })
+            .map(|(records, tree)| {
+                tree.find(range)
+                    .iter()
+                    .map(|e| records[*e.data() as usize].clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return the elements disrupted by `sv` with the given `padding` and VISTA `validation`.
+    pub fn overlapping_records(
+        &self,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+        padding: i32,
+        validation: Option<VistaValidation>,
+    ) -> Vec<Record> {
+        if self.trees.is_empty() {
+            return Vec::new();
+        }
+
+        let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
+        let queries = match sv.sv_type {
+            SvType::Bnd => {
+                let chrom_idx2 = *chrom_map
+                    .get(sv.chrom2.as_ref().unwrap_or(&sv.chrom))
+                    .unwrap_or_else(|| panic!("invalid chromosome: {:?}", &sv.chrom2));
+                vec![
+                    (chrom_idx, sv.pos_window(BND_SLACK)),
+                    (chrom_idx2, sv.end_window(BND_SLACK)),
+                ]
+            }
+            SvType::Ins => vec![(chrom_idx, sv.pos_window(INS_SLACK))],
+            _ => vec![(chrom_idx, sv.pos.saturating_sub(1)..sv.end)],
+        };
+
+        queries
+            .into_iter()
+            .flat_map(|(chrom_idx, query)| {
+                self.trees[chrom_idx]
+                    .find(query.start.saturating_sub(padding)..query.end.saturating_add(padding))
+                    .iter()
+                    .map(|e| &self.records[chrom_idx][*e.data() as usize])
+                    .filter(|record| record.passes_vista_validation(validation))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Module with code for loading data from input.
+mod input {
+    use serde::Deserialize;
+
+    use super::Source;
+
+    /// Type for record structs from input.
+    #[derive(Deserialize, Debug)]
+    pub struct Record {
+        /// Chromosome name
+        pub chrom: String,
+        /// 0-based begin position from BED.
+        pub begin: i32,
+        /// 0-based end position from BED.
+        pub end: i32,
+        /// Identifier of the element.
+        pub id: String,
+        /// Source of the element.
+        pub source: Source,
+        /// Element type.
+        pub element_type: String,
+        /// Comma-separated symbols of linked genes, `.` if none.
+        pub linked_genes: String,
+    }
+}
+
+#[tracing::instrument]
+fn load_regulatory_db_records(path: &Path) -> Result<RegulatoryDb, anyhow::Error> {
+    tracing::debug!("loading regulatory element records from {:?}...", path);
+    let chrom_map = build_chrom_map();
+
+    let mut result = RegulatoryDb::default();
+    for _ in CHROMS {
+        result.records.push(Vec::new());
+        result.trees.push(IntervalTree::new());
+    }
+
+    // Setup CSV reader for BED file - header is written as comment and must be
+    // ignored.
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false) // BED has no header
+        .comment(Some(b'#'))
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: input::Record = record?;
+        let chrom_idx = *chrom_map.get(&record.chrom).expect("invalid chromosome");
+
+        let key = record.begin..record.end;
+        result.trees[chrom_idx].insert(key, result.records[chrom_idx].len() as u32);
+        result.records[chrom_idx].push(Record {
+            begin: record.begin,
+            end: record.end,
+            id: record.id,
+            source: record.source,
+            element_type: record.element_type,
+            linked_genes: if record.linked_genes == "." {
+                Vec::new()
+            } else {
+                record
+                    .linked_genes
+                    .split(',')
+                    .map(|s| s.to_string())
+                    .collect()
+            },
+        });
+
+        total_count += 1;
+    }
+    result.trees.iter_mut().for_each(|tree| tree.index());
+    tracing::debug!(
+        "... done loading {} records and building trees",
+        total_count
+    );
+
+    Ok(result)
+}
+
+/// Load the regulatory elements from the database, if present.
+#[tracing::instrument]
+pub fn load_regulatory_db(
+    path_db: &str,
+    genome_release: GenomeRelease,
+) -> Result<RegulatoryDb, anyhow::Error> {
+    info!("Loading regulatory elements db");
+    let path = Path::new(path_db).join(format!(
+        "{}/features/regulatory_elements.bed.gz",
+        genome_release
+    ));
+    if path.exists() {
+        load_regulatory_db_records(&path)
+    } else {
+        info!("  no regulatory elements at {:?}", &path);
+        Ok(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Record, Source};
+    use crate::{common::build_chrom_map, strucvars::query::schema::VistaValidation};
+
+    #[test]
+    fn records_in_range() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_bed = tmpdir.join("regulatory_elements.bed");
+        std::fs::write(
+            &path_bed,
+            "#chrom\tbegin\tend\tid\tsource\telement_type\tlinked_genes\n\
+             1\t100\t200\tEH38E1\tencode_ccre\tdELS\tGENE1\n\
+             1\t1000\t2000\tENST1\tpromoter\tpromoter\tGENE2\n",
+        )?;
+        let db = super::load_regulatory_db_records(&path_bed)?;
+        let chrom_map = build_chrom_map();
+
+        let ids = |chrom: &str, range: std::ops::Range<i32>| {
+            db.records_in_range(&chrom_map, chrom, range)
+                .into_iter()
+                .map(|record| (record.id, record.source))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids("chr1", 150..151),
+            vec![("EH38E1".to_string(), Source::EncodeCcre)]
+        );
+        assert_eq!(
+            ids("1", 1999..2000),
+            vec![("ENST1".to_string(), Source::Promoter)]
+        );
+        assert!(ids("1", 500..600).is_empty());
+        assert!(ids("GL000220.1", 150..151).is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(Source::Vista, "positive", None, true)]
+    #[case(Source::Vista, "positive", Some(VistaValidation::Any), true)]
+    #[case(Source::Vista, "positive", Some(VistaValidation::Positive), true)]
+    #[case(Source::Vista, "negative", Some(VistaValidation::Positive), false)]
+    #[case(Source::Vista, "negative", Some(VistaValidation::Negative), true)]
+    #[case(Source::EncodeCcre, "dELS", Some(VistaValidation::Positive), true)]
+    fn passes_vista_validation(
+        #[case] source: Source,
+        #[case] element_type: &str,
+        #[case] validation: Option<VistaValidation>,
+        #[case] expected: bool,
+    ) {
+        let record = Record {
+            source,
+            element_type: element_type.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(record.passes_vista_validation(validation), expected);
+    }
+}
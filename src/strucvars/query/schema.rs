@@ -1,7 +1,7 @@
 //! Supporting code for SV query definition.
 
 use crate::{
-    common::{genotype_to_string, TadSet},
+    common::{genotype_to_string, Genotype as CallGenotype, TadSet},
     pbs::varfish::v1::strucvars::clinvar,
 };
 use indexmap::IndexMap;
@@ -225,6 +225,48 @@ impl SvSubType {
                 | SvSubType::InsMeAlu
         )
     }
+    /// Return whether SV sub type is a mobile element insertion
+    pub fn is_ins_me(&self) -> bool {
+        matches!(
+            self,
+            SvSubType::InsMe | SvSubType::InsMeSva | SvSubType::InsMeL1 | SvSubType::InsMeAlu
+        )
+    }
+
+    /// Return whether the mobile element insertion sub type matches `other`; an
+    /// unclassified mobile element insertion matches all families.
+    pub fn is_compatible_me(&self, other: SvSubType) -> bool {
+        self.is_ins_me()
+            && other.is_ins_me()
+            && (*self == other || *self == SvSubType::InsMe || other == SvSubType::InsMe)
+    }
+
+    /// Return the mobile element insertion sub type for the mobile element `name`,
+    /// e.g., `ALU`, `LINE1`, or `AluYa5` as written to `INFO/MEINFO` by MELT.
+    pub fn from_me_name(name: &str) -> SvSubType {
+        let name = name.to_ascii_uppercase();
+        if name.starts_with("ALU") {
+            SvSubType::InsMeAlu
+        } else if name.starts_with("L1") || name.starts_with("LINE") {
+            SvSubType::InsMeL1
+        } else if name.starts_with("SVA") {
+            SvSubType::InsMeSva
+        } else {
+            SvSubType::InsMe
+        }
+    }
+
+    /// Return the mobile element insertion sub type for a (symbolic) allele or SV type
+    /// such as `<INS:ME:ALU>` or `INS:ME:LINE1`, or `None` if it does not describe a
+    /// mobile element insertion.
+    pub fn from_ins_me_str(value: &str) -> Option<SvSubType> {
+        let value = value.trim_start_matches('<').trim_end_matches('>');
+        match value.strip_prefix("INS:ME")? {
+            "" => Some(SvSubType::InsMe),
+            rest => rest.strip_prefix(':').map(Self::from_me_name),
+        }
+    }
+
     /// Return whether SV sub type is any deletion
     pub fn is_del(&self) -> bool {
         matches!(
@@ -461,6 +503,14 @@ fn default_as_true() -> bool {
     true
 }
 
+fn default_cnv_syndrome_min_overlap() -> Option<f32> {
+    Some(0.5)
+}
+
+fn default_gene_panel_min_confidence() -> u32 {
+    3
+}
+
 /// Define rule to apply to a given sub set of structural variants for matching
 /// a genotype.
 ///
@@ -1079,6 +1129,229 @@ impl GenotypeCriteria {
     }
 }
 
+/// Per-SV-type overlap criteria for the background and known pathogenic databases.
+///
+/// Each value that is set overrides the database-specific minimal reciprocal overlap
+/// or the global slack for SVs of the corresponding type.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct SvTypeOverlap {
+    /// The minimal reciprocal overlap for deletions.
+    pub min_overlap_del: Option<f32>,
+    /// The minimal reciprocal overlap for duplications.
+    pub min_overlap_dup: Option<f32>,
+    /// The minimal reciprocal overlap for inversions.
+    pub min_overlap_inv: Option<f32>,
+    /// The slack around the position of insertions.
+    pub slack_ins: Option<i32>,
+    /// The slack around the position of break-ends.
+    pub slack_bnd: Option<i32>,
+}
+
+impl SvTypeOverlap {
+    /// Return the minimal reciprocal overlap for `sv_type`, if any.
+    pub fn min_overlap(&self, sv_type: SvType) -> Option<f32> {
+        match sv_type {
+            SvType::Del => self.min_overlap_del,
+            SvType::Dup => self.min_overlap_dup,
+            SvType::Inv => self.min_overlap_inv,
+            SvType::Ins | SvType::Bnd | SvType::Cnv => None,
+        }
+    }
+
+    /// Return the slack for `sv_type`, if any.
+    pub fn slack(&self, sv_type: SvType) -> Option<i32> {
+        match sv_type {
+            SvType::Ins => self.slack_ins,
+            SvType::Bnd => self.slack_bnd,
+            SvType::Del | SvType::Dup | SvType::Inv | SvType::Cnv => None,
+        }
+    }
+}
+
+/// Criteria for filtering mosaic CNVs by the estimated mosaicism fraction.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct MosaicCriteria {
+    /// Samples that must have a fraction in range, any sample if empty.
+    #[serde(default)]
+    pub samples: Vec<String>,
+    /// Minimal mosaicism fraction.
+    pub min_fraction: Option<f32>,
+    /// Maximal mosaicism fraction, e.g., `0.9` to exclude constitutional CNVs.
+    pub max_fraction: Option<f32>,
+}
+
+impl MosaicCriteria {
+    /// Return whether `fraction` is in the configured range.
+    pub fn is_fraction_pass(&self, fraction: f32) -> bool {
+        self.min_fraction.map_or(true, |min| fraction >= min)
+            && self.max_fraction.map_or(true, |max| fraction <= max)
+    }
+}
+
+/// Size range for SVs of one type.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct SvSizeRange {
+    /// The minimal SV size to consider.
+    pub min: Option<u32>,
+    /// The maximal SV size to consider.
+    pub max: Option<u32>,
+}
+
+/// Per-sample filter on the genotype evidence of SV calls.
+///
+/// Values that are missing in the call, e.g., split reads for read depth based
+/// CNV callers, pass the corresponding threshold.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct SampleEvidenceFilter {
+    /// Callers to apply the filter to, e.g., `"Manta"` or `"Delly"`, all if empty.
+    #[serde(default)]
+    pub callers: Vec<String>,
+    /// Minimal genotype quality.
+    pub min_gq: Option<f32>,
+    /// Minimal number of variant paired-end reads.
+    pub min_pr_var: Option<u32>,
+    /// Minimal number of variant split reads.
+    pub min_sr_var: Option<u32>,
+    /// Minimal sum of variant paired-end/split reads.
+    pub min_srpr_var: Option<u32>,
+    /// Minimal copy number quality, e.g., from DRAGEN CNV.
+    pub min_cnq: Option<f32>,
+}
+
+impl SampleEvidenceFilter {
+    /// Return whether the filter applies to an SV from `callers`.
+    pub fn is_applicable_to(&self, callers: &[String]) -> bool {
+        self.callers.is_empty() || callers.iter().any(|caller| self.callers.contains(caller))
+    }
+
+    /// Return whether `call_info` passes the filter.
+    pub fn is_call_info_pass(&self, call_info: &CallInfo) -> bool {
+        fn passes<T: PartialOrd>(min: Option<T>, value: Option<T>) -> bool {
+            min.zip(value).map_or(true, |(min, value)| value >= min)
+        }
+
+        let srpr_var = match (call_info.paired_end_var, call_info.split_read_var) {
+            (None, None) => None,
+            (pr_var, sr_var) => Some(pr_var.unwrap_or_default() + sr_var.unwrap_or_default()),
+        };
+
+        passes(self.min_gq, call_info.quality)
+            && passes(self.min_pr_var, call_info.paired_end_var)
+            && passes(self.min_sr_var, call_info.split_read_var)
+            && passes(self.min_srpr_var, srpr_var)
+            && passes(self.min_cnq, call_info.copy_number_quality)
+    }
+}
+
+/// Evidence thresholds for de novo filtering.
+///
+/// Reference read counts are computed as the difference of coverage and variant
+/// support.  Missing read counts fail any threshold that is set.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct DeNovoEvidence {
+    /// Minimal sum of variant paired-end/split reads in the index.
+    pub min_index_srpr_var: Option<u32>,
+    /// Minimal number of reference paired-end reads in each parent.
+    pub min_parent_pr_ref: Option<u32>,
+    /// Minimal number of reference split reads in each parent.
+    pub min_parent_sr_ref: Option<u32>,
+    /// Maximal sum of variant paired-end/split reads in each parent.
+    pub max_parent_srpr_var: Option<u32>,
+}
+
+impl DeNovoEvidence {
+    /// Return whether the index `call_info` passes the thresholds.
+    pub fn is_index_pass(&self, call_info: &CallInfo) -> bool {
+        self.min_index_srpr_var.map_or(true, |min_srpr_var| {
+            call_info
+                .paired_end_var
+                .zip(call_info.split_read_var)
+                .map_or(false, |(pr_var, sr_var)| pr_var + sr_var >= min_srpr_var)
+        })
+    }
+
+    /// Return whether the parent `call_info` passes the thresholds.
+    pub fn is_parent_pass(&self, call_info: &CallInfo) -> bool {
+        let pass_min_pr_ref = self.min_parent_pr_ref.map_or(true, |min_pr_ref| {
+            call_info
+                .paired_end_cov
+                .zip(call_info.paired_end_var)
+                .map_or(false, |(cov, var)| cov.saturating_sub(var) >= min_pr_ref)
+        });
+        let pass_min_sr_ref = self.min_parent_sr_ref.map_or(true, |min_sr_ref| {
+            call_info
+                .split_read_cov
+                .zip(call_info.split_read_var)
+                .map_or(false, |(cov, var)| cov.saturating_sub(var) >= min_sr_ref)
+        });
+        let pass_max_srpr_var = self.max_parent_srpr_var.map_or(true, |max_srpr_var| {
+            call_info
+                .paired_end_var
+                .zip(call_info.split_read_var)
+                .map_or(false, |(pr_var, sr_var)| pr_var + sr_var <= max_srpr_var)
+        });
+
+        pass_min_pr_ref && pass_min_sr_ref && pass_max_srpr_var
+    }
+}
+
+/// Criteria for filtering de novo SVs based on the parental genotypes.
+///
+/// The index must carry the SV while both parents must have a reference genotype
+/// with sufficient read support for the reference allele.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct DeNovoCriteria {
+    /// Name of the index sample.
+    pub index: String,
+    /// Names of the parent samples.
+    pub parents: Vec<String>,
+    /// Evidence thresholds to use if there are no caller-specific ones.
+    #[serde(default)]
+    pub evidence: DeNovoEvidence,
+    /// Caller-specific evidence thresholds, e.g., for `"Manta"` or `"Delly"`.
+    #[serde(default)]
+    pub caller_evidence: IndexMap<String, DeNovoEvidence>,
+}
+
+impl DeNovoCriteria {
+    /// Return the evidence thresholds for the first of `callers` with specific
+    /// thresholds, or the default ones.
+    pub fn evidence_for(&self, callers: &[String]) -> &DeNovoEvidence {
+        callers
+            .iter()
+            .find_map(|caller| self.caller_evidence.get(caller))
+            .unwrap_or(&self.evidence)
+    }
+
+    /// Return whether `sv` is a de novo call in the index.
+    pub fn is_de_novo(&self, sv: &StructuralVariant) -> bool {
+        let evidence = self.evidence_for(&sv.callers);
+        let genotype = |sample: &str| {
+            sv.call_info
+                .get(sample)
+                .and_then(|call_info| call_info.genotype.as_ref())
+                .and_then(|gt| gt.parse::<CallGenotype>().ok())
+        };
+
+        let pass_index = matches!(
+            genotype(&self.index),
+            Some(CallGenotype::Het | CallGenotype::HomAlt)
+        ) && sv
+            .call_info
+            .get(&self.index)
+            .map_or(false, |call_info| evidence.is_index_pass(call_info));
+        let pass_parents = self.parents.iter().all(|parent| {
+            genotype(parent) == Some(CallGenotype::HomRef)
+                && sv
+                    .call_info
+                    .get(parent)
+                    .map_or(false, |call_info| evidence.is_parent_pass(call_info))
+        });
+
+        pass_index && pass_parents
+    }
+}
+
 /// Define a query for structural variants from a case.
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct CaseQuery {
@@ -1100,6 +1373,26 @@ pub struct CaseQuery {
     pub svdb_gnomad_genomes_min_overlap: Option<f32>,
     /// The maximal number of carriers for querying gnomAD SV.
     pub svdb_gnomad_genomes_max_count: Option<u32>,
+    /// The maximal allele frequency by SV type for querying gnomAD SV.
+    ///
+    /// Only available for gnomAD-SV v4 (GRCh38).
+    #[serde(default)]
+    pub svdb_gnomad_genomes_max_af: IndexMap<SvType, f32>,
+    /// The maximal number of hom. alt. carriers by SV type for querying gnomAD SV.
+    ///
+    /// Only available for gnomAD-SV v4 (GRCh38).
+    #[serde(default)]
+    pub svdb_gnomad_genomes_max_homalt: IndexMap<SvType, u32>,
+    /// The maximal number of carriers of the same mobile element family for querying
+    /// mobile element insertions against gnomAD SV.
+    #[serde(default)]
+    pub svdb_gnomad_genomes_mei_max_count: Option<u32>,
+    /// The maximal allele frequency of the same mobile element family for querying
+    /// mobile element insertions against gnomAD SV.
+    ///
+    /// Only available for gnomAD-SV v4 (GRCh38).
+    #[serde(default)]
+    pub svdb_gnomad_genomes_mei_max_af: Option<f32>,
     /// Whether to enable SVDB overlap queries with gnomAD exomes/ExAC.
     pub svdb_gnomad_exomes_enabled: bool,
     /// The minimal reciprocal overlap for querying gnomAD exomes/ExAC.
@@ -1124,16 +1417,39 @@ pub struct CaseQuery {
     pub svdb_inhouse_min_overlap: Option<f32>,
     /// The maximal number of alleles for querying in-house DB.
     pub svdb_inhouse_max_count: Option<u32>,
+    /// Per-SV-type overrides of the overlap criteria.
+    #[serde(default)]
+    pub svdb_sv_type_overlap: SvTypeOverlap,
 
     /// Minimal reciprocal overlap when overlapping with ClinVar SVs
     pub clinvar_sv_min_overlap: Option<f32>,
     /// Minimal pathogenicity when overlapping with ClinVar SVs.
     pub clinvar_sv_min_pathogenicity: Option<Pathogenicity>,
+    /// Whether to require overlap with a ClinVar SV passing the minimal overlap and
+    /// pathogenicity.
+    #[serde(default)]
+    pub clinvar_sv_require_overlap: bool,
+
+    /// Minimal reciprocal overlap for matching recurrent microdeletion/microduplication
+    /// syndromes, matching is disabled if `None`.
+    #[serde(default = "default_cnv_syndrome_min_overlap")]
+    pub cnv_syndrome_min_overlap: Option<f32>,
+
+    /// Maximal number of breakpoints in low-complexity regions.
+    #[serde(default)]
+    pub max_brk_low_complexity: Option<u32>,
+    /// Maximal number of breakpoints in ENCODE blacklist regions.
+    #[serde(default)]
+    pub max_brk_blacklist: Option<u32>,
 
     /// The minimal SV size to consider.
     pub sv_size_min: Option<u32>,
     /// The maximal SV size to consider.
     pub sv_size_max: Option<u32>,
+    /// Size ranges by SV type, overriding `sv_size_min` and `sv_size_max` for
+    /// the given types.
+    #[serde(default)]
+    pub sv_size_by_type: IndexMap<SvType, SvSizeRange>,
 
     /// The SV types to consider.
     pub sv_types: Vec<SvType>,
@@ -1144,6 +1460,18 @@ pub struct CaseQuery {
 
     /// List of genes to require.
     pub gene_allowlist: Option<Vec<String>>,
+    /// IDs of PanelApp panels whose genes to add to the gene allow list.
+    #[serde(default)]
+    pub gene_panels: Vec<String>,
+    /// Minimal confidence level of the genes on the PanelApp panels.
+    #[serde(default = "default_gene_panel_min_confidence")]
+    pub gene_panel_min_confidence: u32,
+    /// HPO terms of the case to match against the terms annotated to the genes.
+    #[serde(default)]
+    pub hpo_terms: Vec<String>,
+    /// Whether to require an overlapping gene with any of `hpo_terms` annotated.
+    #[serde(default)]
+    pub hpo_require_match: bool,
     /// Genomic region to limit consideration to.
     #[serde(deserialize_with = "deserialize_genomic_region")]
     pub genomic_region: Option<Vec<GenomicRegion>>,
@@ -1157,15 +1485,40 @@ pub struct CaseQuery {
 
     /// Custom regulatory maps configuration.
     pub regulatory_custom_configs: Vec<RegulatoryCustomConfig>,
+    /// Whether to require overlap with a regulatory element (ENCODE cCRE or VISTA).
+    #[serde(default)]
+    pub regulatory_require_overlap: bool,
 
     /// Name of the TAD set to use for annotation, if any.
     pub tad_set: Option<TadSet>,
+    /// Minimal TAD disruption score, i.e., `0` if no TAD boundary is spanned and one
+    /// plus the number of re-assigned genes otherwise.
+    #[serde(default)]
+    pub tad_min_disruption_score: Option<u32>,
 
     /// Genotype choices
     pub genotype: IndexMap<String, GenotypeChoice>,
     /// Criteria for filtering CNVs.
     pub genotype_criteria: Vec<GenotypeCriteria>,
 
+    /// Per-sample filters on the genotype evidence.
+    #[serde(default)]
+    pub sample_evidence: IndexMap<String, SampleEvidenceFilter>,
+    /// Criteria for filtering mosaic CNVs, if any.
+    #[serde(default)]
+    pub mosaic: Option<MosaicCriteria>,
+    /// Criteria for de novo filtering, if any.
+    #[serde(default)]
+    pub de_novo: Option<DeNovoCriteria>,
+    /// Name of the sample whose genotype must be consistent with the DDG2P allelic
+    /// requirement of an overlapping gene, if any.
+    #[serde(default)]
+    pub ddg2p_index: Option<String>,
+    /// Whether to also remove SVs for which `ddg2p_index` is het. and all overlapping
+    /// DDG2P genes are biallelic, rather than keeping them as possible compound het.
+    #[serde(default)]
+    pub ddg2p_strict: bool,
+
     /// The mode for recessive inheritance.
     pub recessive_mode: Option<RecessiveMode>,
     /// The index to use for recessive inheritance.
@@ -1232,6 +1585,10 @@ impl Default for CaseQuery {
             svdb_gnomad_genomes_enabled: false,
             svdb_gnomad_genomes_min_overlap: None,
             svdb_gnomad_genomes_max_count: None,
+            svdb_gnomad_genomes_max_af: IndexMap::new(),
+            svdb_gnomad_genomes_max_homalt: IndexMap::new(),
+            svdb_gnomad_genomes_mei_max_count: None,
+            svdb_gnomad_genomes_mei_max_af: None,
             svdb_gnomad_exomes_enabled: false,
             svdb_gnomad_exomes_min_overlap: None,
             svdb_gnomad_exomes_max_count: None,
@@ -1244,21 +1601,38 @@ impl Default for CaseQuery {
             svdb_inhouse_enabled: false,
             svdb_inhouse_min_overlap: None,
             svdb_inhouse_max_count: None,
+            svdb_sv_type_overlap: Default::default(),
             sv_size_min: None,
             sv_size_max: None,
+            sv_size_by_type: IndexMap::new(),
             sv_types: SvType::vec_all(),
             sv_sub_types: SvSubType::vec_all(),
             clinvar_sv_min_overlap: None,
             clinvar_sv_min_pathogenicity: None,
+            clinvar_sv_require_overlap: false,
+            cnv_syndrome_min_overlap: default_cnv_syndrome_min_overlap(),
+            max_brk_low_complexity: None,
+            max_brk_blacklist: None,
             gene_allowlist: None,
+            gene_panels: vec![],
+            gene_panel_min_confidence: default_gene_panel_min_confidence(),
+            hpo_terms: vec![],
+            hpo_require_match: false,
             genomic_region: None,
             regulatory_overlap: 100,
             regulatory_ensembl_features: None,
             regulatory_vista_validation: None,
             regulatory_custom_configs: vec![],
+            regulatory_require_overlap: false,
             tad_set: None,
+            tad_min_disruption_score: None,
             genotype: IndexMap::new(),
             genotype_criteria: vec![],
+            sample_evidence: IndexMap::new(),
+            mosaic: None,
+            de_novo: None,
+            ddg2p_index: None,
+            ddg2p_strict: false,
             recessive_mode: None,
             recessive_index: None,
             tx_effects: TranscriptEffect::vec_all(),
@@ -1293,6 +1667,32 @@ pub struct CallInfo {
     pub point_count: Option<u32>,
     /// Average mapping quality, if applicable
     pub average_mapping_quality: Option<f32>,
+    /// Copy number quality, if applicable
+    #[serde(default)]
+    pub copy_number_quality: Option<f32>,
+    /// Estimated mosaicism fraction of CNVs, if applicable
+    #[serde(default)]
+    pub mosaic_fraction: Option<f32>,
+}
+
+impl CallInfo {
+    /// Estimate the mosaicism fraction of a CNV of `sv_type` from the average
+    /// normalized coverage.
+    ///
+    /// The fraction is `1.0` for constitutional heterozygous deletions (coverage
+    /// of `0.5`) and duplications (coverage of `1.5`) and larger for homozygous
+    /// deletions or higher copy number gains.  `None` is returned if the coverage
+    /// is unknown or for SV types other than `DEL`, `DUP`, and `CNV`.
+    pub fn estimate_mosaic_fraction(&self, sv_type: SvType) -> Option<f32> {
+        let cov = self.average_normalized_cov?;
+        match sv_type {
+            SvType::Del => Some(2.0 * (1.0 - cov)),
+            SvType::Dup => Some(2.0 * (cov - 1.0)),
+            SvType::Cnv => Some(2.0 * (cov - 1.0).abs()),
+            SvType::Inv | SvType::Ins | SvType::Bnd => None,
+        }
+        .map(|fraction| fraction.max(0.0))
+    }
 }
 
 /// Definition of a structural variant with per-sample genotype calls.
@@ -1316,6 +1716,12 @@ pub struct StructuralVariant {
     pub end: i32,
     /// The strand orientation of the structural variant.
     pub strand_orientation: StrandOrientation,
+    /// Confidence interval around `pos` from `INFO/CIPOS`, if any.
+    #[serde(default)]
+    pub ci_pos: Option<(i32, i32)>,
+    /// Confidence interval around `end` from `INFO/CIEND`, if any.
+    #[serde(default)]
+    pub ci_end: Option<(i32, i32)>,
 
     /// The callers of the variant.
     pub callers: Vec<String>,
@@ -1340,6 +1746,28 @@ impl StructuralVariant {
         }
     }
 
+    /// Return the window around `pos` for fuzzy breakpoint matching.
+    ///
+    /// To each side, the window extends by the confidence interval, if given, or
+    /// `slack`, whichever is larger.
+    pub fn pos_window(&self, slack: i32) -> std::ops::Range<i32> {
+        Self::window(self.pos, self.ci_pos, slack)
+    }
+
+    /// Return the window around `end` for fuzzy breakpoint matching.
+    ///
+    /// To each side, the window extends by the confidence interval, if given, or
+    /// `slack`, whichever is larger.
+    pub fn end_window(&self, slack: i32) -> std::ops::Range<i32> {
+        Self::window(self.end, self.ci_end, slack)
+    }
+
+    fn window(pos: i32, ci: Option<(i32, i32)>, slack: i32) -> std::ops::Range<i32> {
+        let (left, right) = ci.unwrap_or_default();
+        pos.saturating_sub(left.saturating_abs().max(slack))
+            ..pos.saturating_add(right.saturating_abs().max(slack))
+    }
+
     /// Convert from VCF record.
     pub fn from_vcf(
         record: &vcf::variant::RecordBuf,
@@ -1364,7 +1792,7 @@ impl StructuralVariant {
             SvType::Del => SvSubType::Del,
             SvType::Dup => SvSubType::Dup,
             SvType::Inv => SvSubType::Inv,
-            SvType::Ins => SvSubType::Ins,
+            SvType::Ins => Self::mei_sub_type(record).unwrap_or(SvSubType::Ins),
             SvType::Bnd => SvSubType::Bnd,
             SvType::Cnv => SvSubType::Cnv,
         };
@@ -1428,11 +1856,45 @@ impl StructuralVariant {
             chrom2,
             end,
             strand_orientation,
+            ci_pos: Self::confidence_interval(record, key::POSITION_CONFIDENCE_INTERVALS),
+            ci_end: Self::confidence_interval(record, key::END_CONFIDENCE_INTERVALS),
             callers,
             call_info,
         })
     }
 
+    /// Classify a mobile element insertion from the symbolic ALT allele (e.g.,
+    /// `<INS:ME:ALU>`) or, failing that, the element name in `INFO/MEINFO`.
+    fn mei_sub_type(record: &vcf::variant::RecordBuf) -> Option<SvSubType> {
+        use vcf::variant::record_buf::info::field::{value::Array, Value};
+
+        let from_alt = record
+            .alternate_bases()
+            .as_ref()
+            .first()
+            .and_then(|alt| SvSubType::from_ins_me_str(alt));
+        let from_meinfo = || match record.info().get("MEINFO") {
+            Some(Some(Value::String(meinfo))) => meinfo.split(',').next().map(str::to_string),
+            Some(Some(Value::Array(Array::String(meinfo)))) => meinfo.first().cloned().flatten(),
+            _ => None,
+        };
+
+        from_alt.or_else(|| from_meinfo().map(|name| SvSubType::from_me_name(&name)))
+    }
+
+    /// Read the confidence interval from the `INFO` field `key`, if any.
+    fn confidence_interval(record: &vcf::variant::RecordBuf, key: &str) -> Option<(i32, i32)> {
+        use vcf::variant::record_buf::info::field::{value::Array, Value};
+
+        match record.info().get(key) {
+            Some(Some(Value::Array(Array::Integer(values)))) => match values.as_slice() {
+                [Some(left), Some(right)] => Some((*left, *right)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Build call information.
     fn build_call_info(
         record: &vcf::variant::RecordBuf,
@@ -1514,6 +1976,18 @@ impl StructuralVariant {
                             average_mapping_quality,
                         ),
                     ) => call_info.average_mapping_quality = Some(*average_mapping_quality as f32),
+                    (
+                        "cnq",
+                        vcf::variant::record_buf::samples::sample::Value::Float(
+                            copy_number_quality,
+                        ),
+                    ) => call_info.copy_number_quality = Some(*copy_number_quality),
+                    (
+                        "cnq",
+                        vcf::variant::record_buf::samples::sample::Value::Integer(
+                            copy_number_quality,
+                        ),
+                    ) => call_info.copy_number_quality = Some(*copy_number_quality as f32),
                     _ => {
                         panic!("unknown FORMAT key: {}", key);
                     }
@@ -2044,6 +2518,56 @@ mod tests {
         assert!(crit.is_call_info_pass(&pass_info, genotype_choice));
     }
 
+    #[rstest::rstest]
+    #[case(SvType::Del, Some(0.5), None)]
+    #[case(SvType::Dup, Some(0.6), None)]
+    #[case(SvType::Inv, None, None)]
+    #[case(SvType::Ins, None, Some(100))]
+    #[case(SvType::Bnd, None, Some(200))]
+    #[case(SvType::Cnv, None, None)]
+    fn test_sv_type_overlap(
+        #[case] sv_type: SvType,
+        #[case] min_overlap: Option<f32>,
+        #[case] slack: Option<i32>,
+    ) {
+        let overlap = SvTypeOverlap {
+            min_overlap_del: Some(0.5),
+            min_overlap_dup: Some(0.6),
+            slack_ins: Some(100),
+            slack_bnd: Some(200),
+            ..Default::default()
+        };
+
+        assert_eq!(overlap.min_overlap(sv_type), min_overlap);
+        assert_eq!(overlap.slack(sv_type), slack);
+    }
+
+    #[rstest::rstest]
+    #[case("<INS:ME:ALU>", Some(SvSubType::InsMeAlu))]
+    #[case("<INS:ME:LINE1>", Some(SvSubType::InsMeL1))]
+    #[case("INS:ME:SVA", Some(SvSubType::InsMeSva))]
+    #[case("<INS:ME>", Some(SvSubType::InsMe))]
+    #[case("<INS:ME:HERV>", Some(SvSubType::InsMe))]
+    #[case("<INS>", None)]
+    #[case("<INS:MEX>", None)]
+    fn test_sv_sub_type_from_ins_me_str(#[case] value: &str, #[case] expected: Option<SvSubType>) {
+        assert_eq!(SvSubType::from_ins_me_str(value), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(SvSubType::InsMeAlu, SvSubType::InsMeAlu, true)]
+    #[case(SvSubType::InsMeAlu, SvSubType::InsMe, true)]
+    #[case(SvSubType::InsMe, SvSubType::InsMeSva, true)]
+    #[case(SvSubType::InsMeAlu, SvSubType::InsMeL1, false)]
+    #[case(SvSubType::InsMeAlu, SvSubType::Ins, false)]
+    fn test_sv_sub_type_is_compatible_me(
+        #[case] lhs: SvSubType,
+        #[case] rhs: SvSubType,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(lhs.is_compatible_me(rhs), expected);
+    }
+
     #[test]
     fn test_case_query_serde_smoke() {
         let query: CaseQuery = CaseQuery::default();
@@ -2078,6 +2602,8 @@ mod tests {
             chrom2: None,
             end: 200,
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             callers: Vec::new(),
             call_info: IndexMap::new(),
         };
@@ -2094,6 +2620,8 @@ mod tests {
             chrom2: None,
             end: 100,
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             callers: Vec::new(),
             call_info: IndexMap::new(),
         };
@@ -2110,6 +2638,8 @@ mod tests {
             chrom2: Some("chr2".to_owned()),
             end: 200,
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             callers: Vec::new(),
             call_info: IndexMap::new(),
         };
@@ -2126,12 +2656,40 @@ mod tests {
             chrom2: None,
             end: 245,
             strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
             callers: Vec::new(),
             call_info: IndexMap::new(),
         };
         insta::assert_snapshot!(serde_json::to_string_pretty(&sv).unwrap());
     }
 
+    #[rstest::rstest]
+    #[case::slack(None, 950..1050)]
+    #[case::ci_wide(Some((-100, 200)), 900..1200)]
+    #[case::ci_mixed(Some((-10, 200)), 950..1200)]
+    #[case::ci_narrow(Some((0, 0)), 950..1050)]
+    fn test_structural_variant_pos_window(
+        #[case] ci_pos: Option<(i32, i32)>,
+        #[case] expected: std::ops::Range<i32>,
+    ) {
+        let sv = StructuralVariant {
+            chrom: "chr1".to_owned(),
+            pos: 1000,
+            sv_type: SvType::Bnd,
+            sv_sub_type: SvSubType::Bnd,
+            chrom2: Some("chr2".to_owned()),
+            end: 2000,
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos,
+            ci_end: None,
+            callers: Vec::new(),
+            call_info: IndexMap::new(),
+        };
+        assert_eq!(sv.pos_window(50), expected);
+        assert_eq!(sv.end_window(50), 1950..2050);
+    }
+
     #[test]
     fn test_sv_sub_type_is_ins() {
         assert_eq!(SvSubType::Del.is_ins(), false);
@@ -0,0 +1,241 @@
+//! Code for matching recurrent microdeletion/microduplication syndromes.
+
+use std::path::Path;
+
+use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
+use indexmap::IndexMap;
+use mehari::common::io::std::open_read_maybe_gz;
+use serde::Serialize;
+use tracing::info;
+
+use crate::common::{build_chrom_map, GenomeRelease, CHROMS};
+
+use super::{
+    bgdbs::{reciprocal_overlap, BeginEnd},
+    schema::{StructuralVariant, SvType},
+};
+
+/// Alias for the interval tree that we use.
+type IntervalTree = ArrayBackedIntervalTree<i32, u32>;
+
+/// Information to store for a recurrent CNV syndrome region.
+#[derive(Default, Debug, Serialize, Clone)]
+pub struct Record {
+    /// 0-based begin position.
+    pub begin: i32,
+    /// End position.
+    pub end: i32,
+    /// Type of the syndrome CNV, either `DEL` or `DUP`.
+    pub sv_type: SvType,
+    /// Name of the syndrome, e.g., `22q11.2 deletion syndrome`.
+    pub name: String,
+}
+
+impl BeginEnd for Record {
+    fn begin(&self) -> i32 {
+        self.begin
+    }
+
+    fn end(&self) -> i32 {
+        self.end
+    }
+}
+
+/// A syndrome region matching a structural variant.
+#[derive(Debug, Clone)]
+pub struct SyndromeMatch {
+    /// The syndrome record.
+    pub record: Record,
+    /// The reciprocal overlap with the structural variant.
+    pub overlap: f32,
+}
+
+/// Code for recurrent CNV syndrome overlappers.
+#[derive(Default, Debug)]
+pub struct SyndromeDb {
+    /// Records, stored by chromosome.
+    pub records: Vec<Vec<Record>>,
+    /// Interval trees, stored by chromosome.
+    pub trees: Vec<IntervalTree>,
+}
+
+impl SyndromeDb {
+    /// Return the syndromes matching `sv` with at least `min_overlap` reciprocal overlap.
+    ///
+    /// Deletions only match deletion syndromes and duplications only duplication
+    /// syndromes while CNVs match both.  Other SV types never match.
+    pub fn matching_records(
+        &self,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+        min_overlap: f32,
+    ) -> Vec<SyndromeMatch> {
+        if self.trees.is_empty() || !matches!(sv.sv_type, SvType::Del | SvType::Dup | SvType::Cnv) {
+            return Vec::new();
+        }
+
+        let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
+        let range = sv.pos.saturating_sub(1)..sv.end;
+        self.trees[chrom_idx]
+            .find(range.clone())
+            .iter()
+            .map(|e| &self.records[chrom_idx][*e.data() as usize])
+            .filter(|record| sv.sv_type == SvType::Cnv || record.sv_type == sv.sv_type)
+            .map(|record| SyndromeMatch {
+                record: record.clone(),
+                overlap: reciprocal_overlap(record, &range),
+            })
+            .filter(|m| m.overlap >= min_overlap)
+            .collect()
+    }
+}
+
+/// Module with code for loading data from input.
+mod input {
+    use serde::Deserialize;
+
+    use crate::strucvars::query::schema::SvType;
+
+    /// Type for record structs from input.
+    #[derive(Deserialize, Debug)]
+    pub struct Record {
+        /// Chromosome name
+        pub chrom: String,
+        /// 0-based begin position from BED.
+        pub begin: i32,
+        /// 0-based end position from BED.
+        pub end: i32,
+        /// Name of the syndrome.
+        pub name: String,
+        /// Type of the syndrome CNV.
+        pub sv_type: SvType,
+    }
+}
+
+#[tracing::instrument]
+fn load_syndrome_db_records(path: &Path) -> Result<SyndromeDb, anyhow::Error> {
+    tracing::debug!("loading CNV syndrome records from {:?}...", path);
+    let chrom_map = build_chrom_map();
+
+    let mut result = SyndromeDb::default();
+    for _ in CHROMS {
+        result.records.push(Vec::new());
+        result.trees.push(IntervalTree::new());
+    }
+
+    // Setup CSV reader for BED file - header is written as comment and must be
+    // ignored.
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false) // BED has no header
+        .comment(Some(b'#'))
+        .delimiter(b'\t')
+        .from_reader(open_read_maybe_gz(path.to_str().unwrap())?);
+    let mut total_count = 0;
+    for record in reader.deserialize() {
+        let record: input::Record = record?;
+        let chrom_idx = *chrom_map.get(&record.chrom).expect("invalid chromosome");
+
+        let key = record.begin..record.end;
+        result.trees[chrom_idx].insert(key, result.records[chrom_idx].len() as u32);
+        result.records[chrom_idx].push(Record {
+            begin: record.begin,
+            end: record.end,
+            sv_type: record.sv_type,
+            name: record.name,
+        });
+
+        total_count += 1;
+    }
+    result.trees.iter_mut().for_each(|tree| tree.index());
+    tracing::debug!(
+        "... done loading {} records and building trees",
+        total_count
+    );
+
+    Ok(result)
+}
+
+/// Load the recurrent CNV syndromes from the database, if present.
+#[tracing::instrument]
+pub fn load_syndrome_db(
+    path_db: &str,
+    genome_release: GenomeRelease,
+) -> Result<SyndromeDb, anyhow::Error> {
+    info!("Loading CNV syndromes db");
+    let path = Path::new(path_db).join(format!("{}/strucvars/cnv_syndromes.bed", genome_release));
+    if path.exists() {
+        load_syndrome_db_records(&path)
+    } else {
+        info!("  no CNV syndromes at {:?}", &path);
+        Ok(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+    use mehari::annotate::strucvars::csq::interface::StrandOrientation;
+
+    use super::{Record, SyndromeDb};
+    use crate::{
+        common::{build_chrom_map, CHROMS},
+        strucvars::query::schema::{StructuralVariant, SvSubType, SvType},
+    };
+
+    fn syndrome_db() -> SyndromeDb {
+        let mut db = SyndromeDb {
+            records: vec![Vec::new(); CHROMS.len()],
+            trees: CHROMS.iter().map(|_| super::IntervalTree::new()).collect(),
+        };
+        let chrom_idx = *build_chrom_map().get("22").unwrap();
+        for (idx, sv_type) in [SvType::Del, SvType::Dup].into_iter().enumerate() {
+            db.trees[chrom_idx].insert(1000..2000, idx as u32);
+            db.records[chrom_idx].push(Record {
+                begin: 1000,
+                end: 2000,
+                sv_type,
+                name: format!("22q11.2 {:?}", sv_type),
+            });
+        }
+        db.trees.iter_mut().for_each(|tree| tree.index());
+        db
+    }
+
+    #[rstest::rstest]
+    #[case(SvType::Del, 1001, 2000, 0.8, vec!["22q11.2 Del"])]
+    #[case(SvType::Dup, 1001, 2000, 0.8, vec!["22q11.2 Dup"])]
+    #[case(SvType::Cnv, 1001, 2000, 0.8, vec!["22q11.2 Del", "22q11.2 Dup"])]
+    #[case(SvType::Del, 1001, 1500, 0.8, vec![])]
+    #[case(SvType::Del, 1001, 1500, 0.5, vec!["22q11.2 Del"])]
+    #[case(SvType::Inv, 1001, 2000, 0.8, vec![])]
+    fn matching_records(
+        #[case] sv_type: SvType,
+        #[case] pos: i32,
+        #[case] end: i32,
+        #[case] min_overlap: f32,
+        #[case] expected: Vec<&str>,
+    ) {
+        let sv = StructuralVariant {
+            chrom: "22".to_owned(),
+            pos,
+            sv_type,
+            sv_sub_type: SvSubType::Del,
+            chrom2: None,
+            end,
+            strand_orientation: StrandOrientation::ThreeToFive,
+            ci_pos: None,
+            ci_end: None,
+            callers: Vec::new(),
+            call_info: IndexMap::new(),
+        };
+
+        let mut names = syndrome_db()
+            .matching_records(&sv, &build_chrom_map(), min_overlap)
+            .into_iter()
+            .map(|m| m.record.name)
+            .collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(names, expected);
+    }
+}
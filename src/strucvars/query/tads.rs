@@ -32,6 +32,41 @@ pub struct Record {
     pub end: i32,
 }
 
+/// Disruption of TADs by a structural variant.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct TadDisruption {
+    /// Number of TAD boundaries spanned by the SV, for break-ends the number of
+    /// positions within a TAD.
+    pub boundaries_spanned: u32,
+    /// Number of genes in the overlapping TADs but not overlapping the SV itself
+    /// that are re-assigned across a boundary.
+    pub genes_reassigned: u32,
+}
+
+impl TadDisruption {
+    /// Construct from the number of spanned boundaries and genes in the overlapping TADs.
+    pub fn new(boundaries_spanned: u32, tad_genes: usize) -> Self {
+        Self {
+            boundaries_spanned,
+            genes_reassigned: if boundaries_spanned > 0 {
+                tad_genes as u32
+            } else {
+                0
+            },
+        }
+    }
+
+    /// Return the disruption score, `0` if no boundary is spanned and one plus the
+    /// number of re-assigned genes otherwise.
+    pub fn score(&self) -> u32 {
+        if self.boundaries_spanned > 0 {
+            1 + self.genes_reassigned
+        } else {
+            0
+        }
+    }
+}
+
 /// TAD set overlapping information.
 #[derive(Default, Debug)]
 pub struct TadSet {
@@ -90,20 +125,11 @@ impl TadSet {
                         .get(sv.chrom2.as_ref().expect("no chrom2?"))
                         .unwrap_or_else(|| panic!("invalid chromosome: {:?}", &sv.chrom2));
                     vec![
-                        (
-                            chrom_idx,
-                            sv.pos.saturating_sub(BND_SLACK)..sv.pos.saturating_add(BND_SLACK),
-                        ),
-                        (
-                            chrom_idx2,
-                            sv.end.saturating_sub(BND_SLACK)..sv.end.saturating_add(BND_SLACK),
-                        ),
+                        (chrom_idx, sv.pos_window(BND_SLACK)),
+                        (chrom_idx2, sv.end_window(BND_SLACK)),
                     ]
                 }
-                SvType::Ins => vec![(
-                    chrom_idx,
-                    sv.pos.saturating_sub(INS_SLACK)..sv.pos.saturating_sub(INS_SLACK),
-                )],
+                SvType::Ins => vec![(chrom_idx, sv.pos_window(INS_SLACK))],
                 _ => vec![(chrom_idx, sv.pos.saturating_sub(1)..sv.end)],
             }
         };
@@ -120,6 +146,39 @@ impl TadSet {
         result
     }
 
+    /// Return the number of TAD boundaries spanned by `sv`.
+    ///
+    /// For break-ends, the number of positions within a TAD is returned.
+    pub fn spanned_boundaries(
+        &self,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+    ) -> u32 {
+        let chrom_idx = *chrom_map.get(&sv.chrom).expect("invalid chromosome");
+        match sv.sv_type {
+            SvType::Bnd => {
+                let chrom_idx2 = *chrom_map
+                    .get(sv.chrom2.as_ref().expect("no chrom2?"))
+                    .expect("invalid chromosome");
+                [(chrom_idx, sv.pos), (chrom_idx2, sv.end)]
+                    .into_iter()
+                    .filter(|(chrom_idx, pos)| {
+                        !self.records_trees[*chrom_idx]
+                            .find(pos.saturating_sub(1)..*pos)
+                            .is_empty()
+                    })
+                    .count() as u32
+            }
+            SvType::Ins => 0,
+            _ => self.boundaries_trees[chrom_idx]
+                .find(sv.pos.saturating_sub(1)..sv.end)
+                .iter()
+                .map(|cursor| self.boundaries[chrom_idx][*cursor.data() as usize])
+                .filter(|boundary| sv.pos <= *boundary && *boundary < sv.end)
+                .count() as u32,
+        }
+    }
+
     pub fn boundary_dist(
         &self,
         sv: &StructuralVariant,
@@ -210,6 +269,17 @@ impl TadSetBundle {
         }
     }
 
+    pub fn spanned_boundaries(
+        &self,
+        tad_set: TadSetChoice,
+        sv: &StructuralVariant,
+        chrom_map: &IndexMap<String, usize>,
+    ) -> u32 {
+        match tad_set {
+            TadSetChoice::Hesc => self.hesc.spanned_boundaries(sv, chrom_map),
+        }
+    }
+
     pub fn boundary_dist(
         &self,
         tad_set: TadSetChoice,
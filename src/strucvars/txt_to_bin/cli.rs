@@ -5,9 +5,9 @@ use std::path::PathBuf;
 use clap::Parser;
 
 use crate::{
-    common::trace_rss_now,
+    common::{trace_rss_now, GenomeRelease},
     strucvars::txt_to_bin::{
-        clinvar, masked,
+        clinvar, info, masked,
         vardbs::{self, InputFileType},
         xlink,
     },
@@ -67,6 +67,12 @@ pub struct Args {
     /// Path to output BIN file.
     #[arg(long)]
     pub path_output: PathBuf,
+    /// Genome release of the input file, stored in the provenance.
+    #[arg(long, value_enum)]
+    pub genome_release: Option<GenomeRelease>,
+    /// Version of the input data (e.g., the download date), stored in the provenance.
+    #[arg(long)]
+    pub source_version: Option<String>,
 }
 
 /// Main entry point for the `strucvars txt-to-bin` command.
@@ -77,46 +83,75 @@ pub fn run(common_args: &crate::common::Args, args: &Args) -> Result<(), anyhow:
 
     trace_rss_now();
 
+    let provenance = info::build_provenance(
+        &args.path_input,
+        args.genome_release,
+        args.source_version.clone(),
+    )?;
+
     tracing::info!("Starting conversion...");
     match args.input_type {
-        InputType::ClinvarSv => clinvar::convert_to_bin(&args.path_input, &args.path_output)?,
+        InputType::ClinvarSv => {
+            clinvar::convert_to_bin(&args.path_input, &args.path_output, provenance)?
+        }
         InputType::StrucvarInhouse => vardbs::convert_to_bin(
             &args.path_input,
             &args.path_output,
             InputFileType::InhouseDb,
+            provenance,
+        )?,
+        InputType::StrucvarDbVar => vardbs::convert_to_bin(
+            &args.path_input,
+            &args.path_output,
+            InputFileType::Dbvar,
+            provenance,
+        )?,
+        InputType::StrucvarDgv => vardbs::convert_to_bin(
+            &args.path_input,
+            &args.path_output,
+            InputFileType::Dgv,
+            provenance,
+        )?,
+        InputType::StrucvarDgvGs => vardbs::convert_to_bin(
+            &args.path_input,
+            &args.path_output,
+            InputFileType::DgvGs,
+            provenance,
+        )?,
+        InputType::StrucvarExacCnv => vardbs::convert_to_bin(
+            &args.path_input,
+            &args.path_output,
+            InputFileType::Exac,
+            provenance,
+        )?,
+        InputType::StrucvarG1k => vardbs::convert_to_bin(
+            &args.path_input,
+            &args.path_output,
+            InputFileType::G1k,
+            provenance,
         )?,
-        InputType::StrucvarDbVar => {
-            vardbs::convert_to_bin(&args.path_input, &args.path_output, InputFileType::Dbvar)?
-        }
-        InputType::StrucvarDgv => {
-            vardbs::convert_to_bin(&args.path_input, &args.path_output, InputFileType::Dgv)?
-        }
-        InputType::StrucvarDgvGs => {
-            vardbs::convert_to_bin(&args.path_input, &args.path_output, InputFileType::DgvGs)?
-        }
-        InputType::StrucvarExacCnv => {
-            vardbs::convert_to_bin(&args.path_input, &args.path_output, InputFileType::Exac)?
-        }
-        InputType::StrucvarG1k => {
-            vardbs::convert_to_bin(&args.path_input, &args.path_output, InputFileType::G1k)?
-        }
         InputType::StrucvarGnomadSv2 => vardbs::convert_to_bin(
             &args.path_input,
             &args.path_output,
             InputFileType::GnomadSv2,
+            provenance,
         )?,
         InputType::StrucvarGnomadCnv4 => vardbs::convert_to_bin(
             &args.path_input,
             &args.path_output,
             InputFileType::GnomadCnv4,
+            provenance,
         )?,
         InputType::StrucvarGnomadSv4 => vardbs::convert_to_bin(
             &args.path_input,
             &args.path_output,
             InputFileType::GnomadSv4,
+            provenance,
         )?,
-        InputType::MaskedRegion => masked::convert_to_bin(&args.path_input, &args.path_output)?,
-        InputType::Xlink => xlink::convert_to_bin(&args.path_input, &args.path_output)?,
+        InputType::MaskedRegion => {
+            masked::convert_to_bin(&args.path_input, &args.path_output, provenance)?
+        }
+        InputType::Xlink => xlink::convert_to_bin(&args.path_input, &args.path_output, provenance)?,
     }
     tracing::info!("... done with conversion");
 
@@ -145,6 +180,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/clinvar/clinvar-svs.jsonl.gz",
             ),
             path_output: tmp_dir.join("clinvar.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -164,6 +201,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/inhouse.tsv",
             ),
             path_output: tmp_dir.join("strucvar_inhouse.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -183,6 +222,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/dbvar.bed.gz",
             ),
             path_output: tmp_dir.join("strucvar_dbvar.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -202,6 +243,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/dgv.bed.gz",
             ),
             path_output: tmp_dir.join("strucvar_dgv.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -221,6 +264,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/dgv_gs.bed.gz",
             ),
             path_output: tmp_dir.join("strucvar_dgv_gs.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -240,6 +285,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/exac.bed.gz",
             ),
             path_output: tmp_dir.join("exac.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -259,6 +306,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/g1k.bed.gz",
             ),
             path_output: tmp_dir.join("g1k.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -278,6 +327,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch37/strucvar/gnomad_sv.bed.gz",
             ),
             path_output: tmp_dir.join("gnomad.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -297,6 +348,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch38/strucvar/gnomad-cnv.bed.gz",
             ),
             path_output: tmp_dir.join("gnomad-cnv.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -316,6 +369,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/vardbs/grch38/strucvar/gnomad-sv.bed.gz",
             ),
             path_output: tmp_dir.join("gnomad-sv.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -335,6 +390,8 @@ mod test {
                 "tests/db/to-bin/varfish-db-downloader/features/grch37/masked/repeat.bed.gz",
             ),
             path_output: tmp_dir.join("masked.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -352,6 +409,8 @@ mod test {
             input_type: InputType::Xlink,
             path_input: String::from("tests/db/to-bin/varfish-db-downloader/genes/xlink/hgnc.tsv"),
             path_output: tmp_dir.join("xlink.bin"),
+            genome_release: None,
+            source_version: None,
         };
 
         super::run(&common_args, &args)?;
@@ -10,13 +10,19 @@ use crate::{
     common::{build_chrom_map, trace_rss_now},
     pbs::{
         self,
-        varfish::v1::strucvars::clinvar::{Pathogenicity, SvDatabase, SvRecord},
+        varfish::v1::strucvars::clinvar::{Pathogenicity, ReviewStatus, SvDatabase, SvRecord},
     },
 };
 
 // pub mod input;
 
-/// Helper to convert RCV IDs to numbers.
+/// Version of the schema of the records written by `convert_to_bin`.
+///
+/// Increment when the meaning of the records changes so that older workers
+/// refuse to load the file.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Helper to convert RCV and VCV IDs to numbers.
 fn numeric_id(raw_id: &str, prefix: &str) -> Result<u32, anyhow::Error> {
     let clean_id: String = raw_id
         .chars()
@@ -25,7 +31,7 @@ fn numeric_id(raw_id: &str, prefix: &str) -> Result<u32, anyhow::Error> {
         .collect();
     clean_id
         .parse::<u32>()
-        .map_err(|e| anyhow::anyhow!("could not parse RCV id {:?}: {}", &clean_id, &e))
+        .map_err(|e| anyhow::anyhow!("could not parse {} id {:?}: {}", prefix, raw_id, &e))
 }
 
 /// Read JSONL file and convert to protobuf records.
@@ -103,6 +109,17 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
             }
         };
 
+        // Convert review status from upstream to internal protocolbuffers.
+        let review_status = match annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::try_from(agc.review_status) {
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::NoAssertionCriteriaProvided) => ReviewStatus::NoAssertionCriteriaProvided,
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::CriteriaProvidedConflictingClassifications) => ReviewStatus::CriteriaProvidedConflictingClassifications,
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::CriteriaProvidedSingleSubmitter) => ReviewStatus::CriteriaProvidedSingleSubmitter,
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::CriteriaProvidedMultipleSubmittersNoConflicts) => ReviewStatus::CriteriaProvidedMultipleSubmittersNoConflicts,
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::ReviewedByExpertPanel) => ReviewStatus::ReviewedByExpertPanel,
+            Ok(annonars::pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::PracticeGuideline) => ReviewStatus::PracticeGuideline,
+            _ => ReviewStatus::Unspecified,
+        };
+
         // Convert variation type from upstream to internal protocolbuffers.
         let variation_type = match annonars::pbs::clinvar_data::extracted_vars::VariationType::try_from(record.variation_type) {
             Ok(variation_type) => match variation_type {
@@ -154,6 +171,7 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
                 pathogenicity: pathogenicity as i32,
                 rcv,
                 vcv,
+                review_status: review_status as i32,
             });
         } else if let (Some(inner_start), Some(inner_stop)) = (sl.inner_start, sl.inner_stop) {
             records.push(SvRecord {
@@ -164,6 +182,7 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
                 pathogenicity: pathogenicity as i32,
                 rcv,
                 vcv,
+                review_status: review_status as i32,
             });
         } else if let (Some(outer_start), Some(outer_stop)) = (sl.outer_start, sl.outer_stop) {
             records.push(SvRecord {
@@ -174,6 +193,7 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
                 pathogenicity: pathogenicity as i32,
                 rcv,
                 vcv,
+                review_status: review_status as i32,
             });
         } else if let (Some(position_vcf), Some(reference_allele_vcf), Some(_)) = (
             sl.position_vcf,
@@ -188,6 +208,7 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
                 pathogenicity: pathogenicity as i32,
                 rcv,
                 vcv,
+                review_status: review_status as i32,
             });
         }
     }
@@ -195,7 +216,11 @@ fn convert_jsonl_to_protobuf(reader: Box<dyn BufRead>) -> Result<Vec<SvRecord>,
 }
 
 /// Perform conversion to protocolbuffers `.bin` file.
-pub fn convert_to_bin<P, Q>(path_input_jsonl: P, path_output: Q) -> Result<(), anyhow::Error>
+pub fn convert_to_bin<P, Q>(
+    path_input_jsonl: P,
+    path_output: Q,
+    provenance: pbs::varfish::v1::common::misc::Provenance,
+) -> Result<(), anyhow::Error>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
@@ -205,7 +230,13 @@ where
 
     let records = convert_jsonl_to_protobuf(reader)?;
 
-    let clinvar_db = SvDatabase { records };
+    let clinvar_db = SvDatabase {
+        records,
+        provenance: Some(pbs::varfish::v1::common::misc::Provenance {
+            schema_version: SCHEMA_VERSION,
+            ..provenance
+        }),
+    };
 
     tracing::debug!(
         "total time spent reading {} records: {:?}",
@@ -229,6 +260,14 @@ where
 
 #[cfg(test)]
 mod test {
+    #[rstest::rstest]
+    #[case::vcv("VCV000000536", "VCV", Some(536))]
+    #[case::rcv("RCV000000566", "RCV", Some(566))]
+    #[case::invalid("VCVxyz", "VCV", None)]
+    fn numeric_id(#[case] raw_id: &str, #[case] prefix: &str, #[case] expected: Option<u32>) {
+        assert_eq!(super::numeric_id(raw_id, prefix).ok(), expected);
+    }
+
     #[tracing_test::traced_test]
     #[rstest::rstest]
     fn run_convert_jsonl_to_protobuf() -> Result<(), anyhow::Error> {
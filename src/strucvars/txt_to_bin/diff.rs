@@ -0,0 +1,320 @@
+//! Implementation of the `strucvars bin-diff` command.
+//!
+//! Compares the binary databases of two database directories, e.g., before
+//! and after a data update, and reports the added, removed, and changed
+//! records per track.
+
+use std::{collections::HashMap, hash::Hash, path::Path};
+
+use clap::Parser;
+use prost::Message;
+
+use crate::{
+    common::GenomeRelease,
+    pbs::varfish::v1::strucvars::{bgdb, clinvar},
+};
+
+/// Command line arguments for `strucvars bin-diff` sub command.
+#[derive(Parser, Debug)]
+#[command(about = "Compare binary databases of two database directories", long_about = None)]
+pub struct Args {
+    /// Path to the old database directory.
+    #[arg(long)]
+    pub path_db_old: String,
+    /// Path to the new database directory.
+    #[arg(long)]
+    pub path_db_new: String,
+    /// Genome release of the tracks to compare.
+    #[arg(long, value_enum, default_value_t = GenomeRelease::Grch37)]
+    pub genome_release: GenomeRelease,
+}
+
+/// Kind of a track, determines the record type and key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    /// Background database of structural variants.
+    Background,
+    /// ClinVar structural variants.
+    Clinvar,
+    /// Masked regions.
+    Masked,
+    /// Gene cross-links.
+    Xlink,
+}
+
+/// Return the tracks of a database directory with name, path, and kind.
+pub fn tracks(genome_release: GenomeRelease) -> Vec<(String, String, TrackKind)> {
+    let mut result = [
+        "dbvar",
+        "dgv",
+        "dgv_gs",
+        "g1k",
+        "gnomad_exomes",
+        "gnomad_genomes",
+    ]
+    .iter()
+    .map(|name| {
+        (
+            name.to_string(),
+            format!("{}/strucvars/bgdbs/{}.bin", genome_release, name),
+            TrackKind::Background,
+        )
+    })
+    .collect::<Vec<_>>();
+    result.push((
+        "inhouse".into(),
+        format!("{}/strucvars/inhouse.bin", genome_release),
+        TrackKind::Background,
+    ));
+    result.push((
+        "clinvar".into(),
+        format!("{}/strucvars/clinvar.bin", genome_release),
+        TrackKind::Clinvar,
+    ));
+    for name in [
+        "masked_repeat",
+        "masked_segdup",
+        "masked_low_complexity",
+        "masked_blacklist",
+    ] {
+        result.push((
+            name.into(),
+            format!("{}/features/{}.bin", genome_release, name),
+            TrackKind::Masked,
+        ));
+    }
+    result.push((
+        "xlink".into(),
+        "noref/genes/xlink.bin".into(),
+        TrackKind::Xlink,
+    ));
+    result
+}
+
+/// Differences of one track.
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackDiff {
+    /// Name of the track.
+    pub name: String,
+    /// Path of the track relative to the database directory.
+    pub path: String,
+    /// Number of records in the old database, `None` if missing.
+    pub count_old: Option<usize>,
+    /// Number of records in the new database, `None` if missing.
+    pub count_new: Option<usize>,
+    /// Number of records only in the new database.
+    pub added: usize,
+    /// Number of records only in the old database.
+    pub removed: usize,
+    /// Number of records with the same key but different values.
+    pub changed: usize,
+}
+
+/// Count added, removed, and changed records by the key given by `key`.
+pub fn diff_records<R, K, F>(old: &[R], new: &[R], key: F) -> (usize, usize, usize)
+where
+    R: PartialEq,
+    K: Eq + Hash,
+    F: Fn(&R) -> K,
+{
+    let mut by_key: HashMap<K, (Vec<&R>, Vec<&R>)> = HashMap::new();
+    for record in old {
+        by_key.entry(key(record)).or_default().0.push(record);
+    }
+    for record in new {
+        by_key.entry(key(record)).or_default().1.push(record);
+    }
+
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for (old, new) in by_key.values() {
+        added += new.len().saturating_sub(old.len());
+        removed += old.len().saturating_sub(new.len());
+        if old != new {
+            changed += old.len().min(new.len());
+        }
+    }
+    (added, removed, changed)
+}
+
+/// Decode the records of the file at `path`, `None` if it does not exist.
+fn read_records<M: Message + Default, R>(
+    path: &Path,
+    records: fn(M) -> Vec<R>,
+) -> Result<Option<Vec<R>>, anyhow::Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let fcontents =
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("error reading {:?}: {}", path, e))?;
+    let db = M::decode(std::io::Cursor::new(fcontents))
+        .map_err(|e| anyhow::anyhow!("error decoding {:?}: {}", path, e))?;
+    Ok(Some(records(db)))
+}
+
+/// Compare the records at `path_old` and `path_new` by the key given by `key`.
+fn diff_files<M: Message + Default, R: PartialEq, K: Eq + Hash>(
+    path_old: &Path,
+    path_new: &Path,
+    records: fn(M) -> Vec<R>,
+    key: fn(&R) -> K,
+) -> Result<TrackDiff, anyhow::Error> {
+    let old = read_records(path_old, records)?;
+    let new = read_records(path_new, records)?;
+    let (added, removed, changed) = diff_records(
+        old.as_deref().unwrap_or_default(),
+        new.as_deref().unwrap_or_default(),
+        key,
+    );
+    Ok(TrackDiff {
+        count_old: old.map(|records| records.len()),
+        count_new: new.map(|records| records.len()),
+        added,
+        removed,
+        changed,
+        ..Default::default()
+    })
+}
+
+/// Compare the track at `path` of kind `kind` in `path_db_old` and `path_db_new`.
+pub fn diff_track(
+    path_db_old: &str,
+    path_db_new: &str,
+    path: &str,
+    kind: TrackKind,
+) -> Result<TrackDiff, anyhow::Error> {
+    let path_old = Path::new(path_db_old).join(path);
+    let path_new = Path::new(path_db_new).join(path);
+    match kind {
+        TrackKind::Background => diff_files(
+            &path_old,
+            &path_new,
+            |db: bgdb::BackgroundDatabase| db.records,
+            |r: &bgdb::BgDbRecord| {
+                (
+                    r.chrom_no,
+                    r.chrom_no2,
+                    r.sv_type,
+                    r.start,
+                    r.stop,
+                    r.mei_type,
+                )
+            },
+        ),
+        TrackKind::Clinvar => diff_files(
+            &path_old,
+            &path_new,
+            |db: clinvar::SvDatabase| db.records,
+            |r: &clinvar::SvRecord| r.rcv,
+        ),
+        TrackKind::Masked => diff_files(
+            &path_old,
+            &path_new,
+            |db: bgdb::MaskedDatabase| db.records,
+            |r: &bgdb::MaskedDbRecord| (r.chrom_no, r.start, r.stop),
+        ),
+        TrackKind::Xlink => diff_files(
+            &path_old,
+            &path_new,
+            |db: bgdb::XlinkDatabase| db.records,
+            |r: &bgdb::XlinkRecord| r.hgnc_id.clone(),
+        ),
+    }
+}
+
+/// Main entry point for the `strucvars bin-diff` command.
+pub fn run(common_args: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting `strucvars bin-diff`");
+    tracing::info!("  common_args = {:?}", &common_args);
+    tracing::info!("  args = {:?}", &args);
+
+    for (name, path, kind) in tracks(args.genome_release) {
+        let diff = diff_track(&args.path_db_old, &args.path_db_new, &path, kind)?;
+        let diff = TrackDiff { name, path, ..diff };
+        if diff.count_old.is_none() && diff.count_new.is_none() {
+            tracing::debug!("skipping {}, missing in both directories", &diff.path);
+            continue;
+        }
+        println!("{}", serde_json::to_string(&diff)?);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use prost::Message as _;
+
+    use crate::pbs::varfish::v1::strucvars::bgdb::{MaskedDatabase, MaskedDbRecord};
+
+    use super::TrackKind;
+
+    #[rstest::rstest]
+    #[case::equal(vec![(1, 1), (2, 1)], vec![(1, 1), (2, 1)], (0, 0, 0))]
+    #[case::added(vec![(1, 1)], vec![(1, 1), (2, 1)], (1, 0, 0))]
+    #[case::removed(vec![(1, 1), (2, 1)], vec![(2, 1)], (0, 1, 0))]
+    #[case::changed(vec![(1, 1), (2, 1)], vec![(1, 2), (2, 1)], (0, 0, 1))]
+    #[case::duplicate(vec![(1, 1)], vec![(1, 1), (1, 1)], (1, 0, 0))]
+    fn diff_records(
+        #[case] old: Vec<(i32, i32)>,
+        #[case] new: Vec<(i32, i32)>,
+        #[case] expected: (usize, usize, usize),
+    ) {
+        assert_eq!(
+            super::diff_records(&old, &new, |r: &(i32, i32)| r.0),
+            expected
+        );
+    }
+
+    #[test]
+    fn diff_track_masked() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path = "grch37/features/masked_repeat.bin";
+        let write = |db_dir: &str, stops: &[i32]| -> Result<(), anyhow::Error> {
+            let data = MaskedDatabase {
+                records: stops
+                    .iter()
+                    .map(|stop| MaskedDbRecord {
+                        chrom_no: 0,
+                        start: 1,
+                        stop: *stop,
+                    })
+                    .collect(),
+                provenance: None,
+            };
+            let path_bin = tmpdir.join(db_dir).join(path);
+            std::fs::create_dir_all(path_bin.parent().unwrap())?;
+            std::fs::write(&path_bin, data.encode_to_vec())?;
+            Ok(())
+        };
+        write("old", &[10, 20])?;
+        write("new", &[20, 30, 40])?;
+
+        let diff = super::diff_track(
+            tmpdir.join("old").to_str().unwrap(),
+            tmpdir.join("new").to_str().unwrap(),
+            path,
+            TrackKind::Masked,
+        )?;
+
+        assert_eq!((diff.count_old, diff.count_new), (Some(2), Some(3)));
+        assert_eq!((diff.added, diff.removed, diff.changed), (2, 1, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_track_missing() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+
+        let diff = super::diff_track(
+            tmpdir.join("old").to_str().unwrap(),
+            tmpdir.join("new").to_str().unwrap(),
+            "noref/genes/xlink.bin",
+            TrackKind::Xlink,
+        )?;
+
+        assert_eq!((diff.count_old, diff.count_new), (None, None));
+
+        Ok(())
+    }
+}
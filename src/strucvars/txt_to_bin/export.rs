@@ -0,0 +1,385 @@
+//! Implementation of the `strucvars bin-export` command.
+//!
+//! Dumps the records of a binary file written by `strucvars txt-to-bin` as TSV,
+//! JSONL, or VCF so that they can be inspected with external tools.
+
+use std::io::Write;
+
+use clap::Parser;
+use mehari::common::io::std::open_write_maybe_bgzf;
+use prost::Message;
+
+use crate::{
+    common::CHROMS,
+    pbs::varfish::v1::strucvars::{bgdb, clinvar},
+    strucvars::txt_to_bin::cli::InputType,
+};
+
+/// Format of the exported file.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Tab-separated values with header, 1-based positions.
+    #[default]
+    Tsv,
+    /// One JSON record per line.
+    Jsonl,
+    /// VCF with symbolic alleles, only for structural variant databases.
+    Vcf,
+}
+
+/// Command line arguments for `strucvars bin-export` sub command.
+#[derive(Parser, Debug)]
+#[command(about = "Export binary protobuf files to text", long_about = None)]
+pub struct Args {
+    /// Type of the binary file, as given to `strucvars txt-to-bin`.
+    #[arg(long, value_enum)]
+    pub input_type: InputType,
+    /// Path to the BIN file.
+    #[arg(long)]
+    pub path_input: String,
+    /// Path to the output file, compressed if ending in `.gz`.
+    #[arg(long)]
+    pub path_output: String,
+    /// Format of the output file.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Tsv)]
+    pub output_format: OutputFormat,
+}
+
+/// Return the chromosome name for `chrom_no`.
+fn chrom_name(chrom_no: i32) -> &'static str {
+    CHROMS.get(chrom_no as usize).copied().unwrap_or(".")
+}
+
+/// Return the enum value `name` without `prefix` in lower case.
+fn enum_value(name: &str, prefix: &str) -> String {
+    name.trim_start_matches(prefix).to_lowercase()
+}
+
+/// Format the optional `value`, "." if missing.
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or(".".into())
+}
+
+/// A database record that can be exported.
+trait ExportRecord: serde::Serialize {
+    /// Column names of the TSV file.
+    const TSV_HEADER: &'static [&'static str];
+
+    /// Whether the records can be written as VCF.
+    const SUPPORTS_VCF: bool = false;
+
+    /// Values of the record in the TSV file.
+    fn tsv_values(&self) -> Vec<String>;
+
+    /// Values of `CHROM`, `POS`, `ALT`, and `INFO` of the record in the VCF file.
+    fn vcf_values(&self) -> Vec<String> {
+        unreachable!("VCF export is not supported")
+    }
+}
+
+impl ExportRecord for bgdb::BgDbRecord {
+    const TSV_HEADER: &'static [&'static str] = &[
+        "chrom", "chrom2", "sv_type", "start", "stop", "count", "ac", "an", "n_homalt", "mei_type",
+    ];
+    const SUPPORTS_VCF: bool = true;
+
+    fn tsv_values(&self) -> Vec<String> {
+        vec![
+            chrom_name(self.chrom_no).to_string(),
+            chrom_name(self.chrom_no2).to_string(),
+            enum_value(self.sv_type().as_str_name(), "SV_TYPE_"),
+            self.start.to_string(),
+            self.stop.to_string(),
+            self.count.to_string(),
+            optional(self.ac),
+            optional(self.an),
+            optional(self.n_homalt),
+            enum_value(self.mei_type().as_str_name(), "MEI_TYPE_"),
+        ]
+    }
+
+    fn vcf_values(&self) -> Vec<String> {
+        let sv_type = enum_value(self.sv_type().as_str_name(), "SV_TYPE_").to_uppercase();
+        let mut info = format!("END={};SVTYPE={};COUNT={}", self.stop, sv_type, self.count);
+        if self.chrom_no2 != self.chrom_no {
+            info.push_str(&format!(";CHR2={}", chrom_name(self.chrom_no2)));
+        }
+        for (key, value) in [
+            ("AC", self.ac),
+            ("AN", self.an),
+            ("N_HOMALT", self.n_homalt),
+        ] {
+            if let Some(value) = value {
+                info.push_str(&format!(";{}={}", key, value));
+            }
+        }
+        vec![
+            chrom_name(self.chrom_no).to_string(),
+            self.start.to_string(),
+            format!("<{}>", sv_type),
+            info,
+        ]
+    }
+}
+
+impl ExportRecord for clinvar::SvRecord {
+    const TSV_HEADER: &'static [&'static str] = &[
+        "chrom",
+        "start",
+        "stop",
+        "variation_type",
+        "pathogenicity",
+        "rcv",
+        "vcv",
+        "review_status",
+    ];
+    const SUPPORTS_VCF: bool = true;
+
+    fn tsv_values(&self) -> Vec<String> {
+        vec![
+            chrom_name(self.chrom_no).to_string(),
+            self.start.to_string(),
+            self.stop.to_string(),
+            enum_value(self.variation_type().as_str_name(), "VARIATION_TYPE_"),
+            enum_value(self.pathogenicity().as_str_name(), "PATHOGENICITY_"),
+            format!("RCV{:09}", self.rcv),
+            format!("VCV{:09}", self.vcv),
+            enum_value(self.review_status().as_str_name(), "REVIEW_STATUS_"),
+        ]
+    }
+
+    fn vcf_values(&self) -> Vec<String> {
+        let sv_type =
+            enum_value(self.variation_type().as_str_name(), "VARIATION_TYPE_").to_uppercase();
+        vec![
+            chrom_name(self.chrom_no).to_string(),
+            self.start.to_string(),
+            format!("<{}>", sv_type),
+            format!(
+                "END={};SVTYPE={};RCV=RCV{:09};VCV=VCV{:09};PATHOGENICITY={};REVIEW_STATUS={}",
+                self.stop,
+                sv_type,
+                self.rcv,
+                self.vcv,
+                enum_value(self.pathogenicity().as_str_name(), "PATHOGENICITY_"),
+                enum_value(self.review_status().as_str_name(), "REVIEW_STATUS_"),
+            ),
+        ]
+    }
+}
+
+impl ExportRecord for bgdb::MaskedDbRecord {
+    const TSV_HEADER: &'static [&'static str] = &["chrom", "start", "stop"];
+
+    fn tsv_values(&self) -> Vec<String> {
+        vec![
+            chrom_name(self.chrom_no).to_string(),
+            self.start.to_string(),
+            self.stop.to_string(),
+        ]
+    }
+}
+
+impl ExportRecord for bgdb::XlinkRecord {
+    const TSV_HEADER: &'static [&'static str] = &["hgnc_id", "symbol", "entrez_id", "ensembl_id"];
+
+    fn tsv_values(&self) -> Vec<String> {
+        vec![
+            self.hgnc_id.clone(),
+            self.symbol.clone(),
+            self.entrez_id.to_string(),
+            format!("ENSG{:011}", self.ensembl_id),
+        ]
+    }
+}
+
+/// Write `records` to `writer` in the given `format`.
+fn write_records<R: ExportRecord>(
+    records: &[R],
+    format: OutputFormat,
+    writer: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match format {
+        OutputFormat::Tsv => {
+            writeln!(writer, "{}", R::TSV_HEADER.join("\t"))?;
+            for record in records {
+                writeln!(writer, "{}", record.tsv_values().join("\t"))?;
+            }
+        }
+        OutputFormat::Jsonl => {
+            for record in records {
+                writeln!(writer, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+        OutputFormat::Vcf => {
+            if !R::SUPPORTS_VCF {
+                anyhow::bail!("VCF export is only supported for structural variant databases");
+            }
+            writeln!(writer, "##fileformat=VCFv4.4")?;
+            writeln!(
+                writer,
+                "##source=varfish-server-worker strucvars bin-export"
+            )?;
+            writeln!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+            for record in records {
+                let values = record.vcf_values();
+                writeln!(
+                    writer,
+                    "{}\t{}\t.\tN\t{}\t.\t.\t{}",
+                    values[0], values[1], values[2], values[3]
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Export the binary file at `path_input` of `input_type` to `writer`.
+pub fn export(
+    path_input: &str,
+    input_type: InputType,
+    format: OutputFormat,
+    writer: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let fcontents = std::fs::read(path_input)
+        .map_err(|e| anyhow::anyhow!("could not read {}: {}", path_input, e))?;
+    let buf = std::io::Cursor::new(fcontents);
+    match input_type {
+        InputType::ClinvarSv => {
+            write_records(&clinvar::SvDatabase::decode(buf)?.records, format, writer)
+        }
+        InputType::MaskedRegion => {
+            write_records(&bgdb::MaskedDatabase::decode(buf)?.records, format, writer)
+        }
+        InputType::Xlink => {
+            write_records(&bgdb::XlinkDatabase::decode(buf)?.records, format, writer)
+        }
+        _ => write_records(
+            &bgdb::BackgroundDatabase::decode(buf)?.records,
+            format,
+            writer,
+        ),
+    }
+}
+
+/// Main entry point for the `strucvars bin-export` command.
+pub fn run(common_args: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting `strucvars bin-export`");
+    tracing::info!("  common_args = {:?}", &common_args);
+    tracing::info!("  args = {:?}", &args);
+
+    let mut writer = open_write_maybe_bgzf(&args.path_output)
+        .map_err(|e| anyhow::anyhow!("could not open {} for writing: {}", args.path_output, e))?;
+    export(
+        &args.path_input,
+        args.input_type,
+        args.output_format,
+        &mut writer,
+    )?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use prost::Message as _;
+
+    use crate::pbs::varfish::v1::strucvars::bgdb::{BackgroundDatabase, BgDbRecord, SvType};
+
+    use super::{InputType, OutputFormat};
+
+    /// Write a background database with one record and export it in `format`.
+    fn export_background(format: OutputFormat) -> Result<(BgDbRecord, String), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_bin = tmpdir.join("dbvar.bin");
+        let record = BgDbRecord {
+            chrom_no: 0,
+            chrom_no2: 0,
+            sv_type: SvType::Del as i32,
+            start: 1000,
+            stop: 2000,
+            count: 3,
+            ac: Some(4),
+            ..Default::default()
+        };
+        let data = BackgroundDatabase {
+            records: vec![record.clone()],
+            provenance: None,
+        };
+        std::fs::write(&path_bin, data.encode_to_vec())?;
+
+        let mut buf = Vec::new();
+        super::export(
+            path_bin.to_str().unwrap(),
+            InputType::StrucvarDbVar,
+            format,
+            &mut buf,
+        )?;
+
+        Ok((record, String::from_utf8(buf)?))
+    }
+
+    #[test]
+    fn export_background_tsv() -> Result<(), anyhow::Error> {
+        let (_, output) = export_background(OutputFormat::Tsv)?;
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "chrom\tchrom2\tsv_type\tstart\tstop\tcount\tac\tan\tn_homalt\tmei_type",
+                "1\t1\tdel\t1000\t2000\t3\t4\t.\t.\tunspecified",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_background_jsonl() -> Result<(), anyhow::Error> {
+        let (record, output) = export_background(OutputFormat::Jsonl)?;
+
+        let records = output
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<BgDbRecord>, _>>()?;
+        assert_eq!(records, vec![record]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_background_vcf() -> Result<(), anyhow::Error> {
+        let (_, output) = export_background(OutputFormat::Vcf)?;
+
+        assert_eq!(
+            output.lines().last(),
+            Some("1\t1000\t.\tN\t<DEL>\t.\t.\tEND=2000;SVTYPE=DEL;COUNT=3;AC=4")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_xlink_vcf_fails() -> Result<(), anyhow::Error> {
+        let tmpdir = temp_testdir::TempDir::default();
+        let path_bin = tmpdir.join("xlink.bin");
+        super::super::xlink::convert_to_bin(
+            "tests/db/to-bin/varfish-db-downloader/genes/xlink/hgnc.tsv",
+            &path_bin,
+            Default::default(),
+        )?;
+
+        let mut buf = Vec::new();
+        let result = super::export(
+            path_bin.to_str().unwrap(),
+            InputType::Xlink,
+            OutputFormat::Vcf,
+            &mut buf,
+        );
+
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+//! Provenance of the binary files and the `strucvars bin-info` command.
+
+use std::{fs::File, io::Read, path::Path};
+
+use clap::Parser;
+use prost::Message;
+use sha2::Digest;
+
+use crate::{
+    common::{now_as_pbjson_timestamp, GenomeRelease},
+    pbs::varfish::v1::{
+        common::misc::{provenance::SourceFile, Provenance},
+        strucvars::{bgdb, clinvar},
+    },
+    strucvars::txt_to_bin::cli::InputType,
+};
+
+/// Describe the source file at `path` with its size and SHA256 checksum.
+pub fn source_file<P: AsRef<Path>>(path: P) -> Result<SourceFile, anyhow::Error> {
+    let mut file = File::open(path.as_ref())
+        .map_err(|e| anyhow::anyhow!("could not open {:?}: {}", path.as_ref(), e))?;
+    let mut hasher = sha2::Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    let mut size = 0;
+    loop {
+        let count = file.read(&mut buf)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buf[..count]);
+        size += count as u64;
+    }
+
+    Ok(SourceFile {
+        path: path.as_ref().display().to_string(),
+        size,
+        sha256: base16ct::lower::encode_string(&hasher.finalize()),
+    })
+}
+
+/// Build the provenance for a database compiled from `path_input` now.
+pub fn build_provenance<P: AsRef<Path>>(
+    path_input: P,
+    genome_release: Option<GenomeRelease>,
+    source_version: Option<String>,
+) -> Result<Provenance, anyhow::Error> {
+    Ok(Provenance {
+        sources: vec![source_file(path_input)?],
+        builder_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: Some(now_as_pbjson_timestamp()),
+        genome_release: genome_release.map(|release| release.to_string()),
+        source_version,
+        ..Default::default()
+    })
+}
+
+/// Check that the file at `path` with `provenance` has a schema version of at most `supported`.
+///
+/// Files written without provenance or schema version predate versioning and
+/// are accepted.
+pub fn check_schema_version(
+    path: &Path,
+    provenance: Option<&Provenance>,
+    supported: u32,
+) -> Result<(), anyhow::Error> {
+    let schema_version = provenance
+        .map(|provenance| provenance.schema_version)
+        .unwrap_or_default();
+    if schema_version > supported {
+        anyhow::bail!(
+            "{:?} has schema version {}, this worker supports up to {}; please update the worker",
+            path,
+            schema_version,
+            supported
+        );
+    }
+    Ok(())
+}
+
+/// Command line arguments for `strucvars bin-info` sub command.
+#[derive(Parser, Debug)]
+#[command(about = "Print provenance of binary protobuf files", long_about = None)]
+pub struct Args {
+    /// Type of the binary file, as given to `strucvars txt-to-bin`.
+    #[arg(long, value_enum)]
+    pub input_type: InputType,
+    /// Path to the BIN file.
+    #[arg(long)]
+    pub path_input: String,
+}
+
+/// Information on one binary file, as printed by `strucvars bin-info`.
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+pub struct BinInfo {
+    /// Path to the file.
+    pub path: String,
+    /// Number of records in the file.
+    pub record_count: usize,
+    /// Provenance, if the file was written with it.
+    pub provenance: Option<Provenance>,
+}
+
+/// Read the record count and provenance from the binary file at `path_input`.
+pub fn read_bin_info(path_input: &str, input_type: InputType) -> Result<BinInfo, anyhow::Error> {
+    let fcontents = std::fs::read(path_input)
+        .map_err(|e| anyhow::anyhow!("could not read {}: {}", path_input, e))?;
+    let buf = std::io::Cursor::new(fcontents);
+    let (record_count, provenance) = match input_type {
+        InputType::ClinvarSv => {
+            let db = clinvar::SvDatabase::decode(buf)?;
+            (db.records.len(), db.provenance)
+        }
+        InputType::MaskedRegion => {
+            let db = bgdb::MaskedDatabase::decode(buf)?;
+            (db.records.len(), db.provenance)
+        }
+        InputType::Xlink => {
+            let db = bgdb::XlinkDatabase::decode(buf)?;
+            (db.records.len(), db.provenance)
+        }
+        _ => {
+            let db = bgdb::BackgroundDatabase::decode(buf)?;
+            (db.records.len(), db.provenance)
+        }
+    };
+
+    Ok(BinInfo {
+        path: path_input.to_string(),
+        record_count,
+        provenance,
+    })
+}
+
+/// Main entry point for the `strucvars bin-info` command.
+pub fn run(common_args: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting `strucvars bin-info`");
+    tracing::info!("  common_args = {:?}", &common_args);
+    tracing::info!("  args = {:?}", &args);
+
+    let info = read_bin_info(&args.path_input, args.input_type)?;
+    if info.provenance.is_none() {
+        tracing::warn!("{} was written without provenance", &args.path_input);
+    }
+    println!("{}", serde_json::to_string_pretty(&info)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{common::GenomeRelease, pbs::varfish::v1::common::misc::Provenance};
+
+    use super::super::cli::InputType;
+
+    #[test]
+    fn source_file() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("input.txt");
+        std::fs::write(&path, "abc")?;
+
+        let source = super::source_file(&path)?;
+
+        assert_eq!(source.size, 3);
+        assert_eq!(
+            source.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_bin_info_xlink() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path_input = "tests/db/to-bin/varfish-db-downloader/genes/xlink/hgnc.tsv";
+        let path_output = tmp_dir.join("xlink.bin");
+        let provenance = super::build_provenance(
+            path_input,
+            Some(GenomeRelease::Grch37),
+            Some("20230624".into()),
+        )?;
+        super::super::xlink::convert_to_bin(path_input, &path_output, provenance.clone())?;
+
+        let info = super::read_bin_info(path_output.to_str().unwrap(), InputType::Xlink)?;
+
+        assert_eq!(
+            info.provenance,
+            Some(Provenance {
+                schema_version: super::super::xlink::SCHEMA_VERSION,
+                ..provenance
+            })
+        );
+        assert_eq!(
+            info.provenance.unwrap().genome_release.as_deref(),
+            Some("grch37")
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::unversioned(None, true)]
+    #[case::supported(Some(1), true)]
+    #[case::newer(Some(2), false)]
+    fn check_schema_version(#[case] schema_version: Option<u32>, #[case] expected: bool) {
+        let provenance = schema_version.map(|schema_version| Provenance {
+            schema_version,
+            ..Default::default()
+        });
+        assert_eq!(
+            super::check_schema_version(std::path::Path::new("x.bin"), provenance.as_ref(), 1)
+                .is_ok(),
+            expected
+        );
+    }
+}
@@ -8,9 +8,18 @@ use thousands::Separable;
 
 use crate::{
     common::{build_chrom_map, trace_rss_now},
-    pbs::varfish::v1::strucvars::bgdb::{MaskedDatabase, MaskedDbRecord},
+    pbs::varfish::v1::{
+        common::misc::Provenance,
+        strucvars::bgdb::{MaskedDatabase, MaskedDbRecord},
+    },
 };
 
+/// Version of the schema of the records written by `convert_to_bin`.
+///
+/// Increment when the meaning of the records changes so that older workers
+/// refuse to load the file.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Module with code supporting the parsing.
 mod input {
     use serde::Deserialize;
@@ -24,14 +33,24 @@ mod input {
         pub begin: i32,
         /// 1-based end position
         pub end: i32,
-        /// Masked region label
+        /// Masked region label, missing in plain 3-column BED files such as the
+        /// ENCODE blacklist.
         #[allow(dead_code)]
-        pub label: String,
+        #[serde(default)]
+        pub label: Option<String>,
     }
 }
 
 /// Perform conversion to protocolbuffers `.bin` file.
-pub fn convert_to_bin<P, Q>(path_input_tsv: P, path_output: Q) -> Result<(), anyhow::Error>
+///
+/// Used for all masked region tracks, i.e., repeats, segmental duplications,
+/// low-complexity regions, and the ENCODE blacklist.  Records on chromosomes
+/// not supported by VarFish (e.g., alternative contigs) are skipped.
+pub fn convert_to_bin<P, Q>(
+    path_input_tsv: P,
+    path_output: Q,
+    provenance: Provenance,
+) -> Result<(), anyhow::Error>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
@@ -49,22 +68,30 @@ where
         .has_headers(false)
         .delimiter(b'\t')
         .comment(Some(b'#'))
+        .flexible(true)
         .from_reader(open_read_maybe_gz(path_input_tsv.as_ref())?);
     let before_parsing = Instant::now();
 
     let mut records = Vec::new();
     for record in reader.deserialize() {
         let record: input::Record = record?;
+        let Some(chrom_no) = chrom_map.get(&record.chromosome) else {
+            tracing::warn!("skipping record on unknown chrom {:?}", &record.chromosome);
+            continue;
+        };
         records.push(MaskedDbRecord {
-            chrom_no: *chrom_map
-                .get(&record.chromosome)
-                .unwrap_or_else(|| panic!("unknown chrom {:?}", &record.chromosome))
-                as i32,
+            chrom_no: *chrom_no as i32,
             start: record.begin + 1,
             stop: record.end,
         });
     }
-    let masked_region_db = MaskedDatabase { records };
+    let masked_region_db = MaskedDatabase {
+        records,
+        provenance: Some(Provenance {
+            schema_version: SCHEMA_VERSION,
+            ..provenance
+        }),
+    };
 
     tracing::debug!(
         "total time spent reading {:?} records: {:?}",
@@ -85,3 +112,34 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use prost::Message as _;
+
+    use crate::pbs::varfish::v1::strucvars::bgdb::MaskedDatabase;
+
+    #[test]
+    fn convert_to_bin_three_columns() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path_bed = tmp_dir.join("blacklist.bed");
+        std::fs::write(
+            &path_bed,
+            "# ENCODE blacklist\nchr1\t10\t20\nchrUn_gl000220\t1\t2\nchrX\t30\t40\tHigh Signal Region\n",
+        )?;
+        let path_bin = tmp_dir.join("masked_blacklist.bin");
+
+        super::convert_to_bin(&path_bed, &path_bin, Default::default())?;
+
+        let db = MaskedDatabase::decode(std::fs::read(&path_bin)?.as_slice())?;
+        assert_eq!(
+            db.records
+                .iter()
+                .map(|r| (r.chrom_no, r.start, r.stop))
+                .collect::<Vec<_>>(),
+            vec![(0, 11, 20), (22, 31, 40)]
+        );
+
+        Ok(())
+    }
+}
@@ -2,6 +2,9 @@
 
 pub mod cli;
 pub mod clinvar;
+pub mod diff;
+pub mod export;
+pub mod info;
 pub mod masked;
 pub mod vardbs;
 pub mod xlink;
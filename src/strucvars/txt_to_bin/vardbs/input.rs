@@ -5,7 +5,7 @@ use serde::Deserialize;
 use tracing::error;
 
 use crate::strucvars::aggregate::output::Record as InhouseDbRecord;
-use crate::strucvars::query::schema::SvType;
+use crate::strucvars::query::schema::{SvSubType, SvType};
 
 /// dbVar database record as read from TSV file.
 #[derive(Debug, Deserialize)]
@@ -115,26 +115,22 @@ pub struct GnomadSv4Record {
     /// The structural vairant type
     pub svtype: String,
     /// Number of male homozygous reference allele carriers.
-    #[allow(dead_code)]
     pub male_n_homref: u32,
     /// Number of male heterozygous alternate allele carriers.
     pub male_n_het: u32,
     /// Number of male homozygous alternate allele carriers.
     pub male_n_homalt: u32,
     /// Number of male hemizygous alternate allele carriers.
-    #[allow(dead_code)]
     pub male_n_hemiref: u32,
     /// Number of male hemizygous reference allele carriers.
     pub male_n_hemialt: u32,
     /// Number of female homozygous reference allele carriers.
-    #[allow(dead_code)]
     pub female_n_homref: u32,
     /// Number of female heterozygous alternate allele carriers.
     pub female_n_het: u32,
     /// Number of female homozygous alternate allele carriers.
     pub female_n_homalt: u32,
     /// Number of samples at this site (CNV only).
-    #[allow(dead_code)]
     pub cnv_n_total: u32,
     /// Number of samples with a CNV at this site (CNV only).
     pub cnv_n_var: u32,
@@ -172,6 +168,14 @@ pub struct InputRecord {
     pub end: i32,
     /// Number of carriers (or alleles), depending on database.
     pub count: u32,
+    /// Allele count, if available.
+    pub ac: Option<u32>,
+    /// Allele number, if available.
+    pub an: Option<u32>,
+    /// Number of homozygous alternative carriers, if available.
+    pub n_homalt: Option<u32>,
+    /// Mobile element insertion sub type, if any.
+    pub mei_type: Option<SvSubType>,
 }
 
 impl TryInto<Option<InputRecord>> for InhouseDbRecord {
@@ -185,6 +189,10 @@ impl TryInto<Option<InputRecord>> for InhouseDbRecord {
             begin: self.begin,
             end: self.end,
             count: self.carriers,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -217,6 +225,10 @@ impl TryInto<Option<InputRecord>> for DbVarRecord {
             end: self.end,
             sv_type,
             count: 1,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -257,6 +269,10 @@ impl TryInto<Option<InputRecord>> for DgvRecord {
             end: self.end,
             sv_type,
             count: self.observed_gains + self.observed_losses,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -280,6 +296,10 @@ impl TryInto<Option<InputRecord>> for DgvGsRecord {
             end: self.end_outer,
             sv_type,
             count: self.num_carriers,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -303,6 +323,10 @@ impl TryInto<Option<InputRecord>> for ExacRecord {
             end: self.end,
             sv_type,
             count: 1,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -331,6 +355,10 @@ impl TryInto<Option<InputRecord>> for GnomadSv2Record {
             end: self.end,
             sv_type,
             count: self.n_homalt + self.n_het,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -353,6 +381,10 @@ impl TryInto<Option<InputRecord>> for GnomadCnv4Record {
                 }
             },
             count: self.n_var,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: None,
         }))
     }
 }
@@ -361,6 +393,24 @@ impl TryInto<Option<InputRecord>> for GnomadSv4Record {
     type Error = &'static str;
 
     fn try_into(self) -> Result<Option<InputRecord>, Self::Error> {
+        // CNVs only come with the number of samples, so we use the site frequency as
+        // allele frequency for them.
+        let (ac, an, n_homalt) = if self.svtype == "CNV" {
+            (self.cnv_n_var, self.cnv_n_total, None)
+        } else {
+            (
+                self.male_n_het
+                    + 2 * self.male_n_homalt
+                    + self.male_n_hemialt
+                    + self.female_n_het
+                    + 2 * self.female_n_homalt,
+                2 * (self.male_n_homref + self.male_n_het + self.male_n_homalt)
+                    + self.male_n_hemiref
+                    + self.male_n_hemialt
+                    + 2 * (self.female_n_homref + self.female_n_het + self.female_n_homalt),
+                Some(self.male_n_homalt + self.female_n_homalt),
+            )
+        };
         Ok(Some(InputRecord {
             chromosome: self.chromosome.clone(),
             chromosome2: self.chromosome,
@@ -371,19 +421,23 @@ impl TryInto<Option<InputRecord>> for GnomadSv4Record {
                 "CNV" => SvType::Cnv,
                 "DEL" => SvType::Del,
                 "DUP" => SvType::Dup,
-                "INS" => SvType::Ins,
+                "INS" | "INS:ME" | "INS:ME:ALU" | "INS:ME:LINE1" | "INS:ME:SVA" => SvType::Ins,
                 "INV" => SvType::Inv,
                 _ => {
                     error!("sv_type = {}", &self.svtype);
                     return Err("unknown SV type");
                 }
             },
+            mei_type: SvSubType::from_ins_me_str(&self.svtype),
             count: self.male_n_het
                 + self.male_n_homalt
                 + self.male_n_hemialt
                 + self.female_n_het
                 + self.female_n_homalt
                 + self.cnv_n_var,
+            ac: Some(ac),
+            an: Some(an),
+            n_homalt,
         }))
     }
 }
@@ -411,6 +465,10 @@ impl TryInto<Option<InputRecord>> for G1kRecord {
             end: self.end,
             sv_type,
             count: self.n_homalt + self.n_het,
+            ac: None,
+            an: None,
+            n_homalt: None,
+            mei_type: SvSubType::from_ins_me_str(&self.sv_type),
         }))
     }
 }
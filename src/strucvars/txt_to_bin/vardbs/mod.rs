@@ -10,14 +10,21 @@ use prost::Message;
 use thousands::Separable;
 
 use crate::common::{build_chrom_map, trace_rss_now};
-use crate::pbs::varfish::v1::strucvars::bgdb::{BackgroundDatabase, BgDbRecord};
+use crate::pbs::varfish::v1::common::misc::Provenance;
+use crate::pbs::varfish::v1::strucvars::bgdb::{BackgroundDatabase, BgDbRecord, MeiType};
 use crate::strucvars::aggregate::output::Record as InhouseDbRecord;
-use crate::strucvars::query::schema::SvType;
+use crate::strucvars::query::schema::{SvSubType, SvType};
 
 use self::input::InputRecord;
 
 mod input;
 
+/// Version of the schema of the records written by `convert_to_bin`.
+///
+/// Increment when the meaning of the records changes so that older workers
+/// refuse to load the file.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Known input file types.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum InputFileType {
@@ -70,6 +77,16 @@ where
                 start: record.begin + 1,
                 stop: record.end,
                 count: record.count,
+                ac: record.ac,
+                an: record.an,
+                n_homalt: record.n_homalt,
+                mei_type: match record.mei_type {
+                    Some(SvSubType::InsMeAlu) => MeiType::Alu,
+                    Some(SvSubType::InsMeL1) => MeiType::Line1,
+                    Some(SvSubType::InsMeSva) => MeiType::Sva,
+                    Some(_) => MeiType::Me,
+                    None => MeiType::Unspecified,
+                } as i32,
             });
         }
     }
@@ -100,6 +117,7 @@ pub fn convert_to_bin<P, Q>(
     path_input_tsv: P,
     path_output: Q,
     input_type: InputFileType,
+    provenance: Provenance,
 ) -> Result<(), anyhow::Error>
 where
     P: AsRef<Path>,
@@ -117,7 +135,13 @@ where
     let before_parsing = Instant::now();
 
     let records = deserialize_branch(input_type, &mut reader)?;
-    let bg_db = BackgroundDatabase { records };
+    let bg_db = BackgroundDatabase {
+        records,
+        provenance: Some(Provenance {
+            schema_version: SCHEMA_VERSION,
+            ..provenance
+        }),
+    };
 
     tracing::debug!(
         "total time spent reading {} records: {:?}",
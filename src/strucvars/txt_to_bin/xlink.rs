@@ -8,9 +8,18 @@ use thousands::Separable;
 
 use crate::{
     common::{numeric_gene_id, trace_rss_now},
-    pbs::varfish::v1::strucvars::bgdb::{XlinkDatabase, XlinkRecord},
+    pbs::varfish::v1::{
+        common::misc::Provenance,
+        strucvars::bgdb::{XlinkDatabase, XlinkRecord},
+    },
 };
 
+/// Version of the schema of the records written by `convert_to_bin`.
+///
+/// Increment when the meaning of the records changes so that older workers
+/// refuse to load the file.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Module with code for parsing the TSVs.
 pub mod input {
     use serde::Deserialize;
@@ -25,7 +34,11 @@ pub mod input {
 }
 
 /// Perform conversion to protocolbuffers `.bin` file.
-pub fn convert_to_bin<P, Q>(path_input_tsv: P, path_output: Q) -> Result<(), anyhow::Error>
+pub fn convert_to_bin<P, Q>(
+    path_input_tsv: P,
+    path_output: Q,
+    provenance: Provenance,
+) -> Result<(), anyhow::Error>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
@@ -55,7 +68,13 @@ where
             });
         }
     }
-    let xlink_db = XlinkDatabase { records };
+    let xlink_db = XlinkDatabase {
+        records,
+        provenance: Some(Provenance {
+            schema_version: SCHEMA_VERSION,
+            ..provenance
+        }),
+    };
 
     tracing::debug!(
         "total time spent reading {} records: {:?}",